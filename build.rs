@@ -1,7 +1,7 @@
 use std::{env, path::PathBuf};
 
 use cc::Build;
-use pkg_config::Config;
+use pkg_config::{Config, Library};
 
 fn main() {
     let mut build = Build::new();
@@ -11,8 +11,12 @@ fn main() {
     }
 
     build
+        .file("src/analysis/mod.c")
+        .file("src/analysis/quality.c")
+        .file("src/encryption.c")
         .file("src/error.c")
         .file("src/logger.c")
+        .file("src/options.c")
         .file("src/packet.c")
         .file("src/time.c")
         .file("src/format/demuxer.c")
@@ -22,9 +26,28 @@ fn main() {
         .file("src/codec/bsf.c")
         .file("src/codec/mod.c")
         .file("src/codec/frame.c")
+        .file("src/codec/audio/filter.c")
         .file("src/codec/audio/resampler.c")
-        .file("src/codec/video/scaler.c")
-        .compile("ffwrapper");
+        .file("src/codec/video/filter.c")
+        .file("src/codec/video/hw.c")
+        .file("src/codec/video/scaler.c");
+
+    // the SRT statistics need direct access to the libsrt API
+    let srt = if env::var_os("CARGO_FEATURE_SRT").is_some() {
+        Some(srt_lib())
+    } else {
+        None
+    };
+
+    if let Some(srt) = srt.as_ref() {
+        for dir in &srt.include_paths {
+            build.include(dir);
+        }
+
+        build.file("src/format/srt.c");
+    }
+
+    build.compile("ffwrapper");
 
     link_static("ffwrapper");
 
@@ -35,10 +58,19 @@ fn main() {
     let ffmpeg_link_mode = lib_mode("ffmpeg");
 
     link("avcodec", ffmpeg_link_mode);
+    link("avfilter", ffmpeg_link_mode);
     link("avformat", ffmpeg_link_mode);
     link("avutil", ffmpeg_link_mode);
     link("swresample", ffmpeg_link_mode);
     link("swscale", ffmpeg_link_mode);
+
+    if let Some(srt) = srt {
+        for dir in srt.link_paths {
+            println!("cargo:rustc-link-search=native={}", dir.to_str().unwrap());
+        }
+
+        link("srt", lib_mode("srt"));
+    }
 }
 
 fn ffmpeg_include_dirs() -> Vec<PathBuf> {
@@ -81,6 +113,16 @@ fn ffmpeg_lib_dirs() -> Vec<PathBuf> {
     lib.link_paths
 }
 
+fn srt_lib() -> Library {
+    Config::new()
+        .cargo_metadata(false)
+        .env_metadata(false)
+        .print_system_libs(false)
+        .print_system_cflags(false)
+        .probe("srt")
+        .expect("Unable to find libsrt. Make sure that pkg-config can find the srt package.")
+}
+
 fn link_static(lib: &str) {
     link(lib, "static")
 }
@@ -90,7 +132,7 @@ fn link(lib: &str, mode: &str) {
 }
 
 fn lib_mode(lib: &str) -> &'static str {
-    let kind = env::var(&format!("{}_STATIC", lib.to_uppercase()));
+    let kind = env::var(format!("{}_STATIC", lib.to_uppercase()));
 
     match kind.ok().as_deref() {
         Some("0") => "dylib",
@@ -31,7 +31,13 @@ fn print_info(input: &str) -> Result<(), Error> {
         let params = stream.codec_parameters();
 
         println!("Stream #{}:", index);
-        println!("  duration: {}", stream.duration().as_f64().unwrap_or(0f64));
+        println!(
+            "  duration: {}",
+            stream
+                .duration()
+                .and_then(|duration| duration.as_f64())
+                .unwrap_or(0f64)
+        );
 
         if let Some(params) = params.as_audio_codec_parameters() {
             println!("  type: audio");
@@ -0,0 +1,368 @@
+//! HRD/VBV compliance verification.
+//!
+//! The verifier simulates the hypothetical decoder buffer (VBV) of a given
+//! size filled at a given bit rate. Every packet is removed from the buffer
+//! instantaneously at its decoding time. A buffer underflow occurs when a
+//! packet is not completely in the buffer at its decoding time. In the
+//! constant bit rate mode, a buffer overflow occurs when the buffer would
+//! need to hold more data than its size (in the variable bit rate mode, the
+//! buffer simply stops filling up when it is full).
+//!
+//! The model corresponds to the encoder settings `vbv_buffer_size()`,
+//! `max_bit_rate()` and `vbv_initial_occupancy()` of the
+//! `VideoEncoderBuilder`.
+
+use crate::{packet::Packet, time::Timestamp, Error};
+
+/// VBV model.
+#[derive(Debug, Copy, Clone)]
+pub struct VbvModel {
+    buffer_size: u64,
+    bit_rate: u64,
+    initial_occupancy: u64,
+    constant_bit_rate: bool,
+}
+
+impl VbvModel {
+    /// Create a new VBV model with a given buffer size (in bits) and a given
+    /// maximum bit rate (in bits per second). The initial buffer occupancy is
+    /// 3/4 of the buffer size (the FFmpeg default) and the model uses the
+    /// variable bit rate mode.
+    ///
+    /// # Panics
+    /// The method panics if the buffer size or the bit rate is zero.
+    pub fn new(buffer_size: u64, bit_rate: u64) -> Self {
+        assert!(buffer_size > 0, "the buffer size must not be zero");
+        assert!(bit_rate > 0, "the bit rate must not be zero");
+
+        Self {
+            buffer_size,
+            bit_rate,
+            initial_occupancy: buffer_size * 3 / 4,
+            constant_bit_rate: false,
+        }
+    }
+
+    /// Set the buffer occupancy (in bits) at the decoding time of the first
+    /// packet.
+    ///
+    /// # Panics
+    /// The method panics if the occupancy is greater than the buffer size.
+    pub fn with_initial_occupancy(mut self, bits: u64) -> Self {
+        assert!(
+            bits <= self.buffer_size,
+            "the initial occupancy must not be greater than the buffer size"
+        );
+
+        self.initial_occupancy = bits;
+        self
+    }
+
+    /// Enable or disable the constant bit rate mode.
+    pub fn with_constant_bit_rate(mut self, enabled: bool) -> Self {
+        self.constant_bit_rate = enabled;
+        self
+    }
+
+    /// Get the buffer size in bits.
+    pub fn buffer_size(&self) -> u64 {
+        self.buffer_size
+    }
+
+    /// Get the bit rate in bits per second.
+    pub fn bit_rate(&self) -> u64 {
+        self.bit_rate
+    }
+
+    /// Get the initial buffer occupancy in bits.
+    pub fn initial_occupancy(&self) -> u64 {
+        self.initial_occupancy
+    }
+
+    /// Check if the constant bit rate mode is enabled.
+    pub fn is_constant_bit_rate(&self) -> bool {
+        self.constant_bit_rate
+    }
+}
+
+/// Kind of a VBV violation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HrdViolationKind {
+    Underflow,
+    Overflow,
+}
+
+/// VBV violation.
+#[derive(Debug, Clone)]
+pub struct HrdViolation {
+    kind: HrdViolationKind,
+    packet: usize,
+    timestamp: Timestamp,
+    fullness: u64,
+    size: u64,
+}
+
+impl HrdViolation {
+    /// Get the violation kind.
+    pub fn kind(&self) -> HrdViolationKind {
+        self.kind
+    }
+
+    /// Get index of the packet (in the order of pushing) where the violation
+    /// occurred.
+    pub fn packet(&self) -> usize {
+        self.packet
+    }
+
+    /// Get decoding timestamp of the packet.
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    /// Get the buffer fullness (in bits) right before the packet removal. In
+    /// case of an overflow, this is the fullness the buffer would have
+    /// without the size limit.
+    pub fn fullness(&self) -> u64 {
+        self.fullness
+    }
+
+    /// Get the packet size in bits.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Result of the VBV verification.
+#[derive(Debug, Clone)]
+pub struct HrdReport {
+    packets: usize,
+    violations: Vec<HrdViolation>,
+    min_fullness: u64,
+    max_fullness: u64,
+}
+
+impl HrdReport {
+    /// Get the number of verified packets.
+    pub fn packets(&self) -> usize {
+        self.packets
+    }
+
+    /// Check if the stream complies with the model.
+    pub fn is_compliant(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Get all violations.
+    pub fn violations(&self) -> &[HrdViolation] {
+        &self.violations
+    }
+
+    /// Get all buffer underflows.
+    pub fn underflows(&self) -> impl Iterator<Item = &HrdViolation> {
+        self.violations
+            .iter()
+            .filter(|violation| violation.kind == HrdViolationKind::Underflow)
+    }
+
+    /// Get the minimum buffer fullness (in bits) after a packet removal.
+    pub fn min_fullness(&self) -> u64 {
+        self.min_fullness
+    }
+
+    /// Get the maximum buffer fullness (in bits) before a packet removal.
+    pub fn max_fullness(&self) -> u64 {
+        self.max_fullness
+    }
+}
+
+/// VBV verifier.
+pub struct HrdVerifier {
+    model: VbvModel,
+    fullness: u64,
+    origin: Option<Timestamp>,
+    last_time: Timestamp,
+    arrived: u64,
+    report: HrdReport,
+}
+
+impl HrdVerifier {
+    /// Create a new verifier for a given model.
+    pub fn new(model: VbvModel) -> Self {
+        Self {
+            model,
+            fullness: model.initial_occupancy,
+            origin: None,
+            last_time: Timestamp::null(),
+            arrived: 0,
+            report: HrdReport {
+                packets: 0,
+                violations: Vec::new(),
+                min_fullness: model.initial_occupancy,
+                max_fullness: model.initial_occupancy,
+            },
+        }
+    }
+
+    /// Get the number of bits arriving into the buffer between the decoding
+    /// time of the first packet and a given time.
+    fn arrived_bits(&self, origin: Timestamp, time: Timestamp) -> u64 {
+        let time_base = origin.time_base();
+
+        let ticks = time.with_time_base(time_base).timestamp() - origin.timestamp();
+
+        let res = ticks as i128 * time_base.num() as i128 * self.model.bit_rate as i128
+            / time_base.den() as i128;
+
+        res as u64
+    }
+
+    /// Push the next packet in the decoding order. The decoding time is
+    /// taken from the packet DTS (or PTS if the DTS is missing). Any
+    /// violation caused by the packet is returned and it is also recorded
+    /// in the report.
+    pub fn push(&mut self, packet: &Packet) -> Result<Option<HrdViolation>, Error> {
+        let timestamp = if packet.dts().is_null() {
+            packet.pts()
+        } else {
+            packet.dts()
+        };
+
+        if timestamp.is_null() {
+            return Err(Error::new("packet without a timestamp"));
+        } else if !self.last_time.is_null() && timestamp < self.last_time {
+            return Err(Error::new("non-monotonic decoding timestamp"));
+        }
+
+        let origin = *self.origin.get_or_insert(timestamp);
+
+        // the total amount is used in order to avoid accumulating rounding
+        // errors
+        let arrived = self.arrived_bits(origin, timestamp);
+
+        self.fullness += arrived - self.arrived;
+        self.arrived = arrived;
+        self.last_time = timestamp;
+
+        let size = packet.data().len() as u64 * 8;
+
+        let mut violation = None;
+
+        if self.fullness > self.model.buffer_size {
+            if self.model.constant_bit_rate {
+                violation = Some(HrdViolation {
+                    kind: HrdViolationKind::Overflow,
+                    packet: self.report.packets,
+                    timestamp,
+                    fullness: self.fullness,
+                    size,
+                });
+            }
+
+            self.fullness = self.model.buffer_size;
+        }
+
+        self.report.max_fullness = self.report.max_fullness.max(self.fullness);
+
+        if size > self.fullness {
+            // underflow takes precedence as it breaks decoding
+            violation = Some(HrdViolation {
+                kind: HrdViolationKind::Underflow,
+                packet: self.report.packets,
+                timestamp,
+                fullness: self.fullness,
+                size,
+            });
+
+            // the decoder waits until the whole packet arrives
+            self.fullness = 0;
+        } else {
+            self.fullness -= size;
+        }
+
+        self.report.min_fullness = self.report.min_fullness.min(self.fullness);
+        self.report.packets += 1;
+
+        if let Some(violation) = violation.as_ref() {
+            self.report.violations.push(violation.clone());
+        }
+
+        Ok(violation)
+    }
+
+    /// Get the report for all packets pushed so far.
+    pub fn report(&self) -> &HrdReport {
+        &self.report
+    }
+
+    /// Consume the verifier and get the final report.
+    pub fn finish(self) -> HrdReport {
+        self.report
+    }
+}
+
+/// Verify a given packet stream (in the decoding order) against a given VBV
+/// model.
+pub fn hrd_verify<I>(model: VbvModel, packets: I) -> Result<HrdReport, Error>
+where
+    I: IntoIterator<Item = Packet>,
+{
+    let mut verifier = HrdVerifier::new(model);
+
+    for packet in packets {
+        verifier.push(&packet)?;
+    }
+
+    Ok(verifier.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hrd_verify, HrdViolationKind, VbvModel};
+
+    use crate::{
+        packet::{Packet, PacketMut},
+        time::{TimeBase, Timestamp},
+    };
+
+    fn packet(size: usize, dts: i64) -> Packet {
+        let time_base = TimeBase::new(1, 1000);
+
+        PacketMut::new(size)
+            .with_time_base(time_base)
+            .with_dts(Timestamp::new(dts, time_base))
+            .freeze()
+    }
+
+    #[test]
+    fn test_hrd_verify() {
+        // 8 kb/s with 100 ms between packets, i.e. 100 bytes per packet
+        let model = VbvModel::new(8000, 8000);
+
+        let packets = (0..20).map(|i| packet(100, i * 100));
+
+        let report = hrd_verify(model, packets).unwrap();
+
+        assert!(report.is_compliant());
+        assert_eq!(report.packets(), 20);
+        assert_eq!(report.min_fullness(), 5200);
+
+        let packets = (0..20).map(|i| packet(if i == 10 { 1000 } else { 100 }, i * 100));
+
+        let report = hrd_verify(model, packets).unwrap();
+
+        let underflows = report.underflows().collect::<Vec<_>>();
+
+        assert_eq!(underflows.len(), 1);
+        assert_eq!(underflows[0].packet(), 10);
+        assert_eq!(underflows[0].size(), 8000);
+
+        // small packets overflow the buffer in the CBR mode
+        let model = model.with_constant_bit_rate(true);
+
+        let packets = (0..20).map(|i| packet(10, i * 100));
+
+        let report = hrd_verify(model, packets).unwrap();
+
+        assert_eq!(report.violations()[0].kind(), HrdViolationKind::Overflow);
+    }
+}
@@ -0,0 +1,58 @@
+#include <libavcodec/avfft.h>
+#include <libavutil/mem.h>
+
+#include <stdlib.h>
+
+typedef struct Spectrum {
+    RDFTContext* rdft;
+    FFTSample* data;
+} Spectrum;
+
+Spectrum* ffw_spectrum_new(int nbits);
+float* ffw_spectrum_get_data(Spectrum* spectrum);
+void ffw_spectrum_calc(Spectrum* spectrum);
+void ffw_spectrum_free(Spectrum* spectrum);
+
+Spectrum* ffw_spectrum_new(int nbits) {
+    Spectrum* res = malloc(sizeof(Spectrum));
+    if (res == NULL) {
+        return NULL;
+    }
+
+    // NOTE: the FFT input needs to be properly aligned, so we cannot use
+    // a buffer allocated on the Rust side
+    res->rdft = av_rdft_init(nbits, DFT_R2C);
+    res->data = av_malloc_array(1 << nbits, sizeof(FFTSample));
+
+    if (res->rdft == NULL || res->data == NULL) {
+        goto err;
+    }
+
+    return res;
+
+err:
+    ffw_spectrum_free(res);
+
+    return NULL;
+}
+
+float* ffw_spectrum_get_data(Spectrum* spectrum) {
+    return spectrum->data;
+}
+
+void ffw_spectrum_calc(Spectrum* spectrum) {
+    av_rdft_calc(spectrum->rdft, spectrum->data);
+}
+
+void ffw_spectrum_free(Spectrum* spectrum) {
+    if (spectrum == NULL) {
+        return;
+    }
+
+    if (spectrum->rdft != NULL) {
+        av_rdft_end(spectrum->rdft);
+    }
+
+    av_free(spectrum->data);
+    free(spectrum);
+}
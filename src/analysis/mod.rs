@@ -0,0 +1,359 @@
+//! Media analysis.
+//!
+//! The spectrum analyzer splits audio into overlapping windows and computes
+//! a magnitude spectrum for each window using the FFmpeg real FFT. All
+//! channels are mixed down before the analysis.
+
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    os::raw::{c_int, c_void},
+    slice,
+};
+
+use crate::{
+    codec::audio::AudioFrame,
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+pub mod hrd;
+pub mod quality;
+pub mod stats;
+
+extern "C" {
+    fn ffw_spectrum_new(nbits: c_int) -> *mut c_void;
+    fn ffw_spectrum_get_data(spectrum: *mut c_void) -> *mut f32;
+    fn ffw_spectrum_calc(spectrum: *mut c_void);
+    fn ffw_spectrum_free(spectrum: *mut c_void);
+}
+
+/// Window function applied on every window before the FFT.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    /// Get window coefficients for a given window size.
+    fn coefficients(self, size: usize) -> Vec<f32> {
+        let n = size as f32;
+
+        (0..size)
+            .map(|i| {
+                let x = 2.0 * PI * i as f32 / n;
+
+                match self {
+                    Self::Rectangular => 1.0,
+                    Self::Hann => 0.5 - 0.5 * x.cos(),
+                    Self::Hamming => 0.54 - 0.46 * x.cos(),
+                    Self::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Builder for the spectrum analyzer.
+pub struct SpectrumBuilder {
+    fft_size: usize,
+    overlap: Option<usize>,
+    window: Window,
+}
+
+impl SpectrumBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            fft_size: 2048,
+            overlap: None,
+            window: Window::Hann,
+        }
+    }
+
+    /// Set the FFT size (i.e. the number of samples in a window). The size
+    /// must be a power of two between 16 and 65536. The default is 2048.
+    pub fn fft_size(mut self, size: usize) -> Self {
+        self.fft_size = size;
+        self
+    }
+
+    /// Set the number of samples shared by two consecutive windows. The
+    /// default is half of the FFT size.
+    pub fn overlap(mut self, samples: usize) -> Self {
+        self.overlap = Some(samples);
+        self
+    }
+
+    /// Set the window function. The default is the Hann window.
+    pub fn window(mut self, window: Window) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Build the spectrum analyzer.
+    pub fn build(self) -> Result<Spectrum, Error> {
+        let fft_size = self.fft_size;
+
+        if !fft_size.is_power_of_two() || !(16..=65536).contains(&fft_size) {
+            return Err(Error::new("unsupported FFT size"));
+        }
+
+        let overlap = self.overlap.unwrap_or(fft_size / 2);
+
+        if overlap >= fft_size {
+            return Err(Error::new("the overlap must be less than the FFT size"));
+        }
+
+        let ptr = unsafe { ffw_spectrum_new(fft_size.trailing_zeros() as _) };
+
+        if ptr.is_null() {
+            panic!("unable to allocate a spectrum analyzer");
+        }
+
+        let window = self.window.coefficients(fft_size);
+
+        // scale the magnitudes so that a full-scale sine wave has magnitude
+        // close to 1
+        let scale = 2.0 / window.iter().sum::<f32>();
+
+        let res = Spectrum {
+            ptr,
+            fft_size,
+            hop_size: fft_size - overlap,
+            window,
+            scale,
+            buffer: VecDeque::new(),
+            sample_rate: 0,
+            origin: None,
+            position: 0,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Magnitude spectrum of a single window.
+pub struct SpectrumFrame {
+    pts: Timestamp,
+    sample_rate: u32,
+    fft_size: usize,
+    magnitudes: Vec<f32>,
+}
+
+impl SpectrumFrame {
+    /// Get timestamp of the first sample in the window.
+    pub fn pts(&self) -> Timestamp {
+        self.pts
+    }
+
+    /// Get magnitudes of all frequency bins (from DC up to the Nyquist
+    /// frequency, i.e. fft_size / 2 + 1 values).
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes
+    }
+
+    /// Get center frequency of a given bin in Hz.
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate as f32 / self.fft_size as f32
+    }
+}
+
+/// Spectrum analyzer.
+pub struct Spectrum {
+    ptr: *mut c_void,
+    fft_size: usize,
+    hop_size: usize,
+    window: Vec<f32>,
+    scale: f32,
+    buffer: VecDeque<f32>,
+    sample_rate: u32,
+    origin: Option<i64>,
+    position: i64,
+}
+
+impl Spectrum {
+    /// Get a builder for the spectrum analyzer.
+    pub fn builder() -> SpectrumBuilder {
+        SpectrumBuilder::new()
+    }
+
+    /// Get the FFT size.
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Process a given frame and return spectra of all windows completed by
+    /// the frame. The analyzer is reset if the sample rate changes.
+    pub fn push(&mut self, frame: &AudioFrame) -> Result<Vec<SpectrumFrame>, Error> {
+        let format = frame.sample_format();
+
+        let convert = format
+            .f32_converter()
+            .ok_or_else(|| Error::new("unsupported sample format"))?;
+
+        if frame.sample_rate() != self.sample_rate {
+            self.sample_rate = frame.sample_rate();
+            self.buffer.clear();
+            self.origin = None;
+            self.position = 0;
+        }
+
+        if self.origin.is_none() {
+            let pts = frame.pts();
+
+            let pts = if pts.is_null() {
+                0
+            } else {
+                pts.with_time_base(TimeBase::new(1, self.sample_rate))
+                    .timestamp()
+            };
+
+            self.origin = Some(pts);
+        }
+
+        let bytes_per_sample = format.bytes_per_sample();
+        let channels = frame.channels() as usize;
+        let samples = frame.samples();
+
+        let planes = frame.planes();
+
+        for index in 0..samples {
+            let mut sum = 0.0;
+
+            for channel in 0..channels {
+                let (plane, offset) = if format.is_planar() {
+                    (&planes[channel], index * bytes_per_sample)
+                } else {
+                    (&planes[0], (index * channels + channel) * bytes_per_sample)
+                };
+
+                sum += convert(&plane.data()[offset..offset + bytes_per_sample]);
+            }
+
+            self.buffer.push_back(sum / channels as f32);
+        }
+
+        let mut res = Vec::new();
+
+        while self.buffer.len() >= self.fft_size {
+            res.push(self.analyze());
+
+            self.buffer.drain(..self.hop_size);
+
+            self.position += self.hop_size as i64;
+        }
+
+        Ok(res)
+    }
+
+    /// Compute spectrum of the current window.
+    fn analyze(&mut self) -> SpectrumFrame {
+        let fft_size = self.fft_size;
+
+        let data = unsafe {
+            let ptr = ffw_spectrum_get_data(self.ptr);
+
+            slice::from_raw_parts_mut(ptr, fft_size)
+        };
+
+        for ((dst, src), w) in data.iter_mut().zip(&self.buffer).zip(&self.window) {
+            *dst = src * w;
+        }
+
+        unsafe {
+            ffw_spectrum_calc(self.ptr);
+        }
+
+        // the output is packed as follows: DC, Nyquist, re(1), im(1), re(2),
+        // im(2), ...
+        let mut magnitudes = Vec::with_capacity(fft_size / 2 + 1);
+
+        magnitudes.push(data[0].abs() * self.scale / 2.0);
+
+        for bin in 1..fft_size / 2 {
+            let re = data[bin * 2];
+            let im = data[bin * 2 + 1];
+
+            magnitudes.push((re * re + im * im).sqrt() * self.scale);
+        }
+
+        magnitudes.push(data[1].abs() * self.scale / 2.0);
+
+        let tb = TimeBase::new(1, self.sample_rate);
+
+        let pts = Timestamp::new(self.origin.unwrap_or(0) + self.position, tb);
+
+        SpectrumFrame {
+            pts,
+            sample_rate: self.sample_rate,
+            fft_size,
+            magnitudes,
+        }
+    }
+}
+
+impl Drop for Spectrum {
+    fn drop(&mut self) {
+        unsafe { ffw_spectrum_free(self.ptr) }
+    }
+}
+
+unsafe impl Send for Spectrum {}
+unsafe impl Sync for Spectrum {}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::Spectrum;
+
+    use crate::codec::audio::{self, AudioFrameMut, ChannelLayout};
+
+    #[test]
+    fn test_spectrum() {
+        let mut frame = AudioFrameMut::silence(
+            ChannelLayout::from_channels(1).unwrap(),
+            audio::frame::get_sample_format("flt"),
+            6400,
+            96,
+        );
+
+        let mut planes = frame.planes_mut();
+
+        // 800 Hz sine wave, i.e. bin 8 for FFT size 64
+        for (i, dst) in planes[0].data_mut().chunks_exact_mut(4).enumerate() {
+            let v = (2.0 * PI * 800.0 * i as f32 / 6400.0).sin();
+
+            dst.copy_from_slice(&v.to_ne_bytes());
+        }
+
+        let frame = frame.freeze();
+
+        let mut spectrum = Spectrum::builder().fft_size(64).build().unwrap();
+
+        let res = spectrum.push(&frame).unwrap();
+
+        // windows starting at 0 and 32
+        assert_eq!(res.len(), 2);
+
+        for spectrum in res {
+            let magnitudes = spectrum.magnitudes();
+
+            assert_eq!(magnitudes.len(), 33);
+
+            let (peak, magnitude) =
+                magnitudes
+                    .iter()
+                    .enumerate()
+                    .fold((0, 0f32), |a, (i, m)| if *m > a.1 { (i, *m) } else { a });
+
+            assert_eq!(peak, 8);
+            assert_eq!(spectrum.bin_frequency(peak), 800.0);
+            assert!((magnitude - 1.0).abs() < 0.01);
+        }
+    }
+}
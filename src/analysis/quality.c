@@ -0,0 +1,208 @@
+#include <libavfilter/avfilter.h>
+#include <libavfilter/buffersink.h>
+#include <libavfilter/buffersrc.h>
+#include <libavutil/dict.h>
+#include <libavutil/frame.h>
+
+#include <stdio.h>
+#include <stdlib.h>
+
+typedef struct QualityFilter {
+    AVFilterGraph* graph;
+    AVFilterContext* main;
+    AVFilterContext* ref;
+    AVFilterContext* sink;
+    AVFrame* frame;
+} QualityFilter;
+
+QualityFilter* ffw_quality_filter_new(
+    int main_format, int main_width, int main_height,
+    int ref_format, int ref_width, int ref_height,
+    const char* description);
+int ffw_quality_filter_push_frame(QualityFilter* filter, int reference, const AVFrame* frame);
+int ffw_quality_filter_take_frame(QualityFilter* filter, int64_t* pts);
+int ffw_quality_filter_get_score(const QualityFilter* filter, const char* key, double* value);
+void ffw_quality_filter_free(QualityFilter* filter);
+
+static int create_source(
+    AVFilterGraph* graph,
+    AVFilterContext** source,
+    const char* name,
+    int format, int width, int height) {
+    char args[512];
+
+    // frames of both inputs are paired using their index as the timestamp
+    snprintf(
+        args, sizeof(args),
+        "video_size=%dx%d:pix_fmt=%d:time_base=1/1:pixel_aspect=1/1",
+        width, height, format);
+
+    return avfilter_graph_create_filter(
+        source, avfilter_get_by_name("buffer"), name, args, NULL, graph);
+}
+
+QualityFilter* ffw_quality_filter_new(
+    int main_format, int main_width, int main_height,
+    int ref_format, int ref_width, int ref_height,
+    const char* description) {
+    AVFilterInOut* inputs = NULL;
+    AVFilterInOut* outputs = NULL;
+    int ret;
+
+    QualityFilter* res = malloc(sizeof(QualityFilter));
+    if (res == NULL) {
+        return NULL;
+    }
+
+    res->graph = NULL;
+    res->main = NULL;
+    res->ref = NULL;
+    res->sink = NULL;
+    res->frame = NULL;
+
+    res->graph = avfilter_graph_alloc();
+    if (res->graph == NULL) {
+        goto err;
+    }
+
+    ret = create_source(res->graph, &res->main, "main", main_format, main_width, main_height);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = create_source(res->graph, &res->ref, "ref", ref_format, ref_width, ref_height);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = avfilter_graph_create_filter(
+        &res->sink, avfilter_get_by_name("buffersink"), "out", NULL, NULL, res->graph);
+    if (ret < 0) {
+        goto err;
+    }
+
+    // NOTE: "outputs" are the unconnected outputs of our buffer sources
+    // (i.e. inputs of the graph described by the description) and vice
+    // versa (see also the video filter)
+    outputs = avfilter_inout_alloc();
+    if (outputs == NULL) {
+        goto err;
+    }
+
+    outputs->name = av_strdup("main");
+    outputs->filter_ctx = res->main;
+    outputs->pad_idx = 0;
+    outputs->next = avfilter_inout_alloc();
+    if (outputs->name == NULL || outputs->next == NULL) {
+        goto err;
+    }
+
+    outputs->next->name = av_strdup("ref");
+    outputs->next->filter_ctx = res->ref;
+    outputs->next->pad_idx = 0;
+    outputs->next->next = NULL;
+    if (outputs->next->name == NULL) {
+        goto err;
+    }
+
+    inputs = avfilter_inout_alloc();
+    if (inputs == NULL) {
+        goto err;
+    }
+
+    inputs->name = av_strdup("out");
+    inputs->filter_ctx = res->sink;
+    inputs->pad_idx = 0;
+    inputs->next = NULL;
+    if (inputs->name == NULL) {
+        goto err;
+    }
+
+    ret = avfilter_graph_parse_ptr(res->graph, description, &inputs, &outputs, NULL);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = avfilter_graph_config(res->graph, NULL);
+    if (ret < 0) {
+        goto err;
+    }
+
+    avfilter_inout_free(&inputs);
+    avfilter_inout_free(&outputs);
+
+    return res;
+
+err:
+    avfilter_inout_free(&inputs);
+    avfilter_inout_free(&outputs);
+
+    ffw_quality_filter_free(res);
+
+    return NULL;
+}
+
+int ffw_quality_filter_push_frame(QualityFilter* filter, int reference, const AVFrame* frame) {
+    AVFilterContext* source = reference ? filter->ref : filter->main;
+
+    // flushing is done by passing NULL
+    if (frame == NULL) {
+        return av_buffersrc_add_frame_flags(source, NULL, 0);
+    }
+
+    return av_buffersrc_add_frame_flags(source, (AVFrame*)frame, AV_BUFFERSRC_FLAG_KEEP_REF);
+}
+
+int ffw_quality_filter_take_frame(QualityFilter* filter, int64_t* pts) {
+    int ret;
+
+    if (filter->frame == NULL) {
+        filter->frame = av_frame_alloc();
+        if (filter->frame == NULL) {
+            return AVERROR(ENOMEM);
+        }
+    }
+
+    av_frame_unref(filter->frame);
+
+    ret = av_buffersink_get_frame(filter->sink, filter->frame);
+    if (ret == AVERROR_EOF || ret == AVERROR(EAGAIN)) {
+        return 0;
+    } else if (ret < 0) {
+        return ret;
+    }
+
+    *pts = filter->frame->pts;
+
+    return 1;
+}
+
+int ffw_quality_filter_get_score(const QualityFilter* filter, const char* key, double* value) {
+    AVDictionaryEntry* entry;
+    char* end;
+
+    if (filter->frame == NULL) {
+        return 0;
+    }
+
+    entry = av_dict_get(filter->frame->metadata, key, NULL, 0);
+    if (entry == NULL) {
+        return 0;
+    }
+
+    *value = strtod(entry->value, &end);
+
+    return end != entry->value;
+}
+
+void ffw_quality_filter_free(QualityFilter* filter) {
+    if (filter == NULL) {
+        return;
+    }
+
+    // NOTE: the filter contexts are owned by the graph
+    avfilter_graph_free(&filter->graph);
+    av_frame_free(&filter->frame);
+
+    free(filter);
+}
@@ -0,0 +1,610 @@
+//! Objective video quality metrics.
+//!
+//! The quality analyzer compares a distorted frame stream (e.g. decoded
+//! output of an encoder) with a reference frame stream using the FFmpeg
+//! `psnr`, `ssim` and `libvmaf` filters. Frames are compared in pairs (i.e.
+//! the n-th distorted frame is compared with the n-th reference frame)
+//! regardless of their timestamps. Distorted frames are scaled to the size
+//! and converted into the pixel format of the reference frames if needed.
+//!
+//! Note that the `libvmaf` filter is available only if FFmpeg was built
+//! with libvmaf.
+
+use std::{
+    collections::VecDeque,
+    ffi::{CStr, CString},
+    fs,
+    os::raw::{c_char, c_int, c_void},
+    path::PathBuf,
+    process, ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    codec::video::VideoFrame,
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+extern "C" {
+    fn ffw_quality_filter_new(
+        main_format: c_int,
+        main_width: c_int,
+        main_height: c_int,
+        ref_format: c_int,
+        ref_width: c_int,
+        ref_height: c_int,
+        description: *const c_char,
+    ) -> *mut c_void;
+    fn ffw_quality_filter_push_frame(
+        filter: *mut c_void,
+        reference: c_int,
+        frame: *const c_void,
+    ) -> c_int;
+    fn ffw_quality_filter_take_frame(filter: *mut c_void, pts: *mut i64) -> c_int;
+    fn ffw_quality_filter_get_score(
+        filter: *const c_void,
+        key: *const c_char,
+        value: *mut f64,
+    ) -> c_int;
+    fn ffw_quality_filter_free(filter: *mut c_void);
+}
+
+/// Counter used for generating unique names of VMAF log files.
+static VMAF_LOG_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Quality metric.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Metric {
+    /// Peak signal-to-noise ratio (in dB) of all planes.
+    Psnr,
+    /// Structural similarity index (0 - 1) of all planes.
+    Ssim,
+    /// Video multi-method assessment fusion score (0 - 100).
+    Vmaf,
+}
+
+impl Metric {
+    /// Get name of the corresponding filter.
+    fn filter(self) -> &'static str {
+        match self {
+            Self::Psnr => "psnr",
+            Self::Ssim => "ssim",
+            Self::Vmaf => "libvmaf",
+        }
+    }
+}
+
+/// Builder for the quality analyzer.
+pub struct QualityAnalyzerBuilder {
+    metrics: Vec<Metric>,
+    vmaf_model: Option<String>,
+}
+
+impl QualityAnalyzerBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            metrics: Vec::new(),
+            vmaf_model: None,
+        }
+    }
+
+    /// Add a given metric. At least one metric must be added.
+    pub fn metric(mut self, metric: Metric) -> Self {
+        if !self.metrics.contains(&metric) {
+            self.metrics.push(metric);
+        }
+
+        self
+    }
+
+    /// Set path to the VMAF model. The default model of the `libvmaf`
+    /// filter is used if not set.
+    pub fn vmaf_model<T>(mut self, path: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.vmaf_model = Some(path.into());
+        self
+    }
+
+    /// Build the analyzer.
+    pub fn build(self) -> Result<QualityAnalyzer, Error> {
+        if self.metrics.is_empty() {
+            return Err(Error::new("no quality metric selected"));
+        }
+
+        if self
+            .vmaf_model
+            .as_ref()
+            .is_some_and(|model| model.contains('\0'))
+        {
+            return Err(Error::new("invalid VMAF model path"));
+        }
+
+        let vmaf_log = if self.metrics.contains(&Metric::Vmaf) {
+            let name = format!(
+                "ac-ffmpeg-vmaf-{}-{}.xml",
+                process::id(),
+                VMAF_LOG_COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+
+            Some(std::env::temp_dir().join(name))
+        } else {
+            None
+        };
+
+        let res = QualityAnalyzer {
+            ptr: ptr::null_mut(),
+            metrics: self.metrics,
+            vmaf_model: self.vmaf_model,
+            vmaf_log,
+            pts: VecDeque::new(),
+            pushed: 0,
+            frames: Vec::new(),
+        };
+
+        Ok(res)
+    }
+}
+
+/// Quality scores of a single frame.
+#[derive(Debug, Clone)]
+pub struct FrameQuality {
+    index: usize,
+    pts: Timestamp,
+    mse: Option<f64>,
+    psnr: Option<f64>,
+    ssim: Option<f64>,
+    vmaf: Option<f64>,
+}
+
+impl FrameQuality {
+    /// Get index of the frame pair.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Get timestamp of the reference frame.
+    pub fn pts(&self) -> Timestamp {
+        self.pts
+    }
+
+    /// Get PSNR of the frame in dB (infinite for identical frames).
+    pub fn psnr(&self) -> Option<f64> {
+        self.psnr
+    }
+
+    /// Get SSIM of the frame.
+    pub fn ssim(&self) -> Option<f64> {
+        self.ssim
+    }
+
+    /// Get VMAF score of the frame.
+    pub fn vmaf(&self) -> Option<f64> {
+        self.vmaf
+    }
+}
+
+/// Quality scores of the whole stream.
+#[derive(Debug, Clone)]
+pub struct QualityReport {
+    frames: Vec<FrameQuality>,
+    psnr: Option<f64>,
+    ssim: Option<f64>,
+    vmaf: Option<f64>,
+}
+
+impl QualityReport {
+    /// Create a new report from given per-frame scores.
+    fn new(frames: Vec<FrameQuality>) -> Self {
+        let mean = |values: Vec<f64>| {
+            if values.is_empty() {
+                None
+            } else {
+                Some(values.iter().sum::<f64>() / values.len() as f64)
+            }
+        };
+
+        // the aggregate PSNR is calculated from the mean MSE (as done by the
+        // psnr filter); the peak value is derived from any frame with a
+        // non-zero MSE
+        let peak = frames
+            .iter()
+            .filter_map(|frame| Some((frame.mse?, frame.psnr?)))
+            .find(|(mse, _)| *mse > 0.0)
+            .map(|(mse, psnr)| mse * 10f64.powf(psnr / 10.0));
+
+        let psnr = mean(frames.iter().filter_map(|frame| frame.mse).collect()).map(|mse| {
+            if mse > 0.0 {
+                10.0 * (peak.unwrap_or(0.0) / mse).log10()
+            } else {
+                f64::INFINITY
+            }
+        });
+
+        let ssim = mean(frames.iter().filter_map(|frame| frame.ssim).collect());
+        let vmaf = mean(frames.iter().filter_map(|frame| frame.vmaf).collect());
+
+        Self {
+            frames,
+            psnr,
+            ssim,
+            vmaf,
+        }
+    }
+
+    /// Get scores of individual frames.
+    pub fn frames(&self) -> &[FrameQuality] {
+        &self.frames
+    }
+
+    /// Get the aggregate PSNR in dB.
+    pub fn psnr(&self) -> Option<f64> {
+        self.psnr
+    }
+
+    /// Get the mean SSIM.
+    pub fn ssim(&self) -> Option<f64> {
+        self.ssim
+    }
+
+    /// Get the mean VMAF score.
+    pub fn vmaf(&self) -> Option<f64> {
+        self.vmaf
+    }
+}
+
+/// Quality analyzer.
+pub struct QualityAnalyzer {
+    ptr: *mut c_void,
+    metrics: Vec<Metric>,
+    vmaf_model: Option<String>,
+    vmaf_log: Option<PathBuf>,
+    pts: VecDeque<Timestamp>,
+    pushed: usize,
+    frames: Vec<FrameQuality>,
+}
+
+impl QualityAnalyzer {
+    /// Get a builder for the quality analyzer.
+    pub fn builder() -> QualityAnalyzerBuilder {
+        QualityAnalyzerBuilder::new()
+    }
+
+    /// Create the filter graph for given frames.
+    fn init(&mut self, reference: &VideoFrame, distorted: &VideoFrame) -> Result<(), Error> {
+        let mut description = format!(
+            "[main]scale={}:{},format={}[m0];",
+            reference.width(),
+            reference.height(),
+            reference.pixel_format().name()
+        );
+
+        let count = self.metrics.len();
+
+        if count > 1 {
+            description.push_str(&format!("[ref]split={}", count));
+
+            for i in 0..count {
+                description.push_str(&format!("[r{}]", i));
+            }
+
+            description.push(';');
+        } else {
+            description.push_str("[ref]null[r0];");
+        }
+
+        for (i, metric) in self.metrics.iter().enumerate() {
+            // every filter passes the main frames through with the scores
+            // attached as frame metadata
+            description.push_str(&format!("[m{}][r{}]{}", i, i, metric.filter()));
+
+            if *metric == Metric::Vmaf {
+                description.push_str("=log_fmt=xml");
+
+                if let Some(log) = self.vmaf_log.as_ref() {
+                    let log = log.to_string_lossy();
+
+                    description.push_str(&format!(":log_path={}", escape_option_value(&log)));
+                }
+
+                if let Some(model) = self.vmaf_model.as_ref() {
+                    description.push_str(&format!(":model_path={}", escape_option_value(model)));
+                }
+            }
+
+            if i + 1 < count {
+                description.push_str(&format!("[m{}];", i + 1));
+            }
+        }
+
+        let description = CString::new(description)
+            .map_err(|_| Error::new("invalid quality filter graph description"))?;
+
+        let ptr = unsafe {
+            ffw_quality_filter_new(
+                distorted.pixel_format().into_raw(),
+                distorted.width() as _,
+                distorted.height() as _,
+                reference.pixel_format().into_raw(),
+                reference.width() as _,
+                reference.height() as _,
+                description.as_ptr(),
+            )
+        };
+
+        if ptr.is_null() {
+            return Err(Error::new("unable to create the quality filter graph"));
+        }
+
+        self.ptr = ptr;
+
+        Ok(())
+    }
+
+    /// Compare a given pair of frames. The frames can be pushed in any
+    /// pixel format supported by the selected filters; the distorted frame
+    /// is converted into the format and size of the reference frame.
+    pub fn push(&mut self, reference: &VideoFrame, distorted: &VideoFrame) -> Result<(), Error> {
+        if self.ptr.is_null() {
+            self.init(reference, distorted)?;
+        }
+
+        let time_base = TimeBase::new(1, 1);
+        let index = Timestamp::new(self.pushed as i64, time_base);
+
+        let reference_pts = reference.pts();
+
+        let reference = reference.clone().with_time_base(time_base).with_pts(index);
+        let distorted = distorted.clone().with_time_base(time_base).with_pts(index);
+
+        unsafe {
+            let ret = ffw_quality_filter_push_frame(self.ptr, 1, reference.as_ptr());
+
+            if ret < 0 {
+                return Err(Error::from_raw_error_code(ret));
+            }
+
+            let ret = ffw_quality_filter_push_frame(self.ptr, 0, distorted.as_ptr());
+
+            if ret < 0 {
+                return Err(Error::from_raw_error_code(ret));
+            }
+        }
+
+        self.pts.push_back(reference_pts);
+        self.pushed += 1;
+
+        self.drain()
+    }
+
+    /// Collect scores of all frames available in the filter graph.
+    fn drain(&mut self) -> Result<(), Error> {
+        let mut pts = 0;
+
+        loop {
+            let ret = unsafe { ffw_quality_filter_take_frame(self.ptr, &mut pts) };
+
+            if ret == 0 {
+                return Ok(());
+            } else if ret < 0 {
+                return Err(Error::from_raw_error_code(ret));
+            }
+
+            let index = pts as usize;
+
+            // drop timestamps of frames skipped by the filters
+            while self.pts.len() > self.pushed - index {
+                self.pts.pop_front();
+            }
+
+            let frame = FrameQuality {
+                index,
+                pts: self.pts.pop_front().unwrap_or_else(Timestamp::null),
+                mse: self.score(b"lavfi.psnr.mse_avg\0"),
+                psnr: self.score(b"lavfi.psnr.psnr_avg\0"),
+                ssim: self.score(b"lavfi.ssim.All\0"),
+                vmaf: None,
+            };
+
+            self.frames.push(frame);
+        }
+    }
+
+    /// Get a given score of the last frame taken from the filter graph. The
+    /// key must be a nul-terminated metadata key.
+    fn score(&self, key: &[u8]) -> Option<f64> {
+        let key = CStr::from_bytes_with_nul(key).ok()?;
+
+        let mut value = 0.0;
+
+        let ret = unsafe { ffw_quality_filter_get_score(self.ptr, key.as_ptr(), &mut value) };
+
+        if ret > 0 {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Flush the filter graph and get the final report.
+    pub fn finish(mut self) -> Result<QualityReport, Error> {
+        if self.ptr.is_null() {
+            return Ok(QualityReport::new(Vec::new()));
+        }
+
+        unsafe {
+            for input in 0..2 {
+                let ret = ffw_quality_filter_push_frame(self.ptr, input, ptr::null());
+
+                if ret < 0 {
+                    return Err(Error::from_raw_error_code(ret));
+                }
+            }
+        }
+
+        self.drain()?;
+
+        let mut frames = std::mem::take(&mut self.frames);
+
+        if let Some(log) = self.vmaf_log.as_ref() {
+            // the libvmaf filter writes the log when the graph is freed
+            unsafe {
+                ffw_quality_filter_free(self.ptr);
+            }
+
+            self.ptr = ptr::null_mut();
+
+            let xml = fs::read_to_string(log)
+                .map_err(|err| Error::new(format!("unable to read the VMAF log: {}", err)))?;
+
+            for (index, score) in parse_vmaf_log(&xml) {
+                if let Some(frame) = frames.get_mut(index) {
+                    frame.vmaf = Some(score);
+                }
+            }
+        }
+
+        Ok(QualityReport::new(frames))
+    }
+}
+
+impl Drop for QualityAnalyzer {
+    fn drop(&mut self) {
+        unsafe { ffw_quality_filter_free(self.ptr) }
+
+        if let Some(log) = self.vmaf_log.as_ref() {
+            let _ = fs::remove_file(log);
+        }
+    }
+}
+
+unsafe impl Send for QualityAnalyzer {}
+unsafe impl Sync for QualityAnalyzer {}
+
+/// Escape a given filter option value. The value needs to be escaped twice
+/// because it is unescaped by the filter graph parser and then again by the
+/// filter option parser.
+fn escape_option_value(value: &str) -> String {
+    let escape = |value: &str| {
+        let mut res = String::with_capacity(value.len());
+
+        for c in value.chars() {
+            if "\\':=,;[]".contains(c) {
+                res.push('\\');
+            }
+
+            res.push(c);
+        }
+
+        res
+    };
+
+    escape(&escape(value))
+}
+
+/// Parse per-frame VMAF scores from a given libvmaf XML log.
+fn parse_vmaf_log(xml: &str) -> Vec<(usize, f64)> {
+    let attribute = |element: &str, name: &str| {
+        let pattern = format!(" {}=\"", name);
+        let start = element.find(&pattern)? + pattern.len();
+        let len = element[start..].find('"')?;
+
+        Some(element[start..start + len].to_string())
+    };
+
+    xml.split("<frame")
+        .skip(1)
+        .filter_map(|element| {
+            let element = &element[..element.find('>')?];
+
+            let index = attribute(element, "frameNum")?.parse().ok()?;
+            let score = attribute(element, "vmaf")?.parse().ok()?;
+
+            Some((index, score))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_vmaf_log, Metric, QualityAnalyzer};
+
+    use crate::{
+        codec::video::{frame::get_pixel_format, VideoFrameMut},
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_psnr_ssim() {
+        let format = get_pixel_format("yuv420p");
+        let time_base = TimeBase::new(1, 25);
+
+        let mut analyzer = QualityAnalyzer::builder()
+            .metric(Metric::Psnr)
+            .metric(Metric::Ssim)
+            .build()
+            .unwrap();
+
+        for i in 0..5 {
+            let pts = Timestamp::new(i, time_base);
+
+            let reference = VideoFrameMut::black(format, 64, 48)
+                .with_time_base(time_base)
+                .with_pts(pts)
+                .freeze();
+
+            let mut distorted = VideoFrameMut::black(format, 32, 24);
+
+            // make the last frame noticeably different
+            if i == 4 {
+                for b in distorted.planes_mut()[0].data_mut().iter_mut() {
+                    *b = 64;
+                }
+            }
+
+            analyzer.push(&reference, &distorted.freeze()).unwrap();
+        }
+
+        let report = analyzer.finish().unwrap();
+
+        let frames = report.frames();
+
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[4].index(), 4);
+        assert_eq!(frames[4].pts().timestamp(), 4);
+
+        assert!(frames[0].psnr().unwrap().is_infinite());
+        assert!(frames[4].psnr().unwrap() < 20.0);
+        assert!(frames[4].ssim().unwrap() < frames[0].ssim().unwrap());
+        assert!(frames[0].vmaf().is_none());
+
+        assert!(report.psnr().unwrap().is_finite());
+        assert!(report.ssim().unwrap() < 1.0);
+        assert!(report.vmaf().is_none());
+    }
+
+    #[test]
+    fn test_invalid_vmaf_model() {
+        let res = QualityAnalyzer::builder()
+            .metric(Metric::Vmaf)
+            .vmaf_model("model\0.pkl")
+            .build();
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_parse_vmaf_log() {
+        let xml = r#"<VMAF version="1.3.15">
+  <frames>
+    <frame frameNum="0" adm2="0.99" vmaf="97.5" />
+    <frame frameNum="1" adm2="0.98" vmaf="95.25" />
+  </frames>
+</VMAF>"#;
+
+        assert_eq!(parse_vmaf_log(xml), vec![(0, 97.5), (1, 95.25)]);
+    }
+}
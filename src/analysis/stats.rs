@@ -0,0 +1,309 @@
+//! Packet-level stream statistics.
+//!
+//! The `StreamStats` accumulator collects statistics of a single stream
+//! from its packets (e.g. demuxed packets of a live ingest). Packets of
+//! other streams must not be pushed into the same accumulator, so use one
+//! accumulator per stream index when monitoring a whole container.
+//!
+//! Timing statistics (bit rate, keyframe intervals and timestamp gaps) use
+//! the packet DTS (or PTS if the DTS is missing). Packets without a
+//! timestamp are counted but they do not affect the timing statistics.
+
+use std::{collections::VecDeque, time::Duration};
+
+use crate::packet::Packet;
+
+/// Builder for the stream statistics accumulator.
+pub struct StreamStatsBuilder {
+    window: Duration,
+    gap_threshold: Duration,
+}
+
+impl StreamStatsBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            window: Duration::from_secs(1),
+            gap_threshold: Duration::from_secs(1),
+        }
+    }
+
+    /// Set length of the sliding window used for the bit rate calculation.
+    /// The default is 1 second.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the minimum difference between two consecutive timestamps that
+    /// is considered a gap. The default is 1 second.
+    pub fn gap_threshold(mut self, threshold: Duration) -> Self {
+        self.gap_threshold = threshold;
+        self
+    }
+
+    /// Build the accumulator.
+    pub fn build(self) -> StreamStats {
+        StreamStats {
+            window: self.window.as_micros() as i64,
+            gap_threshold: self.gap_threshold.as_micros() as i64,
+            packets: 0,
+            bytes: 0,
+            recent: VecDeque::new(),
+            last_timestamp: None,
+            keyframes: 0,
+            last_keyframe: None,
+            keyframe_interval: None,
+            gop_length: 0,
+            max_gop_length: 0,
+            gop_packets: 0,
+            gaps: 0,
+            max_gap: 0,
+            discontinuities: 0,
+        }
+    }
+}
+
+/// Stream statistics accumulator.
+pub struct StreamStats {
+    window: i64,
+    gap_threshold: i64,
+    packets: u64,
+    bytes: u64,
+    recent: VecDeque<(i64, usize)>,
+    last_timestamp: Option<i64>,
+    keyframes: u64,
+    last_keyframe: Option<i64>,
+    keyframe_interval: Option<i64>,
+    gop_length: usize,
+    max_gop_length: usize,
+    gop_packets: u64,
+    gaps: u64,
+    max_gap: i64,
+    discontinuities: u64,
+}
+
+impl StreamStats {
+    /// Create a new accumulator with the default settings.
+    pub fn new() -> Self {
+        StreamStatsBuilder::new().build()
+    }
+
+    /// Get a builder for the accumulator.
+    pub fn builder() -> StreamStatsBuilder {
+        StreamStatsBuilder::new()
+    }
+
+    /// Update the statistics with a given packet.
+    pub fn push(&mut self, packet: &Packet) {
+        let size = packet.data().len();
+
+        self.packets += 1;
+        self.bytes += size as u64;
+
+        if packet.is_key() {
+            if self.keyframes > 0 {
+                self.gop_packets += self.gop_length as u64;
+            }
+
+            self.keyframes += 1;
+            self.gop_length = 0;
+        }
+
+        // packets preceding the first keyframe do not belong to any GOP
+        if self.keyframes > 0 {
+            self.gop_length += 1;
+            self.max_gop_length = self.max_gop_length.max(self.gop_length);
+        }
+
+        let timestamp = if packet.dts().is_null() {
+            packet.pts()
+        } else {
+            packet.dts()
+        };
+
+        let timestamp = match timestamp.as_micros() {
+            Some(t) => t,
+            None => return,
+        };
+
+        if let Some(last) = self.last_timestamp {
+            let diff = timestamp - last;
+
+            if diff < 0 {
+                self.discontinuities += 1;
+
+                // the bit rate window would be meaningless across the jump
+                self.recent.clear();
+            } else if diff >= self.gap_threshold {
+                self.gaps += 1;
+                self.max_gap = self.max_gap.max(diff);
+            }
+        }
+
+        self.last_timestamp = Some(timestamp);
+
+        if packet.is_key() {
+            if let Some(last) = self.last_keyframe {
+                if timestamp > last {
+                    self.keyframe_interval = Some(timestamp - last);
+                }
+            }
+
+            self.last_keyframe = Some(timestamp);
+        }
+
+        self.recent.push_back((timestamp, size));
+
+        while let Some((t, _)) = self.recent.front() {
+            if (timestamp - t) > self.window {
+                self.recent.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Get the number of packets.
+    pub fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Get the total size of all packets in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Get the bit rate (in bits per second) over the sliding window. At
+    /// least two packets with different timestamps are needed.
+    pub fn bit_rate(&self) -> Option<f64> {
+        let (first, _) = self.recent.front()?;
+        let (last, _) = self.recent.back()?;
+
+        let span = last - first;
+
+        if span <= 0 {
+            return None;
+        }
+
+        // the first packet in the window is excluded as it marks the window
+        // start
+        let bytes = self
+            .recent
+            .iter()
+            .skip(1)
+            .map(|(_, size)| *size as u64)
+            .sum::<u64>();
+
+        Some(bytes as f64 * 8_000_000.0 / span as f64)
+    }
+
+    /// Get the number of keyframes.
+    pub fn keyframes(&self) -> u64 {
+        self.keyframes
+    }
+
+    /// Get the time between the last two keyframes.
+    pub fn keyframe_interval(&self) -> Option<Duration> {
+        self.keyframe_interval
+            .map(|interval| Duration::from_micros(interval as u64))
+    }
+
+    /// Get the number of packets since the last keyframe (including the
+    /// keyframe).
+    pub fn gop_length(&self) -> usize {
+        self.gop_length
+    }
+
+    /// Get the maximum GOP length in packets.
+    pub fn max_gop_length(&self) -> usize {
+        self.max_gop_length
+    }
+
+    /// Get the average length of all complete GOPs in packets.
+    pub fn average_gop_length(&self) -> Option<f64> {
+        if self.keyframes > 1 {
+            Some(self.gop_packets as f64 / (self.keyframes - 1) as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of timestamp gaps (i.e. consecutive timestamps
+    /// differing at least by the gap threshold).
+    pub fn gaps(&self) -> u64 {
+        self.gaps
+    }
+
+    /// Get the longest timestamp gap.
+    pub fn max_gap(&self) -> Option<Duration> {
+        if self.gaps > 0 {
+            Some(Duration::from_micros(self.max_gap as u64))
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of backward timestamp jumps.
+    pub fn discontinuities(&self) -> u64 {
+        self.discontinuities
+    }
+}
+
+impl Default for StreamStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::StreamStats;
+
+    use crate::{
+        packet::{Packet, PacketMut},
+        time::{TimeBase, Timestamp},
+    };
+
+    fn packet(size: usize, dts: i64, key: bool) -> Packet {
+        let time_base = TimeBase::new(1, 1000);
+
+        PacketMut::new(size)
+            .with_time_base(time_base)
+            .with_dts(Timestamp::new(dts, time_base))
+            .with_key_flag(key)
+            .freeze()
+    }
+
+    #[test]
+    fn test_stream_stats() {
+        let mut stats = StreamStats::new();
+
+        // 25 fps with a keyframe every 10 frames and 1000 bytes per frame
+        for i in 0..50 {
+            stats.push(&packet(1000, i * 40, i % 10 == 0));
+        }
+
+        assert_eq!(stats.packets(), 50);
+        assert_eq!(stats.bytes(), 50_000);
+        assert_eq!(stats.bit_rate().map(|r| r.round()), Some(200_000.0));
+        assert_eq!(stats.keyframes(), 5);
+        assert_eq!(stats.keyframe_interval(), Some(Duration::from_millis(400)));
+        assert_eq!(stats.max_gop_length(), 10);
+        assert_eq!(stats.average_gop_length(), Some(10.0));
+
+        // a 3 second gap followed by a longer GOP and a backward jump
+        for i in 0..15 {
+            stats.push(&packet(1000, 5000 + i * 40, i == 0));
+        }
+
+        stats.push(&packet(1000, 0, true));
+
+        assert_eq!(stats.gaps(), 1);
+        assert_eq!(stats.max_gap(), Some(Duration::from_millis(3040)));
+        assert_eq!(stats.max_gop_length(), 15);
+        assert_eq!(stats.discontinuities(), 1);
+    }
+}
@@ -0,0 +1,600 @@
+//! Animated image (GIF/APNG/WebP) encoding and decoding.
+//!
+//! The `AnimatedImageEncoder` converts arbitrary video frames into an
+//! animated image. It takes care of the frame rate conversion, scaling,
+//! palette generation (GIF), pixel format conversion and loop count
+//! signaling. The `AnimatedImageDecoder` reads an animated image and returns
+//! fully composed RGBA frames (i.e. the frame disposal and blending is
+//! already applied).
+//!
+//! # Example
+//! ```ignore
+//! let mut encoder = AnimatedImageEncoder::builder(&codec_parameters, AnimatedImageFormat::Gif)
+//!     .frame_rate(Rational::new(15, 1))
+//!     .width(320)
+//!     .build(IO::from_write_stream(output))?;
+//!
+//! for frame in frames {
+//!     encoder.push(frame)?;
+//! }
+//!
+//! encoder.finish()?;
+//! ```
+
+use std::io::{Read, Write};
+
+use crate::{
+    codec::{
+        video::{
+            PixelFormat, VideoDecoder, VideoEncoder, VideoFilter, VideoFrame, VideoFrameScaler,
+        },
+        Decoder, Encoder, Filter, VideoCodecParameters,
+    },
+    format::{
+        demuxer::{Demuxer, DemuxerWithStreamInfo},
+        io::IO,
+        muxer::{Muxer, OutputFormat},
+    },
+    math::Rational,
+    time::TimeBase,
+    Error,
+};
+
+/// Animated image format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AnimatedImageFormat {
+    Gif,
+    Apng,
+    WebP,
+}
+
+impl AnimatedImageFormat {
+    /// Guess the format from a given file name extension.
+    pub fn guess_from_file_name(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+
+        let extension = extension.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "gif" => Some(Self::Gif),
+            "apng" | "png" => Some(Self::Apng),
+            "webp" => Some(Self::WebP),
+            _ => None,
+        }
+    }
+
+    /// Get name of the encoder.
+    fn encoder(self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "apng",
+            Self::WebP => "libwebp_anim",
+        }
+    }
+
+    /// Get name of the muxer.
+    fn muxer(self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "apng",
+            Self::WebP => "webp",
+        }
+    }
+
+    /// Get the muxer option and its value for a given number of plays
+    /// (`None` means infinite).
+    fn loop_option(self, plays: Option<u32>) -> (&'static str, i64) {
+        match self {
+            // the GIF loop count is the number of repetitions, -1 means
+            // that the animation is played only once
+            Self::Gif => match plays {
+                None => ("loop", 0),
+                Some(1) => ("loop", -1),
+                Some(n) => ("loop", n as i64 - 1),
+            },
+            Self::Apng => ("plays", plays.unwrap_or(0) as i64),
+            Self::WebP => ("loop", plays.unwrap_or(0) as i64),
+        }
+    }
+}
+
+/// Dithering used when mapping colors to the GIF palette.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Dither {
+    Bayer,
+    Heckbert,
+    FloydSteinberg,
+    Sierra2,
+    Sierra2_4a,
+}
+
+impl Dither {
+    /// Get name of the dithering mode as understood by the paletteuse
+    /// filter.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bayer => "bayer",
+            Self::Heckbert => "heckbert",
+            Self::FloydSteinberg => "floyd_steinberg",
+            Self::Sierra2 => "sierra2",
+            Self::Sierra2_4a => "sierra2_4a",
+        }
+    }
+}
+
+/// GIF palette generation mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PaletteMode {
+    /// A single palette is generated from all frames. This gives the best
+    /// quality, however, all frames are buffered until the encoder is
+    /// finished.
+    Global,
+    /// A new palette is generated for each frame. The frames are encoded
+    /// immediately.
+    PerFrame,
+}
+
+/// Builder for the animated image encoder.
+pub struct AnimatedImageEncoderBuilder {
+    codec_parameters: VideoCodecParameters,
+    format: AnimatedImageFormat,
+    time_base: TimeBase,
+    frame_rate: Rational,
+    width: Option<usize>,
+    height: Option<usize>,
+    plays: Option<u32>,
+    max_colors: usize,
+    dither: Dither,
+    palette_mode: PaletteMode,
+    optimize_frames: bool,
+}
+
+impl AnimatedImageEncoderBuilder {
+    /// Create a new builder.
+    fn new(codec_parameters: &VideoCodecParameters, format: AnimatedImageFormat) -> Self {
+        Self {
+            codec_parameters: codec_parameters.clone(),
+            format,
+            time_base: TimeBase::MICROSECONDS,
+            frame_rate: Rational::new(10, 1),
+            width: None,
+            height: None,
+            plays: None,
+            max_colors: 256,
+            dither: Dither::Sierra2_4a,
+            palette_mode: PaletteMode::Global,
+            optimize_frames: true,
+        }
+    }
+
+    /// Set input time base (all input frames will be rescaled into this time
+    /// base). The default time base is in microseconds.
+    pub fn input_time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Set frame rate of the animation. Input frames are dropped or
+    /// duplicated as needed. The default is 10 frames per second.
+    pub fn frame_rate(mut self, frame_rate: Rational) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// Set output width. If only one of the dimensions is set, the other one
+    /// is computed to keep the aspect ratio. The input size is used by
+    /// default.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Set output height. If only one of the dimensions is set, the other
+    /// one is computed to keep the aspect ratio. The input size is used by
+    /// default.
+    pub fn height(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Set how many times the animation is played (`None` means forever).
+    /// The animation loops forever by default.
+    pub fn play_count(mut self, plays: Option<u32>) -> Self {
+        self.plays = plays;
+        self
+    }
+
+    /// Set the maximum number of colors in the GIF palette (2 - 256). The
+    /// default is 256.
+    pub fn max_colors(mut self, colors: usize) -> Self {
+        self.max_colors = colors;
+        self
+    }
+
+    /// Set the GIF dithering mode. The default is `Sierra2_4a`.
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Set the GIF palette generation mode. The default is `Global`.
+    pub fn palette_mode(mut self, mode: PaletteMode) -> Self {
+        self.palette_mode = mode;
+        self
+    }
+
+    /// Store only the changed part of each GIF frame and keep the previous
+    /// frame displayed underneath (i.e. no frame disposal). This makes the
+    /// file smaller, however, it should be disabled for animations with
+    /// changing transparent areas. The optimization is enabled by default.
+    /// APNG and WebP encoders choose the frame disposal automatically.
+    pub fn optimize_frames(mut self, optimize: bool) -> Self {
+        self.optimize_frames = optimize;
+        self
+    }
+
+    /// Get the filter graph description.
+    fn filter_description(&self) -> String {
+        let mut chain = format!("fps={}/{}", self.frame_rate.num(), self.frame_rate.den());
+
+        if self.width.is_some() || self.height.is_some() {
+            let dimension = |d: Option<usize>| d.map(|d| d as i64).unwrap_or(-1);
+
+            chain = format!(
+                "{},scale={}:{}:flags=lanczos",
+                chain,
+                dimension(self.width),
+                dimension(self.height)
+            );
+        }
+
+        match self.format {
+            AnimatedImageFormat::Gif => {
+                let (stats_mode, new) = match self.palette_mode {
+                    PaletteMode::Global => ("full", 0),
+                    PaletteMode::PerFrame => ("single", 1),
+                };
+
+                let mut options = format!("dither={}:new={}", self.dither.name(), new);
+
+                // update only the changed rectangle of each frame
+                if self.optimize_frames {
+                    options.push_str(":diff_mode=rectangle");
+                }
+
+                format!(
+                    "{},split[a][b];[a]palettegen=max_colors={}:stats_mode={}[p];\
+                     [b][p]paletteuse={}",
+                    chain, self.max_colors, stats_mode, options
+                )
+            }
+            AnimatedImageFormat::Apng => format!("{},format=rgba", chain),
+            AnimatedImageFormat::WebP => format!("{},format=yuva420p", chain),
+        }
+    }
+
+    /// Build the encoder. The animated image will be written into a given
+    /// IO.
+    pub fn build<W>(self, io: IO<W>) -> Result<AnimatedImageEncoder<W>, Error>
+    where
+        W: Write,
+    {
+        if !(2..=256).contains(&self.max_colors) {
+            return Err(Error::new("the number of colors must be between 2 and 256"));
+        } else if self.plays == Some(0) {
+            return Err(Error::new("the play count must be positive"));
+        } else if self.frame_rate.num() == 0 || self.frame_rate.den() == 0 {
+            return Err(Error::new("invalid frame rate"));
+        }
+
+        let description = self.filter_description();
+
+        let filter = VideoFilter::builder(&self.codec_parameters, &description)
+            .input_time_base(self.time_base)
+            .build()?;
+
+        let res = AnimatedImageEncoder {
+            filter,
+            encoder: None,
+            muxer: None,
+            io: Some(io),
+            format: self.format,
+            plays: self.plays,
+            optimize_frames: self.optimize_frames,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Animated image encoder. The encoder and the muxer are created once the
+/// first frame leaves the filter graph (i.e. when the output frame size is
+/// known).
+pub struct AnimatedImageEncoder<W> {
+    filter: VideoFilter,
+    encoder: Option<VideoEncoder>,
+    muxer: Option<Muxer<W>>,
+    io: Option<IO<W>>,
+    format: AnimatedImageFormat,
+    plays: Option<u32>,
+    optimize_frames: bool,
+}
+
+impl AnimatedImageEncoder<()> {
+    /// Get a builder of an animated image encoder for frames described by
+    /// given codec parameters.
+    pub fn builder(
+        codec_parameters: &VideoCodecParameters,
+        format: AnimatedImageFormat,
+    ) -> AnimatedImageEncoderBuilder {
+        AnimatedImageEncoderBuilder::new(codec_parameters, format)
+    }
+}
+
+impl<W> AnimatedImageEncoder<W>
+where
+    W: Write,
+{
+    /// Push a given frame into the animation.
+    pub fn push(&mut self, frame: VideoFrame) -> Result<(), Error> {
+        self.filter.push(frame)?;
+
+        self.write_filtered_frames()
+    }
+
+    /// Flush all buffered frames, finish the animated image and return the
+    /// underlying IO. An error is returned if no frames were pushed.
+    pub fn finish(mut self) -> Result<IO<W>, Error> {
+        self.filter.flush()?;
+
+        self.write_filtered_frames()?;
+
+        let encoder = self
+            .encoder
+            .as_mut()
+            .ok_or_else(|| Error::new("no frames"))?;
+
+        encoder.flush()?;
+
+        self.write_packets()?;
+
+        self.muxer.take().unwrap().close()
+    }
+
+    /// Encode all frames available in the filter graph.
+    fn write_filtered_frames(&mut self) -> Result<(), Error> {
+        while let Some(frame) = self.filter.take()? {
+            if self.encoder.is_none() {
+                self.init(&frame)?;
+            }
+
+            self.encoder.as_mut().unwrap().push(frame)?;
+
+            self.write_packets()?;
+        }
+
+        Ok(())
+    }
+
+    /// Write all packets available in the encoder.
+    fn write_packets(&mut self) -> Result<(), Error> {
+        let encoder = self.encoder.as_mut().unwrap();
+        let muxer = self.muxer.as_mut().unwrap();
+
+        while let Some(packet) = encoder.take()? {
+            muxer.push(packet.with_stream_index(0))?;
+        }
+
+        Ok(())
+    }
+
+    /// Create the encoder and the muxer for frames like a given one.
+    fn init(&mut self, frame: &VideoFrame) -> Result<(), Error> {
+        let time_base = TimeBase::new(1, 100);
+
+        let mut builder = VideoEncoder::builder(self.format.encoder())?
+            .pixel_format(frame.pixel_format())
+            .width(frame.width())
+            .height(frame.height())
+            .time_base(time_base);
+
+        if self.format == AnimatedImageFormat::Gif && !self.optimize_frames {
+            builder = builder.set_option("gifflags", 0);
+        }
+
+        let encoder = builder.build()?;
+
+        let mut muxer_builder = Muxer::builder();
+
+        muxer_builder.add_stream(&encoder.codec_parameters().into())?;
+
+        let (name, value) = self.format.loop_option(self.plays);
+
+        let format = OutputFormat::find_by_name(self.format.muxer())
+            .ok_or_else(|| Error::new("unknown output format"))?;
+
+        let muxer = muxer_builder
+            .set_option(name, value)
+            .build(self.io.take().unwrap(), format)?;
+
+        self.encoder = Some(encoder);
+        self.muxer = Some(muxer);
+
+        Ok(())
+    }
+}
+
+/// Animated image decoder. It returns fully composed RGBA frames (i.e. the
+/// frame disposal and blending is applied) with presentation timestamps.
+/// The loop count of the input is ignored (i.e. each frame is returned only
+/// once).
+pub struct AnimatedImageDecoder<R> {
+    demuxer: DemuxerWithStreamInfo<R>,
+    decoder: VideoDecoder,
+    stream_index: usize,
+    scaler: Option<VideoFrameScaler>,
+    flushed: bool,
+}
+
+impl<R> AnimatedImageDecoder<R>
+where
+    R: Read,
+{
+    /// Create a new decoder reading an animated image (or any other video)
+    /// from a given IO. Note that the GIF demuxer needs a seekable IO.
+    pub fn new(io: IO<R>) -> Result<Self, Error> {
+        let demuxer = Demuxer::builder()
+            .build(io)?
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)?;
+
+        let (stream_index, stream) = demuxer
+            .streams()
+            .iter()
+            .enumerate()
+            .find(|(_, stream)| stream.codec_parameters().is_video_codec())
+            .ok_or_else(|| Error::new("no video stream"))?;
+
+        let decoder = VideoDecoder::from_stream(stream)?.build()?;
+
+        let res = Self {
+            demuxer,
+            decoder,
+            stream_index,
+            scaler: None,
+            flushed: false,
+        };
+
+        Ok(res)
+    }
+
+    /// Get time base of the decoded frames.
+    pub fn time_base(&self) -> TimeBase {
+        self.demuxer.streams()[self.stream_index].time_base()
+    }
+
+    /// Take the next frame. `None` is returned at the end of the animation.
+    pub fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        loop {
+            if let Some(frame) = self.decoder.take()? {
+                return self.convert(frame).map(Some);
+            } else if self.flushed {
+                return Ok(None);
+            }
+
+            match self.demuxer.take()? {
+                Some(packet) if packet.stream_index() == self.stream_index => {
+                    self.decoder.push(packet)?
+                }
+                Some(_) => (),
+                None => {
+                    self.decoder.flush()?;
+                    self.flushed = true;
+                }
+            }
+        }
+    }
+
+    /// Convert a given decoded frame into RGBA.
+    fn convert(&mut self, frame: VideoFrame) -> Result<VideoFrame, Error> {
+        let rgba = "rgba".parse::<PixelFormat>().unwrap();
+
+        if frame.pixel_format() == rgba {
+            return Ok(frame);
+        }
+
+        if self.scaler.is_none() {
+            let scaler = VideoFrameScaler::builder()
+                .source_pixel_format(frame.pixel_format())
+                .source_width(frame.width())
+                .source_height(frame.height())
+                .target_pixel_format(rgba)
+                .target_width(frame.width())
+                .target_height(frame.height())
+                .build()?;
+
+            self.scaler = Some(scaler);
+        }
+
+        self.scaler.as_mut().unwrap().scale(&frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, str::FromStr};
+
+    use super::{AnimatedImageDecoder, AnimatedImageEncoder, AnimatedImageFormat, PaletteMode};
+
+    use crate::{
+        codec::{
+            video::{PixelFormat, VideoFrameMut},
+            VideoCodecParameters,
+        },
+        format::io::IO,
+        math::Rational,
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_gif_round_trip() {
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+        let time_base = TimeBase::new(1, 25);
+
+        let codec_parameters = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .build();
+
+        for mode in [PaletteMode::Global, PaletteMode::PerFrame].iter() {
+            let mut encoder =
+                AnimatedImageEncoder::builder(&codec_parameters, AnimatedImageFormat::Gif)
+                    .input_time_base(time_base)
+                    .frame_rate(Rational::new(5, 1))
+                    .width(32)
+                    .palette_mode(*mode)
+                    .play_count(Some(2))
+                    .build(IO::from_write_stream(Vec::new()))
+                    .unwrap();
+
+            // one second of input at 25 fps
+            for i in 0..25 {
+                let mut frame = VideoFrameMut::black(pixel_format, 64, 48)
+                    .with_time_base(time_base)
+                    .with_pts(Timestamp::new(i, time_base));
+
+                for line in frame.planes_mut()[0].lines_mut() {
+                    line.iter_mut().for_each(|b| *b = (i * 10) as u8);
+                }
+
+                encoder.push(frame.freeze()).unwrap();
+            }
+
+            let data = encoder.finish().unwrap().into_stream();
+
+            assert!(data.starts_with(b"GIF89a"));
+
+            let mut decoder =
+                AnimatedImageDecoder::new(IO::from_seekable_read_stream(Cursor::new(data)))
+                    .unwrap();
+
+            let mut frames = 0;
+
+            while let Some(frame) = decoder.take().unwrap() {
+                assert_eq!(frame.pixel_format().name(), "rgba");
+                assert_eq!((frame.width(), frame.height()), (32, 24));
+
+                frames += 1;
+            }
+
+            assert_eq!(frames, 5);
+        }
+
+        assert_eq!(
+            AnimatedImageFormat::guess_from_file_name("out.GIF"),
+            Some(AnimatedImageFormat::Gif)
+        );
+    }
+}
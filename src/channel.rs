@@ -0,0 +1,288 @@
+//! Channel adapters.
+//!
+//! The adapters run a demuxer or a decoder in a background thread and pass
+//! the produced packets/frames over a bounded channel. The producer is
+//! blocked when the channel is full, so it never runs ahead of the consumer
+//! more than the channel capacity allows.
+//!
+//! Unlike a plain `std::sync::mpsc` channel, the receiving side
+//! distinguishes between the end of the stream (after the producer has
+//! been fully flushed) and a producer failure. Dropping the receiver stops
+//! the producer thread. The drop does not wait for the thread, so it does
+//! not block even if the producer is stuck (e.g. in a read from a live
+//! input). The thread will stop once the producer notices that the
+//! receiver is gone.
+//!
+//! If the `async` feature is enabled, the receiving side also implements
+//! the `futures_core::Stream` trait.
+
+use std::{
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    sync::{mpsc::TryRecvError, Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use crate::Error;
+
+/// Message sent from the producer thread.
+enum Message<T> {
+    Item(T),
+    Error(Error),
+    End,
+}
+
+/// Sending side of a producer thread.
+pub(crate) struct Producer<T> {
+    tx: SyncSender<Message<T>>,
+    #[cfg(feature = "async")]
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl<T> Producer<T> {
+    /// Send a given item to the consumer. The method blocks if the channel
+    /// is full. False is returned if the consumer is gone.
+    pub fn send(&self, item: T) -> bool {
+        self.send_message(Message::Item(item))
+    }
+
+    /// Send a given message and wake up the consumer task (if any).
+    fn send_message(&self, msg: Message<T>) -> bool {
+        let res = self.tx.send(msg).is_ok();
+
+        #[cfg(feature = "async")]
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+
+        res
+    }
+}
+
+/// Spawn a producer thread running a given function. The function is
+/// expected to return once it has produced all items or once the consumer
+/// is gone.
+pub(crate) fn spawn<T, F>(capacity: usize, f: F) -> ChannelReceiver<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&Producer<T>) -> Result<(), Error> + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel(capacity);
+
+    #[cfg(feature = "async")]
+    let waker = Arc::new(Mutex::new(None));
+
+    let producer = Producer {
+        tx,
+        #[cfg(feature = "async")]
+        waker: waker.clone(),
+    };
+
+    let thread = thread::spawn(move || {
+        let msg = match f(&producer) {
+            Ok(()) => Message::End,
+            Err(err) => Message::Error(err),
+        };
+
+        producer.send_message(msg);
+    });
+
+    ChannelReceiver {
+        rx: Some(rx),
+        thread: Some(thread),
+        #[cfg(feature = "async")]
+        waker,
+        finished: false,
+    }
+}
+
+/// Receiving side of a channel adapter.
+pub struct ChannelReceiver<T> {
+    // NOTE: there is no Drop implementation on purpose, dropping the
+    // receiver closes the channel and detaches the producer thread
+    rx: Option<Receiver<Message<T>>>,
+    thread: Option<JoinHandle<()>>,
+    #[cfg(feature = "async")]
+    waker: Arc<Mutex<Option<Waker>>>,
+    finished: bool,
+}
+
+impl<T> ChannelReceiver<T> {
+    /// Receive the next item. The method blocks until an item is available.
+    /// `None` is returned once the producer has finished. An error is
+    /// returned if the producer has failed. No more items will be returned
+    /// after the end or an error.
+    pub fn recv(&mut self) -> Result<Option<T>, Error> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let msg = self.rx.as_ref().and_then(|rx| rx.recv().ok());
+
+        self.process_message(msg)
+    }
+
+    /// Receive the next item without blocking. `None` is returned if no
+    /// message is available at the moment.
+    #[cfg(feature = "async")]
+    fn try_recv(&mut self) -> Option<Result<Option<T>, Error>> {
+        if self.finished {
+            return Some(Ok(None));
+        }
+
+        let msg = match self.rx.as_ref().map(|rx| rx.try_recv()) {
+            Some(Ok(msg)) => Some(msg),
+            Some(Err(TryRecvError::Empty)) => return None,
+            Some(Err(TryRecvError::Disconnected)) | None => None,
+        };
+
+        Some(self.process_message(msg))
+    }
+
+    /// Process a given message (`None` means that the producer is gone).
+    fn process_message(&mut self, msg: Option<Message<T>>) -> Result<Option<T>, Error> {
+        match msg {
+            Some(Message::Item(item)) => Ok(Some(item)),
+            Some(Message::End) => {
+                self.finish();
+
+                Ok(None)
+            }
+            Some(Message::Error(err)) => {
+                self.finish();
+
+                Err(err)
+            }
+            None => {
+                self.finish();
+
+                Err(Error::new("producer thread terminated unexpectedly"))
+            }
+        }
+    }
+
+    /// Close the channel and wait for the producer thread. This is called
+    /// only after the producer has finished, so the join does not block.
+    fn finish(&mut self) {
+        self.finished = true;
+
+        // this will unblock the producer if it is waiting for free space
+        self.rx = None;
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl<T> Iterator for ChannelReceiver<T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv().transpose()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> futures_core::Stream for ChannelReceiver<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(res) = this.try_recv() {
+            return Poll::Ready(res.transpose());
+        }
+
+        *this.waker.lock().unwrap() = Some(cx.waker().clone());
+
+        // check again in case a message has been sent before the waker was
+        // registered
+        if let Some(res) = this.try_recv() {
+            Poll::Ready(res.transpose())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Sending side of a channel adapter (e.g. for passing packets to a decoder
+/// running in a background thread).
+///
+/// Dropping the sender (or calling `close()`) signals the end of the input.
+/// The consumer will be flushed afterwards.
+pub struct ChannelSender<T> {
+    tx: SyncSender<T>,
+}
+
+impl<T> ChannelSender<T> {
+    /// Create a new sender.
+    pub(crate) fn new(tx: SyncSender<T>) -> Self {
+        Self { tx }
+    }
+
+    /// Send a given item. The method blocks if the channel is full. An
+    /// error is returned if the consumer has terminated (either due to an
+    /// error or because its output has been dropped).
+    pub fn send(&self, item: T) -> Result<(), Error> {
+        self.tx
+            .send(item)
+            .map_err(|_| Error::new("the receiving side has terminated"))
+    }
+
+    /// Signal the end of the input.
+    pub fn close(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use super::spawn;
+
+    #[test]
+    fn test_end_of_stream() {
+        let mut receiver = spawn(1, |producer| {
+            for i in 0..3 {
+                producer.send(i);
+            }
+
+            Ok(())
+        });
+
+        let items = receiver.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(items, [0, 1, 2]);
+        assert!(receiver.recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_drop_with_blocked_producer() {
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        // the producer simulates a read blocked on a live input
+        let receiver = spawn::<u32, _>(1, move |_| {
+            let _ = release_rx.recv();
+
+            Ok(())
+        });
+
+        let (done_tx, done_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            drop(receiver);
+
+            let _ = done_tx.send(());
+        });
+
+        assert!(done_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        // unblock the producer thread
+        drop(release_tx);
+    }
+}
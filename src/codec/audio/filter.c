@@ -0,0 +1,191 @@
+#include <libavfilter/avfilter.h>
+#include <libavfilter/buffersink.h>
+#include <libavfilter/buffersrc.h>
+#include <libavutil/frame.h>
+#include <libavutil/samplefmt.h>
+
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+typedef struct AudioFilter {
+    AVFilterGraph* graph;
+    AVFilterContext* source;
+    AVFilterContext* sink;
+    AVFrame* frame;
+} AudioFilter;
+
+int ffw_audio_filter_new(
+    AudioFilter** filter,
+    int format, int sample_rate, uint64_t channel_layout,
+    int tb_num, int tb_den,
+    const char* description);
+void ffw_audio_filter_get_output_time_base(const AudioFilter* filter, int* num, int* den);
+int ffw_audio_filter_push_frame(AudioFilter* filter, const AVFrame* frame);
+int ffw_audio_filter_take_frame(AudioFilter* filter, AVFrame** frame);
+void ffw_audio_filter_free(AudioFilter* filter);
+
+int ffw_audio_filter_new(
+    AudioFilter** filter,
+    int format, int sample_rate, uint64_t channel_layout,
+    int tb_num, int tb_den,
+    const char* description) {
+    AVFilterInOut* inputs = NULL;
+    AVFilterInOut* outputs = NULL;
+    const char* sample_fmt;
+    char args[512];
+    int ret;
+
+    AudioFilter* res = malloc(sizeof(AudioFilter));
+    if (res == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    res->graph = NULL;
+    res->source = NULL;
+    res->sink = NULL;
+    res->frame = NULL;
+
+    sample_fmt = av_get_sample_fmt_name(format);
+    if (sample_fmt == NULL) {
+        ret = AVERROR(EINVAL);
+        goto err;
+    }
+
+    snprintf(
+        args, sizeof(args),
+        "time_base=%d/%d:sample_rate=%d:sample_fmt=%s:channel_layout=0x%" PRIx64,
+        tb_num, tb_den, sample_rate, sample_fmt, channel_layout);
+
+    res->graph = avfilter_graph_alloc();
+    if (res->graph == NULL) {
+        ret = AVERROR(ENOMEM);
+        goto err;
+    }
+
+    ret = avfilter_graph_create_filter(
+        &res->source, avfilter_get_by_name("abuffer"), "in", args, NULL, res->graph);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = avfilter_graph_create_filter(
+        &res->sink, avfilter_get_by_name("abuffersink"), "out", NULL, NULL, res->graph);
+    if (ret < 0) {
+        goto err;
+    }
+
+    // NOTE: the naming is a bit counter-intuitive here; "outputs" is the
+    // list of the unconnected outputs of our buffer source (i.e. inputs of
+    // the graph described by the description) and vice versa
+    outputs = avfilter_inout_alloc();
+    inputs = avfilter_inout_alloc();
+    if (outputs == NULL || inputs == NULL) {
+        ret = AVERROR(ENOMEM);
+        goto err;
+    }
+
+    outputs->name = av_strdup("in");
+    outputs->filter_ctx = res->source;
+    outputs->pad_idx = 0;
+    outputs->next = NULL;
+
+    inputs->name = av_strdup("out");
+    inputs->filter_ctx = res->sink;
+    inputs->pad_idx = 0;
+    inputs->next = NULL;
+
+    if (outputs->name == NULL || inputs->name == NULL) {
+        ret = AVERROR(ENOMEM);
+        goto err;
+    }
+
+    ret = avfilter_graph_parse_ptr(res->graph, description, &inputs, &outputs, NULL);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = avfilter_graph_config(res->graph, NULL);
+    if (ret < 0) {
+        goto err;
+    }
+
+    avfilter_inout_free(&inputs);
+    avfilter_inout_free(&outputs);
+
+    *filter = res;
+
+    return 0;
+
+err:
+    avfilter_inout_free(&inputs);
+    avfilter_inout_free(&outputs);
+
+    ffw_audio_filter_free(res);
+
+    return ret;
+}
+
+void ffw_audio_filter_get_output_time_base(const AudioFilter* filter, int* num, int* den) {
+    AVRational tb = av_buffersink_get_time_base(filter->sink);
+
+    *num = tb.num;
+    *den = tb.den;
+}
+
+int ffw_audio_filter_push_frame(AudioFilter* filter, const AVFrame* frame) {
+    int ret;
+
+    // the buffer source will take its own reference to the frame (the
+    // frame is owned by the caller); flushing is done by passing NULL
+    if (frame == NULL) {
+        ret = av_buffersrc_add_frame_flags(filter->source, NULL, 0);
+    } else {
+        ret = av_buffersrc_add_frame_flags(
+            filter->source, (AVFrame*)frame, AV_BUFFERSRC_FLAG_KEEP_REF);
+    }
+
+    if (ret == AVERROR(EAGAIN)) {
+        return 0;
+    } else if (ret < 0) {
+        return ret;
+    }
+
+    return 1;
+}
+
+int ffw_audio_filter_take_frame(AudioFilter* filter, AVFrame** frame) {
+    int ret;
+
+    if (filter->frame == NULL) {
+        filter->frame = av_frame_alloc();
+        if (filter->frame == NULL) {
+            return AVERROR(ENOMEM);
+        }
+    }
+
+    ret = av_buffersink_get_frame(filter->sink, filter->frame);
+    if (ret == AVERROR_EOF || ret == AVERROR(EAGAIN)) {
+        return 0;
+    } else if (ret < 0) {
+        return ret;
+    }
+
+    *frame = filter->frame;
+
+    filter->frame = NULL;
+
+    return 1;
+}
+
+void ffw_audio_filter_free(AudioFilter* filter) {
+    if (filter == NULL) {
+        return;
+    }
+
+    // NOTE: the filter contexts are owned by the graph
+    avfilter_graph_free(&filter->graph);
+    av_frame_free(&filter->frame);
+
+    free(filter);
+}
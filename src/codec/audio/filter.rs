@@ -0,0 +1,243 @@
+//! Audio filter graph.
+
+use std::{
+    ffi::CString,
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+};
+
+use crate::{
+    codec::{
+        audio::{AudioFrame, ChannelLayout, SampleFormat},
+        AudioCodecParameters, CodecError, Filter,
+    },
+    time::TimeBase,
+    Error,
+};
+
+extern "C" {
+    fn ffw_audio_filter_new(
+        filter: *mut *mut c_void,
+        format: c_int,
+        sample_rate: c_int,
+        channel_layout: u64,
+        tb_num: c_int,
+        tb_den: c_int,
+        description: *const c_char,
+    ) -> c_int;
+    fn ffw_audio_filter_get_output_time_base(
+        filter: *const c_void,
+        num: *mut c_int,
+        den: *mut c_int,
+    );
+    fn ffw_audio_filter_push_frame(filter: *mut c_void, frame: *const c_void) -> c_int;
+    fn ffw_audio_filter_take_frame(filter: *mut c_void, frame: *mut *mut c_void) -> c_int;
+    fn ffw_audio_filter_free(filter: *mut c_void);
+}
+
+/// Builder for an audio filter.
+pub struct AudioFilterBuilder {
+    description: String,
+
+    sample_format: SampleFormat,
+    sample_rate: u32,
+    channel_layout: Option<ChannelLayout>,
+    time_base: TimeBase,
+}
+
+impl AudioFilterBuilder {
+    /// Create a new audio filter builder.
+    fn new(codec_parameters: &AudioCodecParameters, description: &str) -> Self {
+        let channel_layout = codec_parameters.channel_layout();
+
+        // the channel layout may be unknown even if the number of channels
+        // is known
+        let channel_layout = if channel_layout.into_raw() == 0 {
+            ChannelLayout::from_channels(codec_parameters.channels())
+        } else {
+            Some(channel_layout)
+        };
+
+        Self {
+            description: description.to_string(),
+
+            sample_format: codec_parameters.sample_format(),
+            sample_rate: codec_parameters.sample_rate(),
+            channel_layout,
+            time_base: TimeBase::MICROSECONDS,
+        }
+    }
+
+    /// Set input time base (all input frames will be rescaled into this time
+    /// base). The default time base is in microseconds.
+    pub fn input_time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Build the audio filter.
+    pub fn build(self) -> Result<AudioFilter, Error> {
+        let description = CString::new(self.description)
+            .map_err(|_| Error::new("invalid filter graph description"))?;
+
+        let channel_layout = self
+            .channel_layout
+            .ok_or_else(|| Error::new("unknown channel layout"))?;
+
+        let tb = self.time_base;
+
+        let mut ptr = ptr::null_mut();
+
+        let ret = unsafe {
+            ffw_audio_filter_new(
+                &mut ptr,
+                self.sample_format.into_raw(),
+                self.sample_rate as _,
+                channel_layout.into_raw(),
+                tb.num() as _,
+                tb.den() as _,
+                description.as_ptr(),
+            )
+        };
+
+        if ret < 0 {
+            return Err(Error::from_raw_error_code(ret));
+        }
+
+        let mut num = 0;
+        let mut den = 0;
+
+        unsafe {
+            ffw_audio_filter_get_output_time_base(ptr, &mut num, &mut den);
+        }
+
+        let output_time_base = TimeBase::new(num as _, den as _);
+
+        let res = AudioFilter {
+            ptr,
+            input_time_base: tb,
+            output_time_base,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Audio filter graph.
+///
+/// The filter graph is described using the FFmpeg filter graph syntax (e.g.
+/// `"atempo=1.5"` or `"volume=0.5,aecho=0.8:0.9:1000:0.3"`). The graph must
+/// have exactly one input and one output.
+pub struct AudioFilter {
+    ptr: *mut c_void,
+    input_time_base: TimeBase,
+    output_time_base: TimeBase,
+}
+
+impl AudioFilter {
+    /// Create a new audio filter for frames described by given codec
+    /// parameters.
+    pub fn new(codec_parameters: &AudioCodecParameters, description: &str) -> Result<Self, Error> {
+        AudioFilterBuilder::new(codec_parameters, description).build()
+    }
+
+    /// Get an audio filter builder for frames described by given codec
+    /// parameters.
+    pub fn builder(
+        codec_parameters: &AudioCodecParameters,
+        description: &str,
+    ) -> AudioFilterBuilder {
+        AudioFilterBuilder::new(codec_parameters, description)
+    }
+
+    /// Get input time base.
+    pub fn input_time_base(&self) -> TimeBase {
+        self.input_time_base
+    }
+
+    /// Get time base of the output frames.
+    pub fn output_time_base(&self) -> TimeBase {
+        self.output_time_base
+    }
+}
+
+impl Filter for AudioFilter {
+    type Frame = AudioFrame;
+
+    fn try_push(&mut self, frame: AudioFrame) -> Result<(), CodecError> {
+        let frame = frame.with_time_base(self.input_time_base);
+
+        unsafe {
+            match ffw_audio_filter_push_frame(self.ptr, frame.as_ptr()) {
+                1 => Ok(()),
+                0 => Err(CodecError::again(
+                    "all frames must be consumed before pushing a new frame",
+                )),
+                e => Err(CodecError::from_raw_error_code(e)),
+            }
+        }
+    }
+
+    fn try_flush(&mut self) -> Result<(), CodecError> {
+        unsafe {
+            match ffw_audio_filter_push_frame(self.ptr, ptr::null()) {
+                1 => Ok(()),
+                0 => Err(CodecError::again(
+                    "all frames must be consumed before flushing",
+                )),
+                e => Err(CodecError::from_raw_error_code(e)),
+            }
+        }
+    }
+
+    fn take(&mut self) -> Result<Option<AudioFrame>, Error> {
+        let mut fptr = ptr::null_mut();
+
+        unsafe {
+            match ffw_audio_filter_take_frame(self.ptr, &mut fptr) {
+                1 => {
+                    if fptr.is_null() {
+                        panic!("no frame received")
+                    } else {
+                        Ok(Some(AudioFrame::from_raw_ptr(fptr, self.output_time_base)))
+                    }
+                }
+                0 => Ok(None),
+                e => Err(Error::from_raw_error_code(e)),
+            }
+        }
+    }
+}
+
+impl Drop for AudioFilter {
+    fn drop(&mut self) {
+        unsafe { ffw_audio_filter_free(self.ptr) }
+    }
+}
+
+unsafe impl Send for AudioFilter {}
+unsafe impl Sync for AudioFilter {}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioFilter;
+
+    use crate::codec::{
+        audio::{self, ChannelLayout},
+        AudioCodecParameters,
+    };
+
+    #[test]
+    fn test_invalid_description() {
+        let codec_parameters = AudioCodecParameters::builder("pcm_f32le")
+            .unwrap()
+            .sample_format(audio::frame::get_sample_format("flt"))
+            .sample_rate(48000)
+            .channel_layout(ChannelLayout::STEREO)
+            .build();
+
+        assert!(AudioFilter::new(&codec_parameters, "anull").is_ok());
+        assert!(AudioFilter::new(&codec_parameters, "an\0ull").is_err());
+        assert!(AudioFilter::new(&codec_parameters, "no_such_filter").is_err());
+    }
+}
@@ -4,22 +4,28 @@ use std::{
     ffi::{CStr, CString},
     fmt::{self, Display, Formatter},
     marker::PhantomData,
-    ops::{Deref, DerefMut},
+    ops::{Bound, Deref, DerefMut, RangeBounds},
     os::raw::{c_char, c_int, c_void},
     ptr, slice,
     str::FromStr,
 };
 
-use crate::time::{TimeBase, Timestamp};
+use crate::{
+    time::{TimeBase, Timestamp},
+    Error,
+};
 
 extern "C" {
     fn ffw_get_channel_layout_by_name(name: *const c_char) -> u64;
     fn ffw_get_channel_layout_channels(layout: u64) -> c_int;
     fn ffw_get_default_channel_layout(channels: c_int) -> u64;
+    fn ffw_get_channel_layout_name(layout: u64, buffer: *mut c_char, size: c_int);
+    fn ffw_get_channel_layout_channel_name(layout: u64, index: c_int) -> *const c_char;
 
     fn ffw_get_sample_format_by_name(name: *const c_char) -> c_int;
     fn ffw_get_sample_format_name(format: c_int) -> *const c_char;
     fn ffw_sample_format_is_planar(format: c_int) -> c_int;
+    fn ffw_sample_format_get_bytes_per_sample(format: c_int) -> c_int;
     fn ffw_sample_format_is_none(format: c_int) -> c_int;
 
     fn ffw_frame_new_silence(
@@ -28,6 +34,11 @@ extern "C" {
         sample_rate: c_int,
         nb_samples: c_int,
     ) -> *mut c_void;
+    fn ffw_frame_slice_samples(
+        frame: *const c_void,
+        offset: c_int,
+        nb_samples: c_int,
+    ) -> *mut c_void;
     fn ffw_frame_get_format(frame: *const c_void) -> c_int;
     fn ffw_frame_get_nb_samples(frame: *const c_void) -> c_int;
     fn ffw_frame_get_sample_rate(frame: *const c_void) -> c_int;
@@ -58,6 +69,29 @@ impl std::error::Error for UnknownChannelLayout {}
 pub struct ChannelLayout(u64);
 
 impl ChannelLayout {
+    /// Mono (front center).
+    pub const MONO: Self = Self(0x4);
+
+    /// Stereo (front left, front right).
+    pub const STEREO: Self = Self(0x3);
+
+    /// 2.1 (stereo + LFE).
+    pub const STEREO_2_1: Self = Self(0xb);
+
+    /// Quad (stereo + back left, back right).
+    pub const QUAD: Self = Self(0x33);
+
+    /// 5.1 with side channels (i.e. `5.1(side)`, front left, front right,
+    /// front center, LFE, side left, side right).
+    pub const SURROUND_5_1: Self = Self(0x60f);
+
+    /// 5.1 with back channels (i.e. `5.1`, front left, front right, front
+    /// center, LFE, back left, back right).
+    pub const SURROUND_5_1_BACK: Self = Self(0x3f);
+
+    /// 7.1 (5.1 with side channels + back left, back right).
+    pub const SURROUND_7_1: Self = Self(0x63f);
+
     /// Create channel layout from its raw representation.
     pub(crate) fn from_raw(v: u64) -> Self {
         Self(v)
@@ -85,6 +119,50 @@ impl ChannelLayout {
     pub fn channels(self) -> u32 {
         unsafe { ffw_get_channel_layout_channels(self.into_raw()) as _ }
     }
+
+    /// Get number of channels (the same as `channels()`).
+    pub fn channel_count(self) -> u32 {
+        self.channels()
+    }
+
+    /// Get names of the individual channels (e.g. `FL`, `FR`, `LFE`) in the
+    /// order they are stored in audio frames.
+    pub fn channel_names(self) -> impl Iterator<Item = &'static str> {
+        (0..self.channels()).map(move |index| unsafe {
+            let name = ffw_get_channel_layout_channel_name(self.into_raw(), index as _);
+
+            if name.is_null() {
+                "?"
+            } else {
+                CStr::from_ptr(name as _).to_str().unwrap_or("?")
+            }
+        })
+    }
+
+    /// Get index of a channel with a given name (e.g. `LFE`) within audio
+    /// frames of this layout.
+    pub fn channel_index(self, name: &str) -> Option<usize> {
+        self.channel_names().position(|n| n == name)
+    }
+
+    /// Check if the layout contains a channel with a given name.
+    pub fn has_channel(self, name: &str) -> bool {
+        self.channel_index(name).is_some()
+    }
+}
+
+impl Display for ChannelLayout {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        let mut buffer = [0 as c_char; 64];
+
+        let name = unsafe {
+            ffw_get_channel_layout_name(self.into_raw(), buffer.as_mut_ptr(), buffer.len() as _);
+
+            CStr::from_ptr(buffer.as_ptr())
+        };
+
+        f.write_str(&name.to_string_lossy())
+    }
 }
 
 impl FromStr for ChannelLayout {
@@ -147,6 +225,44 @@ impl SampleFormat {
     pub fn is_planar(self) -> bool {
         unsafe { ffw_sample_format_is_planar(self.into_raw()) != 0 }
     }
+
+    /// Get the number of bytes per sample (of a single channel).
+    pub fn bytes_per_sample(self) -> usize {
+        unsafe { ffw_sample_format_get_bytes_per_sample(self.into_raw()) as _ }
+    }
+
+    /// Check if the samples are floating point numbers.
+    pub fn is_float(self) -> bool {
+        let name = self.name();
+
+        name.starts_with("flt") || name.starts_with("dbl")
+    }
+
+    /// Get a function converting a single sample into a float. Integer
+    /// samples are scaled into the -1 to 1 range. `None` is returned for
+    /// unknown sample formats.
+    pub(crate) fn f32_converter(self) -> Option<fn(&[u8]) -> f32> {
+        if self.into_raw() < 0 {
+            return None;
+        }
+
+        let res: fn(&[u8]) -> f32 = match self.bytes_per_sample() {
+            1 => |b| (f32::from(b[0]) - 128.0) / 128.0,
+            2 => |b| f32::from(i16::from_ne_bytes([b[0], b[1]])) / 32768.0,
+            4 if self.is_float() => |b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]),
+            4 => |b| i32::from_ne_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+            8 if self.is_float() => {
+                |b| f64::from_ne_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+            }
+            8 => |b| {
+                i64::from_ne_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+                    / 9_223_372_036_854_775_808.0
+            },
+            _ => return None,
+        };
+
+        Some(res)
+    }
 }
 
 impl FromStr for SampleFormat {
@@ -299,12 +415,29 @@ pub struct AudioFrameMut {
 impl AudioFrameMut {
     /// Create an audio frame containing silence. The time base of the frame
     /// will be in microseconds.
+    ///
+    /// # Panics
+    /// The method panics if the frame cannot be allocated. Use
+    /// `try_silence()` if you want to handle the error.
     pub fn silence(
         channel_layout: ChannelLayout,
         sample_format: SampleFormat,
         sample_rate: u32,
         samples: usize,
     ) -> Self {
+        Self::try_silence(channel_layout, sample_format, sample_rate, samples)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create an audio frame containing silence. The time base of the frame
+    /// will be in microseconds. An error is returned if the frame cannot be
+    /// allocated.
+    pub fn try_silence(
+        channel_layout: ChannelLayout,
+        sample_format: SampleFormat,
+        sample_rate: u32,
+        samples: usize,
+    ) -> Result<Self, Error> {
         let ptr = unsafe {
             ffw_frame_new_silence(
                 channel_layout.into_raw(),
@@ -315,13 +448,15 @@ impl AudioFrameMut {
         };
 
         if ptr.is_null() {
-            panic!("unable to allocate an audio frame");
+            return Err(Error::new("unable to allocate an audio frame"));
         }
 
-        Self {
+        let res = Self {
             ptr,
             time_base: TimeBase::MICROSECONDS,
-        }
+        };
+
+        Ok(res)
     }
 
     /// Get frame sample format.
@@ -340,12 +475,12 @@ impl AudioFrameMut {
     }
 
     /// Get sample data planes for this frame.
-    pub fn planes(&self) -> Planes {
+    pub fn planes(&self) -> Planes<'_> {
         Planes::from(self)
     }
 
     /// Get mutable sample data planes for this frame.
-    pub fn planes_mut(&mut self) -> PlanesMut {
+    pub fn planes_mut(&mut self) -> PlanesMut<'_> {
         PlanesMut::from(self)
     }
 
@@ -379,6 +514,7 @@ impl AudioFrameMut {
     }
 
     /// Get presentation timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn pts(&self) -> Timestamp {
         let pts = unsafe { ffw_frame_get_pts(self.ptr) };
 
@@ -444,7 +580,7 @@ impl AudioFrame {
     }
 
     /// Get sample data planes for this frame.
-    pub fn planes(&self) -> Planes {
+    pub fn planes(&self) -> Planes<'_> {
         Planes::from(self)
     }
 
@@ -478,6 +614,7 @@ impl AudioFrame {
     }
 
     /// Get presentation timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn pts(&self) -> Timestamp {
         let pts = unsafe { ffw_frame_get_pts(self.ptr) };
 
@@ -493,6 +630,96 @@ impl AudioFrame {
         self
     }
 
+    /// Create a new frame containing a given range of samples. The sample
+    /// data is not copied, the new frame refers to the data of this frame.
+    /// The timestamp of the new frame will be shifted accordingly.
+    ///
+    /// # Panics
+    /// The method panics if the range is out of bounds or if the frame
+    /// cannot be allocated.
+    pub fn slice<R>(&self, range: R) -> Self
+    where
+        R: RangeBounds<usize>,
+    {
+        let len = self.samples();
+
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n + 1,
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => len,
+        };
+
+        assert!(start <= end && end <= len, "sample range out of bounds");
+
+        let ptr = unsafe { ffw_frame_slice_samples(self.ptr, start as _, (end - start) as _) };
+
+        if ptr.is_null() {
+            panic!("unable to allocate an audio frame");
+        }
+
+        let res = Self {
+            ptr,
+            time_base: self.time_base,
+        };
+
+        let pts = self.pts();
+
+        if pts.is_null() {
+            return res;
+        }
+
+        let sample_time_base = TimeBase::new(1, self.sample_rate());
+
+        let pts = pts.with_time_base(sample_time_base);
+        let pts = Timestamp::new(pts.timestamp() + start as i64, sample_time_base);
+
+        res.with_pts(pts)
+    }
+
+    /// Truncate the frame to a given number of samples (per channel). The
+    /// sample data is not copied. The frame is returned unchanged if it
+    /// does not contain more samples than requested.
+    pub fn with_samples_truncated(self, samples: usize) -> Self {
+        if samples < self.samples() {
+            self.slice(..samples)
+        } else {
+            self
+        }
+    }
+
+    /// Check if all samples in the frame are equal to zero (i.e. the frame
+    /// contains digital silence). The method returns false for unknown
+    /// sample formats.
+    pub fn is_silent(&self) -> bool {
+        let sample_format = self.sample_format();
+
+        let convert = match sample_format.f32_converter() {
+            Some(f) => f,
+            None => return false,
+        };
+
+        let sample_size = sample_format.bytes_per_sample();
+
+        // the planes may contain some padding after the samples
+        let len = if sample_format.is_planar() {
+            self.samples() * sample_size
+        } else {
+            self.samples() * sample_size * self.channels() as usize
+        };
+
+        self.planes().iter().all(|plane| {
+            plane.data()[..len]
+                .chunks_exact(sample_size)
+                .all(|sample| convert(sample) == 0.0)
+        })
+    }
+
     /// Get raw pointer.
     pub(crate) fn as_ptr(&self) -> *const c_void {
         self.ptr
@@ -522,3 +749,100 @@ impl Drop for AudioFrame {
 
 unsafe impl Send for AudioFrame {}
 unsafe impl Sync for AudioFrame {}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioFrameMut, ChannelLayout, SampleFormat};
+
+    use crate::time::{TimeBase, Timestamp};
+
+    #[test]
+    fn test_channel_layout() {
+        let layout = "5.1(side)".parse::<ChannelLayout>().unwrap();
+
+        assert!(layout == ChannelLayout::SURROUND_5_1);
+        assert_eq!(layout.channel_count(), 6);
+        assert_eq!(layout.to_string(), "5.1(side)");
+        assert_eq!(
+            layout.channel_names().collect::<Vec<_>>(),
+            ["FL", "FR", "FC", "LFE", "SL", "SR"]
+        );
+        assert_eq!(layout.channel_index("LFE"), Some(3));
+
+        assert!(!ChannelLayout::STEREO.has_channel("FC"));
+        assert_eq!(ChannelLayout::MONO.to_string(), "mono");
+        assert_eq!(ChannelLayout::SURROUND_7_1.to_string(), "7.1");
+        assert_eq!(ChannelLayout::SURROUND_5_1_BACK.to_string(), "5.1");
+        assert!(ChannelLayout::from_channels(2) == Some(ChannelLayout::STEREO));
+    }
+
+    #[test]
+    fn test_audio_frame_slice() {
+        let layout = ChannelLayout::from_channels(2).unwrap();
+
+        for format in ["s16", "s16p"].iter() {
+            let format = format.parse::<SampleFormat>().unwrap();
+
+            let mut frame = AudioFrameMut::silence(layout, format, 48_000, 1024);
+
+            for plane in frame.planes_mut().iter_mut() {
+                for (i, b) in plane.data_mut().iter_mut().enumerate() {
+                    *b = i as u8;
+                }
+            }
+
+            let frame = frame
+                .with_time_base(TimeBase::new(1, 48_000))
+                .with_pts(Timestamp::new(100, TimeBase::new(1, 48_000)))
+                .freeze();
+
+            let slice = frame.slice(256..512);
+
+            assert_eq!(slice.samples(), 256);
+            assert_eq!(slice.pts().timestamp(), 356);
+
+            let bytes_per_sample = if format.is_planar() { 2 } else { 4 };
+
+            let planes = frame.planes();
+            let slice_planes = slice.planes();
+
+            assert_eq!(planes.len(), slice_planes.len());
+
+            for (plane, slice_plane) in planes.iter().zip(slice_planes.iter()) {
+                let start = 256 * bytes_per_sample;
+                let end = 512 * bytes_per_sample;
+
+                // the data is shared
+                assert_eq!(plane.data()[start..].as_ptr(), slice_plane.data().as_ptr());
+                assert_eq!(&plane.data()[start..end], slice_plane.data());
+            }
+
+            assert_eq!(frame.slice(..).samples(), 1024);
+            assert_eq!(frame.slice(1024..).samples(), 0);
+        }
+    }
+
+    #[test]
+    fn test_audio_frame_silence() {
+        let layout = ChannelLayout::from_channels(2).unwrap();
+
+        for format in ["u8", "s16p", "flt"].iter() {
+            let format = format.parse::<SampleFormat>().unwrap();
+
+            let frame = AudioFrameMut::silence(layout, format, 48_000, 1024).freeze();
+
+            assert!(frame.is_silent());
+
+            let frame = frame.with_samples_truncated(100);
+
+            assert_eq!(frame.samples(), 100);
+            assert_eq!(frame.with_samples_truncated(200).samples(), 100);
+
+            let mut frame = AudioFrameMut::silence(layout, format, 48_000, 1024);
+
+            frame.planes_mut()[0].data_mut()[4] ^= 0x11;
+
+            assert!(!frame.freeze().is_silent());
+        }
+    }
+}
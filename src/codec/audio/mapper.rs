@@ -0,0 +1,266 @@
+//! Audio channel mapping.
+
+use crate::{
+    codec::{
+        audio::{AudioFilter, AudioFrame, ChannelLayout},
+        AudioCodecParameters, CodecError, Filter,
+    },
+    time::TimeBase,
+    Error,
+};
+
+/// Builder for the channel mapper.
+pub struct ChannelMapperBuilder {
+    codec_parameters: AudioCodecParameters,
+    outputs: Vec<(ChannelLayout, Vec<usize>)>,
+}
+
+impl ChannelMapperBuilder {
+    /// Create a new builder for audio frames described by given codec
+    /// parameters.
+    fn new(codec_parameters: &AudioCodecParameters) -> Self {
+        Self {
+            codec_parameters: codec_parameters.clone(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Add an output with a given channel layout. The output channels are
+    /// taken from the given source channels (i.e. `channels[i]` is the index
+    /// of the source channel used as the i-th output channel). The number of
+    /// source channels must match the number of channels of the output
+    /// channel layout. Outputs are indexed in the order they were added.
+    pub fn add_output(mut self, channel_layout: ChannelLayout, channels: &[usize]) -> Self {
+        self.outputs.push((channel_layout, channels.to_vec()));
+        self
+    }
+
+    /// Add a mono output for each source channel (in the order of the
+    /// source channels).
+    pub fn split(mut self) -> Self {
+        let mono = ChannelLayout::MONO;
+
+        for channel in 0..self.codec_parameters.channels() as usize {
+            self.outputs.push((mono, vec![channel]));
+        }
+
+        self
+    }
+
+    /// Build the channel mapper.
+    pub fn build(self) -> Result<ChannelMapper, Error> {
+        if self.outputs.is_empty() {
+            return Err(Error::new("no outputs"));
+        }
+
+        let source_channels = self.codec_parameters.channels() as usize;
+        let sample_format = self.codec_parameters.sample_format();
+        let time_base = TimeBase::new(1, self.codec_parameters.sample_rate());
+
+        let mut outputs = Vec::with_capacity(self.outputs.len());
+
+        for (channel_layout, channels) in self.outputs {
+            if channels.len() != channel_layout.channels() as usize {
+                return Err(Error::new(
+                    "number of output channels does not match the channel layout",
+                ));
+            }
+
+            if channels.iter().any(|&channel| channel >= source_channels) {
+                return Err(Error::new("invalid source channel"));
+            }
+
+            let map = channels
+                .iter()
+                .map(|channel| channel.to_string())
+                .collect::<Vec<_>>()
+                .join("|");
+
+            // the channelmap filter works with planar formats only, so the
+            // original sample format needs to be restored
+            let description = format!(
+                "channelmap=map={}:channel_layout=0x{:x},aformat=sample_fmts={}",
+                map,
+                channel_layout.into_raw(),
+                sample_format.name(),
+            );
+
+            let filter = AudioFilter::builder(&self.codec_parameters, &description)
+                .input_time_base(time_base)
+                .build()?;
+
+            outputs.push(filter);
+        }
+
+        let res = ChannelMapper { outputs };
+
+        Ok(res)
+    }
+}
+
+/// Channel mapper.
+///
+/// The channel mapper extracts and reorders channels of audio frames (e.g.
+/// it can split an 8-channel capture into four stereo pairs). Every input
+/// frame produces frames on all outputs.
+///
+/// # Mapper operation
+/// 1. Push an audio frame to the mapper.
+/// 2. Take all frames from all outputs until you get None.
+/// 3. If there are more frames to be mapped, continue with 1.
+/// 4. Flush the mapper.
+/// 5. Take all frames from all outputs until you get None.
+///
+/// Timestamps of the output frames will be in 1 / sample_rate time base.
+pub struct ChannelMapper {
+    outputs: Vec<AudioFilter>,
+}
+
+impl ChannelMapper {
+    /// Get a builder for a channel mapper of audio frames described by given
+    /// codec parameters.
+    pub fn builder(codec_parameters: &AudioCodecParameters) -> ChannelMapperBuilder {
+        ChannelMapperBuilder::new(codec_parameters)
+    }
+
+    /// Get the number of outputs.
+    pub fn outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Push a given frame to the mapper.
+    ///
+    /// # Panics
+    /// The method panics if the operation is not expected (i.e. another
+    /// operation needs to be done).
+    pub fn push(&mut self, frame: AudioFrame) -> Result<(), Error> {
+        self.try_push(frame).map_err(|err| err.unwrap_inner())
+    }
+
+    /// Push a given frame to the mapper.
+    pub fn try_push(&mut self, frame: AudioFrame) -> Result<(), CodecError> {
+        for output in &mut self.outputs {
+            output.try_push(frame.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush the mapper.
+    ///
+    /// # Panics
+    /// The method panics if the operation is not expected (i.e. another
+    /// operation needs to be done).
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.try_flush().map_err(|err| err.unwrap_inner())
+    }
+
+    /// Flush the mapper.
+    pub fn try_flush(&mut self) -> Result<(), CodecError> {
+        for output in &mut self.outputs {
+            output.try_flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Take a frame from a given output (if available).
+    ///
+    /// # Panics
+    /// The method panics if the output index is out of range.
+    pub fn take(&mut self, output: usize) -> Result<Option<AudioFrame>, Error> {
+        self.outputs[output].take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChannelMapper;
+
+    use crate::{
+        codec::{
+            audio::{self, AudioFrame, AudioFrameMut, ChannelLayout},
+            AudioCodecParameters,
+        },
+        time::{TimeBase, Timestamp},
+    };
+
+    fn samples(frame: &AudioFrame) -> Vec<f32> {
+        frame
+            .planes()
+            .iter()
+            .map(|plane| {
+                let b = &plane.data()[..4];
+
+                f32::from_ne_bytes([b[0], b[1], b[2], b[3]])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_channel_mapper() {
+        let sample_format = audio::frame::get_sample_format("fltp");
+        let channel_layout = ChannelLayout::from_channels(8).unwrap();
+
+        let codec_parameters = AudioCodecParameters::builder("pcm_f32le")
+            .unwrap()
+            .sample_format(sample_format)
+            .sample_rate(48000)
+            .channel_layout(channel_layout)
+            .build();
+
+        let mut mapper = ChannelMapper::builder(&codec_parameters)
+            .add_output(ChannelLayout::STEREO, &[0, 1])
+            .add_output(ChannelLayout::STEREO, &[3, 2])
+            .add_output(ChannelLayout::MONO, &[7])
+            .build()
+            .unwrap();
+
+        assert_eq!(mapper.outputs(), 3);
+
+        let mut frame = AudioFrameMut::silence(channel_layout, sample_format, 48000, 100);
+
+        for (i, plane) in frame.planes_mut().iter_mut().enumerate() {
+            for dst in plane.data_mut().chunks_exact_mut(4) {
+                dst.copy_from_slice(&(i as f32).to_ne_bytes());
+            }
+        }
+
+        let time_base = TimeBase::new(1, 48000);
+
+        let frame = frame
+            .with_time_base(time_base)
+            .with_pts(Timestamp::new(480, time_base))
+            .freeze();
+
+        mapper.push(frame).unwrap();
+        mapper.flush().unwrap();
+
+        let expected: [&[f32]; 3] = [&[0.0, 1.0], &[3.0, 2.0], &[7.0]];
+
+        for (output, expected) in expected.iter().enumerate() {
+            let frame = mapper.take(output).unwrap().unwrap();
+
+            assert_eq!(frame.samples(), 100);
+            assert!(frame.sample_format() == sample_format);
+            assert_eq!(frame.pts(), Timestamp::from_millis(10));
+            assert_eq!(&samples(&frame), expected);
+
+            assert!(mapper.take(output).unwrap().is_none());
+        }
+
+        assert_eq!(
+            ChannelMapper::builder(&codec_parameters)
+                .split()
+                .build()
+                .unwrap()
+                .outputs(),
+            8
+        );
+
+        assert!(ChannelMapper::builder(&codec_parameters)
+            .add_output(ChannelLayout::STEREO, &[0, 8])
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,473 @@
+//! Audio mixer.
+
+use std::collections::VecDeque;
+
+use crate::{
+    codec::audio::{AudioFrame, AudioFrameMut, AudioResampler, ChannelLayout, SampleFormat},
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Builder for the audio mixer.
+pub struct AudioMixerBuilder {
+    channel_layout: Option<ChannelLayout>,
+    sample_rate: Option<u32>,
+    frame_samples: usize,
+    volume: f32,
+    inputs: Vec<f32>,
+}
+
+impl AudioMixerBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            channel_layout: None,
+            sample_rate: None,
+            frame_samples: 1024,
+            volume: 1.0,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Set output channel layout.
+    pub fn channel_layout(mut self, channel_layout: ChannelLayout) -> Self {
+        self.channel_layout = Some(channel_layout);
+        self
+    }
+
+    /// Set output sample rate.
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// Set the number of samples per channel in the output frames (except
+    /// for the last one). The default is 1024.
+    pub fn frame_samples(mut self, samples: usize) -> Self {
+        self.frame_samples = samples;
+        self
+    }
+
+    /// Set the output volume. The default is 1.0.
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Add an input with a given gain. Inputs are indexed in the order they
+    /// were added.
+    pub fn add_input(mut self, gain: f32) -> Self {
+        self.inputs.push(gain);
+        self
+    }
+
+    /// Build the mixer.
+    pub fn build(self) -> Result<AudioMixer, Error> {
+        let channel_layout = self
+            .channel_layout
+            .ok_or_else(|| Error::new("channel layout was not set"))?;
+        let sample_rate = self
+            .sample_rate
+            .ok_or_else(|| Error::new("sample rate was not set"))?;
+
+        if self.frame_samples == 0 {
+            return Err(Error::new("the number of frame samples must not be zero"));
+        }
+
+        if self.inputs.is_empty() {
+            return Err(Error::new("no inputs"));
+        }
+
+        let channels = channel_layout.channels() as usize;
+
+        let inputs = self
+            .inputs
+            .into_iter()
+            .map(|gain| MixerInput::new(gain, channels))
+            .collect();
+
+        let res = AudioMixer {
+            inputs,
+            channel_layout,
+            sample_format: super::frame::get_sample_format("flt"),
+            sample_rate,
+            frame_samples: self.frame_samples,
+            volume: self.volume,
+            origin: None,
+            position: 0,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Audio mixer.
+///
+/// The mixer sums frames from multiple inputs, each multiplied by its gain.
+/// Input frames may use any sample format, sample rate and channel layout.
+/// They are resampled into the output format. The output frames use the
+/// "flt" sample format and they are not clipped.
+///
+/// Input timestamps are used to align the inputs. Gaps in an input are
+/// filled with silence. The output starts at the timestamp of the first
+/// frame pushed into the mixer.
+///
+/// Output frames are produced once all inputs have enough samples, so
+/// frames need to be pushed into all inputs. Inputs without more data need
+/// to be finished.
+pub struct AudioMixer {
+    inputs: Vec<MixerInput>,
+    channel_layout: ChannelLayout,
+    sample_format: SampleFormat,
+    sample_rate: u32,
+    frame_samples: usize,
+    volume: f32,
+    origin: Option<i64>,
+    position: i64,
+}
+
+impl AudioMixer {
+    /// Get a builder for the audio mixer.
+    pub fn builder() -> AudioMixerBuilder {
+        AudioMixerBuilder::new()
+    }
+
+    /// Get the number of inputs.
+    pub fn inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Get gain of a given input.
+    pub fn gain(&self, input: usize) -> f32 {
+        self.inputs[input].gain
+    }
+
+    /// Set gain of a given input.
+    pub fn set_gain(&mut self, input: usize, gain: f32) {
+        self.inputs[input].gain = gain;
+    }
+
+    /// Get the output volume.
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Set the output volume.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    /// Push a given frame into a given input.
+    ///
+    /// # Panics
+    /// The method panics if the input has been already finished.
+    pub fn push(&mut self, input: usize, frame: AudioFrame) -> Result<(), Error> {
+        let tb = TimeBase::new(1, self.sample_rate);
+
+        if self.origin.is_none() {
+            let pts = frame.pts();
+
+            self.origin = if pts.is_null() {
+                Some(0)
+            } else {
+                Some(pts.with_time_base(tb).timestamp())
+            };
+        }
+
+        let origin = self.origin.unwrap_or(0);
+
+        let input = &mut self.inputs[input];
+
+        assert!(!input.finished, "the input has been already finished");
+
+        let channel_layout = frame.channel_layout();
+        let sample_format = frame.sample_format();
+        let sample_rate = frame.sample_rate();
+
+        let reuse = input
+            .resampler
+            .as_ref()
+            .map(|(_, l, f, r)| *l == channel_layout && *f == sample_format && *r == sample_rate)
+            .unwrap_or(false);
+
+        if !reuse {
+            input.flush_resampler(origin)?;
+
+            let resampler = AudioResampler::builder()
+                .source_channel_layout(channel_layout)
+                .source_sample_format(sample_format)
+                .source_sample_rate(sample_rate)
+                .target_channel_layout(self.channel_layout)
+                .target_sample_format(self.sample_format)
+                .target_sample_rate(self.sample_rate)
+                .build()?;
+
+            input.resampler = Some((resampler, channel_layout, sample_format, sample_rate));
+        }
+
+        if let Some((mut resampler, l, f, r)) = input.resampler.take() {
+            resampler.push(frame)?;
+
+            while let Some(frame) = resampler.take()? {
+                input.append(&frame, origin);
+            }
+
+            input.resampler = Some((resampler, l, f, r));
+        }
+
+        Ok(())
+    }
+
+    /// Signal the end of a given input.
+    pub fn finish(&mut self, input: usize) -> Result<(), Error> {
+        let origin = self.origin.unwrap_or(0);
+
+        let input = &mut self.inputs[input];
+
+        input.flush_resampler(origin)?;
+        input.finished = true;
+
+        Ok(())
+    }
+
+    /// Take the next mixed frame (if available).
+    pub fn take(&mut self) -> Result<Option<AudioFrame>, Error> {
+        let finished = self.inputs.iter().all(|input| input.finished);
+
+        let available = if finished {
+            self.inputs
+                .iter()
+                .map(|input| input.available())
+                .max()
+                .unwrap_or(0)
+                .min(self.frame_samples)
+        } else {
+            let available = self
+                .inputs
+                .iter()
+                .filter(|input| !input.finished)
+                .map(|input| input.available())
+                .min()
+                .unwrap_or(0);
+
+            if available < self.frame_samples {
+                return Ok(None);
+            }
+
+            self.frame_samples
+        };
+
+        if available == 0 {
+            return Ok(None);
+        }
+
+        let channels = self.channel_layout.channels() as usize;
+
+        let mut mixed = vec![0f32; available * channels];
+
+        for input in &mut self.inputs {
+            input.mix_into(&mut mixed);
+        }
+
+        let mut frame = AudioFrameMut::try_silence(
+            self.channel_layout,
+            self.sample_format,
+            self.sample_rate,
+            available,
+        )?;
+
+        let mut planes = frame.planes_mut();
+
+        let data = planes[0].data_mut().chunks_exact_mut(4);
+
+        for (dst, src) in data.zip(&mixed) {
+            dst.copy_from_slice(&(src * self.volume).to_ne_bytes());
+        }
+
+        let tb = TimeBase::new(1, self.sample_rate);
+
+        let pts = Timestamp::new(self.origin.unwrap_or(0) + self.position, tb);
+
+        self.position += available as i64;
+
+        let frame = frame.with_time_base(tb).with_pts(pts).freeze();
+
+        Ok(Some(frame))
+    }
+}
+
+/// Single mixer input.
+struct MixerInput {
+    gain: f32,
+    channels: usize,
+    resampler: Option<(AudioResampler, ChannelLayout, SampleFormat, u32)>,
+    buffer: VecDeque<f32>,
+    end: i64,
+    finished: bool,
+}
+
+impl MixerInput {
+    /// Create a new input.
+    fn new(gain: f32, channels: usize) -> Self {
+        Self {
+            gain,
+            channels,
+            resampler: None,
+            buffer: VecDeque::new(),
+            end: 0,
+            finished: false,
+        }
+    }
+
+    /// Get the number of available samples per channel.
+    fn available(&self) -> usize {
+        self.buffer.len() / self.channels
+    }
+
+    /// Flush the current resampler (if any) into the input buffer.
+    fn flush_resampler(&mut self, origin: i64) -> Result<(), Error> {
+        if let Some((mut resampler, _, _, _)) = self.resampler.take() {
+            resampler.flush()?;
+
+            while let Some(frame) = resampler.take()? {
+                self.append(&frame, origin);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append a given resampled frame to the input buffer.
+    fn append(&mut self, frame: &AudioFrame, origin: i64) {
+        let samples = frame.samples();
+
+        let pts = frame.pts();
+
+        // allow some timestamp jitter (10 ms)
+        let tolerance = (frame.sample_rate() / 100) as i64;
+
+        let mut skip = 0;
+
+        if !pts.is_null() {
+            let start = pts.timestamp() - origin;
+
+            if start > self.end + tolerance {
+                // fill the gap with silence
+                let gap = (start - self.end) as usize;
+
+                self.buffer
+                    .resize(self.buffer.len() + gap * self.channels, 0.0);
+
+                self.end = start;
+            } else if start < self.end - tolerance {
+                // skip the overlapping samples
+                skip = ((self.end - start) as usize).min(samples);
+            }
+        }
+
+        let planes = frame.planes();
+
+        let data = planes[0]
+            .data()
+            .chunks_exact(4)
+            .take(samples * self.channels)
+            .skip(skip * self.channels)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]));
+
+        let len = self.buffer.len();
+
+        self.buffer.extend(data);
+
+        self.end += ((self.buffer.len() - len) / self.channels) as i64;
+    }
+
+    /// Mix the buffered samples into a given output buffer.
+    fn mix_into(&mut self, output: &mut [f32]) {
+        let len = self.buffer.len().min(output.len());
+
+        for (dst, src) in output.iter_mut().zip(self.buffer.drain(..len)) {
+            *dst += src * self.gain;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioMixer;
+
+    use crate::{
+        codec::audio::{self, AudioFrame, AudioFrameMut, ChannelLayout},
+        time::{TimeBase, Timestamp},
+    };
+
+    fn frame(value: f32, pts: i64, samples: usize) -> AudioFrame {
+        let mut frame = AudioFrameMut::silence(
+            ChannelLayout::from_channels(1).unwrap(),
+            audio::frame::get_sample_format("flt"),
+            1000,
+            samples,
+        );
+
+        let mut planes = frame.planes_mut();
+
+        for dst in planes[0].data_mut().chunks_exact_mut(4) {
+            dst.copy_from_slice(&value.to_ne_bytes());
+        }
+
+        let tb = TimeBase::new(1, 1000);
+
+        frame
+            .with_time_base(tb)
+            .with_pts(Timestamp::new(pts, tb))
+            .freeze()
+    }
+
+    fn samples(frame: &AudioFrame) -> Vec<f32> {
+        frame.planes()[0]
+            .data()
+            .chunks_exact(4)
+            .take(frame.samples())
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    }
+
+    #[test]
+    fn test_mixer() {
+        let mut mixer = AudioMixer::builder()
+            .channel_layout(ChannelLayout::from_channels(1).unwrap())
+            .sample_rate(1000)
+            .frame_samples(40)
+            .add_input(0.5)
+            .add_input(2.0)
+            .build()
+            .unwrap();
+
+        mixer.push(0, frame(0.5, 0, 50)).unwrap();
+
+        // the second input is not available yet
+        assert!(mixer.take().unwrap().is_none());
+
+        // the second input starts 20 samples later
+        mixer.push(1, frame(0.25, 20, 30)).unwrap();
+
+        let mixed = mixer.take().unwrap().unwrap();
+
+        assert_eq!(mixed.pts(), Timestamp::from_millis(0));
+
+        let data = samples(&mixed);
+
+        assert_eq!(data.len(), 40);
+        assert!(data[..20].iter().all(|v| *v == 0.25));
+        assert!(data[20..].iter().all(|v| *v == 0.75));
+
+        mixer.finish(0).unwrap();
+        mixer.finish(1).unwrap();
+
+        let mixed = mixer.take().unwrap().unwrap();
+
+        assert_eq!(mixed.pts(), Timestamp::from_millis(40));
+        assert_eq!(samples(&mixed), vec![0.75; 10]);
+
+        assert!(mixer.take().unwrap().is_none());
+    }
+}
@@ -1,29 +1,44 @@
 //! Audio decoder/encoder.
 
+pub mod filter;
 pub mod frame;
+pub mod mapper;
+pub mod mixer;
+pub mod opus;
 pub mod resampler;
+pub mod seeker;
 pub mod transcoder;
+pub mod waveform;
 
 use std::{ffi::CString, os::raw::c_void, ptr};
 
 use crate::{
-    codec::{AudioCodecParameters, CodecError, CodecParameters, Decoder, Encoder},
+    codec::{
+        AudioCodecParameters, CodecError, CodecParameters, Decoder, Encoder, ErrorRecognition,
+    },
     format::stream::Stream,
+    options::OptionInfo,
     packet::Packet,
     time::TimeBase,
     Error,
 };
 
 pub use self::{
+    filter::{AudioFilter, AudioFilterBuilder},
     frame::{AudioFrame, AudioFrameMut, ChannelLayout, SampleFormat},
+    mapper::{ChannelMapper, ChannelMapperBuilder},
+    mixer::{AudioMixer, AudioMixerBuilder},
     resampler::AudioResampler,
+    seeker::AudioSeeker,
     transcoder::AudioTranscoder,
+    waveform::{Peak, WaveformGenerator},
 };
 
 /// Builder for the audio decoder.
 pub struct AudioDecoderBuilder {
     ptr: *mut c_void,
     time_base: TimeBase,
+    error: Option<Error>,
 }
 
 impl AudioDecoderBuilder {
@@ -33,7 +48,11 @@ impl AudioDecoderBuilder {
 
         super::ffw_decoder_set_pkt_timebase(ptr, time_base.num() as _, time_base.den() as _);
 
-        Self { ptr, time_base }
+        Self {
+            ptr,
+            time_base,
+            error: None,
+        }
     }
 
     /// Create a new builder for a given codec.
@@ -61,24 +80,40 @@ impl AudioDecoderBuilder {
     }
 
     /// Set a decoder option.
-    pub fn set_option<V>(self, name: &str, value: V) -> Self
+    /// An invalid option (e.g. a name containing a NUL character) or an
+    /// option that is not recognized will make the `build()` method fail.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
     where
         V: ToString,
     {
-        let name = CString::new(name).expect("invalid option name");
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (name, value) = match crate::options::option_to_cstrings(name, &value.to_string()) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
 
         let ret = unsafe {
             super::ffw_decoder_set_initial_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
         };
 
         if ret < 0 {
-            panic!("unable to allocate an option");
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
         }
 
         self
     }
 
+    /// Set error recognition flags of the decoder. Use
+    /// `ErrorRecognition::IgnoreErrors` to decode corrupted inputs on a
+    /// best-effort basis or `ErrorRecognition::Explode` to fail on any
+    /// detected error.
+    pub fn error_recognition(self, flags: &[ErrorRecognition]) -> Self {
+        self.set_option("err_detect", ErrorRecognition::option_value(flags))
+    }
+
     /// Set decoder time base (all input packets will be rescaled into this
     /// time base). The default time base is in microseconds.
     pub fn time_base(mut self, time_base: TimeBase) -> Self {
@@ -124,10 +159,16 @@ impl AudioDecoderBuilder {
 
     /// Build the decoder.
     pub fn build(mut self) -> Result<AudioDecoder, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
         unsafe {
             if super::ffw_decoder_open(self.ptr) != 0 {
                 return Err(Error::new("unable to build the decoder"));
             }
+
+            crate::options::check_unused_options(super::ffw_decoder_get_unused_options(self.ptr))?;
         }
 
         let ptr = self.ptr;
@@ -191,6 +232,12 @@ impl AudioDecoder {
     pub fn builder(codec: &str) -> Result<AudioDecoderBuilder, Error> {
         AudioDecoderBuilder::new(codec)
     }
+
+    /// Reset the internal decoder state and drop all buffered frames. This
+    /// should be used after seeking in the input.
+    pub fn reset(&mut self) {
+        unsafe { super::ffw_decoder_reset(self.ptr) }
+    }
 }
 
 impl Decoder for AudioDecoder {
@@ -272,6 +319,8 @@ pub struct AudioEncoderBuilder {
     sample_format: Option<SampleFormat>,
     sample_rate: Option<u32>,
     channel_layout: Option<ChannelLayout>,
+
+    error: Option<Error>,
 }
 
 impl AudioEncoderBuilder {
@@ -297,6 +346,8 @@ impl AudioEncoderBuilder {
             sample_format: None,
             sample_rate: None,
             channel_layout: None,
+
+            error: None,
         };
 
         Ok(res)
@@ -328,30 +379,89 @@ impl AudioEncoderBuilder {
             sample_format: Some(sample_format),
             sample_rate: Some(sample_rate),
             channel_layout: Some(channel_layout),
+
+            error: None,
         };
 
         Ok(res)
     }
 
     /// Set an encoder option.
-    pub fn set_option<V>(self, name: &str, value: V) -> Self
+    /// An invalid option (e.g. a name containing a NUL character) or an
+    /// option that is not recognized will make the `build()` method fail.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
     where
         V: ToString,
     {
-        let name = CString::new(name).expect("invalid option name");
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (name, value) = match crate::options::option_to_cstrings(name, &value.to_string()) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
 
         let ret = unsafe {
             super::ffw_encoder_set_initial_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
         };
 
         if ret < 0 {
-            panic!("unable to allocate an option");
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
         }
 
         self
     }
 
+    /// Put codec headers (e.g. H.264 SPS/PPS) into the codec extradata
+    /// instead of every key frame. This is equivalent to setting the
+    /// `+global_header` flag. Most containers (e.g. MP4 or
+    /// Matroska) require global headers.
+    pub fn global_header(self, enabled: bool) -> Self {
+        if enabled {
+            self.set_context_option("flags", "+global_header")
+        } else {
+            self.set_context_option("flags", "-global_header")
+        }
+    }
+
+    /// Set an option directly on the codec context. Unlike `set_option()`,
+    /// flags given as `+flag`/`-flag` are combined with the current value.
+    fn set_context_option(mut self, name: &str, value: &str) -> Self {
+        let (name, value) = match crate::options::option_to_cstrings(name, value) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
+
+        let ret = unsafe {
+            super::ffw_encoder_set_context_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
+        };
+
+        if ret < 0 {
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
+        }
+
+        self
+    }
+
+    /// Get options available for this encoder (i.e. the generic codec
+    /// options followed by the encoder-specific ones).
+    pub fn options(&self) -> Vec<OptionInfo> {
+        let mut res = crate::options::codec_options();
+
+        unsafe {
+            let class = super::ffw_encoder_get_priv_class(self.ptr);
+
+            res.extend(crate::options::class_options(class));
+        }
+
+        res
+    }
+
     /// Set encoder bit rate. The default is 0 (i.e. automatic).
     pub fn bit_rate(self, bit_rate: u64) -> Self {
         unsafe {
@@ -387,6 +497,10 @@ impl AudioEncoderBuilder {
 
     /// Build the encoder.
     pub fn build(mut self) -> Result<AudioEncoder, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
         let sample_format = self
             .sample_format
             .ok_or_else(|| Error::new("sample format not set"))?;
@@ -410,6 +524,8 @@ impl AudioEncoderBuilder {
             if super::ffw_encoder_open(self.ptr) != 0 {
                 return Err(Error::new("unable to build the encoder"));
             }
+
+            crate::options::check_unused_options(super::ffw_encoder_get_unused_options(self.ptr))?;
         }
 
         let ptr = self.ptr;
@@ -0,0 +1,184 @@
+//! Opus framing utilities.
+//!
+//! Opus always uses a 48 kHz clock for timestamps (RFC 7587) regardless of
+//! the actual sample rate. The framer converts encoded Opus packets into raw
+//! Opus frames with timestamps and durations in this clock.
+
+use std::time::Duration;
+
+use crate::{
+    packet::Packet,
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// The Opus clock rate.
+pub const OPUS_CLOCK_RATE: u32 = 48_000;
+
+/// Raw Opus frame.
+pub struct OpusFrame {
+    data: Vec<u8>,
+    timestamp: u64,
+    samples: u32,
+}
+
+impl OpusFrame {
+    /// Get the frame data (i.e. a complete Opus packet as defined in RFC
+    /// 6716).
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the frame data.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Get timestamp of the frame in the 48 kHz clock.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Get the number of samples (per channel) in the 48 kHz clock.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Get the frame duration.
+    pub fn duration(&self) -> Duration {
+        Duration::from_micros(self.samples as u64 * 1_000_000 / OPUS_CLOCK_RATE as u64)
+    }
+}
+
+/// Opus framer.
+///
+/// Timestamps are taken from the packets if they are available. Packets
+/// without a timestamp continue where the previous packet ended.
+pub struct OpusFramer {
+    next_timestamp: Option<u64>,
+}
+
+impl OpusFramer {
+    /// Create a new framer.
+    pub fn new() -> Self {
+        Self {
+            next_timestamp: None,
+        }
+    }
+
+    /// Convert a given packet.
+    pub fn push(&mut self, packet: &Packet) -> Result<OpusFrame, Error> {
+        let data = packet.data();
+
+        let samples = packet_samples(data).ok_or_else(|| Error::new("invalid Opus packet"))?;
+
+        let pts = packet.pts();
+
+        let timestamp = if pts.is_null() {
+            self.next_timestamp.unwrap_or(0)
+        } else {
+            let tb = TimeBase::new(1, OPUS_CLOCK_RATE);
+
+            pts.with_time_base(tb).timestamp().max(0) as u64
+        };
+
+        self.next_timestamp = Some(timestamp + samples as u64);
+
+        let res = OpusFrame {
+            data: data.to_vec(),
+            timestamp,
+            samples,
+        };
+
+        Ok(res)
+    }
+
+    /// Get the expected timestamp of the next frame.
+    pub fn next_timestamp(&self) -> Option<Timestamp> {
+        self.next_timestamp
+            .map(|ts| Timestamp::new(ts as i64, TimeBase::new(1, OPUS_CLOCK_RATE)))
+    }
+}
+
+impl Default for OpusFramer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the number of samples (in the 48 kHz clock) in a given Opus packet
+/// or `None` if the packet is not valid.
+pub fn packet_samples(data: &[u8]) -> Option<u32> {
+    let toc = *data.first()?;
+
+    let config = toc >> 3;
+
+    // frame sizes in 1/400 s (i.e. 120 samples)
+    let frame_size = match config {
+        0..=11 => [4, 8, 16, 24][config as usize & 3],
+        12..=15 => [4, 8][config as usize & 1],
+        _ => [1, 2, 4, 8][config as usize & 3],
+    };
+
+    let frames = match toc & 3 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => (*data.get(1)? & 0x3f) as u32,
+    };
+
+    let samples = frame_size * 120 * frames;
+
+    // packets are limited to 120 ms
+    if frames == 0 || samples > 5760 {
+        None
+    } else {
+        Some(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{packet_samples, OpusFramer};
+
+    use crate::{
+        packet::PacketMut,
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_packet_samples() {
+        // CELT 20 ms, single frame
+        assert_eq!(packet_samples(&[0xf8, 0]), Some(960));
+        // SILK 60 ms, two frames
+        assert_eq!(packet_samples(&[0x19, 0]), Some(5760));
+        // CELT 2.5 ms, 3 frames (code 3)
+        assert_eq!(packet_samples(&[0x83, 3]), Some(360));
+        // too long
+        assert_eq!(packet_samples(&[0x1b, 3]), None);
+        assert_eq!(packet_samples(&[]), None);
+    }
+
+    #[test]
+    fn test_opus_framer() {
+        let mut framer = OpusFramer::new();
+
+        let mut packet = PacketMut::new(2);
+
+        packet.data_mut().copy_from_slice(&[0xf8, 0]);
+
+        let packet = packet
+            .with_time_base(TimeBase::new(1, 1000))
+            .with_pts(Timestamp::new(100, TimeBase::new(1, 1000)))
+            .freeze();
+
+        let frame = framer.push(&packet).unwrap();
+
+        assert_eq!(frame.timestamp(), 4800);
+        assert_eq!(frame.samples(), 960);
+        assert_eq!(frame.duration().as_millis(), 20);
+
+        let frame = framer.push(&packet.with_pts(Timestamp::null())).unwrap();
+
+        assert_eq!(frame.timestamp(), 5760);
+    }
+}
@@ -1,3 +1,4 @@
+#include <libavutil/opt.h>
 #include <libswresample/swresample.h>
 
 typedef struct AudioResampler {
@@ -59,7 +60,9 @@ AudioResampler* ffw_audio_resampler_new(
     int target_frame_samples,
     uint64_t source_channel_layout,
     int source_sample_format,
-    int source_sample_rate);
+    int source_sample_rate,
+    const char* options,
+    const double* matrix);
 
 void ffw_audio_resampler_free(AudioResampler* resampler);
 
@@ -70,7 +73,9 @@ AudioResampler* ffw_audio_resampler_new(
     int target_frame_samples,
     uint64_t source_channel_layout,
     int source_sample_format,
-    int source_sample_rate) {
+    int source_sample_rate,
+    const char* options,
+    const double* matrix) {
     AudioResampler* res = malloc(sizeof(AudioResampler));
     if (!res) {
         return NULL;
@@ -112,6 +117,20 @@ AudioResampler* ffw_audio_resampler_new(
         goto err;
     }
 
+    if (options && av_opt_set_from_string(res->resample_context, options, NULL, "=", ":") < 0) {
+        goto err;
+    }
+
+    // the matrix is expected to contain one row (of source channel
+    // coefficients) for every target channel
+    if (matrix) {
+        int source_channels = av_get_channel_layout_nb_channels(source_channel_layout);
+
+        if (swr_set_matrix(res->resample_context, matrix, source_channels) < 0) {
+            goto err;
+        }
+    }
+
     if (swr_init(res->resample_context) < 0) {
         goto err;
     }
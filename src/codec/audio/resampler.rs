@@ -1,7 +1,8 @@
 //! Audio resampler.
 
 use std::{
-    os::raw::{c_int, c_void},
+    ffi::CString,
+    os::raw::{c_char, c_int, c_void},
     ptr,
 };
 
@@ -23,6 +24,8 @@ extern "C" {
         source_channel_layout: u64,
         source_sample_format: c_int,
         source_sample_rate: c_int,
+        options: *const c_char,
+        matrix: *const f64,
     ) -> *mut c_void;
     fn ffw_audio_resampler_free(resampler: *mut c_void);
     fn ffw_audio_resampler_push_frame(resampler: *mut c_void, frame: *const c_void) -> c_int;
@@ -40,6 +43,12 @@ pub struct AudioResamplerBuilder {
     target_sample_rate: Option<u32>,
 
     target_frame_samples: Option<usize>,
+
+    center_mix_level: Option<f64>,
+    surround_mix_level: Option<f64>,
+    lfe_mix_level: Option<f64>,
+    normalize_mix: Option<bool>,
+    mix_matrix: Option<Vec<Vec<f64>>>,
 }
 
 impl AudioResamplerBuilder {
@@ -55,6 +64,12 @@ impl AudioResamplerBuilder {
             target_sample_rate: None,
 
             target_frame_samples: None,
+
+            center_mix_level: None,
+            surround_mix_level: None,
+            lfe_mix_level: None,
+            normalize_mix: None,
+            mix_matrix: None,
         }
     }
 
@@ -101,6 +116,53 @@ impl AudioResamplerBuilder {
         self
     }
 
+    /// Set the gain applied to the center channel when it is mixed into the
+    /// front channels (e.g. 5.1 -> stereo downmix). The default is -3 dB
+    /// (i.e. 0.707).
+    pub fn center_mix_level(mut self, level: f64) -> Self {
+        self.center_mix_level = Some(level);
+        self
+    }
+
+    /// Set the gain applied to the surround channels when they are mixed
+    /// into the front channels. The default is -3 dB (i.e. 0.707).
+    pub fn surround_mix_level(mut self, level: f64) -> Self {
+        self.surround_mix_level = Some(level);
+        self
+    }
+
+    /// Set the gain applied to the LFE channel when it is mixed into
+    /// non-LFE channels. Note that the LFE channel is split between the left
+    /// and right channel in case of a stereo downmix (i.e. the level is
+    /// further multiplied by 0.707). The default is 0 (i.e. the LFE channel
+    /// is dropped).
+    pub fn lfe_mix_level(mut self, level: f64) -> Self {
+        self.lfe_mix_level = Some(level);
+        self
+    }
+
+    /// Enable or disable normalization of the mix coefficients. If enabled,
+    /// the coefficients are scaled down so that the output cannot clip
+    /// (which also makes the downmix quieter). If disabled, the mix levels
+    /// are used as they are. By default, the coefficients are normalized
+    /// only for integer target sample formats.
+    pub fn normalize_mix(mut self, normalize: bool) -> Self {
+        self.normalize_mix = Some(normalize);
+        self
+    }
+
+    /// Set a custom mix matrix. The matrix must contain one row for each
+    /// target channel and each row must contain one coefficient for each
+    /// source channel (i.e. `matrix[target][source]` is the gain of a given
+    /// source channel in a given target channel). Channels are in the order
+    /// given by the channel layouts.
+    ///
+    /// A custom matrix overrides the mix levels and the normalization.
+    pub fn mix_matrix(mut self, matrix: Vec<Vec<f64>>) -> Self {
+        self.mix_matrix = Some(matrix);
+        self
+    }
+
     /// Build the resampler.
     pub fn build(self) -> Result<AudioResampler, Error> {
         let source_channel_layout = self
@@ -125,6 +187,44 @@ impl AudioResamplerBuilder {
 
         let target_frame_samples = self.target_frame_samples.unwrap_or(0);
 
+        let mut options = Vec::new();
+
+        if let Some(level) = self.center_mix_level {
+            options.push(format!("clev={}", level));
+        }
+
+        if let Some(level) = self.surround_mix_level {
+            options.push(format!("slev={}", level));
+        }
+
+        if let Some(level) = self.lfe_mix_level {
+            options.push(format!("lfe_mix_level={}", level));
+        }
+
+        // zero means the FFmpeg default, 1000 is the maximum allowed value
+        match self.normalize_mix {
+            Some(true) => options.push(String::from("rematrix_maxval=1")),
+            Some(false) => options.push(String::from("rematrix_maxval=1000")),
+            None => (),
+        }
+
+        let options = CString::new(options.join(":")).unwrap();
+
+        let matrix = if let Some(matrix) = self.mix_matrix {
+            let target_channels = target_channel_layout.channels() as usize;
+            let source_channels = source_channel_layout.channels() as usize;
+
+            if matrix.len() != target_channels
+                || matrix.iter().any(|row| row.len() != source_channels)
+            {
+                return Err(Error::new("mix matrix does not match the channel layouts"));
+            }
+
+            Some(matrix.concat())
+        } else {
+            None
+        };
+
         let ptr = unsafe {
             ffw_audio_resampler_new(
                 target_channel_layout.into_raw(),
@@ -134,6 +234,8 @@ impl AudioResamplerBuilder {
                 source_channel_layout.into_raw(),
                 source_sample_format.into_raw(),
                 source_sample_rate as _,
+                options.as_ptr(),
+                matrix.as_ref().map(|m| m.as_ptr()).unwrap_or(ptr::null()),
             )
         };
 
@@ -276,3 +378,93 @@ impl Drop for AudioResampler {
 
 unsafe impl Send for AudioResampler {}
 unsafe impl Sync for AudioResampler {}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioResampler;
+
+    use crate::codec::audio::{self, AudioFrame, AudioFrameMut, ChannelLayout};
+
+    fn frame(channel_layout: ChannelLayout, values: &[f32]) -> AudioFrame {
+        let mut frame = AudioFrameMut::silence(
+            channel_layout,
+            audio::frame::get_sample_format("fltp"),
+            1000,
+            100,
+        );
+
+        let mut planes = frame.planes_mut();
+
+        for (i, value) in values.iter().enumerate() {
+            for dst in planes[i].data_mut().chunks_exact_mut(4) {
+                dst.copy_from_slice(&value.to_ne_bytes());
+            }
+        }
+
+        frame.freeze()
+    }
+
+    fn resample(resampler: &mut AudioResampler, frame: AudioFrame) -> Vec<f32> {
+        let mut frames = Vec::new();
+
+        resampler.push(frame).unwrap();
+
+        while let Some(frame) = resampler.take().unwrap() {
+            frames.push(frame);
+        }
+
+        resampler.flush().unwrap();
+
+        while let Some(frame) = resampler.take().unwrap() {
+            frames.push(frame);
+        }
+
+        frames[0]
+            .planes()
+            .iter()
+            .map(|plane| {
+                let b = &plane.data()[..4];
+
+                f32::from_ne_bytes([b[0], b[1], b[2], b[3]])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_downmix() {
+        let format = audio::frame::get_sample_format("fltp");
+
+        let builder = || {
+            AudioResampler::builder()
+                .source_channel_layout(ChannelLayout::SURROUND_5_1)
+                .source_sample_format(format)
+                .source_sample_rate(1000)
+                .target_channel_layout(ChannelLayout::STEREO)
+                .target_sample_format(format)
+                .target_sample_rate(1000)
+        };
+
+        // FL, FR, FC, LFE, SL, SR
+        let input = frame(ChannelLayout::SURROUND_5_1, &[0.0, 0.0, 0.5, 0.5, 0.0, 0.0]);
+
+        let mut resampler = builder()
+            .center_mix_level(1.0)
+            .normalize_mix(false)
+            .build()
+            .unwrap();
+
+        assert_eq!(resample(&mut resampler, input.clone()), [0.5, 0.5]);
+
+        let mut resampler = builder()
+            .mix_matrix(vec![
+                vec![0.0, 0.0, 0.5, 0.0, 0.0, 0.0],
+                vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            ])
+            .build()
+            .unwrap();
+
+        assert_eq!(resample(&mut resampler, input), [0.25, 0.5]);
+
+        assert!(builder().mix_matrix(vec![vec![1.0; 6]]).build().is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Sample-accurate audio seeking.
+//!
+//! Demuxers can seek only to packet boundaries (and usually only to
+//! keyframes). This module contains a convenience struct combining a demuxer
+//! and an audio decoder that trims the decoded audio, so that the first frame
+//! after a seek starts exactly at the requested sample.
+
+use std::time::Duration;
+
+use crate::{
+    codec::{
+        audio::{AudioDecoder, AudioFrame},
+        Decoder,
+    },
+    format::demuxer::{Demuxer, DemuxerWithStreamInfo, SeekTarget},
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Sample-accurate audio seeker.
+///
+/// The seeker reads packets of a given audio stream from the underlying
+/// demuxer and decodes them. After calling `seek()`, all decoded audio
+/// preceding the seek target is dropped and the first returned frame is
+/// trimmed to start at the sample corresponding to the seek target.
+///
+/// Encoder priming and padding (e.g. from MP4 edit lists) is signaled by
+/// the demuxer using packet side data and it is handled by the decoder, so
+/// the timestamps of the returned frames are always presentation
+/// timestamps.
+pub struct AudioSeeker<T> {
+    demuxer: Demuxer<T>,
+    decoder: AudioDecoder,
+    stream_index: usize,
+    time_base: TimeBase,
+    preroll: Duration,
+    target: Option<Timestamp>,
+    flushed: bool,
+}
+
+impl<T> AudioSeeker<T> {
+    /// Create a new audio seeker for a given audio stream. An error is
+    /// returned if the stream is not an audio stream.
+    pub fn new(demuxer: DemuxerWithStreamInfo<T>, stream_index: usize) -> Result<Self, Error> {
+        let stream = demuxer
+            .streams()
+            .get(stream_index)
+            .ok_or_else(|| Error::new("invalid stream index"))?;
+
+        if !stream.codec_parameters().is_audio_codec() {
+            return Err(Error::new("not an audio stream"));
+        }
+
+        let time_base = stream.time_base();
+
+        let decoder = AudioDecoder::from_stream(stream)?.build()?;
+
+        let res = Self {
+            demuxer: demuxer.into_demuxer(),
+            decoder,
+            stream_index,
+            time_base,
+            preroll: Duration::from_millis(100),
+            target: None,
+            flushed: false,
+        };
+
+        Ok(res)
+    }
+
+    /// Set the amount of audio that will be decoded and dropped before the
+    /// seek target. Some codecs need to decode a few packets before they can
+    /// produce valid output. The default is 100 ms.
+    pub fn with_preroll(mut self, preroll: Duration) -> Self {
+        self.preroll = preroll;
+        self
+    }
+
+    /// Seek to a given timestamp. The next frame returned by the seeker will
+    /// start exactly at the sample corresponding to the timestamp (unless the
+    /// timestamp is before the first sample of the stream).
+    pub fn seek(&mut self, target: Timestamp) -> Result<(), Error> {
+        if target.is_null() {
+            return Err(Error::new("null timestamp"));
+        }
+
+        self.demuxer
+            .seek_to_timestamp(target - self.preroll, SeekTarget::UpTo)?;
+
+        self.decoder.reset();
+
+        self.target = Some(target);
+        self.flushed = false;
+
+        Ok(())
+    }
+
+    /// Take the next frame or `None` on EOF.
+    pub fn take(&mut self) -> Result<Option<AudioFrame>, Error> {
+        loop {
+            if let Some(frame) = self.decoder.take()? {
+                if let Some(frame) = self.trim(frame)? {
+                    return Ok(Some(frame));
+                }
+            } else if self.flushed {
+                return Ok(None);
+            } else if let Some(packet) = self.demuxer.take()? {
+                if packet.stream_index() == self.stream_index {
+                    self.decoder.push(packet)?;
+                }
+            } else {
+                self.decoder.flush()?;
+                self.flushed = true;
+            }
+        }
+    }
+
+    /// Get time base of the returned frames.
+    pub fn time_base(&self) -> TimeBase {
+        self.time_base
+    }
+
+    /// Get the underlying demuxer.
+    pub fn into_demuxer(self) -> Demuxer<T> {
+        self.demuxer
+    }
+
+    /// Drop samples preceding the current seek target (if any).
+    fn trim(&mut self, frame: AudioFrame) -> Result<Option<AudioFrame>, Error> {
+        let target = match self.target {
+            Some(target) => target,
+            None => return Ok(Some(frame)),
+        };
+
+        let pts = frame.pts();
+
+        if pts.is_null() {
+            // we cannot do anything better without knowing the frame position
+            self.target = None;
+
+            return Ok(Some(frame));
+        }
+
+        let sample_time_base = TimeBase::new(1, frame.sample_rate());
+
+        let start = pts.with_time_base(sample_time_base).timestamp();
+        let target = target.with_time_base(sample_time_base).timestamp();
+
+        let samples = frame.samples() as i64;
+
+        if (start + samples) <= target {
+            return Ok(None);
+        }
+
+        self.target = None;
+
+        if start >= target {
+            return Ok(Some(frame));
+        }
+
+        let offset = (target - start) as usize;
+
+        let res = frame.slice(offset..);
+
+        Ok(Some(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::AudioSeeker;
+
+    use crate::{
+        codec::{
+            audio::{
+                frame::{get_channel_layout, get_sample_format},
+                AudioEncoder, AudioFrameMut,
+            },
+            video::{frame::get_pixel_format, VideoEncoder, VideoFrameMut},
+            Encoder,
+        },
+        format::{
+            demuxer::{Demuxer, DemuxerWithStreamInfo},
+            io::IO,
+            muxer::{Muxer, OutputFormat},
+        },
+        time::{TimeBase, Timestamp},
+    };
+
+    /// Create a two-second NUT file with a raw video stream and a PCM
+    /// audio stream.
+    fn nut_file() -> Vec<u8> {
+        let pixel_format = get_pixel_format("gray");
+        let video_time_base = TimeBase::new(1, 25);
+
+        let channel_layout = get_channel_layout("mono");
+        let sample_format = get_sample_format("s16");
+        let audio_time_base = TimeBase::new(1, 48_000);
+
+        let mut video_encoder = VideoEncoder::builder("rawvideo")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(16)
+            .height(16)
+            .time_base(video_time_base)
+            .build()
+            .unwrap();
+
+        let mut audio_encoder = AudioEncoder::builder("pcm_s16le")
+            .unwrap()
+            .sample_format(sample_format)
+            .sample_rate(48_000)
+            .channel_layout(channel_layout)
+            .time_base(audio_time_base)
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder().interleaved(true);
+
+        builder
+            .add_stream(&video_encoder.codec_parameters().into())
+            .unwrap();
+        builder
+            .add_stream(&audio_encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_seekable_write_stream(Cursor::new(Vec::new())),
+                OutputFormat::find_by_name("nut").unwrap(),
+            )
+            .unwrap();
+
+        for i in 0..50 {
+            let frame = VideoFrameMut::black(pixel_format, 16, 16)
+                .with_time_base(video_time_base)
+                .with_pts(Timestamp::new(i, video_time_base))
+                .freeze();
+
+            video_encoder.push(frame).unwrap();
+
+            while let Some(packet) = video_encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(0)).unwrap();
+            }
+
+            let frame = AudioFrameMut::silence(channel_layout, sample_format, 48_000, 1920)
+                .with_time_base(audio_time_base)
+                .with_pts(Timestamp::new(i * 1920, audio_time_base))
+                .freeze();
+
+            audio_encoder.push(frame).unwrap();
+
+            while let Some(packet) = audio_encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(1)).unwrap();
+            }
+        }
+
+        muxer.close().unwrap().into_stream().into_inner()
+    }
+
+    fn demuxer(data: Vec<u8>) -> DemuxerWithStreamInfo<Cursor<Vec<u8>>> {
+        Demuxer::builder()
+            .build(IO::from_seekable_read_stream(Cursor::new(data)))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_not_an_audio_stream() {
+        assert!(AudioSeeker::new(demuxer(nut_file()), 0).is_err());
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut seeker = AudioSeeker::new(demuxer(nut_file()), 1).unwrap();
+
+        let sample_time_base = TimeBase::new(1, 48_000);
+
+        // the target is in the middle of a packet
+        for target in [50_000, 12_345, 70_000].iter() {
+            seeker
+                .seek(Timestamp::new(*target, sample_time_base))
+                .unwrap();
+
+            let frame = seeker.take().unwrap().unwrap();
+
+            let pts = frame.pts().with_time_base(sample_time_base);
+
+            assert_eq!(pts.timestamp(), *target);
+
+            let next = seeker.take().unwrap().unwrap();
+
+            let next_pts = next.pts().with_time_base(sample_time_base);
+
+            assert_eq!(next_pts.timestamp(), *target + frame.samples() as i64);
+        }
+    }
+}
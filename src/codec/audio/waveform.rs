@@ -0,0 +1,204 @@
+//! Waveform peaks.
+
+use crate::{
+    channel::ChannelReceiver,
+    codec::audio::{AudioDecoder, AudioFrame},
+    format::demuxer::DemuxerWithStreamInfo,
+    Error,
+};
+
+/// Minimum and maximum sample value within a bucket of samples. Values of
+/// all channels are included. Integer samples are scaled into the -1 to 1
+/// range.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Peak {
+    min: f32,
+    max: f32,
+}
+
+impl Peak {
+    /// Get the minimum sample value.
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+
+    /// Get the maximum sample value.
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+}
+
+/// Waveform peak generator.
+///
+/// The generator splits audio into buckets of a given number of samples
+/// (per channel) and produces a min/max peak for every bucket (i.e. one
+/// peak per pixel of a waveform).
+pub struct WaveformGenerator {
+    samples_per_pixel: usize,
+    current: Option<Peak>,
+    samples: usize,
+}
+
+impl WaveformGenerator {
+    /// Create a new generator.
+    ///
+    /// # Panics
+    /// The method panics if the number of samples per pixel is zero.
+    pub fn new(samples_per_pixel: usize) -> Self {
+        assert!(samples_per_pixel > 0);
+
+        Self {
+            samples_per_pixel,
+            current: None,
+            samples: 0,
+        }
+    }
+
+    /// Get the number of samples (per channel) in a single bucket.
+    pub fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    /// Process a given frame and return all buckets completed by the frame.
+    pub fn push(&mut self, frame: &AudioFrame) -> Result<Vec<Peak>, Error> {
+        let format = frame.sample_format();
+
+        let convert = format
+            .f32_converter()
+            .ok_or_else(|| Error::new("unsupported sample format"))?;
+
+        let bytes_per_sample = format.bytes_per_sample();
+        let channels = frame.channels() as usize;
+        let samples = frame.samples();
+
+        let planes = frame.planes();
+
+        let mut res = Vec::new();
+
+        for index in 0..samples {
+            let mut min = f32::MAX;
+            let mut max = f32::MIN;
+
+            for channel in 0..channels {
+                let (plane, offset) = if format.is_planar() {
+                    (&planes[channel], index * bytes_per_sample)
+                } else {
+                    (&planes[0], (index * channels + channel) * bytes_per_sample)
+                };
+
+                let value = convert(&plane.data()[offset..offset + bytes_per_sample]);
+
+                min = min.min(value);
+                max = max.max(value);
+            }
+
+            if let Some(peak) = self.update(min, max) {
+                res.push(peak);
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Get the last incomplete bucket (if any).
+    pub fn finish(&mut self) -> Option<Peak> {
+        self.samples = 0;
+        self.current.take()
+    }
+
+    /// Move the generator into a background thread analyzing a given audio
+    /// stream. Peaks are sent as soon as they are available, so the
+    /// waveform can be drawn progressively. The demuxer will be blocked
+    /// while the channel is full.
+    pub fn analyze<T>(
+        mut self,
+        mut demuxer: DemuxerWithStreamInfo<T>,
+        stream_index: usize,
+        capacity: usize,
+    ) -> Result<ChannelReceiver<Vec<Peak>>, Error>
+    where
+        T: Send + 'static,
+    {
+        let stream = demuxer
+            .streams()
+            .get(stream_index)
+            .ok_or_else(|| Error::new("invalid stream index"))?;
+
+        let decoder = AudioDecoder::from_stream(stream)?.build()?;
+
+        let res = crate::channel::spawn(capacity, move |output| {
+            for frame in demuxer.as_mut().frames(stream_index, decoder) {
+                let peaks = self.push(&frame?)?;
+
+                if !peaks.is_empty() && !output.send(peaks) {
+                    return Ok(());
+                }
+            }
+
+            if let Some(peak) = self.finish() {
+                output.send(vec![peak]);
+            }
+
+            Ok(())
+        });
+
+        Ok(res)
+    }
+
+    /// Add a given sample range into the current bucket.
+    fn update(&mut self, min: f32, max: f32) -> Option<Peak> {
+        let peak = self.current.get_or_insert(Peak { min, max });
+
+        peak.min = peak.min.min(min);
+        peak.max = peak.max.max(max);
+
+        self.samples += 1;
+
+        if self.samples < self.samples_per_pixel {
+            return None;
+        }
+
+        self.samples = 0;
+        self.current.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WaveformGenerator;
+
+    use crate::codec::audio::{self, AudioFrameMut, ChannelLayout};
+
+    #[test]
+    fn test_waveform_generator() {
+        let mut frame = AudioFrameMut::silence(
+            ChannelLayout::from_channels(2).unwrap(),
+            audio::frame::get_sample_format("s16"),
+            1000,
+            5,
+        );
+
+        let samples: [i16; 10] = [0, 0, 16384, -16384, -32768, 8192, 0, 0, 16384, 0];
+
+        let mut planes = frame.planes_mut();
+
+        for (dst, src) in planes[0].data_mut().chunks_exact_mut(2).zip(&samples) {
+            dst.copy_from_slice(&src.to_ne_bytes());
+        }
+
+        let frame = frame.freeze();
+
+        let mut generator = WaveformGenerator::new(2);
+
+        let peaks = generator.push(&frame).unwrap();
+
+        assert_eq!(peaks.len(), 2);
+        assert_eq!((peaks[0].min(), peaks[0].max()), (-0.5, 0.5));
+        assert_eq!((peaks[1].min(), peaks[1].max()), (-1.0, 0.25));
+
+        let last = generator.finish().unwrap();
+
+        assert_eq!((last.min(), last.max()), (0.0, 0.5));
+        assert!(generator.finish().is_none());
+    }
+}
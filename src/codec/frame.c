@@ -1,10 +1,25 @@
 #include <libavutil/channel_layout.h>
 #include <libavutil/frame.h>
+#include <libavutil/hwcontext.h>
 #include <libavutil/imgutils.h>
+#include <libavutil/mastering_display_metadata.h>
 #include <libavutil/pixdesc.h>
 #include <libavutil/pixfmt.h>
 #include <libavutil/samplefmt.h>
 
+#include <string.h>
+
+// Dolby Vision RPU frame side data are available since FFmpeg 5.0
+#if LIBAVUTIL_VERSION_INT >= AV_VERSION_INT(57, 16, 100)
+#define FFW_HAS_DOVI_RPU
+#endif
+
+// AV1 film grain frame side data are available since FFmpeg 4.4
+#if LIBAVUTIL_VERSION_INT >= AV_VERSION_INT(56, 62, 100)
+#define FFW_HAS_FILM_GRAIN
+#include <libavutil/film_grain_params.h>
+#endif
+
 uint64_t ffw_get_channel_layout_by_name(const char* name) {
     return av_get_channel_layout(name);
 }
@@ -17,6 +32,14 @@ int ffw_get_channel_layout_channels(uint64_t layout) {
     return av_get_channel_layout_nb_channels(layout);
 }
 
+void ffw_get_channel_layout_name(uint64_t layout, char* buffer, int size) {
+    av_get_channel_layout_string(buffer, size, 0, layout);
+}
+
+const char* ffw_get_channel_layout_channel_name(uint64_t layout, int index) {
+    return av_get_channel_name(av_channel_layout_extract_channel(layout, index));
+}
+
 int ffw_get_sample_format_by_name(const char* name) {
     return av_get_sample_fmt(name);
 }
@@ -29,6 +52,10 @@ int ffw_sample_format_is_planar(int format) {
     return av_sample_fmt_is_planar(format);
 }
 
+int ffw_sample_format_get_bytes_per_sample(int format) {
+    return av_get_bytes_per_sample(format);
+}
+
 int ffw_sample_format_is_none(int format) {
     return format == AV_SAMPLE_FMT_NONE;
 }
@@ -45,8 +72,38 @@ int ffw_pixel_format_is_none(int format) {
     return format == AV_PIX_FMT_NONE;
 }
 
+const char* ffw_get_color_primaries_name(int primaries) {
+    return av_color_primaries_name(primaries);
+}
+
+int ffw_get_color_primaries_by_name(const char* name) {
+    return av_color_primaries_from_name(name);
+}
+
+const char* ffw_get_color_transfer_name(int transfer) {
+    return av_color_transfer_name(transfer);
+}
+
+int ffw_get_color_transfer_by_name(const char* name) {
+    return av_color_transfer_from_name(name);
+}
+
+const char* ffw_get_color_space_name(int space) {
+    return av_color_space_name(space);
+}
+
+int ffw_get_color_space_by_name(const char* name) {
+    return av_color_space_from_name(name);
+}
+
 AVFrame* ffw_frame_new_silence(uint64_t, int, int, int);
 AVFrame* ffw_frame_new_black(int, int, int);
+int ffw_frame_get_buffer_size(int, int, int, int);
+AVFrame* ffw_frame_new_with_buffer(int, int, int, int, uint8_t*, int, void (*)(void*, uint8_t*), void*);
+AVFrame* ffw_frame_slice_samples(const AVFrame*, int, int);
+AVFrame* ffw_frame_crop(const AVFrame*, int, int, int, int);
+AVFrame* ffw_frame_with_alpha(const AVFrame*, const AVFrame*, int);
+int ffw_frame_make_writable(AVFrame*);
 void ffw_frame_free(AVFrame*);
 
 AVFrame* ffw_frame_new_silence(uint64_t channel_layout, int sample_fmt, int sample_rate, int nb_samples) {
@@ -81,6 +138,70 @@ err:
     return NULL;
 }
 
+AVFrame* ffw_frame_slice_samples(const AVFrame* src, int offset, int nb_samples) {
+    AVFrame* frame;
+    int planes;
+    int sample_size;
+    int i;
+
+    frame = av_frame_clone(src);
+
+    if (frame == NULL) {
+        return NULL;
+    }
+
+    // NOTE: the sample data is shared with the source frame, only the data
+    // pointers are moved
+    sample_size = av_get_bytes_per_sample(src->format);
+
+    if (av_sample_fmt_is_planar(src->format)) {
+        planes = src->channels;
+    } else {
+        planes = 1;
+        sample_size *= src->channels;
+    }
+
+    for (i = 0; i < planes; i++) {
+        frame->extended_data[i] += offset * sample_size;
+
+        if (i < AV_NUM_DATA_POINTERS) {
+            frame->data[i] = frame->extended_data[i];
+        }
+    }
+
+    frame->nb_samples = nb_samples;
+    frame->linesize[0] = nb_samples * sample_size;
+
+    return frame;
+}
+
+AVFrame* ffw_frame_crop(const AVFrame* src, int x, int y, int width, int height) {
+    AVFrame* frame;
+
+    frame = av_frame_clone(src);
+
+    if (frame == NULL) {
+        return NULL;
+    }
+
+    // NOTE: the picture data is shared with the source frame, only the data
+    // pointers are moved
+    frame->crop_left = x;
+    frame->crop_top = y;
+    frame->crop_right = src->width - x - width;
+    frame->crop_bottom = src->height - y - height;
+
+    if (av_frame_apply_cropping(frame, AV_FRAME_CROP_UNALIGNED) < 0) {
+        av_frame_free(&frame);
+    }
+
+    return frame;
+}
+
+int ffw_frame_make_writable(AVFrame* frame) {
+    return av_frame_make_writable(frame);
+}
+
 AVFrame* ffw_frame_new_black(int pixel_format, int width, int height) {
     AVFrame* frame;
     uint8_t* data[4];
@@ -122,6 +243,53 @@ err:
     return NULL;
 }
 
+int ffw_frame_get_buffer_size(int pixel_format, int width, int height, int align) {
+    return av_image_get_buffer_size(pixel_format, width, height, align);
+}
+
+AVFrame* ffw_frame_new_with_buffer(
+    int pixel_format,
+    int width,
+    int height,
+    int align,
+    uint8_t* data,
+    int size,
+    void (*free_buffer)(void*, uint8_t*),
+    void* opaque) {
+    AVFrame* frame;
+
+    frame = av_frame_alloc();
+
+    if (frame == NULL) {
+        return NULL;
+    }
+
+    frame->format = pixel_format;
+    frame->width = width;
+    frame->height = height;
+
+    if (av_image_fill_arrays(frame->data, frame->linesize, data, pixel_format, width, height, align) < 0) {
+        goto err;
+    }
+
+    // NOTE: the buffer reference must be created as the last step; once it
+    // exists, the buffer is owned by the frame and it will be released using
+    // the given callback
+    frame->buf[0] = av_buffer_create(data, size, free_buffer, opaque, 0);
+    if (frame->buf[0] == NULL) {
+        goto err;
+    }
+
+    frame->extended_data = frame->data;
+
+    return frame;
+
+err:
+    ffw_frame_free(frame);
+
+    return NULL;
+}
+
 int ffw_frame_get_format(const AVFrame* frame) {
     return frame->format;
 }
@@ -158,6 +326,408 @@ void ffw_frame_set_pts(AVFrame* frame, int64_t pts) {
     frame->pts = pts;
 }
 
+int64_t ffw_frame_get_best_effort_timestamp(const AVFrame* frame) {
+    return frame->best_effort_timestamp;
+}
+
+void ffw_frame_set_best_effort_timestamp(AVFrame* frame, int64_t timestamp) {
+    frame->best_effort_timestamp = timestamp;
+}
+
+void ffw_frame_get_sample_aspect_ratio(const AVFrame* frame, int* num, int* den) {
+    *num = frame->sample_aspect_ratio.num;
+    *den = frame->sample_aspect_ratio.den;
+}
+
+void ffw_frame_set_sample_aspect_ratio(AVFrame* frame, int num, int den) {
+    frame->sample_aspect_ratio.num = num;
+    frame->sample_aspect_ratio.den = den;
+}
+
+// NOTE: unspecified color properties are represented as -1 on the Rust side
+static int color_property_to_raw(int value) {
+    return value == AVCOL_PRI_UNSPECIFIED ? -1 : value;
+}
+
+static int color_property_from_raw(int value) {
+    return value < 0 ? AVCOL_PRI_UNSPECIFIED : value;
+}
+
+void ffw_frame_get_color_properties(const AVFrame* frame, int* primaries, int* transfer, int* space) {
+    *primaries = color_property_to_raw(frame->color_primaries);
+    *transfer = color_property_to_raw(frame->color_trc);
+    *space = color_property_to_raw(frame->colorspace);
+}
+
+void ffw_frame_set_color_properties(AVFrame* frame, int primaries, int transfer, int space) {
+    frame->color_primaries = color_property_from_raw(primaries);
+    frame->color_trc = color_property_from_raw(transfer);
+    frame->colorspace = color_property_from_raw(space);
+}
+
+// The mastering display metadata are represented as an array of 20 integers
+// (numerators and denominators of the red, green and blue primaries, the
+// white point and the min and max luminance). The returned value is a
+// bitmask telling whether the primaries (1) and the luminance (2) are set.
+int ffw_mastering_display_metadata_get(const AVMasteringDisplayMetadata* metadata, int* values) {
+    int i;
+    int j;
+
+    for (i = 0; i < 3; i++) {
+        for (j = 0; j < 2; j++) {
+            values[i * 4 + j * 2] = metadata->display_primaries[i][j].num;
+            values[i * 4 + j * 2 + 1] = metadata->display_primaries[i][j].den;
+        }
+    }
+
+    for (j = 0; j < 2; j++) {
+        values[12 + j * 2] = metadata->white_point[j].num;
+        values[12 + j * 2 + 1] = metadata->white_point[j].den;
+    }
+
+    values[16] = metadata->min_luminance.num;
+    values[17] = metadata->min_luminance.den;
+    values[18] = metadata->max_luminance.num;
+    values[19] = metadata->max_luminance.den;
+
+    return (metadata->has_primaries ? 1 : 0) | (metadata->has_luminance ? 2 : 0);
+}
+
+void ffw_mastering_display_metadata_set(AVMasteringDisplayMetadata* metadata, int flags, const int* values) {
+    int i;
+    int j;
+
+    for (i = 0; i < 3; i++) {
+        for (j = 0; j < 2; j++) {
+            metadata->display_primaries[i][j] = av_make_q(values[i * 4 + j * 2], values[i * 4 + j * 2 + 1]);
+        }
+    }
+
+    for (j = 0; j < 2; j++) {
+        metadata->white_point[j] = av_make_q(values[12 + j * 2], values[12 + j * 2 + 1]);
+    }
+
+    metadata->min_luminance = av_make_q(values[16], values[17]);
+    metadata->max_luminance = av_make_q(values[18], values[19]);
+
+    metadata->has_primaries = (flags & 1) != 0;
+    metadata->has_luminance = (flags & 2) != 0;
+}
+
+const AVMasteringDisplayMetadata* ffw_frame_get_mastering_display_metadata(const AVFrame* frame) {
+    AVFrameSideData* sd = av_frame_get_side_data(frame, AV_FRAME_DATA_MASTERING_DISPLAY_METADATA);
+
+    if (!sd || sd->size < (int)sizeof(AVMasteringDisplayMetadata)) {
+        return NULL;
+    }
+
+    return (const AVMasteringDisplayMetadata*)sd->data;
+}
+
+AVMasteringDisplayMetadata* ffw_frame_new_mastering_display_metadata(AVFrame* frame) {
+    av_frame_remove_side_data(frame, AV_FRAME_DATA_MASTERING_DISPLAY_METADATA);
+
+    return av_mastering_display_metadata_create_side_data(frame);
+}
+
+void ffw_frame_remove_mastering_display_metadata(AVFrame* frame) {
+    av_frame_remove_side_data(frame, AV_FRAME_DATA_MASTERING_DISPLAY_METADATA);
+}
+
+int ffw_frame_get_content_light_level(const AVFrame* frame, uint32_t* max_cll, uint32_t* max_fall) {
+    const AVContentLightMetadata* metadata;
+    AVFrameSideData* sd;
+
+    sd = av_frame_get_side_data(frame, AV_FRAME_DATA_CONTENT_LIGHT_LEVEL);
+    if (!sd || sd->size < (int)sizeof(AVContentLightMetadata)) {
+        return 0;
+    }
+
+    metadata = (const AVContentLightMetadata*)sd->data;
+
+    *max_cll = metadata->MaxCLL;
+    *max_fall = metadata->MaxFALL;
+
+    return 1;
+}
+
+int ffw_frame_set_content_light_level(AVFrame* frame, uint32_t max_cll, uint32_t max_fall) {
+    AVContentLightMetadata* metadata;
+
+    av_frame_remove_side_data(frame, AV_FRAME_DATA_CONTENT_LIGHT_LEVEL);
+
+    metadata = av_content_light_metadata_create_side_data(frame);
+    if (!metadata) {
+        return AVERROR(ENOMEM);
+    }
+
+    metadata->MaxCLL = max_cll;
+    metadata->MaxFALL = max_fall;
+
+    return 0;
+}
+
+void ffw_frame_remove_content_light_level(AVFrame* frame) {
+    av_frame_remove_side_data(frame, AV_FRAME_DATA_CONTENT_LIGHT_LEVEL);
+}
+
+int ffw_frame_get_dovi_rpu(const AVFrame* frame, const uint8_t** data, int* size) {
+#ifdef FFW_HAS_DOVI_RPU
+    AVFrameSideData* sd = av_frame_get_side_data(frame, AV_FRAME_DATA_DOVI_RPU_BUFFER);
+
+    if (!sd) {
+        return 0;
+    }
+
+    *data = sd->data;
+    *size = sd->size;
+
+    return 1;
+#else
+    (void)frame;
+    (void)data;
+    (void)size;
+
+    return 0;
+#endif
+}
+
+int ffw_frame_set_dovi_rpu(AVFrame* frame, const uint8_t* data, int size) {
+#ifdef FFW_HAS_DOVI_RPU
+    AVFrameSideData* sd;
+
+    av_frame_remove_side_data(frame, AV_FRAME_DATA_DOVI_RPU_BUFFER);
+
+    if (!data) {
+        return 0;
+    }
+
+    sd = av_frame_new_side_data(frame, AV_FRAME_DATA_DOVI_RPU_BUFFER, size);
+    if (!sd) {
+        return AVERROR(ENOMEM);
+    }
+
+    memcpy(sd->data, data, size);
+
+    return 0;
+#else
+    (void)frame;
+    (void)size;
+
+    return data ? AVERROR(ENOSYS) : 0;
+#endif
+}
+
+int ffw_frame_get_av1_film_grain_params(const AVFrame* frame, uint64_t* seed, int* values) {
+#ifdef FFW_HAS_FILM_GRAIN
+    const AVFrameSideData* sd = av_frame_get_side_data(frame, AV_FRAME_DATA_FILM_GRAIN_PARAMS);
+    const AVFilmGrainParams* params;
+    const AVFilmGrainAOMParams* aom;
+    int i;
+    int j;
+    int k = 0;
+
+    if (!sd) {
+        return 0;
+    }
+
+    params = (const AVFilmGrainParams*)sd->data;
+    if (params->type != AV_FILM_GRAIN_PARAMS_AV1) {
+        return 0;
+    }
+
+    aom = &params->codec.aom;
+
+    *seed = params->seed;
+
+    values[k++] = aom->num_y_points;
+    for (i = 0; i < 14; i++) {
+        values[k++] = aom->y_points[i][0];
+        values[k++] = aom->y_points[i][1];
+    }
+
+    values[k++] = aom->chroma_scaling_from_luma;
+    for (i = 0; i < 2; i++) {
+        values[k++] = aom->num_uv_points[i];
+        for (j = 0; j < 10; j++) {
+            values[k++] = aom->uv_points[i][j][0];
+            values[k++] = aom->uv_points[i][j][1];
+        }
+    }
+
+    values[k++] = aom->scaling_shift;
+    values[k++] = aom->ar_coeff_lag;
+    for (i = 0; i < 24; i++) {
+        values[k++] = aom->ar_coeffs_y[i];
+    }
+
+    for (i = 0; i < 2; i++) {
+        for (j = 0; j < 25; j++) {
+            values[k++] = aom->ar_coeffs_uv[i][j];
+        }
+    }
+
+    values[k++] = aom->ar_coeff_shift;
+    values[k++] = aom->grain_scale_shift;
+
+    for (i = 0; i < 2; i++) {
+        values[k++] = aom->uv_mult[i];
+        values[k++] = aom->uv_mult_luma[i];
+        values[k++] = aom->uv_offset[i];
+    }
+
+    values[k++] = aom->overlap_flag;
+    values[k++] = aom->limit_output_range;
+
+    return 1;
+#else
+    (void)frame;
+    (void)seed;
+    (void)values;
+
+    return 0;
+#endif
+}
+
+int ffw_frame_copy_hdr_metadata(AVFrame* dst, const AVFrame* src) {
+    const AVMasteringDisplayMetadata* mastering;
+    AVMasteringDisplayMetadata* tmp;
+    uint32_t max_cll;
+    uint32_t max_fall;
+
+    dst->color_primaries = src->color_primaries;
+    dst->color_trc = src->color_trc;
+
+    av_frame_remove_side_data(dst, AV_FRAME_DATA_MASTERING_DISPLAY_METADATA);
+    av_frame_remove_side_data(dst, AV_FRAME_DATA_CONTENT_LIGHT_LEVEL);
+
+    mastering = ffw_frame_get_mastering_display_metadata(src);
+    if (mastering) {
+        tmp = av_mastering_display_metadata_create_side_data(dst);
+        if (!tmp) {
+            return AVERROR(ENOMEM);
+        }
+
+        *tmp = *mastering;
+    }
+
+    if (ffw_frame_get_content_light_level(src, &max_cll, &max_fall)) {
+        return ffw_frame_set_content_light_level(dst, max_cll, max_fall);
+    }
+
+    return 0;
+}
+
+int ffw_frame_is_interlaced(const AVFrame* frame) {
+    return frame->interlaced_frame;
+}
+
+int ffw_frame_is_top_field_first(const AVFrame* frame) {
+    return frame->top_field_first;
+}
+
+int ffw_frame_is_key(const AVFrame* frame) {
+    return frame->key_frame;
+}
+
+char ffw_frame_get_picture_type(const AVFrame* frame) {
+    return av_get_picture_type_char(frame->pict_type);
+}
+
+int ffw_frame_get_hw_handle_type(const AVFrame* frame) {
+    switch (frame->format) {
+        case AV_PIX_FMT_VIDEOTOOLBOX: return 1;
+        case AV_PIX_FMT_CUDA: return 2;
+        case AV_PIX_FMT_VAAPI: return 3;
+        case AV_PIX_FMT_D3D11: return 4;
+        default: return 0;
+    }
+}
+
+const AVBufferRef* ffw_frame_get_hw_frames_ctx(const AVFrame* frame) {
+    return frame->hw_frames_ctx;
+}
+
+AVFrame* ffw_frame_download(const AVFrame* frame) {
+    AVFrame* res;
+    int ret;
+
+    res = av_frame_alloc();
+    if (res == NULL) {
+        return NULL;
+    }
+
+    ret = av_hwframe_transfer_data(res, frame, 0);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = av_frame_copy_props(res, frame);
+    if (ret < 0) {
+        goto err;
+    }
+
+    return res;
+
+err:
+    av_frame_free(&res);
+
+    return NULL;
+}
+
+AVFrame* ffw_frame_with_alpha(const AVFrame* frame, const AVFrame* alpha, int format) {
+    const AVPixFmtDescriptor* desc = av_pix_fmt_desc_get(frame->format);
+    AVFrame* res;
+    int chroma_width;
+    int chroma_height;
+    int ret;
+    int i;
+
+    res = av_frame_alloc();
+    if (res == NULL) {
+        return NULL;
+    }
+
+    res->format = format;
+    res->width = frame->width;
+    res->height = frame->height;
+
+    ret = av_frame_get_buffer(res, 0);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = av_frame_copy_props(res, frame);
+    if (ret < 0) {
+        goto err;
+    }
+
+    chroma_width = AV_CEIL_RSHIFT(frame->width, desc->log2_chroma_w);
+    chroma_height = AV_CEIL_RSHIFT(frame->height, desc->log2_chroma_h);
+
+    av_image_copy_plane(res->data[0], res->linesize[0], frame->data[0], frame->linesize[0], frame->width, frame->height);
+
+    for (i = 1; i < 3; i++) {
+        av_image_copy_plane(res->data[i], res->linesize[i], frame->data[i], frame->linesize[i], chroma_width, chroma_height);
+    }
+
+    // the alpha channel is stored in the luma plane of the alpha frame
+    if (alpha) {
+        av_image_copy_plane(res->data[3], res->linesize[3], alpha->data[0], alpha->linesize[0], frame->width, frame->height);
+    } else {
+        for (i = 0; i < frame->height; i++) {
+            memset(res->data[3] + i * res->linesize[3], 255, frame->width);
+        }
+    }
+
+    return res;
+
+err:
+    av_frame_free(&res);
+
+    return NULL;
+}
+
 AVFrame* ffw_frame_clone(const AVFrame* frame) {
     return av_frame_clone(frame);
 }
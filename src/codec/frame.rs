@@ -0,0 +1,162 @@
+//! Media frames.
+
+use crate::{
+    codec::{audio::AudioFrame, video::VideoFrame, MediaType},
+    time::{TimeBase, Timestamp},
+};
+
+/// Common trait for all media frames.
+pub trait MediaFrame {
+    /// Get media type of the frame.
+    fn media_type(&self) -> MediaType;
+
+    /// Get frame time base.
+    fn time_base(&self) -> TimeBase;
+
+    /// Get presentation timestamp.
+    fn pts(&self) -> Timestamp;
+}
+
+impl MediaFrame for AudioFrame {
+    fn media_type(&self) -> MediaType {
+        MediaType::Audio
+    }
+
+    fn time_base(&self) -> TimeBase {
+        AudioFrame::time_base(self)
+    }
+
+    fn pts(&self) -> Timestamp {
+        AudioFrame::pts(self)
+    }
+}
+
+impl MediaFrame for VideoFrame {
+    fn media_type(&self) -> MediaType {
+        MediaType::Video
+    }
+
+    fn time_base(&self) -> TimeBase {
+        VideoFrame::time_base(self)
+    }
+
+    fn pts(&self) -> Timestamp {
+        VideoFrame::pts(self)
+    }
+}
+
+/// A frame of any media type.
+///
+/// The type allows implementing pipeline stages (e.g. buffering or
+/// reordering) only once for all media types. Subtitles are not decoded
+/// into frames, so there is no subtitle variant.
+#[derive(Clone)]
+pub enum Frame {
+    Audio(AudioFrame),
+    Video(VideoFrame),
+}
+
+impl Frame {
+    /// Get media type of the frame.
+    pub fn media_type(&self) -> MediaType {
+        match self {
+            Self::Audio(_) => MediaType::Audio,
+            Self::Video(_) => MediaType::Video,
+        }
+    }
+
+    /// Get frame time base.
+    pub fn time_base(&self) -> TimeBase {
+        match self {
+            Self::Audio(frame) => frame.time_base(),
+            Self::Video(frame) => frame.time_base(),
+        }
+    }
+
+    /// Set frame time base. (This will rescale the current timestamp into a
+    /// given time base.)
+    pub fn with_time_base(self, time_base: TimeBase) -> Self {
+        match self {
+            Self::Audio(frame) => Self::Audio(frame.with_time_base(time_base)),
+            Self::Video(frame) => Self::Video(frame.with_time_base(time_base)),
+        }
+    }
+
+    /// Get presentation timestamp of the frame.
+    pub fn pts(&self) -> Timestamp {
+        match self {
+            Self::Audio(frame) => frame.pts(),
+            Self::Video(frame) => frame.pts(),
+        }
+    }
+
+    /// Set presentation timestamp.
+    pub fn with_pts(self, pts: Timestamp) -> Self {
+        match self {
+            Self::Audio(frame) => Self::Audio(frame.with_pts(pts)),
+            Self::Video(frame) => Self::Video(frame.with_pts(pts)),
+        }
+    }
+
+    /// Get reference to the audio frame (if possible).
+    pub fn as_audio_frame(&self) -> Option<&AudioFrame> {
+        if let Self::Audio(frame) = self {
+            Some(frame)
+        } else {
+            None
+        }
+    }
+
+    /// Get reference to the video frame (if possible).
+    pub fn as_video_frame(&self) -> Option<&VideoFrame> {
+        if let Self::Video(frame) = self {
+            Some(frame)
+        } else {
+            None
+        }
+    }
+
+    /// Convert this object into an audio frame (if possible).
+    pub fn into_audio_frame(self) -> Option<AudioFrame> {
+        if let Self::Audio(frame) = self {
+            Some(frame)
+        } else {
+            None
+        }
+    }
+
+    /// Convert this object into a video frame (if possible).
+    pub fn into_video_frame(self) -> Option<VideoFrame> {
+        if let Self::Video(frame) = self {
+            Some(frame)
+        } else {
+            None
+        }
+    }
+}
+
+impl MediaFrame for Frame {
+    fn media_type(&self) -> MediaType {
+        Frame::media_type(self)
+    }
+
+    fn time_base(&self) -> TimeBase {
+        Frame::time_base(self)
+    }
+
+    fn pts(&self) -> Timestamp {
+        Frame::pts(self)
+    }
+}
+
+impl From<AudioFrame> for Frame {
+    fn from(frame: AudioFrame) -> Self {
+        Self::Audio(frame)
+    }
+}
+
+impl From<VideoFrame> for Frame {
+    fn from(frame: VideoFrame) -> Self {
+        Self::Video(frame)
+    }
+}
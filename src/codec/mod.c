@@ -1,4 +1,14 @@
 #include <libavcodec/avcodec.h>
+#include <libavutil/hwcontext.h>
+#include <libavutil/opt.h>
+#include <libavutil/pixdesc.h>
+
+#define MEDIA_TYPE_UNKNOWN    -1
+#define MEDIA_TYPE_VIDEO      0
+#define MEDIA_TYPE_AUDIO      1
+#define MEDIA_TYPE_SUBTITLE   2
+#define MEDIA_TYPE_DATA       3
+#define MEDIA_TYPE_ATTACHMENT 4
 
 static const AVCodec* ffw_find_codec(const char* name, int type) {
     const AVCodec* codec;
@@ -81,6 +91,91 @@ int ffw_codec_parameters_is_subtitle_codec(const AVCodecParameters* params) {
     return params->codec_type == AVMEDIA_TYPE_SUBTITLE;
 }
 
+int ffw_codec_parameters_is_data_codec(const AVCodecParameters* params) {
+    return params->codec_type == AVMEDIA_TYPE_DATA;
+}
+
+int ffw_codec_parameters_is_attachment_codec(const AVCodecParameters* params) {
+    return params->codec_type == AVMEDIA_TYPE_ATTACHMENT;
+}
+
+const char* ffw_codec_parameters_get_codec_name(const AVCodecParameters* params) {
+    return avcodec_get_name(params->codec_id);
+}
+
+const char* ffw_codec_parameters_get_codec_long_name(const AVCodecParameters* params) {
+    const AVCodecDescriptor* descriptor = avcodec_descriptor_get(params->codec_id);
+
+    if (!descriptor) {
+        return NULL;
+    }
+
+    return descriptor->long_name;
+}
+
+const AVCodecDescriptor* ffw_codec_parameters_get_descriptor(const AVCodecParameters* params) {
+    return avcodec_descriptor_get(params->codec_id);
+}
+
+const AVCodecDescriptor* ffw_codec_descriptor_find_by_name(const char* name) {
+    return avcodec_descriptor_get_by_name(name);
+}
+
+const char* ffw_codec_descriptor_get_name(const AVCodecDescriptor* descriptor) {
+    return descriptor->name;
+}
+
+const char* ffw_codec_descriptor_get_long_name(const AVCodecDescriptor* descriptor) {
+    return descriptor->long_name;
+}
+
+int ffw_codec_descriptor_get_media_type(const AVCodecDescriptor* descriptor) {
+    switch (descriptor->type) {
+        case AVMEDIA_TYPE_VIDEO: return MEDIA_TYPE_VIDEO;
+        case AVMEDIA_TYPE_AUDIO: return MEDIA_TYPE_AUDIO;
+        case AVMEDIA_TYPE_SUBTITLE: return MEDIA_TYPE_SUBTITLE;
+        case AVMEDIA_TYPE_DATA: return MEDIA_TYPE_DATA;
+        case AVMEDIA_TYPE_ATTACHMENT: return MEDIA_TYPE_ATTACHMENT;
+        default: return MEDIA_TYPE_UNKNOWN;
+    }
+}
+
+int ffw_codec_descriptor_is_intra_only(const AVCodecDescriptor* descriptor) {
+    return (descriptor->props & AV_CODEC_PROP_INTRA_ONLY) != 0;
+}
+
+int ffw_codec_descriptor_is_lossy(const AVCodecDescriptor* descriptor) {
+    return (descriptor->props & AV_CODEC_PROP_LOSSY) != 0;
+}
+
+int ffw_codec_descriptor_is_lossless(const AVCodecDescriptor* descriptor) {
+    return (descriptor->props & AV_CODEC_PROP_LOSSLESS) != 0;
+}
+
+int ffw_codec_descriptor_has_reorder(const AVCodecDescriptor* descriptor) {
+    return (descriptor->props & AV_CODEC_PROP_REORDER) != 0;
+}
+
+const char* ffw_codec_descriptor_get_mime_type(const AVCodecDescriptor* descriptor, size_t index) {
+    size_t i;
+
+    if (!descriptor->mime_types) {
+        return NULL;
+    }
+
+    for (i = 0; i < index; i++) {
+        if (!descriptor->mime_types[i]) {
+            return NULL;
+        }
+    }
+
+    return descriptor->mime_types[index];
+}
+
+uint32_t ffw_codec_parameters_get_codec_tag(const AVCodecParameters* params) {
+    return params->codec_tag;
+}
+
 const char* ffw_codec_parameters_get_decoder_name(const AVCodecParameters* params) {
     AVCodec* codec = avcodec_find_decoder(params->codec_id);
     if (!codec) {
@@ -99,6 +194,18 @@ const char* ffw_codec_parameters_get_encoder_name(const AVCodecParameters* param
     return codec->name;
 }
 
+int ffw_codec_parameters_get_profile(const AVCodecParameters* params) {
+    return params->profile;
+}
+
+const char* ffw_codec_parameters_get_profile_name(const AVCodecParameters* params) {
+    return avcodec_profile_name(params->codec_id, params->profile);
+}
+
+int ffw_codec_parameters_get_level(const AVCodecParameters* params) {
+    return params->level;
+}
+
 int64_t ffw_codec_parameters_get_bit_rate(const AVCodecParameters* params) {
     return params->bit_rate;
 }
@@ -123,6 +230,58 @@ uint64_t ffw_codec_parameters_get_channel_layout(const AVCodecParameters* params
     return params->channel_layout;
 }
 
+int ffw_codec_parameters_get_channels(const AVCodecParameters* params) {
+    return params->channels;
+}
+
+int ffw_codec_parameters_get_frame_size(const AVCodecParameters* params) {
+    return params->frame_size;
+}
+
+int ffw_codec_parameters_get_bits_per_coded_sample(const AVCodecParameters* params) {
+    return params->bits_per_coded_sample;
+}
+
+int ffw_codec_parameters_get_bits_per_raw_sample(const AVCodecParameters* params) {
+    return params->bits_per_raw_sample;
+}
+
+int ffw_codec_parameters_get_initial_padding(const AVCodecParameters* params) {
+    return params->initial_padding;
+}
+
+int ffw_codec_parameters_get_trailing_padding(const AVCodecParameters* params) {
+    return params->trailing_padding;
+}
+
+void ffw_codec_parameters_get_sample_aspect_ratio(const AVCodecParameters* params, uint32_t* num, uint32_t* den) {
+    if (params->sample_aspect_ratio.num > 0 && params->sample_aspect_ratio.den > 0) {
+        *num = params->sample_aspect_ratio.num;
+        *den = params->sample_aspect_ratio.den;
+    } else {
+        *num = 0;
+        *den = 0;
+    }
+}
+
+#define FIELD_ORDER_UNKNOWN     0
+#define FIELD_ORDER_PROGRESSIVE 1
+#define FIELD_ORDER_TT          2
+#define FIELD_ORDER_BB          3
+#define FIELD_ORDER_TB          4
+#define FIELD_ORDER_BT          5
+
+int ffw_codec_parameters_get_field_order(const AVCodecParameters* params) {
+    switch (params->field_order) {
+        case AV_FIELD_PROGRESSIVE: return FIELD_ORDER_PROGRESSIVE;
+        case AV_FIELD_TT:          return FIELD_ORDER_TT;
+        case AV_FIELD_BB:          return FIELD_ORDER_BB;
+        case AV_FIELD_TB:          return FIELD_ORDER_TB;
+        case AV_FIELD_BT:          return FIELD_ORDER_BT;
+        default:                   return FIELD_ORDER_UNKNOWN;
+    }
+}
+
 uint8_t* ffw_codec_parameters_get_extradata(AVCodecParameters* params) {
     return params->extradata;
 }
@@ -151,11 +310,35 @@ void ffw_codec_parameters_set_sample_rate(AVCodecParameters* params, int sample_
     params->sample_rate = sample_rate;
 }
 
+void ffw_codec_parameters_set_sample_aspect_ratio(AVCodecParameters* params, uint32_t num, uint32_t den) {
+    params->sample_aspect_ratio.num = num;
+    params->sample_aspect_ratio.den = den;
+}
+
+void ffw_codec_parameters_set_field_order(AVCodecParameters* params, int field_order) {
+    switch (field_order) {
+        case FIELD_ORDER_PROGRESSIVE: params->field_order = AV_FIELD_PROGRESSIVE; break;
+        case FIELD_ORDER_TT:          params->field_order = AV_FIELD_TT; break;
+        case FIELD_ORDER_BB:          params->field_order = AV_FIELD_BB; break;
+        case FIELD_ORDER_TB:          params->field_order = AV_FIELD_TB; break;
+        case FIELD_ORDER_BT:          params->field_order = AV_FIELD_BT; break;
+        default:                      params->field_order = AV_FIELD_UNKNOWN; break;
+    }
+}
+
 void ffw_codec_parameters_set_channel_layout(AVCodecParameters* params, uint64_t channel_layout) {
     params->channel_layout = channel_layout;
     params->channels = av_get_channel_layout_nb_channels(channel_layout);
 }
 
+void ffw_codec_parameters_set_initial_padding(AVCodecParameters* params, int padding) {
+    params->initial_padding = padding;
+}
+
+void ffw_codec_parameters_set_trailing_padding(AVCodecParameters* params, int padding) {
+    params->trailing_padding = padding;
+}
+
 int ffw_codec_parameters_set_extradata(AVCodecParameters* params, const uint8_t* extradata, int size) {
     if (params->extradata) {
         av_freep(&params->extradata);
@@ -188,16 +371,22 @@ typedef struct Decoder {
     struct AVDictionary* options;
     struct AVCodecContext* cc;
     struct AVFrame* frame;
+    enum AVPixelFormat hw_format;
 } Decoder;
 
 Decoder* ffw_decoder_new(const char* codec);
 Decoder* ffw_decoder_from_codec_parameters(const AVCodecParameters* params);
 int ffw_decoder_set_extradata(Decoder* decoder, const uint8_t* extradata, int size);
 int ffw_decoder_set_initial_option(Decoder* decoder, const char* key, const char* value);
+int ffw_decoder_set_context_option(Decoder* decoder, const char* key, const char* value);
+int ffw_decoder_set_hw_device(Decoder* decoder, const AVBufferRef* device);
 int ffw_decoder_open(Decoder* decoder);
+const AVDictionary* ffw_decoder_get_unused_options(const Decoder* decoder);
 int ffw_decoder_push_packet(Decoder* decoder, const AVPacket* packet);
 int ffw_decoder_take_frame(Decoder* decoder, AVFrame** frame);
+void ffw_decoder_reset(Decoder* decoder);
 AVCodecParameters* ffw_decoder_get_codec_parameters(const Decoder* decoder);
+const AVClass* ffw_decoder_get_priv_class(const Decoder* decoder);
 void ffw_decoder_free(Decoder* decoder);
 
 Decoder* ffw_decoder_new(const char* codec) {
@@ -215,6 +404,7 @@ Decoder* ffw_decoder_new(const char* codec) {
     res->options = NULL;
     res->cc = NULL;
     res->frame = NULL;
+    res->hw_format = AV_PIX_FMT_NONE;
 
     res->cc = avcodec_alloc_context3(decoder);
     if (res->cc == NULL) {
@@ -249,6 +439,7 @@ Decoder* ffw_decoder_from_codec_parameters(const AVCodecParameters* params) {
     res->options = NULL;
     res->cc = NULL;
     res->frame = NULL;
+    res->hw_format = AV_PIX_FMT_NONE;
 
     res->cc = avcodec_alloc_context3(decoder);
     if (res->cc == NULL) {
@@ -299,6 +490,10 @@ int ffw_decoder_set_initial_option(Decoder* decoder, const char* key, const char
     return av_dict_set(&decoder->options, key, value, 0);
 }
 
+int ffw_decoder_set_context_option(Decoder* decoder, const char* key, const char* value) {
+    return av_opt_set(decoder->cc, key, value, 0);
+}
+
 void ffw_decoder_set_pkt_timebase(Decoder* decoder, int num, int den) {
     AVRational r;
 
@@ -308,10 +503,68 @@ void ffw_decoder_set_pkt_timebase(Decoder* decoder, int num, int den) {
     decoder->cc->pkt_timebase = r;
 }
 
+static enum AVPixelFormat ffw_decoder_get_format(AVCodecContext* cc, const enum AVPixelFormat* formats) {
+    const Decoder* decoder = cc->opaque;
+    const AVPixFmtDescriptor* desc;
+    const enum AVPixelFormat* p;
+
+    for (p = formats; *p != AV_PIX_FMT_NONE; p++) {
+        if (*p == decoder->hw_format) {
+            return *p;
+        }
+    }
+
+    // fall back to software decoding if the hardware format is not offered
+    for (p = formats; *p != AV_PIX_FMT_NONE; p++) {
+        desc = av_pix_fmt_desc_get(*p);
+
+        if (desc != NULL && !(desc->flags & AV_PIX_FMT_FLAG_HWACCEL)) {
+            return *p;
+        }
+    }
+
+    return AV_PIX_FMT_NONE;
+}
+
+int ffw_decoder_set_hw_device(Decoder* decoder, const AVBufferRef* device) {
+    const AVHWDeviceContext* dc = (const AVHWDeviceContext*)device->data;
+    const AVCodecHWConfig* config;
+    int i;
+
+    for (i = 0;; i++) {
+        config = avcodec_get_hw_config(decoder->decoder, i);
+        if (config == NULL) {
+            return AVERROR(ENOSYS);
+        }
+
+        if ((config->methods & AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX) && config->device_type == dc->type) {
+            break;
+        }
+    }
+
+    av_buffer_unref(&decoder->cc->hw_device_ctx);
+
+    decoder->cc->hw_device_ctx = av_buffer_ref((AVBufferRef*)device);
+    if (decoder->cc->hw_device_ctx == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    decoder->hw_format = config->pix_fmt;
+
+    decoder->cc->opaque = decoder;
+    decoder->cc->get_format = ffw_decoder_get_format;
+
+    return 0;
+}
+
 int ffw_decoder_open(Decoder* decoder) {
     return avcodec_open2(decoder->cc, decoder->decoder, &decoder->options);
 }
 
+const AVDictionary* ffw_decoder_get_unused_options(const Decoder* decoder) {
+    return decoder->options;
+}
+
 int ffw_decoder_push_packet(Decoder* decoder, const AVPacket* packet) {
     int ret = avcodec_send_packet(decoder->cc, packet);
 
@@ -338,6 +591,10 @@ int ffw_decoder_take_frame(Decoder* decoder, AVFrame** frame) {
     return 1;
 }
 
+void ffw_decoder_reset(Decoder* decoder) {
+    avcodec_flush_buffers(decoder->cc);
+}
+
 AVCodecParameters* ffw_decoder_get_codec_parameters(const Decoder* decoder) {
     AVCodecParameters* params;
     int ret;
@@ -360,6 +617,10 @@ err:
     return NULL;
 }
 
+const AVClass* ffw_decoder_get_priv_class(const Decoder* decoder) {
+    return decoder->decoder->priv_class;
+}
+
 void ffw_decoder_free(Decoder* decoder) {
     if (decoder == NULL) {
         return;
@@ -384,6 +645,9 @@ typedef struct Encoder {
     struct AVCodecContext* cc;
     struct AVCodec* codec;
     struct AVPacket* packet;
+
+    int64_t hw_direct;
+    int64_t hw_transfers;
 } Encoder;
 
 Encoder* ffw_encoder_new(const char* codec);
@@ -395,7 +659,9 @@ int ffw_encoder_get_sample_format(const Encoder* encoder);
 int ffw_encoder_get_sample_rate(const Encoder* encoder);
 uint64_t ffw_encoder_get_channel_layout(const Encoder* encoder);
 void ffw_encoder_set_time_base(Encoder* encoder, int num, int den);
+void ffw_encoder_set_frame_rate(Encoder* encoder, int num, int den);
 void ffw_encoder_set_bit_rate(Encoder* encoder, int64_t bit_rate);
+void ffw_encoder_set_color_properties(Encoder* encoder, int primaries, int transfer, int space);
 void ffw_encoder_set_pixel_format(Encoder* encoder, int format);
 void ffw_encoder_set_width(Encoder* encoder, int width);
 void ffw_encoder_set_height(Encoder* encoder, int height);
@@ -403,9 +669,16 @@ void ffw_encoder_set_sample_format(Encoder* encoder, int format);
 void ffw_encoder_set_sample_rate(Encoder* encoder, int sample_rate);
 void ffw_encoder_set_channel_layout(Encoder* encoder, uint64_t channel_layout);
 int ffw_encoder_set_initial_option(Encoder* encoder, const char* key, const char* value);
+int ffw_encoder_set_context_option(Encoder* encoder, const char* key, const char* value);
+int ffw_encoder_set_hw_device(Encoder* encoder, const AVBufferRef* device);
+int ffw_encoder_init_hw_frames(Encoder* encoder, const AVBufferRef* device, int hw_format, int pool_size);
+int ffw_encoder_set_hw_frames(Encoder* encoder, const AVBufferRef* frames);
+int ffw_encoder_is_zero_copy(const Encoder* encoder);
 int ffw_encoder_open(Encoder* encoder);
+const AVDictionary* ffw_encoder_get_unused_options(const Encoder* encoder);
 int ffw_encoder_push_frame(Encoder* encoder, const AVFrame* frame);
 int ffw_encoder_take_packet(Encoder* encoder, AVPacket** packet);
+const AVClass* ffw_encoder_get_priv_class(const Encoder* encoder);
 void ffw_encoder_free(Encoder* encoder);
 
 Encoder* ffw_encoder_new(const char* codec) {
@@ -423,6 +696,8 @@ Encoder* ffw_encoder_new(const char* codec) {
     res->options = NULL;
     res->cc = NULL;
     res->packet = NULL;
+    res->hw_direct = 0;
+    res->hw_transfers = 0;
 
     res->cc = avcodec_alloc_context3(encoder);
     if (res->cc == NULL) {
@@ -457,6 +732,8 @@ Encoder* ffw_encoder_from_codec_parameters(const AVCodecParameters* params) {
     res->options = NULL;
     res->cc = NULL;
     res->packet = NULL;
+    res->hw_direct = 0;
+    res->hw_transfers = 0;
 
     res->cc = avcodec_alloc_context3(encoder);
     if (res->cc == NULL) {
@@ -536,10 +813,31 @@ void ffw_encoder_set_time_base(Encoder* encoder, int num, int den) {
     encoder->cc->time_base.den = den;
 }
 
+void ffw_encoder_set_frame_rate(Encoder* encoder, int num, int den) {
+    encoder->cc->framerate.num = num;
+    encoder->cc->framerate.den = den;
+}
+
 void ffw_encoder_set_bit_rate(Encoder* encoder, int64_t bit_rate) {
     encoder->cc->bit_rate = bit_rate;
 }
 
+// NOTE: negative values (i.e. unspecified properties) are ignored, so that
+// the properties taken from codec parameters are not overridden
+void ffw_encoder_set_color_properties(Encoder* encoder, int primaries, int transfer, int space) {
+    if (primaries >= 0) {
+        encoder->cc->color_primaries = primaries;
+    }
+
+    if (transfer >= 0) {
+        encoder->cc->color_trc = transfer;
+    }
+
+    if (space >= 0) {
+        encoder->cc->colorspace = space;
+    }
+}
+
 void ffw_encoder_set_pixel_format(Encoder* encoder, int format) {
     encoder->cc->pix_fmt = format;
 }
@@ -569,12 +867,162 @@ int ffw_encoder_set_initial_option(Encoder* encoder, const char* key, const char
     return av_dict_set(&encoder->options, key, value, 0);
 }
 
+int ffw_encoder_set_context_option(Encoder* encoder, const char* key, const char* value) {
+    return av_opt_set(encoder->cc, key, value, 0);
+}
+
+int ffw_encoder_set_hw_device(Encoder* encoder, const AVBufferRef* device) {
+    av_buffer_unref(&encoder->cc->hw_device_ctx);
+
+    encoder->cc->hw_device_ctx = av_buffer_ref((AVBufferRef*)device);
+    if (encoder->cc->hw_device_ctx == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    return 0;
+}
+
+int ffw_encoder_init_hw_frames(Encoder* encoder, const AVBufferRef* device, int hw_format, int pool_size) {
+    AVBufferRef* frames_ref;
+    AVHWFramesContext* frames;
+    int ret;
+
+    frames_ref = av_hwframe_ctx_alloc((AVBufferRef*)device);
+    if (frames_ref == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    frames = (AVHWFramesContext*)frames_ref->data;
+
+    frames->format = hw_format;
+    frames->sw_format = encoder->cc->pix_fmt;
+    frames->width = encoder->cc->width;
+    frames->height = encoder->cc->height;
+    frames->initial_pool_size = pool_size;
+
+    ret = av_hwframe_ctx_init(frames_ref);
+    if (ret < 0) {
+        av_buffer_unref(&frames_ref);
+        return ret;
+    }
+
+    av_buffer_unref(&encoder->cc->hw_frames_ctx);
+
+    encoder->cc->hw_frames_ctx = frames_ref;
+    encoder->cc->pix_fmt = hw_format;
+
+    return 0;
+}
+
+int ffw_encoder_set_hw_frames(Encoder* encoder, const AVBufferRef* frames) {
+    const AVHWFramesContext* fc = (const AVHWFramesContext*)frames->data;
+
+    av_buffer_unref(&encoder->cc->hw_frames_ctx);
+
+    encoder->cc->hw_frames_ctx = av_buffer_ref((AVBufferRef*)frames);
+    if (encoder->cc->hw_frames_ctx == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    encoder->cc->pix_fmt = fc->format;
+
+    return 0;
+}
+
+int ffw_encoder_is_zero_copy(const Encoder* encoder) {
+    return encoder->cc->hw_frames_ctx != NULL
+        && encoder->hw_direct > 0
+        && encoder->hw_transfers == 0;
+}
+
 int ffw_encoder_open(Encoder* encoder) {
     return avcodec_open2(encoder->cc, encoder->codec, &encoder->options);
 }
 
+const AVDictionary* ffw_encoder_get_unused_options(const Encoder* encoder) {
+    return encoder->options;
+}
+
+static int ffw_encoder_send_frame(Encoder* encoder, const AVFrame* frame) {
+    AVFrame* sw_frame = NULL;
+    AVFrame* hw_frame = NULL;
+    int ret;
+
+    if (frame == NULL) {
+        return avcodec_send_frame(encoder->cc, frame);
+    }
+
+    // hardware frames from the same frame pool can be passed directly
+    if (frame->hw_frames_ctx != NULL
+        && encoder->cc->hw_frames_ctx != NULL
+        && frame->hw_frames_ctx->data == encoder->cc->hw_frames_ctx->data) {
+        encoder->hw_direct++;
+        return avcodec_send_frame(encoder->cc, frame);
+    }
+
+    if (frame->hw_frames_ctx == NULL && encoder->cc->hw_frames_ctx == NULL) {
+        return avcodec_send_frame(encoder->cc, frame);
+    }
+
+    // all other combinations need a round-trip through the system memory
+    encoder->hw_transfers++;
+
+    if (frame->hw_frames_ctx != NULL) {
+        sw_frame = av_frame_alloc();
+        if (sw_frame == NULL) {
+            return AVERROR(ENOMEM);
+        }
+
+        ret = av_hwframe_transfer_data(sw_frame, frame, 0);
+        if (ret < 0) {
+            goto end;
+        }
+
+        ret = av_frame_copy_props(sw_frame, frame);
+        if (ret < 0) {
+            goto end;
+        }
+
+        frame = sw_frame;
+    }
+
+    if (encoder->cc->hw_frames_ctx == NULL) {
+        ret = avcodec_send_frame(encoder->cc, frame);
+        goto end;
+    }
+
+    hw_frame = av_frame_alloc();
+    if (hw_frame == NULL) {
+        ret = AVERROR(ENOMEM);
+        goto end;
+    }
+
+    ret = av_hwframe_get_buffer(encoder->cc->hw_frames_ctx, hw_frame, 0);
+    if (ret < 0) {
+        goto end;
+    }
+
+    ret = av_hwframe_transfer_data(hw_frame, frame, 0);
+    if (ret < 0) {
+        goto end;
+    }
+
+    ret = av_frame_copy_props(hw_frame, frame);
+    if (ret < 0) {
+        goto end;
+    }
+
+    ret = avcodec_send_frame(encoder->cc, hw_frame);
+
+end:
+    av_frame_free(&sw_frame);
+    av_frame_free(&hw_frame);
+
+    return ret;
+}
+
 int ffw_encoder_push_frame(Encoder* encoder, const AVFrame* frame) {
-    int ret = avcodec_send_frame(encoder->cc, frame);
+    int ret = ffw_encoder_send_frame(encoder, frame);
 
     if (ret == 0 || ret == AVERROR_EOF) {
         return 1;
@@ -599,6 +1047,10 @@ int ffw_encoder_take_packet(Encoder* encoder, AVPacket** packet) {
     return 1;
 }
 
+const AVClass* ffw_encoder_get_priv_class(const Encoder* encoder) {
+    return encoder->codec->priv_class;
+}
+
 void ffw_encoder_free(Encoder* encoder) {
     if (encoder == NULL) {
         return;
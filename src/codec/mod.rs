@@ -2,24 +2,39 @@
 
 pub mod audio;
 pub mod bsf;
+pub mod frame;
 pub mod video;
 
 use std::{
+    convert::TryInto,
     ffi::{CStr, CString},
-    fmt::{self, Display, Formatter},
+    fmt::{self, Debug, Display, Formatter},
     os::raw::{c_char, c_int, c_void},
     ptr, slice,
+    sync::mpsc,
 };
 
 use crate::{
+    channel::{ChannelReceiver, ChannelSender},
     codec::{
-        audio::{ChannelLayout, SampleFormat},
-        video::PixelFormat,
+        audio::{AudioDecoder, AudioDecoderBuilder, ChannelLayout, SampleFormat},
+        video::{FieldOrder, PixelFormat, VideoDecoder, VideoDecoderBuilder},
     },
+    format::stream::Stream,
+    math::Rational,
     packet::Packet,
+    time::TimeBase,
     Error,
 };
 
+pub use self::frame::{Frame, MediaFrame};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const FF_PROFILE_UNKNOWN: c_int = -99;
+const FF_LEVEL_UNKNOWN: c_int = -99;
+
 extern "C" {
     fn ffw_audio_codec_parameters_new(codec: *const c_char) -> *mut c_void;
     fn ffw_video_codec_parameters_new(codec: *const c_char) -> *mut c_void;
@@ -28,22 +43,47 @@ extern "C" {
     fn ffw_codec_parameters_is_audio_codec(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_is_video_codec(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_is_subtitle_codec(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_is_data_codec(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_is_attachment_codec(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_codec_name(params: *const c_void) -> *const c_char;
+    fn ffw_codec_parameters_get_codec_long_name(params: *const c_void) -> *const c_char;
+    fn ffw_codec_parameters_get_codec_tag(params: *const c_void) -> u32;
+    fn ffw_codec_parameters_get_descriptor(params: *const c_void) -> *const c_void;
     fn ffw_codec_parameters_get_decoder_name(params: *const c_void) -> *const c_char;
     fn ffw_codec_parameters_get_encoder_name(params: *const c_void) -> *const c_char;
+    fn ffw_codec_parameters_get_profile(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_profile_name(params: *const c_void) -> *const c_char;
+    fn ffw_codec_parameters_get_level(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_get_bit_rate(params: *const c_void) -> i64;
     fn ffw_codec_parameters_get_format(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_get_width(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_get_height(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_get_sample_rate(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_get_channel_layout(params: *const c_void) -> u64;
+    fn ffw_codec_parameters_get_channels(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_frame_size(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_initial_padding(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_trailing_padding(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_bits_per_coded_sample(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_bits_per_raw_sample(params: *const c_void) -> c_int;
+    fn ffw_codec_parameters_get_sample_aspect_ratio(
+        params: *const c_void,
+        num: *mut u32,
+        den: *mut u32,
+    );
+    fn ffw_codec_parameters_get_field_order(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_get_extradata(params: *mut c_void) -> *mut c_void;
     fn ffw_codec_parameters_get_extradata_size(params: *const c_void) -> c_int;
     fn ffw_codec_parameters_set_bit_rate(params: *mut c_void, bit_rate: i64);
+    fn ffw_codec_parameters_set_initial_padding(params: *mut c_void, padding: c_int);
+    fn ffw_codec_parameters_set_trailing_padding(params: *mut c_void, padding: c_int);
     fn ffw_codec_parameters_set_format(params: *mut c_void, format: c_int);
     fn ffw_codec_parameters_set_width(params: *mut c_void, width: c_int);
     fn ffw_codec_parameters_set_height(params: *mut c_void, height: c_int);
     fn ffw_codec_parameters_set_sample_rate(params: *mut c_void, rate: c_int);
     fn ffw_codec_parameters_set_channel_layout(params: *mut c_void, layout: u64);
+    fn ffw_codec_parameters_set_sample_aspect_ratio(params: *mut c_void, num: u32, den: u32);
+    fn ffw_codec_parameters_set_field_order(params: *mut c_void, field_order: c_int);
     fn ffw_codec_parameters_set_extradata(
         params: *mut c_void,
         extradata: *const u8,
@@ -51,6 +91,17 @@ extern "C" {
     ) -> c_int;
     fn ffw_codec_parameters_free(params: *mut c_void);
 
+    fn ffw_codec_descriptor_find_by_name(name: *const c_char) -> *const c_void;
+    fn ffw_codec_descriptor_get_name(descriptor: *const c_void) -> *const c_char;
+    fn ffw_codec_descriptor_get_long_name(descriptor: *const c_void) -> *const c_char;
+    fn ffw_codec_descriptor_get_media_type(descriptor: *const c_void) -> c_int;
+    fn ffw_codec_descriptor_is_intra_only(descriptor: *const c_void) -> c_int;
+    fn ffw_codec_descriptor_is_lossy(descriptor: *const c_void) -> c_int;
+    fn ffw_codec_descriptor_is_lossless(descriptor: *const c_void) -> c_int;
+    fn ffw_codec_descriptor_has_reorder(descriptor: *const c_void) -> c_int;
+    fn ffw_codec_descriptor_get_mime_type(descriptor: *const c_void, index: usize)
+        -> *const c_char;
+
     fn ffw_decoder_new(codec: *const c_char) -> *mut c_void;
     fn ffw_decoder_from_codec_parameters(params: *const c_void) -> *mut c_void;
     fn ffw_decoder_set_extradata(decoder: *mut c_void, extradata: *const u8, size: c_int) -> c_int;
@@ -59,11 +110,20 @@ extern "C" {
         key: *const c_char,
         value: *const c_char,
     ) -> c_int;
+    fn ffw_decoder_set_context_option(
+        decoder: *mut c_void,
+        key: *const c_char,
+        value: *const c_char,
+    ) -> c_int;
     fn ffw_decoder_set_pkt_timebase(decoder: *mut c_void, num: c_int, den: c_int);
+    fn ffw_decoder_set_hw_device(decoder: *mut c_void, device: *const c_void) -> c_int;
     fn ffw_decoder_open(decoder: *mut c_void) -> c_int;
+    fn ffw_decoder_get_unused_options(decoder: *const c_void) -> *const c_void;
     fn ffw_decoder_push_packet(decoder: *mut c_void, packet: *const c_void) -> c_int;
     fn ffw_decoder_take_frame(decoder: *mut c_void, frame: *mut *mut c_void) -> c_int;
+    fn ffw_decoder_reset(decoder: *mut c_void);
     fn ffw_decoder_get_codec_parameters(decoder: *const c_void) -> *mut c_void;
+    fn ffw_decoder_get_priv_class(decoder: *const c_void) -> *const c_void;
     fn ffw_decoder_free(decoder: *mut c_void);
 
     fn ffw_encoder_new(codec: *const c_char) -> *mut c_void;
@@ -77,7 +137,14 @@ extern "C" {
     fn ffw_encoder_get_channel_layout(encoder: *const c_void) -> u64;
     fn ffw_encoder_get_frame_size(encoder: *const c_void) -> c_int;
     fn ffw_encoder_set_time_base(encoder: *mut c_void, num: c_int, den: c_int);
+    fn ffw_encoder_set_frame_rate(encoder: *mut c_void, num: c_int, den: c_int);
     fn ffw_encoder_set_bit_rate(encoder: *mut c_void, bit_rate: i64);
+    fn ffw_encoder_set_color_properties(
+        encoder: *mut c_void,
+        primaries: c_int,
+        transfer: c_int,
+        space: c_int,
+    );
     fn ffw_encoder_set_pixel_format(encoder: *mut c_void, format: c_int);
     fn ffw_encoder_set_width(encoder: *mut c_void, width: c_int);
     fn ffw_encoder_set_height(encoder: *mut c_void, height: c_int);
@@ -89,9 +156,25 @@ extern "C" {
         key: *const c_char,
         value: *const c_char,
     ) -> c_int;
+    fn ffw_encoder_set_context_option(
+        encoder: *mut c_void,
+        key: *const c_char,
+        value: *const c_char,
+    ) -> c_int;
+    fn ffw_encoder_set_hw_device(encoder: *mut c_void, device: *const c_void) -> c_int;
+    fn ffw_encoder_init_hw_frames(
+        encoder: *mut c_void,
+        device: *const c_void,
+        hw_format: c_int,
+        pool_size: c_int,
+    ) -> c_int;
+    fn ffw_encoder_set_hw_frames(encoder: *mut c_void, frames: *const c_void) -> c_int;
+    fn ffw_encoder_is_zero_copy(encoder: *const c_void) -> c_int;
     fn ffw_encoder_open(encoder: *mut c_void) -> c_int;
+    fn ffw_encoder_get_unused_options(encoder: *const c_void) -> *const c_void;
     fn ffw_encoder_push_frame(encoder: *mut c_void, frame: *const c_void) -> c_int;
     fn ffw_encoder_take_packet(encoder: *mut c_void, packet: *mut *mut c_void) -> c_int;
+    fn ffw_encoder_get_priv_class(encoder: *const c_void) -> *const c_void;
     fn ffw_encoder_free(encoder: *mut c_void);
 }
 
@@ -207,6 +290,53 @@ impl InnerCodecParameters {
         unsafe { ffw_codec_parameters_is_subtitle_codec(self.ptr) != 0 }
     }
 
+    /// Check if these codec parameters are for a data codec.
+    fn is_data_codec(&self) -> bool {
+        unsafe { ffw_codec_parameters_is_data_codec(self.ptr) != 0 }
+    }
+
+    /// Check if these codec parameters are for an attachment.
+    fn is_attachment_codec(&self) -> bool {
+        unsafe { ffw_codec_parameters_is_attachment_codec(self.ptr) != 0 }
+    }
+
+    /// Get name of the codec (e.g. "h264" or "timed_id3").
+    fn codec_name(&self) -> &'static str {
+        unsafe {
+            let ptr = ffw_codec_parameters_get_codec_name(self.ptr);
+
+            let name = CStr::from_ptr(ptr as _);
+
+            name.to_str().unwrap()
+        }
+    }
+
+    /// Get descriptive name of the codec (e.g. "H.264 / AVC / MPEG-4 AVC /
+    /// MPEG-4 part 10").
+    fn codec_long_name(&self) -> Option<&'static str> {
+        unsafe {
+            let ptr = ffw_codec_parameters_get_codec_long_name(self.ptr);
+
+            if ptr.is_null() {
+                None
+            } else {
+                let name = CStr::from_ptr(ptr as _);
+
+                Some(name.to_str().unwrap())
+            }
+        }
+    }
+
+    /// Get the codec descriptor or None if the codec is unknown.
+    fn descriptor(&self) -> Option<CodecDescriptor> {
+        unsafe { CodecDescriptor::from_raw_ptr(ffw_codec_parameters_get_descriptor(self.ptr)) }
+    }
+
+    /// Get the codec tag (e.g. FourCC).
+    fn codec_tag(&self) -> u32 {
+        unsafe { ffw_codec_parameters_get_codec_tag(self.ptr) }
+    }
+
     /// Get name of the decoder that is able to decode this codec or None
     /// if the decoder is not available.
     fn decoder_name(&self) -> Option<&'static str> {
@@ -238,6 +368,44 @@ impl InnerCodecParameters {
             }
         }
     }
+
+    /// Get codec profile or None if the profile is unknown.
+    fn profile(&self) -> Option<i32> {
+        let profile = unsafe { ffw_codec_parameters_get_profile(self.ptr) };
+
+        if profile == FF_PROFILE_UNKNOWN {
+            None
+        } else {
+            Some(profile as _)
+        }
+    }
+
+    /// Get human-readable name of the codec profile (e.g. "High" or
+    /// "Main 10") or None if the profile is unknown.
+    fn profile_name(&self) -> Option<&'static str> {
+        unsafe {
+            let ptr = ffw_codec_parameters_get_profile_name(self.ptr);
+
+            if ptr.is_null() {
+                None
+            } else {
+                let name = CStr::from_ptr(ptr as _);
+
+                Some(name.to_str().unwrap())
+            }
+        }
+    }
+
+    /// Get codec level or None if the level is unknown.
+    fn level(&self) -> Option<i32> {
+        let level = unsafe { ffw_codec_parameters_get_level(self.ptr) };
+
+        if level == FF_LEVEL_UNKNOWN {
+            None
+        } else {
+            Some(level as _)
+        }
+    }
 }
 
 impl Drop for InnerCodecParameters {
@@ -332,6 +500,41 @@ impl CodecParameters {
         self.inner.as_ref().is_subtitle_codec()
     }
 
+    /// Check if these codec parameters are for a data codec (e.g. timed
+    /// ID3 metadata).
+    pub fn is_data_codec(&self) -> bool {
+        self.inner.as_ref().is_data_codec()
+    }
+
+    /// Check if these codec parameters are for an attachment (e.g. a font
+    /// embedded in a Matroska file).
+    pub fn is_attachment_codec(&self) -> bool {
+        self.inner.as_ref().is_attachment_codec()
+    }
+
+    /// Get name of the codec (e.g. "h264" or "timed_id3").
+    pub fn codec_name(&self) -> &'static str {
+        self.inner.as_ref().codec_name()
+    }
+
+    /// Get descriptive name of the codec (e.g. "H.264 / AVC / MPEG-4 AVC /
+    /// MPEG-4 part 10") or None if the codec is unknown.
+    pub fn codec_long_name(&self) -> Option<&'static str> {
+        self.inner.as_ref().codec_long_name()
+    }
+
+    /// Get the codec descriptor (i.e. static properties of the codec) or
+    /// None if the codec is unknown.
+    pub fn descriptor(&self) -> Option<CodecDescriptor> {
+        self.inner.as_ref().descriptor()
+    }
+
+    /// Get the codec tag (e.g. FourCC used by the container). Zero means
+    /// that the tag is not set.
+    pub fn codec_tag(&self) -> u32 {
+        self.inner.as_ref().codec_tag()
+    }
+
     /// Get name of the decoder that is able to decode this codec or None
     /// if the decoder is not available.
     pub fn decoder_name(&self) -> Option<&'static str> {
@@ -344,6 +547,25 @@ impl CodecParameters {
         self.inner.as_ref().encoder_name()
     }
 
+    /// Get codec profile or None if the profile is unknown. The meaning of
+    /// the value is codec specific.
+    pub fn profile(&self) -> Option<i32> {
+        self.inner.as_ref().profile()
+    }
+
+    /// Get human-readable name of the codec profile (e.g. "High" or
+    /// "Main 10") or None if the profile is unknown.
+    pub fn profile_name(&self) -> Option<&'static str> {
+        self.inner.as_ref().profile_name()
+    }
+
+    /// Get codec level or None if the level is unknown. The meaning of the
+    /// value is codec specific (e.g. 41 means level 4.1 for H.264 while it is
+    /// 123 for H.265).
+    pub fn level(&self) -> Option<i32> {
+        self.inner.as_ref().level()
+    }
+
     /// Get reference to audio codec parameters (if possible).
     pub fn as_audio_codec_parameters(&self) -> Option<&AudioCodecParameters> {
         if let CodecParametersVariant::Audio(params) = &self.inner {
@@ -397,6 +619,402 @@ impl CodecParameters {
             None
         }
     }
+
+    /// Get media type of the codec.
+    pub fn media_type(&self) -> MediaType {
+        if self.is_video_codec() {
+            MediaType::Video
+        } else if self.is_audio_codec() {
+            MediaType::Audio
+        } else if self.is_subtitle_codec() {
+            MediaType::Subtitle
+        } else if self.is_data_codec() {
+            MediaType::Data
+        } else if self.is_attachment_codec() {
+            MediaType::Attachment
+        } else {
+            MediaType::Unknown
+        }
+    }
+
+    /// Get an owned summary of these codec parameters.
+    pub fn summary(&self) -> CodecParametersSummary {
+        CodecParametersSummary::new(self)
+    }
+}
+
+/// Media type of a codec.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MediaType {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Attachment,
+    Unknown,
+}
+
+impl MediaType {
+    /// Create media type from its internal raw representation.
+    pub(crate) fn from_raw(v: c_int) -> Self {
+        match v {
+            0 => MediaType::Video,
+            1 => MediaType::Audio,
+            2 => MediaType::Subtitle,
+            3 => MediaType::Data,
+            4 => MediaType::Attachment,
+            _ => MediaType::Unknown,
+        }
+    }
+
+    /// Get the internal raw representation.
+    pub(crate) fn into_raw(self) -> c_int {
+        match self {
+            MediaType::Video => 0,
+            MediaType::Audio => 1,
+            MediaType::Subtitle => 2,
+            MediaType::Data => 3,
+            MediaType::Attachment => 4,
+            MediaType::Unknown => -1,
+        }
+    }
+}
+
+/// Decoder error recognition flag (see the `err_detect` codec option).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ErrorRecognition {
+    /// Verify embedded CRCs.
+    CrcCheck,
+    /// Detect bitstream specification deviations.
+    Bitstream,
+    /// Detect improper bitstream length.
+    Buffer,
+    /// Abort decoding on minor error detection.
+    Explode,
+    /// Ignore decoding errors and continue decoding.
+    IgnoreErrors,
+    /// Consider things that violate the spec and have not been seen in the
+    /// wild as errors.
+    Careful,
+    /// Consider all spec non compliances as errors.
+    Compliant,
+    /// Consider things that a sane encoder should not do as errors.
+    Aggressive,
+}
+
+impl ErrorRecognition {
+    /// Get name of the flag.
+    fn name(self) -> &'static str {
+        match self {
+            Self::CrcCheck => "crccheck",
+            Self::Bitstream => "bitstream",
+            Self::Buffer => "buffer",
+            Self::Explode => "explode",
+            Self::IgnoreErrors => "ignore_err",
+            Self::Careful => "careful",
+            Self::Compliant => "compliant",
+            Self::Aggressive => "aggressive",
+        }
+    }
+
+    /// Get the `err_detect` option value for given flags.
+    pub(crate) fn option_value(flags: &[Self]) -> String {
+        if flags.is_empty() {
+            return String::from("0");
+        }
+
+        flags
+            .iter()
+            .map(|flag| flag.name())
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+}
+
+/// Codec descriptor. It describes static properties of a codec (e.g.
+/// whether the codec is lossless) regardless of the encoder/decoder
+/// availability.
+#[derive(Copy, Clone)]
+pub struct CodecDescriptor {
+    ptr: *const c_void,
+}
+
+impl CodecDescriptor {
+    /// Create a codec descriptor from its raw representation.
+    unsafe fn from_raw_ptr(ptr: *const c_void) -> Option<Self> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(Self { ptr })
+        }
+    }
+
+    /// Find a codec descriptor by a given codec name (e.g. "ffv1").
+    pub fn find_by_name(name: &str) -> Option<Self> {
+        let name = CString::new(name).expect("invalid codec name");
+
+        unsafe { Self::from_raw_ptr(ffw_codec_descriptor_find_by_name(name.as_ptr() as _)) }
+    }
+
+    /// Get name of the codec.
+    pub fn name(&self) -> &'static str {
+        unsafe {
+            let ptr = ffw_codec_descriptor_get_name(self.ptr);
+
+            let name = CStr::from_ptr(ptr as _);
+
+            name.to_str().unwrap()
+        }
+    }
+
+    /// Get descriptive name of the codec.
+    pub fn long_name(&self) -> Option<&'static str> {
+        unsafe {
+            let ptr = ffw_codec_descriptor_get_long_name(self.ptr);
+
+            if ptr.is_null() {
+                None
+            } else {
+                let name = CStr::from_ptr(ptr as _);
+
+                Some(name.to_str().unwrap())
+            }
+        }
+    }
+
+    /// Get media type of the codec.
+    pub fn media_type(&self) -> MediaType {
+        let media_type = unsafe { ffw_codec_descriptor_get_media_type(self.ptr) };
+
+        MediaType::from_raw(media_type)
+    }
+
+    /// Check if the codec uses only intra compression (i.e. every frame is
+    /// a keyframe).
+    pub fn is_intra_only(&self) -> bool {
+        unsafe { ffw_codec_descriptor_is_intra_only(self.ptr) != 0 }
+    }
+
+    /// Check if the codec supports lossy compression. Note that a codec may
+    /// support both lossy and lossless compression.
+    pub fn is_lossy(&self) -> bool {
+        unsafe { ffw_codec_descriptor_is_lossy(self.ptr) != 0 }
+    }
+
+    /// Check if the codec supports lossless compression. Note that a codec
+    /// may support both lossy and lossless compression.
+    pub fn is_lossless(&self) -> bool {
+        unsafe { ffw_codec_descriptor_is_lossless(self.ptr) != 0 }
+    }
+
+    /// Check if the codec supports frame reordering (i.e. the coded order
+    /// may differ from the presentation order, e.g. due to B-frames).
+    pub fn has_reorder(&self) -> bool {
+        unsafe { ffw_codec_descriptor_has_reorder(self.ptr) != 0 }
+    }
+
+    /// Get MIME types associated with the codec.
+    pub fn mime_types(&self) -> Vec<&'static str> {
+        let mut res = Vec::new();
+
+        loop {
+            let ptr = unsafe { ffw_codec_descriptor_get_mime_type(self.ptr, res.len()) };
+
+            if ptr.is_null() {
+                break;
+            }
+
+            let mime_type = unsafe { CStr::from_ptr(ptr as _) };
+
+            res.push(mime_type.to_str().unwrap());
+        }
+
+        res
+    }
+}
+
+impl Debug for CodecDescriptor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("CodecDescriptor")
+            .field("name", &self.name())
+            .field("media_type", &self.media_type())
+            .field("intra_only", &self.is_intra_only())
+            .field("lossy", &self.is_lossy())
+            .field("lossless", &self.is_lossless())
+            .field("reorder", &self.has_reorder())
+            .finish()
+    }
+}
+
+unsafe impl Send for CodecDescriptor {}
+unsafe impl Sync for CodecDescriptor {}
+
+/// Owned summary of codec parameters.
+///
+/// Unlike `CodecParameters`, the summary does not reference any FFmpeg
+/// objects, so it can be stored (or serialized if the `serde` feature is
+/// enabled) and inspected later. Fields that are not relevant for the media
+/// type or that are unknown are set to `None`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CodecParametersSummary {
+    media_type: MediaType,
+    codec_name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    codec_long_name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    profile: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    level: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    bit_rate: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    width: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    height: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pixel_format: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    sample_aspect_ratio: Option<Rational>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    sample_format: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    sample_rate: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    channels: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    channel_layout: Option<String>,
+}
+
+impl CodecParametersSummary {
+    /// Create a new summary of given codec parameters.
+    fn new(params: &CodecParameters) -> Self {
+        let mut res = Self {
+            media_type: params.media_type(),
+            codec_name: params.codec_name().to_string(),
+            codec_long_name: params.codec_long_name().map(String::from),
+            profile: params.profile_name().map(String::from),
+            level: params.level(),
+            bit_rate: None,
+            width: None,
+            height: None,
+            pixel_format: None,
+            sample_aspect_ratio: None,
+            sample_format: None,
+            sample_rate: None,
+            channels: None,
+            channel_layout: None,
+        };
+
+        if let Some(params) = params.as_video_codec_parameters() {
+            let format = params.pixel_format();
+
+            res.bit_rate = Some(params.bit_rate()).filter(|bit_rate| *bit_rate > 0);
+            res.width = Some(params.width());
+            res.height = Some(params.height());
+            res.sample_aspect_ratio = params.sample_aspect_ratio();
+
+            if format.into_raw() >= 0 {
+                res.pixel_format = Some(format.name().to_string());
+            }
+        } else if let Some(params) = params.as_audio_codec_parameters() {
+            let format = params.sample_format();
+
+            res.bit_rate = Some(params.bit_rate()).filter(|bit_rate| *bit_rate > 0);
+            res.sample_rate = Some(params.sample_rate());
+            res.channels = Some(params.channels());
+            res.channel_layout = Some(params.channel_layout().to_string());
+
+            if format.into_raw() >= 0 {
+                res.sample_format = Some(format.name().to_string());
+            }
+        }
+
+        res
+    }
+
+    /// Get media type of the codec.
+    pub fn media_type(&self) -> MediaType {
+        self.media_type
+    }
+
+    /// Get name of the codec (e.g. "h264").
+    pub fn codec_name(&self) -> &str {
+        &self.codec_name
+    }
+
+    /// Get descriptive name of the codec.
+    pub fn codec_long_name(&self) -> Option<&str> {
+        self.codec_long_name.as_deref()
+    }
+
+    /// Get name of the codec profile.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Get codec level.
+    pub fn level(&self) -> Option<i32> {
+        self.level
+    }
+
+    /// Get bit rate (audio and video only).
+    pub fn bit_rate(&self) -> Option<u64> {
+        self.bit_rate
+    }
+
+    /// Get picture width (video only).
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+
+    /// Get picture height (video only).
+    pub fn height(&self) -> Option<usize> {
+        self.height
+    }
+
+    /// Get name of the pixel format (video only).
+    pub fn pixel_format(&self) -> Option<&str> {
+        self.pixel_format.as_deref()
+    }
+
+    /// Get sample aspect ratio (video only).
+    pub fn sample_aspect_ratio(&self) -> Option<Rational> {
+        self.sample_aspect_ratio
+    }
+
+    /// Get name of the sample format (audio only).
+    pub fn sample_format(&self) -> Option<&str> {
+        self.sample_format.as_deref()
+    }
+
+    /// Get sampling rate (audio only).
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+    }
+
+    /// Get the number of channels (audio only).
+    pub fn channels(&self) -> Option<u32> {
+        self.channels
+    }
+
+    /// Get name of the channel layout (audio only).
+    pub fn channel_layout(&self) -> Option<&str> {
+        self.channel_layout.as_deref()
+    }
+}
+
+impl Display for CodecParameters {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match &self.inner {
+            CodecParametersVariant::Audio(params) => Display::fmt(params, f),
+            CodecParametersVariant::Video(params) => Display::fmt(params, f),
+            CodecParametersVariant::Subtitle(params) => Display::fmt(params, f),
+            CodecParametersVariant::Other(params) => Display::fmt(params, f),
+        }
+    }
 }
 
 impl From<AudioCodecParameters> for CodecParameters {
@@ -483,6 +1101,26 @@ impl AudioCodecParametersBuilder {
         self
     }
 
+    /// Set number of priming samples (per channel) inserted by the encoder
+    /// at the beginning of the stream.
+    pub fn initial_padding(self, samples: u32) -> Self {
+        unsafe {
+            ffw_codec_parameters_set_initial_padding(self.inner.ptr, samples.try_into().unwrap());
+        }
+
+        self
+    }
+
+    /// Set number of padding samples (per channel) appended by the encoder
+    /// at the end of the stream.
+    pub fn trailing_padding(self, samples: u32) -> Self {
+        unsafe {
+            ffw_codec_parameters_set_trailing_padding(self.inner.ptr, samples.try_into().unwrap());
+        }
+
+        self
+    }
+
     /// Set extradata.
     pub fn extradata<T>(self, data: Option<T>) -> Self
     where
@@ -541,6 +1179,16 @@ impl AudioCodecParameters {
         self.inner.ptr
     }
 
+    /// Get name of the codec.
+    pub fn codec_name(&self) -> &'static str {
+        self.inner.codec_name()
+    }
+
+    /// Get the codec descriptor or None if the codec is unknown.
+    pub fn descriptor(&self) -> Option<CodecDescriptor> {
+        self.inner.descriptor()
+    }
+
     /// Get name of the decoder that is able to decode this codec or None
     /// if the decoder is not available.
     pub fn decoder_name(&self) -> Option<&'static str> {
@@ -553,6 +1201,25 @@ impl AudioCodecParameters {
         self.inner.encoder_name()
     }
 
+    /// Get codec profile or None if the profile is unknown. The meaning of
+    /// the value is codec specific.
+    pub fn profile(&self) -> Option<i32> {
+        self.inner.profile()
+    }
+
+    /// Get human-readable name of the codec profile (e.g. "High" or
+    /// "Main 10") or None if the profile is unknown.
+    pub fn profile_name(&self) -> Option<&'static str> {
+        self.inner.profile_name()
+    }
+
+    /// Get codec level or None if the level is unknown. The meaning of the
+    /// value is codec specific (e.g. 41 means level 4.1 for H.264 while it is
+    /// 123 for H.265).
+    pub fn level(&self) -> Option<i32> {
+        self.inner.level()
+    }
+
     /// Get bit rate.
     pub fn bit_rate(&self) -> u64 {
         unsafe { ffw_codec_parameters_get_bit_rate(self.inner.ptr) as _ }
@@ -573,6 +1240,67 @@ impl AudioCodecParameters {
         unsafe { ChannelLayout::from_raw(ffw_codec_parameters_get_channel_layout(self.inner.ptr)) }
     }
 
+    /// Get number of channels. The value may be known even if the channel
+    /// layout is not.
+    pub fn channels(&self) -> u32 {
+        unsafe { ffw_codec_parameters_get_channels(self.inner.ptr) as _ }
+    }
+
+    /// Get number of samples per channel in an audio frame or `None` if the
+    /// frame size is unknown or variable.
+    pub fn frame_size(&self) -> Option<usize> {
+        let res = unsafe { ffw_codec_parameters_get_frame_size(self.inner.ptr) };
+
+        if res > 0 {
+            Some(res as _)
+        } else {
+            None
+        }
+    }
+
+    /// Get number of bits per sample in the coded bitstream or `None` if the
+    /// value is unknown.
+    pub fn bits_per_coded_sample(&self) -> Option<u32> {
+        let res = unsafe { ffw_codec_parameters_get_bits_per_coded_sample(self.inner.ptr) };
+
+        if res > 0 {
+            Some(res as _)
+        } else {
+            None
+        }
+    }
+
+    /// Get number of valid bits per output sample or `None` if the value is
+    /// unknown.
+    pub fn bits_per_raw_sample(&self) -> Option<u32> {
+        let res = unsafe { ffw_codec_parameters_get_bits_per_raw_sample(self.inner.ptr) };
+
+        if res > 0 {
+            Some(res as _)
+        } else {
+            None
+        }
+    }
+
+    /// Get number of priming samples (per channel) inserted by the encoder
+    /// at the beginning of the stream (e.g. the encoder delay of AAC or the
+    /// Opus pre-skip). The samples are not part of the original audio and
+    /// should be dropped after decoding. Zero is returned if the value is
+    /// unknown.
+    ///
+    /// The duration of the original audio is the total number of samples in
+    /// the stream minus the initial and trailing padding.
+    pub fn initial_padding(&self) -> u32 {
+        unsafe { ffw_codec_parameters_get_initial_padding(self.inner.ptr).max(0) as _ }
+    }
+
+    /// Get number of padding samples (per channel) appended by the encoder
+    /// at the end of the stream to fill the last frame. Zero is returned if
+    /// the value is unknown.
+    pub fn trailing_padding(&self) -> u32 {
+        unsafe { ffw_codec_parameters_get_trailing_padding(self.inner.ptr).max(0) as _ }
+    }
+
     /// Get extradata.
     pub fn extradata(&self) -> Option<&[u8]> {
         unsafe {
@@ -600,6 +1328,36 @@ impl From<InnerCodecParameters> for AudioCodecParameters {
     }
 }
 
+impl Display for AudioCodecParameters {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Audio: {}", self.codec_name())?;
+
+        if let Some(profile) = self.profile_name() {
+            write!(f, " ({})", profile)?;
+        }
+
+        if self.sample_rate() > 0 {
+            write!(f, ", {} Hz", self.sample_rate())?;
+        }
+
+        if self.channels() > 0 {
+            write!(f, ", {}", self.channel_layout())?;
+        }
+
+        let sample_format = self.sample_format();
+
+        if sample_format.into_raw() >= 0 {
+            write!(f, ", {}", sample_format.name())?;
+        }
+
+        if self.bit_rate() > 0 {
+            write!(f, ", {} kb/s", self.bit_rate() / 1000)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Builder for video codec parameters.
 pub struct VideoCodecParametersBuilder {
     inner: InnerCodecParameters,
@@ -659,6 +1417,24 @@ impl VideoCodecParametersBuilder {
         self
     }
 
+    /// Set sample (pixel) aspect ratio.
+    pub fn sample_aspect_ratio(self, ratio: Rational) -> Self {
+        unsafe {
+            ffw_codec_parameters_set_sample_aspect_ratio(self.inner.ptr, ratio.num(), ratio.den());
+        }
+
+        self
+    }
+
+    /// Set field order.
+    pub fn field_order(self, field_order: FieldOrder) -> Self {
+        unsafe {
+            ffw_codec_parameters_set_field_order(self.inner.ptr, field_order.into_raw());
+        }
+
+        self
+    }
+
     /// Set extradata.
     pub fn extradata<T>(self, data: Option<T>) -> Self
     where
@@ -717,6 +1493,16 @@ impl VideoCodecParameters {
         self.inner.ptr
     }
 
+    /// Get name of the codec.
+    pub fn codec_name(&self) -> &'static str {
+        self.inner.codec_name()
+    }
+
+    /// Get the codec descriptor or None if the codec is unknown.
+    pub fn descriptor(&self) -> Option<CodecDescriptor> {
+        self.inner.descriptor()
+    }
+
     /// Get name of the decoder that is able to decode this codec or None
     /// if the decoder is not available.
     pub fn decoder_name(&self) -> Option<&'static str> {
@@ -729,6 +1515,25 @@ impl VideoCodecParameters {
         self.inner.encoder_name()
     }
 
+    /// Get codec profile or None if the profile is unknown. The meaning of
+    /// the value is codec specific.
+    pub fn profile(&self) -> Option<i32> {
+        self.inner.profile()
+    }
+
+    /// Get human-readable name of the codec profile (e.g. "High" or
+    /// "Main 10") or None if the profile is unknown.
+    pub fn profile_name(&self) -> Option<&'static str> {
+        self.inner.profile_name()
+    }
+
+    /// Get codec level or None if the level is unknown. The meaning of the
+    /// value is codec specific (e.g. 41 means level 4.1 for H.264 while it is
+    /// 123 for H.265).
+    pub fn level(&self) -> Option<i32> {
+        self.inner.level()
+    }
+
     /// Get bit rate.
     pub fn bit_rate(&self) -> u64 {
         unsafe { ffw_codec_parameters_get_bit_rate(self.inner.ptr) as _ }
@@ -749,6 +1554,35 @@ impl VideoCodecParameters {
         unsafe { ffw_codec_parameters_get_height(self.inner.ptr) as _ }
     }
 
+    /// Get sample (pixel) aspect ratio or `None` if it is unknown.
+    pub fn sample_aspect_ratio(&self) -> Option<Rational> {
+        let mut num = 0;
+        let mut den = 0;
+
+        unsafe {
+            ffw_codec_parameters_get_sample_aspect_ratio(self.inner.ptr, &mut num, &mut den);
+        }
+
+        Rational::from_raw(num, den)
+    }
+
+    /// Get field order.
+    pub fn field_order(&self) -> FieldOrder {
+        unsafe { FieldOrder::from_raw(ffw_codec_parameters_get_field_order(self.inner.ptr)) }
+    }
+
+    /// Get number of valid bits per output sample or `None` if the value is
+    /// unknown.
+    pub fn bits_per_raw_sample(&self) -> Option<u32> {
+        let res = unsafe { ffw_codec_parameters_get_bits_per_raw_sample(self.inner.ptr) };
+
+        if res > 0 {
+            Some(res as _)
+        } else {
+            None
+        }
+    }
+
     /// Get extradata.
     pub fn extradata(&self) -> Option<&[u8]> {
         unsafe {
@@ -776,6 +1610,36 @@ impl From<InnerCodecParameters> for VideoCodecParameters {
     }
 }
 
+impl Display for VideoCodecParameters {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Video: {}", self.codec_name())?;
+
+        if let Some(profile) = self.profile_name() {
+            write!(f, " ({})", profile)?;
+        }
+
+        let pixel_format = self.pixel_format();
+
+        if pixel_format.into_raw() >= 0 {
+            write!(f, ", {}", pixel_format.name())?;
+        }
+
+        if self.width() > 0 && self.height() > 0 {
+            write!(f, ", {}x{}", self.width(), self.height())?;
+        }
+
+        if let Some(sar) = self.sample_aspect_ratio() {
+            write!(f, " [SAR {}:{}]", sar.num(), sar.den())?;
+        }
+
+        if self.bit_rate() > 0 {
+            write!(f, ", {} kb/s", self.bit_rate() / 1000)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Subtitle codec parameters.
 #[derive(Clone)]
 pub struct SubtitleCodecParameters {
@@ -798,6 +1662,16 @@ impl SubtitleCodecParameters {
         Ok(res)
     }
 
+    /// Get name of the codec.
+    pub fn codec_name(&self) -> &'static str {
+        self.inner.codec_name()
+    }
+
+    /// Get the codec descriptor or None if the codec is unknown.
+    pub fn descriptor(&self) -> Option<CodecDescriptor> {
+        self.inner.descriptor()
+    }
+
     /// Get name of the decoder that is able to decode this codec or None
     /// if the decoder is not available.
     pub fn decoder_name(&self) -> Option<&'static str> {
@@ -823,6 +1697,12 @@ impl From<InnerCodecParameters> for SubtitleCodecParameters {
     }
 }
 
+impl Display for SubtitleCodecParameters {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Subtitle: {}", self.codec_name())
+    }
+}
+
 /// Other codec parameters.
 #[derive(Clone)]
 struct OtherCodecParameters {
@@ -841,6 +1721,20 @@ impl From<InnerCodecParameters> for OtherCodecParameters {
     }
 }
 
+impl Display for OtherCodecParameters {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        let kind = if self.inner.is_data_codec() {
+            "Data"
+        } else if self.inner.is_attachment_codec() {
+            "Attachment"
+        } else {
+            "Unknown"
+        };
+
+        write!(f, "{}: {}", kind, self.inner.codec_name())
+    }
+}
+
 /// A media decoder.
 ///
 /// # Common decoder operation
@@ -882,6 +1776,211 @@ pub trait Decoder {
 
     /// Take the next frame from the decoder.
     fn take(&mut self) -> Result<Option<Self::Frame>, Error>;
+
+    /// Move the decoder into a background thread. Packets sent using the
+    /// returned sender will be decoded and the decoded frames will be
+    /// available in the returned receiver. Closing the sender flushes the
+    /// decoder. Both channels are bounded by a given capacity.
+    fn into_channel(
+        mut self,
+        capacity: usize,
+    ) -> (ChannelSender<Packet>, ChannelReceiver<Self::Frame>)
+    where
+        Self: Sized + Send + 'static,
+        Self::Frame: Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel::<Packet>(capacity);
+
+        let receiver = crate::channel::spawn(capacity, move |output| {
+            for packet in rx {
+                self.push(packet)?;
+
+                while let Some(frame) = self.take()? {
+                    if !output.send(frame) {
+                        return Ok(());
+                    }
+                }
+            }
+
+            self.flush()?;
+
+            while let Some(frame) = self.take()? {
+                if !output.send(frame) {
+                    return Ok(());
+                }
+            }
+
+            Ok(())
+        });
+
+        (ChannelSender::new(tx), receiver)
+    }
+}
+
+/// Builder for the generic decoder.
+pub struct MediaDecoderBuilder {
+    inner: MediaDecoderBuilderVariant,
+}
+
+/// Builder variants.
+enum MediaDecoderBuilderVariant {
+    Audio(AudioDecoderBuilder),
+    Video(VideoDecoderBuilder),
+}
+
+impl MediaDecoderBuilder {
+    /// Create a new builder from given codec parameters.
+    fn from_codec_parameters(codec_parameters: &CodecParameters) -> Result<Self, Error> {
+        let inner = if let Some(params) = codec_parameters.as_audio_codec_parameters() {
+            MediaDecoderBuilderVariant::Audio(AudioDecoder::from_codec_parameters(params)?)
+        } else if let Some(params) = codec_parameters.as_video_codec_parameters() {
+            MediaDecoderBuilderVariant::Video(VideoDecoder::from_codec_parameters(params)?)
+        } else {
+            return Err(Error::new("only audio and video streams can be decoded"));
+        };
+
+        Ok(Self { inner })
+    }
+
+    /// Set a decoder option.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.inner = match self.inner {
+            MediaDecoderBuilderVariant::Audio(builder) => {
+                MediaDecoderBuilderVariant::Audio(builder.set_option(name, value))
+            }
+            MediaDecoderBuilderVariant::Video(builder) => {
+                MediaDecoderBuilderVariant::Video(builder.set_option(name, value))
+            }
+        };
+
+        self
+    }
+
+    /// Set packet time base.
+    pub fn time_base(mut self, time_base: TimeBase) -> Self {
+        self.inner = match self.inner {
+            MediaDecoderBuilderVariant::Audio(builder) => {
+                MediaDecoderBuilderVariant::Audio(builder.time_base(time_base))
+            }
+            MediaDecoderBuilderVariant::Video(builder) => {
+                MediaDecoderBuilderVariant::Video(builder.time_base(time_base))
+            }
+        };
+
+        self
+    }
+
+    /// Configure the decoder for low latency. This has an effect only on
+    /// video decoders (see `VideoDecoderBuilder::low_latency()`).
+    pub fn low_latency(mut self) -> Self {
+        if let MediaDecoderBuilderVariant::Video(builder) = self.inner {
+            self.inner = MediaDecoderBuilderVariant::Video(builder.low_latency());
+        }
+
+        self
+    }
+
+    /// Build the decoder.
+    pub fn build(self) -> Result<MediaDecoder, Error> {
+        match self.inner {
+            MediaDecoderBuilderVariant::Audio(builder) => builder.build().map(MediaDecoder::Audio),
+            MediaDecoderBuilderVariant::Video(builder) => builder.build().map(MediaDecoder::Video),
+        }
+    }
+}
+
+/// Decoder of any media type producing generic frames.
+pub enum MediaDecoder {
+    Audio(AudioDecoder),
+    Video(VideoDecoder),
+}
+
+impl MediaDecoder {
+    /// Create a new decoder builder from given codec parameters. The
+    /// decoder type is selected according to the media type. Only audio and
+    /// video codecs are supported.
+    pub fn from_codec_parameters(
+        codec_parameters: &CodecParameters,
+    ) -> Result<MediaDecoderBuilder, Error> {
+        MediaDecoderBuilder::from_codec_parameters(codec_parameters)
+    }
+
+    /// Create a new decoder builder for a given stream. The decoder type is
+    /// selected according to the media type of the stream and the decoder
+    /// is initialized from the stream codec parameters (including
+    /// extradata) and time base. Only audio and video streams are
+    /// supported.
+    pub fn from_stream(stream: &Stream) -> Result<MediaDecoderBuilder, Error> {
+        let builder = MediaDecoderBuilder::from_codec_parameters(&stream.codec_parameters())?
+            .time_base(stream.time_base());
+
+        Ok(builder)
+    }
+
+    /// Get media type of the decoder.
+    pub fn media_type(&self) -> MediaType {
+        match self {
+            Self::Audio(_) => MediaType::Audio,
+            Self::Video(_) => MediaType::Video,
+        }
+    }
+
+    /// Reset the internal decoder state and drop all buffered frames. This
+    /// should be used after seeking in the input.
+    pub fn reset(&mut self) {
+        match self {
+            Self::Audio(decoder) => decoder.reset(),
+            Self::Video(decoder) => decoder.reset(),
+        }
+    }
+}
+
+impl Decoder for MediaDecoder {
+    type CodecParameters = CodecParameters;
+    type Frame = Frame;
+
+    fn codec_parameters(&self) -> Self::CodecParameters {
+        match self {
+            Self::Audio(decoder) => decoder.codec_parameters().into(),
+            Self::Video(decoder) => decoder.codec_parameters().into(),
+        }
+    }
+
+    fn try_push(&mut self, packet: Packet) -> Result<(), CodecError> {
+        match self {
+            Self::Audio(decoder) => decoder.try_push(packet),
+            Self::Video(decoder) => decoder.try_push(packet),
+        }
+    }
+
+    fn try_flush(&mut self) -> Result<(), CodecError> {
+        match self {
+            Self::Audio(decoder) => decoder.try_flush(),
+            Self::Video(decoder) => decoder.try_flush(),
+        }
+    }
+
+    fn take(&mut self) -> Result<Option<Self::Frame>, Error> {
+        match self {
+            Self::Audio(decoder) => decoder.take().map(|frame| frame.map(Frame::Audio)),
+            Self::Video(decoder) => decoder.take().map(|frame| frame.map(Frame::Video)),
+        }
+    }
+}
+
+impl From<AudioDecoder> for MediaDecoder {
+    fn from(decoder: AudioDecoder) -> Self {
+        Self::Audio(decoder)
+    }
+}
+
+impl From<VideoDecoder> for MediaDecoder {
+    fn from(decoder: VideoDecoder) -> Self {
+        Self::Video(decoder)
+    }
 }
 
 /// A media encoder.
@@ -897,6 +1996,13 @@ pub trait Encoder {
     type Frame;
 
     /// Get codec parameters.
+    ///
+    /// The parameters can be used for creating output streams of a muxer.
+    /// They also contain the codec extradata generated when the encoder was
+    /// opened. Note that most encoders generate the extradata only if the
+    /// global header flag is set (see the `global_header()` method of the
+    /// encoder builders). This is required by containers like MP4 or
+    /// Matroska.
     fn codec_parameters(&self) -> Self::CodecParameters;
 
     /// Push a given frame to the encoder.
@@ -926,3 +2032,71 @@ pub trait Encoder {
     /// Take the next packet from the encoder.
     fn take(&mut self) -> Result<Option<Packet>, Error>;
 }
+
+/// A media filter.
+///
+/// # Common filter operation
+/// 1. Push a frame to the filter.
+/// 2. Take all frames from the filter until you get None.
+/// 3. If there are more frames to be filtered, continue with 1.
+/// 4. Flush the filter.
+/// 5. Take all frames from the filter until you get None.
+pub trait Filter {
+    type Frame;
+
+    /// Push a given frame to the filter.
+    ///
+    /// # Panics
+    /// The method panics if the operation is not expected (i.e. another
+    /// operation needs to be done).
+    fn push(&mut self, frame: Self::Frame) -> Result<(), Error> {
+        self.try_push(frame).map_err(|err| err.unwrap_inner())
+    }
+
+    /// Push a given frame to the filter.
+    fn try_push(&mut self, frame: Self::Frame) -> Result<(), CodecError>;
+
+    /// Flush the filter.
+    ///
+    /// # Panics
+    /// The method panics if the operation is not expected (i.e. another
+    /// operation needs to be done).
+    fn flush(&mut self) -> Result<(), Error> {
+        self.try_flush().map_err(|err| err.unwrap_inner())
+    }
+
+    /// Flush the filter.
+    fn try_flush(&mut self) -> Result<(), CodecError>;
+
+    /// Take the next frame from the filter.
+    fn take(&mut self) -> Result<Option<Self::Frame>, Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodecDescriptor, MediaType, VideoCodecParameters};
+
+    #[test]
+    fn test_codec_descriptor() {
+        let params = VideoCodecParameters::builder("rawvideo").unwrap().build();
+
+        let descriptor = params.descriptor().unwrap();
+
+        assert_eq!(descriptor.name(), "rawvideo");
+        assert_eq!(descriptor.media_type(), MediaType::Video);
+        assert!(descriptor.is_intra_only());
+        assert!(descriptor.is_lossless());
+
+        let descriptor = CodecDescriptor::find_by_name("h264").unwrap();
+
+        assert!(descriptor.is_lossy());
+        assert!(descriptor.has_reorder());
+        assert!(!descriptor.is_intra_only());
+
+        let descriptor = CodecDescriptor::find_by_name("png").unwrap();
+
+        assert!(descriptor.mime_types().contains(&"image/png"));
+
+        assert!(CodecDescriptor::find_by_name("foo").is_none());
+    }
+}
@@ -0,0 +1,161 @@
+//! Decoding of video with a side-band alpha channel.
+
+use std::collections::VecDeque;
+
+use crate::{
+    codec::{
+        video::{VideoDecoder, VideoFrame},
+        CodecError, Decoder, VideoCodecParameters,
+    },
+    format::stream::Stream,
+    packet::{Packet, PacketMut},
+    Error,
+};
+
+/// Decoder for VP8/VP9/AV1 video with an alpha channel stored as a separate
+/// bitstream in the Matroska BlockAdditional data (see
+/// `Packet::alpha_data()`), e.g. WebM files with transparency produced by
+/// design tools.
+///
+/// The color and the alpha bitstreams are decoded by two separate decoders
+/// of the same codec and the alpha channel is merged into the decoded
+/// frames (i.e. `yuv420p` frames become `yuva420p` frames, see
+/// `VideoFrame::with_alpha_plane()`). Frames decoded from packets without
+/// alpha data are fully opaque. Frames that already have an alpha plane
+/// (e.g. produced by the libvpx decoder) are passed through unchanged.
+pub struct AlphaVideoDecoder {
+    decoder: VideoDecoder,
+    alpha_decoder: VideoDecoder,
+    frames: VecDeque<VideoFrame>,
+    alpha_frames: VecDeque<VideoFrame>,
+}
+
+impl AlphaVideoDecoder {
+    /// Create a new decoder from a given pair of decoders. Both decoders
+    /// must be of the same codec and they should be configured the same way
+    /// (e.g. the same threading), so that they output frames with the same
+    /// delay.
+    pub fn new(decoder: VideoDecoder, alpha_decoder: VideoDecoder) -> Self {
+        Self {
+            decoder,
+            alpha_decoder,
+            frames: VecDeque::new(),
+            alpha_frames: VecDeque::new(),
+        }
+    }
+
+    /// Create a new decoder for a given stream.
+    ///
+    /// # Panics
+    /// The method panics if the stream is not a video stream.
+    pub fn from_stream(stream: &Stream) -> Result<Self, Error> {
+        let decoder = VideoDecoder::from_stream(stream)?.build()?;
+
+        // the alpha bitstream carries its own headers
+        let alpha_decoder = VideoDecoder::from_stream(stream)?
+            .extradata(None::<&[u8]>)
+            .build()?;
+
+        Ok(Self::new(decoder, alpha_decoder))
+    }
+
+    /// Reset the internal decoder state and drop all buffered frames. This
+    /// should be used after seeking in the input.
+    pub fn reset(&mut self) {
+        self.decoder.reset();
+        self.alpha_decoder.reset();
+        self.frames.clear();
+        self.alpha_frames.clear();
+    }
+
+    /// Move all frames available in the decoders into the internal queues.
+    fn drain(&mut self) -> Result<(), Error> {
+        while let Some(frame) = self.decoder.take()? {
+            self.frames.push_back(frame);
+        }
+
+        while let Some(frame) = self.alpha_decoder.take()? {
+            self.alpha_frames.push_back(frame);
+        }
+
+        Ok(())
+    }
+
+    /// Take the alpha frame matching a given frame (if any). Alpha frames
+    /// preceding the frame are dropped.
+    fn take_alpha_frame(&mut self, frame: &VideoFrame) -> Option<VideoFrame> {
+        let pts = frame.pts();
+
+        while let Some(alpha) = self.alpha_frames.front() {
+            let alpha_pts = alpha.pts();
+
+            if alpha_pts.is_null() || pts.is_null() || alpha_pts >= pts {
+                break;
+            }
+
+            self.alpha_frames.pop_front();
+        }
+
+        if self.alpha_frames.front()?.pts() == pts {
+            self.alpha_frames.pop_front()
+        } else {
+            None
+        }
+    }
+}
+
+impl Decoder for AlphaVideoDecoder {
+    type CodecParameters = VideoCodecParameters;
+    type Frame = VideoFrame;
+
+    fn codec_parameters(&self) -> VideoCodecParameters {
+        self.decoder.codec_parameters()
+    }
+
+    fn try_push(&mut self, packet: Packet) -> Result<(), CodecError> {
+        self.drain()?;
+
+        let alpha_packet = packet.alpha_data().map(|data| {
+            PacketMut::from(data)
+                .with_time_base(packet.time_base())
+                .with_pts(packet.pts())
+                .with_dts(packet.dts())
+                .with_key_flag(packet.is_key())
+                .freeze()
+        });
+
+        self.decoder.try_push(packet)?;
+
+        if let Some(packet) = alpha_packet {
+            self.alpha_decoder.try_push(packet)?;
+        }
+
+        Ok(())
+    }
+
+    fn try_flush(&mut self) -> Result<(), CodecError> {
+        self.drain()?;
+
+        self.decoder.try_flush()?;
+        self.alpha_decoder.try_flush()?;
+
+        Ok(())
+    }
+
+    fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        self.drain()?;
+
+        let frame = match self.frames.pop_front() {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let alpha = self.take_alpha_frame(&frame);
+
+        if frame.pixel_format().name().starts_with("yuva") {
+            return Ok(Some(frame));
+        }
+
+        frame.with_alpha_plane(alpha.as_ref()).map(Some)
+    }
+}
@@ -0,0 +1,340 @@
+//! Video frame compositor.
+
+use crate::{
+    codec::video::{
+        frame::{PixelFormat, Plane, VideoFrame},
+        scaler::VideoFrameScaler,
+    },
+    time::Timestamp,
+    Error,
+};
+
+/// Supported packed RGB formats with an alpha channel and offsets of the
+/// alpha component.
+const PACKED_ALPHA_FORMATS: &[(&str, usize)] =
+    &[("rgba", 3), ("bgra", 3), ("argb", 0), ("abgr", 0)];
+
+/// Supported planar YUV formats with an alpha plane (e.g. decoded VP9 video
+/// with an alpha channel). Overlays in these formats are converted before
+/// blending.
+const PLANAR_ALPHA_FORMATS: &[&str] = &["yuva420p", "yuva422p", "yuva444p"];
+
+/// Get offset of the alpha component if a given pixel format is one of the
+/// supported packed formats with alpha.
+fn packed_alpha_offset(format: PixelFormat) -> Option<usize> {
+    PACKED_ALPHA_FORMATS
+        .iter()
+        .find(|(name, _)| format.name() == *name)
+        .map(|(_, offset)| *offset)
+}
+
+/// Blend a given overlay component value into a given frame component value
+/// using a given alpha (0 - 255).
+fn blend(src: u8, dst: u8, alpha: u32) -> u8 {
+    ((src as u32 * alpha + dst as u32 * (255 - alpha) + 127) / 255) as u8
+}
+
+/// Builder for the compositor.
+pub struct CompositorBuilder {
+    overlay: VideoFrame,
+    x: isize,
+    y: isize,
+    opacity: f32,
+    start: Option<Timestamp>,
+    end: Option<Timestamp>,
+}
+
+impl CompositorBuilder {
+    /// Create a new builder for a given overlay.
+    fn new(overlay: VideoFrame) -> Self {
+        Self {
+            overlay,
+            x: 0,
+            y: 0,
+            opacity: 1.0,
+            start: None,
+            end: None,
+        }
+    }
+
+    /// Set position of the top left corner of the overlay. The overlay may
+    /// be placed partially outside of the frame. The default is `(0, 0)`.
+    pub fn position(mut self, x: isize, y: isize) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Set overlay opacity (from 0.0 to 1.0). The opacity is combined with
+    /// the alpha channel of the overlay. The default is 1.0.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Composite the overlay only onto frames with presentation timestamps
+    /// within a given window (the end is exclusive). Frames without a
+    /// timestamp are passed through unchanged if the window is set. The
+    /// overlay is composited onto all frames by default.
+    pub fn time_window(mut self, start: Timestamp, end: Timestamp) -> Self {
+        self.start = Some(start);
+        self.end = Some(end);
+        self
+    }
+
+    /// Build the compositor.
+    pub fn build(self) -> Result<Compositor, Error> {
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(Error::new("opacity must be between 0.0 and 1.0"));
+        }
+
+        let mut res = Compositor {
+            overlay: self.overlay.clone(),
+            converted: None,
+            x: self.x,
+            y: self.y,
+            opacity: (self.opacity * 255.0).round() as u32,
+            start: self.start,
+            end: self.end,
+        };
+
+        res.set_overlay(self.overlay)?;
+
+        Ok(res)
+    }
+}
+
+/// Compositor overlaying a picture with an alpha channel (e.g. a watermark
+/// or a logo) onto video frames.
+///
+/// The overlay must be a packed RGB frame with alpha (i.e. `rgba`, `bgra`,
+/// `argb` or `abgr`) or a planar YUV frame with alpha (i.e. `yuva420p`,
+/// `yuva422p` or `yuva444p`, see `AlphaVideoDecoder`). The frames must be
+/// in one of the packed formats or in the `yuv420p` format. The overlay is
+/// converted into the format of the frames once and the blending itself is
+/// done natively without any filter graph. In case of `yuv420p` frames, the
+/// chroma of the overlay is placed at the nearest even position.
+pub struct Compositor {
+    overlay: VideoFrame,
+    converted: Option<VideoFrame>,
+    x: isize,
+    y: isize,
+    opacity: u32,
+    start: Option<Timestamp>,
+    end: Option<Timestamp>,
+}
+
+impl Compositor {
+    /// Get a compositor builder for a given overlay.
+    pub fn builder(overlay: VideoFrame) -> CompositorBuilder {
+        CompositorBuilder::new(overlay)
+    }
+
+    /// Replace the overlay (e.g. to animate it). The position, opacity and
+    /// time window are kept.
+    pub fn set_overlay(&mut self, overlay: VideoFrame) -> Result<(), Error> {
+        if overlay.is_hw_frame() {
+            return Err(Error::new("hardware frames cannot be used as overlays"));
+        }
+
+        let format = overlay.pixel_format();
+
+        if packed_alpha_offset(format).is_none() && !PLANAR_ALPHA_FORMATS.contains(&format.name()) {
+            return Err(Error::new(
+                "the overlay must be a frame with an alpha channel",
+            ));
+        }
+
+        self.overlay = overlay;
+        self.converted = None;
+
+        Ok(())
+    }
+
+    /// Composite the overlay onto a given frame. The picture data are
+    /// copied only if they are shared with another frame. Frames outside of
+    /// the time window are returned unchanged.
+    pub fn compose(&mut self, frame: VideoFrame) -> Result<VideoFrame, Error> {
+        if self.opacity == 0 || !self.is_active(frame.pts()) {
+            return Ok(frame);
+        } else if frame.is_hw_frame() {
+            return Err(Error::new("hardware frames are not supported"));
+        }
+
+        let format = frame.pixel_format();
+        let width = frame.width();
+        let height = frame.height();
+
+        let overlay_format = if packed_alpha_offset(format).is_some() {
+            format
+        } else if format.name() == "yuv420p" {
+            "yuva420p".parse().unwrap()
+        } else {
+            return Err(Error::new("unsupported pixel format"));
+        };
+
+        let overlay = self.converted_overlay(overlay_format)?;
+
+        let mut frame = frame.into_mut()?;
+
+        let mut planes = frame.planes_mut();
+
+        let overlay_planes = overlay.planes();
+
+        let opacity = self.opacity;
+
+        if let Some(alpha_offset) = packed_alpha_offset(format) {
+            blend_packed(
+                &mut planes[0],
+                &overlay_planes[0],
+                (width, height),
+                (overlay.width(), overlay.height()),
+                (self.x, self.y),
+                alpha_offset,
+                opacity,
+            );
+        } else {
+            let alpha = &overlay_planes[3];
+
+            for (index, plane) in planes.iter_mut().take(3).enumerate() {
+                let shift = if index == 0 { 0 } else { 1 };
+
+                blend_planar(
+                    plane,
+                    &overlay_planes[index],
+                    alpha,
+                    ((width + shift) >> shift, (height + shift) >> shift),
+                    (
+                        (overlay.width() + shift) >> shift,
+                        (overlay.height() + shift) >> shift,
+                    ),
+                    (self.x.div_euclid(1 << shift), self.y.div_euclid(1 << shift)),
+                    shift,
+                    opacity,
+                );
+            }
+        }
+
+        Ok(frame.freeze())
+    }
+
+    /// Check if the overlay should be composited onto a frame with a given
+    /// timestamp.
+    fn is_active(&self, pts: Timestamp) -> bool {
+        if self.start.is_none() && self.end.is_none() {
+            return true;
+        } else if pts.is_null() {
+            return false;
+        }
+
+        let after_start = self.start.map(|start| pts >= start).unwrap_or(true);
+        let before_end = self.end.map(|end| pts < end).unwrap_or(true);
+
+        after_start && before_end
+    }
+
+    /// Get the overlay converted into a given pixel format.
+    fn converted_overlay(&mut self, format: PixelFormat) -> Result<VideoFrame, Error> {
+        if let Some(converted) = self.converted.as_ref() {
+            if converted.pixel_format() == format {
+                return Ok(converted.clone());
+            }
+        }
+
+        let converted = if self.overlay.pixel_format() == format {
+            self.overlay.clone()
+        } else {
+            VideoFrameScaler::builder()
+                .source_pixel_format(self.overlay.pixel_format())
+                .source_width(self.overlay.width())
+                .source_height(self.overlay.height())
+                .target_pixel_format(format)
+                .target_width(self.overlay.width())
+                .target_height(self.overlay.height())
+                .build()?
+                .scale(&self.overlay)?
+        };
+
+        self.converted = Some(converted.clone());
+
+        Ok(converted)
+    }
+}
+
+/// Get the range of overlay samples (along one axis) that fall into the
+/// frame if the overlay is placed at a given offset.
+fn visible_range(offset: isize, overlay_size: usize, frame_size: usize) -> (usize, usize) {
+    let start = (-offset).max(0) as usize;
+    let end = (frame_size as isize - offset).clamp(0, overlay_size as isize) as usize;
+
+    (start, end.max(start))
+}
+
+/// Blend a packed overlay plane into a packed frame plane (4 bytes per
+/// pixel, including alpha).
+fn blend_packed(
+    dst: &mut Plane,
+    src: &Plane,
+    dst_size: (usize, usize),
+    src_size: (usize, usize),
+    offset: (isize, isize),
+    alpha_offset: usize,
+    opacity: u32,
+) {
+    let (col_start, col_end) = visible_range(offset.0, src_size.0, dst_size.0);
+    let (row_start, row_end) = visible_range(offset.1, src_size.1, dst_size.1);
+
+    for row in row_start..row_end {
+        let src_line = src.line(row).unwrap();
+        let dst_line = dst.line_mut((offset.1 + row as isize) as usize).unwrap();
+
+        for col in col_start..col_end {
+            let dst_col = (offset.0 + col as isize) as usize;
+
+            let s = &src_line[col * 4..col * 4 + 4];
+            let d = &mut dst_line[dst_col * 4..dst_col * 4 + 4];
+
+            let alpha = s[alpha_offset] as u32 * opacity / 255;
+
+            for i in 0..4 {
+                if i == alpha_offset {
+                    d[i] = (alpha + d[i] as u32 * (255 - alpha) / 255) as u8;
+                } else {
+                    d[i] = blend(s[i], d[i], alpha);
+                }
+            }
+        }
+    }
+}
+
+/// Blend a planar overlay plane into a planar frame plane using a given
+/// full-resolution alpha plane. The shift is the log2 of the plane
+/// subsampling factor.
+#[allow(clippy::too_many_arguments)]
+fn blend_planar(
+    dst: &mut Plane,
+    src: &Plane,
+    alpha: &Plane,
+    dst_size: (usize, usize),
+    src_size: (usize, usize),
+    offset: (isize, isize),
+    shift: usize,
+    opacity: u32,
+) {
+    let (col_start, col_end) = visible_range(offset.0, src_size.0, dst_size.0);
+    let (row_start, row_end) = visible_range(offset.1, src_size.1, dst_size.1);
+
+    for row in row_start..row_end {
+        let src_line = src.line(row).unwrap();
+        let alpha_line = alpha.line(row << shift).unwrap();
+        let dst_line = dst.line_mut((offset.1 + row as isize) as usize).unwrap();
+
+        for col in col_start..col_end {
+            let dst_col = (offset.0 + col as isize) as usize;
+
+            let alpha = alpha_line[col << shift] as u32 * opacity / 255;
+
+            dst_line[dst_col] = blend(src_line[col], dst_line[dst_col], alpha);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Video deinterlacer.
+
+use crate::{
+    codec::{
+        video::{filter::VideoFilter, VideoFrame},
+        CodecError, Filter, VideoCodecParameters,
+    },
+    math::Rational,
+    time::TimeBase,
+    Error,
+};
+
+/// Deinterlacing algorithm.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Algorithm {
+    /// Yet Another DeInterlacing Filter.
+    Yadif,
+    /// Bob Weaver Deinterlacing Filter. It is slower than yadif but it
+    /// usually gives better results.
+    Bwdif,
+}
+
+impl Algorithm {
+    /// Get name of the corresponding FFmpeg filter.
+    fn filter_name(self) -> &'static str {
+        match self {
+            Algorithm::Yadif => "yadif",
+            Algorithm::Bwdif => "bwdif",
+        }
+    }
+}
+
+/// Deinterlacing mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Mode {
+    /// Output one frame for each input frame.
+    Frame,
+    /// Output one frame for each field (i.e. double the frame rate).
+    Field,
+}
+
+impl Mode {
+    /// Get value of the corresponding filter option.
+    fn option_value(self) -> &'static str {
+        match self {
+            Mode::Frame => "send_frame",
+            Mode::Field => "send_field",
+        }
+    }
+}
+
+/// Builder for a video deinterlacer.
+pub struct VideoDeinterlacerBuilder {
+    codec_parameters: VideoCodecParameters,
+    time_base: TimeBase,
+    frame_rate: Option<Rational>,
+
+    algorithm: Algorithm,
+    mode: Mode,
+    interlaced_only: bool,
+}
+
+impl VideoDeinterlacerBuilder {
+    /// Create a new video deinterlacer builder.
+    fn new(codec_parameters: &VideoCodecParameters) -> Self {
+        Self {
+            codec_parameters: codec_parameters.clone(),
+            time_base: TimeBase::MICROSECONDS,
+            frame_rate: None,
+
+            algorithm: Algorithm::Yadif,
+            mode: Mode::Frame,
+            interlaced_only: true,
+        }
+    }
+
+    /// Set input time base (all input frames will be rescaled into this time
+    /// base). The default time base is in microseconds.
+    pub fn input_time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Set input frame rate. The frame rate is unknown by default.
+    pub fn input_frame_rate(mut self, frame_rate: Rational) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Set deinterlacing algorithm. The default is yadif.
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set deinterlacing mode. The default is one output frame for each
+    /// input frame.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Deinterlace only frames marked as interlaced (this is the default)
+    /// or all frames.
+    pub fn interlaced_only(mut self, interlaced_only: bool) -> Self {
+        self.interlaced_only = interlaced_only;
+        self
+    }
+
+    /// Build the deinterlacer.
+    pub fn build(self) -> Result<VideoDeinterlacer, Error> {
+        let deint = if self.interlaced_only {
+            "interlaced"
+        } else {
+            "all"
+        };
+
+        let description = format!(
+            "{}=mode={}:parity=auto:deint={}",
+            self.algorithm.filter_name(),
+            self.mode.option_value(),
+            deint
+        );
+
+        let mut builder = VideoFilter::builder(&self.codec_parameters, &description)
+            .input_time_base(self.time_base);
+
+        if let Some(frame_rate) = self.frame_rate {
+            builder = builder.input_frame_rate(frame_rate);
+        }
+
+        let res = VideoDeinterlacer {
+            filter: builder.build()?,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Video deinterlacer. It is a convenience wrapper around a video filter
+/// graph containing a single yadif or bwdif filter. Field parity is detected
+/// automatically from the input frames.
+pub struct VideoDeinterlacer {
+    filter: VideoFilter,
+}
+
+impl VideoDeinterlacer {
+    /// Create a new deinterlacer with the default settings for frames
+    /// described by given codec parameters.
+    pub fn new(codec_parameters: &VideoCodecParameters) -> Result<Self, Error> {
+        VideoDeinterlacerBuilder::new(codec_parameters).build()
+    }
+
+    /// Get a deinterlacer builder for frames described by given codec
+    /// parameters.
+    pub fn builder(codec_parameters: &VideoCodecParameters) -> VideoDeinterlacerBuilder {
+        VideoDeinterlacerBuilder::new(codec_parameters)
+    }
+
+    /// Get time base of the output frames.
+    pub fn output_time_base(&self) -> TimeBase {
+        self.filter.output_time_base()
+    }
+}
+
+impl Filter for VideoDeinterlacer {
+    type Frame = VideoFrame;
+
+    fn try_push(&mut self, frame: VideoFrame) -> Result<(), CodecError> {
+        self.filter.try_push(frame)
+    }
+
+    fn try_flush(&mut self) -> Result<(), CodecError> {
+        self.filter.try_flush()
+    }
+
+    fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        self.filter.take()
+    }
+}
@@ -0,0 +1,238 @@
+//! AV1 film grain parameters.
+
+use std::{os::raw::c_int, slice::Iter};
+
+/// Number of integers in the raw representation of the AV1 film grain
+/// parameters.
+pub(crate) const RAW_AV1_FILM_GRAIN_PARAMS_SIZE: usize = 158;
+
+/// Reader of the raw film grain parameters.
+struct RawReader<'a> {
+    values: Iter<'a, c_int>,
+}
+
+impl<'a> RawReader<'a> {
+    /// Read the next value.
+    fn next(&mut self) -> c_int {
+        *self.values.next().unwrap()
+    }
+
+    /// Read a given number of scaling points (only the first `count` points
+    /// are kept).
+    fn points(&mut self, count: c_int, capacity: usize) -> Vec<(u8, u8)> {
+        let mut res = (0..capacity)
+            .map(|_| (self.next() as u8, self.next() as u8))
+            .collect::<Vec<_>>();
+
+        res.truncate(count.max(0) as usize);
+        res
+    }
+
+    /// Read a given number of coefficients (only the first `count`
+    /// coefficients are kept).
+    fn coefficients(&mut self, count: usize, capacity: usize) -> Vec<i8> {
+        let mut res = (0..capacity).map(|_| self.next() as i8).collect::<Vec<_>>();
+
+        res.truncate(count);
+        res
+    }
+}
+
+/// AV1 film grain synthesis parameters attached to decoded frames (see the
+/// AV1 specification for the meaning of the individual fields). The
+/// parameters are available only if the decoder does not apply the film
+/// grain itself and FFmpeg supports exporting it (i.e. FFmpeg 4.4 or newer).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Av1FilmGrainParams {
+    seed: u64,
+    y_points: Vec<(u8, u8)>,
+    chroma_scaling_from_luma: bool,
+    uv_points: [Vec<(u8, u8)>; 2],
+    scaling_shift: u8,
+    ar_coeff_lag: u8,
+    ar_coeffs_y: Vec<i8>,
+    ar_coeffs_uv: [Vec<i8>; 2],
+    ar_coeff_shift: u8,
+    grain_scale_shift: u8,
+    uv_mult: [i32; 2],
+    uv_mult_luma: [i32; 2],
+    uv_offset: [i32; 2],
+    overlap: bool,
+    limit_output_range: bool,
+}
+
+impl Av1FilmGrainParams {
+    /// Create the film grain parameters from a given raw representation.
+    pub(crate) fn from_raw(seed: u64, raw: &[c_int; RAW_AV1_FILM_GRAIN_PARAMS_SIZE]) -> Self {
+        let mut reader = RawReader { values: raw.iter() };
+
+        let num_y_points = reader.next();
+        let y_points = reader.points(num_y_points, 14);
+        let chroma_scaling_from_luma = reader.next() != 0;
+
+        let num_cb_points = reader.next();
+        let cb_points = reader.points(num_cb_points, 10);
+        let num_cr_points = reader.next();
+        let cr_points = reader.points(num_cr_points, 10);
+
+        let scaling_shift = reader.next() as u8;
+        let ar_coeff_lag = reader.next() as u8;
+
+        let num_pos_luma = 2 * ar_coeff_lag as usize * (ar_coeff_lag as usize + 1);
+
+        let ar_coeffs_y = reader.coefficients(num_pos_luma, 24);
+        let ar_coeffs_cb = reader.coefficients(num_pos_luma + 1, 25);
+        let ar_coeffs_cr = reader.coefficients(num_pos_luma + 1, 25);
+
+        let ar_coeff_shift = reader.next() as u8;
+        let grain_scale_shift = reader.next() as u8;
+
+        let mut uv_mult = [0; 2];
+        let mut uv_mult_luma = [0; 2];
+        let mut uv_offset = [0; 2];
+
+        for i in 0..2 {
+            uv_mult[i] = reader.next();
+            uv_mult_luma[i] = reader.next();
+            uv_offset[i] = reader.next();
+        }
+
+        let overlap = reader.next() != 0;
+        let limit_output_range = reader.next() != 0;
+
+        Self {
+            seed,
+            y_points,
+            chroma_scaling_from_luma,
+            uv_points: [cb_points, cr_points],
+            scaling_shift,
+            ar_coeff_lag,
+            ar_coeffs_y,
+            ar_coeffs_uv: [ar_coeffs_cb, ar_coeffs_cr],
+            ar_coeff_shift,
+            grain_scale_shift,
+            uv_mult,
+            uv_mult_luma,
+            uv_offset,
+            overlap,
+            limit_output_range,
+        }
+    }
+
+    /// Get the random seed used for generating the grain.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Get the luma scaling function points (value, scaling).
+    pub fn y_points(&self) -> &[(u8, u8)] {
+        &self.y_points
+    }
+
+    /// Check if the chroma scaling is derived from the luma scaling.
+    pub fn chroma_scaling_from_luma(&self) -> bool {
+        self.chroma_scaling_from_luma
+    }
+
+    /// Get the Cb and Cr scaling function points (value, scaling).
+    pub fn uv_points(&self) -> [&[(u8, u8)]; 2] {
+        [&self.uv_points[0], &self.uv_points[1]]
+    }
+
+    /// Get the scaling shift (8 - 11).
+    pub fn scaling_shift(&self) -> u8 {
+        self.scaling_shift
+    }
+
+    /// Get the auto-regression lag (0 - 3).
+    pub fn ar_coeff_lag(&self) -> u8 {
+        self.ar_coeff_lag
+    }
+
+    /// Get the luma auto-regression coefficients.
+    pub fn ar_coeffs_y(&self) -> &[i8] {
+        &self.ar_coeffs_y
+    }
+
+    /// Get the Cb and Cr auto-regression coefficients. The last
+    /// coefficient of each plane is the luma contribution.
+    pub fn ar_coeffs_uv(&self) -> [&[i8]; 2] {
+        [&self.ar_coeffs_uv[0], &self.ar_coeffs_uv[1]]
+    }
+
+    /// Get the auto-regression coefficient shift (6 - 9).
+    pub fn ar_coeff_shift(&self) -> u8 {
+        self.ar_coeff_shift
+    }
+
+    /// Get the grain scale shift.
+    pub fn grain_scale_shift(&self) -> u8 {
+        self.grain_scale_shift
+    }
+
+    /// Get the Cb and Cr multipliers used for combining the chroma
+    /// components when deriving the scaling function input.
+    pub fn uv_mult(&self) -> [i32; 2] {
+        self.uv_mult
+    }
+
+    /// Get the Cb and Cr multipliers used for combining the average luma
+    /// when deriving the scaling function input.
+    pub fn uv_mult_luma(&self) -> [i32; 2] {
+        self.uv_mult_luma
+    }
+
+    /// Get the Cb and Cr offsets used when deriving the scaling function
+    /// input.
+    pub fn uv_offset(&self) -> [i32; 2] {
+        self.uv_offset
+    }
+
+    /// Check if the grain blocks overlap.
+    pub fn overlap(&self) -> bool {
+        self.overlap
+    }
+
+    /// Check if the output should be clipped to the studio range.
+    pub fn limit_output_range(&self) -> bool {
+        self.limit_output_range
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Av1FilmGrainParams, RAW_AV1_FILM_GRAIN_PARAMS_SIZE};
+
+    #[test]
+    fn test_from_raw() {
+        let mut raw = [0; RAW_AV1_FILM_GRAIN_PARAMS_SIZE];
+
+        // two luma points
+        raw[0] = 2;
+        raw[1..5].copy_from_slice(&[0, 20, 255, 40]);
+
+        // one Cr point
+        raw[51] = 1;
+        raw[52..54].copy_from_slice(&[128, 10]);
+
+        // scaling shift and lag 1 (i.e. 4 luma coefficients)
+        raw[72] = 8;
+        raw[73] = 1;
+        raw[74..78].copy_from_slice(&[-3, 5, 7, -9]);
+
+        // overlap flag
+        raw[156] = 1;
+
+        let params = Av1FilmGrainParams::from_raw(42, &raw);
+
+        assert_eq!(params.seed(), 42);
+        assert_eq!(params.y_points(), &[(0, 20), (255, 40)]);
+        assert!(params.uv_points()[0].is_empty());
+        assert_eq!(params.uv_points()[1], &[(128, 10)]);
+        assert_eq!(params.scaling_shift(), 8);
+        assert_eq!(params.ar_coeffs_y(), &[-3, 5, 7, -9]);
+        assert_eq!(params.ar_coeffs_uv()[0].len(), 5);
+        assert!(params.overlap());
+        assert!(!params.limit_output_range());
+    }
+}
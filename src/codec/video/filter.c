@@ -0,0 +1,201 @@
+#include <libavfilter/avfilter.h>
+#include <libavfilter/buffersink.h>
+#include <libavfilter/buffersrc.h>
+#include <libavutil/frame.h>
+
+#include <stdio.h>
+#include <stdlib.h>
+
+typedef struct VideoFilter {
+    AVFilterGraph* graph;
+    AVFilterContext* source;
+    AVFilterContext* sink;
+    AVFrame* frame;
+} VideoFilter;
+
+int ffw_video_filter_new(
+    VideoFilter** filter,
+    int format, int width, int height,
+    int tb_num, int tb_den,
+    int sar_num, int sar_den,
+    int fr_num, int fr_den,
+    const char* description);
+void ffw_video_filter_get_output_time_base(const VideoFilter* filter, int* num, int* den);
+int ffw_video_filter_push_frame(VideoFilter* filter, const AVFrame* frame);
+int ffw_video_filter_take_frame(VideoFilter* filter, AVFrame** frame);
+int ffw_video_filter_send_command(VideoFilter* filter, const char* target, const char* command, const char* arg);
+void ffw_video_filter_free(VideoFilter* filter);
+
+int ffw_video_filter_new(
+    VideoFilter** filter,
+    int format, int width, int height,
+    int tb_num, int tb_den,
+    int sar_num, int sar_den,
+    int fr_num, int fr_den,
+    const char* description) {
+    AVFilterInOut* inputs = NULL;
+    AVFilterInOut* outputs = NULL;
+    char args[512];
+    int ret;
+
+    VideoFilter* res = malloc(sizeof(VideoFilter));
+    if (res == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    res->graph = NULL;
+    res->source = NULL;
+    res->sink = NULL;
+    res->frame = NULL;
+
+    if (sar_num <= 0 || sar_den <= 0) {
+        sar_num = 0;
+        sar_den = 1;
+    }
+
+    if (fr_num <= 0 || fr_den <= 0) {
+        fr_num = 0;
+        fr_den = 1;
+    }
+
+    snprintf(
+        args, sizeof(args),
+        "video_size=%dx%d:pix_fmt=%d:time_base=%d/%d:pixel_aspect=%d/%d:frame_rate=%d/%d",
+        width, height, format, tb_num, tb_den, sar_num, sar_den, fr_num, fr_den);
+
+    res->graph = avfilter_graph_alloc();
+    if (res->graph == NULL) {
+        ret = AVERROR(ENOMEM);
+        goto err;
+    }
+
+    ret = avfilter_graph_create_filter(
+        &res->source, avfilter_get_by_name("buffer"), "in", args, NULL, res->graph);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = avfilter_graph_create_filter(
+        &res->sink, avfilter_get_by_name("buffersink"), "out", NULL, NULL, res->graph);
+    if (ret < 0) {
+        goto err;
+    }
+
+    // NOTE: the naming is a bit counter-intuitive here; "outputs" is the
+    // list of the unconnected outputs of our buffer source (i.e. inputs of
+    // the graph described by the description) and vice versa
+    outputs = avfilter_inout_alloc();
+    inputs = avfilter_inout_alloc();
+    if (outputs == NULL || inputs == NULL) {
+        ret = AVERROR(ENOMEM);
+        goto err;
+    }
+
+    outputs->name = av_strdup("in");
+    outputs->filter_ctx = res->source;
+    outputs->pad_idx = 0;
+    outputs->next = NULL;
+
+    inputs->name = av_strdup("out");
+    inputs->filter_ctx = res->sink;
+    inputs->pad_idx = 0;
+    inputs->next = NULL;
+
+    if (outputs->name == NULL || inputs->name == NULL) {
+        ret = AVERROR(ENOMEM);
+        goto err;
+    }
+
+    ret = avfilter_graph_parse_ptr(res->graph, description, &inputs, &outputs, NULL);
+    if (ret < 0) {
+        goto err;
+    }
+
+    ret = avfilter_graph_config(res->graph, NULL);
+    if (ret < 0) {
+        goto err;
+    }
+
+    avfilter_inout_free(&inputs);
+    avfilter_inout_free(&outputs);
+
+    *filter = res;
+
+    return 0;
+
+err:
+    avfilter_inout_free(&inputs);
+    avfilter_inout_free(&outputs);
+
+    ffw_video_filter_free(res);
+
+    return ret;
+}
+
+void ffw_video_filter_get_output_time_base(const VideoFilter* filter, int* num, int* den) {
+    AVRational tb = av_buffersink_get_time_base(filter->sink);
+
+    *num = tb.num;
+    *den = tb.den;
+}
+
+int ffw_video_filter_push_frame(VideoFilter* filter, const AVFrame* frame) {
+    int ret;
+
+    // the buffer source will take its own reference to the frame (the
+    // frame is owned by the caller); flushing is done by passing NULL
+    if (frame == NULL) {
+        ret = av_buffersrc_add_frame_flags(filter->source, NULL, 0);
+    } else {
+        ret = av_buffersrc_add_frame_flags(
+            filter->source, (AVFrame*)frame, AV_BUFFERSRC_FLAG_KEEP_REF);
+    }
+
+    if (ret == AVERROR(EAGAIN)) {
+        return 0;
+    } else if (ret < 0) {
+        return ret;
+    }
+
+    return 1;
+}
+
+int ffw_video_filter_take_frame(VideoFilter* filter, AVFrame** frame) {
+    int ret;
+
+    if (filter->frame == NULL) {
+        filter->frame = av_frame_alloc();
+        if (filter->frame == NULL) {
+            return AVERROR(ENOMEM);
+        }
+    }
+
+    ret = av_buffersink_get_frame(filter->sink, filter->frame);
+    if (ret == AVERROR_EOF || ret == AVERROR(EAGAIN)) {
+        return 0;
+    } else if (ret < 0) {
+        return ret;
+    }
+
+    *frame = filter->frame;
+
+    filter->frame = NULL;
+
+    return 1;
+}
+
+int ffw_video_filter_send_command(VideoFilter* filter, const char* target, const char* command, const char* arg) {
+    return avfilter_graph_send_command(filter->graph, target, command, arg, NULL, 0, 0);
+}
+
+void ffw_video_filter_free(VideoFilter* filter) {
+    if (filter == NULL) {
+        return;
+    }
+
+    // NOTE: the filter contexts are owned by the graph
+    avfilter_graph_free(&filter->graph);
+    av_frame_free(&filter->frame);
+
+    free(filter);
+}
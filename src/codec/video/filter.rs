@@ -0,0 +1,276 @@
+//! Video filter graph.
+
+use std::{
+    ffi::CString,
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+};
+
+use crate::{
+    codec::{
+        video::{PixelFormat, VideoFrame},
+        CodecError, Filter, VideoCodecParameters,
+    },
+    math::Rational,
+    time::TimeBase,
+    Error,
+};
+
+extern "C" {
+    fn ffw_video_filter_new(
+        filter: *mut *mut c_void,
+        format: c_int,
+        width: c_int,
+        height: c_int,
+        tb_num: c_int,
+        tb_den: c_int,
+        sar_num: c_int,
+        sar_den: c_int,
+        fr_num: c_int,
+        fr_den: c_int,
+        description: *const c_char,
+    ) -> c_int;
+    fn ffw_video_filter_get_output_time_base(
+        filter: *const c_void,
+        num: *mut c_int,
+        den: *mut c_int,
+    );
+    fn ffw_video_filter_push_frame(filter: *mut c_void, frame: *const c_void) -> c_int;
+    fn ffw_video_filter_take_frame(filter: *mut c_void, frame: *mut *mut c_void) -> c_int;
+    fn ffw_video_filter_send_command(
+        filter: *mut c_void,
+        target: *const c_char,
+        command: *const c_char,
+        arg: *const c_char,
+    ) -> c_int;
+    fn ffw_video_filter_free(filter: *mut c_void);
+}
+
+/// Builder for a video filter.
+pub struct VideoFilterBuilder {
+    description: String,
+
+    pixel_format: PixelFormat,
+    width: usize,
+    height: usize,
+    sample_aspect_ratio: Option<Rational>,
+    frame_rate: Option<Rational>,
+    time_base: TimeBase,
+}
+
+impl VideoFilterBuilder {
+    /// Create a new video filter builder.
+    fn new(codec_parameters: &VideoCodecParameters, description: &str) -> Self {
+        Self {
+            description: description.to_string(),
+
+            pixel_format: codec_parameters.pixel_format(),
+            width: codec_parameters.width(),
+            height: codec_parameters.height(),
+            sample_aspect_ratio: codec_parameters.sample_aspect_ratio(),
+            frame_rate: None,
+            time_base: TimeBase::MICROSECONDS,
+        }
+    }
+
+    /// Set input time base (all input frames will be rescaled into this time
+    /// base). The default time base is in microseconds.
+    pub fn input_time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Set input frame rate. Some filters need to know the frame rate of the
+    /// input. The frame rate is unknown by default.
+    pub fn input_frame_rate(mut self, frame_rate: Rational) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Build the video filter.
+    pub fn build(self) -> Result<VideoFilter, Error> {
+        let description = CString::new(self.description)
+            .map_err(|_| Error::new("invalid filter graph description"))?;
+
+        let sar = self.sample_aspect_ratio.unwrap_or(Rational::new(0, 1));
+        let frame_rate = self.frame_rate.unwrap_or(Rational::new(0, 1));
+
+        let tb = self.time_base;
+
+        let mut ptr = ptr::null_mut();
+
+        let ret = unsafe {
+            ffw_video_filter_new(
+                &mut ptr,
+                self.pixel_format.into_raw(),
+                self.width as _,
+                self.height as _,
+                tb.num() as _,
+                tb.den() as _,
+                sar.num() as _,
+                sar.den() as _,
+                frame_rate.num() as _,
+                frame_rate.den() as _,
+                description.as_ptr(),
+            )
+        };
+
+        if ret < 0 {
+            return Err(Error::from_raw_error_code(ret));
+        }
+
+        let mut num = 0;
+        let mut den = 0;
+
+        unsafe {
+            ffw_video_filter_get_output_time_base(ptr, &mut num, &mut den);
+        }
+
+        let output_time_base = TimeBase::new(num as _, den as _);
+
+        let res = VideoFilter {
+            ptr,
+            input_time_base: tb,
+            output_time_base,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Video filter graph.
+///
+/// The filter graph is described using the FFmpeg filter graph syntax (e.g.
+/// `"yadif=mode=send_frame"` or `"scale=640:-2,hflip"`). The graph must have
+/// exactly one input and one output.
+pub struct VideoFilter {
+    ptr: *mut c_void,
+    input_time_base: TimeBase,
+    output_time_base: TimeBase,
+}
+
+impl VideoFilter {
+    /// Create a new video filter for frames described by given codec
+    /// parameters.
+    pub fn new(codec_parameters: &VideoCodecParameters, description: &str) -> Result<Self, Error> {
+        VideoFilterBuilder::new(codec_parameters, description).build()
+    }
+
+    /// Get a video filter builder for frames described by given codec
+    /// parameters.
+    pub fn builder(
+        codec_parameters: &VideoCodecParameters,
+        description: &str,
+    ) -> VideoFilterBuilder {
+        VideoFilterBuilder::new(codec_parameters, description)
+    }
+
+    /// Get input time base.
+    pub fn input_time_base(&self) -> TimeBase {
+        self.input_time_base
+    }
+
+    /// Get time base of the output frames.
+    pub fn output_time_base(&self) -> TimeBase {
+        self.output_time_base
+    }
+
+    /// Send a given command to filters in the graph. The target is either
+    /// a filter instance name, a filter name or "all". The command takes
+    /// effect immediately.
+    pub fn send_command(&mut self, target: &str, command: &str, arg: &str) -> Result<(), Error> {
+        let target = CString::new(target).map_err(|_| Error::new("invalid command target"))?;
+        let command = CString::new(command).map_err(|_| Error::new("invalid command"))?;
+        let arg = CString::new(arg).map_err(|_| Error::new("invalid command argument"))?;
+
+        let ret = unsafe {
+            ffw_video_filter_send_command(self.ptr, target.as_ptr(), command.as_ptr(), arg.as_ptr())
+        };
+
+        if ret < 0 {
+            Err(Error::from_raw_error_code(ret))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Filter for VideoFilter {
+    type Frame = VideoFrame;
+
+    fn try_push(&mut self, frame: VideoFrame) -> Result<(), CodecError> {
+        let frame = frame.with_time_base(self.input_time_base);
+
+        unsafe {
+            match ffw_video_filter_push_frame(self.ptr, frame.as_ptr()) {
+                1 => Ok(()),
+                0 => Err(CodecError::again(
+                    "all frames must be consumed before pushing a new frame",
+                )),
+                e => Err(CodecError::from_raw_error_code(e)),
+            }
+        }
+    }
+
+    fn try_flush(&mut self) -> Result<(), CodecError> {
+        unsafe {
+            match ffw_video_filter_push_frame(self.ptr, ptr::null()) {
+                1 => Ok(()),
+                0 => Err(CodecError::again(
+                    "all frames must be consumed before flushing",
+                )),
+                e => Err(CodecError::from_raw_error_code(e)),
+            }
+        }
+    }
+
+    fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        let mut fptr = ptr::null_mut();
+
+        unsafe {
+            match ffw_video_filter_take_frame(self.ptr, &mut fptr) {
+                1 => {
+                    if fptr.is_null() {
+                        panic!("no frame received")
+                    } else {
+                        Ok(Some(VideoFrame::from_raw_ptr(fptr, self.output_time_base)))
+                    }
+                }
+                0 => Ok(None),
+                e => Err(Error::from_raw_error_code(e)),
+            }
+        }
+    }
+}
+
+impl Drop for VideoFilter {
+    fn drop(&mut self) {
+        unsafe { ffw_video_filter_free(self.ptr) }
+    }
+}
+
+unsafe impl Send for VideoFilter {}
+unsafe impl Sync for VideoFilter {}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::VideoFilter;
+
+    use crate::codec::{video::PixelFormat, VideoCodecParameters};
+
+    #[test]
+    fn test_invalid_description() {
+        let codec_parameters = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(PixelFormat::from_str("yuv420p").unwrap())
+            .width(64)
+            .height(64)
+            .build();
+
+        assert!(VideoFilter::new(&codec_parameters, "null").is_ok());
+        assert!(VideoFilter::new(&codec_parameters, "nu\0ll").is_err());
+        assert!(VideoFilter::new(&codec_parameters, "no_such_filter").is_err());
+    }
+}
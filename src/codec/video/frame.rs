@@ -11,7 +11,27 @@ use std::{
     str::FromStr,
 };
 
-use crate::time::{TimeBase, Timestamp};
+use crate::{
+    codec::{
+        video::{
+            film_grain::{Av1FilmGrainParams, RAW_AV1_FILM_GRAIN_PARAMS_SIZE},
+            hdr::{ContentLightLevel, HdrMetadata, MasteringDisplayMetadata},
+            hw::{HwFrameHandle, HwFramesContext},
+            scaler::{Algorithm, VideoFrameScaler},
+            VideoFilter,
+        },
+        Filter, VideoCodecParameters,
+    },
+    math::{Rational, Rect},
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Alignment (in bytes) of plane lines and of the buffer start required by
+/// `VideoFrameMut::from_buffer()`.
+pub const FRAME_BUFFER_ALIGNMENT: usize = 32;
+
+type FreeBufferCallback = extern "C" fn(opaque: *mut c_void, data: *mut u8);
 
 extern "C" {
     fn ffw_get_pixel_format_by_name(name: *const c_char) -> c_int;
@@ -19,18 +39,99 @@ extern "C" {
     fn ffw_get_pixel_format_name(format: c_int) -> *const c_char;
 
     fn ffw_frame_new_black(pixel_format: c_int, width: c_int, height: c_int) -> *mut c_void;
+    fn ffw_frame_get_buffer_size(
+        pixel_format: c_int,
+        width: c_int,
+        height: c_int,
+        align: c_int,
+    ) -> c_int;
+    fn ffw_frame_new_with_buffer(
+        pixel_format: c_int,
+        width: c_int,
+        height: c_int,
+        align: c_int,
+        data: *mut u8,
+        size: c_int,
+        free_buffer: FreeBufferCallback,
+        opaque: *mut c_void,
+    ) -> *mut c_void;
     fn ffw_frame_get_format(frame: *const c_void) -> c_int;
     fn ffw_frame_get_width(frame: *const c_void) -> c_int;
     fn ffw_frame_get_height(frame: *const c_void) -> c_int;
     fn ffw_frame_get_pts(frame: *const c_void) -> i64;
     fn ffw_frame_set_pts(frame: *mut c_void, pts: i64);
+    fn ffw_frame_get_best_effort_timestamp(frame: *const c_void) -> i64;
+    fn ffw_frame_set_best_effort_timestamp(frame: *mut c_void, timestamp: i64);
+    fn ffw_frame_get_sample_aspect_ratio(frame: *const c_void, num: *mut c_int, den: *mut c_int);
+    fn ffw_frame_set_sample_aspect_ratio(frame: *mut c_void, num: c_int, den: c_int);
+    fn ffw_frame_make_writable(frame: *mut c_void) -> c_int;
+    fn ffw_frame_get_color_properties(
+        frame: *const c_void,
+        primaries: *mut c_int,
+        transfer: *mut c_int,
+        space: *mut c_int,
+    );
+    fn ffw_frame_set_color_properties(
+        frame: *mut c_void,
+        primaries: c_int,
+        transfer: c_int,
+        space: c_int,
+    );
+    fn ffw_frame_get_mastering_display_metadata(frame: *const c_void) -> *const c_void;
+    fn ffw_frame_new_mastering_display_metadata(frame: *mut c_void) -> *mut c_void;
+    fn ffw_frame_remove_mastering_display_metadata(frame: *mut c_void);
+    fn ffw_frame_get_content_light_level(
+        frame: *const c_void,
+        max_cll: *mut u32,
+        max_fall: *mut u32,
+    ) -> c_int;
+    fn ffw_frame_set_content_light_level(frame: *mut c_void, max_cll: u32, max_fall: u32) -> c_int;
+    fn ffw_frame_remove_content_light_level(frame: *mut c_void);
+    fn ffw_frame_get_dovi_rpu(
+        frame: *const c_void,
+        data: *mut *const u8,
+        size: *mut c_int,
+    ) -> c_int;
+    fn ffw_frame_set_dovi_rpu(frame: *mut c_void, data: *const u8, size: c_int) -> c_int;
+    fn ffw_frame_get_av1_film_grain_params(
+        frame: *const c_void,
+        seed: *mut u64,
+        values: *mut c_int,
+    ) -> c_int;
+    fn ffw_frame_crop(
+        frame: *const c_void,
+        x: c_int,
+        y: c_int,
+        width: c_int,
+        height: c_int,
+    ) -> *mut c_void;
+    fn ffw_frame_with_alpha(
+        frame: *const c_void,
+        alpha: *const c_void,
+        format: c_int,
+    ) -> *mut c_void;
+    fn ffw_frame_is_interlaced(frame: *const c_void) -> c_int;
+    fn ffw_frame_is_top_field_first(frame: *const c_void) -> c_int;
+    fn ffw_frame_is_key(frame: *const c_void) -> c_int;
+    fn ffw_frame_get_picture_type(frame: *const c_void) -> c_char;
     fn ffw_frame_get_plane_data(frame: *mut c_void, index: usize) -> *mut u8;
     fn ffw_frame_get_line_size(frame: *const c_void, plane: usize) -> usize;
     fn ffw_frame_get_line_count(frame: *const c_void, plane: usize) -> usize;
     fn ffw_frame_clone(frame: *const c_void) -> *mut c_void;
+    fn ffw_frame_get_hw_frames_ctx(frame: *const c_void) -> *const c_void;
+    fn ffw_frame_get_hw_handle_type(frame: *const c_void) -> c_int;
+    fn ffw_frame_download(frame: *const c_void) -> *mut c_void;
     fn ffw_frame_free(frame: *mut c_void);
 }
 
+/// Planar 8-bit YUV pixel formats and their counterparts with an alpha
+/// plane.
+const ALPHA_PIXEL_FORMATS: &[(&str, &str)] = &[
+    ("yuv420p", "yuva420p"),
+    ("yuv422p", "yuva422p"),
+    ("yuv444p", "yuva444p"),
+];
+
 /// An error indicating an unknown pixel format.
 #[derive(Debug, Copy, Clone)]
 pub struct UnknownPixelFormat;
@@ -99,6 +200,66 @@ pub fn get_pixel_format(name: &str) -> PixelFormat {
     PixelFormat::from_str(name).unwrap()
 }
 
+const FIELD_ORDER_UNKNOWN: c_int = 0;
+const FIELD_ORDER_PROGRESSIVE: c_int = 1;
+const FIELD_ORDER_TT: c_int = 2;
+const FIELD_ORDER_BB: c_int = 3;
+const FIELD_ORDER_TB: c_int = 4;
+const FIELD_ORDER_BT: c_int = 5;
+
+/// Field order of interlaced video.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FieldOrder {
+    /// The field order is not known.
+    Unknown,
+    /// Progressive video (i.e. no fields).
+    Progressive,
+    /// Top field coded first, top field displayed first.
+    TopFirst,
+    /// Bottom field coded first, bottom field displayed first.
+    BottomFirst,
+    /// Top field coded first, bottom field displayed first.
+    TopCodedBottomDisplayedFirst,
+    /// Bottom field coded first, top field displayed first.
+    BottomCodedTopDisplayedFirst,
+}
+
+impl FieldOrder {
+    /// Create field order from its raw representation.
+    pub(crate) fn from_raw(v: c_int) -> Self {
+        match v {
+            FIELD_ORDER_PROGRESSIVE => Self::Progressive,
+            FIELD_ORDER_TT => Self::TopFirst,
+            FIELD_ORDER_BB => Self::BottomFirst,
+            FIELD_ORDER_TB => Self::TopCodedBottomDisplayedFirst,
+            FIELD_ORDER_BT => Self::BottomCodedTopDisplayedFirst,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Get the raw representation.
+    pub(crate) fn into_raw(self) -> c_int {
+        match self {
+            Self::Unknown => FIELD_ORDER_UNKNOWN,
+            Self::Progressive => FIELD_ORDER_PROGRESSIVE,
+            Self::TopFirst => FIELD_ORDER_TT,
+            Self::BottomFirst => FIELD_ORDER_BB,
+            Self::TopCodedBottomDisplayedFirst => FIELD_ORDER_TB,
+            Self::BottomCodedTopDisplayedFirst => FIELD_ORDER_BT,
+        }
+    }
+
+    /// Check if the video is interlaced. `None` is returned if the field
+    /// order is unknown.
+    pub fn is_interlaced(self) -> Option<bool> {
+        match self {
+            Self::Unknown => None,
+            Self::Progressive => Some(false),
+            _ => Some(true),
+        }
+    }
+}
+
 /// Picture plane (i.e. a planar array of pixel components).
 pub struct Plane<'a> {
     frame: *mut c_void,
@@ -167,7 +328,7 @@ impl Plane<'_> {
     }
 
     /// Get an iterator over all lines.
-    pub fn lines(&self) -> LinesIter {
+    pub fn lines(&self) -> LinesIter<'_> {
         let line_size = self.line_size();
         let data = self.data();
 
@@ -175,7 +336,7 @@ impl Plane<'_> {
     }
 
     /// Get an iterator over all mutable lines.
-    pub fn lines_mut(&mut self) -> LinesIterMut {
+    pub fn lines_mut(&mut self) -> LinesIterMut<'_> {
         let line_size = self.line_size();
         let data = self.data_mut();
 
@@ -307,6 +468,11 @@ impl<'a> DerefMut for PlanesMut<'a> {
     }
 }
 
+/// A callback used for releasing caller-supplied frame buffers.
+extern "C" fn free_frame_buffer<B>(opaque: *mut c_void, _: *mut u8) {
+    unsafe { drop(Box::from_raw(opaque as *mut B)) }
+}
+
 /// A video frame with mutable data.
 pub struct VideoFrameMut {
     ptr: *mut c_void,
@@ -316,19 +482,126 @@ pub struct VideoFrameMut {
 impl VideoFrameMut {
     /// Create a black video frame. The time base of the frame will be in
     /// microseconds.
+    ///
+    /// # Panics
+    /// The method panics if the frame cannot be allocated. Use `try_black()`
+    /// if you want to handle the error.
     pub fn black(pixel_format: PixelFormat, width: usize, height: usize) -> Self {
+        Self::try_black(pixel_format, width, height).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a black video frame. The time base of the frame will be in
+    /// microseconds. An error is returned if the frame cannot be allocated
+    /// (e.g. due to invalid frame dimensions or lack of memory).
+    pub fn try_black(
+        pixel_format: PixelFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, Error> {
         let ptr = unsafe { ffw_frame_new_black(pixel_format.into_raw(), width as _, height as _) };
 
         if ptr.is_null() {
-            panic!("unable to allocate a video frame");
+            return Err(Error::new("unable to allocate a video frame"));
         }
 
-        VideoFrameMut {
+        let res = VideoFrameMut {
             ptr,
             time_base: TimeBase::MICROSECONDS,
+        };
+
+        Ok(res)
+    }
+
+    /// Get size of a buffer (in bytes) required by the `from_buffer()`
+    /// constructor for a given pixel format and frame dimensions.
+    pub fn buffer_size(
+        pixel_format: PixelFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<usize, Error> {
+        let ret = unsafe {
+            ffw_frame_get_buffer_size(
+                pixel_format.into_raw(),
+                width as _,
+                height as _,
+                FRAME_BUFFER_ALIGNMENT as _,
+            )
+        };
+
+        if ret < 0 {
+            Err(Error::from_raw_error_code(ret))
+        } else {
+            Ok(ret as _)
         }
     }
 
+    /// Create a video frame backed by a given caller-supplied buffer (e.g. a
+    /// shared memory region). The buffer will be dropped once there are no
+    /// references to the frame data. The time base of the frame will be in
+    /// microseconds.
+    ///
+    /// The buffer must be at least `buffer_size()` bytes long and it must
+    /// start at an address aligned to `FRAME_BUFFER_ALIGNMENT` bytes. Planes
+    /// are stored one after another and their lines are aligned to
+    /// `FRAME_BUFFER_ALIGNMENT` bytes. The buffer content is used as is.
+    pub fn from_buffer<B>(
+        pixel_format: PixelFormat,
+        width: usize,
+        height: usize,
+        buffer: B,
+    ) -> Result<Self, Error>
+    where
+        B: AsMut<[u8]> + Send + 'static,
+    {
+        let required_size = Self::buffer_size(pixel_format, width, height)?;
+
+        let mut buffer = Box::new(buffer);
+
+        let data = buffer.as_mut().as_mut();
+
+        let data_ptr = data.as_mut_ptr();
+        let data_size = data.len();
+
+        if data_size < required_size {
+            return Err(Error::new("the buffer is too small"));
+        } else if data_size > c_int::MAX as usize {
+            return Err(Error::new("the buffer is too large"));
+        } else if data_ptr.align_offset(FRAME_BUFFER_ALIGNMENT) != 0 {
+            return Err(Error::new("the buffer is not aligned"));
+        }
+
+        let opaque = Box::into_raw(buffer);
+
+        let ptr = unsafe {
+            ffw_frame_new_with_buffer(
+                pixel_format.into_raw(),
+                width as _,
+                height as _,
+                FRAME_BUFFER_ALIGNMENT as _,
+                data_ptr,
+                data_size as _,
+                free_frame_buffer::<B>,
+                opaque as _,
+            )
+        };
+
+        if ptr.is_null() {
+            // the ownership of the buffer has not been transferred
+            unsafe {
+                drop(Box::from_raw(opaque));
+            }
+
+            return Err(Error::new("unable to allocate a video frame"));
+        }
+
+        let res = VideoFrameMut {
+            ptr,
+            time_base: TimeBase::MICROSECONDS,
+        };
+
+        Ok(res)
+    }
+
     /// Get frame pixel format.
     pub fn pixel_format(&self) -> PixelFormat {
         unsafe { PixelFormat::from_raw(ffw_frame_get_format(self.ptr)) }
@@ -344,6 +617,17 @@ impl VideoFrameMut {
         unsafe { ffw_frame_get_height(self.ptr) as _ }
     }
 
+    /// Check if the frame content is interlaced.
+    pub fn is_interlaced(&self) -> bool {
+        unsafe { ffw_frame_is_interlaced(self.ptr) != 0 }
+    }
+
+    /// Check if the top field is displayed first. The value is meaningful
+    /// only for interlaced frames.
+    pub fn top_field_first(&self) -> bool {
+        unsafe { ffw_frame_is_top_field_first(self.ptr) != 0 }
+    }
+
     /// Get frame time base.
     pub fn time_base(&self) -> TimeBase {
         self.time_base
@@ -353,9 +637,11 @@ impl VideoFrameMut {
     /// given time base.)
     pub fn with_time_base(mut self, time_base: TimeBase) -> Self {
         let new_pts = self.pts().with_time_base(time_base);
+        let new_best_effort_pts = self.best_effort_pts().with_time_base(time_base);
 
         unsafe {
             ffw_frame_set_pts(self.ptr, new_pts.timestamp());
+            ffw_frame_set_best_effort_timestamp(self.ptr, new_best_effort_pts.timestamp());
         }
 
         self.time_base = time_base;
@@ -364,12 +650,20 @@ impl VideoFrameMut {
     }
 
     /// Get presentation timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn pts(&self) -> Timestamp {
         let pts = unsafe { ffw_frame_get_pts(self.ptr) };
 
         Timestamp::new(pts, self.time_base)
     }
 
+    /// Get the presentation timestamp estimated by the decoder.
+    fn best_effort_pts(&self) -> Timestamp {
+        let pts = unsafe { ffw_frame_get_best_effort_timestamp(self.ptr) };
+
+        Timestamp::new(pts, self.time_base)
+    }
+
     /// Set presentation timestamp.
     pub fn with_pts(self, pts: Timestamp) -> Self {
         let pts = pts.with_time_base(self.time_base);
@@ -380,12 +674,12 @@ impl VideoFrameMut {
     }
 
     /// Get picture planes.
-    pub fn planes(&self) -> Planes {
+    pub fn planes(&self) -> Planes<'_> {
         Planes::from(self)
     }
 
     /// Get mutable picture planes.
-    pub fn planes_mut(&mut self) -> PlanesMut {
+    pub fn planes_mut(&mut self) -> PlanesMut<'_> {
         PlanesMut::from(self)
     }
 
@@ -439,10 +733,98 @@ impl VideoFrame {
     }
 
     /// Get picture planes.
-    pub fn planes(&self) -> Planes {
+    pub fn planes(&self) -> Planes<'_> {
         Planes::from(self)
     }
 
+    /// Check if the frame content is interlaced.
+    pub fn is_interlaced(&self) -> bool {
+        unsafe { ffw_frame_is_interlaced(self.ptr) != 0 }
+    }
+
+    /// Check if the top field is displayed first. The value is meaningful
+    /// only for interlaced frames.
+    pub fn top_field_first(&self) -> bool {
+        unsafe { ffw_frame_is_top_field_first(self.ptr) != 0 }
+    }
+
+    /// Check if the frame is a key frame.
+    pub fn is_key(&self) -> bool {
+        unsafe { ffw_frame_is_key(self.ptr) != 0 }
+    }
+
+    /// Get the picture type as a single character (e.g. 'I', 'P' or 'B').
+    /// The '?' character is returned if the type is unknown.
+    pub fn picture_type(&self) -> char {
+        unsafe { ffw_frame_get_picture_type(self.ptr) as u8 as char }
+    }
+
+    /// Check if the frame data is stored in a hardware device memory.
+    pub fn is_hw_frame(&self) -> bool {
+        unsafe { !ffw_frame_get_hw_frames_ctx(self.ptr).is_null() }
+    }
+
+    /// Get the hardware frame pool this frame belongs to (if this is a
+    /// hardware frame).
+    pub fn hw_frames_context(&self) -> Option<HwFramesContext> {
+        unsafe {
+            let ptr = ffw_frame_get_hw_frames_ctx(self.ptr);
+
+            if ptr.is_null() {
+                None
+            } else {
+                Some(HwFramesContext::from_raw_ref(ptr))
+            }
+        }
+    }
+
+    /// Get the native handle of a hardware frame (e.g. `CVPixelBufferRef`
+    /// for VideoToolbox frames). `None` is returned for software frames and
+    /// for hardware frames of other types.
+    ///
+    /// The handle is valid only as long as the frame exists.
+    pub fn hw_frame_handle(&self) -> Option<HwFrameHandle> {
+        let data = |index| unsafe { ffw_frame_get_plane_data(self.ptr, index) };
+        let line_size = |index| unsafe { ffw_frame_get_line_size(self.ptr, index) };
+
+        let handle_type = unsafe { ffw_frame_get_hw_handle_type(self.ptr) };
+
+        let res = match handle_type {
+            1 => HwFrameHandle::CvPixelBuffer(data(3) as _),
+            2 => HwFrameHandle::Cuda {
+                data: [data(0) as _, data(1) as _, data(2) as _, data(3) as _],
+                line_size: [line_size(0), line_size(1), line_size(2), line_size(3)],
+            },
+            3 => HwFrameHandle::VaSurface(data(3) as usize as _),
+            4 => HwFrameHandle::D3D11Texture {
+                texture: data(0) as _,
+                index: data(1) as usize,
+            },
+            _ => return None,
+        };
+
+        Some(res)
+    }
+
+    /// Download a hardware frame into the system memory. The pixel format
+    /// of the resulting frame is the software format of the frame pool.
+    pub fn download(&self) -> Result<VideoFrame, Error> {
+        if !self.is_hw_frame() {
+            return Err(Error::new("not a hardware frame"));
+        }
+
+        let ptr = unsafe { ffw_frame_download(self.ptr) };
+
+        if ptr.is_null() {
+            return Err(Error::new("unable to download a hardware frame"));
+        }
+
+        Ok(VideoFrame {
+            ptr,
+            time_base: self.time_base,
+        })
+    }
+
     /// Get frame time base.
     pub fn time_base(&self) -> TimeBase {
         self.time_base
@@ -452,9 +834,11 @@ impl VideoFrame {
     /// given time base.)
     pub fn with_time_base(mut self, time_base: TimeBase) -> Self {
         let new_pts = self.pts().with_time_base(time_base);
+        let new_best_effort_pts = self.best_effort_pts().with_time_base(time_base);
 
         unsafe {
             ffw_frame_set_pts(self.ptr, new_pts.timestamp());
+            ffw_frame_set_best_effort_timestamp(self.ptr, new_best_effort_pts.timestamp());
         }
 
         self.time_base = time_base;
@@ -463,12 +847,24 @@ impl VideoFrame {
     }
 
     /// Get presentation timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn pts(&self) -> Timestamp {
         let pts = unsafe { ffw_frame_get_pts(self.ptr) };
 
         Timestamp::new(pts, self.time_base)
     }
 
+    /// Get the presentation timestamp estimated by the decoder (using
+    /// various heuristics). This is useful for streams with missing
+    /// presentation timestamps. Note that decoders use this value as the
+    /// presentation timestamp of output frames if the presentation
+    /// timestamp is missing.
+    pub fn best_effort_pts(&self) -> Timestamp {
+        let pts = unsafe { ffw_frame_get_best_effort_timestamp(self.ptr) };
+
+        Timestamp::new(pts, self.time_base)
+    }
+
     /// Set presentation timestamp.
     pub fn with_pts(self, pts: Timestamp) -> Self {
         let pts = pts.with_time_base(self.time_base);
@@ -478,6 +874,331 @@ impl VideoFrame {
         self
     }
 
+    /// Get sample aspect ratio of the frame (if known).
+    pub fn sample_aspect_ratio(&self) -> Option<Rational> {
+        let mut num = 0;
+        let mut den = 0;
+
+        unsafe { ffw_frame_get_sample_aspect_ratio(self.ptr, &mut num, &mut den) }
+
+        Rational::from_raw(num as _, den as _)
+    }
+
+    /// Set sample aspect ratio of the frame (`None` means unknown).
+    pub fn with_sample_aspect_ratio(self, ratio: Option<Rational>) -> Self {
+        let ratio = ratio.unwrap_or_else(|| Rational::new(0, 1));
+
+        unsafe { ffw_frame_set_sample_aspect_ratio(self.ptr, ratio.num() as _, ratio.den() as _) }
+
+        self
+    }
+
+    /// Get HDR metadata of the frame (i.e. color properties, mastering
+    /// display metadata and content light level). Decoders attach the
+    /// metadata to the frames if they are present in the bitstream.
+    pub fn hdr_metadata(&self) -> HdrMetadata {
+        let mut primaries = 0;
+        let mut transfer = 0;
+        let mut space = 0;
+
+        unsafe {
+            ffw_frame_get_color_properties(self.ptr, &mut primaries, &mut transfer, &mut space);
+        }
+
+        let mastering_display = unsafe {
+            let ptr = ffw_frame_get_mastering_display_metadata(self.ptr);
+
+            if ptr.is_null() {
+                None
+            } else {
+                Some(MasteringDisplayMetadata::from_raw_ptr(ptr))
+            }
+        };
+
+        let mut max_cll = 0;
+        let mut max_fall = 0;
+
+        let ret =
+            unsafe { ffw_frame_get_content_light_level(self.ptr, &mut max_cll, &mut max_fall) };
+
+        let content_light_level = if ret != 0 {
+            Some(ContentLightLevel::new(max_cll, max_fall))
+        } else {
+            None
+        };
+
+        HdrMetadata::from_raw_color_properties(primaries, transfer, space)
+            .with_mastering_display(mastering_display)
+            .with_content_light_level(content_light_level)
+    }
+
+    /// Replace HDR metadata of the frame. Missing properties are removed from
+    /// the frame.
+    pub fn with_hdr_metadata(self, metadata: &HdrMetadata) -> Self {
+        let (primaries, transfer, space) = metadata.raw_color_properties();
+
+        unsafe {
+            ffw_frame_set_color_properties(self.ptr, primaries, transfer, space);
+
+            if let Some(mastering_display) = metadata.mastering_display() {
+                let ptr = ffw_frame_new_mastering_display_metadata(self.ptr);
+
+                if ptr.is_null() {
+                    panic!("unable to allocate mastering display metadata");
+                }
+
+                mastering_display.write_raw(ptr);
+            } else {
+                ffw_frame_remove_mastering_display_metadata(self.ptr);
+            }
+
+            if let Some(level) = metadata.content_light_level() {
+                let ret =
+                    ffw_frame_set_content_light_level(self.ptr, level.max_cll(), level.max_fall());
+
+                if ret < 0 {
+                    panic!("unable to allocate content light level");
+                }
+            } else {
+                ffw_frame_remove_content_light_level(self.ptr);
+            }
+        }
+
+        self
+    }
+
+    /// Get the raw Dolby Vision RPU attached to the frame (if any). Decoders
+    /// attach the RPUs to the frames if they are present in the bitstream.
+    /// Note that FFmpeg 5.0 or newer is needed, `None` is always returned
+    /// otherwise.
+    pub fn dolby_vision_rpu(&self) -> Option<&[u8]> {
+        let mut data = ptr::null();
+        let mut size = 0;
+
+        let ret = unsafe { ffw_frame_get_dovi_rpu(self.ptr, &mut data, &mut size) };
+
+        if ret == 0 {
+            None
+        } else if data.is_null() || size <= 0 {
+            Some(&[])
+        } else {
+            unsafe { Some(slice::from_raw_parts(data, size as usize)) }
+        }
+    }
+
+    /// Replace the raw Dolby Vision RPU attached to the frame (e.g. to pass
+    /// it through to an encoder supporting it). `None` removes the RPU. The
+    /// method fails if the FFmpeg version does not support Dolby Vision RPU
+    /// side data (i.e. it is older than 5.0).
+    pub fn with_dolby_vision_rpu(self, rpu: Option<&[u8]>) -> Result<Self, Error> {
+        let (data, size) = rpu
+            .map(|rpu| (rpu.as_ptr(), rpu.len()))
+            .unwrap_or((ptr::null(), 0));
+
+        if size > c_int::MAX as usize {
+            return Err(Error::new("RPU too large"));
+        }
+
+        let ret = unsafe { ffw_frame_set_dovi_rpu(self.ptr, data, size as _) };
+
+        if ret < 0 {
+            Err(Error::from_raw_error_code(ret))
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Get the AV1 film grain parameters attached to the frame (if any).
+    /// Decoders attach the parameters only if they are configured not to
+    /// apply the film grain (see `VideoDecoderBuilder::apply_film_grain()`).
+    /// Note that FFmpeg 4.4 or newer is needed, `None` is always returned
+    /// otherwise.
+    pub fn av1_film_grain_params(&self) -> Option<Av1FilmGrainParams> {
+        let mut seed = 0;
+        let mut raw = [0; RAW_AV1_FILM_GRAIN_PARAMS_SIZE];
+
+        let ret =
+            unsafe { ffw_frame_get_av1_film_grain_params(self.ptr, &mut seed, raw.as_mut_ptr()) };
+
+        if ret == 0 {
+            None
+        } else {
+            Some(Av1FilmGrainParams::from_raw(seed, &raw))
+        }
+    }
+
+    /// Get a given region of the frame. The picture data are shared with
+    /// this frame (i.e. nothing is copied). Timestamp and sample aspect ratio
+    /// are preserved. Note that chroma planes of subsampled pixel formats
+    /// are cropped at the nearest chroma sample.
+    pub fn cropped(&self, rect: Rect) -> Result<VideoFrame, Error> {
+        if self.is_hw_frame() {
+            return Err(Error::new("hardware frames cannot be cropped"));
+        } else if rect.width() < 1 || rect.height() < 1 {
+            return Err(Error::new("invalid crop size"));
+        } else if (rect.x() + rect.width()) > self.width()
+            || (rect.y() + rect.height()) > self.height()
+        {
+            return Err(Error::new("crop rectangle out of frame bounds"));
+        }
+
+        let ptr = unsafe {
+            ffw_frame_crop(
+                self.ptr,
+                rect.x() as _,
+                rect.y() as _,
+                rect.width() as _,
+                rect.height() as _,
+            )
+        };
+
+        if ptr.is_null() {
+            return Err(Error::new("unable to crop the frame"));
+        }
+
+        let res = VideoFrame {
+            ptr,
+            time_base: self.time_base,
+        };
+
+        Ok(res)
+    }
+
+    /// Get a scaled copy of the frame. The timestamp is preserved and the
+    /// sample aspect ratio is adjusted so that the display aspect ratio
+    /// remains the same.
+    pub fn scaled(
+        &self,
+        width: usize,
+        height: usize,
+        algorithm: Algorithm,
+    ) -> Result<VideoFrame, Error> {
+        if self.is_hw_frame() {
+            return Err(Error::new("hardware frames cannot be scaled"));
+        }
+
+        let mut scaler = VideoFrameScaler::builder()
+            .source_pixel_format(self.pixel_format())
+            .source_width(self.width())
+            .source_height(self.height())
+            .target_width(width)
+            .target_height(height)
+            .algorithm(algorithm)
+            .build()?;
+
+        scaler.scale(self)
+    }
+
+    /// Get a copy of the frame placed at a given position of a black canvas
+    /// of a given size (e.g. for letterboxing). Timestamp and sample aspect
+    /// ratio are preserved.
+    pub fn padded(
+        &self,
+        width: usize,
+        height: usize,
+        x: usize,
+        y: usize,
+    ) -> Result<VideoFrame, Error> {
+        if self.is_hw_frame() {
+            return Err(Error::new("hardware frames cannot be padded"));
+        } else if (x + self.width()) > width || (y + self.height()) > height {
+            return Err(Error::new("the frame does not fit into the padded area"));
+        }
+
+        let mut codec_parameters = VideoCodecParameters::builder("rawvideo")?
+            .pixel_format(self.pixel_format())
+            .width(self.width())
+            .height(self.height());
+
+        if let Some(sar) = self.sample_aspect_ratio() {
+            codec_parameters = codec_parameters.sample_aspect_ratio(sar);
+        }
+
+        let description = format!("pad={}:{}:{}:{}:color=black", width, height, x, y);
+
+        let mut filter = VideoFilter::builder(&codec_parameters.build(), &description)
+            .input_time_base(self.time_base)
+            .build()?;
+
+        filter.push(self.clone())?;
+        filter.flush()?;
+
+        let frame = filter
+            .take()?
+            .ok_or_else(|| Error::new("unable to pad the frame"))?;
+
+        Ok(frame.with_time_base(self.time_base))
+    }
+
+    /// Get a copy of the frame with an alpha plane (e.g. `yuva420p` for a
+    /// `yuv420p` frame). The alpha plane is taken from the luma plane of a
+    /// given frame (i.e. the way alpha channels of VP8/VP9/AV1 video are
+    /// coded) or it is fully opaque if no alpha frame is given. Only planar
+    /// 8-bit YUV frames (`yuv420p`, `yuv422p` and `yuv444p`) are supported
+    /// and the alpha frame must be of the same size and of one of these
+    /// formats. Timestamp and other frame properties are preserved.
+    pub fn with_alpha_plane(&self, alpha: Option<&VideoFrame>) -> Result<VideoFrame, Error> {
+        let alpha_format = |format: PixelFormat| {
+            ALPHA_PIXEL_FORMATS
+                .iter()
+                .find(|(name, _)| format.name() == *name)
+                .map(|(_, with_alpha)| *with_alpha)
+        };
+
+        if self.is_hw_frame() || alpha.map(|a| a.is_hw_frame()).unwrap_or(false) {
+            return Err(Error::new("hardware frames are not supported"));
+        }
+
+        let format = alpha_format(self.pixel_format())
+            .ok_or_else(|| Error::new("unsupported pixel format"))?;
+
+        if let Some(alpha) = alpha {
+            if alpha_format(alpha.pixel_format()).is_none() {
+                return Err(Error::new("unsupported pixel format of the alpha frame"));
+            } else if alpha.width() != self.width() || alpha.height() != self.height() {
+                return Err(Error::new("the alpha frame size does not match"));
+            }
+        }
+
+        let format = PixelFormat::from_str(format).unwrap();
+
+        let alpha = alpha.map(|a| a.ptr as *const c_void).unwrap_or(ptr::null());
+
+        let ptr = unsafe { ffw_frame_with_alpha(self.ptr, alpha, format.into_raw()) };
+
+        if ptr.is_null() {
+            return Err(Error::new("unable to allocate a video frame"));
+        }
+
+        let res = VideoFrame {
+            ptr,
+            time_base: self.time_base,
+        };
+
+        Ok(res)
+    }
+
+    /// Convert this frame into a mutable one. The picture data are copied
+    /// only if they are shared with another frame.
+    pub(crate) fn into_mut(mut self) -> Result<VideoFrameMut, Error> {
+        let ret = unsafe { ffw_frame_make_writable(self.ptr) };
+
+        if ret < 0 {
+            return Err(Error::from_raw_error_code(ret));
+        }
+
+        let ptr = self.ptr;
+
+        self.ptr = ptr::null_mut();
+
+        let res = VideoFrameMut {
+            ptr,
+            time_base: self.time_base,
+        };
+
+        Ok(res)
+    }
+
     /// Get raw pointer.
     pub(crate) fn as_ptr(&self) -> *const c_void {
         self.ptr
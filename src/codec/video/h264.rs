@@ -0,0 +1,262 @@
+//! H.264 bitstream utilities.
+//!
+//! Encoded H.264 packets come either in the Annex-B format (NAL units
+//! separated by start codes, e.g. MPEG-TS or most encoders) or in the
+//! length-prefixed format (NAL units prefixed with their length, e.g. MP4 or
+//! encoders with global headers). The format of length-prefixed streams is
+//! described by an `avcC` record in the codec extradata.
+
+use crate::Error;
+
+/// SPS NAL unit type.
+pub const NAL_UNIT_TYPE_SPS: u8 = 7;
+
+/// PPS NAL unit type.
+pub const NAL_UNIT_TYPE_PPS: u8 = 8;
+
+/// IDR slice NAL unit type.
+pub const NAL_UNIT_TYPE_IDR: u8 = 5;
+
+/// H.264 bitstream format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NalFormat {
+    /// NAL units separated by start codes.
+    AnnexB,
+    /// NAL units prefixed with their length. The value is the size of the
+    /// length field in bytes (1, 2 or 4).
+    LengthPrefixed(usize),
+}
+
+impl NalFormat {
+    /// Detect the bitstream format from given codec extradata. The
+    /// length-prefixed format is used if the extradata contain an `avcC`
+    /// record. Annex-B is assumed otherwise.
+    pub fn from_extradata(extradata: Option<&[u8]>) -> Self {
+        match extradata {
+            Some(data) if data.len() > 4 && data[0] == 1 => {
+                Self::LengthPrefixed((data[4] & 3) as usize + 1)
+            }
+            _ => Self::AnnexB,
+        }
+    }
+}
+
+/// Iterator over NAL units in a given H.264 bitstream.
+pub struct NalUnits<'a> {
+    data: &'a [u8],
+    format: NalFormat,
+}
+
+impl<'a> NalUnits<'a> {
+    /// Create a new iterator over NAL units in a given bitstream. NAL units
+    /// are returned without start codes/length prefixes.
+    pub fn new(data: &'a [u8], format: NalFormat) -> Self {
+        let data = if format == NalFormat::AnnexB {
+            skip_start_code(data)
+        } else {
+            data
+        };
+
+        Self { data, format }
+    }
+}
+
+impl<'a> Iterator for NalUnits<'a> {
+    type Item = Result<&'a [u8], Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        match self.format {
+            NalFormat::AnnexB => {
+                let (nal, rest) = match find_start_code(self.data) {
+                    Some(pos) => (&self.data[..pos], skip_start_code(&self.data[pos..])),
+                    None => (self.data, &[][..]),
+                };
+
+                self.data = rest;
+
+                // strip trailing zero bytes (they belong to a 4-byte start
+                // code or they are just padding)
+                let len = nal.iter().rposition(|&b| b != 0).map_or(0, |pos| pos + 1);
+
+                Some(Ok(&nal[..len]))
+            }
+            NalFormat::LengthPrefixed(size) => {
+                if self.data.len() < size {
+                    self.data = &[];
+
+                    return Some(Err(Error::new("truncated NAL unit length")));
+                }
+
+                let len = self.data[..size]
+                    .iter()
+                    .fold(0usize, |len, &b| (len << 8) | b as usize);
+
+                let data = &self.data[size..];
+
+                if data.len() < len {
+                    self.data = &[];
+
+                    return Some(Err(Error::new("truncated NAL unit")));
+                }
+
+                self.data = &data[len..];
+
+                Some(Ok(&data[..len]))
+            }
+        }
+    }
+}
+
+/// Get type of a given NAL unit.
+pub fn nal_unit_type(nal: &[u8]) -> Option<u8> {
+    nal.first().map(|&b| b & 0x1f)
+}
+
+/// Convert a given bitstream into the Annex-B format (using 4-byte start
+/// codes).
+pub fn to_annex_b(data: &[u8], format: NalFormat) -> Result<Vec<u8>, Error> {
+    let mut res = Vec::with_capacity(data.len() + 16);
+
+    for nal in NalUnits::new(data, format) {
+        res.extend_from_slice(&[0, 0, 0, 1]);
+        res.extend_from_slice(nal?);
+    }
+
+    Ok(res)
+}
+
+/// Convert a given bitstream into the length-prefixed format with a given
+/// size of the length field (1, 2 or 4 bytes).
+///
+/// # Panics
+/// The method panics if the size of the length field is not 1, 2 or 4.
+pub fn to_length_prefixed(
+    data: &[u8],
+    format: NalFormat,
+    length_size: usize,
+) -> Result<Vec<u8>, Error> {
+    assert!(matches!(length_size, 1 | 2 | 4));
+
+    let mut res = Vec::with_capacity(data.len() + 16);
+
+    for nal in NalUnits::new(data, format) {
+        let nal = nal?;
+
+        if (nal.len() as u64) >> (length_size * 8) != 0 {
+            return Err(Error::new("NAL unit too big for the length field"));
+        }
+
+        let len = (nal.len() as u32).to_be_bytes();
+
+        res.extend_from_slice(&len[4 - length_size..]);
+        res.extend_from_slice(nal);
+    }
+
+    Ok(res)
+}
+
+/// Get SPS and PPS NAL units from given codec extradata. Both `avcC` and
+/// Annex-B extradata are supported.
+pub fn parameter_sets(extradata: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    if NalFormat::from_extradata(Some(extradata)) == NalFormat::AnnexB {
+        return NalUnits::new(extradata, NalFormat::AnnexB).collect();
+    }
+
+    let truncated = || Error::new("truncated avcC record");
+
+    let mut res = Vec::new();
+
+    let mut data = extradata.get(5..).ok_or_else(truncated)?;
+
+    // number of SPS followed by the SPS units, then the same for PPS
+    for mask in [0x1f, 0xff].iter() {
+        let count = data.first().ok_or_else(truncated)? & mask;
+
+        data = &data[1..];
+
+        for _ in 0..count {
+            let len = data.get(..2).ok_or_else(truncated)?;
+            let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+
+            let nal = data.get(2..2 + len).ok_or_else(truncated)?;
+
+            res.push(nal);
+
+            data = &data[2 + len..];
+        }
+    }
+
+    Ok(res)
+}
+
+/// Find position of the next start code (0x000001) in given data.
+fn find_start_code(data: &[u8]) -> Option<usize> {
+    data.windows(3).position(|w| w == [0, 0, 1])
+}
+
+/// Skip the leading start code (if any).
+fn skip_start_code(data: &[u8]) -> &[u8] {
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+
+    if zeros >= 2 && data.get(zeros) == Some(&1) {
+        &data[zeros + 1..]
+    } else {
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parameter_sets, to_annex_b, to_length_prefixed, NalFormat, NalUnits};
+
+    #[test]
+    fn test_nal_units() {
+        let annex_b = [
+            0, 0, 0, 1, 0x67, 1, 2, 0, 0, 1, 0x68, 3, 0, 0, 0, 1, 0x65, 4, 5,
+        ];
+
+        let nals = NalUnits::new(&annex_b, NalFormat::AnnexB)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(nals, [&[0x67, 1, 2][..], &[0x68, 3], &[0x65, 4, 5]]);
+
+        let prefixed = to_length_prefixed(&annex_b, NalFormat::AnnexB, 4).unwrap();
+
+        assert_eq!(
+            prefixed,
+            [0, 0, 0, 3, 0x67, 1, 2, 0, 0, 0, 2, 0x68, 3, 0, 0, 0, 3, 0x65, 4, 5]
+        );
+
+        let converted = to_annex_b(&prefixed, NalFormat::LengthPrefixed(4)).unwrap();
+
+        assert_eq!(
+            converted,
+            [0, 0, 0, 1, 0x67, 1, 2, 0, 0, 0, 1, 0x68, 3, 0, 0, 0, 1, 0x65, 4, 5]
+        );
+
+        assert!(to_annex_b(&prefixed[..6], NalFormat::LengthPrefixed(4)).is_err());
+    }
+
+    #[test]
+    fn test_parameter_sets() {
+        let avcc = [
+            1, 0x42, 0, 0x1e, 0xff, 0xe1, 0, 2, 0x67, 1, 1, 0, 2, 0x68, 2,
+        ];
+
+        assert_eq!(
+            NalFormat::from_extradata(Some(&avcc)),
+            NalFormat::LengthPrefixed(4)
+        );
+
+        let sets = parameter_sets(&avcc).unwrap();
+
+        assert_eq!(sets, [&[0x67, 1][..], &[0x68, 2]]);
+
+        assert!(parameter_sets(&avcc[..10]).is_err());
+    }
+}
@@ -0,0 +1,669 @@
+//! HDR metadata.
+
+use std::{
+    ffi::{CStr, CString},
+    fmt::{self, Debug, Display, Formatter},
+    os::raw::{c_char, c_int, c_void},
+    str::FromStr,
+};
+
+extern "C" {
+    fn ffw_get_color_primaries_name(primaries: c_int) -> *const c_char;
+    fn ffw_get_color_primaries_by_name(name: *const c_char) -> c_int;
+    fn ffw_get_color_transfer_name(transfer: c_int) -> *const c_char;
+    fn ffw_get_color_transfer_by_name(name: *const c_char) -> c_int;
+    fn ffw_get_color_space_name(space: c_int) -> *const c_char;
+    fn ffw_get_color_space_by_name(name: *const c_char) -> c_int;
+
+    fn ffw_mastering_display_metadata_get(metadata: *const c_void, values: *mut c_int) -> c_int;
+    fn ffw_mastering_display_metadata_set(
+        metadata: *mut c_void,
+        flags: c_int,
+        values: *const c_int,
+    );
+}
+
+/// Denominator used for chromaticity coordinates (the same one as in the
+/// HEVC mastering display colour volume SEI).
+const CHROMATICITY_DEN: c_int = 50000;
+
+/// Denominator used for luminance values (the same one as in the HEVC
+/// mastering display colour volume SEI).
+const LUMINANCE_DEN: c_int = 10000;
+
+/// CIE 1931 xy chromaticity coordinates.
+type Chromaticity = (f64, f64);
+
+const HAS_PRIMARIES: c_int = 1;
+const HAS_LUMINANCE: c_int = 2;
+
+/// An error indicating an unknown color property.
+#[derive(Debug, Copy, Clone)]
+pub struct UnknownColorProperty;
+
+impl Display for UnknownColorProperty {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str("unknown color property")
+    }
+}
+
+impl std::error::Error for UnknownColorProperty {}
+
+/// Get a color property name using a given function.
+fn color_property_name(
+    name: unsafe extern "C" fn(c_int) -> *const c_char,
+    v: c_int,
+) -> &'static str {
+    unsafe {
+        let ptr = name(v);
+
+        if ptr.is_null() {
+            panic!("invalid color property");
+        }
+
+        let name = CStr::from_ptr(ptr as _);
+
+        name.to_str().unwrap()
+    }
+}
+
+/// Find a color property by name using a given function.
+fn color_property_by_name(
+    by_name: unsafe extern "C" fn(*const c_char) -> c_int,
+    name: &str,
+) -> Result<c_int, UnknownColorProperty> {
+    let name = CString::new(name).map_err(|_| UnknownColorProperty)?;
+
+    let v = unsafe { by_name(name.as_ptr() as _) };
+
+    if v < 0 {
+        Err(UnknownColorProperty)
+    } else {
+        Ok(v)
+    }
+}
+
+/// Color primaries (e.g. "bt709" or "bt2020").
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct ColorPrimaries(c_int);
+
+impl ColorPrimaries {
+    /// Create color primaries from a given raw representation (negative
+    /// values mean unspecified).
+    pub(crate) fn from_raw(v: c_int) -> Option<Self> {
+        if v < 0 {
+            None
+        } else {
+            Some(Self(v))
+        }
+    }
+
+    /// Get the raw value.
+    pub(crate) fn into_raw(self) -> c_int {
+        self.0
+    }
+
+    /// Get name of the color primaries.
+    pub fn name(self) -> &'static str {
+        color_property_name(ffw_get_color_primaries_name, self.0)
+    }
+}
+
+impl FromStr for ColorPrimaries {
+    type Err = UnknownColorProperty;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        color_property_by_name(ffw_get_color_primaries_by_name, s).map(Self)
+    }
+}
+
+impl Debug for ColorPrimaries {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str(self.name())
+    }
+}
+
+/// Color transfer characteristic (e.g. "bt709", "smpte2084" for PQ or
+/// "arib-std-b67" for HLG).
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct ColorTransferCharacteristic(c_int);
+
+impl ColorTransferCharacteristic {
+    /// Create a transfer characteristic from a given raw representation
+    /// (negative values mean unspecified).
+    pub(crate) fn from_raw(v: c_int) -> Option<Self> {
+        if v < 0 {
+            None
+        } else {
+            Some(Self(v))
+        }
+    }
+
+    /// Get the raw value.
+    pub(crate) fn into_raw(self) -> c_int {
+        self.0
+    }
+
+    /// Get name of the transfer characteristic.
+    pub fn name(self) -> &'static str {
+        color_property_name(ffw_get_color_transfer_name, self.0)
+    }
+
+    /// Check if this is one of the HDR transfer characteristics (i.e. PQ or
+    /// HLG).
+    pub fn is_hdr(self) -> bool {
+        matches!(self.name(), "smpte2084" | "arib-std-b67")
+    }
+}
+
+impl FromStr for ColorTransferCharacteristic {
+    type Err = UnknownColorProperty;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        color_property_by_name(ffw_get_color_transfer_by_name, s).map(Self)
+    }
+}
+
+impl Debug for ColorTransferCharacteristic {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str(self.name())
+    }
+}
+
+/// Color space, i.e. the YUV matrix coefficients (e.g. "bt709" or
+/// "bt2020nc").
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct ColorSpace(c_int);
+
+impl ColorSpace {
+    /// Create a color space from a given raw representation (negative values
+    /// mean unspecified).
+    pub(crate) fn from_raw(v: c_int) -> Option<Self> {
+        if v < 0 {
+            None
+        } else {
+            Some(Self(v))
+        }
+    }
+
+    /// Get the raw value.
+    pub(crate) fn into_raw(self) -> c_int {
+        self.0
+    }
+
+    /// Get name of the color space.
+    pub fn name(self) -> &'static str {
+        color_property_name(ffw_get_color_space_name, self.0)
+    }
+}
+
+impl FromStr for ColorSpace {
+    type Err = UnknownColorProperty;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        color_property_by_name(ffw_get_color_space_by_name, s).map(Self)
+    }
+}
+
+impl Debug for ColorSpace {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str(self.name())
+    }
+}
+
+/// Mastering display metadata (SMPTE ST 2086). Chromaticity coordinates are
+/// CIE 1931 xy values, luminance values are in cd/m^2.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MasteringDisplayMetadata {
+    primaries: Option<([Chromaticity; 3], Chromaticity)>,
+    luminance: Option<(f64, f64)>,
+}
+
+impl MasteringDisplayMetadata {
+    /// Create new empty mastering display metadata.
+    pub fn new() -> Self {
+        Self {
+            primaries: None,
+            luminance: None,
+        }
+    }
+
+    /// Create mastering display metadata from a given raw representation.
+    pub(crate) unsafe fn from_raw_ptr(ptr: *const c_void) -> Self {
+        let mut values = [0; 20];
+
+        let flags = ffw_mastering_display_metadata_get(ptr, values.as_mut_ptr());
+
+        let value = |index: usize| {
+            let num = values[index * 2];
+            let den = values[index * 2 + 1];
+
+            if den == 0 {
+                0.0
+            } else {
+                num as f64 / den as f64
+            }
+        };
+
+        let point = |index: usize| (value(index * 2), value(index * 2 + 1));
+
+        let primaries = if (flags & HAS_PRIMARIES) != 0 {
+            Some(([point(0), point(1), point(2)], point(3)))
+        } else {
+            None
+        };
+
+        let luminance = if (flags & HAS_LUMINANCE) != 0 {
+            Some((value(8), value(9)))
+        } else {
+            None
+        };
+
+        Self {
+            primaries,
+            luminance,
+        }
+    }
+
+    /// Store the metadata into a given raw representation.
+    pub(crate) unsafe fn write_raw(&self, ptr: *mut c_void) {
+        let mut values = [0; 20];
+        let mut flags = 0;
+
+        let mut set = |index: usize, value: f64, den: c_int| {
+            values[index * 2] = (value * den as f64).round() as c_int;
+            values[index * 2 + 1] = den;
+        };
+
+        if let Some((primaries, white_point)) = self.primaries {
+            let points = primaries.iter().chain(std::iter::once(&white_point));
+
+            for (index, (x, y)) in points.enumerate() {
+                set(index * 2, *x, CHROMATICITY_DEN);
+                set(index * 2 + 1, *y, CHROMATICITY_DEN);
+            }
+
+            flags |= HAS_PRIMARIES;
+        } else {
+            for index in 0..8 {
+                set(index, 0.0, 1);
+            }
+        }
+
+        if let Some((min, max)) = self.luminance {
+            set(8, min, LUMINANCE_DEN);
+            set(9, max, LUMINANCE_DEN);
+
+            flags |= HAS_LUMINANCE;
+        } else {
+            set(8, 0.0, 1);
+            set(9, 0.0, 1);
+        }
+
+        ffw_mastering_display_metadata_set(ptr, flags, values.as_ptr());
+    }
+
+    /// Set the display primaries (red, green and blue) and the white point
+    /// as xy chromaticity coordinates.
+    pub fn with_primaries(
+        mut self,
+        red: (f64, f64),
+        green: (f64, f64),
+        blue: (f64, f64),
+        white_point: (f64, f64),
+    ) -> Self {
+        self.primaries = Some(([red, green, blue], white_point));
+        self
+    }
+
+    /// Set the min and max display luminance in cd/m^2.
+    pub fn with_luminance(mut self, min: f64, max: f64) -> Self {
+        self.luminance = Some((min, max));
+        self
+    }
+
+    /// Get the display primaries (red, green and blue) as xy chromaticity
+    /// coordinates.
+    pub fn display_primaries(&self) -> Option<[(f64, f64); 3]> {
+        self.primaries.map(|(primaries, _)| primaries)
+    }
+
+    /// Get the white point as xy chromaticity coordinates.
+    pub fn white_point(&self) -> Option<(f64, f64)> {
+        self.primaries.map(|(_, white_point)| white_point)
+    }
+
+    /// Get the min display luminance in cd/m^2.
+    pub fn min_luminance(&self) -> Option<f64> {
+        self.luminance.map(|(min, _)| min)
+    }
+
+    /// Get the max display luminance in cd/m^2.
+    pub fn max_luminance(&self) -> Option<f64> {
+        self.luminance.map(|(_, max)| max)
+    }
+}
+
+impl Default for MasteringDisplayMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Content light level (CTA-861.3).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ContentLightLevel {
+    max_cll: u32,
+    max_fall: u32,
+}
+
+impl ContentLightLevel {
+    /// Create a new content light level info.
+    ///
+    /// # Arguments
+    /// * `max_cll` - maximum content light level in cd/m^2
+    /// * `max_fall` - maximum frame-average light level in cd/m^2
+    pub const fn new(max_cll: u32, max_fall: u32) -> Self {
+        Self { max_cll, max_fall }
+    }
+
+    /// Get the maximum content light level in cd/m^2.
+    pub fn max_cll(&self) -> u32 {
+        self.max_cll
+    }
+
+    /// Get the maximum frame-average light level in cd/m^2.
+    pub fn max_fall(&self) -> u32 {
+        self.max_fall
+    }
+}
+
+/// Dolby Vision decoder configuration record (i.e. content of the `dvcC`
+/// or `dvvC` box in MP4 or the corresponding Matroska block addition
+/// mapping).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DolbyVisionConfig {
+    version: (u8, u8),
+    profile: u8,
+    level: u8,
+    rpu_present: bool,
+    el_present: bool,
+    bl_present: bool,
+    bl_signal_compatibility_id: u8,
+}
+
+impl DolbyVisionConfig {
+    /// Create a new Dolby Vision configuration for a given profile and
+    /// level. The version is set to 1.0, the RPU and the base layer are
+    /// marked as present and the base layer signal compatibility ID is set
+    /// to 0.
+    pub const fn new(profile: u8, level: u8) -> Self {
+        Self {
+            version: (1, 0),
+            profile,
+            level,
+            rpu_present: true,
+            el_present: false,
+            bl_present: true,
+            bl_signal_compatibility_id: 0,
+        }
+    }
+
+    /// Create the configuration from its raw representation.
+    pub(crate) fn from_raw(values: &[c_int; 8]) -> Self {
+        Self {
+            version: (values[0] as u8, values[1] as u8),
+            profile: values[2] as u8,
+            level: values[3] as u8,
+            rpu_present: values[4] != 0,
+            el_present: values[5] != 0,
+            bl_present: values[6] != 0,
+            bl_signal_compatibility_id: values[7] as u8,
+        }
+    }
+
+    /// Get the raw representation of the configuration.
+    pub(crate) fn to_raw(self) -> [c_int; 8] {
+        [
+            self.version.0 as _,
+            self.version.1 as _,
+            self.profile as _,
+            self.level as _,
+            self.rpu_present as _,
+            self.el_present as _,
+            self.bl_present as _,
+            self.bl_signal_compatibility_id as _,
+        ]
+    }
+
+    /// Set the version (major, minor).
+    pub const fn with_version(mut self, major: u8, minor: u8) -> Self {
+        self.version = (major, minor);
+        self
+    }
+
+    /// Set the presence flags of the RPU, the enhancement layer and the base
+    /// layer.
+    pub const fn with_layers(mut self, rpu: bool, el: bool, bl: bool) -> Self {
+        self.rpu_present = rpu;
+        self.el_present = el;
+        self.bl_present = bl;
+        self
+    }
+
+    /// Set the base layer signal compatibility ID (e.g. 1 for HDR10 or 4
+    /// for HLG compatible streams).
+    pub const fn with_bl_signal_compatibility_id(mut self, id: u8) -> Self {
+        self.bl_signal_compatibility_id = id;
+        self
+    }
+
+    /// Get the version (major, minor).
+    pub fn version(&self) -> (u8, u8) {
+        self.version
+    }
+
+    /// Get the Dolby Vision profile (e.g. 5, 7 or 8).
+    pub fn profile(&self) -> u8 {
+        self.profile
+    }
+
+    /// Get the Dolby Vision level.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Check if the stream contains RPUs.
+    pub fn rpu_present(&self) -> bool {
+        self.rpu_present
+    }
+
+    /// Check if the stream contains an enhancement layer.
+    pub fn el_present(&self) -> bool {
+        self.el_present
+    }
+
+    /// Check if the stream contains a base layer.
+    pub fn bl_present(&self) -> bool {
+        self.bl_present
+    }
+
+    /// Get the base layer signal compatibility ID.
+    pub fn bl_signal_compatibility_id(&self) -> u8 {
+        self.bl_signal_compatibility_id
+    }
+}
+
+/// HDR metadata, i.e. color properties and static HDR metadata (HDR10) of a
+/// video. Any of the properties may be missing.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct HdrMetadata {
+    color_primaries: Option<ColorPrimaries>,
+    transfer_characteristic: Option<ColorTransferCharacteristic>,
+    color_space: Option<ColorSpace>,
+    mastering_display: Option<MasteringDisplayMetadata>,
+    content_light_level: Option<ContentLightLevel>,
+}
+
+impl HdrMetadata {
+    /// Create new empty HDR metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create HDR metadata from given raw color properties.
+    pub(crate) fn from_raw_color_properties(
+        primaries: c_int,
+        transfer: c_int,
+        space: c_int,
+    ) -> Self {
+        Self {
+            color_primaries: ColorPrimaries::from_raw(primaries),
+            transfer_characteristic: ColorTransferCharacteristic::from_raw(transfer),
+            color_space: ColorSpace::from_raw(space),
+            mastering_display: None,
+            content_light_level: None,
+        }
+    }
+
+    /// Get the raw color properties (primaries, transfer characteristic and
+    /// color space).
+    pub(crate) fn raw_color_properties(&self) -> (c_int, c_int, c_int) {
+        (
+            self.color_primaries.map_or(-1, |v| v.into_raw()),
+            self.transfer_characteristic.map_or(-1, |v| v.into_raw()),
+            self.color_space.map_or(-1, |v| v.into_raw()),
+        )
+    }
+
+    /// Set color primaries.
+    pub fn with_color_primaries(mut self, primaries: Option<ColorPrimaries>) -> Self {
+        self.color_primaries = primaries;
+        self
+    }
+
+    /// Set transfer characteristic.
+    pub fn with_transfer_characteristic(
+        mut self,
+        transfer: Option<ColorTransferCharacteristic>,
+    ) -> Self {
+        self.transfer_characteristic = transfer;
+        self
+    }
+
+    /// Set color space.
+    pub fn with_color_space(mut self, space: Option<ColorSpace>) -> Self {
+        self.color_space = space;
+        self
+    }
+
+    /// Set mastering display metadata.
+    pub fn with_mastering_display(mut self, metadata: Option<MasteringDisplayMetadata>) -> Self {
+        self.mastering_display = metadata;
+        self
+    }
+
+    /// Set content light level.
+    pub fn with_content_light_level(mut self, level: Option<ContentLightLevel>) -> Self {
+        self.content_light_level = level;
+        self
+    }
+
+    /// Get color primaries.
+    pub fn color_primaries(&self) -> Option<ColorPrimaries> {
+        self.color_primaries
+    }
+
+    /// Get transfer characteristic.
+    pub fn transfer_characteristic(&self) -> Option<ColorTransferCharacteristic> {
+        self.transfer_characteristic
+    }
+
+    /// Get color space.
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        self.color_space
+    }
+
+    /// Get mastering display metadata.
+    pub fn mastering_display(&self) -> Option<&MasteringDisplayMetadata> {
+        self.mastering_display.as_ref()
+    }
+
+    /// Get content light level.
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        self.content_light_level
+    }
+
+    /// Check if the metadata describe HDR video (i.e. the transfer
+    /// characteristic is PQ or HLG).
+    pub fn is_hdr(&self) -> bool {
+        self.transfer_characteristic
+            .map(|transfer| transfer.is_hdr())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{
+        ColorPrimaries, ColorSpace, ColorTransferCharacteristic, ContentLightLevel, HdrMetadata,
+        MasteringDisplayMetadata,
+    };
+
+    use crate::codec::video::{PixelFormat, VideoFrameMut};
+
+    #[test]
+    fn test_color_properties() {
+        let primaries = ColorPrimaries::from_str("bt2020").unwrap();
+
+        assert_eq!(primaries.name(), "bt2020");
+
+        assert!(ColorPrimaries::from_str("foo").is_err());
+
+        let pq = ColorTransferCharacteristic::from_str("smpte2084").unwrap();
+        let sdr = ColorTransferCharacteristic::from_str("bt709").unwrap();
+
+        assert!(pq.is_hdr());
+        assert!(!sdr.is_hdr());
+        assert!(HdrMetadata::new()
+            .with_transfer_characteristic(Some(pq))
+            .is_hdr());
+        assert!(!HdrMetadata::new().is_hdr());
+    }
+
+    #[test]
+    fn test_frame_hdr_metadata() {
+        let mastering = MasteringDisplayMetadata::new()
+            .with_primaries(
+                (0.708, 0.292),
+                (0.17, 0.797),
+                (0.131, 0.046),
+                (0.3127, 0.329),
+            )
+            .with_luminance(0.0001, 1000.0);
+
+        let metadata = HdrMetadata::new()
+            .with_color_primaries(Some(ColorPrimaries::from_str("bt2020").unwrap()))
+            .with_transfer_characteristic(Some(
+                ColorTransferCharacteristic::from_str("smpte2084").unwrap(),
+            ))
+            .with_color_space(Some(ColorSpace::from_str("bt2020nc").unwrap()))
+            .with_mastering_display(Some(mastering))
+            .with_content_light_level(Some(ContentLightLevel::new(1000, 400)));
+
+        let frame =
+            VideoFrameMut::black(PixelFormat::from_str("yuv420p").unwrap(), 16, 16).freeze();
+
+        assert_eq!(frame.hdr_metadata(), HdrMetadata::new());
+
+        let frame = frame.with_hdr_metadata(&metadata);
+
+        assert_eq!(frame.hdr_metadata(), metadata);
+
+        let frame = frame.with_hdr_metadata(&HdrMetadata::new());
+
+        assert_eq!(frame.hdr_metadata(), HdrMetadata::new());
+    }
+}
@@ -0,0 +1,90 @@
+#include <libavutil/buffer.h>
+#include <libavutil/hwcontext.h>
+
+int ffw_hw_device_type_exists(const char* name);
+const char* ffw_hw_device_type_next(const char* prev);
+int ffw_hw_device_new(const char* type, const char* device, AVBufferRef** ctx);
+AVBufferRef* ffw_hw_device_clone(const AVBufferRef* ctx);
+const char* ffw_hw_device_get_type_name(const AVBufferRef* ctx);
+void ffw_hw_device_free(AVBufferRef* ctx);
+AVBufferRef* ffw_hw_frames_clone(const AVBufferRef* ctx);
+AVBufferRef* ffw_hw_frames_get_device(const AVBufferRef* ctx);
+int ffw_hw_frames_get_format(const AVBufferRef* ctx);
+int ffw_hw_frames_get_sw_format(const AVBufferRef* ctx);
+int ffw_hw_frames_get_width(const AVBufferRef* ctx);
+int ffw_hw_frames_get_height(const AVBufferRef* ctx);
+void ffw_hw_frames_free(AVBufferRef* ctx);
+
+int ffw_hw_device_type_exists(const char* name) {
+    return av_hwdevice_find_type_by_name(name) != AV_HWDEVICE_TYPE_NONE;
+}
+
+const char* ffw_hw_device_type_next(const char* prev) {
+    enum AVHWDeviceType type = AV_HWDEVICE_TYPE_NONE;
+
+    if (prev != NULL) {
+        type = av_hwdevice_find_type_by_name(prev);
+    }
+
+    type = av_hwdevice_iterate_types(type);
+
+    if (type == AV_HWDEVICE_TYPE_NONE) {
+        return NULL;
+    }
+
+    return av_hwdevice_get_type_name(type);
+}
+
+int ffw_hw_device_new(const char* type, const char* device, AVBufferRef** ctx) {
+    enum AVHWDeviceType t = av_hwdevice_find_type_by_name(type);
+
+    if (t == AV_HWDEVICE_TYPE_NONE) {
+        return AVERROR(EINVAL);
+    }
+
+    return av_hwdevice_ctx_create(ctx, t, device, NULL, 0);
+}
+
+AVBufferRef* ffw_hw_device_clone(const AVBufferRef* ctx) {
+    return av_buffer_ref((AVBufferRef*)ctx);
+}
+
+const char* ffw_hw_device_get_type_name(const AVBufferRef* ctx) {
+    const AVHWDeviceContext* dc = (const AVHWDeviceContext*)ctx->data;
+
+    return av_hwdevice_get_type_name(dc->type);
+}
+
+void ffw_hw_device_free(AVBufferRef* ctx) {
+    av_buffer_unref(&ctx);
+}
+
+AVBufferRef* ffw_hw_frames_clone(const AVBufferRef* ctx) {
+    return av_buffer_ref((AVBufferRef*)ctx);
+}
+
+AVBufferRef* ffw_hw_frames_get_device(const AVBufferRef* ctx) {
+    const AVHWFramesContext* fc = (const AVHWFramesContext*)ctx->data;
+
+    return av_buffer_ref(fc->device_ref);
+}
+
+int ffw_hw_frames_get_format(const AVBufferRef* ctx) {
+    return ((const AVHWFramesContext*)ctx->data)->format;
+}
+
+int ffw_hw_frames_get_sw_format(const AVBufferRef* ctx) {
+    return ((const AVHWFramesContext*)ctx->data)->sw_format;
+}
+
+int ffw_hw_frames_get_width(const AVBufferRef* ctx) {
+    return ((const AVHWFramesContext*)ctx->data)->width;
+}
+
+int ffw_hw_frames_get_height(const AVBufferRef* ctx) {
+    return ((const AVHWFramesContext*)ctx->data)->height;
+}
+
+void ffw_hw_frames_free(AVBufferRef* ctx) {
+    av_buffer_unref(&ctx);
+}
@@ -0,0 +1,227 @@
+//! Hardware acceleration devices and frame pools.
+//!
+//! Decoders with a hardware device attached produce hardware frames (i.e.
+//! frames stored in the device memory). If such frames are passed to an
+//! encoder sharing the same hardware frame pool, they are encoded without
+//! any copying through the system memory (see
+//! `VideoEncoderBuilder::hw_frames_context()`).
+
+use std::{
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+};
+
+use crate::{codec::video::PixelFormat, Error};
+
+extern "C" {
+    fn ffw_hw_device_type_exists(name: *const c_char) -> c_int;
+    fn ffw_hw_device_type_next(prev: *const c_char) -> *const c_char;
+    fn ffw_hw_device_new(
+        device_type: *const c_char,
+        device: *const c_char,
+        ctx: *mut *mut c_void,
+    ) -> c_int;
+    fn ffw_hw_device_clone(ctx: *const c_void) -> *mut c_void;
+    fn ffw_hw_device_get_type_name(ctx: *const c_void) -> *const c_char;
+    fn ffw_hw_device_free(ctx: *mut c_void);
+    fn ffw_hw_frames_clone(ctx: *const c_void) -> *mut c_void;
+    fn ffw_hw_frames_get_device(ctx: *const c_void) -> *mut c_void;
+    fn ffw_hw_frames_get_format(ctx: *const c_void) -> c_int;
+    fn ffw_hw_frames_get_sw_format(ctx: *const c_void) -> c_int;
+    fn ffw_hw_frames_get_width(ctx: *const c_void) -> c_int;
+    fn ffw_hw_frames_get_height(ctx: *const c_void) -> c_int;
+    fn ffw_hw_frames_free(ctx: *mut c_void);
+}
+
+/// Hardware acceleration device (e.g. a CUDA, QSV or VideoToolbox device).
+///
+/// The device is reference counted. Cloning the device does not open a new
+/// device, it just creates a new reference to the same one.
+pub struct HwDevice {
+    ptr: *mut c_void,
+}
+
+impl HwDevice {
+    /// Open a hardware device of a given type (e.g. "cuda", "qsv", "vaapi",
+    /// "d3d11va" or "videotoolbox"). The device name is type specific (e.g.
+    /// a GPU index for CUDA or a DRM render node for VAAPI). The default
+    /// device is used if the name is not given.
+    pub fn new(device_type: &str, device: Option<&str>) -> Result<Self, Error> {
+        let device_type =
+            CString::new(device_type).map_err(|_| Error::new("invalid device type"))?;
+
+        let device = device
+            .map(CString::new)
+            .transpose()
+            .map_err(|_| Error::new("invalid device name"))?;
+
+        let device_ptr = device
+            .as_ref()
+            .map(|name| name.as_ptr())
+            .unwrap_or(ptr::null());
+
+        unsafe {
+            if ffw_hw_device_type_exists(device_type.as_ptr()) == 0 {
+                return Err(Error::new("unknown hardware device type"));
+            }
+
+            let mut ptr = ptr::null_mut();
+
+            let ret = ffw_hw_device_new(device_type.as_ptr(), device_ptr, &mut ptr);
+
+            if ret < 0 {
+                return Err(Error::from_raw_error_code(ret));
+            } else if ptr.is_null() {
+                return Err(Error::new("unable to allocate a hardware device"));
+            }
+
+            Ok(Self { ptr })
+        }
+    }
+
+    /// Get names of all device types supported by the FFmpeg build.
+    pub fn supported_types() -> Vec<&'static str> {
+        let mut res = Vec::new();
+
+        let mut prev = ptr::null();
+
+        loop {
+            unsafe {
+                prev = ffw_hw_device_type_next(prev);
+
+                if prev.is_null() {
+                    return res;
+                }
+
+                let name = CStr::from_ptr(prev);
+
+                res.push(name.to_str().unwrap());
+            }
+        }
+    }
+
+    /// Get name of the device type.
+    pub fn device_type(&self) -> &'static str {
+        unsafe {
+            let name = CStr::from_ptr(ffw_hw_device_get_type_name(self.ptr));
+
+            name.to_str().unwrap()
+        }
+    }
+
+    /// Get raw pointer to the underlying device context reference.
+    pub(crate) fn as_ptr(&self) -> *const c_void {
+        self.ptr
+    }
+}
+
+impl Clone for HwDevice {
+    fn clone(&self) -> Self {
+        let ptr = unsafe { ffw_hw_device_clone(self.ptr) };
+
+        if ptr.is_null() {
+            panic!("unable to clone a hardware device");
+        }
+
+        Self { ptr }
+    }
+}
+
+impl Drop for HwDevice {
+    fn drop(&mut self) {
+        unsafe { ffw_hw_device_free(self.ptr) }
+    }
+}
+
+unsafe impl Send for HwDevice {}
+unsafe impl Sync for HwDevice {}
+
+/// Native handle of a hardware frame.
+#[derive(Debug, Copy, Clone)]
+pub enum HwFrameHandle {
+    /// VideoToolbox frame (`CVPixelBufferRef`).
+    CvPixelBuffer(*mut c_void),
+    /// CUDA frame (device pointers and line sizes of the frame planes).
+    Cuda {
+        data: [u64; 4],
+        line_size: [usize; 4],
+    },
+    /// VAAPI frame (`VASurfaceID`).
+    VaSurface(u32),
+    /// D3D11 frame (`ID3D11Texture2D` and index of the texture array slice).
+    D3D11Texture { texture: *mut c_void, index: usize },
+}
+
+/// Pool of hardware frames (i.e. the FFmpeg hardware frames context).
+///
+/// The pool is reference counted. Cloning the pool creates a new reference
+/// to the same one.
+pub struct HwFramesContext {
+    ptr: *mut c_void,
+}
+
+impl HwFramesContext {
+    /// Create a new reference to a given raw hardware frames context.
+    pub(crate) unsafe fn from_raw_ref(ptr: *const c_void) -> Self {
+        let ptr = ffw_hw_frames_clone(ptr);
+
+        if ptr.is_null() {
+            panic!("unable to clone a hardware frames context");
+        }
+
+        Self { ptr }
+    }
+
+    /// Get the device the frames are allocated on.
+    pub fn device(&self) -> HwDevice {
+        let ptr = unsafe { ffw_hw_frames_get_device(self.ptr) };
+
+        if ptr.is_null() {
+            panic!("unable to clone a hardware device");
+        }
+
+        HwDevice { ptr }
+    }
+
+    /// Get the hardware pixel format of the frames (e.g. "cuda").
+    pub fn pixel_format(&self) -> PixelFormat {
+        unsafe { PixelFormat::from_raw(ffw_hw_frames_get_format(self.ptr)) }
+    }
+
+    /// Get the pixel format of the frame data stored in the device memory
+    /// (e.g. "nv12").
+    pub fn sw_pixel_format(&self) -> PixelFormat {
+        unsafe { PixelFormat::from_raw(ffw_hw_frames_get_sw_format(self.ptr)) }
+    }
+
+    /// Get frame width.
+    pub fn width(&self) -> usize {
+        unsafe { ffw_hw_frames_get_width(self.ptr) as _ }
+    }
+
+    /// Get frame height.
+    pub fn height(&self) -> usize {
+        unsafe { ffw_hw_frames_get_height(self.ptr) as _ }
+    }
+
+    /// Get raw pointer to the underlying frames context reference.
+    pub(crate) fn as_ptr(&self) -> *const c_void {
+        self.ptr
+    }
+}
+
+impl Clone for HwFramesContext {
+    fn clone(&self) -> Self {
+        unsafe { Self::from_raw_ref(self.ptr) }
+    }
+}
+
+impl Drop for HwFramesContext {
+    fn drop(&mut self) {
+        unsafe { ffw_hw_frames_free(self.ptr) }
+    }
+}
+
+unsafe impl Send for HwFramesContext {}
+unsafe impl Sync for HwFramesContext {}
@@ -0,0 +1,448 @@
+//! Hardware encoder presets.
+//!
+//! The builders in this module configure hardware encoders (NVENC, QSV, AMF
+//! and VideoToolbox). They open the required hardware device, set up the
+//! hardware frame pool (if the encoder needs one) and expose the most common
+//! encoder-specific options as typed methods. Software frames passed to the
+//! resulting encoder are uploaded to the device automatically.
+//!
+//! Note that the encoders are available only if FFmpeg has been built with
+//! the corresponding support and if the hardware is present.
+
+use std::marker::PhantomData;
+
+use crate::{
+    codec::video::{frame::get_pixel_format, HwDevice, PixelFormat, VideoEncoder},
+    time::TimeBase,
+    Error,
+};
+
+/// NVIDIA NVENC backend marker.
+pub struct Nvenc;
+
+/// Intel Quick Sync Video backend marker.
+pub struct Qsv;
+
+/// AMD AMF backend marker.
+pub struct Amf;
+
+/// Apple VideoToolbox backend marker.
+pub struct VideoToolbox;
+
+/// Builder for NVENC encoders.
+pub type NvencEncoderBuilder = HwEncoderBuilder<Nvenc>;
+
+/// Builder for QSV encoders.
+pub type QsvEncoderBuilder = HwEncoderBuilder<Qsv>;
+
+/// Builder for AMF encoders.
+pub type AmfEncoderBuilder = HwEncoderBuilder<Amf>;
+
+/// Builder for VideoToolbox encoders.
+pub type VideoToolboxEncoderBuilder = HwEncoderBuilder<VideoToolbox>;
+
+/// Builder for a hardware encoder. Use one of the type aliases (e.g.
+/// `NvencEncoderBuilder`) to create it.
+pub struct HwEncoderBuilder<B> {
+    encoder: &'static str,
+    device_type: Option<&'static str>,
+    hw_format: Option<&'static str>,
+
+    device: Option<HwDevice>,
+    pixel_format: PixelFormat,
+    width: Option<usize>,
+    height: Option<usize>,
+    time_base: TimeBase,
+    bit_rate: u64,
+    pool_size: usize,
+    options: Vec<(String, String)>,
+
+    phantom: PhantomData<B>,
+}
+
+impl<B> HwEncoderBuilder<B> {
+    /// Create a new builder.
+    ///
+    /// # Arguments
+    /// * `encoder` - FFmpeg encoder name
+    /// * `device_type` - type of the device that needs to be opened if the
+    ///   device is not given explicitly
+    /// * `hw_format` - hardware pixel format of the input frames (software
+    ///   frames are passed to the encoder if not set)
+    /// * `sw_format` - default software pixel format
+    fn new(
+        encoder: &'static str,
+        device_type: Option<&'static str>,
+        hw_format: Option<&'static str>,
+        sw_format: &str,
+    ) -> Self {
+        Self {
+            encoder,
+            device_type,
+            hw_format,
+
+            device: None,
+            pixel_format: get_pixel_format(sw_format),
+            width: None,
+            height: None,
+            time_base: TimeBase::MICROSECONDS,
+            bit_rate: 0,
+            pool_size: 20,
+            options: Vec::new(),
+
+            phantom: PhantomData,
+        }
+    }
+
+    /// Use a given hardware device. A default device of the corresponding
+    /// type is opened if needed and if no device is given.
+    pub fn device(mut self, device: &HwDevice) -> Self {
+        self.device = Some(device.clone());
+        self
+    }
+
+    /// Set the software pixel format of the input frames.
+    pub fn pixel_format(mut self, format: PixelFormat) -> Self {
+        self.pixel_format = format;
+        self
+    }
+
+    /// Set frame width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Set frame height.
+    pub fn height(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Set encoder time base. The default time base is in microseconds.
+    pub fn time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Set encoder bit rate. The default is 0 (i.e. automatic).
+    pub fn bit_rate(mut self, bit_rate: u64) -> Self {
+        self.bit_rate = bit_rate;
+        self
+    }
+
+    /// Set the number of frames in the hardware frame pool. The default is
+    /// 20.
+    pub fn pool_size(mut self, size: usize) -> Self {
+        self.pool_size = size;
+        self
+    }
+
+    /// Set an encoder option.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.options.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Build the encoder.
+    pub fn build(self) -> Result<VideoEncoder, Error> {
+        let mut builder = VideoEncoder::builder(self.encoder)?
+            .pixel_format(self.pixel_format)
+            .time_base(self.time_base)
+            .bit_rate(self.bit_rate);
+
+        if let Some(width) = self.width {
+            builder = builder.width(width);
+        }
+
+        if let Some(height) = self.height {
+            builder = builder.height(height);
+        }
+
+        for (name, value) in &self.options {
+            builder = builder.set_option(name, value);
+        }
+
+        let device = match (self.device, self.hw_format.and(self.device_type)) {
+            (Some(device), _) => Some(device),
+            (None, Some(device_type)) => Some(HwDevice::new(device_type, None)?),
+            (None, None) => None,
+        };
+
+        if let Some(device) = device {
+            if let Some(format) = self.hw_format {
+                builder = builder.hw_frames(&device, get_pixel_format(format), self.pool_size);
+            } else {
+                builder = builder.hw_device(&device);
+            }
+        }
+
+        builder.build()
+    }
+}
+
+/// NVENC encoding preset.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NvencPreset {
+    Slow,
+    Medium,
+    Fast,
+    HighPerformance,
+    HighQuality,
+    LowLatency,
+    LowLatencyHighQuality,
+    LowLatencyHighPerformance,
+    Lossless,
+}
+
+impl NvencPreset {
+    /// Get the option value.
+    fn option_value(self) -> &'static str {
+        match self {
+            Self::Slow => "slow",
+            Self::Medium => "medium",
+            Self::Fast => "fast",
+            Self::HighPerformance => "hp",
+            Self::HighQuality => "hq",
+            Self::LowLatency => "ll",
+            Self::LowLatencyHighQuality => "llhq",
+            Self::LowLatencyHighPerformance => "llhp",
+            Self::Lossless => "lossless",
+        }
+    }
+}
+
+/// NVENC rate control mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NvencRateControl {
+    /// Constant quantization parameter.
+    ConstQp,
+    /// Variable bit rate.
+    Vbr,
+    /// Constant bit rate.
+    Cbr,
+    /// High quality variable bit rate (two-pass).
+    VbrHq,
+    /// High quality constant bit rate (two-pass).
+    CbrHq,
+    /// Low delay high quality constant bit rate.
+    CbrLowDelayHq,
+}
+
+impl NvencRateControl {
+    /// Get the option value.
+    fn option_value(self) -> &'static str {
+        match self {
+            Self::ConstQp => "constqp",
+            Self::Vbr => "vbr",
+            Self::Cbr => "cbr",
+            Self::VbrHq => "vbr_hq",
+            Self::CbrHq => "cbr_hq",
+            Self::CbrLowDelayHq => "cbr_ld_hq",
+        }
+    }
+}
+
+impl HwEncoderBuilder<Nvenc> {
+    /// Create a builder for the H.264 NVENC encoder.
+    pub fn h264() -> Self {
+        Self::new("h264_nvenc", Some("cuda"), Some("cuda"), "yuv420p")
+    }
+
+    /// Create a builder for the HEVC NVENC encoder.
+    pub fn hevc() -> Self {
+        Self::new("hevc_nvenc", Some("cuda"), Some("cuda"), "yuv420p")
+    }
+
+    /// Set the encoding preset.
+    pub fn preset(self, preset: NvencPreset) -> Self {
+        self.set_option("preset", preset.option_value())
+    }
+
+    /// Set the rate control mode.
+    pub fn rate_control(self, rc: NvencRateControl) -> Self {
+        self.set_option("rc", rc.option_value())
+    }
+
+    /// Set the number of look-ahead frames used by the rate control.
+    pub fn look_ahead(self, frames: u32) -> Self {
+        self.set_option("rc-lookahead", frames)
+    }
+}
+
+/// QSV encoding preset.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum QsvPreset {
+    VeryFast,
+    Faster,
+    Fast,
+    Medium,
+    Slow,
+    Slower,
+    VerySlow,
+}
+
+impl QsvPreset {
+    /// Get the option value.
+    fn option_value(self) -> &'static str {
+        match self {
+            Self::VeryFast => "veryfast",
+            Self::Faster => "faster",
+            Self::Fast => "fast",
+            Self::Medium => "medium",
+            Self::Slow => "slow",
+            Self::Slower => "slower",
+            Self::VerySlow => "veryslow",
+        }
+    }
+}
+
+/// QSV rate control mode.
+///
+/// The QSV encoders select the rate control method based on the bit rate
+/// and quality options, so the variants set these options accordingly.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum QsvRateControl {
+    /// Constant bit rate.
+    Cbr(u64),
+    /// Variable bit rate with a given target and maximum bit rate.
+    Vbr(u64, u64),
+    /// Intelligent constant quality with a given quality (1 to 51, lower is
+    /// better).
+    Icq(u32),
+}
+
+impl HwEncoderBuilder<Qsv> {
+    /// Create a builder for the H.264 QSV encoder.
+    pub fn h264() -> Self {
+        Self::new("h264_qsv", Some("qsv"), Some("qsv"), "nv12")
+    }
+
+    /// Create a builder for the HEVC QSV encoder.
+    pub fn hevc() -> Self {
+        Self::new("hevc_qsv", Some("qsv"), Some("qsv"), "nv12")
+    }
+
+    /// Set the encoding preset.
+    pub fn preset(self, preset: QsvPreset) -> Self {
+        self.set_option("preset", preset.option_value())
+    }
+
+    /// Set the rate control mode.
+    pub fn rate_control(self, rc: QsvRateControl) -> Self {
+        match rc {
+            QsvRateControl::Cbr(bit_rate) => {
+                self.bit_rate(bit_rate).set_option("maxrate", bit_rate)
+            }
+            QsvRateControl::Vbr(bit_rate, max_bit_rate) => {
+                self.bit_rate(bit_rate).set_option("maxrate", max_bit_rate)
+            }
+            QsvRateControl::Icq(quality) => self.set_option("global_quality", quality),
+        }
+    }
+
+    /// Enable look-ahead rate control with a given depth (in frames).
+    pub fn look_ahead(self, frames: u32) -> Self {
+        self.set_option("look_ahead", 1)
+            .set_option("look_ahead_depth", frames)
+    }
+}
+
+/// AMF quality preset.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AmfQuality {
+    Speed,
+    Balanced,
+    Quality,
+}
+
+impl AmfQuality {
+    /// Get the option value.
+    fn option_value(self) -> &'static str {
+        match self {
+            Self::Speed => "speed",
+            Self::Balanced => "balanced",
+            Self::Quality => "quality",
+        }
+    }
+}
+
+/// AMF rate control mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AmfRateControl {
+    /// Constant quantization parameter.
+    ConstQp,
+    /// Constant bit rate.
+    Cbr,
+    /// Peak constrained variable bit rate.
+    VbrPeak,
+    /// Latency constrained variable bit rate.
+    VbrLatency,
+}
+
+impl AmfRateControl {
+    /// Get the option value.
+    fn option_value(self) -> &'static str {
+        match self {
+            Self::ConstQp => "cqp",
+            Self::Cbr => "cbr",
+            Self::VbrPeak => "vbr_peak",
+            Self::VbrLatency => "vbr_latency",
+        }
+    }
+}
+
+impl HwEncoderBuilder<Amf> {
+    /// Create a builder for the H.264 AMF encoder.
+    ///
+    /// The AMF encoders accept software frames, so no device is opened by
+    /// default. A D3D11 or DXVA2 device can be attached using the `device()`
+    /// method.
+    pub fn h264() -> Self {
+        Self::new("h264_amf", None, None, "nv12")
+    }
+
+    /// Create a builder for the HEVC AMF encoder.
+    pub fn hevc() -> Self {
+        Self::new("hevc_amf", None, None, "nv12")
+    }
+
+    /// Set the quality preset.
+    pub fn preset(self, quality: AmfQuality) -> Self {
+        self.set_option("quality", quality.option_value())
+    }
+
+    /// Set the rate control mode.
+    pub fn rate_control(self, rc: AmfRateControl) -> Self {
+        self.set_option("rc", rc.option_value())
+    }
+}
+
+impl HwEncoderBuilder<VideoToolbox> {
+    /// Create a builder for the H.264 VideoToolbox encoder.
+    ///
+    /// The VideoToolbox encoders accept software frames, so no device is
+    /// opened by default.
+    pub fn h264() -> Self {
+        Self::new("h264_videotoolbox", None, None, "nv12")
+    }
+
+    /// Create a builder for the HEVC VideoToolbox encoder.
+    pub fn hevc() -> Self {
+        Self::new("hevc_videotoolbox", None, None, "nv12")
+    }
+
+    /// Hint the encoder to encode in real time.
+    pub fn realtime(self, realtime: bool) -> Self {
+        self.set_option("realtime", realtime as i32)
+    }
+
+    /// Allow falling back to a software encoder if the hardware one is not
+    /// available.
+    pub fn allow_software(self, allow: bool) -> Self {
+        self.set_option("allow_sw", allow as i32)
+    }
+}
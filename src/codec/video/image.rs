@@ -0,0 +1,181 @@
+//! Still image encoding.
+
+use crate::{
+    codec::{
+        video::{
+            frame::get_pixel_format, PixelFormat, VideoEncoder, VideoEncoderBuilder, VideoFrame,
+            VideoFrameScaler,
+        },
+        Encoder,
+    },
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Image file format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl ImageFormat {
+    /// Guess the format from a given file name (or pattern) extension.
+    pub fn guess_from_file_name(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+
+        let extension = extension.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::WebP),
+            _ => None,
+        }
+    }
+
+    /// Get name of the encoder.
+    fn encoder(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "mjpeg",
+            Self::WebP => "libwebp",
+        }
+    }
+
+    /// Get the pixel format the encoder should use for frames of a given
+    /// pixel format.
+    pub(crate) fn encoder_pixel_format(self, format: PixelFormat) -> PixelFormat {
+        let name = format.name();
+
+        let res = match self {
+            Self::Png if PNG_PIXEL_FORMATS.contains(&name) => name,
+            Self::Png if has_alpha(name) => "rgba",
+            Self::Png => "rgb24",
+            Self::Jpeg if name.contains("444") => "yuvj444p",
+            Self::Jpeg if name.contains("422") => "yuvj422p",
+            Self::Jpeg => "yuvj420p",
+            Self::WebP if has_alpha(name) => "yuva420p",
+            Self::WebP => "yuv420p",
+        };
+
+        get_pixel_format(res)
+    }
+
+    /// Get an encoder builder for images of a given pixel format (see
+    /// `encoder_pixel_format()`) and size.
+    pub(crate) fn encoder_builder(
+        self,
+        pixel_format: PixelFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<VideoEncoderBuilder, Error> {
+        let res = VideoEncoder::builder(self.encoder())?
+            .pixel_format(pixel_format)
+            .width(width)
+            .height(height)
+            .time_base(TimeBase::new(1, 1));
+
+        Ok(res)
+    }
+}
+
+/// Pixel formats supported by the PNG encoder.
+const PNG_PIXEL_FORMATS: &[&str] = &[
+    "rgb24", "rgba", "rgb48be", "rgba64be", "pal8", "gray", "ya8", "gray16be", "ya16be", "monob",
+];
+
+/// Check if a pixel format with a given name has an alpha channel.
+fn has_alpha(name: &str) -> bool {
+    ["rgba", "bgra", "argb", "abgr", "yuva", "gbrap", "ya"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+impl VideoFrame {
+    /// Encode the frame as a single image (e.g. a thumbnail). The frame is
+    /// converted into a pixel format supported by the image format first.
+    /// The quality ranges from 0 (worst) to 100 (best) and it is ignored for
+    /// PNG.
+    ///
+    /// # Panics
+    /// The method panics if the quality is greater than 100.
+    pub fn encode_image(&self, format: ImageFormat, quality: u8) -> Result<Vec<u8>, Error> {
+        assert!(quality <= 100, "the quality must be in the 0 - 100 range");
+
+        let pixel_format = format.encoder_pixel_format(self.pixel_format());
+
+        let frame = if pixel_format == self.pixel_format() {
+            self.clone()
+        } else {
+            let mut scaler = VideoFrameScaler::builder()
+                .source_pixel_format(self.pixel_format())
+                .source_width(self.width())
+                .source_height(self.height())
+                .target_pixel_format(pixel_format)
+                .target_width(self.width())
+                .target_height(self.height())
+                .build()?;
+
+            scaler.scale(self)?
+        };
+
+        let mut builder = format.encoder_builder(pixel_format, self.width(), self.height())?;
+
+        match format {
+            ImageFormat::Png => (),
+            ImageFormat::Jpeg => {
+                // map the quality onto the 2 - 31 quantizer scale
+                let qscale = 31 - (quality as u32 * 29) / 100;
+
+                builder = builder
+                    .set_option("qmin", qscale)
+                    .set_option("qmax", qscale);
+            }
+            ImageFormat::WebP => builder = builder.set_option("quality", quality),
+        }
+
+        let mut encoder = builder.build()?;
+
+        let time_base = TimeBase::new(1, 1);
+
+        let frame = frame
+            .with_time_base(time_base)
+            .with_pts(Timestamp::new(0, time_base));
+
+        encoder.push(frame)?;
+        encoder.flush()?;
+
+        let mut res = Vec::new();
+
+        while let Some(packet) = encoder.take()? {
+            res.extend_from_slice(packet.data());
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImageFormat;
+
+    use crate::codec::video::{frame::get_pixel_format, VideoFrameMut};
+
+    #[test]
+    fn test_encode_jpeg() {
+        let frame = VideoFrameMut::black(get_pixel_format("yuv420p"), 64, 48).freeze();
+
+        let best = frame.encode_image(ImageFormat::Jpeg, 100).unwrap();
+        let worst = frame.encode_image(ImageFormat::Jpeg, 0).unwrap();
+
+        assert!(best.starts_with(&[0xff, 0xd8]));
+        assert!(worst.ends_with(&[0xff, 0xd9]));
+
+        assert_eq!(
+            ImageFormat::guess_from_file_name("thumb.WebP"),
+            Some(ImageFormat::WebP)
+        );
+    }
+}
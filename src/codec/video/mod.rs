@@ -1,27 +1,121 @@
 //! Video decoder/encoder.
 
+pub mod alpha;
+pub mod compositor;
+pub mod deinterlacer;
+pub mod film_grain;
+pub mod filter;
 pub mod frame;
+pub mod h264;
+pub mod hdr;
+pub mod hw;
+pub mod hw_encoder;
+pub mod image;
 pub mod scaler;
+pub mod text;
+pub mod tonemap;
 
 use std::{ffi::CString, os::raw::c_void, ptr};
 
 use crate::{
-    codec::{CodecError, CodecParameters, Decoder, Encoder, VideoCodecParameters},
+    codec::{
+        CodecError, CodecParameters, Decoder, Encoder, ErrorRecognition, VideoCodecParameters,
+    },
     format::stream::Stream,
+    math::Rational,
+    options::OptionInfo,
     packet::Packet,
-    time::TimeBase,
+    time::{Rounding, TimeBase, Timestamp},
     Error,
 };
 
 pub use self::{
-    frame::{PixelFormat, VideoFrame, VideoFrameMut},
+    alpha::AlphaVideoDecoder,
+    compositor::{Compositor, CompositorBuilder},
+    deinterlacer::{VideoDeinterlacer, VideoDeinterlacerBuilder},
+    film_grain::Av1FilmGrainParams,
+    filter::{VideoFilter, VideoFilterBuilder},
+    frame::{FieldOrder, PixelFormat, VideoFrame, VideoFrameMut},
+    hdr::{
+        ColorPrimaries, ColorSpace, ColorTransferCharacteristic, ContentLightLevel,
+        DolbyVisionConfig, HdrMetadata, MasteringDisplayMetadata,
+    },
+    hw::{HwDevice, HwFrameHandle, HwFramesContext},
+    hw_encoder::{
+        AmfEncoderBuilder, HwEncoderBuilder, NvencEncoderBuilder, QsvEncoderBuilder,
+        VideoToolboxEncoderBuilder,
+    },
+    image::ImageFormat,
     scaler::{VideoFrameScaler, VideoFrameScalerBuilder},
+    text::{TextOverlay, TextOverlayBuilder},
+    tonemap::{ToneMapper, ToneMapperBuilder, ToneMappingAlgorithm},
 };
 
+/// Frames to be discarded by the decoder (see the `skip_frame` and
+/// `skip_loop_filter` codec options).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Discard {
+    /// Discard nothing.
+    None,
+    /// Discard useless frames (e.g. zero-sized packets).
+    Default,
+    /// Discard all non-reference frames.
+    NonReference,
+    /// Discard all bidirectional frames.
+    BiDirectional,
+    /// Discard all frames except keyframes and intra frames.
+    NonIntra,
+    /// Discard all frames except keyframes.
+    NonKey,
+    /// Discard all frames.
+    All,
+}
+
+impl Discard {
+    /// Get name of the option constant.
+    fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Default => "default",
+            Self::NonReference => "noref",
+            Self::BiDirectional => "bidir",
+            Self::NonIntra => "nointra",
+            Self::NonKey => "nokey",
+            Self::All => "all",
+        }
+    }
+}
+
+/// Error concealment strategy of a video decoder (see the `ec` codec
+/// option).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ErrorConcealment {
+    /// Iterative motion vector search.
+    GuessMotionVectors,
+    /// Use a strong deblock filter for damaged macroblocks.
+    Deblock,
+    /// Favor predicting from the previous frame instead of the current
+    /// frame.
+    FavorInter,
+}
+
+impl ErrorConcealment {
+    /// Get name of the flag.
+    fn name(self) -> &'static str {
+        match self {
+            Self::GuessMotionVectors => "guess_mvs",
+            Self::Deblock => "deblock",
+            Self::FavorInter => "favor_inter",
+        }
+    }
+}
+
 /// Builder for the video decoder.
 pub struct VideoDecoderBuilder {
     ptr: *mut c_void,
     time_base: TimeBase,
+    hw_device: Option<HwDevice>,
+    error: Option<Error>,
 }
 
 impl VideoDecoderBuilder {
@@ -31,7 +125,12 @@ impl VideoDecoderBuilder {
 
         super::ffw_decoder_set_pkt_timebase(ptr, time_base.num() as _, time_base.den() as _);
 
-        Self { ptr, time_base }
+        Self {
+            ptr,
+            time_base,
+            hw_device: None,
+            error: None,
+        }
     }
 
     /// Create a new builder for a given codec.
@@ -59,24 +158,199 @@ impl VideoDecoderBuilder {
     }
 
     /// Set a decoder option.
-    pub fn set_option<V>(self, name: &str, value: V) -> Self
+    /// An invalid option (e.g. a name containing a NUL character) or an
+    /// option that is not recognized will make the `build()` method fail.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
     where
         V: ToString,
     {
-        let name = CString::new(name).expect("invalid option name");
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (name, value) = match crate::options::option_to_cstrings(name, &value.to_string()) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
 
         let ret = unsafe {
             super::ffw_decoder_set_initial_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
         };
 
         if ret < 0 {
-            panic!("unable to allocate an option");
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
+        }
+
+        self
+    }
+
+    /// Configure the decoder for low latency. Frames are output as soon as
+    /// possible (the `low_delay` flag), non-spec compliant speedups are
+    /// allowed (the `fast` flag) and frame threading is disabled because it
+    /// delays the output by one frame per thread. Slice threading can still
+    /// be enabled using the `threads` option.
+    pub fn low_latency(self) -> Self {
+        self.set_context_option("flags", "+low_delay")
+            .set_context_option("flags2", "+fast")
+            .set_context_option("thread_type", "slice")
+            .set_context_option("threads", "1")
+    }
+
+    /// Enable or disable applying the film grain to decoded frames (AV1
+    /// decoders only). If disabled, the film grain parameters are attached
+    /// to the frames instead (see `VideoFrame::av1_film_grain_params()`),
+    /// e.g. to pass them through to an encoder. The film grain is applied
+    /// by default. The `build()` method fails if the decoder does not
+    /// support the option.
+    pub fn apply_film_grain(self, apply: bool) -> Self {
+        let options = self.options();
+
+        let export = options.iter().any(|option| {
+            option.name() == "export_side_data"
+                && option
+                    .constants()
+                    .iter()
+                    .any(|constant| constant.name() == "film_grain")
+        });
+
+        let filmgrain = options.iter().any(|option| option.name() == "filmgrain");
+
+        // the filmgrain option of libdav1d is deprecated in favor of
+        // exporting the film grain
+        if export {
+            self.set_context_option(
+                "export_side_data",
+                if apply { "-film_grain" } else { "+film_grain" },
+            )
+        } else if filmgrain {
+            self.set_option("filmgrain", apply as i32)
+        } else {
+            self.unsupported_option("film grain")
+        }
+    }
+
+    /// Set the number of threads used for decoding tiles (libdav1d only).
+    /// The `build()` method fails if the decoder does not support the
+    /// option.
+    pub fn tile_threads(self, threads: usize) -> Self {
+        if self.has_private_option("tilethreads") {
+            self.set_option("tilethreads", threads)
+        } else {
+            self.unsupported_option("tile threads")
+        }
+    }
+
+    /// Select the AV1 operating point to decode (i.e. a subset of the
+    /// scalability layers). The default is 0. The `build()` method fails if
+    /// the decoder does not support the option.
+    pub fn operating_point(self, operating_point: u8) -> Self {
+        if self.has_private_option("oppoint") {
+            self.set_option("oppoint", operating_point)
+        } else if self.has_private_option("operating_point") {
+            self.set_option("operating_point", operating_point)
+        } else {
+            self.unsupported_option("operating point selection")
+        }
+    }
+
+    /// Get options available for this decoder (i.e. the generic codec
+    /// options followed by the decoder-specific ones).
+    pub fn options(&self) -> Vec<OptionInfo> {
+        let mut res = crate::options::codec_options();
+
+        res.extend(self.private_options());
+
+        res
+    }
+
+    /// Get the decoder-specific options.
+    fn private_options(&self) -> Vec<OptionInfo> {
+        unsafe {
+            let class = super::ffw_decoder_get_priv_class(self.ptr);
+
+            crate::options::class_options(class)
+        }
+    }
+
+    /// Check if the decoder has a given private option.
+    fn has_private_option(&self, name: &str) -> bool {
+        self.private_options()
+            .iter()
+            .any(|option| option.name() == name)
+    }
+
+    /// Make the `build()` method fail because of a given unsupported
+    /// feature.
+    fn unsupported_option(mut self, feature: &str) -> Self {
+        let msg = format!("the decoder does not support {}", feature);
+
+        self.error.get_or_insert(Error::new(msg));
+        self
+    }
+
+    /// Set an option directly on the codec context. Unlike `set_option()`,
+    /// flags given as `+flag`/`-flag` are combined with the current value.
+    fn set_context_option(mut self, name: &str, value: &str) -> Self {
+        let (name, value) = match crate::options::option_to_cstrings(name, value) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
+
+        let ret = unsafe {
+            super::ffw_decoder_set_context_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
+        };
+
+        if ret < 0 {
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
         }
 
         self
     }
 
+    /// Set error recognition flags of the decoder. Use
+    /// `ErrorRecognition::IgnoreErrors` to decode corrupted inputs on a
+    /// best-effort basis or `ErrorRecognition::Explode` to fail on any
+    /// detected error.
+    pub fn error_recognition(self, flags: &[ErrorRecognition]) -> Self {
+        self.set_context_option("err_detect", &ErrorRecognition::option_value(flags))
+    }
+
+    /// Set error concealment strategies used for damaged frames. The
+    /// default is `GuessMotionVectors` and `Deblock`. An empty slice
+    /// disables error concealment.
+    pub fn error_concealment(self, flags: &[ErrorConcealment]) -> Self {
+        let value = if flags.is_empty() {
+            String::from("0")
+        } else {
+            flags
+                .iter()
+                .map(|flag| flag.name())
+                .collect::<Vec<_>>()
+                .join("+")
+        };
+
+        self.set_context_option("ec", &value)
+    }
+
+    /// Skip decoding of given frames. For example, `Discard::NonKey` makes
+    /// the decoder output only keyframes, which is useful for fast preview
+    /// generation. Support depends on the decoder. The default is
+    /// `Discard::Default`.
+    pub fn skip_frame(self, discard: Discard) -> Self {
+        self.set_context_option("skip_frame", discard.name())
+    }
+
+    /// Skip the loop (deblocking) filter for given frames. This speeds up
+    /// decoding at the cost of visual quality (e.g. H.264 or HEVC). The
+    /// default is `Discard::Default`.
+    pub fn skip_loop_filter(self, discard: Discard) -> Self {
+        self.set_context_option("skip_loop_filter", discard.name())
+    }
+
     /// Set decoder time base (all input packets will be rescaled into this
     /// time base). The default time base is in microseconds.
     pub fn time_base(mut self, time_base: TimeBase) -> Self {
@@ -120,12 +394,37 @@ impl VideoDecoderBuilder {
         self
     }
 
+    /// Decode using a given hardware device. The decoder will produce
+    /// hardware frames (see `VideoFrame::is_hw_frame()`). If the hardware
+    /// decoder cannot handle the stream (e.g. an unsupported profile), the
+    /// decoder falls back to software decoding.
+    pub fn hw_device(mut self, device: &HwDevice) -> Self {
+        self.hw_device = Some(device.clone());
+        self
+    }
+
     /// Build the decoder.
     pub fn build(mut self) -> Result<VideoDecoder, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
         unsafe {
+            if let Some(device) = self.hw_device.as_ref() {
+                let ret = super::ffw_decoder_set_hw_device(self.ptr, device.as_ptr());
+
+                if ret < 0 {
+                    return Err(Error::new(
+                        "the decoder does not support the given hardware device",
+                    ));
+                }
+            }
+
             if super::ffw_decoder_open(self.ptr) != 0 {
                 return Err(Error::new("unable to build the decoder"));
             }
+
+            crate::options::check_unused_options(super::ffw_decoder_get_unused_options(self.ptr))?;
         }
 
         let ptr = self.ptr;
@@ -189,6 +488,12 @@ impl VideoDecoder {
     pub fn builder(codec: &str) -> Result<VideoDecoderBuilder, Error> {
         VideoDecoderBuilder::new(codec)
     }
+
+    /// Reset the internal decoder state and drop all buffered frames. This
+    /// should be used after seeking in the input.
+    pub fn reset(&mut self) {
+        unsafe { super::ffw_decoder_reset(self.ptr) }
+    }
 }
 
 impl Decoder for VideoDecoder {
@@ -242,7 +547,19 @@ impl Decoder for VideoDecoder {
                     if fptr.is_null() {
                         panic!("no frame received")
                     } else {
-                        Ok(Some(VideoFrame::from_raw_ptr(fptr, self.time_base)))
+                        let frame = VideoFrame::from_raw_ptr(fptr, self.time_base);
+
+                        // use the estimated timestamp if the frame has no
+                        // presentation timestamp
+                        let frame = if frame.pts().is_null() {
+                            let pts = frame.best_effort_pts();
+
+                            frame.with_pts(pts)
+                        } else {
+                            frame
+                        };
+
+                        Ok(Some(frame))
                     }
                 }
                 0 => Ok(None),
@@ -265,11 +582,20 @@ unsafe impl Sync for VideoDecoder {}
 pub struct VideoEncoderBuilder {
     ptr: *mut c_void,
 
-    time_base: TimeBase,
+    time_base: Option<TimeBase>,
+    frame_rate: Option<Rational>,
 
     format: Option<PixelFormat>,
     width: Option<usize>,
     height: Option<usize>,
+
+    hw_device: Option<HwDevice>,
+    hw_frames: Option<(HwDevice, PixelFormat, usize)>,
+    hw_frames_context: Option<HwFramesContext>,
+
+    hdr_metadata: Option<HdrMetadata>,
+
+    error: Option<Error>,
 }
 
 impl VideoEncoderBuilder {
@@ -290,11 +616,20 @@ impl VideoEncoderBuilder {
         let res = Self {
             ptr,
 
-            time_base: TimeBase::MICROSECONDS,
+            time_base: None,
+            frame_rate: None,
 
             format: None,
             width: None,
             height: None,
+
+            hw_device: None,
+            hw_frames: None,
+            hw_frames_context: None,
+
+            hdr_metadata: None,
+
+            error: None,
         };
 
         Ok(res)
@@ -321,35 +656,126 @@ impl VideoEncoderBuilder {
         let res = Self {
             ptr,
 
-            time_base: TimeBase::MICROSECONDS,
+            time_base: None,
+            frame_rate: None,
 
             format: Some(pixel_format),
             width: Some(width),
             height: Some(height),
+
+            hw_device: None,
+            hw_frames: None,
+            hw_frames_context: None,
+
+            hdr_metadata: None,
+
+            error: None,
         };
 
         Ok(res)
     }
 
     /// Set an encoder option.
-    pub fn set_option<V>(self, name: &str, value: V) -> Self
+    /// An invalid option (e.g. a name containing a NUL character) or an
+    /// option that is not recognized will make the `build()` method fail.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
     where
         V: ToString,
     {
-        let name = CString::new(name).expect("invalid option name");
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (name, value) = match crate::options::option_to_cstrings(name, &value.to_string()) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
 
         let ret = unsafe {
             super::ffw_encoder_set_initial_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
         };
 
         if ret < 0 {
-            panic!("unable to allocate an option");
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
         }
 
         self
     }
 
+    /// Put codec headers (e.g. H.264 SPS/PPS) into the codec extradata
+    /// instead of every key frame. This is equivalent to setting the
+    /// `+global_header` flag. Most containers (e.g. MP4 or
+    /// Matroska) require global headers.
+    pub fn global_header(self, enabled: bool) -> Self {
+        if enabled {
+            self.set_context_option("flags", "+global_header")
+        } else {
+            self.set_context_option("flags", "-global_header")
+        }
+    }
+
+    /// Configure the encoder for low latency. B-frames are disabled and
+    /// encoders supporting it are tuned for zero latency (i.e. `tune` is
+    /// set to `zerolatency` for libx264/libx265 and the `zerolatency`
+    /// option is enabled for NVENC).
+    pub fn low_latency(self) -> Self {
+        let options = self.options();
+
+        let has_option = |name| options.iter().any(|option| option.name() == name);
+
+        let tune = has_option("tune");
+        let zerolatency = has_option("zerolatency");
+
+        let mut res = self.set_context_option("bf", "0");
+
+        if tune {
+            res = res.set_option("tune", "zerolatency");
+        }
+
+        if zerolatency {
+            res = res.set_option("zerolatency", 1);
+        }
+
+        res
+    }
+
+    /// Set an option directly on the codec context. Unlike `set_option()`,
+    /// flags given as `+flag`/`-flag` are combined with the current value.
+    fn set_context_option(mut self, name: &str, value: &str) -> Self {
+        let (name, value) = match crate::options::option_to_cstrings(name, value) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
+
+        let ret = unsafe {
+            super::ffw_encoder_set_context_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
+        };
+
+        if ret < 0 {
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
+        }
+
+        self
+    }
+
+    /// Get options available for this encoder (i.e. the generic codec
+    /// options followed by the encoder-specific ones).
+    pub fn options(&self) -> Vec<OptionInfo> {
+        let mut res = crate::options::codec_options();
+
+        unsafe {
+            let class = super::ffw_encoder_get_priv_class(self.ptr);
+
+            res.extend(crate::options::class_options(class));
+        }
+
+        res
+    }
+
     /// Set encoder bit rate. The default is 0 (i.e. automatic).
     pub fn bit_rate(self, bit_rate: u64) -> Self {
         unsafe {
@@ -359,9 +785,58 @@ impl VideoEncoderBuilder {
         self
     }
 
-    /// Set encoder time base. The default time base is in microseconds.
+    /// Set the maximum bit rate (in bits per second) of the rate control.
+    /// Most encoders require the VBV buffer size to be set as well (see
+    /// `vbv_buffer_size()`).
+    pub fn max_bit_rate(self, bit_rate: u64) -> Self {
+        self.set_context_option("maxrate", &bit_rate.to_string())
+    }
+
+    /// Set the minimum bit rate (in bits per second) of the rate control.
+    /// Setting the minimum bit rate equal to the maximum bit rate and to the
+    /// bit rate requests constant bit rate encoding.
+    pub fn min_bit_rate(self, bit_rate: u64) -> Self {
+        self.set_context_option("minrate", &bit_rate.to_string())
+    }
+
+    /// Set the size of the VBV (decoder) buffer in bits. Packets produced
+    /// by the encoder can be checked against the VBV model using
+    /// `analysis::hrd::hrd_verify()`.
+    pub fn vbv_buffer_size(self, bits: u64) -> Self {
+        self.set_context_option("bufsize", &bits.to_string())
+    }
+
+    /// Set the number of bits that should be loaded into the VBV buffer
+    /// before decoding starts. The default is 3/4 of the buffer size.
+    pub fn vbv_initial_occupancy(self, bits: u64) -> Self {
+        self.set_context_option("rc_init_occupancy", &bits.to_string())
+    }
+
+    /// Set encoder time base. Timestamps of all frames pushed into the
+    /// encoder are rescaled into this time base and all packets produced by
+    /// the encoder use it. The default time base is the inverse of the frame
+    /// rate (if set) or microseconds.
     pub fn time_base(mut self, time_base: TimeBase) -> Self {
-        self.time_base = time_base;
+        self.time_base = Some(time_base);
+        self
+    }
+
+    /// Set the nominal frame rate of the encoded video. The frame rate is
+    /// independent of the time base (e.g. a 29.97 fps video can use a
+    /// millisecond time base). It is used by rate control and signaled in
+    /// the bitstream by some encoders, and it is used for generating
+    /// timestamps of frames without one. The frame rate is unknown by
+    /// default. A frame rate with a zero numerator or denominator will make
+    /// the `build()` method fail.
+    pub fn frame_rate(mut self, frame_rate: Rational) -> Self {
+        if frame_rate.num() == 0 || frame_rate.den() == 0 {
+            self.error
+                .get_or_insert(Error::new("the frame rate must be positive"));
+
+            return self;
+        }
+
+        self.frame_rate = Some(frame_rate);
         self
     }
 
@@ -383,8 +858,69 @@ impl VideoEncoderBuilder {
         self
     }
 
+    /// Set HDR metadata of the encoded video. The color properties are used
+    /// for the encoder output (i.e. they are signaled in the bitstream by
+    /// encoders supporting it and they become part of the encoder codec
+    /// parameters). Mastering display metadata and content light level are
+    /// attached to all frames that do not carry their own. Use
+    /// `Stream::set_hdr_metadata()` to signal them in the container as well.
+    ///
+    /// Color properties of encoders created from codec parameters are taken
+    /// from the codec parameters. Missing properties do not override them.
+    pub fn hdr_metadata(mut self, metadata: HdrMetadata) -> Self {
+        let (primaries, transfer, space) = metadata.raw_color_properties();
+
+        unsafe {
+            super::ffw_encoder_set_color_properties(self.ptr, primaries, transfer, space);
+        }
+
+        self.hdr_metadata = Some(metadata);
+        self
+    }
+
+    /// Attach a given hardware device to the encoder. This is needed by
+    /// hardware encoders that derive their internal context from a device
+    /// (e.g. AMF).
+    pub fn hw_device(mut self, device: &HwDevice) -> Self {
+        self.hw_device = Some(device.clone());
+        self
+    }
+
+    /// Feed the encoder with hardware frames of a given format (e.g. "cuda"
+    /// or "qsv") allocated from a given device. The pixel format set using
+    /// the `pixel_format()` method is used as the underlying software format
+    /// of the frames. Software frames passed to the encoder will be uploaded
+    /// to the device automatically.
+    ///
+    /// # Arguments
+    /// * `device` - hardware device
+    /// * `format` - hardware pixel format
+    /// * `pool_size` - initial number of frames in the frame pool (some
+    ///   devices, e.g. QSV, do not support growing the pool later)
+    pub fn hw_frames(mut self, device: &HwDevice, format: PixelFormat, pool_size: usize) -> Self {
+        self.hw_frames = Some((device.clone(), format, pool_size));
+        self
+    }
+
+    /// Feed the encoder with hardware frames from a given frame pool (e.g.
+    /// the pool of a hardware decoder, see `VideoFrame::hw_frames_context()`).
+    /// Frames from this pool are passed to the encoder directly without
+    /// copying them through the system memory. Other frames are copied
+    /// into the pool automatically.
+    ///
+    /// The pixel format set using the `pixel_format()` method is ignored in
+    /// this case.
+    pub fn hw_frames_context(mut self, frames: &HwFramesContext) -> Self {
+        self.hw_frames_context = Some(frames.clone());
+        self
+    }
+
     /// Build the encoder.
     pub fn build(mut self) -> Result<VideoEncoder, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
         let format = self
             .format
             .ok_or_else(|| Error::new("pixel format not set"))?;
@@ -392,24 +928,76 @@ impl VideoEncoderBuilder {
         let width = self.width.ok_or_else(|| Error::new("width not set"))?;
         let height = self.height.ok_or_else(|| Error::new("height not set"))?;
 
-        let tb = self.time_base;
+        let tb = match (self.time_base, self.frame_rate) {
+            (Some(tb), _) => tb,
+            (None, Some(frame_rate)) => TimeBase::new(frame_rate.den(), frame_rate.num()),
+            (None, None) => TimeBase::MICROSECONDS,
+        };
 
         unsafe {
             super::ffw_encoder_set_time_base(self.ptr, tb.num() as _, tb.den() as _);
+
+            if let Some(frame_rate) = self.frame_rate {
+                super::ffw_encoder_set_frame_rate(
+                    self.ptr,
+                    frame_rate.num() as _,
+                    frame_rate.den() as _,
+                );
+            }
+
             super::ffw_encoder_set_pixel_format(self.ptr, format.into_raw());
             super::ffw_encoder_set_width(self.ptr, width as _);
             super::ffw_encoder_set_height(self.ptr, height as _);
 
+            if let Some(device) = self.hw_device.as_ref() {
+                let ret = super::ffw_encoder_set_hw_device(self.ptr, device.as_ptr());
+
+                if ret < 0 {
+                    return Err(Error::from_raw_error_code(ret));
+                }
+            }
+
+            if let Some((device, format, pool_size)) = self.hw_frames.as_ref() {
+                let ret = super::ffw_encoder_init_hw_frames(
+                    self.ptr,
+                    device.as_ptr(),
+                    format.into_raw(),
+                    *pool_size as _,
+                );
+
+                if ret < 0 {
+                    return Err(Error::from_raw_error_code(ret));
+                }
+            }
+
+            if let Some(frames) = self.hw_frames_context.as_ref() {
+                let ret = super::ffw_encoder_set_hw_frames(self.ptr, frames.as_ptr());
+
+                if ret < 0 {
+                    return Err(Error::from_raw_error_code(ret));
+                }
+            }
+
             if super::ffw_encoder_open(self.ptr) != 0 {
                 return Err(Error::new("unable to build the encoder"));
             }
+
+            crate::options::check_unused_options(super::ffw_encoder_get_unused_options(self.ptr))?;
         }
 
         let ptr = self.ptr;
 
         self.ptr = ptr::null_mut();
 
-        let res = VideoEncoder { ptr, time_base: tb };
+        let res = VideoEncoder {
+            ptr,
+            time_base: tb,
+            frame_rate: self.frame_rate,
+            last_pts: None,
+            last_source_pts: Timestamp::null(),
+            dropped_frames: 0,
+            hdr_metadata: self.hdr_metadata,
+        };
 
         Ok(res)
     }
@@ -428,6 +1016,11 @@ unsafe impl Sync for VideoEncoderBuilder {}
 pub struct VideoEncoder {
     ptr: *mut c_void,
     time_base: TimeBase,
+    frame_rate: Option<Rational>,
+    last_pts: Option<i64>,
+    last_source_pts: Timestamp,
+    dropped_frames: u64,
+    hdr_metadata: Option<HdrMetadata>,
 }
 
 impl VideoEncoder {
@@ -442,6 +1035,88 @@ impl VideoEncoder {
     pub fn builder(codec: &str) -> Result<VideoEncoderBuilder, Error> {
         VideoEncoderBuilder::new(codec)
     }
+
+    /// Check if a zero-copy path has been established, i.e. the encoder
+    /// has received hardware frames from its own frame pool and no frame
+    /// had to be copied through the system memory so far.
+    pub fn is_zero_copy(&self) -> bool {
+        unsafe { super::ffw_encoder_is_zero_copy(self.ptr) != 0 }
+    }
+
+    /// Get HDR metadata set using `VideoEncoderBuilder::hdr_metadata()`.
+    pub fn hdr_metadata(&self) -> Option<&HdrMetadata> {
+        self.hdr_metadata.as_ref()
+    }
+
+    /// Get the encoder time base.
+    pub fn time_base(&self) -> TimeBase {
+        self.time_base
+    }
+
+    /// Get the nominal frame rate (if set).
+    pub fn frame_rate(&self) -> Option<Rational> {
+        self.frame_rate
+    }
+
+    /// Get the number of frames dropped because their timestamps collided
+    /// with the timestamp of the previous frame after rescaling into the
+    /// encoder time base.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Rescale timestamp of a given frame into the encoder time base
+    /// (rounding to the nearest tick). Frames without a timestamp get one
+    /// generated from the frame rate (if known).
+    ///
+    /// `None` is returned if the frame should be dropped because it lands
+    /// on the same tick as the previous frame even though its original
+    /// timestamp is greater (i.e. the encoder time base is too coarse). An
+    /// error is returned if the original timestamp is not greater than the
+    /// timestamp of the previous frame.
+    fn rescale_pts(&self, frame: VideoFrame) -> Result<Option<VideoFrame>, CodecError> {
+        let source_pts = frame.pts();
+
+        if source_pts.is_null() {
+            let mut frame = frame.with_time_base(self.time_base);
+
+            let pts = if let (Some(last_pts), Some(frame_rate)) = (self.last_pts, self.frame_rate) {
+                let duration = Timestamp::new(1, TimeBase::new(frame_rate.den(), frame_rate.num()))
+                    .with_time_base_rounded(self.time_base, Rounding::NearInf)
+                    .timestamp()
+                    .max(1);
+
+                Timestamp::new(last_pts + duration, self.time_base)
+            } else if self.frame_rate.is_some() {
+                Timestamp::new(0, self.time_base)
+            } else {
+                return Ok(Some(frame));
+            };
+
+            frame = frame.with_pts(pts);
+
+            return Ok(Some(frame));
+        }
+
+        if !self.last_source_pts.is_null() && source_pts <= self.last_source_pts {
+            return Err(CodecError::from(Error::new(format!(
+                "non-increasing frame timestamp {:?} (previous {:?})",
+                source_pts, self.last_source_pts
+            ))));
+        }
+
+        let pts = source_pts.with_time_base_rounded(self.time_base, Rounding::NearInf);
+
+        if let Some(last_pts) = self.last_pts {
+            if pts.timestamp() <= last_pts {
+                return Ok(None);
+            }
+        }
+
+        let frame = frame.with_time_base(self.time_base).with_pts(pts);
+
+        Ok(Some(frame))
+    }
 }
 
 impl Encoder for VideoEncoder {
@@ -461,17 +1136,53 @@ impl Encoder for VideoEncoder {
     }
 
     fn try_push(&mut self, frame: VideoFrame) -> Result<(), CodecError> {
-        let frame = frame.with_time_base(self.time_base);
+        let source_pts = frame.pts();
+
+        let mut frame = match self.rescale_pts(frame)? {
+            Some(frame) => frame,
+            None => {
+                self.last_source_pts = source_pts;
+                self.dropped_frames += 1;
+
+                return Ok(());
+            }
+        };
+
+        if let Some(metadata) = self.hdr_metadata.as_ref() {
+            let current = frame.hdr_metadata();
+
+            if current.mastering_display().is_none() && current.content_light_level().is_none() {
+                let metadata = current
+                    .with_mastering_display(metadata.mastering_display().copied())
+                    .with_content_light_level(metadata.content_light_level());
+
+                frame = frame.with_hdr_metadata(&metadata);
+            }
+        }
+
+        let pts = frame.pts();
 
         unsafe {
             match super::ffw_encoder_push_frame(self.ptr, frame.as_ptr()) {
-                1 => Ok(()),
-                0 => Err(CodecError::again(
-                    "all packets must be consumed before pushing a new frame",
-                )),
-                e => Err(CodecError::from_raw_error_code(e)),
+                1 => (),
+                0 => {
+                    return Err(CodecError::again(
+                        "all packets must be consumed before pushing a new frame",
+                    ))
+                }
+                e => return Err(CodecError::from_raw_error_code(e)),
             }
         }
+
+        if !pts.is_null() {
+            self.last_pts = Some(pts.timestamp());
+        }
+
+        if !source_pts.is_null() {
+            self.last_source_pts = source_pts;
+        }
+
+        Ok(())
     }
 
     fn try_flush(&mut self) -> Result<(), CodecError> {
@@ -513,3 +1224,543 @@ impl Drop for VideoEncoder {
 
 unsafe impl Send for VideoEncoder {}
 unsafe impl Sync for VideoEncoder {}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{
+        scaler::Algorithm, AlphaVideoDecoder, Compositor, Discard, PixelFormat, VideoDecoder,
+        VideoEncoder, VideoFrameMut,
+    };
+
+    use crate::{
+        codec::{Decoder, Encoder, ErrorRecognition},
+        math::{Rational, Rect},
+        packet::BLOCK_ADDITIONAL_ID_ALPHA,
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_low_latency() {
+        let time_base = TimeBase::new(1, 25);
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(64)
+            .time_base(time_base)
+            .low_latency()
+            .build()
+            .unwrap();
+
+        let mut decoder = VideoDecoder::from_codec_parameters(&encoder.codec_parameters())
+            .unwrap()
+            .time_base(time_base)
+            .low_latency()
+            .build()
+            .unwrap();
+
+        // every frame should go through the encoder and the decoder
+        // immediately
+        for i in 0..10 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 64)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            let packet = encoder.take().unwrap().unwrap();
+
+            assert!(encoder.take().unwrap().is_none());
+
+            decoder.push(packet).unwrap();
+
+            let frame = decoder.take().unwrap().unwrap();
+
+            assert_eq!(frame.pts().with_time_base(time_base).timestamp(), i);
+        }
+    }
+
+    #[test]
+    fn test_missing_pts() {
+        let time_base = TimeBase::new(1, 25);
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(64)
+            .time_base(time_base)
+            .low_latency()
+            .build()
+            .unwrap();
+
+        let mut decoder = VideoDecoder::from_codec_parameters(&encoder.codec_parameters())
+            .unwrap()
+            .time_base(time_base)
+            .low_latency()
+            .build()
+            .unwrap();
+
+        for i in 0..10 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 64)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            // keep only the decoding timestamp
+            let packet = encoder.take().unwrap().unwrap().with_pts(Timestamp::null());
+
+            decoder.push(packet).unwrap();
+
+            let frame = decoder.take().unwrap().unwrap();
+
+            assert_eq!(frame.pts(), frame.best_effort_pts());
+            assert_eq!(frame.pts().with_time_base(time_base).timestamp(), i);
+        }
+    }
+
+    #[test]
+    fn test_skip_frame() {
+        let time_base = TimeBase::new(1, 25);
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(64)
+            .time_base(time_base)
+            .set_option("g", 5)
+            .low_latency()
+            .build()
+            .unwrap();
+
+        let mut decoder = VideoDecoder::from_codec_parameters(&encoder.codec_parameters())
+            .unwrap()
+            .time_base(time_base)
+            .low_latency()
+            .error_recognition(&[ErrorRecognition::IgnoreErrors])
+            .error_concealment(&[])
+            .skip_frame(Discard::NonKey)
+            .skip_loop_filter(Discard::All)
+            .build()
+            .unwrap();
+
+        let mut frames = 0;
+
+        for i in 0..10 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 64)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                decoder.push(packet).unwrap();
+
+                while decoder.take().unwrap().is_some() {
+                    frames += 1;
+                }
+            }
+        }
+
+        // only the keyframes should be decoded
+        assert_eq!(frames, 2);
+    }
+
+    #[test]
+    fn test_alpha_decoder() {
+        let time_base = TimeBase::new(1, 25);
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let new_encoder = || {
+            VideoEncoder::builder("mpeg4")
+                .unwrap()
+                .pixel_format(pixel_format)
+                .width(64)
+                .height(64)
+                .time_base(time_base)
+                .low_latency()
+                .build()
+                .unwrap()
+        };
+
+        let mut encoder = new_encoder();
+        let mut alpha_encoder = new_encoder();
+
+        let new_decoder = || {
+            VideoDecoder::from_codec_parameters(&encoder.codec_parameters())
+                .unwrap()
+                .time_base(time_base)
+                .low_latency()
+                .build()
+                .unwrap()
+        };
+
+        let mut decoder = AlphaVideoDecoder::new(new_decoder(), new_decoder());
+
+        for i in 0..3 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 64)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base));
+
+            let mut alpha = VideoFrameMut::black(pixel_format, 64, 64)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base));
+
+            for line in alpha.planes_mut()[0].lines_mut() {
+                line.iter_mut().for_each(|b| *b = 128);
+            }
+
+            encoder.push(frame.freeze()).unwrap();
+            alpha_encoder.push(alpha.freeze()).unwrap();
+
+            let packet = encoder.take().unwrap().unwrap();
+            let alpha_packet = alpha_encoder.take().unwrap().unwrap();
+
+            // the last frame has no alpha channel
+            let packet = if i < 2 {
+                packet
+                    .into_mut()
+                    .with_block_additional(BLOCK_ADDITIONAL_ID_ALPHA, alpha_packet.data())
+                    .freeze()
+            } else {
+                packet
+            };
+
+            decoder.push(packet).unwrap();
+
+            let frame = decoder.take().unwrap().unwrap();
+
+            assert_eq!(frame.pixel_format().name(), "yuva420p");
+            assert_eq!(frame.pts().with_time_base(time_base).timestamp(), i);
+
+            let alpha = frame.planes()[3].line(32).unwrap()[32];
+
+            if i < 2 {
+                assert!((alpha as i32 - 128).abs() < 8);
+            } else {
+                assert_eq!(alpha, 255);
+            }
+
+            assert!(Compositor::builder(frame).build().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_av1_options() {
+        let builder = VideoDecoder::builder("mpeg4").unwrap();
+
+        assert!(builder
+            .options()
+            .iter()
+            .any(|option| option.name() == "threads"));
+
+        // mpeg4 does not have any of the AV1 options
+        assert!(builder.tile_threads(2).build().is_err());
+
+        let res = VideoDecoder::builder("mpeg4")
+            .unwrap()
+            .operating_point(1)
+            .build();
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let res = VideoDecoder::builder("mpeg4")
+            .unwrap()
+            .set_option("threads", 1)
+            .build();
+
+        assert!(res.is_ok());
+
+        let res = VideoDecoder::builder("mpeg4")
+            .unwrap()
+            .set_option("no_such_option", 1)
+            .set_option("threads", 1)
+            .build();
+
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "unknown options: no_such_option"
+        );
+
+        let res = VideoDecoder::builder("mpeg4")
+            .unwrap()
+            .set_option("thr\0eads", 1)
+            .build();
+
+        assert!(res.is_err());
+
+        let res = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(PixelFormat::from_str("yuv420p").unwrap())
+            .width(64)
+            .height(64)
+            .time_base(TimeBase::new(1, 25))
+            .set_option("threads", "1\0")
+            .build();
+
+        assert!(res.is_err());
+
+        let res = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(PixelFormat::from_str("yuv420p").unwrap())
+            .width(64)
+            .height(64)
+            .time_base(TimeBase::new(1, 25))
+            .set_option("no_such_option", 1)
+            .build();
+
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "unknown options: no_such_option"
+        );
+    }
+
+    #[test]
+    fn test_frame_transforms() {
+        let time_base = TimeBase::new(1, 25);
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let mut frame = VideoFrameMut::black(pixel_format, 64, 48).with_time_base(time_base);
+
+        // every luma sample is set to its column index
+        for line in frame.planes_mut()[0].lines_mut() {
+            for (i, sample) in line.iter_mut().enumerate() {
+                *sample = i as u8;
+            }
+        }
+
+        let frame = frame
+            .freeze()
+            .with_pts(Timestamp::new(7, time_base))
+            .with_sample_aspect_ratio(Some(Rational::new(1, 1)));
+
+        let cropped = frame.cropped(Rect::new(16, 8, 32, 16)).unwrap();
+
+        assert_eq!(cropped.width(), 32);
+        assert_eq!(cropped.height(), 16);
+        assert_eq!(cropped.pts(), frame.pts());
+        assert_eq!(cropped.planes()[0].line(0).unwrap()[0], 16);
+
+        assert!(frame.cropped(Rect::new(48, 0, 32, 16)).is_err());
+
+        let scaled = frame.scaled(32, 48, Algorithm::Bilinear).unwrap();
+
+        assert_eq!(scaled.width(), 32);
+        assert_eq!(scaled.height(), 48);
+        assert_eq!(scaled.pts(), frame.pts());
+        assert_eq!(scaled.sample_aspect_ratio(), Some(Rational::new(2, 1)));
+
+        let padded = frame.padded(64, 64, 0, 8).unwrap();
+
+        assert_eq!(padded.width(), 64);
+        assert_eq!(padded.height(), 64);
+        assert_eq!(padded.pts(), frame.pts());
+        assert_eq!(padded.sample_aspect_ratio(), Some(Rational::new(1, 1)));
+        assert_eq!(padded.planes()[0].line(0).unwrap()[8], 16);
+        assert_eq!(padded.planes()[0].line(8).unwrap()[8], 8);
+
+        assert!(frame.padded(64, 64, 8, 0).is_err());
+    }
+
+    #[test]
+    fn test_compositor() {
+        let time_base = TimeBase::new(1, 25);
+
+        let mut overlay = VideoFrameMut::black(PixelFormat::from_str("rgba").unwrap(), 16, 16);
+
+        for line in overlay.planes_mut()[0].lines_mut() {
+            for sample in line.iter_mut() {
+                *sample = 255;
+            }
+        }
+
+        let overlay = overlay.freeze();
+
+        let mut compositor = Compositor::builder(overlay.clone())
+            .position(-8, 40)
+            .time_window(Timestamp::new(1, time_base), Timestamp::new(2, time_base))
+            .build()
+            .unwrap();
+
+        let frame = VideoFrameMut::black(PixelFormat::from_str("yuv420p").unwrap(), 64, 48)
+            .with_time_base(time_base)
+            .freeze();
+
+        // outside of the time window
+        let res = compositor
+            .compose(frame.clone().with_pts(Timestamp::new(0, time_base)))
+            .unwrap();
+
+        assert_eq!(res.planes()[0].line(40).unwrap()[0], 16);
+
+        let res = compositor
+            .compose(frame.clone().with_pts(Timestamp::new(1, time_base)))
+            .unwrap();
+
+        let luma = &res.planes()[0];
+
+        assert_eq!(luma.line(39).unwrap()[0], 16);
+        assert_eq!(luma.line(40).unwrap()[0], 235);
+        assert_eq!(luma.line(47).unwrap()[7], 235);
+        assert_eq!(luma.line(47).unwrap()[8], 16);
+
+        // the source frame must not be modified
+        assert_eq!(frame.planes()[0].line(40).unwrap()[0], 16);
+
+        // overlays without alpha are not supported
+        assert!(Compositor::builder(res).build().is_err());
+
+        let mut compositor = Compositor::builder(overlay.clone())
+            .opacity(0.5)
+            .build()
+            .unwrap();
+
+        let frame = VideoFrameMut::black(PixelFormat::from_str("bgra").unwrap(), 32, 32).freeze();
+
+        let res = compositor.compose(frame).unwrap();
+
+        assert_eq!(
+            &res.planes()[0].line(0).unwrap()[..4],
+            &[128, 128, 128, 255]
+        );
+        assert_eq!(&res.planes()[0].line(16).unwrap()[..4], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_encoder_timestamps() {
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(64)
+            .frame_rate(Rational::new(30000, 1001))
+            .build()
+            .unwrap();
+
+        assert_eq!(encoder.time_base().num(), 1001);
+        assert_eq!(encoder.time_base().den(), 30000);
+
+        let micros = TimeBase::MICROSECONDS;
+
+        let mut timestamps = Vec::new();
+
+        // frame timestamps in a different time base are rescaled and
+        // missing timestamps are generated from the frame rate
+        for pts in [Some(0), Some(33_367), None].iter() {
+            let mut frame = VideoFrameMut::black(pixel_format, 64, 64).with_time_base(micros);
+
+            if let Some(pts) = pts {
+                frame = frame.with_pts(Timestamp::new(*pts, micros));
+            }
+
+            encoder.push(frame.freeze()).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                timestamps.push(packet.pts().timestamp());
+            }
+        }
+
+        assert_eq!(timestamps, [0, 1, 2]);
+
+        // frames colliding with the previous frame in the encoder time base
+        // are dropped, non-increasing frame timestamps are rejected
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(64)
+            .time_base(TimeBase::new(1, 25))
+            .build()
+            .unwrap();
+
+        for (pts, ok) in [(0, true), (10_000, true), (10_000, false)].iter() {
+            let frame = VideoFrameMut::black(pixel_format, 64, 64)
+                .with_time_base(micros)
+                .with_pts(Timestamp::new(*pts, micros))
+                .freeze();
+
+            let res = encoder.push(frame);
+
+            assert_eq!(res.is_ok(), *ok);
+
+            while encoder.take().unwrap().is_some() {}
+        }
+
+        assert_eq!(encoder.dropped_frames(), 1);
+    }
+
+    #[test]
+    fn test_invalid_frame_rate() {
+        let res = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(PixelFormat::from_str("yuv420p").unwrap())
+            .width(64)
+            .height(64)
+            .frame_rate(Rational::new(0, 1))
+            .build();
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_encoder_millisecond_timestamps() {
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(64)
+            .time_base(TimeBase::new(1001, 24000))
+            .build()
+            .unwrap();
+
+        let millis = TimeBase::new(1, 1000);
+
+        let mut timestamps = Vec::new();
+
+        // 24000/1001 fps timestamps truncated to milliseconds (e.g. by the
+        // matroska demuxer) must map to consecutive encoder ticks
+        for i in 0..48 {
+            let pts = (i * 1001 * 1000) / 24000;
+
+            let frame = VideoFrameMut::black(pixel_format, 64, 64)
+                .with_time_base(millis)
+                .with_pts(Timestamp::new(pts, millis))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                timestamps.push(packet.pts().timestamp());
+            }
+        }
+
+        encoder.flush().unwrap();
+
+        while let Some(packet) = encoder.take().unwrap() {
+            timestamps.push(packet.pts().timestamp());
+        }
+
+        timestamps.sort_unstable();
+
+        assert_eq!(timestamps, (0..48).collect::<Vec<i64>>());
+        assert_eq!(encoder.dropped_frames(), 0);
+    }
+}
@@ -21,6 +21,9 @@ AVFrame* ffw_frame_scaler_scale(FrameScaler* scaler, const AVFrame* src);
 void ffw_frame_scaler_free(FrameScaler* scaler);
 int ffw_alg_id_to_flags(size_t id);
 
+// defined in frame.c
+int ffw_frame_copy_hdr_metadata(AVFrame* dst, const AVFrame* src);
+
 static AVFrame* alloc_frame(int format, int width, int height) {
     AVFrame* frame = av_frame_alloc();
 
@@ -89,6 +92,20 @@ AVFrame* ffw_frame_scaler_scale(FrameScaler* scaler, const AVFrame* src) {
 
     dst->pts = src->pts;
 
+    // keep the display aspect ratio
+    if (src->sample_aspect_ratio.num) {
+        dst->sample_aspect_ratio = av_mul_q(
+            (AVRational) { scaler->theight * src->width, scaler->twidth * src->height },
+            src->sample_aspect_ratio);
+    } else {
+        dst->sample_aspect_ratio = src->sample_aspect_ratio;
+    }
+
+    // the scaler does not change primaries or transfer characteristic
+    if (ffw_frame_copy_hdr_metadata(dst, src) < 0) {
+        return NULL;
+    }
+
     sws_scale(scaler->scale_context,
         (const uint8_t* const*)src->data, src->linesize, 0, src->height,
         dst->data, dst->linesize);
@@ -0,0 +1,359 @@
+//! Text overlay.
+
+use crate::{
+    codec::{
+        video::{filter::VideoFilter, VideoFrame},
+        CodecError, Filter, VideoCodecParameters,
+    },
+    math::Rational,
+    time::TimeBase,
+    Error,
+};
+
+/// RGBA color.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    /// Create a new opaque color.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 255)
+    }
+
+    /// Create a new color with a given alpha (0 is fully transparent).
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Get the FFmpeg representation of the color.
+    fn to_option_value(self) -> String {
+        format!("0x{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+    }
+}
+
+/// Horizontal text alignment.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HorizontalAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl HorizontalAlignment {
+    /// Get the x coordinate expression for a given margin.
+    fn expression(self, margin: usize) -> String {
+        match self {
+            Self::Left => format!("{}", margin),
+            Self::Center => String::from("(w-text_w)/2"),
+            Self::Right => format!("w-text_w-{}", margin),
+        }
+    }
+}
+
+/// Vertical text alignment.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VerticalAlignment {
+    Top,
+    Center,
+    Bottom,
+}
+
+impl VerticalAlignment {
+    /// Get the y coordinate expression for a given margin.
+    fn expression(self, margin: usize) -> String {
+        match self {
+            Self::Top => format!("{}", margin),
+            Self::Center => String::from("(h-text_h)/2"),
+            Self::Bottom => format!("h-text_h-{}", margin),
+        }
+    }
+}
+
+/// Escape a given filter option value.
+fn escape_option_value(value: &str) -> String {
+    escape(value, &['\\', '\'', ':'])
+}
+
+/// Escape a given filter graph description.
+fn escape_graph_description(description: &str) -> String {
+    escape(description, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+/// Escape given characters using backslashes.
+fn escape(value: &str, special: &[char]) -> String {
+    let mut res = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if special.contains(&c) {
+            res.push('\\');
+        }
+
+        res.push(c);
+    }
+
+    res
+}
+
+/// Builder for a text overlay.
+pub struct TextOverlayBuilder {
+    codec_parameters: VideoCodecParameters,
+    time_base: TimeBase,
+    frame_rate: Option<Rational>,
+
+    text: String,
+    font_file: Option<String>,
+    font_size: u32,
+    font_color: Color,
+    text_box: Option<(Color, u32)>,
+    horizontal_alignment: HorizontalAlignment,
+    vertical_alignment: VerticalAlignment,
+    margin: usize,
+    timecode: Option<(String, Rational)>,
+}
+
+impl TextOverlayBuilder {
+    /// Create a new text overlay builder.
+    fn new(codec_parameters: &VideoCodecParameters) -> Self {
+        Self {
+            codec_parameters: codec_parameters.clone(),
+            time_base: TimeBase::MICROSECONDS,
+            frame_rate: None,
+
+            text: String::new(),
+            font_file: None,
+            font_size: 16,
+            font_color: Color::WHITE,
+            text_box: None,
+            horizontal_alignment: HorizontalAlignment::Left,
+            vertical_alignment: VerticalAlignment::Top,
+            margin: 0,
+            timecode: None,
+        }
+    }
+
+    /// Set input time base (all input frames will be rescaled into this time
+    /// base). The default time base is in microseconds.
+    pub fn input_time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Set input frame rate. The frame rate is unknown by default.
+    pub fn input_frame_rate(mut self, frame_rate: Rational) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Set the text. The text is rendered as is (i.e. no FFmpeg text
+    /// expansion is done). The text is empty by default.
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    /// Set path to the font file. The default font of the system
+    /// (provided by fontconfig) is used if the font file is not set.
+    pub fn font_file(mut self, path: &str) -> Self {
+        self.font_file = Some(path.to_string());
+        self
+    }
+
+    /// Set font size in pixels. The default is 16.
+    pub fn font_size(mut self, size: u32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Set font color. The default is white.
+    pub fn font_color(mut self, color: Color) -> Self {
+        self.font_color = color;
+        self
+    }
+
+    /// Draw a box of a given color with a given border width (in pixels)
+    /// behind the text. No box is drawn by default.
+    pub fn text_box(mut self, color: Color, border_width: u32) -> Self {
+        self.text_box = Some((color, border_width));
+        self
+    }
+
+    /// Set text alignment within the frame. The default is top left.
+    pub fn alignment(
+        mut self,
+        horizontal: HorizontalAlignment,
+        vertical: VerticalAlignment,
+    ) -> Self {
+        self.horizontal_alignment = horizontal;
+        self.vertical_alignment = vertical;
+        self
+    }
+
+    /// Set distance (in pixels) of the text from the frame edges it is
+    /// aligned to. The default is 0.
+    pub fn margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Render a running SMPTE timecode (e.g. `"01:00:00:00"` or
+    /// `"01:00:00;00"` for drop frame) starting at a given value and
+    /// advancing by one frame for each input frame at a given rate. The
+    /// text is used as a prefix of the timecode.
+    pub fn timecode(mut self, start: &str, rate: Rational) -> Self {
+        self.timecode = Some((start.to_string(), rate));
+        self
+    }
+
+    /// Get the drawtext filter options.
+    fn filter_options(&self) -> String {
+        let mut options = vec![
+            format!("text={}", escape_option_value(&self.text)),
+            String::from("expansion=none"),
+            format!("fontsize={}", self.font_size),
+            format!("fontcolor={}", self.font_color.to_option_value()),
+            format!("x={}", self.horizontal_alignment.expression(self.margin)),
+            format!("y={}", self.vertical_alignment.expression(self.margin)),
+        ];
+
+        if let Some(font_file) = self.font_file.as_ref() {
+            options.push(format!("fontfile={}", escape_option_value(font_file)));
+        }
+
+        if let Some((color, border_width)) = self.text_box {
+            options.push(String::from("box=1"));
+            options.push(format!("boxcolor={}", color.to_option_value()));
+            options.push(format!("boxborderw={}", border_width));
+        }
+
+        if let Some((start, rate)) = self.timecode.as_ref() {
+            options.push(format!("timecode={}", escape_option_value(start)));
+            options.push(format!("timecode_rate={}/{}", rate.num(), rate.den()));
+        }
+
+        options.join(":")
+    }
+
+    /// Build the text overlay.
+    pub fn build(self) -> Result<TextOverlay, Error> {
+        let description = format!(
+            "drawtext={}",
+            escape_graph_description(&self.filter_options())
+        );
+
+        let mut builder = VideoFilter::builder(&self.codec_parameters, &description)
+            .input_time_base(self.time_base);
+
+        if let Some(frame_rate) = self.frame_rate {
+            builder = builder.input_frame_rate(frame_rate);
+        }
+
+        let res = TextOverlay {
+            filter: builder.build()?,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Text overlay. It is a convenience wrapper around a video filter graph
+/// containing a single drawtext filter (i.e. FFmpeg must be built with
+/// libfreetype). The text can be changed at runtime.
+pub struct TextOverlay {
+    filter: VideoFilter,
+}
+
+impl TextOverlay {
+    /// Get a text overlay builder for frames described by given codec
+    /// parameters.
+    pub fn builder(codec_parameters: &VideoCodecParameters) -> TextOverlayBuilder {
+        TextOverlayBuilder::new(codec_parameters)
+    }
+
+    /// Change the text. The change applies to all frames taken from the
+    /// overlay after this call (i.e. including frames pushed before and
+    /// not taken yet).
+    pub fn set_text(&mut self, text: &str) -> Result<(), Error> {
+        let arg = format!("text={}", escape_option_value(text));
+
+        self.filter.send_command("drawtext", "reinit", &arg)
+    }
+
+    /// Get time base of the output frames.
+    pub fn output_time_base(&self) -> TimeBase {
+        self.filter.output_time_base()
+    }
+}
+
+impl Filter for TextOverlay {
+    type Frame = VideoFrame;
+
+    fn try_push(&mut self, frame: VideoFrame) -> Result<(), CodecError> {
+        self.filter.try_push(frame)
+    }
+
+    fn try_flush(&mut self) -> Result<(), CodecError> {
+        self.filter.try_flush()
+    }
+
+    fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        self.filter.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{
+        escape_graph_description, escape_option_value, Color, HorizontalAlignment, TextOverlay,
+        VerticalAlignment,
+    };
+
+    use crate::{
+        codec::{video::PixelFormat, VideoCodecParameters},
+        math::Rational,
+    };
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(escape_option_value("12:34 it's"), "12\\:34 it\\'s");
+        assert_eq!(
+            escape_graph_description("text=a\\:b,[c];d"),
+            "text=a\\\\:b\\,\\[c\\]\\;d"
+        );
+    }
+
+    #[test]
+    fn test_filter_options() {
+        let codec_parameters = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(PixelFormat::from_str("yuv420p").unwrap())
+            .width(64)
+            .height(64)
+            .build();
+
+        let builder = TextOverlay::builder(&codec_parameters)
+            .text("Score: 1:0")
+            .font_size(24)
+            .font_color(Color::rgba(255, 0, 0, 128))
+            .text_box(Color::BLACK, 4)
+            .alignment(HorizontalAlignment::Right, VerticalAlignment::Bottom)
+            .margin(10)
+            .timecode("01:00:00:00", Rational::new(25, 1));
+
+        assert_eq!(
+            builder.filter_options(),
+            "text=Score\\: 1\\:0:expansion=none:fontsize=24:fontcolor=0xff000080:\
+             x=w-text_w-10:y=h-text_h-10:box=1:boxcolor=0x000000ff:boxborderw=4:\
+             timecode=01\\:00\\:00\\:00:timecode_rate=25/1"
+        );
+    }
+}
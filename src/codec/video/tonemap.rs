@@ -0,0 +1,320 @@
+//! HDR to SDR tone mapping.
+
+use crate::{
+    codec::{
+        video::{filter::VideoFilter, HdrMetadata, PixelFormat, VideoFrame},
+        CodecError, Filter, VideoCodecParameters,
+    },
+    math::Rational,
+    time::TimeBase,
+    Error,
+};
+
+/// Tone mapping algorithm.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ToneMappingAlgorithm {
+    /// Only desaturate out-of-range colors.
+    None,
+    /// Hard-clip out-of-range values.
+    Clip,
+    /// Stretch the reference range linearly to the target range.
+    Linear,
+    /// Fit a logarithmic transfer curve between the ranges.
+    Gamma,
+    /// Simple Reinhard tone mapping operator.
+    Reinhard,
+    /// Filmic tone mapping curve preserving both dark and bright details.
+    Hable,
+    /// Linear up to a threshold and Reinhard-like above it.
+    Mobius,
+}
+
+impl ToneMappingAlgorithm {
+    /// Get name of the algorithm as understood by the tonemap filter.
+    fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Clip => "clip",
+            Self::Linear => "linear",
+            Self::Gamma => "gamma",
+            Self::Reinhard => "reinhard",
+            Self::Hable => "hable",
+            Self::Mobius => "mobius",
+        }
+    }
+}
+
+/// Builder for the tone mapper.
+pub struct ToneMapperBuilder {
+    codec_parameters: VideoCodecParameters,
+    time_base: TimeBase,
+    frame_rate: Option<Rational>,
+
+    algorithm: ToneMappingAlgorithm,
+    parameter: Option<f64>,
+    desaturation: Option<f64>,
+    target_nits: f64,
+    peak: Option<f64>,
+    output_pixel_format: PixelFormat,
+    input_metadata: Option<HdrMetadata>,
+}
+
+impl ToneMapperBuilder {
+    /// Create a new tone mapper builder.
+    fn new(codec_parameters: &VideoCodecParameters) -> Self {
+        Self {
+            codec_parameters: codec_parameters.clone(),
+            time_base: TimeBase::MICROSECONDS,
+            frame_rate: None,
+
+            algorithm: ToneMappingAlgorithm::Hable,
+            parameter: None,
+            desaturation: None,
+            target_nits: 100.0,
+            peak: None,
+            output_pixel_format: codec_parameters.pixel_format(),
+            input_metadata: None,
+        }
+    }
+
+    /// Set input time base (all input frames will be rescaled into this time
+    /// base). The default time base is in microseconds.
+    pub fn input_time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Set input frame rate. The frame rate is unknown by default.
+    pub fn input_frame_rate(mut self, frame_rate: Rational) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Set the tone mapping algorithm. The default is `Hable`.
+    pub fn algorithm(mut self, algorithm: ToneMappingAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the algorithm specific tuning parameter (e.g. the contrast for
+    /// `Reinhard` or the transition point for `Mobius`). The filter default
+    /// is used if not set.
+    pub fn parameter(mut self, parameter: f64) -> Self {
+        self.parameter = Some(parameter);
+        self
+    }
+
+    /// Set the desaturation strength for overly bright colors (0.0 disables
+    /// the desaturation). The filter default (2.0) is used if not set.
+    pub fn desaturation(mut self, strength: f64) -> Self {
+        self.desaturation = Some(strength);
+        self
+    }
+
+    /// Set the nominal peak luminance of the target SDR display in nits.
+    /// The default is 100.
+    pub fn target_nits(mut self, nits: f64) -> Self {
+        self.target_nits = nits;
+        self
+    }
+
+    /// Override the signal peak (relative to the target luminance). By
+    /// default, the peak is derived from the mastering display and content
+    /// light level side data of each frame.
+    pub fn peak(mut self, peak: f64) -> Self {
+        self.peak = Some(peak);
+        self
+    }
+
+    /// Set pixel format of the output frames. The input pixel format is
+    /// used by default.
+    pub fn output_pixel_format(mut self, format: PixelFormat) -> Self {
+        self.output_pixel_format = format;
+        self
+    }
+
+    /// Set color metadata for input frames that do not carry their own
+    /// (e.g. the metadata of the source stream). Only the missing
+    /// properties of each frame are filled in. The frame metadata are used
+    /// as is by default.
+    pub fn input_hdr_metadata(mut self, metadata: HdrMetadata) -> Self {
+        self.input_metadata = Some(metadata);
+        self
+    }
+
+    /// Get the tonemap filter options.
+    fn tonemap_options(&self) -> String {
+        let mut options = vec![format!("tonemap={}", self.algorithm.name())];
+
+        if let Some(parameter) = self.parameter {
+            options.push(format!("param={}", parameter));
+        }
+
+        if let Some(desaturation) = self.desaturation {
+            options.push(format!("desat={}", desaturation));
+        }
+
+        if let Some(peak) = self.peak {
+            options.push(format!("peak={}", peak));
+        }
+
+        options.join(":")
+    }
+
+    /// Get the filter graph description.
+    fn graph_description(&self) -> String {
+        format!(
+            "zscale=transfer=linear:npl={},format=gbrpf32le,zscale=primaries=bt709,\
+             tonemap={},zscale=transfer=bt709:matrix=bt709:range=tv,format={}",
+            self.target_nits,
+            self.tonemap_options(),
+            self.output_pixel_format.name()
+        )
+    }
+
+    /// Build the tone mapper.
+    pub fn build(self) -> Result<ToneMapper, Error> {
+        if self.target_nits <= 0.0 {
+            return Err(Error::new("the target luminance must be positive"));
+        } else if self.desaturation.map(|d| d < 0.0).unwrap_or(false) {
+            return Err(Error::new("the desaturation strength must not be negative"));
+        } else if self.peak.map(|p| p <= 0.0).unwrap_or(false) {
+            return Err(Error::new("the signal peak must be positive"));
+        }
+
+        let description = self.graph_description();
+
+        let mut builder = VideoFilter::builder(&self.codec_parameters, &description)
+            .input_time_base(self.time_base);
+
+        if let Some(frame_rate) = self.frame_rate {
+            builder = builder.input_frame_rate(frame_rate);
+        }
+
+        let res = ToneMapper {
+            filter: builder.build()?,
+            input_metadata: self.input_metadata,
+        };
+
+        Ok(res)
+    }
+}
+
+/// HDR to SDR tone mapper. It is a convenience wrapper around a video filter
+/// graph converting frames into linear light, tone mapping them using the
+/// tonemap filter and converting the result into BT.709 (i.e. FFmpeg must be
+/// built with libzimg).
+///
+/// The transfer characteristic, color primaries and color space of the
+/// input are taken from each frame. The signal peak is derived from the
+/// mastering display and content light level side data of each frame unless
+/// it is set explicitly.
+pub struct ToneMapper {
+    filter: VideoFilter,
+    input_metadata: Option<HdrMetadata>,
+}
+
+impl ToneMapper {
+    /// Get a tone mapper builder for frames described by given codec
+    /// parameters.
+    pub fn builder(codec_parameters: &VideoCodecParameters) -> ToneMapperBuilder {
+        ToneMapperBuilder::new(codec_parameters)
+    }
+
+    /// Get time base of the output frames.
+    pub fn output_time_base(&self) -> TimeBase {
+        self.filter.output_time_base()
+    }
+
+    /// Fill in color metadata missing in a given frame.
+    fn complete_metadata(&self, frame: VideoFrame) -> VideoFrame {
+        let fallback = match self.input_metadata.as_ref() {
+            Some(metadata) => metadata,
+            None => return frame,
+        };
+
+        let current = frame.hdr_metadata();
+
+        let metadata = HdrMetadata::new()
+            .with_color_primaries(current.color_primaries().or(fallback.color_primaries()))
+            .with_transfer_characteristic(
+                current
+                    .transfer_characteristic()
+                    .or(fallback.transfer_characteristic()),
+            )
+            .with_color_space(current.color_space().or(fallback.color_space()))
+            .with_mastering_display(
+                current
+                    .mastering_display()
+                    .or(fallback.mastering_display())
+                    .copied(),
+            )
+            .with_content_light_level(
+                current
+                    .content_light_level()
+                    .or(fallback.content_light_level()),
+            );
+
+        if metadata == current {
+            frame
+        } else {
+            frame.with_hdr_metadata(&metadata)
+        }
+    }
+}
+
+impl Filter for ToneMapper {
+    type Frame = VideoFrame;
+
+    fn try_push(&mut self, frame: VideoFrame) -> Result<(), CodecError> {
+        let frame = self.complete_metadata(frame);
+
+        self.filter.try_push(frame)
+    }
+
+    fn try_flush(&mut self) -> Result<(), CodecError> {
+        self.filter.try_flush()
+    }
+
+    fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        self.filter.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{ToneMapper, ToneMappingAlgorithm};
+
+    use crate::codec::{video::PixelFormat, VideoCodecParameters};
+
+    #[test]
+    fn test_graph_description() {
+        let codec_parameters = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(PixelFormat::from_str("yuv420p10le").unwrap())
+            .width(64)
+            .height(64)
+            .build();
+
+        let builder = ToneMapper::builder(&codec_parameters)
+            .algorithm(ToneMappingAlgorithm::Mobius)
+            .parameter(0.3)
+            .desaturation(0.0)
+            .target_nits(203.0)
+            .output_pixel_format(PixelFormat::from_str("yuv420p").unwrap());
+
+        assert_eq!(
+            builder.graph_description(),
+            "zscale=transfer=linear:npl=203,format=gbrpf32le,zscale=primaries=bt709,\
+             tonemap=tonemap=mobius:param=0.3:desat=0,\
+             zscale=transfer=bt709:matrix=bt709:range=tv,format=yuv420p"
+        );
+
+        let builder = ToneMapper::builder(&codec_parameters);
+
+        assert_eq!(builder.tonemap_options(), "tonemap=hable");
+        assert!(builder.target_nits(0.0).build().is_err());
+    }
+}
@@ -0,0 +1,126 @@
+#include <libavcodec/avcodec.h>
+#include <libavformat/avformat.h>
+#include <libavutil/encryption_info.h>
+
+AVEncryptionInfo* ffw_packet_get_encryption_info(const AVPacket* packet);
+AVEncryptionInitInfo* ffw_packet_get_encryption_init_info(const AVPacket* packet);
+AVEncryptionInitInfo* ffw_stream_get_encryption_init_info(const AVStream* stream);
+
+uint32_t ffw_encryption_info_get_scheme(const AVEncryptionInfo* info);
+uint32_t ffw_encryption_info_get_crypt_byte_block(const AVEncryptionInfo* info);
+uint32_t ffw_encryption_info_get_skip_byte_block(const AVEncryptionInfo* info);
+const uint8_t* ffw_encryption_info_get_key_id(const AVEncryptionInfo* info, uint32_t* size);
+const uint8_t* ffw_encryption_info_get_iv(const AVEncryptionInfo* info, uint32_t* size);
+uint32_t ffw_encryption_info_get_subsample_count(const AVEncryptionInfo* info);
+void ffw_encryption_info_get_subsample(const AVEncryptionInfo* info, uint32_t index, uint32_t* clear, uint32_t* protected);
+void ffw_encryption_info_free(AVEncryptionInfo* info);
+
+const uint8_t* ffw_encryption_init_info_get_system_id(const AVEncryptionInitInfo* info, uint32_t* size);
+uint32_t ffw_encryption_init_info_get_key_id_count(const AVEncryptionInitInfo* info);
+const uint8_t* ffw_encryption_init_info_get_key_id(const AVEncryptionInitInfo* info, uint32_t index, uint32_t* size);
+const uint8_t* ffw_encryption_init_info_get_data(const AVEncryptionInitInfo* info, uint32_t* size);
+const AVEncryptionInitInfo* ffw_encryption_init_info_get_next(const AVEncryptionInitInfo* info);
+void ffw_encryption_init_info_free(AVEncryptionInitInfo* info);
+
+AVEncryptionInfo* ffw_packet_get_encryption_info(const AVPacket* packet) {
+    uint8_t* data;
+    int size;
+
+    data = av_packet_get_side_data(packet, AV_PKT_DATA_ENCRYPTION_INFO, &size);
+    if (!data) {
+        return NULL;
+    }
+
+    return av_encryption_info_get_side_data(data, size);
+}
+
+AVEncryptionInitInfo* ffw_packet_get_encryption_init_info(const AVPacket* packet) {
+    uint8_t* data;
+    int size;
+
+    data = av_packet_get_side_data(packet, AV_PKT_DATA_ENCRYPTION_INIT_INFO, &size);
+    if (!data) {
+        return NULL;
+    }
+
+    return av_encryption_init_info_get_side_data(data, size);
+}
+
+AVEncryptionInitInfo* ffw_stream_get_encryption_init_info(const AVStream* stream) {
+    uint8_t* data;
+    int size;
+
+    data = av_stream_get_side_data(stream, AV_PKT_DATA_ENCRYPTION_INIT_INFO, &size);
+    if (!data) {
+        return NULL;
+    }
+
+    return av_encryption_init_info_get_side_data(data, size);
+}
+
+uint32_t ffw_encryption_info_get_scheme(const AVEncryptionInfo* info) {
+    return info->scheme;
+}
+
+uint32_t ffw_encryption_info_get_crypt_byte_block(const AVEncryptionInfo* info) {
+    return info->crypt_byte_block;
+}
+
+uint32_t ffw_encryption_info_get_skip_byte_block(const AVEncryptionInfo* info) {
+    return info->skip_byte_block;
+}
+
+const uint8_t* ffw_encryption_info_get_key_id(const AVEncryptionInfo* info, uint32_t* size) {
+    *size = info->key_id_size;
+
+    return info->key_id;
+}
+
+const uint8_t* ffw_encryption_info_get_iv(const AVEncryptionInfo* info, uint32_t* size) {
+    *size = info->iv_size;
+
+    return info->iv;
+}
+
+uint32_t ffw_encryption_info_get_subsample_count(const AVEncryptionInfo* info) {
+    return info->subsample_count;
+}
+
+void ffw_encryption_info_get_subsample(const AVEncryptionInfo* info, uint32_t index, uint32_t* clear, uint32_t* protected) {
+    *clear = info->subsamples[index].bytes_of_clear_data;
+    *protected = info->subsamples[index].bytes_of_protected_data;
+}
+
+void ffw_encryption_info_free(AVEncryptionInfo* info) {
+    av_encryption_info_free(info);
+}
+
+const uint8_t* ffw_encryption_init_info_get_system_id(const AVEncryptionInitInfo* info, uint32_t* size) {
+    *size = info->system_id_size;
+
+    return info->system_id;
+}
+
+uint32_t ffw_encryption_init_info_get_key_id_count(const AVEncryptionInitInfo* info) {
+    return info->num_key_ids;
+}
+
+const uint8_t* ffw_encryption_init_info_get_key_id(const AVEncryptionInitInfo* info, uint32_t index, uint32_t* size) {
+    *size = info->key_id_size;
+
+    return info->key_ids[index];
+}
+
+const uint8_t* ffw_encryption_init_info_get_data(const AVEncryptionInitInfo* info, uint32_t* size) {
+    *size = info->data_size;
+
+    return info->data;
+}
+
+const AVEncryptionInitInfo* ffw_encryption_init_info_get_next(const AVEncryptionInitInfo* info) {
+    return info->next;
+}
+
+void ffw_encryption_init_info_free(AVEncryptionInitInfo* info) {
+    av_encryption_init_info_free(info);
+}
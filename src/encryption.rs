@@ -0,0 +1,222 @@
+//! Common encryption (CENC) info.
+//!
+//! Demuxers (e.g. MP4) export information about encrypted content as packet
+//! and stream side data. Packet data remains encrypted, it is up to the user
+//! to decrypt it (e.g. using an external decryptor) before passing the
+//! packets to a decoder.
+
+use std::{os::raw::c_void, slice};
+
+extern "C" {
+    fn ffw_encryption_info_get_scheme(info: *const c_void) -> u32;
+    fn ffw_encryption_info_get_crypt_byte_block(info: *const c_void) -> u32;
+    fn ffw_encryption_info_get_skip_byte_block(info: *const c_void) -> u32;
+    fn ffw_encryption_info_get_key_id(info: *const c_void, size: *mut u32) -> *const u8;
+    fn ffw_encryption_info_get_iv(info: *const c_void, size: *mut u32) -> *const u8;
+    fn ffw_encryption_info_get_subsample_count(info: *const c_void) -> u32;
+    fn ffw_encryption_info_get_subsample(
+        info: *const c_void,
+        index: u32,
+        clear: *mut u32,
+        protected: *mut u32,
+    );
+    fn ffw_encryption_info_free(info: *mut c_void);
+
+    fn ffw_encryption_init_info_get_system_id(info: *const c_void, size: *mut u32) -> *const u8;
+    fn ffw_encryption_init_info_get_key_id_count(info: *const c_void) -> u32;
+    fn ffw_encryption_init_info_get_key_id(
+        info: *const c_void,
+        index: u32,
+        size: *mut u32,
+    ) -> *const u8;
+    fn ffw_encryption_init_info_get_data(info: *const c_void, size: *mut u32) -> *const u8;
+    fn ffw_encryption_init_info_get_next(info: *const c_void) -> *const c_void;
+    fn ffw_encryption_init_info_free(info: *mut c_void);
+}
+
+/// Helper function for copying byte arrays returned by the C getters.
+unsafe fn to_vec(data: *const u8, size: u32) -> Vec<u8> {
+    if data.is_null() || size == 0 {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(data, size as usize).to_vec()
+    }
+}
+
+/// Encryption info of a subsample. Each subsample consists of a clear part
+/// followed by a protected part.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Subsample {
+    clear_bytes: u32,
+    protected_bytes: u32,
+}
+
+impl Subsample {
+    /// Get the number of bytes that are not encrypted.
+    pub fn clear_bytes(&self) -> u32 {
+        self.clear_bytes
+    }
+
+    /// Get the number of bytes that are protected. If pattern encryption is
+    /// used, the pattern applies only to the protected bytes.
+    pub fn protected_bytes(&self) -> u32 {
+        self.protected_bytes
+    }
+}
+
+/// Encryption info of a single packet.
+#[derive(Debug, Clone)]
+pub struct EncryptionInfo {
+    scheme: [u8; 4],
+    crypt_byte_block: u32,
+    skip_byte_block: u32,
+    key_id: Vec<u8>,
+    iv: Vec<u8>,
+    subsamples: Vec<Subsample>,
+}
+
+impl EncryptionInfo {
+    /// Create encryption info from its raw representation. The raw object
+    /// will be freed.
+    pub(crate) unsafe fn from_raw_ptr(ptr: *mut c_void) -> Self {
+        let mut size = 0;
+
+        let key_id = ffw_encryption_info_get_key_id(ptr, &mut size);
+        let key_id = to_vec(key_id, size);
+
+        let iv = ffw_encryption_info_get_iv(ptr, &mut size);
+        let iv = to_vec(iv, size);
+
+        let subsample_count = ffw_encryption_info_get_subsample_count(ptr);
+
+        let mut subsamples = Vec::with_capacity(subsample_count as usize);
+
+        for i in 0..subsample_count {
+            let mut clear_bytes = 0;
+            let mut protected_bytes = 0;
+
+            ffw_encryption_info_get_subsample(ptr, i, &mut clear_bytes, &mut protected_bytes);
+
+            subsamples.push(Subsample {
+                clear_bytes,
+                protected_bytes,
+            });
+        }
+
+        let res = Self {
+            scheme: ffw_encryption_info_get_scheme(ptr).to_be_bytes(),
+            crypt_byte_block: ffw_encryption_info_get_crypt_byte_block(ptr),
+            skip_byte_block: ffw_encryption_info_get_skip_byte_block(ptr),
+            key_id,
+            iv,
+            subsamples,
+        };
+
+        ffw_encryption_info_free(ptr);
+
+        res
+    }
+
+    /// Get the encryption scheme fourcc (e.g. `b"cenc"` or `b"cbcs"`).
+    pub fn scheme(&self) -> [u8; 4] {
+        self.scheme
+    }
+
+    /// Get the number of encrypted 16-byte blocks in the encryption pattern.
+    /// Used only for pattern encryption.
+    pub fn crypt_byte_block(&self) -> u32 {
+        self.crypt_byte_block
+    }
+
+    /// Get the number of clear 16-byte blocks in the encryption pattern.
+    /// Used only for pattern encryption.
+    pub fn skip_byte_block(&self) -> u32 {
+        self.skip_byte_block
+    }
+
+    /// Get ID of the key used to encrypt the packet.
+    pub fn key_id(&self) -> &[u8] {
+        &self.key_id
+    }
+
+    /// Get the initialization vector.
+    pub fn iv(&self) -> &[u8] {
+        &self.iv
+    }
+
+    /// Get subsample encryption info. If there are no subsamples, the whole
+    /// packet is encrypted.
+    pub fn subsamples(&self) -> &[Subsample] {
+        &self.subsamples
+    }
+}
+
+/// Encryption initialization info (e.g. content of an MP4 `pssh` box).
+#[derive(Debug, Clone)]
+pub struct EncryptionInitInfo {
+    system_id: Vec<u8>,
+    key_ids: Vec<Vec<u8>>,
+    data: Vec<u8>,
+}
+
+impl EncryptionInitInfo {
+    /// Create a list of encryption init info from its raw representation.
+    /// The raw object will be freed.
+    pub(crate) unsafe fn from_raw_ptr(ptr: *mut c_void) -> Vec<Self> {
+        let mut res = Vec::new();
+
+        let mut current = ptr as *const c_void;
+
+        while !current.is_null() {
+            res.push(Self::from_raw_item(current));
+
+            current = ffw_encryption_init_info_get_next(current);
+        }
+
+        ffw_encryption_init_info_free(ptr);
+
+        res
+    }
+
+    /// Create encryption init info from a given item of the raw list.
+    unsafe fn from_raw_item(ptr: *const c_void) -> Self {
+        let mut size = 0;
+
+        let system_id = ffw_encryption_init_info_get_system_id(ptr, &mut size);
+        let system_id = to_vec(system_id, size);
+
+        let data = ffw_encryption_init_info_get_data(ptr, &mut size);
+        let data = to_vec(data, size);
+
+        let key_id_count = ffw_encryption_init_info_get_key_id_count(ptr);
+
+        let mut key_ids = Vec::with_capacity(key_id_count as usize);
+
+        for i in 0..key_id_count {
+            let key_id = ffw_encryption_init_info_get_key_id(ptr, i, &mut size);
+
+            key_ids.push(to_vec(key_id, size));
+        }
+
+        Self {
+            system_id,
+            key_ids,
+            data,
+        }
+    }
+
+    /// Get ID of the key system. The ID is empty if it is not known.
+    pub fn system_id(&self) -> &[u8] {
+        &self.system_id
+    }
+
+    /// Get IDs of the keys this init info is for.
+    pub fn key_ids(&self) -> &[Vec<u8>] {
+        &self.key_ids
+    }
+
+    /// Get key-system specific initialization data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
@@ -0,0 +1,247 @@
+//! Container-level codec private data.
+//!
+//! FFmpeg stores codec initialization data of a stream as codec extradata.
+//! The format of the extradata is not always the same as the format used by
+//! containers (e.g. the Matroska `CodecPrivate` element). For example, FLAC
+//! extradata may contain only the STREAMINFO block while Matroska stores the
+//! `fLaC` marker followed by all metadata blocks, and Vorbis/Theora headers
+//! are laced differently in Ogg and in Matroska. Demuxers may also pass the
+//! container representation as is, so the extradata of the same stream can
+//! differ depending on the source container.
+//!
+//! The functions in this module convert between the two representations,
+//! so that the codec private data can be inspected and rewritten per stream
+//! (see `Stream::codec_private()` and `Stream::set_codec_private()`).
+//! Codecs whose container representation matches the extradata (e.g. H.264
+//! `avcC` or AAC AudioSpecificConfig) are passed through unchanged.
+
+use crate::Error;
+
+/// FLAC stream marker.
+const FLAC_MARKER: &[u8] = b"fLaC";
+
+/// Size of the FLAC STREAMINFO metadata block.
+const FLAC_STREAMINFO_SIZE: usize = 34;
+
+/// Convert given codec extradata into the container-level codec private
+/// data (i.e. the Matroska `CodecPrivate` representation).
+pub fn to_codec_private(codec_name: &str, extradata: &[u8]) -> Result<Vec<u8>, Error> {
+    match codec_name {
+        "flac" => Ok(flac_to_codec_private(extradata)),
+        "vorbis" | "theora" => {
+            let headers =
+                split_xiph_headers(extradata).ok_or_else(|| Error::new("invalid Xiph headers"))?;
+
+            Ok(xiph_lace(&headers))
+        }
+        _ => Ok(extradata.to_vec()),
+    }
+}
+
+/// Convert given container-level codec private data (i.e. the Matroska
+/// `CodecPrivate` representation) into codec extradata as expected by
+/// FFmpeg muxers and decoders.
+pub fn from_codec_private(codec_name: &str, codec_private: &[u8]) -> Result<Vec<u8>, Error> {
+    match codec_name {
+        "flac" => flac_streaminfo(codec_private)
+            .map(|streaminfo| streaminfo.to_vec())
+            .ok_or_else(|| Error::new("missing FLAC STREAMINFO")),
+        "vorbis" | "theora" => {
+            let headers = split_xiph_headers(codec_private)
+                .ok_or_else(|| Error::new("invalid Xiph headers"))?;
+
+            let mut res = Vec::new();
+
+            for header in headers {
+                res.extend_from_slice(&(header.len() as u16).to_be_bytes());
+                res.extend_from_slice(header);
+            }
+
+            Ok(res)
+        }
+        _ => Ok(codec_private.to_vec()),
+    }
+}
+
+/// Get the FLAC codec private data (i.e. the `fLaC` marker followed by
+/// metadata blocks) for given extradata.
+fn flac_to_codec_private(extradata: &[u8]) -> Vec<u8> {
+    if extradata.starts_with(FLAC_MARKER) || extradata.len() != FLAC_STREAMINFO_SIZE {
+        return extradata.to_vec();
+    }
+
+    let mut res = Vec::with_capacity(8 + FLAC_STREAMINFO_SIZE);
+
+    res.extend_from_slice(FLAC_MARKER);
+
+    // the last metadata block of type STREAMINFO
+    res.extend_from_slice(&[0x80, 0, 0, FLAC_STREAMINFO_SIZE as u8]);
+    res.extend_from_slice(extradata);
+    res
+}
+
+/// Find the FLAC STREAMINFO block in given data. The data may be either the
+/// bare STREAMINFO block or the `fLaC` marker followed by metadata blocks.
+fn flac_streaminfo(data: &[u8]) -> Option<&[u8]> {
+    if !data.starts_with(FLAC_MARKER) {
+        if data.len() == FLAC_STREAMINFO_SIZE {
+            return Some(data);
+        } else {
+            return None;
+        }
+    }
+
+    let mut blocks = &data[FLAC_MARKER.len()..];
+
+    while blocks.len() >= 4 {
+        let block_type = blocks[0] & 0x7f;
+        let last = (blocks[0] & 0x80) != 0;
+        let size = u32::from_be_bytes([0, blocks[1], blocks[2], blocks[3]]) as usize;
+
+        let block = blocks.get(4..4 + size)?;
+
+        if block_type == 0 && size >= FLAC_STREAMINFO_SIZE {
+            return Some(&block[..FLAC_STREAMINFO_SIZE]);
+        } else if last {
+            break;
+        }
+
+        blocks = &blocks[4 + size..];
+    }
+
+    None
+}
+
+/// Split given Xiph (Vorbis/Theora) headers. The headers may be either
+/// Xiph-laced (the Matroska representation) or each of them may be prefixed
+/// with its 16-bit size (the Ogg demuxer representation).
+fn split_xiph_headers(data: &[u8]) -> Option<Vec<&[u8]>> {
+    if data.len() >= 6 && data[0] == 0 && data[1] >= 30 {
+        let mut res = Vec::with_capacity(3);
+        let mut data = data;
+
+        for _ in 0..3 {
+            let size = u16::from_be_bytes([*data.first()?, *data.get(1)?]) as usize;
+            let header = data.get(2..2 + size)?;
+
+            res.push(header);
+
+            data = &data[2 + size..];
+        }
+
+        Some(res)
+    } else if data.first() == Some(&2) {
+        let mut sizes = [0usize; 2];
+        let mut pos = 1;
+
+        for size in sizes.iter_mut() {
+            loop {
+                let b = *data.get(pos)?;
+
+                pos += 1;
+
+                *size += b as usize;
+
+                if b < 255 {
+                    break;
+                }
+            }
+        }
+
+        let first = data.get(pos..pos + sizes[0])?;
+
+        pos += sizes[0];
+
+        let second = data.get(pos..pos + sizes[1])?;
+
+        pos += sizes[1];
+
+        let third = &data[pos..];
+
+        Some(vec![first, second, third])
+    } else {
+        None
+    }
+}
+
+/// Xiph-lace given headers.
+fn xiph_lace(headers: &[&[u8]]) -> Vec<u8> {
+    let mut res = vec![(headers.len() - 1) as u8];
+
+    for header in &headers[..headers.len() - 1] {
+        let mut size = header.len();
+
+        while size >= 255 {
+            res.push(255);
+
+            size -= 255;
+        }
+
+        res.push(size as u8);
+    }
+
+    for header in headers {
+        res.extend_from_slice(header);
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_codec_private, to_codec_private};
+
+    #[test]
+    fn test_flac() {
+        let streaminfo = (0..34).collect::<Vec<u8>>();
+
+        let codec_private = to_codec_private("flac", &streaminfo).unwrap();
+
+        assert!(codec_private.starts_with(b"fLaC\x80\x00\x00\x22"));
+
+        // add a VORBIS_COMMENT block after the STREAMINFO block
+        let mut with_comment = codec_private.clone();
+
+        with_comment[4] = 0;
+        with_comment.extend_from_slice(&[0x84, 0, 0, 2, 1, 2]);
+
+        assert_eq!(
+            from_codec_private("flac", &with_comment).unwrap(),
+            streaminfo
+        );
+        assert_eq!(from_codec_private("flac", &streaminfo).unwrap(), streaminfo);
+        assert!(from_codec_private("flac", b"fLaC").is_err());
+    }
+
+    #[test]
+    fn test_xiph() {
+        let id = vec![1u8; 30];
+        let comment = vec![3u8; 300];
+        let setup = vec![5u8; 10];
+
+        let mut extradata = Vec::new();
+
+        for header in [&id, &comment, &setup].iter() {
+            extradata.extend_from_slice(&(header.len() as u16).to_be_bytes());
+            extradata.extend_from_slice(header);
+        }
+
+        let codec_private = to_codec_private("vorbis", &extradata).unwrap();
+
+        assert_eq!(&codec_private[..5], &[2, 30, 255, 45, 1]);
+        assert_eq!(codec_private.len(), 4 + 340);
+
+        assert_eq!(
+            from_codec_private("vorbis", &codec_private).unwrap(),
+            extradata
+        );
+
+        // Xiph-laced input is kept as is
+        assert_eq!(
+            to_codec_private("vorbis", &codec_private).unwrap(),
+            codec_private
+        );
+
+        assert_eq!(to_codec_private("h264", &[1, 2, 3]).unwrap(), [1, 2, 3]);
+    }
+}
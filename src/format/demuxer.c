@@ -1,14 +1,32 @@
+#include <inttypes.h>
+
 #include <libavformat/avformat.h>
 #include <libavutil/opt.h>
 #include <libavutil/avstring.h>
+#include <libavutil/intreadwrite.h>
 
 #define SEEK_TYPE_TIME  0
 #define SEEK_TYPE_BYTE  1
 #define SEEK_TYPE_FRAME 2
 
-#define SEEK_TARGET_FROM    0
-#define SEEK_TARGET_UP_TO   1
-#define SEEK_TARGET_PRECISE 2
+#define SEEK_TARGET_FROM          0
+#define SEEK_TARGET_UP_TO         1
+#define SEEK_TARGET_PRECISE       2
+#define SEEK_TARGET_PRECISE_UP_TO 3
+
+#define INPUT_FORMAT_FLAG_NO_FILE         0x01
+#define INPUT_FORMAT_FLAG_NO_BYTE_SEEK    0x02
+#define INPUT_FORMAT_FLAG_NO_TIMESTAMPS   0x04
+#define INPUT_FORMAT_FLAG_TS_DISCONT      0x08
+#define INPUT_FORMAT_FLAG_GENERIC_INDEX   0x10
+#define INPUT_FORMAT_FLAG_DEVICE          0x20
+
+#define MEDIA_TYPE_UNKNOWN    -1
+#define MEDIA_TYPE_VIDEO      0
+#define MEDIA_TYPE_AUDIO      1
+#define MEDIA_TYPE_SUBTITLE   2
+#define MEDIA_TYPE_DATA       3
+#define MEDIA_TYPE_ATTACHMENT 4
 
 AVInputFormat* ffw_guess_input_format(
     const char* short_name,
@@ -44,23 +62,103 @@ AVInputFormat* ffw_guess_input_format(
     return (AVInputFormat*)res;
 }
 
+const char* ffw_input_format_get_name(const AVInputFormat* format) {
+    return format->name;
+}
+
+const char* ffw_input_format_get_long_name(const AVInputFormat* format) {
+    return format->long_name;
+}
+
+const char* ffw_input_format_get_extensions(const AVInputFormat* format) {
+    return format->extensions;
+}
+
+const char* ffw_input_format_get_mime_type(const AVInputFormat* format) {
+    return format->mime_type;
+}
+
+int ffw_input_format_get_flags(const AVInputFormat* format) {
+    const AVClass* class = format->priv_class;
+    int res = 0;
+
+    if (format->flags & AVFMT_NOFILE) {
+        res |= INPUT_FORMAT_FLAG_NO_FILE;
+    }
+
+    if (format->flags & AVFMT_NO_BYTE_SEEK) {
+        res |= INPUT_FORMAT_FLAG_NO_BYTE_SEEK;
+    }
+
+    if (format->flags & AVFMT_NOTIMESTAMPS) {
+        res |= INPUT_FORMAT_FLAG_NO_TIMESTAMPS;
+    }
+
+    if (format->flags & AVFMT_TS_DISCONT) {
+        res |= INPUT_FORMAT_FLAG_TS_DISCONT;
+    }
+
+    if (format->flags & AVFMT_GENERIC_INDEX) {
+        res |= INPUT_FORMAT_FLAG_GENERIC_INDEX;
+    }
+
+    if (class && (class->category == AV_CLASS_CATEGORY_DEVICE_INPUT
+        || class->category == AV_CLASS_CATEGORY_DEVICE_VIDEO_INPUT
+        || class->category == AV_CLASS_CATEGORY_DEVICE_AUDIO_INPUT)) {
+        res |= INPUT_FORMAT_FLAG_DEVICE;
+    }
+
+    return res;
+}
+
+typedef struct GaplessInfo {
+    int64_t skip_samples;
+    int64_t padding_samples;
+
+    // padding signaled using the packet side data since the last seek
+    int64_t discarded_samples;
+} GaplessInfo;
+
 typedef struct Demuxer {
     AVFormatContext* fc;
     AVDictionary* options;
     AVPacket* packet;
+    char* url;
+
+    GaplessInfo* gapless_info;
+    unsigned nb_gapless_info;
 } Demuxer;
 
 Demuxer* ffw_demuxer_new();
 int ffw_demuxer_init(Demuxer* demuxer, AVIOContext* io_context, AVInputFormat* format);
+const AVDictionary* ffw_demuxer_get_unused_options(const Demuxer* demuxer);
+int ffw_demuxer_set_url(Demuxer* demuxer, const char* url);
 int ffw_demuxer_set_initial_option(Demuxer* demuxer, const char* key, const char* value);
 int ffw_demuxer_set_option(Demuxer* demuxer, const char* key, const char* value);
 int ffw_demuxer_find_stream_info(Demuxer* demuxer, int64_t max_analyze_duration);
+void ffw_demuxer_set_interrupt_callback(Demuxer* demuxer, int (*callback)(void*), void* opaque);
+void ffw_demuxer_get_progress(const Demuxer* demuxer, int64_t* bytes, int64_t* duration);
 unsigned ffw_demuxer_get_nb_streams(const Demuxer* demuxer);
 AVStream* ffw_demuxer_get_stream(Demuxer* demuxer, unsigned stream_index);
+int ffw_demuxer_find_best_stream(Demuxer* demuxer, int media_type, int related_stream, const char** decoder);
 int ffw_demuxer_read_frame(Demuxer* demuxer, AVPacket** packet, uint32_t* tb_num, uint32_t* tb_den);
 int ffw_demuxer_seek(Demuxer* demuxer, int64_t timestamp, int seek_by, int seek_target);
+int ffw_demuxer_flush(Demuxer* demuxer);
+void ffw_demuxer_get_gapless_info(const Demuxer* demuxer, unsigned stream_index, int64_t* skip_samples, int64_t* padding_samples);
+int ffw_demuxer_get_pcr_pid(const Demuxer* demuxer, unsigned stream_index);
+const AVInputFormat* ffw_demuxer_get_input_format(const Demuxer* demuxer);
+int64_t ffw_demuxer_get_start_time(const Demuxer* demuxer);
+int64_t ffw_demuxer_get_duration(const Demuxer* demuxer);
+int64_t ffw_demuxer_get_bit_rate(const Demuxer* demuxer);
+unsigned ffw_demuxer_get_nb_programs(const Demuxer* demuxer);
+int ffw_demuxer_get_probe_score(const Demuxer* demuxer);
+const AVDictionaryEntry* ffw_demuxer_next_metadata_entry(const Demuxer* demuxer, const AVDictionaryEntry* prev, const char** key, const char** value);
 void ffw_demuxer_free(Demuxer* demuxer);
 
+static int init_gapless_info(Demuxer* demuxer);
+static void update_gapless_info(Demuxer* demuxer, const AVPacket* packet);
+static void reset_gapless_info(Demuxer* demuxer);
+
 Demuxer* ffw_demuxer_new() {
     Demuxer* demuxer = calloc(1, sizeof(Demuxer));
     if (!demuxer) {
@@ -88,22 +186,132 @@ err:
 int ffw_demuxer_init(Demuxer* demuxer, AVIOContext* avio_context, AVInputFormat* format) {
     int ret;
 
-    demuxer->fc->pb = avio_context;
+    // formats that open their own files (e.g. image sequences) cannot use
+    // a custom IO context
+    if (!format || !(format->flags & AVFMT_NOFILE)) {
+        demuxer->fc->pb = avio_context;
+    }
 
-    ret = avformat_open_input(&demuxer->fc, NULL, format, &demuxer->options);
+    ret = avformat_open_input(&demuxer->fc, demuxer->url, format, &demuxer->options);
     if (ret < 0) {
         return ret;
     }
 
-    av_dict_free(&demuxer->options);
+    ret = init_gapless_info(demuxer);
+    if (ret < 0) {
+        return ret;
+    }
 
-    return ret;
+    return 0;
+}
+
+int ffw_demuxer_set_url(Demuxer* demuxer, const char* url) {
+    av_freep(&demuxer->url);
+    demuxer->url = av_strdup(url);
+    return demuxer->url ? 0 : AVERROR(ENOMEM);
+}
+
+static int init_gapless_info(Demuxer* demuxer) {
+    AVDictionaryEntry* entry;
+    AVStream* stream;
+    GaplessInfo* info;
+    unsigned i;
+    unsigned priming;
+    unsigned remainder;
+    uint64_t samples;
+    int smpb;
+
+    if (demuxer->fc->nb_streams == 0) {
+        return 0;
+    }
+
+    demuxer->gapless_info = calloc(demuxer->fc->nb_streams, sizeof(GaplessInfo));
+    if (!demuxer->gapless_info) {
+        return AVERROR(ENOMEM);
+    }
+
+    demuxer->nb_gapless_info = demuxer->fc->nb_streams;
+
+    // iTunes stores encoder delay and padding in the iTunSMPB tag
+    entry = av_dict_get(demuxer->fc->metadata, "iTunSMPB", NULL, 0);
+
+    smpb = entry && sscanf(entry->value, "%*x %x %x %" SCNx64, &priming, &remainder, &samples) == 3;
+
+    for (i = 0; i < demuxer->fc->nb_streams; i++) {
+        stream = demuxer->fc->streams[i];
+        info = &demuxer->gapless_info[i];
+
+        if (stream->codecpar->codec_type != AVMEDIA_TYPE_AUDIO) {
+            continue;
+        }
+
+        if (stream->codecpar->initial_padding > 0) {
+            info->skip_samples = stream->codecpar->initial_padding;
+        }
+
+        if (stream->codecpar->trailing_padding > 0) {
+            info->padding_samples = stream->codecpar->trailing_padding;
+        }
+
+        // the iTunSMPB tag is a global tag, so it applies to the first audio
+        // stream only
+        if (smpb) {
+            if (info->skip_samples == 0 && priming > 0) {
+                info->skip_samples = priming;
+            }
+
+            if (info->padding_samples == 0 && remainder > 0) {
+                info->padding_samples = remainder;
+            }
+
+            smpb = 0;
+        }
+    }
+
+    return 0;
+}
+
+static void update_gapless_info(Demuxer* demuxer, const AVPacket* packet) {
+    GaplessInfo* info;
+    uint8_t* data;
+    int size;
+
+    if ((unsigned)packet->stream_index >= demuxer->nb_gapless_info) {
+        return;
+    }
+
+    data = av_packet_get_side_data(packet, AV_PKT_DATA_SKIP_SAMPLES, &size);
+    if (!data || size < 10) {
+        return;
+    }
+
+    info = &demuxer->gapless_info[packet->stream_index];
+
+    // the number of samples to skip is signaled in the first packet of the
+    // stream (e.g. from the LAME header or MP4 edit lists)
+    if (info->skip_samples == 0) {
+        info->skip_samples = AV_RL32(data);
+    }
+
+    info->discarded_samples += AV_RL32(data + 4);
+}
+
+static void reset_gapless_info(Demuxer* demuxer) {
+    unsigned i;
+
+    for (i = 0; i < demuxer->nb_gapless_info; i++) {
+        demuxer->gapless_info[i].discarded_samples = 0;
+    }
 }
 
 int ffw_demuxer_set_initial_option(Demuxer* demuxer, const char* key, const char* value) {
     return av_dict_set(&demuxer->options, key, value, 0);
 }
 
+const AVDictionary* ffw_demuxer_get_unused_options(const Demuxer* demuxer) {
+    return demuxer->options;
+}
+
 int ffw_demuxer_set_option(Demuxer* demuxer, const char* key, const char* value) {
     return av_opt_set(demuxer->fc, key, value, AV_OPT_SEARCH_CHILDREN);
 }
@@ -125,6 +333,42 @@ int ffw_demuxer_find_stream_info(Demuxer* demuxer, int64_t max_analyze_duration)
     return avformat_find_stream_info(demuxer->fc, NULL);
 }
 
+void ffw_demuxer_set_interrupt_callback(Demuxer* demuxer, int (*callback)(void*), void* opaque) {
+    demuxer->fc->interrupt_callback.callback = callback;
+    demuxer->fc->interrupt_callback.opaque = opaque;
+}
+
+void ffw_demuxer_get_progress(const Demuxer* demuxer, int64_t* bytes, int64_t* duration) {
+#if LIBAVFORMAT_VERSION_MAJOR < 59
+    AVStream* stream;
+    AVRational micro;
+    int64_t stream_duration;
+    unsigned i;
+#endif
+
+    *bytes = demuxer->fc->pb ? avio_tell(demuxer->fc->pb) : 0;
+    *duration = -1;
+
+#if LIBAVFORMAT_VERSION_MAJOR < 59
+    micro.num = 1;
+    micro.den = 1000000;
+
+    for (i = 0; i < demuxer->fc->nb_streams; i++) {
+        stream = demuxer->fc->streams[i];
+
+        if (stream->first_dts == AV_NOPTS_VALUE || stream->cur_dts == AV_NOPTS_VALUE) {
+            continue;
+        }
+
+        stream_duration = av_rescale_q(stream->cur_dts - stream->first_dts, stream->time_base, micro);
+
+        if (stream_duration > *duration) {
+            *duration = stream_duration;
+        }
+    }
+#endif
+}
+
 unsigned ffw_demuxer_get_nb_streams(const Demuxer* demuxer) {
     return demuxer->fc->nb_streams;
 }
@@ -133,6 +377,29 @@ AVStream* ffw_demuxer_get_stream(Demuxer* demuxer, unsigned stream_index) {
     return demuxer->fc->streams[stream_index];
 }
 
+int ffw_demuxer_find_best_stream(Demuxer* demuxer, int media_type, int related_stream, const char** decoder) {
+    AVCodec* codec = NULL;
+    enum AVMediaType type;
+    int ret;
+
+    switch (media_type) {
+        case MEDIA_TYPE_VIDEO: type = AVMEDIA_TYPE_VIDEO; break;
+        case MEDIA_TYPE_AUDIO: type = AVMEDIA_TYPE_AUDIO; break;
+        case MEDIA_TYPE_SUBTITLE: type = AVMEDIA_TYPE_SUBTITLE; break;
+        case MEDIA_TYPE_DATA: type = AVMEDIA_TYPE_DATA; break;
+        case MEDIA_TYPE_ATTACHMENT: type = AVMEDIA_TYPE_ATTACHMENT; break;
+        default: return AVERROR_STREAM_NOT_FOUND;
+    }
+
+    ret = av_find_best_stream(demuxer->fc, type, -1, related_stream, decoder ? &codec : NULL, 0);
+
+    if (ret >= 0 && decoder) {
+        *decoder = codec ? codec->name : NULL;
+    }
+
+    return ret;
+}
+
 int ffw_demuxer_read_frame(Demuxer* demuxer, AVPacket** packet, uint32_t* tb_num, uint32_t* tb_den) {
     AVStream* stream;
     AVPacket* res;
@@ -158,6 +425,8 @@ int ffw_demuxer_read_frame(Demuxer* demuxer, AVPacket** packet, uint32_t* tb_num
         return AVERROR(ENOMEM);
     }
 
+    update_gapless_info(demuxer, res);
+
     stream = demuxer->fc->streams[res->stream_index];
 
     *packet = res;
@@ -191,21 +460,119 @@ int ffw_demuxer_seek(Demuxer* demuxer, int64_t timestamp, int seek_by, int seek_
         case SEEK_TARGET_PRECISE:
             flags |= AVSEEK_FLAG_ANY;
             break;
+        case SEEK_TARGET_PRECISE_UP_TO:
+            flags |= AVSEEK_FLAG_ANY | AVSEEK_FLAG_BACKWARD;
+            break;
         default:
             break;
     }
 
+    reset_gapless_info(demuxer);
+
     return av_seek_frame(demuxer->fc, -1, timestamp, flags);
 }
 
+int ffw_demuxer_flush(Demuxer* demuxer) {
+    int ret;
+
+    ret = avformat_flush(demuxer->fc);
+    if (ret < 0) {
+        return ret;
+    }
+
+    // NOTE: flushing a read context drops the buffered data
+    if (demuxer->fc->pb) {
+        avio_flush(demuxer->fc->pb);
+    }
+
+    return 0;
+}
+
+void ffw_demuxer_get_gapless_info(const Demuxer* demuxer, unsigned stream_index, int64_t* skip_samples, int64_t* padding_samples) {
+    const GaplessInfo* info;
+
+    if (stream_index < demuxer->nb_gapless_info) {
+        info = &demuxer->gapless_info[stream_index];
+
+        *skip_samples = info->skip_samples;
+
+        if (info->padding_samples > 0) {
+            *padding_samples = info->padding_samples;
+        } else {
+            *padding_samples = info->discarded_samples;
+        }
+    } else {
+        *skip_samples = 0;
+        *padding_samples = 0;
+    }
+}
+
+int ffw_demuxer_get_pcr_pid(const Demuxer* demuxer, unsigned stream_index) {
+    AVProgram* program;
+    unsigned i;
+    unsigned j;
+
+    for (i = 0; i < demuxer->fc->nb_programs; i++) {
+        program = demuxer->fc->programs[i];
+
+        for (j = 0; j < program->nb_stream_indexes; j++) {
+            // PID 0 is reserved for PAT, it is also the default value for
+            // formats that do not set the PCR PID
+            if (program->stream_index[j] == stream_index && program->pcr_pid > 0) {
+                return program->pcr_pid;
+            }
+        }
+    }
+
+    return -1;
+}
+
+const AVInputFormat* ffw_demuxer_get_input_format(const Demuxer* demuxer) {
+    return demuxer->fc->iformat;
+}
+
+int64_t ffw_demuxer_get_start_time(const Demuxer* demuxer) {
+    return demuxer->fc->start_time;
+}
+
+int64_t ffw_demuxer_get_duration(const Demuxer* demuxer) {
+    return demuxer->fc->duration;
+}
+
+int64_t ffw_demuxer_get_bit_rate(const Demuxer* demuxer) {
+    return demuxer->fc->bit_rate;
+}
+
+unsigned ffw_demuxer_get_nb_programs(const Demuxer* demuxer) {
+    return demuxer->fc->nb_programs;
+}
+
+int ffw_demuxer_get_probe_score(const Demuxer* demuxer) {
+    return demuxer->fc->probe_score;
+}
+
+const AVDictionaryEntry* ffw_demuxer_next_metadata_entry(const Demuxer* demuxer, const AVDictionaryEntry* prev, const char** key, const char** value) {
+    AVDictionaryEntry* entry = av_dict_get(demuxer->fc->metadata, "", prev, AV_DICT_IGNORE_SUFFIX);
+
+    if (entry) {
+        *key = entry->key;
+        *value = entry->value;
+    }
+
+    return entry;
+}
+
 void ffw_demuxer_free(Demuxer* demuxer) {
     if (!demuxer) {
         return;
     }
 
+    free(demuxer->gapless_info);
+
     av_packet_free(&demuxer->packet);
     avformat_close_input(&demuxer->fc);
     av_dict_free(&demuxer->options);
+    av_free(demuxer->url);
 
     free(demuxer);
 }
@@ -3,16 +3,23 @@
 use std::{
     borrow::{Borrow, BorrowMut},
     convert::TryInto,
-    ffi::CString,
+    ffi::{CStr, CString},
     io::Read,
     ops::{Deref, DerefMut},
     os::raw::{c_char, c_int, c_uint, c_void},
     ptr,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    format::{io::IO, stream::Stream},
+    channel::ChannelReceiver,
+    codec::{Decoder, MediaType},
+    format::{
+        index::SeekIndex,
+        io::IO,
+        stream::{FrameCount, FrameCountMethod, Stream},
+    },
+    options::OptionInfo,
     packet::Packet,
     time::{TimeBase, Timestamp},
     Error,
@@ -25,12 +32,20 @@ extern "C" {
         mime_type: *const c_char,
     ) -> *mut c_void;
 
+    fn ffw_input_format_get_name(format: *const c_void) -> *const c_char;
+    fn ffw_input_format_get_long_name(format: *const c_void) -> *const c_char;
+    fn ffw_input_format_get_extensions(format: *const c_void) -> *const c_char;
+    fn ffw_input_format_get_mime_type(format: *const c_void) -> *const c_char;
+    fn ffw_input_format_get_flags(format: *const c_void) -> c_int;
+
     fn ffw_demuxer_new() -> *mut c_void;
     fn ffw_demuxer_init(
         demuxer: *mut c_void,
         io_context: *mut c_void,
         format: *mut c_void,
     ) -> c_int;
+    fn ffw_demuxer_get_unused_options(demuxer: *const c_void) -> *const c_void;
+    fn ffw_demuxer_set_url(demuxer: *mut c_void, url: *const c_char) -> c_int;
     fn ffw_demuxer_set_initial_option(
         demuxer: *mut c_void,
         key: *const c_char,
@@ -42,8 +57,20 @@ extern "C" {
         value: *const c_char,
     ) -> c_int;
     fn ffw_demuxer_find_stream_info(demuxer: *mut c_void, max_analyze_duration: i64) -> c_int;
+    fn ffw_demuxer_set_interrupt_callback(
+        demuxer: *mut c_void,
+        callback: Option<InterruptCallback>,
+        opaque: *mut c_void,
+    );
+    fn ffw_demuxer_get_progress(demuxer: *const c_void, bytes: *mut i64, duration: *mut i64);
     fn ffw_demuxer_get_nb_streams(demuxer: *const c_void) -> c_uint;
     fn ffw_demuxer_get_stream(demuxer: *mut c_void, index: c_uint) -> *mut c_void;
+    fn ffw_demuxer_find_best_stream(
+        demuxer: *mut c_void,
+        media_type: c_int,
+        related_stream: c_int,
+        decoder: *mut *const c_char,
+    ) -> c_int;
     fn ffw_demuxer_read_frame(
         demuxer: *mut c_void,
         packet: *mut *mut c_void,
@@ -56,9 +83,38 @@ extern "C" {
         seek_by: c_int,
         seek_target: c_int,
     ) -> c_int;
+    fn ffw_demuxer_flush(demuxer: *mut c_void) -> c_int;
+    fn ffw_demuxer_get_gapless_info(
+        demuxer: *const c_void,
+        stream_index: c_uint,
+        skip_samples: *mut i64,
+        padding_samples: *mut i64,
+    );
+    fn ffw_demuxer_get_pcr_pid(demuxer: *const c_void, stream_index: c_uint) -> c_int;
+    fn ffw_demuxer_get_input_format(demuxer: *const c_void) -> *const c_void;
+    fn ffw_demuxer_get_start_time(demuxer: *const c_void) -> i64;
+    fn ffw_demuxer_get_duration(demuxer: *const c_void) -> i64;
+    fn ffw_demuxer_get_bit_rate(demuxer: *const c_void) -> i64;
+    fn ffw_demuxer_get_nb_programs(demuxer: *const c_void) -> c_uint;
+    fn ffw_demuxer_get_probe_score(demuxer: *const c_void) -> c_int;
+    fn ffw_demuxer_next_metadata_entry(
+        demuxer: *const c_void,
+        prev: *const c_void,
+        key: *mut *const c_char,
+        value: *mut *const c_char,
+    ) -> *const c_void;
     fn ffw_demuxer_free(demuxer: *mut c_void);
 }
 
+type InterruptCallback = extern "C" fn(opaque: *mut c_void) -> c_int;
+
+const INPUT_FORMAT_FLAG_NO_FILE: c_int = 0x01;
+const INPUT_FORMAT_FLAG_NO_BYTE_SEEK: c_int = 0x02;
+const INPUT_FORMAT_FLAG_NO_TIMESTAMPS: c_int = 0x04;
+const INPUT_FORMAT_FLAG_TS_DISCONT: c_int = 0x08;
+const INPUT_FORMAT_FLAG_GENERIC_INDEX: c_int = 0x10;
+const INPUT_FORMAT_FLAG_DEVICE: c_int = 0x20;
+
 /// Seek type/mode.
 enum SeekType {
     Time,
@@ -78,7 +134,9 @@ impl SeekType {
 }
 
 /// Used to specify a search direction when a stream cannot seek exactly to the requested target
-/// point; timestamp, frame or byte.
+/// point; timestamp, frame or byte. The `From` and `UpTo` targets seek to key frames only, the
+/// `Precise` and `PreciseUpTo` targets allow seeking to non-key frames as well.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum SeekTarget {
     /// Seek, at least, to the requested target point in the stream. If the target cannot be met
     /// then move forward in the stream until a possible seek target can be hit.
@@ -86,9 +144,14 @@ pub enum SeekTarget {
     /// Seek, at most, to the requested target point in the stream. If the target cannot be met
     /// then move backward in the stream until a possible seek target can be hit.
     UpTo,
-    /// Force seeking to the requested target point in the stream, even if the Demuxer for this
-    /// type of stream, does not support it.
+    /// Seek to any frame (including non-key frames) at or after the requested target point.
+    /// Note that decoding from a non-key frame may produce corrupted frames until the next key
+    /// frame.
     Precise,
+    /// Seek to any frame (including non-key frames) at or before the requested target point.
+    /// Note that decoding from a non-key frame may produce corrupted frames until the next key
+    /// frame.
+    PreciseUpTo,
 }
 
 impl SeekTarget {
@@ -98,14 +161,57 @@ impl SeekTarget {
             SeekTarget::From => 0,
             SeekTarget::UpTo => 1,
             SeekTarget::Precise => 2,
+            SeekTarget::PreciseUpTo => 3,
+        }
+    }
+}
+
+/// Generic demuxer flags (i.e. the `fflags` option).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FormatFlag {
+    /// Generate missing presentation timestamps (if possible).
+    GeneratePts,
+    /// Ignore decoding timestamps (the presentation timestamps are used
+    /// instead when generating the missing ones).
+    IgnoreDts,
+    /// Ignore the index of the input file.
+    IgnoreIndex,
+    /// Discard packets marked as corrupted (e.g. packets with missing data
+    /// due to lost UDP datagrams).
+    DiscardCorrupt,
+    /// Allow fast but inaccurate seeking for some formats.
+    FastSeek,
+    /// Do not buffer packets during stream analysis.
+    NoBuffer,
+}
+
+impl FormatFlag {
+    /// Get name of the flag.
+    fn name(self) -> &'static str {
+        match self {
+            FormatFlag::GeneratePts => "genpts",
+            FormatFlag::IgnoreDts => "igndts",
+            FormatFlag::IgnoreIndex => "ignidx",
+            FormatFlag::DiscardCorrupt => "discardcorrupt",
+            FormatFlag::FastSeek => "fastseek",
+            FormatFlag::NoBuffer => "nobuffer",
         }
     }
+
+    /// Get value of the `fflags` option adding given flags.
+    fn option_value(flags: &[Self]) -> String {
+        flags
+            .iter()
+            .map(|flag| format!("+{}", flag.name()))
+            .collect()
+    }
 }
 
 /// Demuxer builder.
 pub struct DemuxerBuilder {
     ptr: *mut c_void,
     input_format: Option<InputFormat>,
+    error: Option<Error>,
 }
 
 impl DemuxerBuilder {
@@ -120,23 +226,132 @@ impl DemuxerBuilder {
         DemuxerBuilder {
             ptr,
             input_format: None,
+            error: None,
         }
     }
 
     /// Set a demuxer option.
-    pub fn set_option<V>(self, name: &str, value: V) -> DemuxerBuilder
+    /// An invalid option (e.g. a name containing a NUL character) or an
+    /// option that is not recognized will make the `build()` method fail.
+    ///
+    /// The `url` option sets the input URL. It is required by input formats
+    /// that open their own files (e.g. `image2`), the IO passed to the
+    /// `build()` method is not used in such case.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> DemuxerBuilder
     where
         V: ToString,
     {
-        let name = CString::new(name).expect("invalid option name");
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (c_name, value) = match crate::options::option_to_cstrings(name, &value.to_string()) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
 
-        let ret = unsafe {
-            ffw_demuxer_set_initial_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
+                return self;
+            }
+        };
+
+        // NOTE: the URL is passed directly to avformat_open_input()
+        let ret = if name == "url" {
+            unsafe { ffw_demuxer_set_url(self.ptr, value.as_ptr()) }
+        } else {
+            unsafe {
+                ffw_demuxer_set_initial_option(self.ptr, c_name.as_ptr() as _, value.as_ptr() as _)
+            }
+        };
+
+        if ret < 0 {
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
+        }
+
+        self
+    }
+
+    /// Set the maximum number of bytes read from the input when probing
+    /// the input format and when looking for stream info (i.e. the
+    /// `probesize` option). The FFmpeg default is 5 MB. Live inputs may
+    /// want to lower the value in order to reduce the start-up latency. A
+    /// size less than 32 bytes will make the `build()` method fail.
+    pub fn probe_size(mut self, bytes: u64) -> DemuxerBuilder {
+        if bytes < 32 {
+            self.error
+                .get_or_insert(Error::new("the probe size must be at least 32 bytes"));
+
+            return self;
+        }
+
+        self.set_option("probesize", bytes)
+    }
+
+    /// Set the maximum duration of the input analyzed when looking for
+    /// stream info (i.e. the `analyzeduration` option). The FFmpeg default
+    /// is 5 seconds (zero means the default). Live inputs may want to lower
+    /// the value in order to reduce the start-up latency. A duration that
+    /// does not fit into a 64-bit signed integer number of microseconds
+    /// will make the `build()` method fail.
+    pub fn analyze_duration(mut self, duration: Duration) -> DemuxerBuilder {
+        let micros = duration.as_micros();
+
+        if micros > i64::MAX as u128 {
+            self.error
+                .get_or_insert(Error::new("analyze duration out of range"));
+
+            return self;
+        }
+
+        self.set_option("analyzeduration", micros)
+    }
+
+    /// Configure the demuxer for low latency live inputs. Buffering of
+    /// packets during stream analysis is disabled (the `nobuffer` format
+    /// flag), the probe size is set to 32 kB and the analyze duration to
+    /// 500 ms.
+    pub fn low_latency(self) -> DemuxerBuilder {
+        self.format_flags(&[FormatFlag::NoBuffer])
+            .probe_size(32 * 1024)
+            .analyze_duration(Duration::from_millis(500))
+    }
+
+    /// Make the HTTP protocol reconnect automatically if the connection
+    /// drops, also for streamed (i.e. non-seekable) inputs such as live
+    /// HLS or Icecast streams (i.e. the `reconnect`, `reconnect_streamed`
+    /// and `reconnect_delay_max` protocol options). The delay between
+    /// reconnection attempts grows exponentially up to a given maximum.
+    ///
+    /// The options are used only if the demuxer opens the input itself
+    /// (see the `url` option). Use `UrlStreamBuilder::reconnect()` for
+    /// streams passed to the `build()` method.
+    pub fn reconnect(self, max_delay: Duration) -> DemuxerBuilder {
+        self.set_option("reconnect", 1)
+            .set_option("reconnect_streamed", 1)
+            .set_option("reconnect_delay_max", max_delay.as_secs())
+    }
+
+    /// Enable given demuxer flags. The flags are added to the flags that are
+    /// already set, so the method can be called multiple times. Note that
+    /// setting the `fflags` option using `set_option()` will replace all
+    /// the flags unless the value starts with `+`.
+    pub fn format_flags(mut self, flags: &[FormatFlag]) -> DemuxerBuilder {
+        let value = FormatFlag::option_value(flags);
+
+        if value.is_empty() {
+            return self;
+        }
+
+        let (name, value) = match crate::options::option_to_cstrings("fflags", &value) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
         };
 
+        // NOTE: the flags are set directly on the format context, so that
+        // they get combined with flags set via the `fflags` option
+        let ret = unsafe { ffw_demuxer_set_option(self.ptr, name.as_ptr(), value.as_ptr()) };
+
         if ret < 0 {
-            panic!("unable to allocate an option");
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
         }
 
         self
@@ -156,6 +371,10 @@ impl DemuxerBuilder {
     where
         T: Read,
     {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
         let io_context_ptr = io.io_context_mut().as_mut_ptr();
 
         let format_ptr = self
@@ -167,14 +386,25 @@ impl DemuxerBuilder {
         let ret = unsafe { ffw_demuxer_init(self.ptr, io_context_ptr, format_ptr) };
 
         if ret < 0 {
-            return Err(Error::from_raw_error_code(ret));
+            return Err(io.make_error(ret));
+        }
+
+        let unused = unsafe { ffw_demuxer_get_unused_options(self.ptr) };
+
+        if let Err(err) = unsafe { crate::options::check_unused_options(unused) } {
+            // the demuxer needs to be freed while the IO is still alive
+            unsafe { ffw_demuxer_free(self.ptr) };
+
+            self.ptr = ptr::null_mut();
+
+            return Err(err);
         }
 
         let ptr = self.ptr;
 
         self.ptr = ptr::null_mut();
 
-        let res = Demuxer { ptr, io };
+        let res = Demuxer { ptr, io: Some(io) };
 
         Ok(res)
     }
@@ -189,10 +419,81 @@ impl Drop for DemuxerBuilder {
 unsafe impl Send for DemuxerBuilder {}
 unsafe impl Sync for DemuxerBuilder {}
 
+/// Progress of the stream info analysis.
+#[derive(Debug, Clone)]
+pub struct StreamInfoProgress {
+    bytes: u64,
+    analyzed: Option<Duration>,
+    elapsed: Duration,
+}
+
+impl StreamInfoProgress {
+    /// Get the number of input bytes analyzed so far. The value is always
+    /// zero for input formats that open their own files.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Get duration of the media analyzed so far. The value is not
+    /// available until the demuxer returns packets with timestamps or if
+    /// FFmpeg does not expose it (FFmpeg 5.0 and newer).
+    pub fn analyzed(&self) -> Option<Duration> {
+        self.analyzed
+    }
+
+    /// Get the wall-clock time elapsed since the analysis started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Context of the interrupt callback used by the stream info analysis.
+struct InterruptContext<'a> {
+    demuxer: *const c_void,
+    start: Instant,
+    progress: &'a mut dyn FnMut(&StreamInfoProgress) -> bool,
+    cancelled: bool,
+}
+
+/// An InterruptCallback function reporting the stream info analysis
+/// progress.
+extern "C" fn interrupt_callback(opaque: *mut c_void) -> c_int {
+    let context = unsafe { &mut *(opaque as *mut InterruptContext) };
+
+    if context.cancelled {
+        return 1;
+    }
+
+    let mut bytes = 0;
+    let mut duration = -1;
+
+    unsafe {
+        ffw_demuxer_get_progress(context.demuxer, &mut bytes, &mut duration);
+    }
+
+    let progress = StreamInfoProgress {
+        bytes: bytes.max(0) as u64,
+        analyzed: if duration < 0 {
+            None
+        } else {
+            Some(Duration::from_micros(duration as u64))
+        },
+        elapsed: context.start.elapsed(),
+    };
+
+    if (context.progress)(&progress) {
+        0
+    } else {
+        context.cancelled = true;
+
+        1
+    }
+}
+
 /// Demuxer.
 pub struct Demuxer<T> {
     ptr: *mut c_void,
-    io: IO<T>,
+    io: Option<IO<T>>,
 }
 
 impl Demuxer<()> {
@@ -208,8 +509,7 @@ impl<T> Demuxer<T> {
     where
         V: ToString,
     {
-        let name = CString::new(name).expect("invalid option name");
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (name, value) = crate::options::option_to_cstrings(name, &value.to_string())?;
 
         let ret =
             unsafe { ffw_demuxer_set_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _) };
@@ -231,7 +531,7 @@ impl<T> Demuxer<T> {
         let ret = unsafe { ffw_demuxer_read_frame(self.ptr, &mut pptr, &mut tb_num, &mut tb_den) };
 
         if ret < 0 {
-            Err(Error::from_raw_error_code(ret))
+            Err(self.io().make_error(ret))
         } else if pptr.is_null() {
             Ok(None)
         } else {
@@ -254,6 +554,12 @@ impl<T> Demuxer<T> {
         self.seek(micros, SeekType::Time, seek_target)
     }
 
+    /// Seek to the last key frame at or before a given timestamp. This is
+    /// the same as seeking with `SeekTarget::UpTo`.
+    pub fn seek_to_keyframe_before(&self, timestamp: Timestamp) -> Result<(), Error> {
+        self.seek_to_timestamp(timestamp, SeekTarget::UpTo)
+    }
+
     /// Seek to a specific frame in the stream.
     pub fn seek_to_frame(&self, frame: u64, seek_target: SeekTarget) -> Result<(), Error> {
         self.seek(frame as _, SeekType::Frame, seek_target)
@@ -265,6 +571,41 @@ impl<T> Demuxer<T> {
         self.seek(offset as _, SeekType::Byte, SeekTarget::Precise)
     }
 
+    /// Drop all packets buffered by the demuxer and all data buffered by
+    /// the IO context. This is useful for live inputs after a discontinuity
+    /// (e.g. after the underlying connection was re-established), so that
+    /// stale data are not mixed with the new data. Note that the dropped
+    /// data are lost, the demuxer will continue with reading new data from
+    /// the underlying stream.
+    pub fn flush_buffers(&mut self) -> Result<(), Error> {
+        let ret = unsafe { ffw_demuxer_flush(self.ptr) };
+
+        if ret < 0 {
+            Err(Error::from_raw_error_code(ret))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Seek to the last key frame at or before a given timestamp using a
+    /// given seek index. The seek is done by byte position, so it is fast
+    /// even for inputs without any index (e.g. raw MPEG-TS captures). The
+    /// method returns timestamp of the key frame. Note that packets
+    /// preceding the key frame may still be returned by the demuxer.
+    pub fn seek_with_index(
+        &self,
+        index: &SeekIndex,
+        timestamp: Timestamp,
+    ) -> Result<Timestamp, Error> {
+        let entry = index
+            .lookup(timestamp)
+            .ok_or_else(|| Error::new("no key frame found"))?;
+
+        self.seek_to_byte(entry.position())?;
+
+        Ok(entry.pts())
+    }
+
     /// Seek to a given position.
     fn seek(
         &self,
@@ -284,7 +625,7 @@ impl<T> Demuxer<T> {
         if res >= 0 {
             Ok(())
         } else {
-            Err(Error::from_raw_error_code(res))
+            Err(self.io().make_error(res))
         }
     }
 
@@ -293,6 +634,32 @@ impl<T> Demuxer<T> {
     pub fn find_stream_info(
         self,
         max_analyze_duration: Option<Duration>,
+    ) -> Result<DemuxerWithStreamInfo<T>, (Self, Error)> {
+        self.find_stream_info_internal(max_analyze_duration, None)
+    }
+
+    /// Try to find stream info while reporting the analysis progress. The
+    /// progress callback is invoked repeatedly while FFmpeg reads the input
+    /// (i.e. it should be cheap) and the analysis can be cancelled at any
+    /// time by returning `false` from the callback. An error is returned in
+    /// such case. Note that the analysis cannot be restarted on the same
+    /// demuxer, a new demuxer must be created in order to retry it.
+    pub fn find_stream_info_with_progress<F>(
+        self,
+        max_analyze_duration: Option<Duration>,
+        mut progress: F,
+    ) -> Result<DemuxerWithStreamInfo<T>, (Self, Error)>
+    where
+        F: FnMut(&StreamInfoProgress) -> bool,
+    {
+        self.find_stream_info_internal(max_analyze_duration, Some(&mut progress))
+    }
+
+    /// Try to find stream info (optionally reporting the progress).
+    fn find_stream_info_internal(
+        self,
+        max_analyze_duration: Option<Duration>,
+        progress: Option<&mut dyn FnMut(&StreamInfoProgress) -> bool>,
     ) -> Result<DemuxerWithStreamInfo<T>, (Self, Error)> {
         let max_analyze_duration = max_analyze_duration
             .unwrap_or_else(|| Duration::from_secs(0))
@@ -300,10 +667,41 @@ impl<T> Demuxer<T> {
             .try_into()
             .unwrap();
 
-        let ret = unsafe { ffw_demuxer_find_stream_info(self.ptr, max_analyze_duration) };
+        let ret = if let Some(progress) = progress {
+            let mut context = InterruptContext {
+                demuxer: self.ptr,
+                start: Instant::now(),
+                progress,
+                cancelled: false,
+            };
+
+            let ret = unsafe {
+                ffw_demuxer_set_interrupt_callback(
+                    self.ptr,
+                    Some(interrupt_callback),
+                    &mut context as *mut InterruptContext as _,
+                );
+
+                let ret = ffw_demuxer_find_stream_info(self.ptr, max_analyze_duration);
+
+                ffw_demuxer_set_interrupt_callback(self.ptr, None, ptr::null_mut());
+
+                ret
+            };
+
+            if context.cancelled {
+                return Err((self, Error::new("stream info analysis cancelled")));
+            }
+
+            ret
+        } else {
+            unsafe { ffw_demuxer_find_stream_info(self.ptr, max_analyze_duration) }
+        };
 
         if ret < 0 {
-            return Err((self, Error::from_raw_error_code(ret)));
+            let err = self.io().make_error(ret);
+
+            return Err((self, err));
         }
 
         let stream_count = unsafe { ffw_demuxer_get_nb_streams(self.ptr) };
@@ -318,7 +716,19 @@ impl<T> Demuxer<T> {
                     panic!("unable to get stream info");
                 }
 
+                let (skip_samples, padding_samples) = self.gapless_info(i as _);
+
+                let pcr_pid = ffw_demuxer_get_pcr_pid(self.ptr, i as _);
+
+                let pcr_pid = if pcr_pid < 0 {
+                    None
+                } else {
+                    Some(pcr_pid as u16)
+                };
+
                 Stream::from_raw_ptr(ptr)
+                    .with_gapless_info(skip_samples, padding_samples)
+                    .with_pcr_pid(pcr_pid)
             };
 
             streams.push(stream);
@@ -332,14 +742,164 @@ impl<T> Demuxer<T> {
         Ok(res)
     }
 
+    /// Get encoder delay and padding of a given stream.
+    fn gapless_info(&self, stream_index: usize) -> (u64, u64) {
+        let mut skip_samples = 0;
+        let mut padding_samples = 0;
+
+        unsafe {
+            ffw_demuxer_get_gapless_info(
+                self.ptr,
+                stream_index as _,
+                &mut skip_samples,
+                &mut padding_samples,
+            );
+        }
+
+        (skip_samples as _, padding_samples as _)
+    }
+
+    /// Get the input format detected (or forced) for this demuxer.
+    pub fn input_format(&self) -> InputFormat {
+        let ptr = unsafe { ffw_demuxer_get_input_format(self.ptr) };
+
+        InputFormat { ptr: ptr as _ }
+    }
+
+    /// Get the start time of the input (in microseconds) if known.
+    pub fn start_time(&self) -> Option<Timestamp> {
+        let pts = unsafe { ffw_demuxer_get_start_time(self.ptr) };
+
+        Timestamp::from_micros(pts).into_option()
+    }
+
+    /// Get the duration of the input (in microseconds) if known.
+    pub fn duration(&self) -> Option<Timestamp> {
+        let duration = unsafe { ffw_demuxer_get_duration(self.ptr) };
+
+        Timestamp::from_micros(duration).into_option()
+    }
+
+    /// Get the total bit rate of the input (if known).
+    pub fn bit_rate(&self) -> Option<u64> {
+        let bit_rate = unsafe { ffw_demuxer_get_bit_rate(self.ptr) };
+
+        if bit_rate > 0 {
+            Some(bit_rate as u64)
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of programs in the input (e.g. in an MPEG-TS).
+    pub fn program_count(&self) -> usize {
+        unsafe { ffw_demuxer_get_nb_programs(self.ptr) as _ }
+    }
+
+    /// Get the score of the input format detection (100 is the maximum).
+    pub fn probe_score(&self) -> i32 {
+        unsafe { ffw_demuxer_get_probe_score(self.ptr) as _ }
+    }
+
+    /// Get all container metadata entries (e.g. "title" or "encoder").
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let mut res = Vec::new();
+
+        let mut entry = ptr::null();
+
+        loop {
+            let mut key = ptr::null();
+            let mut value = ptr::null();
+
+            unsafe {
+                entry = ffw_demuxer_next_metadata_entry(self.ptr, entry, &mut key, &mut value);
+
+                if entry.is_null() {
+                    return res;
+                }
+
+                let key = CStr::from_ptr(key).to_string_lossy();
+                let value = CStr::from_ptr(value).to_string_lossy();
+
+                res.push((key.into_owned(), value.into_owned()));
+            }
+        }
+    }
+
+    /// Get options available for this demuxer (i.e. the generic format
+    /// options followed by the options specific to the input format).
+    pub fn options(&self) -> Vec<OptionInfo> {
+        let mut res = crate::options::format_options();
+
+        unsafe {
+            let format = ffw_demuxer_get_input_format(self.ptr);
+
+            res.extend(crate::options::input_format_options(format));
+        }
+
+        res
+    }
+
     /// Get reference to the underlying IO.
     pub fn io(&self) -> &IO<T> {
-        &self.io
+        self.io.as_ref().unwrap()
     }
 
     /// Get mutable reference to the underlying IO.
     pub fn io_mut(&mut self) -> &mut IO<T> {
-        &mut self.io
+        self.io.as_mut().unwrap()
+    }
+
+    /// Close the demuxer and take the underlying IO (e.g. to reuse the
+    /// wrapped stream). Note that the IO may have read ahead of the last
+    /// packet taken from the demuxer.
+    pub fn into_io(mut self) -> IO<T> {
+        unsafe { ffw_demuxer_free(self.ptr) };
+
+        self.ptr = ptr::null_mut();
+
+        self.io.take().unwrap()
+    }
+
+    /// Get an iterator over all remaining packets.
+    pub fn packets(&mut self) -> Packets<'_, T> {
+        Packets {
+            demuxer: self,
+            finished: false,
+        }
+    }
+
+    /// Get an iterator decoding all remaining packets of a given stream
+    /// using a given decoder. Packets of other streams are skipped. The
+    /// decoder is flushed at the end of the input.
+    pub fn frames<D>(&mut self, stream_index: usize, decoder: D) -> Frames<'_, T, D>
+    where
+        D: Decoder,
+    {
+        Frames {
+            demuxer: self,
+            decoder,
+            stream_index,
+            flushed: false,
+        }
+    }
+
+    /// Move the demuxer into a background thread and get a channel of
+    /// demuxed packets. The demuxer will be blocked while the channel is
+    /// full.
+    pub fn into_channel(mut self, capacity: usize) -> ChannelReceiver<Packet>
+    where
+        T: Send + 'static,
+    {
+        crate::channel::spawn(capacity, move |output| {
+            while let Some(packet) = self.take()? {
+                if !output.send(packet) {
+                    break;
+                }
+            }
+
+            Ok(())
+        })
     }
 }
 
@@ -352,6 +912,116 @@ impl<T> Drop for Demuxer<T> {
 unsafe impl<T> Send for Demuxer<T> where T: Send {}
 unsafe impl<T> Sync for Demuxer<T> where T: Sync {}
 
+/// Format a given duration in microseconds as HH:MM:SS.cc.
+fn format_duration(duration: i64) -> String {
+    let centis = duration / 10_000;
+    let secs = centis / 100;
+    let mins = secs / 60;
+    let hours = mins / 60;
+
+    format!(
+        "{:02}:{:02}:{:02}.{:02}",
+        hours,
+        mins % 60,
+        secs % 60,
+        centis % 100
+    )
+}
+
+/// Format a given start time in microseconds as seconds with six decimal
+/// places (the sign is kept even if the integer part is zero).
+fn format_start_time(start: i64) -> String {
+    let sign = if start < 0 { "-" } else { "" };
+
+    format!(
+        "{}{}.{:06}",
+        sign,
+        (start / 1_000_000).unsigned_abs(),
+        (start % 1_000_000).unsigned_abs()
+    )
+}
+
+/// Iterator over demuxed packets.
+pub struct Packets<'a, T> {
+    demuxer: &'a mut Demuxer<T>,
+    finished: bool,
+}
+
+impl<T> Iterator for Packets<'_, T> {
+    type Item = Result<Packet, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let res = self.demuxer.take();
+
+        // do not try to continue after an error or EOF
+        self.finished = !matches!(res, Ok(Some(_)));
+
+        res.transpose()
+    }
+}
+
+/// Iterator over decoded frames of a single stream.
+pub struct Frames<'a, T, D> {
+    demuxer: &'a mut Demuxer<T>,
+    decoder: D,
+    stream_index: usize,
+    flushed: bool,
+}
+
+impl<T, D> Frames<'_, T, D>
+where
+    D: Decoder,
+{
+    /// Get the next frame.
+    fn next_frame(&mut self) -> Result<Option<D::Frame>, Error> {
+        loop {
+            if let Some(frame) = self.decoder.take()? {
+                return Ok(Some(frame));
+            } else if self.flushed {
+                return Ok(None);
+            }
+
+            match self.demuxer.take()? {
+                Some(packet) if packet.stream_index() == self.stream_index => {
+                    self.decoder.push(packet)?;
+                }
+                Some(_) => (),
+                None => {
+                    self.decoder.flush()?;
+                    self.flushed = true;
+                }
+            }
+        }
+    }
+
+    /// Get the underlying decoder.
+    pub fn into_decoder(self) -> D {
+        self.decoder
+    }
+}
+
+impl<T, D> Iterator for Frames<'_, T, D>
+where
+    D: Decoder,
+{
+    type Item = Result<D::Frame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.next_frame();
+
+        if res.is_err() {
+            // do not try to continue after an error
+            self.flushed = true;
+        }
+
+        res.transpose()
+    }
+}
+
 /// Demuxer with information about individual streams.
 pub struct DemuxerWithStreamInfo<T> {
     inner: Demuxer<T>,
@@ -364,10 +1034,177 @@ impl<T> DemuxerWithStreamInfo<T> {
         &self.streams
     }
 
+    /// Take the next packet from the demuxer or `None` on EOF. Unlike
+    /// `Demuxer::take()`, this also updates the encoder delay and padding
+    /// of the corresponding stream if they are signaled in the packet.
+    pub fn take(&mut self) -> Result<Option<Packet>, Error> {
+        let packet = self.inner.take()?;
+
+        if let Some(packet) = packet.as_ref() {
+            let index = packet.stream_index();
+
+            let (skip_samples, padding_samples) = self.inner.gapless_info(index);
+
+            if let Some(stream) = self.streams.get_mut(index) {
+                stream.set_gapless_info(skip_samples, padding_samples);
+            }
+        }
+
+        Ok(packet)
+    }
+
+    /// Find a stream of a given media type and (optionally) in a given
+    /// language. The language is compared case-insensitively with the stream
+    /// language (usually an ISO 639-2 code like "eng"). Attached pictures
+    /// are skipped. The default stream is preferred if there are multiple
+    /// candidates, otherwise the first one is returned.
+    pub fn find_stream(&self, media_type: MediaType, language: Option<&str>) -> Option<usize> {
+        let mut candidates = self
+            .streams
+            .iter()
+            .enumerate()
+            .filter(|(_, stream)| stream.media_type() == media_type)
+            .filter(|(_, stream)| !stream.disposition().is_attached_pic())
+            .filter(|(_, stream)| match language {
+                Some(language) => stream
+                    .language()
+                    .map(|l| l.eq_ignore_ascii_case(language))
+                    .unwrap_or(false),
+                None => true,
+            })
+            .peekable();
+
+        let first = candidates.peek().map(|(index, _)| *index);
+
+        candidates
+            .find(|(_, stream)| stream.disposition().is_default())
+            .map(|(index, _)| index)
+            .or(first)
+    }
+
+    /// Find the "best" stream of a given media type using FFmpeg's own
+    /// heuristics (`av_find_best_stream`). Unlike picking the first stream of
+    /// the type, this prefers the default stream, streams with more decoded
+    /// frames and higher bit rates, so e.g. cover art in MP3 files won't be
+    /// selected as the video stream.
+    pub fn best_stream(&self, media_type: MediaType) -> Option<usize> {
+        let ret = unsafe {
+            ffw_demuxer_find_best_stream(self.inner.ptr, media_type.into_raw(), -1, ptr::null_mut())
+        };
+
+        if ret < 0 {
+            None
+        } else {
+            Some(ret as usize)
+        }
+    }
+
+    /// Find the "best" stream of a given media type together with the name
+    /// of a decoder that can be used for it. Streams without an available
+    /// decoder are skipped. If `related_stream` is given, streams from the
+    /// same program as the related stream are preferred.
+    pub fn best_stream_with_decoder(
+        &self,
+        media_type: MediaType,
+        related_stream: Option<usize>,
+    ) -> Option<(usize, &'static str)> {
+        let related_stream = related_stream
+            .map(|index| index.try_into().unwrap())
+            .unwrap_or(-1);
+
+        let mut decoder = ptr::null();
+
+        let ret = unsafe {
+            ffw_demuxer_find_best_stream(
+                self.inner.ptr,
+                media_type.into_raw(),
+                related_stream,
+                &mut decoder,
+            )
+        };
+
+        if ret < 0 || decoder.is_null() {
+            return None;
+        }
+
+        let decoder = unsafe { CStr::from_ptr(decoder) };
+
+        Some((ret as usize, decoder.to_str().unwrap()))
+    }
+
+    /// Count frames of a given stream by reading all packets of the input.
+    /// The result is exact even if the container does not store the number
+    /// of frames, however, the whole input needs to be read. The demuxer
+    /// seeks back to the start of the input afterwards, so the input must
+    /// be seekable.
+    pub fn count_frames(&mut self, stream_index: usize) -> Result<FrameCount, Error> {
+        if stream_index >= self.streams.len() {
+            return Err(Error::new("invalid stream index"));
+        }
+
+        let mut count = 0;
+
+        while let Some(packet) = self.inner.take()? {
+            if packet.stream_index() == stream_index {
+                count += 1;
+            }
+        }
+
+        let start = self.inner.start_time().unwrap_or(Timestamp::from_secs(0));
+
+        self.inner.seek_to_timestamp(start, SeekTarget::UpTo)?;
+
+        Ok(FrameCount::new(count, FrameCountMethod::Scan))
+    }
+
     /// Get the underlying demuxer.
     pub fn into_demuxer(self) -> Demuxer<T> {
         self.inner
     }
+
+    /// Get a human-readable description of the input (similar to the
+    /// FFmpeg `av_dump_format()` output) containing the container format,
+    /// duration, bit rate and all streams.
+    pub fn dump(&self) -> String {
+        let format = self.inner.input_format();
+
+        let mut res = format!("Input: {}", format.name());
+
+        if !format.long_name().is_empty() {
+            res += &format!(" ({})", format.long_name());
+        }
+
+        res += "\n  Duration: ";
+
+        match self.inner.duration().and_then(|d| d.as_micros()) {
+            Some(duration) => res += &format_duration(duration),
+            None => res += "N/A",
+        }
+
+        if let Some(start) = self.inner.start_time().and_then(|s| s.as_micros()) {
+            res += ", start: ";
+            res += &format_start_time(start);
+        }
+
+        match self.inner.bit_rate() {
+            Some(bit_rate) => res += &format!(", bitrate: {} kb/s", bit_rate / 1000),
+            None => res += ", bitrate: N/A",
+        }
+
+        for (index, stream) in self.streams.iter().enumerate() {
+            res += &format!("\n    Stream #{}", index);
+
+            let id = stream.id();
+
+            if id != 0 {
+                res += &format!("[0x{:x}]", id);
+            }
+
+            res += &format!(": {}", stream);
+        }
+
+        res
+    }
 }
 
 impl<T> AsRef<Demuxer<T>> for DemuxerWithStreamInfo<T> {
@@ -458,7 +1295,430 @@ impl InputFormat {
 
         Some(res)
     }
+
+    /// Get short name of the format (e.g. "mov,mp4,m4a,3gp,3g2,mj2").
+    pub fn name(&self) -> &'static str {
+        unsafe {
+            let ptr = ffw_input_format_get_name(self.ptr);
+
+            CStr::from_ptr(ptr).to_str().unwrap_or("")
+        }
+    }
+
+    /// Get descriptive name of the format (e.g. "QuickTime / MOV").
+    pub fn long_name(&self) -> &'static str {
+        unsafe {
+            let ptr = ffw_input_format_get_long_name(self.ptr);
+
+            if ptr.is_null() {
+                return "";
+            }
+
+            CStr::from_ptr(ptr).to_str().unwrap_or("")
+        }
+    }
+
+    /// Get file name extensions commonly used for the format (e.g. "mp4").
+    /// Note that the list may be empty even for file-based formats.
+    pub fn extensions(&self) -> Vec<&'static str> {
+        let ptr = unsafe { ffw_input_format_get_extensions(self.ptr) };
+
+        if ptr.is_null() {
+            return Vec::new();
+        }
+
+        let extensions = unsafe { CStr::from_ptr(ptr).to_str().unwrap_or("") };
+
+        extensions
+            .split(',')
+            .filter(|extension| !extension.is_empty())
+            .collect()
+    }
+
+    /// Get MIME types associated with the format.
+    pub fn mime_types(&self) -> Vec<&'static str> {
+        let ptr = unsafe { ffw_input_format_get_mime_type(self.ptr) };
+
+        if ptr.is_null() {
+            return Vec::new();
+        }
+
+        let mime_types = unsafe { CStr::from_ptr(ptr).to_str().unwrap_or("") };
+
+        mime_types
+            .split(',')
+            .filter(|mime_type| !mime_type.is_empty())
+            .collect()
+    }
+
+    /// Check if a given flag is set.
+    fn has_flag(&self, flag: c_int) -> bool {
+        let flags = unsafe { ffw_input_format_get_flags(self.ptr) };
+
+        (flags & flag) != 0
+    }
+
+    /// Check if the format reads a live source, i.e. a capture device or a
+    /// real-time network stream (RTSP, RTP or SDP). Seeking does not make
+    /// sense for such inputs.
+    pub fn is_live(&self) -> bool {
+        if self.has_flag(INPUT_FORMAT_FLAG_DEVICE) {
+            return true;
+        }
+
+        self.name()
+            .split(',')
+            .any(|name| matches!(name, "rtsp" | "rtp" | "sdp"))
+    }
+
+    /// Check if the format opens its own files or network connections (e.g.
+    /// image sequences or RTSP), i.e. the IO passed to the demuxer is not
+    /// used and the `url` option must be set instead.
+    pub fn no_file(&self) -> bool {
+        self.has_flag(INPUT_FORMAT_FLAG_NO_FILE)
+    }
+
+    /// Check if the format does not support seeking by byte offsets (see
+    /// `Demuxer::seek_to_byte()`).
+    pub fn no_byte_seek(&self) -> bool {
+        self.has_flag(INPUT_FORMAT_FLAG_NO_BYTE_SEEK)
+    }
+
+    /// Check if the format does not store timestamps.
+    pub fn no_timestamps(&self) -> bool {
+        self.has_flag(INPUT_FORMAT_FLAG_NO_TIMESTAMPS)
+    }
+
+    /// Check if the format allows timestamp discontinuities (e.g. MPEG-TS).
+    pub fn discontinuous_timestamps(&self) -> bool {
+        self.has_flag(INPUT_FORMAT_FLAG_TS_DISCONT)
+    }
+
+    /// Check if the format does not have its own seeking support and FFmpeg
+    /// builds a generic index while reading the input instead (i.e. seeking
+    /// may be slow or inaccurate).
+    pub fn generic_index(&self) -> bool {
+        self.has_flag(INPUT_FORMAT_FLAG_GENERIC_INDEX)
+    }
+
+    /// Get options specific to this input format.
+    pub fn options(&self) -> Vec<OptionInfo> {
+        unsafe { crate::options::input_format_options(self.ptr) }
+    }
 }
 
 unsafe impl Send for InputFormat {}
 unsafe impl Sync for InputFormat {}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use super::{format_start_time, Demuxer, FormatFlag, InputFormat};
+
+    use crate::{
+        codec::{
+            audio::{
+                frame::{get_channel_layout, get_sample_format},
+                AudioEncoder, AudioFrameMut,
+            },
+            video::{frame::get_pixel_format, VideoEncoder, VideoFrameMut},
+            Encoder,
+        },
+        format::{
+            io::IO,
+            muxer::{Muxer, OutputFormat},
+            stream::FrameCountMethod,
+        },
+        time::{TimeBase, Timestamp},
+    };
+
+    /// Create a short MPEG-TS file with a single MJPEG stream.
+    fn mpegts_file() -> Vec<u8> {
+        mpegts_file_with_codec("mjpeg", "yuvj420p")
+    }
+
+    /// Create a short MPEG-TS file with a single video stream.
+    fn mpegts_file_with_codec(codec: &str, pixel_format: &str) -> Vec<u8> {
+        let pixel_format = get_pixel_format(pixel_format);
+        let time_base = TimeBase::new(1, 25);
+
+        let mut encoder = VideoEncoder::builder(codec)
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .time_base(time_base)
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder();
+
+        builder
+            .add_stream(&encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_write_stream(Vec::new()),
+                OutputFormat::find_by_name("mpegts").unwrap(),
+            )
+            .unwrap();
+
+        for i in 0..25 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 48)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(0)).unwrap();
+            }
+        }
+
+        muxer.close().unwrap().into_stream()
+    }
+
+    #[test]
+    fn test_format_start_time() {
+        assert_eq!(format_start_time(0), "0.000000");
+        assert_eq!(format_start_time(1_400_000), "1.400000");
+        assert_eq!(format_start_time(-500_000), "-0.500000");
+        assert_eq!(format_start_time(-1_500_000), "-1.500000");
+    }
+
+    #[test]
+    fn test_input_format_info() {
+        let format = InputFormat::find_by_name("mpegts").unwrap();
+
+        assert_eq!(format.name(), "mpegts");
+        assert!(format.discontinuous_timestamps());
+        assert!(!format.is_live());
+        assert!(!format.no_file());
+
+        let format = InputFormat::find_by_name("image2").unwrap();
+
+        assert!(format.no_file());
+
+        let format = InputFormat::guess_from_file_name("in.mp3").unwrap();
+
+        assert!(format.extensions().contains(&"mp3"));
+    }
+
+    #[test]
+    fn test_format_flags() {
+        assert_eq!(
+            FormatFlag::option_value(&[FormatFlag::GeneratePts, FormatFlag::DiscardCorrupt]),
+            "+genpts+discardcorrupt"
+        );
+
+        let mut demuxer = Demuxer::builder()
+            .set_option("fflags", "+igndts")
+            .format_flags(&[FormatFlag::GeneratePts, FormatFlag::DiscardCorrupt])
+            .low_latency()
+            .build(IO::from_seekable_read_stream(Cursor::new(mpegts_file())))
+            .unwrap();
+
+        let mut packets = 0;
+
+        while let Some(packet) = demuxer.take().unwrap() {
+            assert!(!packet.pts().is_null());
+
+            packets += 1;
+        }
+
+        assert_eq!(packets, 25);
+    }
+
+    #[test]
+    fn test_set_option() {
+        let res = Demuxer::builder()
+            .set_option("probesize", 4096)
+            .set_option("scan_all_pmts", 1)
+            .build(IO::from_read_stream(Cursor::new(mpegts_file())));
+
+        assert!(res.is_ok());
+
+        let res = Demuxer::builder()
+            .set_option("probesize", 4096)
+            .set_option("no_such_option", 1)
+            .build(IO::from_read_stream(Cursor::new(mpegts_file())));
+
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "unknown options: no_such_option"
+        );
+
+        let res = Demuxer::builder()
+            .set_option("probe\0size", 4096)
+            .build(IO::from_read_stream(Cursor::new(mpegts_file())));
+
+        assert!(res.is_err());
+
+        let res = Demuxer::builder()
+            .probe_size(16)
+            .build(IO::from_read_stream(Cursor::new(mpegts_file())));
+
+        assert!(res.is_err());
+
+        let res = Demuxer::builder()
+            .analyze_duration(Duration::MAX)
+            .build(IO::from_read_stream(Cursor::new(mpegts_file())));
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_gapless_info() {
+        let channel_layout = get_channel_layout("mono");
+        let sample_format = get_sample_format("fltp");
+        let time_base = TimeBase::new(1, 48_000);
+
+        let mut encoder = AudioEncoder::builder("aac")
+            .unwrap()
+            .sample_format(sample_format)
+            .sample_rate(48_000)
+            .channel_layout(channel_layout)
+            .time_base(time_base)
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder();
+
+        builder
+            .add_stream(&encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_seekable_write_stream(Cursor::new(Vec::new())),
+                OutputFormat::find_by_name("mp4").unwrap(),
+            )
+            .unwrap();
+
+        let frame_size = encoder.samples_per_frame().unwrap();
+
+        for i in 0..10 {
+            let frame = AudioFrameMut::silence(channel_layout, sample_format, 48_000, frame_size)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new((i * frame_size) as i64, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(0)).unwrap();
+            }
+        }
+
+        encoder.flush().unwrap();
+
+        while let Some(packet) = encoder.take().unwrap() {
+            muxer.push(packet.with_stream_index(0)).unwrap();
+        }
+
+        let data = muxer.close().unwrap().into_stream().into_inner();
+
+        let mut demuxer = Demuxer::builder()
+            .build(IO::from_seekable_read_stream(Cursor::new(data)))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        // the encoder delay is stored in the MP4 edit list and signaled in
+        // the first packet
+        demuxer.take().unwrap().unwrap();
+
+        assert_eq!(demuxer.streams()[0].skip_samples(), 1024);
+    }
+
+    #[test]
+    fn test_flush_buffers() {
+        let mut demuxer = Demuxer::builder()
+            .build(IO::from_read_stream(Cursor::new(mpegts_file())))
+            .unwrap();
+
+        for _ in 0..5 {
+            demuxer.take().unwrap().unwrap();
+        }
+
+        demuxer.flush_buffers().unwrap();
+
+        let mut packets = 5;
+
+        while demuxer.take().unwrap().is_some() {
+            packets += 1;
+        }
+
+        // the buffered data should be dropped
+        assert!(packets < 25);
+    }
+
+    #[test]
+    fn test_frame_count() {
+        let mut demuxer = Demuxer::builder()
+            .build(IO::from_seekable_read_stream(Cursor::new(
+                mpegts_file_with_codec("mpeg4", "yuv420p"),
+            )))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        let estimated = demuxer.streams()[0].estimated_frame_count().unwrap();
+
+        assert_eq!(estimated.method(), FrameCountMethod::Duration);
+        assert_eq!(estimated.count(), 25);
+
+        let counted = demuxer.count_frames(0).unwrap();
+
+        assert_eq!(counted.method(), FrameCountMethod::Scan);
+        assert_eq!(counted.count(), 25);
+
+        // the demuxer should be rewound
+        let packet = demuxer.take().unwrap().unwrap();
+
+        assert_eq!(packet.pts(), demuxer.streams()[0].start_time().unwrap());
+
+        assert!(demuxer.count_frames(1).is_err());
+    }
+
+    #[test]
+    fn test_find_stream_info_with_progress() {
+        let data = mpegts_file();
+
+        let demuxer = Demuxer::builder()
+            .build(IO::from_read_stream(Cursor::new(data.clone())))
+            .unwrap();
+
+        let (_, err) = demuxer
+            .find_stream_info_with_progress(None, |_| false)
+            .err()
+            .unwrap();
+
+        assert!(err.to_string().contains("cancelled"));
+
+        let demuxer = Demuxer::builder()
+            .build(IO::from_read_stream(Cursor::new(data)))
+            .unwrap();
+
+        let mut calls = 0;
+        let mut bytes = 0;
+
+        let demuxer = demuxer
+            .find_stream_info_with_progress(None, |progress| {
+                calls += 1;
+                bytes = progress.bytes();
+                true
+            })
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        assert!(calls > 0);
+        assert!(bytes > 0);
+        assert_eq!(demuxer.streams().len(), 1);
+    }
+}
@@ -0,0 +1,462 @@
+//! Timed ID3 metadata.
+//!
+//! HLS streams (and MPEG-TS in general) may carry ID3v2 tags in a data
+//! stream with codec `timed_id3`. The demuxer returns these tags as regular
+//! packets. This module contains a parser converting the packets into
+//! timestamped key/value events (e.g. ad-insertion markers or now-playing
+//! information).
+
+use crate::{packet::Packet, time::Timestamp, Error};
+
+/// Value of an ID3 frame.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Id3Value {
+    /// Text (text frames, comments, URLs, ...).
+    Text(String),
+    /// Binary data (private frames and frames not known to the parser).
+    Binary(Vec<u8>),
+}
+
+impl Id3Value {
+    /// Get the value as text (if possible).
+    pub fn as_text(&self) -> Option<&str> {
+        if let Self::Text(text) = self {
+            Some(text)
+        } else {
+            None
+        }
+    }
+
+    /// Get the value as binary data (if possible).
+    pub fn as_binary(&self) -> Option<&[u8]> {
+        if let Self::Binary(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
+}
+
+/// A single ID3 frame.
+#[derive(Debug, Clone)]
+pub struct Id3Frame {
+    id: String,
+    description: Option<String>,
+    value: Id3Value,
+}
+
+impl Id3Frame {
+    /// Get the frame ID (e.g. "TIT2", "TXXX" or "PRIV").
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the frame description. This is the description of user-defined
+    /// text/URL frames (TXXX, WXXX), description of comments (COMM) or owner
+    /// of private frames (PRIV).
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Get the frame key. The key is the frame ID optionally followed by a
+    /// colon and the frame description (e.g. "TIT2" or
+    /// "PRIV:com.apple.streaming.transportStreamTimestamp").
+    pub fn key(&self) -> String {
+        if let Some(description) = self.description.as_ref() {
+            format!("{}:{}", self.id, description)
+        } else {
+            self.id.clone()
+        }
+    }
+
+    /// Get the frame value.
+    pub fn value(&self) -> &Id3Value {
+        &self.value
+    }
+}
+
+/// Timed ID3 event (i.e. a single ID3 tag with a timestamp).
+#[derive(Debug, Clone)]
+pub struct Id3Event {
+    pts: Timestamp,
+    frames: Vec<Id3Frame>,
+}
+
+impl Id3Event {
+    /// Parse a given timed ID3 packet.
+    pub fn from_packet(packet: &Packet) -> Result<Self, Error> {
+        Self::parse(packet.data(), packet.pts())
+    }
+
+    /// Parse a given ID3v2 tag.
+    pub fn parse(data: &[u8], pts: Timestamp) -> Result<Self, Error> {
+        let frames = parse_tag(data)?;
+
+        let res = Self { pts, frames };
+
+        Ok(res)
+    }
+
+    /// Get presentation timestamp of the event.
+    pub fn pts(&self) -> Timestamp {
+        self.pts
+    }
+
+    /// Get all frames of the tag.
+    pub fn frames(&self) -> &[Id3Frame] {
+        &self.frames
+    }
+
+    /// Get value of the first frame with a given key (see
+    /// `Id3Frame::key()`).
+    pub fn get(&self, key: &str) -> Option<&Id3Value> {
+        self.frames
+            .iter()
+            .find(|frame| frame.key() == key)
+            .map(|frame| &frame.value)
+    }
+}
+
+/// Helper function for creating parse errors.
+fn invalid_tag() -> Error {
+    Error::new("invalid ID3 tag")
+}
+
+/// Decode a 28-bit synchsafe integer.
+fn synchsafe_u32(data: &[u8]) -> u32 {
+    data.iter()
+        .take(4)
+        .fold(0, |acc, &b| (acc << 7) | (b as u32 & 0x7f))
+}
+
+/// Decode a big-endian 32-bit integer.
+fn be_u32(data: &[u8]) -> u32 {
+    data.iter().take(4).fold(0, |acc, &b| (acc << 8) | b as u32)
+}
+
+/// Remove the unsynchronisation scheme (i.e. replace all 0xff 0x00
+/// sequences with 0xff).
+fn resynchronize(data: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(data.len());
+
+    let mut previous = 0;
+
+    for &b in data {
+        if previous != 0xff || b != 0x00 {
+            res.push(b);
+        }
+
+        previous = b;
+    }
+
+    res
+}
+
+/// Parse a given ID3v2 tag and return all its frames.
+fn parse_tag(data: &[u8]) -> Result<Vec<Id3Frame>, Error> {
+    if data.len() < 10 || &data[..3] != b"ID3" {
+        return Err(invalid_tag());
+    }
+
+    let version = data[3];
+    let flags = data[5];
+    let size = synchsafe_u32(&data[6..10]) as usize;
+
+    if version != 3 && version != 4 {
+        return Err(Error::new(format!(
+            "unsupported ID3 version: 2.{}",
+            version
+        )));
+    }
+
+    let body = data.get(10..10 + size).ok_or_else(invalid_tag)?;
+
+    let body = if version == 3 && (flags & 0x80) != 0 {
+        resynchronize(body)
+    } else {
+        body.to_vec()
+    };
+
+    let mut offset = 0;
+
+    if (flags & 0x40) != 0 {
+        let header = body.get(..4).ok_or_else(invalid_tag)?;
+
+        // ID3v2.3 does not include the size field itself
+        offset = if version == 3 {
+            be_u32(header) as usize + 4
+        } else {
+            synchsafe_u32(header) as usize
+        };
+    }
+
+    let mut frames = Vec::new();
+
+    while offset + 10 <= body.len() {
+        let header = &body[offset..offset + 10];
+
+        // padding
+        if header[0] == 0 {
+            break;
+        }
+
+        let id = String::from_utf8_lossy(&header[..4]).into_owned();
+
+        let size = if version == 3 {
+            be_u32(&header[4..8]) as usize
+        } else {
+            synchsafe_u32(&header[4..8]) as usize
+        };
+
+        let flags = u16::from_be_bytes([header[8], header[9]]);
+
+        offset += 10;
+
+        let data = body.get(offset..offset + size).ok_or_else(invalid_tag)?;
+
+        offset += size;
+
+        if let Some(data) = frame_content(version, flags, data) {
+            frames.push(parse_frame(id, &data));
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Get frame content with all frame-level encodings removed. `None` is
+/// returned for compressed and encrypted frames.
+fn frame_content(version: u8, flags: u16, data: &[u8]) -> Option<Vec<u8>> {
+    if version == 3 {
+        // compression or encryption
+        if (flags & 0x00c0) != 0 {
+            return None;
+        }
+
+        // grouping identity
+        if (flags & 0x0020) != 0 {
+            return data.get(1..).map(|data| data.to_vec());
+        }
+
+        Some(data.to_vec())
+    } else {
+        // compression or encryption
+        if (flags & 0x000c) != 0 {
+            return None;
+        }
+
+        let mut data = data;
+
+        // grouping identity
+        if (flags & 0x0040) != 0 {
+            data = data.get(1..)?;
+        }
+
+        // data length indicator
+        if (flags & 0x0001) != 0 {
+            data = data.get(4..)?;
+        }
+
+        // unsynchronisation
+        if (flags & 0x0002) != 0 {
+            Some(resynchronize(data))
+        } else {
+            Some(data.to_vec())
+        }
+    }
+}
+
+/// Parse content of a given frame.
+fn parse_frame(id: String, data: &[u8]) -> Id3Frame {
+    let (description, value) = match id.as_str() {
+        "TXXX" | "WXXX" if !data.is_empty() => {
+            let encoding = data[0];
+
+            let (description, value) = split_string(encoding, &data[1..]);
+
+            let value = if id == "TXXX" {
+                decode_string(encoding, value)
+            } else {
+                decode_string(0, value)
+            };
+
+            (
+                Some(decode_string(encoding, description)),
+                Id3Value::Text(value),
+            )
+        }
+        "COMM" | "USLT" if data.len() >= 4 => {
+            let encoding = data[0];
+
+            // skip the language code
+            let (description, value) = split_string(encoding, &data[4..]);
+
+            (
+                Some(decode_string(encoding, description)),
+                Id3Value::Text(decode_string(encoding, value)),
+            )
+        }
+        "PRIV" => {
+            let (owner, value) = split_string(0, data);
+
+            (
+                Some(decode_string(0, owner)),
+                Id3Value::Binary(value.to_vec()),
+            )
+        }
+        _ if id.starts_with('T') && !data.is_empty() => {
+            (None, Id3Value::Text(decode_string(data[0], &data[1..])))
+        }
+        _ if id.starts_with('W') => (None, Id3Value::Text(decode_string(0, data))),
+        _ => (None, Id3Value::Binary(data.to_vec())),
+    };
+
+    Id3Frame {
+        id,
+        description,
+        value,
+    }
+}
+
+/// Split a given buffer at the first string terminator.
+fn split_string(encoding: u8, data: &[u8]) -> (&[u8], &[u8]) {
+    if encoding == 1 || encoding == 2 {
+        let mut pos = 0;
+
+        while pos + 1 < data.len() {
+            if data[pos] == 0 && data[pos + 1] == 0 {
+                return (&data[..pos], &data[pos + 2..]);
+            }
+
+            pos += 2;
+        }
+    } else if let Some(pos) = data.iter().position(|&b| b == 0) {
+        return (&data[..pos], &data[pos + 1..]);
+    }
+
+    (data, &[])
+}
+
+/// Decode a given string. Multiple null-separated values are joined using
+/// "/".
+fn decode_string(encoding: u8, data: &[u8]) -> String {
+    let res = match encoding {
+        1 | 2 => {
+            let (data, big_endian) = match data {
+                [0xff, 0xfe, rest @ ..] => (rest, false),
+                [0xfe, 0xff, rest @ ..] => (rest, true),
+                _ => (data, true),
+            };
+
+            let units = data
+                .chunks_exact(2)
+                .map(|unit| {
+                    if big_endian {
+                        u16::from_be_bytes([unit[0], unit[1]])
+                    } else {
+                        u16::from_le_bytes([unit[0], unit[1]])
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            String::from_utf16_lossy(&units)
+        }
+        3 => String::from_utf8_lossy(data).into_owned(),
+        _ => data.iter().map(|&b| b as char).collect(),
+    };
+
+    res.trim_end_matches('\0').replace('\0', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Id3Event, Id3Value};
+
+    use crate::time::Timestamp;
+
+    /// Create an ID3v2.4 tag containing given frames.
+    fn make_tag(frames: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut body = Vec::new();
+
+        for (id, data) in frames {
+            let size = data.len() as u32;
+
+            body.extend_from_slice(&id[..]);
+            body.extend_from_slice(&[
+                ((size >> 21) & 0x7f) as u8,
+                ((size >> 14) & 0x7f) as u8,
+                ((size >> 7) & 0x7f) as u8,
+                (size & 0x7f) as u8,
+                0,
+                0,
+            ]);
+            body.extend_from_slice(data);
+        }
+
+        let size = body.len() as u32;
+
+        let mut res = vec![
+            b'I',
+            b'D',
+            b'3',
+            4,
+            0,
+            0,
+            ((size >> 21) & 0x7f) as u8,
+            ((size >> 14) & 0x7f) as u8,
+            ((size >> 7) & 0x7f) as u8,
+            (size & 0x7f) as u8,
+        ];
+
+        res.extend_from_slice(&body);
+        res
+    }
+
+    #[test]
+    fn test_text_frames() {
+        let tag = make_tag(&[
+            (b"TIT2", b"\x03Song title"),
+            (b"TXXX", b"\x00ad\x00start"),
+            (b"TPE1", b"\x01\xff\xfeA\x00B\x00"),
+        ]);
+
+        let event = Id3Event::parse(&tag, Timestamp::null()).unwrap();
+
+        assert_eq!(event.frames().len(), 3);
+
+        assert_eq!(
+            event.get("TIT2"),
+            Some(&Id3Value::Text(String::from("Song title")))
+        );
+
+        assert_eq!(
+            event.get("TXXX:ad"),
+            Some(&Id3Value::Text(String::from("start")))
+        );
+
+        assert_eq!(event.get("TPE1"), Some(&Id3Value::Text(String::from("AB"))));
+    }
+
+    #[test]
+    fn test_private_frame() {
+        let tag = make_tag(&[(b"PRIV", b"owner\x00\x01\x02")]);
+
+        let event = Id3Event::parse(&tag, Timestamp::null()).unwrap();
+
+        let frame = &event.frames()[0];
+
+        assert_eq!(frame.key(), "PRIV:owner");
+        assert_eq!(frame.value().as_binary(), Some(&[1u8, 2][..]));
+    }
+
+    #[test]
+    fn test_invalid_tag() {
+        assert!(Id3Event::parse(b"ID3", Timestamp::null()).is_err());
+
+        let mut tag = make_tag(&[(b"TIT2", b"\x03title")]);
+
+        tag.truncate(tag.len() - 2);
+
+        assert!(Id3Event::parse(&tag, Timestamp::null()).is_err());
+    }
+}
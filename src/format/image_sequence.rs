@@ -0,0 +1,449 @@
+//! Numbered image sequence (e.g. `frame_%04d.png`) input and output.
+//!
+//! Image sequences are read and written using the FFmpeg `image2`
+//! demuxer/muxer. Note that FFmpeg opens the individual image files itself,
+//! i.e. the file name pattern is a path (or URL) rather than an IO. The
+//! pattern uses the `printf`-like `%d` (or `%0Nd`) placeholder for the
+//! frame number.
+//!
+//! # Example
+//! ```ignore
+//! let mut reader = ImageSequenceReader::builder("plates/shot_%04d.jpg")
+//!     .frame_rate(Rational::new(24, 1))
+//!     .start_number(1001)
+//!     .build()?;
+//!
+//! let mut output = ImageSequenceWriter::builder("out/shot_%04d.png")
+//!     .start_number(1001)
+//!     .build()?;
+//!
+//! while let Some(frame) = reader.take()? {
+//!     output.push(frame)?;
+//! }
+//!
+//! output.finish()?;
+//! ```
+
+use std::io::{self, Empty, Sink};
+
+pub use crate::codec::video::ImageFormat;
+
+use crate::{
+    codec::{
+        video::{VideoDecoder, VideoEncoder, VideoFrame, VideoFrameScaler},
+        Decoder, Encoder,
+    },
+    format::{
+        demuxer::{Demuxer, DemuxerWithStreamInfo, InputFormat},
+        io::IO,
+        muxer::{Muxer, OutputFormat},
+        stream::Stream,
+    },
+    math::Rational,
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Builder for the image sequence reader.
+pub struct ImageSequenceReaderBuilder {
+    pattern: String,
+    frame_rate: Rational,
+    start_number: Option<u32>,
+    glob: bool,
+}
+
+impl ImageSequenceReaderBuilder {
+    /// Create a new builder.
+    fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            frame_rate: Rational::new(25, 1),
+            start_number: None,
+            glob: false,
+        }
+    }
+
+    /// Set the frame rate of the resulting video stream. The default is 25
+    /// frames per second.
+    ///
+    /// # Panics
+    /// The method panics if the frame rate is not positive.
+    pub fn frame_rate(mut self, frame_rate: Rational) -> Self {
+        assert!(
+            frame_rate.num() > 0 && frame_rate.den() > 0,
+            "the frame rate must be positive"
+        );
+
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// Set number of the first image. By default, the first existing image
+    /// numbered 0 - 4 is used.
+    pub fn start_number(mut self, number: u32) -> Self {
+        self.start_number = Some(number);
+        self
+    }
+
+    /// Interpret the pattern as a glob pattern (e.g. `*.png`) instead of a
+    /// numbered sequence. The images are read in the alphabetical order.
+    /// Note that FFmpeg must be built with glob support.
+    pub fn glob(mut self, glob: bool) -> Self {
+        self.glob = glob;
+        self
+    }
+
+    /// Open the image sequence.
+    pub fn build(self) -> Result<ImageSequenceReader, Error> {
+        let format = InputFormat::find_by_name("image2")
+            .ok_or_else(|| Error::new("unknown input format"))?;
+
+        let pattern_type = if self.glob { "glob" } else { "sequence" };
+
+        let mut builder = Demuxer::builder()
+            .input_format(Some(format))
+            .set_option("url", &self.pattern)
+            .set_option("pattern_type", pattern_type)
+            .set_option("framerate", self.frame_rate);
+
+        if let Some(number) = self.start_number {
+            builder = builder.set_option("start_number", number);
+        }
+
+        // the IO is not used by the image2 demuxer
+        let demuxer = builder
+            .build(IO::from_read_stream(io::empty()))?
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)?;
+
+        let stream = demuxer
+            .streams()
+            .first()
+            .ok_or_else(|| Error::new("no video stream"))?;
+
+        let decoder = VideoDecoder::from_stream(stream)?.build()?;
+
+        let res = ImageSequenceReader {
+            demuxer,
+            decoder,
+            flushed: false,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Reader of a numbered image sequence. The sequence is read as a video
+/// stream with one frame per image and with timestamps given by the chosen
+/// frame rate. The frames are returned in the pixel format of the images.
+pub struct ImageSequenceReader {
+    demuxer: DemuxerWithStreamInfo<Empty>,
+    decoder: VideoDecoder,
+    flushed: bool,
+}
+
+impl ImageSequenceReader {
+    /// Get a builder for an image sequence matching a given file name
+    /// pattern (e.g. `frames/%04d.png`).
+    pub fn builder(pattern: &str) -> ImageSequenceReaderBuilder {
+        ImageSequenceReaderBuilder::new(pattern)
+    }
+
+    /// Get the video stream.
+    pub fn stream(&self) -> &Stream {
+        &self.demuxer.streams()[0]
+    }
+
+    /// Get time base of the decoded frames.
+    pub fn time_base(&self) -> TimeBase {
+        self.stream().time_base()
+    }
+
+    /// Take the next frame. `None` is returned at the end of the sequence.
+    pub fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        loop {
+            if let Some(frame) = self.decoder.take()? {
+                return Ok(Some(frame));
+            } else if self.flushed {
+                return Ok(None);
+            }
+
+            match self.demuxer.take()? {
+                Some(packet) => self.decoder.push(packet)?,
+                None => {
+                    self.decoder.flush()?;
+                    self.flushed = true;
+                }
+            }
+        }
+    }
+}
+
+/// Builder for the image sequence writer.
+pub struct ImageSequenceWriterBuilder {
+    pattern: String,
+    format: Option<ImageFormat>,
+    start_number: u32,
+    quality: Option<u32>,
+}
+
+impl ImageSequenceWriterBuilder {
+    /// Create a new builder.
+    fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            format: ImageFormat::guess_from_file_name(pattern),
+            start_number: 1,
+            quality: None,
+        }
+    }
+
+    /// Set the image format. By default, the format is guessed from the
+    /// pattern extension.
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set number of the first image. The default is 1.
+    pub fn start_number(mut self, number: u32) -> Self {
+        self.start_number = number;
+        self
+    }
+
+    /// Set JPEG quality scale from 2 (best) to 31 (worst). The option has no
+    /// effect for other formats. The encoder default is used if not set.
+    ///
+    /// # Panics
+    /// The method panics if the quality is out of range.
+    pub fn jpeg_quality(mut self, quality: u32) -> Self {
+        assert!(
+            (2..=31).contains(&quality),
+            "the JPEG quality must be in the 2 - 31 range"
+        );
+
+        self.quality = Some(quality);
+        self
+    }
+
+    /// Build the writer.
+    pub fn build(self) -> Result<ImageSequenceWriter, Error> {
+        let format = self
+            .format
+            .ok_or_else(|| Error::new("unable to guess the image format"))?;
+
+        let res = ImageSequenceWriter {
+            pattern: self.pattern,
+            format,
+            start_number: self.start_number,
+            quality: self.quality,
+            scaler: None,
+            encoder: None,
+            muxer: None,
+            frames: 0,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Writer of decoded frames into a numbered image sequence. Frames are
+/// converted into a pixel format supported by the image format (e.g.
+/// `rgb24`/`rgba` for PNG or `yuvj420p` for JPEG) and each frame is written
+/// into a separate file. The frame timestamps are ignored. The encoder and
+/// the muxer are created with the first frame, all frames must have the
+/// same size and pixel format.
+pub struct ImageSequenceWriter {
+    pattern: String,
+    format: ImageFormat,
+    start_number: u32,
+    quality: Option<u32>,
+    scaler: Option<VideoFrameScaler>,
+    encoder: Option<VideoEncoder>,
+    muxer: Option<Muxer<Sink>>,
+    frames: u64,
+}
+
+impl ImageSequenceWriter {
+    /// Get a builder for an image sequence writer using a given file name
+    /// pattern (e.g. `frames/%04d.png`).
+    pub fn builder(pattern: &str) -> ImageSequenceWriterBuilder {
+        ImageSequenceWriterBuilder::new(pattern)
+    }
+
+    /// Get the number of images written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Write a given frame into the next image file.
+    pub fn push(&mut self, frame: VideoFrame) -> Result<(), Error> {
+        if self.encoder.is_none() {
+            self.init(&frame)?;
+        }
+
+        let frame = match self.scaler.as_mut() {
+            Some(scaler) => scaler.scale(&frame)?,
+            None => frame,
+        };
+
+        let time_base = TimeBase::new(1, 1);
+
+        let frame = frame
+            .with_time_base(time_base)
+            .with_pts(Timestamp::new(self.frames as i64, time_base));
+
+        self.encoder.as_mut().unwrap().push(frame)?;
+
+        self.frames += 1;
+
+        self.write_packets()
+    }
+
+    /// Flush the encoder and close the sequence. An error is returned if no
+    /// frames were pushed.
+    pub fn finish(mut self) -> Result<(), Error> {
+        let encoder = self
+            .encoder
+            .as_mut()
+            .ok_or_else(|| Error::new("no frames"))?;
+
+        encoder.flush()?;
+
+        self.write_packets()?;
+
+        self.muxer.take().unwrap().close()?;
+
+        Ok(())
+    }
+
+    /// Write all packets available in the encoder.
+    fn write_packets(&mut self) -> Result<(), Error> {
+        let encoder = self.encoder.as_mut().unwrap();
+        let muxer = self.muxer.as_mut().unwrap();
+
+        while let Some(packet) = encoder.take()? {
+            muxer.push(packet.with_stream_index(0))?;
+        }
+
+        Ok(())
+    }
+
+    /// Create the scaler, the encoder and the muxer for frames like a given
+    /// one.
+    fn init(&mut self, frame: &VideoFrame) -> Result<(), Error> {
+        let pixel_format = self.format.encoder_pixel_format(frame.pixel_format());
+
+        if pixel_format != frame.pixel_format() {
+            let scaler = VideoFrameScaler::builder()
+                .source_pixel_format(frame.pixel_format())
+                .source_width(frame.width())
+                .source_height(frame.height())
+                .target_pixel_format(pixel_format)
+                .target_width(frame.width())
+                .target_height(frame.height())
+                .build()?;
+
+            self.scaler = Some(scaler);
+        }
+
+        let mut builder =
+            self.format
+                .encoder_builder(pixel_format, frame.width(), frame.height())?;
+
+        if let Some(quality) = self.quality {
+            builder = builder
+                .set_option("qmin", quality)
+                .set_option("qmax", quality);
+        }
+
+        let encoder = builder.build()?;
+
+        let mut muxer_builder = Muxer::builder();
+
+        muxer_builder.add_stream(&encoder.codec_parameters().into())?;
+
+        let format = OutputFormat::find_by_name("image2")
+            .ok_or_else(|| Error::new("unknown output format"))?;
+
+        // the IO is not used by the image2 muxer
+        let muxer = muxer_builder
+            .set_option("url", &self.pattern)
+            .set_option("start_number", self.start_number)
+            .build(IO::from_write_stream(io::sink()), format)?;
+
+        self.encoder = Some(encoder);
+        self.muxer = Some(muxer);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, str::FromStr};
+
+    use super::{ImageFormat, ImageSequenceReader, ImageSequenceWriter};
+
+    use crate::{
+        codec::video::{PixelFormat, VideoFrameMut},
+        math::Rational,
+        time::Timestamp,
+    };
+
+    #[test]
+    fn test_jpeg_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ac-ffmpeg-seq-{}", std::process::id()));
+
+        fs::create_dir_all(&dir).unwrap();
+
+        let pattern = dir.join("frame_%04d.jpg");
+        let pattern = pattern.to_str().unwrap();
+
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+
+        let mut writer = ImageSequenceWriter::builder(pattern)
+            .start_number(1001)
+            .jpeg_quality(2)
+            .build()
+            .unwrap();
+
+        for _ in 0..3 {
+            writer
+                .push(VideoFrameMut::black(pixel_format, 64, 48).freeze())
+                .unwrap();
+        }
+
+        assert_eq!(writer.frames(), 3);
+
+        writer.finish().unwrap();
+
+        assert!(dir.join("frame_1003.jpg").exists());
+
+        let mut reader = ImageSequenceReader::builder(pattern)
+            .frame_rate(Rational::new(24, 1))
+            .start_number(1001)
+            .build()
+            .unwrap();
+
+        assert_eq!(reader.time_base().den(), 24);
+
+        let mut count = 0;
+
+        while let Some(frame) = reader.take().unwrap() {
+            assert_eq!((frame.width(), frame.height()), (64, 48));
+            assert_eq!(frame.pts(), Timestamp::new(count, reader.time_base()));
+
+            count += 1;
+        }
+
+        assert_eq!(count, 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            ImageFormat::guess_from_file_name("out_%04d.JPEG"),
+            Some(ImageFormat::Jpeg)
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Seek index.
+//!
+//! Some containers (e.g. raw MPEG-TS captures) do not contain any index, so
+//! seeking by timestamp is slow and imprecise. The indexer scans the
+//! container once and collects timestamps and byte positions of all key
+//! frames of a given stream. The index can be stored and loaded later and it
+//! can be used for seeking by byte position.
+
+use std::io::{self, Read, Write};
+
+use crate::{
+    format::demuxer::Demuxer,
+    packet::Packet,
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Magic bytes of the serialized index.
+const MAGIC: &[u8; 4] = b"ACIX";
+
+/// Version of the serialized index.
+const VERSION: u32 = 1;
+
+/// Key frame index entry.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IndexEntry {
+    pts: Timestamp,
+    position: u64,
+}
+
+impl IndexEntry {
+    /// Get presentation timestamp of the key frame.
+    pub fn pts(&self) -> Timestamp {
+        self.pts
+    }
+
+    /// Get byte position of the key frame in the input.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+/// Key frame index of a single stream.
+#[derive(Debug, Clone)]
+pub struct SeekIndex {
+    stream_index: usize,
+    time_base: TimeBase,
+    entries: Vec<IndexEntry>,
+}
+
+impl SeekIndex {
+    /// Get index of the indexed stream.
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    /// Get time base of the index entries.
+    pub fn time_base(&self) -> TimeBase {
+        self.time_base
+    }
+
+    /// Get all index entries ordered by their timestamps.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Get the last key frame at or before a given timestamp. The first key
+    /// frame is returned if the timestamp precedes all key frames. `None` is
+    /// returned only if the index is empty or the timestamp is null.
+    pub fn lookup(&self, timestamp: Timestamp) -> Option<&IndexEntry> {
+        if timestamp.is_null() {
+            return None;
+        }
+
+        let pts = timestamp.with_time_base(self.time_base).timestamp();
+
+        let index = self
+            .entries
+            .partition_point(|entry| entry.pts.timestamp() <= pts);
+
+        self.entries.get(index.saturating_sub(1))
+    }
+
+    /// Write the index into a given writer.
+    pub fn write_to<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.stream_index as u64).to_le_bytes())?;
+        writer.write_all(&self.time_base.num().to_le_bytes())?;
+        writer.write_all(&self.time_base.den().to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+
+        for entry in &self.entries {
+            writer.write_all(&entry.pts.timestamp().to_le_bytes())?;
+            writer.write_all(&entry.position.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Read an index from a given reader.
+    pub fn read_from<R>(mut reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut magic = [0u8; 4];
+
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a seek index",
+            ));
+        }
+
+        if read_u32(&mut reader)? != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported seek index version",
+            ));
+        }
+
+        let stream_index = read_u64(&mut reader)? as usize;
+
+        let num = read_u32(&mut reader)?;
+        let den = read_u32(&mut reader)?;
+
+        if num == 0 || den == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid seek index time base",
+            ));
+        }
+
+        let time_base = TimeBase::new(num, den);
+
+        let count = read_u64(&mut reader)?;
+
+        let mut entries = Vec::new();
+
+        for _ in 0..count {
+            let pts = read_u64(&mut reader)? as i64;
+            let position = read_u64(&mut reader)?;
+
+            entries.push(IndexEntry {
+                pts: Timestamp::new(pts, time_base),
+                position,
+            });
+        }
+
+        let res = Self {
+            stream_index,
+            time_base,
+            entries,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Seek indexer.
+///
+/// Push all packets of the input into the indexer and call `finish()` to get
+/// the index. Packets of other streams, non-key packets and packets without
+/// a timestamp or a byte position are ignored.
+pub struct Indexer {
+    stream_index: usize,
+    time_base: Option<TimeBase>,
+    entries: Vec<IndexEntry>,
+}
+
+impl Indexer {
+    /// Create a new indexer for a given stream.
+    pub fn new(stream_index: usize) -> Self {
+        Self {
+            stream_index,
+            time_base: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Scan the whole input of a given demuxer and build an index for a given
+    /// stream. The demuxer is left at the end of the input, so it needs to
+    /// be seeked before reading any further packets.
+    pub fn scan<T>(demuxer: &mut Demuxer<T>, stream_index: usize) -> Result<SeekIndex, Error> {
+        let mut indexer = Self::new(stream_index);
+
+        for packet in demuxer.packets() {
+            indexer.push(&packet?);
+        }
+
+        Ok(indexer.finish())
+    }
+
+    /// Process a given packet.
+    pub fn push(&mut self, packet: &Packet) {
+        if packet.stream_index() != self.stream_index || !packet.is_key() {
+            return;
+        }
+
+        let pts = packet.pts();
+
+        if pts.is_null() {
+            return;
+        }
+
+        let position = match packet.position() {
+            Some(position) => position,
+            None => return,
+        };
+
+        let time_base = *self.time_base.get_or_insert(pts.time_base());
+
+        self.entries.push(IndexEntry {
+            pts: pts.with_time_base(time_base),
+            position,
+        });
+    }
+
+    /// Get the index.
+    pub fn finish(mut self) -> SeekIndex {
+        self.entries.sort_by_key(|entry| entry.pts.timestamp());
+        self.entries.dedup_by_key(|entry| entry.pts.timestamp());
+
+        SeekIndex {
+            stream_index: self.stream_index,
+            time_base: self.time_base.unwrap_or(TimeBase::MICROSECONDS),
+            entries: self.entries,
+        }
+    }
+}
+
+/// Read a little endian u32.
+fn read_u32<R>(reader: &mut R) -> io::Result<u32>
+where
+    R: Read,
+{
+    let mut buffer = [0u8; 4];
+
+    reader.read_exact(&mut buffer)?;
+
+    Ok(u32::from_le_bytes(buffer))
+}
+
+/// Read a little endian u64.
+fn read_u64<R>(reader: &mut R) -> io::Result<u64>
+where
+    R: Read,
+{
+    let mut buffer = [0u8; 8];
+
+    reader.read_exact(&mut buffer)?;
+
+    Ok(u64::from_le_bytes(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Indexer, SeekIndex};
+
+    use crate::{
+        packet::PacketMut,
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_seek_index() {
+        let tb = TimeBase::new(1, 90_000);
+
+        let mut indexer = Indexer::new(0);
+
+        for (i, key) in [true, false, false, true, false, true].iter().enumerate() {
+            let packet = PacketMut::new(0)
+                .with_time_base(tb)
+                .with_pts(Timestamp::new(i as i64 * 3000, tb))
+                .with_key_flag(*key)
+                .freeze();
+
+            indexer.push(&packet);
+        }
+
+        // packets created from memory have no position
+        let index = indexer.finish();
+
+        assert!(index.entries().is_empty());
+        assert!(index.lookup(Timestamp::from_secs(1)).is_none());
+
+        let mut data = Vec::new();
+
+        data.extend_from_slice(b"ACIX");
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&90_000u32.to_le_bytes());
+        data.extend_from_slice(&3u64.to_le_bytes());
+
+        for (pts, pos) in [(0i64, 0u64), (9000, 1880), (18000, 3760)].iter() {
+            data.extend_from_slice(&pts.to_le_bytes());
+            data.extend_from_slice(&pos.to_le_bytes());
+        }
+
+        let index = SeekIndex::read_from(&data[..]).unwrap();
+
+        assert_eq!(index.entries().len(), 3);
+
+        let lookup = |ms| index.lookup(Timestamp::from_millis(ms)).unwrap().position();
+
+        assert_eq!(lookup(-100), 0);
+        assert_eq!(lookup(50), 0);
+        assert_eq!(lookup(100), 1880);
+        assert_eq!(lookup(150), 1880);
+        assert_eq!(lookup(5000), 3760);
+
+        let mut serialized = Vec::new();
+
+        index.write_to(&mut serialized).unwrap();
+
+        assert_eq!(serialized, data);
+    }
+}
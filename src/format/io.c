@@ -1,4 +1,7 @@
 #include <libavformat/avio.h>
+#include <libavutil/opt.h>
+
+#include <stdlib.h>
 
 typedef int read_packet_t(void*, uint8_t*, int);
 typedef int write_packet_t(void*, uint8_t*, int);
@@ -41,6 +44,30 @@ err:
     return NULL;
 }
 
+int ffw_io_context_flush(AVIOContext* context) {
+    // flushing a read context would drop the buffered data
+    if (!context->write_flag) {
+        return 0;
+    }
+
+    avio_flush(context);
+
+    if (context->error < 0) {
+        return context->error;
+    }
+
+    if (context->write_packet == NULL) {
+        return 0;
+    }
+
+    // flush also the underlying stream
+    return context->write_packet(context->opaque, NULL, 0);
+}
+
+void ffw_io_context_set_direct(AVIOContext* context, int direct) {
+    context->direct = direct;
+}
+
 void ffw_io_context_free(AVIOContext* context) {
     if (context) {
         av_freep(&context->buffer);
@@ -48,3 +75,106 @@ void ffw_io_context_free(AVIOContext* context) {
 
     avio_context_free(&context);
 }
+
+typedef struct UrlIO {
+    AVIOContext* context;
+    AVDictionary* options;
+} UrlIO;
+
+UrlIO* ffw_url_io_new() {
+    return calloc(1, sizeof(UrlIO));
+}
+
+int ffw_url_io_set_initial_option(UrlIO* io, const char* key, const char* value) {
+    return av_dict_set(&io->options, key, value, 0);
+}
+
+int ffw_url_io_open(UrlIO* io, const char* url, int write) {
+    int flags = write ? AVIO_FLAG_WRITE : AVIO_FLAG_READ;
+    int ret;
+
+    ret = avio_open2(&io->context, url, flags, NULL, &io->options);
+
+    // NOTE: options not recognized by the protocol are kept in the
+    // dictionary, so that the caller can check them
+    if (ret < 0) {
+        av_dict_free(&io->options);
+    }
+
+    return ret;
+}
+
+const char* ffw_url_io_get_unused_option(const UrlIO* io) {
+    AVDictionaryEntry* entry = av_dict_get(io->options, "", NULL, AV_DICT_IGNORE_SUFFIX);
+
+    if (!entry) {
+        return NULL;
+    }
+
+    return entry->key;
+}
+
+AVIOContext* ffw_url_io_get_context(const UrlIO* io) {
+    return io->context;
+}
+
+int ffw_url_io_get_option_int(UrlIO* io, const char* key, int64_t* value) {
+    return av_opt_get_int(io->context, key, AV_OPT_SEARCH_CHILDREN, value);
+}
+
+int ffw_url_io_read(UrlIO* io, uint8_t* buffer, int size) {
+    int ret = avio_read_partial(io->context, buffer, size);
+
+    if (ret == AVERROR_EOF) {
+        return 0;
+    }
+
+    return ret;
+}
+
+int ffw_url_io_write(UrlIO* io, const uint8_t* buffer, int size) {
+    avio_write(io->context, buffer, size);
+
+    if (io->context->error < 0) {
+        return io->context->error;
+    }
+
+    return size;
+}
+
+int ffw_url_io_flush(UrlIO* io) {
+    avio_flush(io->context);
+
+    if (io->context->error < 0) {
+        return io->context->error;
+    }
+
+    return 0;
+}
+
+int64_t ffw_url_io_seek(UrlIO* io, int64_t offset, int whence) {
+    int64_t size;
+
+    if (whence == SEEK_END) {
+        size = avio_size(io->context);
+        if (size < 0) {
+            return size;
+        }
+
+        offset += size;
+        whence = SEEK_SET;
+    }
+
+    return avio_seek(io->context, offset, whence);
+}
+
+void ffw_url_io_free(UrlIO* io) {
+    if (!io) {
+        return;
+    }
+
+    avio_closep(&io->context);
+    av_dict_free(&io->options);
+
+    free(io);
+}
@@ -1,11 +1,16 @@
 //! Elementary IO used by the muxer and demuxer.
 
 use std::{
+    ffi::{CStr, CString},
     io::{self, Read, Seek, SeekFrom, Write},
-    os::raw::{c_int, c_void},
+    os::raw::{c_char, c_int, c_void},
     slice,
+    sync::Mutex,
+    time::Duration,
 };
 
+use crate::Error;
+
 type ReadPacketCallback =
     extern "C" fn(opaque: *mut c_void, buffer: *mut u8, buffer_size: c_int) -> c_int;
 type WritePacketCallback =
@@ -23,9 +28,29 @@ extern "C" {
         write_packet: Option<WritePacketCallback>,
         seek: Option<SeekCallback>,
     ) -> *mut c_void;
+    fn ffw_io_context_flush(context: *mut c_void) -> c_int;
+    fn ffw_io_context_set_direct(context: *mut c_void, direct: c_int);
     fn ffw_io_context_free(context: *mut c_void);
+
+    fn ffw_url_io_new() -> *mut c_void;
+    fn ffw_url_io_set_initial_option(
+        io: *mut c_void,
+        key: *const c_char,
+        value: *const c_char,
+    ) -> c_int;
+    fn ffw_url_io_open(io: *mut c_void, url: *const c_char, write: c_int) -> c_int;
+    fn ffw_url_io_get_unused_option(io: *const c_void) -> *const c_char;
+    fn ffw_url_io_get_option_int(io: *mut c_void, key: *const c_char, value: *mut i64) -> c_int;
+    fn ffw_url_io_read(io: *mut c_void, buffer: *mut u8, size: c_int) -> c_int;
+    fn ffw_url_io_write(io: *mut c_void, buffer: *const u8, size: c_int) -> c_int;
+    fn ffw_url_io_flush(io: *mut c_void) -> c_int;
+    fn ffw_url_io_seek(io: *mut c_void, offset: i64, whence: c_int) -> i64;
+    fn ffw_url_io_free(io: *mut c_void);
 }
 
+/// Default size of the AVIO buffer.
+const DEFAULT_BUFFER_SIZE: usize = 4096;
+
 /// IO context.
 #[allow(clippy::upper_case_acronyms)]
 pub(crate) struct IOContext {
@@ -59,7 +84,7 @@ fn get_seekable_length<T>(seekable: &mut T) -> Result<u64, std::io::Error>
 where
     T: Seek,
 {
-    let current_position = seekable.seek(SeekFrom::Current(0))?;
+    let current_position = seekable.stream_position()?;
     let end_position = seekable.seek(SeekFrom::End(0))?;
 
     seekable.seek(SeekFrom::Start(current_position))?;
@@ -67,14 +92,55 @@ where
     Ok(end_position)
 }
 
+/// Stream together with IO statistics and the last error returned by the
+/// stream.
+struct StreamContext<T> {
+    stream: T,
+    bytes_read: u64,
+    position: u64,
+    error: Mutex<Option<(c_int, io::Error)>>,
+}
+
+impl<T> StreamContext<T> {
+    /// Create a new stream context.
+    fn new(stream: T) -> Self {
+        Self {
+            stream,
+            bytes_read: 0,
+            position: 0,
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Get an FFmpeg error code for a given IO error and store the error so
+    /// that it can be attached to the error returned by the muxer/demuxer.
+    fn io_error_code(&self, err: io::Error) -> c_int {
+        let code = if let Some(code) = err.raw_os_error() {
+            unsafe { crate::ffw_error_from_posix(code as _) }
+        } else if err.kind() == io::ErrorKind::WouldBlock {
+            unsafe { crate::ffw_error_would_block() }
+        } else {
+            unsafe { crate::ffw_error_unknown() }
+        };
+
+        if err.kind() != io::ErrorKind::WouldBlock {
+            *self.error.lock().unwrap() = Some((code, err));
+        }
+
+        code
+    }
+}
+
 /// A SeekCallback function for the IO.
 extern "C" fn io_seek<T>(opaque: *mut c_void, offset: i64, whence: c_int) -> i64
 where
     T: Seek,
 {
-    let input_ptr = opaque as *mut T;
+    let context_ptr = opaque as *mut StreamContext<T>;
+
+    let context = unsafe { &mut *context_ptr };
 
-    let input = unsafe { &mut *input_ptr };
+    let input = &mut context.stream;
 
     let is_avseek_size = unsafe { ffw_io_is_avseek_size(whence) != 0 };
 
@@ -85,11 +151,14 @@ where
     };
 
     match seek {
-        Ok(len) => len as i64,
-        Err(err) => err
-            .raw_os_error()
-            .map(|code| unsafe { crate::ffw_error_from_posix(code as _) })
-            .unwrap_or(unsafe { crate::ffw_error_unknown() }) as i64,
+        Ok(len) => {
+            if !is_avseek_size {
+                context.position = len;
+            }
+
+            len as i64
+        }
+        Err(err) => context.io_error_code(err) as i64,
     }
 }
 
@@ -98,29 +167,24 @@ extern "C" fn io_read_packet<T>(opaque: *mut c_void, buffer: *mut u8, buffer_siz
 where
     T: Read,
 {
-    let input_ptr = opaque as *mut T;
+    let context_ptr = opaque as *mut StreamContext<T>;
 
-    let input = unsafe { &mut *input_ptr };
+    let context = unsafe { &mut *context_ptr };
 
     let buffer = unsafe { slice::from_raw_parts_mut(buffer, buffer_size as usize) };
 
-    match input.read(buffer) {
+    match context.stream.read(buffer) {
         Ok(n) => {
+            context.bytes_read += n as u64;
+            context.position += n as u64;
+
             if n > 0 {
                 n as c_int
             } else {
                 unsafe { crate::ffw_error_eof() }
             }
         }
-        Err(err) => {
-            if let Some(code) = err.raw_os_error() {
-                unsafe { crate::ffw_error_from_posix(code as _) }
-            } else if err.kind() == io::ErrorKind::WouldBlock {
-                unsafe { crate::ffw_error_would_block() }
-            } else {
-                unsafe { crate::ffw_error_unknown() }
-            }
-        }
+        Err(err) => context.io_error_code(err),
     }
 }
 
@@ -129,39 +193,29 @@ extern "C" fn io_write_packet<T>(opaque: *mut c_void, buffer: *mut u8, buffer_si
 where
     T: Write,
 {
-    let output_ptr = opaque as *mut T;
+    let context_ptr = opaque as *mut StreamContext<T>;
+
+    let context = unsafe { &mut *context_ptr };
 
-    let output = unsafe { &mut *output_ptr };
+    let output = &mut context.stream;
 
     if !buffer.is_null() && buffer_size > 0 {
         let buffer = unsafe { slice::from_raw_parts(buffer, buffer_size as usize) };
 
         match output.write(buffer) {
             Ok(n) => {
+                context.position += n as u64;
+
                 if n > 0 {
                     n as c_int
                 } else {
                     unsafe { crate::ffw_error_eof() }
                 }
             }
-            Err(err) => {
-                if let Some(code) = err.raw_os_error() {
-                    unsafe { crate::ffw_error_from_posix(code as _) }
-                } else if err.kind() == io::ErrorKind::WouldBlock {
-                    unsafe { crate::ffw_error_would_block() }
-                } else {
-                    unsafe { crate::ffw_error_unknown() }
-                }
-            }
+            Err(err) => context.io_error_code(err),
         }
     } else if let Err(err) = output.flush() {
-        if let Some(code) = err.raw_os_error() {
-            unsafe { crate::ffw_error_from_posix(code) }
-        } else if err.kind() == io::ErrorKind::WouldBlock {
-            unsafe { crate::ffw_error_would_block() }
-        } else {
-            unsafe { crate::ffw_error_unknown() }
-        }
+        context.io_error_code(err)
     } else {
         0
     }
@@ -171,26 +225,32 @@ where
 #[allow(clippy::upper_case_acronyms)]
 pub struct IO<T> {
     io_context: IOContext,
-    stream: Box<T>,
+    stream: Box<StreamContext<T>>,
 }
 
 impl<T> IO<T> {
     /// Create a new IO.
     fn new(
         stream: T,
+        buffer_size: usize,
         read_packet: Option<ReadPacketCallback>,
         write_packet: Option<WritePacketCallback>,
         seek: Option<SeekCallback>,
     ) -> Self {
-        let mut stream = Box::new(stream);
-        let stream_ptr = stream.as_mut() as *mut T;
+        assert!(
+            buffer_size > 0 && buffer_size <= c_int::MAX as usize,
+            "invalid buffer size"
+        );
+
+        let mut stream = Box::new(StreamContext::new(stream));
+        let stream_ptr = stream.as_mut() as *mut StreamContext<T>;
         let opaque_ptr = stream_ptr as *mut c_void;
 
         let write_flag = if write_packet.is_some() { 1 } else { 0 };
 
         let io_context = unsafe {
             ffw_io_context_new(
-                4096,
+                buffer_size as _,
                 write_flag,
                 opaque_ptr,
                 read_packet,
@@ -213,19 +273,83 @@ impl<T> IO<T> {
         &mut self.io_context
     }
 
+    /// Enable or disable the direct mode. In the direct mode, the AVIO
+    /// buffer is bypassed whenever possible and reads, writes and seeks are
+    /// passed to the underlying stream directly. The direct mode is disabled
+    /// by default.
+    pub fn set_direct(&mut self, direct: bool) {
+        unsafe { ffw_io_context_set_direct(self.io_context.as_mut_ptr(), direct as _) }
+    }
+
+    /// Write all data buffered in the AVIO buffer into the underlying
+    /// stream and flush the stream. This has no effect on IOs created for
+    /// reading.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        let ret = unsafe { ffw_io_context_flush(self.io_context.as_mut_ptr()) };
+
+        if ret < 0 {
+            Err(self.make_error(ret))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Create an error from a given FFmpeg error code. The last error
+    /// returned by the underlying stream is attached as the error source if
+    /// it was reported to FFmpeg using the same error code.
+    pub(crate) fn make_error(&self, code: c_int) -> Error {
+        let err = Error::from_raw_error_code(code);
+
+        match self.stream.error.lock().unwrap().take() {
+            Some((c, source)) if c == code => err.with_source(source),
+            _ => err,
+        }
+    }
+
     /// Get reference to the underlying stream.
     pub fn stream(&self) -> &T {
-        self.stream.as_ref()
+        &self.stream.stream
     }
 
     /// Get mutable reference to the underlying stream.
     pub fn stream_mut(&mut self) -> &mut T {
-        self.stream.as_mut()
+        &mut self.stream.stream
     }
 
     /// Take the underlying stream dropping this IO.
     pub fn into_stream(self) -> T {
-        *self.stream
+        self.stream.stream
+    }
+
+    /// Get reference to the underlying stream (the same as `stream()`).
+    pub fn get_ref(&self) -> &T {
+        self.stream()
+    }
+
+    /// Get mutable reference to the underlying stream (the same as
+    /// `stream_mut()`).
+    pub fn get_mut(&mut self) -> &mut T {
+        self.stream_mut()
+    }
+
+    /// Take the underlying stream dropping this IO (the same as
+    /// `into_stream()`).
+    pub fn into_inner(self) -> T {
+        self.into_stream()
+    }
+
+    /// Get the total number of bytes read from the underlying stream. Note
+    /// that FFmpeg reads the stream in blocks, so the value may be greater
+    /// than the amount of data consumed by the demuxer.
+    pub fn bytes_read(&self) -> u64 {
+        self.stream.bytes_read
+    }
+
+    /// Get the current position within the underlying stream as seen by
+    /// this IO (i.e. assuming the stream starts at position zero). The
+    /// position is updated by all reads, writes and seeks.
+    pub fn position(&self) -> u64 {
+        self.stream.position
     }
 }
 
@@ -235,7 +359,17 @@ where
 {
     /// Create a new IO from a given stream.
     pub fn from_read_stream(stream: T) -> Self {
-        Self::new(stream, Some(io_read_packet::<T>), None, None)
+        Self::from_read_stream_with_buffer_size(stream, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Create a new IO from a given stream using an AVIO buffer of a given
+    /// size (in bytes). The default buffer size is 4096 bytes.
+    ///
+    /// # Panics
+    /// The method panics if the buffer size is zero or if it does not fit
+    /// into `c_int`.
+    pub fn from_read_stream_with_buffer_size(stream: T, buffer_size: usize) -> Self {
+        Self::new(stream, buffer_size, Some(io_read_packet::<T>), None, None)
     }
 }
 
@@ -245,7 +379,23 @@ where
 {
     /// Create a new IO from a given stream.
     pub fn from_seekable_read_stream(stream: T) -> Self {
-        Self::new(stream, Some(io_read_packet::<T>), None, Some(io_seek::<T>))
+        Self::from_seekable_read_stream_with_buffer_size(stream, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Create a new IO from a given stream using an AVIO buffer of a given
+    /// size (in bytes). The default buffer size is 4096 bytes.
+    ///
+    /// # Panics
+    /// The method panics if the buffer size is zero or if it does not fit
+    /// into `c_int`.
+    pub fn from_seekable_read_stream_with_buffer_size(stream: T, buffer_size: usize) -> Self {
+        Self::new(
+            stream,
+            buffer_size,
+            Some(io_read_packet::<T>),
+            None,
+            Some(io_seek::<T>),
+        )
     }
 }
 
@@ -255,7 +405,17 @@ where
 {
     /// Create a new IO from a given stream.
     pub fn from_write_stream(stream: T) -> Self {
-        Self::new(stream, None, Some(io_write_packet::<T>), None)
+        Self::from_write_stream_with_buffer_size(stream, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Create a new IO from a given stream using an AVIO buffer of a given
+    /// size (in bytes). The default buffer size is 4096 bytes.
+    ///
+    /// # Panics
+    /// The method panics if the buffer size is zero or if it does not fit
+    /// into `c_int`.
+    pub fn from_write_stream_with_buffer_size(stream: T, buffer_size: usize) -> Self {
+        Self::new(stream, buffer_size, None, Some(io_write_packet::<T>), None)
     }
 }
 
@@ -265,12 +425,278 @@ where
 {
     /// Create a new IO from a given stream.
     pub fn from_seekable_write_stream(stream: T) -> Self {
-        Self::new(stream, None, Some(io_write_packet::<T>), Some(io_seek::<T>))
+        Self::from_seekable_write_stream_with_buffer_size(stream, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Create a new IO from a given stream using an AVIO buffer of a given
+    /// size (in bytes). The default buffer size is 4096 bytes.
+    ///
+    /// # Panics
+    /// The method panics if the buffer size is zero or if it does not fit
+    /// into `c_int`.
+    pub fn from_seekable_write_stream_with_buffer_size(stream: T, buffer_size: usize) -> Self {
+        Self::new(
+            stream,
+            buffer_size,
+            None,
+            Some(io_write_packet::<T>),
+            Some(io_seek::<T>),
+        )
     }
 }
 
+/// Helper function for converting FFmpeg error codes into IO errors.
+fn to_io_error(code: c_int) -> io::Error {
+    Error::from_raw_error_code(code)
+        .to_io_error()
+        .unwrap_or_else(|| io::Error::from(io::ErrorKind::Other))
+}
+
+/// Builder for URL streams.
+pub struct UrlStreamBuilder {
+    ptr: *mut c_void,
+    error: Option<Error>,
+    strict_options: bool,
+}
+
+impl UrlStreamBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        let ptr = unsafe { ffw_url_io_new() };
+
+        if ptr.is_null() {
+            panic!("unable to allocate an URL IO");
+        }
+
+        Self {
+            ptr,
+            error: None,
+            strict_options: false,
+        }
+    }
+
+    /// Set a protocol option.
+    /// An invalid option (e.g. a name containing a NUL character) will make
+    /// opening the URL fail.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        let (name, value) = match crate::options::option_to_cstrings(name, &value.to_string()) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
+
+        let ret = unsafe {
+            ffw_url_io_set_initial_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _)
+        };
+
+        if ret < 0 {
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
+        }
+
+        self
+    }
+
+    /// Set timeout for a single read/write operation (i.e. the `rw_timeout`
+    /// protocol option). The option is supported by all protocols. Blocking
+    /// operations taking longer than the timeout fail with an error of the
+    /// `io::ErrorKind::TimedOut` kind. The error is propagated from muxers
+    /// and demuxers using the stream as well (see `Error::kind()`).
+    pub fn rw_timeout(self, timeout: Duration) -> Self {
+        self.set_option("rw_timeout", timeout.as_micros())
+    }
+
+    /// Make the HTTP protocol reconnect automatically if the connection
+    /// drops, also for streamed (i.e. non-seekable) inputs such as live
+    /// HLS or Icecast streams (i.e. the `reconnect`, `reconnect_streamed`
+    /// and `reconnect_delay_max` protocol options). The delay between
+    /// reconnection attempts grows exponentially up to a given maximum.
+    pub fn reconnect(self, max_delay: Duration) -> Self {
+        self.set_option("reconnect", 1)
+            .set_option("reconnect_streamed", 1)
+            .set_option("reconnect_delay_max", max_delay.as_secs())
+    }
+
+    /// Make opening the URL fail if any of the options is not recognized by
+    /// the protocol. By default, unknown options are silently ignored.
+    pub fn strict_options(mut self, strict: bool) -> Self {
+        self.strict_options = strict;
+        self
+    }
+
+    /// Open a given URL for reading.
+    pub fn open_read(self, url: &str) -> Result<UrlStream, Error> {
+        self.open(url, false)
+    }
+
+    /// Open a given URL for writing.
+    pub fn open_write(self, url: &str) -> Result<UrlStream, Error> {
+        self.open(url, true)
+    }
+
+    /// Open a given URL.
+    fn open(mut self, url: &str, write: bool) -> Result<UrlStream, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        let url = CString::new(url).map_err(|_| Error::new("invalid URL"))?;
+
+        let ret = unsafe { ffw_url_io_open(self.ptr, url.as_ptr() as _, write as _) };
+
+        if ret < 0 {
+            return Err(Error::from_raw_error_code(ret));
+        }
+
+        let ptr = self.ptr;
+
+        self.ptr = std::ptr::null_mut();
+
+        let res = UrlStream { ptr };
+
+        if self.strict_options {
+            let name = unsafe { ffw_url_io_get_unused_option(res.ptr) };
+
+            if !name.is_null() {
+                let name = unsafe { CStr::from_ptr(name) };
+
+                return Err(Error::new(format!(
+                    "unknown protocol option: {}",
+                    name.to_string_lossy()
+                )));
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+impl Drop for UrlStreamBuilder {
+    fn drop(&mut self) {
+        unsafe { ffw_url_io_free(self.ptr) }
+    }
+}
+
+unsafe impl Send for UrlStreamBuilder {}
+unsafe impl Sync for UrlStreamBuilder {}
+
+/// A stream opened using one of the FFmpeg protocols (e.g. `file`, `tcp`,
+/// `udp`, `srt`, `http`, ...). The stream can be used as any other Rust
+/// stream, e.g. it can be passed to `IO::from_read_stream()`.
+///
+/// Only protocols enabled in the FFmpeg build can be used.
+pub struct UrlStream {
+    ptr: *mut c_void,
+}
+
+impl UrlStream {
+    /// Get a builder for URL streams.
+    pub fn builder() -> UrlStreamBuilder {
+        UrlStreamBuilder::new()
+    }
+
+    /// Open a given URL for reading using the default options.
+    pub fn open_read(url: &str) -> Result<Self, Error> {
+        Self::builder().open_read(url)
+    }
+
+    /// Open a given URL for writing using the default options.
+    pub fn open_write(url: &str) -> Result<Self, Error> {
+        Self::builder().open_write(url)
+    }
+
+    /// Get raw pointer to the underlying URL IO.
+    #[cfg(feature = "srt")]
+    pub(crate) fn as_ptr(&self) -> *const c_void {
+        self.ptr
+    }
+
+    /// Get the current value of a given integer protocol option (if the
+    /// protocol has such option).
+    pub(crate) fn get_option_int(&mut self, name: &str) -> Option<i64> {
+        let name = CString::new(name).ok()?;
+
+        let mut value = 0;
+
+        let ret = unsafe { ffw_url_io_get_option_int(self.ptr, name.as_ptr(), &mut value) };
+
+        if ret < 0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+impl Read for UrlStream {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, io::Error> {
+        let ret = unsafe { ffw_url_io_read(self.ptr, buffer.as_mut_ptr(), buffer.len() as _) };
+
+        if ret < 0 {
+            Err(to_io_error(ret))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+impl Write for UrlStream {
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, io::Error> {
+        let ret = unsafe { ffw_url_io_write(self.ptr, buffer.as_ptr(), buffer.len() as _) };
+
+        if ret < 0 {
+            Err(to_io_error(ret))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        let ret = unsafe { ffw_url_io_flush(self.ptr) };
+
+        if ret < 0 {
+            Err(to_io_error(ret))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Seek for UrlStream {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, io::Error> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+
+        let ret = unsafe { ffw_url_io_seek(self.ptr, offset, whence) };
+
+        if ret < 0 {
+            Err(to_io_error(ret as _))
+        } else {
+            Ok(ret as u64)
+        }
+    }
+}
+
+impl Drop for UrlStream {
+    fn drop(&mut self) {
+        unsafe { ffw_url_io_free(self.ptr) }
+    }
+}
+
+unsafe impl Send for UrlStream {}
+unsafe impl Sync for UrlStream {}
+
 /// Writer that puts everything in memory. It also allows taking the data on
 /// the fly.
+#[derive(Default)]
 pub struct MemWriter {
     data: Vec<u8>,
 }
@@ -286,12 +712,6 @@ impl MemWriter {
     }
 }
 
-impl Default for MemWriter {
-    fn default() -> Self {
-        Self { data: Vec::new() }
-    }
-}
-
 impl Write for MemWriter {
     fn write(&mut self, buffer: &[u8]) -> Result<usize, io::Error> {
         self.data.extend_from_slice(buffer);
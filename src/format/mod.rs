@@ -1,6 +1,17 @@
 //! Media container handling.
 
+pub mod codec_private;
 pub mod demuxer;
+pub mod id3;
+pub mod image_sequence;
+pub mod index;
 pub mod io;
+pub mod mp4;
 pub mod muxer;
+pub mod raw;
+pub mod rtmp;
+pub mod rtp;
+pub mod srt;
 pub mod stream;
+pub mod tee;
+pub mod udp;
@@ -0,0 +1,275 @@
+//! MP4 utilities.
+//!
+//! The FFmpeg MP4 muxer writes the `moov` box (i.e. the index) at the end
+//! of the file. Such files cannot be played progressively over HTTP. The
+//! `movflags=+faststart` muxer option fixes this, however, it needs to
+//! re-open the output file by its URL, so it does not work with custom IO.
+//! The `faststart()` function does the same thing as a second pass over an
+//! already written file.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Size of the buffer used for moving data.
+const COPY_BUFFER_SIZE: usize = 1 << 20;
+
+/// Boxes containing other boxes on the path to the chunk offset tables.
+const CONTAINERS: [&[u8; 4]; 5] = [b"moov", b"trak", b"mdia", b"minf", b"stbl"];
+
+/// Top-level box.
+struct BoxInfo {
+    kind: [u8; 4],
+    offset: u64,
+    size: u64,
+}
+
+/// Move the `moov` box of a given MP4 file in front of the `mdat` box and
+/// update all chunk offsets accordingly. The file is rewritten in place and
+/// its size does not change. The method returns false if no rewrite was
+/// necessary (i.e. the `moov` box is already in front of the `mdat` box or
+/// the file does not contain any of them).
+pub fn faststart<T>(stream: &mut T) -> io::Result<bool>
+where
+    T: Read + Write + Seek,
+{
+    let boxes = read_top_level_boxes(stream)?;
+
+    let moov = boxes.iter().find(|b| &b.kind == b"moov");
+    let mdat = boxes.iter().find(|b| &b.kind == b"mdat");
+
+    let (moov, mdat) = match (moov, mdat) {
+        (Some(moov), Some(mdat)) if moov.offset > mdat.offset => (moov, mdat),
+        _ => return Ok(false),
+    };
+
+    let insert_at = mdat.offset;
+
+    let mut data = vec![0u8; moov.size as usize];
+
+    stream.seek(SeekFrom::Start(moov.offset))?;
+    stream.read_exact(&mut data)?;
+
+    // all data between the insert position and the moov box will be moved
+    // by the size of the moov box
+    patch_chunk_offsets(&mut data, insert_at, moov.offset, moov.size)?;
+
+    move_data(stream, insert_at, moov.offset, moov.size)?;
+
+    stream.seek(SeekFrom::Start(insert_at))?;
+    stream.write_all(&data)?;
+    stream.flush()?;
+
+    Ok(true)
+}
+
+/// Read headers of all top-level boxes.
+fn read_top_level_boxes<T>(stream: &mut T) -> io::Result<Vec<BoxInfo>>
+where
+    T: Read + Seek,
+{
+    let len = stream.seek(SeekFrom::End(0))?;
+
+    let mut res = Vec::new();
+
+    let mut offset = 0;
+
+    while offset + 8 <= len {
+        let mut header = [0u8; 16];
+
+        stream.seek(SeekFrom::Start(offset))?;
+        stream.read_exact(&mut header[..8])?;
+
+        let mut size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+
+        if size == 1 {
+            stream.read_exact(&mut header[8..])?;
+
+            size = u64::from_be_bytes([
+                header[8], header[9], header[10], header[11], header[12], header[13], header[14],
+                header[15],
+            ]);
+        } else if size == 0 {
+            size = len - offset;
+        }
+
+        if size < 8 || offset + size > len {
+            return Err(invalid_data("invalid MP4 box size"));
+        }
+
+        let mut kind = [0u8; 4];
+
+        kind.copy_from_slice(&header[4..8]);
+
+        res.push(BoxInfo { kind, offset, size });
+
+        offset += size;
+    }
+
+    Ok(res)
+}
+
+/// Add a given shift to all chunk offsets (in `stco` and `co64` boxes)
+/// pointing into a given range.
+fn patch_chunk_offsets(data: &mut [u8], start: u64, end: u64, shift: u64) -> io::Result<()> {
+    let mut offset = 0;
+
+    while offset + 8 <= data.len() {
+        let size = u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]) as usize;
+
+        let mut kind = [0u8; 4];
+
+        kind.copy_from_slice(&data[offset + 4..offset + 8]);
+
+        // 64-bit sizes are not expected within the moov box
+        if size < 8 || offset + size > data.len() {
+            return Err(invalid_data("invalid MP4 box size"));
+        }
+
+        let body = &mut data[offset + 8..offset + size];
+
+        if CONTAINERS.contains(&&kind) {
+            patch_chunk_offsets(body, start, end, shift)?;
+        } else if &kind == b"stco" {
+            for entry in chunk_offset_entries(body, 4)? {
+                let value = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]) as u64;
+
+                if value >= start && value < end {
+                    let value = value + shift;
+
+                    if value > u32::MAX as u64 {
+                        return Err(invalid_data("chunk offset overflow"));
+                    }
+
+                    entry.copy_from_slice(&(value as u32).to_be_bytes());
+                }
+            }
+        } else if &kind == b"co64" {
+            for entry in chunk_offset_entries(body, 8)? {
+                let mut value = [0u8; 8];
+
+                value.copy_from_slice(entry);
+
+                let value = u64::from_be_bytes(value);
+
+                if value >= start && value < end {
+                    entry.copy_from_slice(&(value + shift).to_be_bytes());
+                }
+            }
+        }
+
+        offset += size;
+    }
+
+    Ok(())
+}
+
+/// Get chunk offset entries of a given `stco`/`co64` box body.
+fn chunk_offset_entries(
+    body: &mut [u8],
+    entry_size: usize,
+) -> io::Result<std::slice::ChunksExactMut<'_, u8>> {
+    if body.len() < 8 {
+        return Err(invalid_data("truncated chunk offset box"));
+    }
+
+    let count = u32::from_be_bytes([body[4], body[5], body[6], body[7]]) as usize;
+
+    body[8..]
+        .get_mut(..count * entry_size)
+        .map(|entries| entries.chunks_exact_mut(entry_size))
+        .ok_or_else(|| invalid_data("truncated chunk offset box"))
+}
+
+/// Move data in a given range forward by a given number of bytes. The data
+/// are copied from the end, so that nothing is overwritten before it is
+/// moved.
+fn move_data<T>(stream: &mut T, start: u64, end: u64, shift: u64) -> io::Result<()>
+where
+    T: Read + Write + Seek,
+{
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+
+    let mut chunk_end = end;
+
+    while chunk_end > start {
+        let chunk_start = chunk_end.saturating_sub(COPY_BUFFER_SIZE as u64).max(start);
+
+        let chunk = &mut buffer[..(chunk_end - chunk_start) as usize];
+
+        stream.seek(SeekFrom::Start(chunk_start))?;
+        stream.read_exact(chunk)?;
+        stream.seek(SeekFrom::Start(chunk_start + shift))?;
+        stream.write_all(chunk)?;
+
+        chunk_end = chunk_start;
+    }
+
+    Ok(())
+}
+
+/// Create a new invalid data error.
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::faststart;
+
+    /// Create an MP4 box.
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut res = Vec::new();
+
+        res.extend_from_slice(&(body.len() as u32 + 8).to_be_bytes());
+        res.extend_from_slice(kind);
+        res.extend_from_slice(body);
+        res
+    }
+
+    /// Create an MP4 file with a given chunk offset.
+    fn mp4_file(moov_first: bool, chunk_offset: u32) -> Vec<u8> {
+        let mut stco = vec![0, 0, 0, 0, 0, 0, 0, 1];
+
+        stco.extend_from_slice(&chunk_offset.to_be_bytes());
+
+        let stco = mp4_box(b"stco", &stco);
+        let stbl = mp4_box(b"stbl", &stco);
+        let minf = mp4_box(b"minf", &stbl);
+        let mdia = mp4_box(b"mdia", &minf);
+        let trak = mp4_box(b"trak", &mdia);
+        let moov = mp4_box(b"moov", &trak);
+
+        let ftyp = mp4_box(b"ftyp", b"isom");
+        let mdat = mp4_box(b"mdat", b"abcdef");
+
+        if moov_first {
+            [ftyp, moov, mdat].concat()
+        } else {
+            [ftyp, mdat, moov].concat()
+        }
+    }
+
+    #[test]
+    fn test_faststart() {
+        // the mdat payload starts at 12 + 8
+        let mut stream = Cursor::new(mp4_file(false, 20));
+
+        assert!(faststart(&mut stream).unwrap());
+
+        // the mdat payload is now after the moov box (60 bytes)
+        let expected = mp4_file(true, 80);
+
+        assert_eq!(stream.get_ref(), &expected);
+
+        let mut stream = Cursor::new(expected.clone());
+
+        assert!(!faststart(&mut stream).unwrap());
+        assert_eq!(stream.into_inner(), expected);
+    }
+}
@@ -2,9 +2,14 @@
 #include <libavformat/avio.h>
 #include <libavcodec/avcodec.h>
 #include <libavutil/opt.h>
+#include <libavutil/parseutils.h>
 
 #include <stdlib.h>
 
+#define MEDIA_TYPE_VIDEO      0
+#define MEDIA_TYPE_AUDIO      1
+#define MEDIA_TYPE_SUBTITLE   2
+
 AVOutputFormat* ffw_guess_output_format(
     const char* short_name,
     const char* file_name,
@@ -12,6 +17,47 @@ AVOutputFormat* ffw_guess_output_format(
     return av_guess_format(short_name, file_name, mime_type);
 }
 
+const AVOutputFormat* ffw_output_format_iterate(void** opaque) {
+    return av_muxer_iterate(opaque);
+}
+
+const char* ffw_output_format_get_name(const AVOutputFormat* format) {
+    return format->name;
+}
+
+const char* ffw_output_format_get_long_name(const AVOutputFormat* format) {
+    return format->long_name;
+}
+
+const char* ffw_output_format_get_mime_type(const AVOutputFormat* format) {
+    return format->mime_type;
+}
+
+const char* ffw_output_format_get_extensions(const AVOutputFormat* format) {
+    return format->extensions;
+}
+
+int ffw_output_format_has_global_header(const AVOutputFormat* format) {
+    return (format->flags & AVFMT_GLOBALHEADER) != 0;
+}
+
+const char* ffw_output_format_get_default_codec(const AVOutputFormat* format, int media_type) {
+    enum AVCodecID codec_id;
+
+    switch (media_type) {
+        case MEDIA_TYPE_VIDEO: codec_id = format->video_codec; break;
+        case MEDIA_TYPE_AUDIO: codec_id = format->audio_codec; break;
+        case MEDIA_TYPE_SUBTITLE: codec_id = format->subtitle_codec; break;
+        default: codec_id = AV_CODEC_ID_NONE; break;
+    }
+
+    if (codec_id == AV_CODEC_ID_NONE) {
+        return NULL;
+    }
+
+    return avcodec_get_name(codec_id);
+}
+
 typedef struct Muxer {
     AVFormatContext* fc;
     AVDictionary* options;
@@ -23,12 +69,18 @@ unsigned ffw_muxer_get_nb_streams(const Muxer*);
 AVStream* ffw_muxer_get_stream(Muxer* muxer, unsigned stream_index);
 int ffw_muxer_new_stream(Muxer*, const AVCodecParameters*);
 int ffw_muxer_init(Muxer*, AVIOContext*, AVOutputFormat*);
+int ffw_muxer_write_header(Muxer*);
+const AVDictionary* ffw_muxer_get_unused_options(const Muxer*);
 int ffw_muxer_get_option(Muxer*, const char*, uint8_t**);
 int ffw_muxer_set_initial_option(Muxer*, const char*, const char*);
 int ffw_muxer_set_option(Muxer*, const char*, const char*);
 int ffw_muxer_set_metadata(Muxer*, const char*, const char*);
+int ffw_muxer_add_chapter(Muxer*, int64_t, int64_t, uint32_t, uint32_t);
+int ffw_muxer_set_chapter_metadata(Muxer*, unsigned, const char*, const char*);
+int ffw_muxer_check_time(const char*);
 int ffw_muxer_write_frame(Muxer*, AVPacket*, uint32_t, uint32_t);
 int ffw_muxer_interleaved_write_frame(Muxer*, AVPacket*, uint32_t, uint32_t);
+int ffw_muxer_get_sdp(Muxer**, unsigned, char*, int);
 int ffw_muxer_free(Muxer*);
 
 Muxer* ffw_muxer_new() {
@@ -90,16 +142,35 @@ int ffw_muxer_init(
     muxer->fc->pb = avio_context;
     muxer->fc->oformat = format;
 
-    ret = avformat_write_header(muxer->fc, &muxer->options);
+    // NOTE: the header is written separately, so that unused options can be
+    // checked before anything gets written into the output
+    ret = avformat_init_output(muxer->fc, &muxer->options);
     if (ret < 0) {
         return ret;
     }
 
     muxer->initialized = 1;
 
-    av_dict_free(&muxer->options);
+    return 0;
+}
 
-    return ret;
+int ffw_muxer_write_header(Muxer* muxer) {
+    int ret;
+
+    ret = avformat_write_header(muxer->fc, NULL);
+    if (ret < 0) {
+        // the output gets deinitialized by avformat_write_header() on error
+        muxer->initialized = 0;
+        return ret;
+    }
+
+    muxer->initialized = 2;
+
+    return 0;
+}
+
+const AVDictionary* ffw_muxer_get_unused_options(const Muxer* muxer) {
+    return muxer->options;
 }
 
 int ffw_muxer_set_initial_option(Muxer* muxer, const char* key, const char* value) {
@@ -120,6 +191,40 @@ int ffw_muxer_set_metadata(Muxer* muxer, const char* key, const char* value) {
     return av_dict_set(&muxer->fc->metadata, key, value, 0);
 }
 
+int ffw_muxer_add_chapter(Muxer* muxer, int64_t start, int64_t end, uint32_t tb_num, uint32_t tb_den) {
+    AVChapter* chapter;
+    int ret;
+
+    chapter = av_mallocz(sizeof(AVChapter));
+    if (chapter == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    chapter->id = muxer->fc->nb_chapters;
+    chapter->start = start;
+    chapter->end = end;
+    chapter->time_base.num = tb_num;
+    chapter->time_base.den = tb_den;
+
+    ret = av_dynarray_add_nofree(&muxer->fc->chapters, (int*)&muxer->fc->nb_chapters, chapter);
+    if (ret < 0) {
+        av_free(chapter);
+        return ret;
+    }
+
+    return muxer->fc->nb_chapters - 1;
+}
+
+int ffw_muxer_set_chapter_metadata(Muxer* muxer, unsigned index, const char* key, const char* value) {
+    return av_dict_set(&muxer->fc->chapters[index]->metadata, key, value, 0);
+}
+
+int ffw_muxer_check_time(const char* value) {
+    int64_t t;
+
+    return av_parse_time(&t, value, 0);
+}
+
 static int ffw_rescale_packet_timestamps(Muxer* muxer, AVPacket* packet, uint32_t src_tb_num, uint32_t src_tb_den) {
     AVStream* stream;
     AVRational src_tb;
@@ -166,6 +271,27 @@ int ffw_muxer_interleaved_write_frame(Muxer* muxer, AVPacket* packet, uint32_t t
     return av_interleaved_write_frame(muxer->fc, packet);
 }
 
+int ffw_muxer_get_sdp(Muxer** muxers, unsigned count, char* buffer, int size) {
+    AVFormatContext** contexts;
+    unsigned i;
+    int ret;
+
+    contexts = av_malloc_array(count, sizeof(AVFormatContext*));
+    if (contexts == NULL) {
+        return AVERROR(ENOMEM);
+    }
+
+    for (i = 0; i < count; i++) {
+        contexts[i] = muxers[i]->fc;
+    }
+
+    ret = av_sdp_create(contexts, count, buffer, size);
+
+    av_free(contexts);
+
+    return ret;
+}
+
 int ffw_muxer_free(Muxer* muxer) {
     int ret = 0;
 
@@ -173,8 +299,11 @@ int ffw_muxer_free(Muxer* muxer) {
         return 0;
     }
 
-    if (muxer->initialized) {
+    if (muxer->initialized == 2) {
         ret = av_write_trailer(muxer->fc);
+    } else if (muxer->initialized && muxer->fc->oformat->deinit) {
+        // the header has not been written, so there is no trailer either
+        muxer->fc->oformat->deinit(muxer->fc);
     }
 
     avformat_free_context(muxer->fc);
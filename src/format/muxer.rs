@@ -1,16 +1,19 @@
 //! A/V muxer.
 
 use std::{
-    ffi::CString,
-    io::Write,
+    ffi::{CStr, CString},
+    io::{Read, Seek, Write},
     os::raw::{c_char, c_int, c_uint, c_void},
     ptr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use crate::{
-    codec::CodecParameters,
-    format::{io::IO, stream::Stream},
+    codec::{CodecParameters, MediaType},
+    format::{io::IO, mp4, stream::Stream},
+    options::OptionInfo,
     packet::Packet,
+    time::{Rounding, TimeBase, Timestamp},
     Error,
 };
 
@@ -21,11 +24,24 @@ extern "C" {
         mime_type: *const c_char,
     ) -> *mut c_void;
 
+    fn ffw_output_format_iterate(opaque: *mut *mut c_void) -> *mut c_void;
+    fn ffw_output_format_get_name(format: *const c_void) -> *const c_char;
+    fn ffw_output_format_get_long_name(format: *const c_void) -> *const c_char;
+    fn ffw_output_format_get_mime_type(format: *const c_void) -> *const c_char;
+    fn ffw_output_format_get_extensions(format: *const c_void) -> *const c_char;
+    fn ffw_output_format_has_global_header(format: *const c_void) -> c_int;
+    fn ffw_output_format_get_default_codec(
+        format: *const c_void,
+        media_type: c_int,
+    ) -> *const c_char;
+
     fn ffw_muxer_new() -> *mut c_void;
     fn ffw_muxer_get_nb_streams(muxer: *const c_void) -> c_uint;
     fn ffw_muxer_get_stream(muxer: *mut c_void, stream_index: c_uint) -> *mut c_void;
     fn ffw_muxer_new_stream(muxer: *mut c_void, params: *const c_void) -> c_int;
     fn ffw_muxer_init(muxer: *mut c_void, io_context: *mut c_void, format: *mut c_void) -> c_int;
+    fn ffw_muxer_write_header(muxer: *mut c_void) -> c_int;
+    fn ffw_muxer_get_unused_options(muxer: *const c_void) -> *const c_void;
     fn ffw_muxer_set_initial_option(
         muxer: *mut c_void,
         key: *const c_char,
@@ -38,6 +54,20 @@ extern "C" {
         key: *const c_char,
         value: *const c_char,
     ) -> c_int;
+    fn ffw_muxer_add_chapter(
+        muxer: *mut c_void,
+        start: i64,
+        end: i64,
+        tb_num: u32,
+        tb_den: u32,
+    ) -> c_int;
+    fn ffw_muxer_set_chapter_metadata(
+        muxer: *mut c_void,
+        index: c_uint,
+        key: *const c_char,
+        value: *const c_char,
+    ) -> c_int;
+    fn ffw_muxer_check_time(value: *const c_char) -> c_int;
     fn ffw_muxer_write_frame(
         muxer: *mut c_void,
         packet: *mut c_void,
@@ -50,14 +80,178 @@ extern "C" {
         tb_num: u32,
         tb_den: u32,
     ) -> c_int;
+    fn ffw_muxer_get_sdp(
+        muxers: *mut *mut c_void,
+        count: c_uint,
+        buffer: *mut c_char,
+        size: c_int,
+    ) -> c_int;
     fn ffw_muxer_free(muxer: *mut c_void) -> c_int;
 }
 
+/// Metadata keys that are managed by the muxers themselves.
+const RESERVED_METADATA_KEYS: [&str; 4] = [
+    "duration",
+    "major_brand",
+    "minor_version",
+    "compatible_brands",
+];
+
+/// Check if a given metadata entry can be set.
+fn check_metadata_entry(key: &str, value: &str) -> Result<(), Error> {
+    if key.is_empty() {
+        return Err(Error::new("empty metadata key"));
+    }
+
+    let reserved = RESERVED_METADATA_KEYS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(key));
+
+    if reserved {
+        return Err(Error::new(format!("reserved metadata key: {}", key)));
+    }
+
+    if key.eq_ignore_ascii_case("creation_time") {
+        let value = CString::new(value).map_err(|_| Error::new("invalid metadata value"))?;
+
+        if unsafe { ffw_muxer_check_time(value.as_ptr()) } < 0 {
+            return Err(Error::new("invalid creation_time"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert a given metadata entry into C strings.
+fn metadata_to_cstrings(key: &str, value: &str) -> Result<(CString, CString), Error> {
+    let key = CString::new(key).map_err(|_| Error::new("invalid metadata key"))?;
+    let value = CString::new(value).map_err(|_| Error::new("invalid metadata value"))?;
+
+    Ok((key, value))
+}
+
+/// Format a given time as an ISO 8601 UTC timestamp with microsecond
+/// precision.
+fn format_iso8601(time: SystemTime) -> String {
+    let micros = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_micros() as i64,
+        Err(err) => -(err.duration().as_micros() as i64),
+    };
+
+    let secs = micros.div_euclid(1_000_000);
+    let micros = micros.rem_euclid(1_000_000);
+
+    let days = secs.div_euclid(86_400);
+    let secs = secs.rem_euclid(86_400);
+
+    // convert days since the epoch into a civil date
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        micros
+    )
+}
+
+/// Chapter.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    start: Timestamp,
+    end: Timestamp,
+    metadata: Vec<(String, String)>,
+}
+
+impl Chapter {
+    /// Create a new chapter with given start and end timestamps.
+    pub fn new(start: Timestamp, end: Timestamp) -> Self {
+        Self {
+            start,
+            end,
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Set a chapter metadata entry (e.g. "title").
+    pub fn with_metadata<V>(mut self, key: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Get the start timestamp.
+    pub fn start(&self) -> Timestamp {
+        self.start
+    }
+
+    /// Get the end timestamp.
+    pub fn end(&self) -> Timestamp {
+        self.end
+    }
+
+    /// Get the chapter metadata.
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+}
+
+/// Policy for flushing the muxer output.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FlushPolicy {
+    /// Leave flushing up to the muxer (see the `flush_packets` muxer
+    /// option). Note that the underlying stream itself is not flushed.
+    Auto,
+    /// Flush the IO and the underlying stream after every packet.
+    Packet,
+    /// Flush the IO and the underlying stream after every video keyframe
+    /// (or after every packet if there are no video streams). Data is not
+    /// flushed automatically by the muxer in this mode (i.e. the
+    /// `flush_packets` option is set to 0), so the output is written in
+    /// GOP-sized chunks. Fragmenting muxers (e.g. fragmented MP4 with the
+    /// `frag_keyframe` flag) write the previous fragment when they get a
+    /// keyframe, so complete fragments are flushed.
+    Keyframe,
+}
+
+/// Policy for packets with non-monotonic DTS (i.e. a DTS that is not
+/// greater than the DTS of the previous packet of the same stream).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DtsPolicy {
+    /// Pass all packets to the muxer as they are. Note that most muxers
+    /// reject packets with non-monotonic DTS anyway.
+    Passthrough,
+    /// Reject packets with non-monotonic DTS. The error contains the stream
+    /// index and both the previous and the current DTS.
+    Error,
+    /// Shift timestamps of the packet and all the following packets of the
+    /// same stream, so that the DTS is monotonic again. This is useful e.g.
+    /// for inputs with timestamp discontinuities.
+    Offset,
+}
+
 /// Muxer builder.
 pub struct MuxerBuilder {
     ptr: *mut c_void,
     streams: Vec<Stream>,
     interleaved: bool,
+    flush_policy: FlushPolicy,
+    dts_policy: DtsPolicy,
+    error: Option<Error>,
 }
 
 impl MuxerBuilder {
@@ -73,6 +267,9 @@ impl MuxerBuilder {
             ptr,
             streams: Vec::new(),
             interleaved: false,
+            flush_policy: FlushPolicy::Auto,
+            dts_policy: DtsPolicy::Passthrough,
+            error: None,
         }
     }
 
@@ -108,28 +305,30 @@ impl MuxerBuilder {
     }
 
     /// Set a muxer option.
-    pub fn set_option<V>(self, name: &str, value: V) -> MuxerBuilder
+    /// An invalid option (e.g. a name containing a NUL character) or an
+    /// option that is not recognized will make the `build()` method fail.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> MuxerBuilder
     where
         V: ToString,
     {
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (c_name, value) = match crate::options::option_to_cstrings(name, &value.to_string()) {
+            Ok(option) => option,
+            Err(err) => {
+                self.error.get_or_insert(err);
 
-        // NOTE: the "url" field cannot be set using the options interface
-        if name == "url" {
-            let ret = unsafe { ffw_muxer_set_url(self.ptr, value.as_ptr()) };
-
-            if ret < 0 {
-                panic!("unable to allocate URL")
+                return self;
             }
-        } else {
-            let name = CString::new(name).expect("invalid option name");
+        };
 
-            let ret =
-                unsafe { ffw_muxer_set_initial_option(self.ptr, name.as_ptr(), value.as_ptr()) };
+        // NOTE: the "url" field cannot be set using the options interface
+        let ret = if name == "url" {
+            unsafe { ffw_muxer_set_url(self.ptr, value.as_ptr()) }
+        } else {
+            unsafe { ffw_muxer_set_initial_option(self.ptr, c_name.as_ptr(), value.as_ptr()) }
+        };
 
-            if ret < 0 {
-                panic!("unable to allocate an option");
-            }
+        if ret < 0 {
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
         }
 
         self
@@ -146,30 +345,153 @@ impl MuxerBuilder {
         self.set_option("url", url)
     }
 
-    /// Set container metadata.
-    pub fn set_metadata<V>(self, key: &str, value: V) -> Self
+    /// Set container metadata (e.g. "title", "artist" or custom keys).
+    /// Keys managed by the muxers themselves (e.g. "duration" or
+    /// "major_brand") are reserved and the `creation_time` value must be a
+    /// valid date. An invalid entry will make the `build()` method fail.
+    pub fn set_metadata<V>(mut self, key: &str, value: V) -> Self
     where
         V: ToString,
     {
-        let key = CString::new(key).expect("invalid metadata key");
-        let value = CString::new(value.to_string()).expect("invalid metadata value");
+        let value = value.to_string();
+
+        let entry =
+            check_metadata_entry(key, &value).and_then(|_| metadata_to_cstrings(key, &value));
+
+        let (key, value) = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                self.error.get_or_insert(err);
+
+                return self;
+            }
+        };
 
         let ret = unsafe { ffw_muxer_set_metadata(self.ptr, key.as_ptr(), value.as_ptr()) };
 
         if ret < 0 {
-            panic!("unable to allocate metadata");
+            self.error.get_or_insert(Error::from_raw_error_code(ret));
+        }
+
+        self
+    }
+
+    /// Set the `creation_time` metadata entry.
+    pub fn set_creation_time(self, time: SystemTime) -> Self {
+        self.set_metadata("creation_time", format_iso8601(time))
+    }
+
+    /// Add a given chapter. Chapter metadata are validated the same way as
+    /// the container metadata. An invalid chapter will make the `build()`
+    /// method fail.
+    pub fn add_chapter(mut self, chapter: Chapter) -> Self {
+        let start = chapter.start;
+        let end = chapter.end;
+
+        if start.is_null() || end.is_null() || end < start {
+            self.error
+                .get_or_insert(Error::new("invalid chapter range"));
+
+            return self;
+        }
+
+        let mut metadata = Vec::with_capacity(chapter.metadata.len());
+
+        for (key, value) in &chapter.metadata {
+            let entry =
+                check_metadata_entry(key, value).and_then(|_| metadata_to_cstrings(key, value));
+
+            match entry {
+                Ok(entry) => metadata.push(entry),
+                Err(err) => {
+                    self.error.get_or_insert(err);
+
+                    return self;
+                }
+            }
+        }
+
+        let tb = start.time_base();
+
+        let end = end.with_time_base(tb);
+
+        let index = unsafe {
+            ffw_muxer_add_chapter(
+                self.ptr,
+                start.timestamp(),
+                end.timestamp(),
+                tb.num(),
+                tb.den(),
+            )
+        };
+
+        if index < 0 {
+            self.error.get_or_insert(Error::from_raw_error_code(index));
+
+            return self;
+        }
+
+        for (key, value) in metadata {
+            let ret = unsafe {
+                ffw_muxer_set_chapter_metadata(self.ptr, index as _, key.as_ptr(), value.as_ptr())
+            };
+
+            if ret < 0 {
+                self.error.get_or_insert(Error::from_raw_error_code(ret));
+
+                return self;
+            }
         }
 
         self
     }
 
     /// Set the muxer to do the interleaving automatically. It is disabled by
-    /// default.
+    /// default (i.e. packets are written in the order they are pushed).
+    ///
+    /// In the interleaved mode, packets are passed to the FFmpeg
+    /// interleaving, so the format-specific rules of the muxer are applied.
+    /// Note that the number of buffered packets is not available.
     pub fn interleaved(mut self, interleaved: bool) -> MuxerBuilder {
         self.interleaved = interleaved;
         self
     }
 
+    /// Set the maximum difference between the first and the last buffered
+    /// packet in the interleaved mode (i.e. the `max_interleave_delta`
+    /// option). If the difference is exceeded, packets are written even if
+    /// some streams do not have any buffered packets. The default is 10
+    /// seconds. `None` means no limit. Low-latency outputs (e.g. fragmented
+    /// MP4) may want to lower the value.
+    pub fn max_interleave_delta(self, delta: Option<Duration>) -> MuxerBuilder {
+        let delta = delta.map_or(0, |delta| delta.as_micros().max(1));
+
+        self.set_option("max_interleave_delta", delta)
+    }
+
+    /// Set the policy for flushing data buffered in the IO. Streaming
+    /// outputs (e.g. TCP or HTTP chunked transfer) should flush the data as
+    /// soon as possible in order to avoid adding latency. The default is
+    /// `FlushPolicy::Auto`.
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> MuxerBuilder {
+        self.flush_policy = policy;
+
+        if policy == FlushPolicy::Keyframe {
+            self.set_option("flush_packets", 0)
+        } else {
+            self
+        }
+    }
+
+    /// Set the policy for packets with non-monotonic DTS. The default is
+    /// `DtsPolicy::Passthrough`. Packets are checked when they are pushed
+    /// into the muxer (i.e. before interleaving) and packets without DTS
+    /// are not checked.
+    pub fn dts_policy(mut self, policy: DtsPolicy) -> MuxerBuilder {
+        self.dts_policy = policy;
+        self
+    }
+
     /// Build the muxer.
     ///
     /// # Arguments
@@ -179,23 +501,65 @@ impl MuxerBuilder {
     where
         T: Write,
     {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
         let io_context_ptr = io.io_context_mut().as_mut_ptr();
         let format_ptr = format.ptr;
 
         let ret = unsafe { ffw_muxer_init(self.ptr, io_context_ptr, format_ptr) };
 
         if ret < 0 {
-            return Err(Error::from_raw_error_code(ret));
+            return Err(io.make_error(ret));
+        }
+
+        let unused = unsafe { ffw_muxer_get_unused_options(self.ptr) };
+
+        if let Err(err) = unsafe { crate::options::check_unused_options(unused) } {
+            // the muxer needs to be freed while the IO is still alive
+            unsafe { ffw_muxer_free(self.ptr) };
+
+            self.ptr = ptr::null_mut();
+
+            return Err(err);
+        }
+
+        let ret = unsafe { ffw_muxer_write_header(self.ptr) };
+
+        if ret < 0 {
+            return Err(io.make_error(ret));
         }
 
         let muxer_ptr = self.ptr;
 
         self.ptr = ptr::null_mut();
 
+        let mut video_streams = self
+            .streams
+            .iter()
+            .map(|stream| stream.codec_parameters().is_video_codec())
+            .collect::<Vec<_>>();
+
+        // flush on every packet if there are no video streams
+        if !video_streams.contains(&true) {
+            video_streams.iter_mut().for_each(|video| *video = true);
+        }
+
+        // the muxer may change the stream time bases during initialization
+        let time_bases = (0..self.streams.len())
+            .map(|index| unsafe {
+                Stream::from_raw_ptr(ffw_muxer_get_stream(muxer_ptr, index as _)).time_base()
+            })
+            .collect();
+
         let res = Muxer {
             ptr: muxer_ptr,
             io: Some(io),
             interleaved: self.interleaved,
+            flush_policy: self.flush_policy,
+            video_streams,
+            dts_fixer: DtsFixer::new(self.dts_policy, time_bases),
         };
 
         Ok(res)
@@ -218,6 +582,9 @@ pub struct Muxer<T> {
     ptr: *mut c_void,
     io: Option<IO<T>>,
     interleaved: bool,
+    flush_policy: FlushPolicy,
+    video_streams: Vec<bool>,
+    dts_fixer: DtsFixer,
 }
 
 impl Muxer<()> {
@@ -233,8 +600,7 @@ impl<T> Muxer<T> {
     where
         V: ToString,
     {
-        let name = CString::new(name).expect("invalid option name");
-        let value = CString::new(value.to_string()).expect("invalid option value");
+        let (name, value) = crate::options::option_to_cstrings(name, &value.to_string())?;
 
         let ret =
             unsafe { ffw_muxer_set_option(self.ptr, name.as_ptr() as _, value.as_ptr() as _) };
@@ -249,13 +615,21 @@ impl<T> Muxer<T> {
     /// Mux a given packet. The packet pts and dts are expected to be in
     /// microseconds. They will be automatically rescaled to match the time
     /// base of the corresponding stream.
-    pub fn push(&mut self, mut packet: Packet) -> Result<(), Error> {
+    pub fn push(&mut self, packet: Packet) -> Result<(), Error> {
         let nb_streams = unsafe { ffw_muxer_get_nb_streams(self.ptr) as usize };
 
         assert!(packet.stream_index() < nb_streams);
 
+        let mut packet = self.dts_fixer.fix(packet)?;
+
         let tb = packet.time_base();
 
+        let flush = match self.flush_policy {
+            FlushPolicy::Auto => false,
+            FlushPolicy::Packet => true,
+            FlushPolicy::Keyframe => packet.is_key() && self.video_streams[packet.stream_index()],
+        };
+
         let ret = unsafe {
             if self.interleaved {
                 ffw_muxer_interleaved_write_frame(self.ptr, packet.as_mut_ptr(), tb.num(), tb.den())
@@ -265,13 +639,17 @@ impl<T> Muxer<T> {
         };
 
         if ret < 0 {
-            Err(Error::from_raw_error_code(ret))
+            Err(self.io().make_error(ret))
+        } else if flush {
+            self.io_mut().flush()
         } else {
             Ok(())
         }
     }
 
-    /// Flush the muxer.
+    /// Flush the muxer. All packets buffered for interleaving will be
+    /// written and all data buffered in the IO will be passed to the
+    /// underlying stream.
     pub fn flush(&mut self) -> Result<(), Error> {
         let ret = unsafe {
             if self.interleaved {
@@ -282,10 +660,23 @@ impl<T> Muxer<T> {
         };
 
         if ret < 0 {
-            Err(Error::from_raw_error_code(ret))
-        } else {
-            Ok(())
+            return Err(self.io().make_error(ret));
         }
+
+        self.io_mut().flush()
+    }
+
+    /// Get the number of packets with non-monotonic DTS that were corrected
+    /// (see `DtsPolicy::Offset`).
+    pub fn dts_corrections(&self) -> u64 {
+        self.dts_fixer.corrections
+    }
+
+    /// Generate an SDP description of the muxer output. This is useful
+    /// mainly for the `rtp` output format. The `url` option (e.g.
+    /// `rtp://192.168.0.10:5004`) is used for the connection information.
+    pub fn sdp(&self) -> Result<String, Error> {
+        create_sdp(std::slice::from_ref(self))
     }
 
     /// Close the muxer and take the underlying IO.
@@ -295,7 +686,7 @@ impl<T> Muxer<T> {
         self.ptr = ptr::null_mut();
 
         if ret != 0 {
-            Err(Error::from_raw_error_code(ret))
+            Err(self.io().make_error(ret))
         } else {
             Ok(self.io.take().unwrap())
         }
@@ -322,9 +713,141 @@ impl<T> Drop for Muxer<T> {
     }
 }
 
+impl<T> Muxer<T>
+where
+    T: Read + Write + Seek,
+{
+    /// Close the muxer, move the MP4 index (i.e. the `moov` box) in front of
+    /// the media data and take the underlying IO. This makes MP4 files
+    /// suitable for progressive playback over HTTP (the same thing as the
+    /// `movflags=+faststart` option which does not work with custom IO). The
+    /// returned flag indicates whether the output had to be rewritten.
+    /// Outputs in other formats are left untouched.
+    pub fn close_with_faststart(self) -> Result<(IO<T>, bool), Error> {
+        let mut io = self.close()?;
+
+        let rewritten = mp4::faststart(io.stream_mut()).map_err(Error::new)?;
+
+        Ok((io, rewritten))
+    }
+}
+
 unsafe impl<T> Send for Muxer<T> where T: Send {}
 unsafe impl<T> Sync for Muxer<T> where T: Sync {}
 
+/// Generate an SDP description of a given set of muxers.
+pub(crate) fn create_sdp<T>(muxers: &[Muxer<T>]) -> Result<String, Error> {
+    let mut ptrs = muxers.iter().map(|muxer| muxer.ptr).collect::<Vec<_>>();
+
+    let mut buffer = vec![0u8; 16384];
+
+    let ret = unsafe {
+        ffw_muxer_get_sdp(
+            ptrs.as_mut_ptr(),
+            ptrs.len() as _,
+            buffer.as_mut_ptr() as _,
+            buffer.len() as _,
+        )
+    };
+
+    if ret < 0 {
+        return Err(Error::from_raw_error_code(ret));
+    }
+
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+
+    buffer.truncate(len);
+
+    String::from_utf8(buffer).map_err(|_| Error::new("invalid SDP"))
+}
+
+/// Checker/fixer of non-monotonic DTS.
+struct DtsFixer {
+    policy: DtsPolicy,
+    time_bases: Vec<TimeBase>,
+    last: Vec<Option<i64>>,
+    offsets: Vec<i64>,
+    corrections: u64,
+}
+
+impl DtsFixer {
+    /// Create a new fixer for streams with given time bases.
+    fn new(policy: DtsPolicy, time_bases: Vec<TimeBase>) -> Self {
+        let streams = time_bases.len();
+
+        Self {
+            policy,
+            time_bases,
+            last: vec![None; streams],
+            offsets: vec![0; streams],
+            corrections: 0,
+        }
+    }
+
+    /// Check a given packet and fix its timestamps if needed.
+    fn fix(&mut self, packet: Packet) -> Result<Packet, Error> {
+        if self.policy == DtsPolicy::Passthrough || packet.dts().is_null() {
+            return Ok(packet);
+        }
+
+        let index = packet.stream_index();
+        let time_base = self.time_bases[index];
+
+        // the timestamps are compared in the stream time base using the same
+        // rounding as the muxer
+        let rescale = |ts: Timestamp| {
+            ts.with_time_base_rounded(time_base, Rounding::NearInf)
+                .timestamp()
+        };
+
+        let mut dts = rescale(packet.dts()) + self.offsets[index];
+
+        if let Some(last) = self.last[index] {
+            if dts <= last {
+                if self.policy == DtsPolicy::Error {
+                    return Err(Error::new(format!(
+                        "non-monotonic DTS in stream {}: previous {}, current {}",
+                        index,
+                        Timestamp::new(last, time_base),
+                        Timestamp::new(dts, time_base)
+                    )));
+                }
+
+                self.offsets[index] += last + 1 - dts;
+                self.corrections += 1;
+
+                dts = last + 1;
+            }
+        }
+
+        self.last[index] = Some(dts);
+
+        let offset = self.offsets[index];
+
+        if offset == 0 {
+            return Ok(packet);
+        }
+
+        // the packet needs to be in the stream time base, otherwise the
+        // correction could be lost when rescaling
+        let packet = packet.with_muxer_time_base(time_base);
+
+        let pts = packet.pts();
+
+        let pts = if pts.is_null() {
+            pts
+        } else {
+            Timestamp::new(pts.timestamp() + offset, time_base)
+        };
+
+        let res = packet
+            .with_pts(pts)
+            .with_dts(Timestamp::new(dts, time_base));
+
+        Ok(res)
+    }
+}
+
 /// FFmpeg output format.
 pub struct OutputFormat {
     ptr: *mut c_void,
@@ -380,7 +903,436 @@ impl OutputFormat {
 
         Some(res)
     }
+
+    /// Get all available output formats.
+    pub fn all() -> Vec<OutputFormat> {
+        let mut opaque = ptr::null_mut();
+
+        let mut res = Vec::new();
+
+        loop {
+            let ptr = unsafe { ffw_output_format_iterate(&mut opaque) };
+
+            if ptr.is_null() {
+                return res;
+            }
+
+            res.push(OutputFormat { ptr });
+        }
+    }
+
+    /// Get short name of the format (e.g. "mp4").
+    pub fn name(&self) -> &'static str {
+        unsafe { string_from_ptr(ffw_output_format_get_name(self.ptr)).unwrap_or("") }
+    }
+
+    /// Get descriptive name of the format (e.g. "MP4 (MPEG-4 Part 14)").
+    pub fn long_name(&self) -> &'static str {
+        unsafe { string_from_ptr(ffw_output_format_get_long_name(self.ptr)).unwrap_or("") }
+    }
+
+    /// Get MIME type of the format (if any).
+    pub fn mime_type(&self) -> Option<&'static str> {
+        unsafe { string_from_ptr(ffw_output_format_get_mime_type(self.ptr)) }
+    }
+
+    /// Get file name extensions commonly used for the format (e.g. "mp4").
+    pub fn extensions(&self) -> Vec<&'static str> {
+        unsafe { string_from_ptr(ffw_output_format_get_extensions(self.ptr)) }
+            .map(|extensions| {
+                extensions
+                    .split(',')
+                    .filter(|extension| !extension.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Check if the format stores codec headers (e.g. H.264 SPS/PPS) in the
+    /// container rather than in the stream (e.g. MP4 or Matroska). Encoders
+    /// producing streams for such formats should be created with the
+    /// global header flag enabled (see the `global_header()` method of the
+    /// encoder builders).
+    pub fn global_header(&self) -> bool {
+        unsafe { ffw_output_format_has_global_header(self.ptr) != 0 }
+    }
+
+    /// Get name of the default codec used by the format for a given media
+    /// type (e.g. "h264" for video in MP4). `None` is returned if the format
+    /// does not have a default codec for the media type.
+    pub fn default_codec(&self, media_type: MediaType) -> Option<&'static str> {
+        unsafe {
+            string_from_ptr(ffw_output_format_get_default_codec(
+                self.ptr,
+                media_type.into_raw(),
+            ))
+        }
+    }
+
+    /// Get options specific to this output format.
+    pub fn options(&self) -> Vec<OptionInfo> {
+        unsafe { crate::options::output_format_options(self.ptr) }
+    }
+}
+
+/// Convert a given C string into a static string slice. `None` is returned
+/// for null pointers and invalid UTF-8 strings.
+unsafe fn string_from_ptr(ptr: *const c_char) -> Option<&'static str> {
+    if ptr.is_null() {
+        None
+    } else {
+        CStr::from_ptr(ptr).to_str().ok()
+    }
 }
 
 unsafe impl Send for OutputFormat {}
 unsafe impl Sync for OutputFormat {}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Write},
+        sync::{Arc, Mutex},
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use super::{
+        check_metadata_entry, format_iso8601, Chapter, DtsPolicy, FlushPolicy, Muxer, MuxerBuilder,
+        OutputFormat,
+    };
+
+    use crate::{
+        codec::{video::frame::get_pixel_format, MediaType, VideoCodecParameters},
+        format::io::IO,
+        packet::{Packet, PacketMut},
+        time::{TimeBase, Timestamp},
+    };
+
+    /// Create an empty packet with a given stream index and dts in
+    /// milliseconds.
+    fn packet(stream_index: usize, dts: i64) -> Packet {
+        let ts = Timestamp::new(dts, TimeBase::new(1, 1000));
+
+        PacketMut::new(0)
+            .with_stream_index(stream_index)
+            .with_time_base(TimeBase::new(1, 1000))
+            .with_dts(ts)
+            .with_pts(ts)
+            .freeze()
+    }
+
+    #[test]
+    fn test_format_iso8601() {
+        assert_eq!(format_iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000000Z");
+
+        let time = UNIX_EPOCH + Duration::from_micros(1_709_210_096_123_456);
+
+        assert_eq!(format_iso8601(time), "2024-02-29T12:34:56.123456Z");
+
+        let time = UNIX_EPOCH - Duration::from_secs(1);
+
+        assert_eq!(format_iso8601(time), "1969-12-31T23:59:59.000000Z");
+    }
+
+    #[test]
+    fn test_check_metadata_entry() {
+        assert!(check_metadata_entry("title", "foo").is_ok());
+        assert!(check_metadata_entry("", "foo").is_err());
+        assert!(check_metadata_entry("Major_Brand", "isom").is_err());
+        assert!(check_metadata_entry("creation_time", "2024-02-29T12:34:56Z").is_ok());
+        assert!(check_metadata_entry("creation_time", "yesterday").is_err());
+        assert!(check_metadata_entry("creation_time", "2024\0").is_err());
+    }
+
+    #[test]
+    fn test_interleaving() {
+        let params = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(get_pixel_format("gray"))
+            .width(10)
+            .height(10)
+            .build();
+
+        let mut builder = Muxer::builder()
+            .interleaved(true)
+            .max_interleave_delta(Some(Duration::from_millis(100)))
+            .flush_policy(FlushPolicy::Packet);
+
+        builder.add_stream(&params.clone().into()).unwrap();
+        builder.add_stream(&params.into()).unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_write_stream(Vec::new()),
+                OutputFormat::find_by_name("framecrc").unwrap(),
+            )
+            .unwrap();
+
+        // get stream indices and DTS (in the 1/90000 stream time base) of
+        // the written packets
+        let written = |output: &[u8]| {
+            String::from_utf8_lossy(output)
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .map(|line| {
+                    let mut fields = line.split(',').map(|field| field.trim().parse().unwrap());
+
+                    (fields.next().unwrap(), fields.next().unwrap())
+                })
+                .collect::<Vec<(i64, i64)>>()
+        };
+
+        muxer.push(packet(0, 0)).unwrap();
+        muxer.push(packet(0, 40)).unwrap();
+
+        // waiting for stream 1
+        assert!(written(muxer.io().stream()).is_empty());
+
+        muxer.push(packet(1, 20)).unwrap();
+
+        assert_eq!(written(muxer.io().stream()), [(0, 0), (1, 1800)]);
+
+        // the maximum delta has been exceeded
+        muxer.push(packet(0, 200)).unwrap();
+
+        assert_eq!(written(muxer.io().stream()), [(0, 0), (1, 1800), (0, 3600)]);
+
+        let output = muxer.close().unwrap().into_stream();
+
+        assert_eq!(written(&output), [(0, 0), (1, 1800), (0, 3600), (0, 18000)]);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let format = OutputFormat::find_by_name("mpegts").unwrap();
+
+        let res = Muxer::builder()
+            .set_option("mpegts\0flags", "resend_headers")
+            .build(IO::from_write_stream(Vec::new()), format);
+
+        assert!(res.is_err());
+
+        let params = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(get_pixel_format("gray"))
+            .width(10)
+            .height(10)
+            .build();
+
+        let mut builder = Muxer::builder()
+            .set_option("mpegts_flags", "resend_headers")
+            .set_option("no_such_option", 1);
+
+        builder.add_stream(&params.into()).unwrap();
+
+        let res = builder.build(
+            IO::from_write_stream(Vec::new()),
+            OutputFormat::find_by_name("mpegts").unwrap(),
+        );
+
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "unknown options: no_such_option"
+        );
+    }
+
+    #[test]
+    fn test_invalid_options_output() {
+        /// Writer sharing its buffer with the test.
+        #[derive(Clone, Default)]
+        struct SharedWriter {
+            data: Arc<Mutex<Vec<u8>>>,
+        }
+
+        impl Write for SharedWriter {
+            fn write(&mut self, buffer: &[u8]) -> Result<usize, io::Error> {
+                self.data.lock().unwrap().extend_from_slice(buffer);
+
+                Ok(buffer.len())
+            }
+
+            fn flush(&mut self) -> Result<(), io::Error> {
+                Ok(())
+            }
+        }
+
+        let params = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(get_pixel_format("gray"))
+            .width(10)
+            .height(10)
+            .build();
+
+        let writer = SharedWriter::default();
+
+        let mut builder = Muxer::builder().set_option("no_such_option", 1);
+
+        builder.add_stream(&params.into()).unwrap();
+
+        let res = builder.build(
+            IO::from_write_stream(writer.clone()),
+            OutputFormat::find_by_name("nut").unwrap(),
+        );
+
+        assert!(res.is_err());
+
+        // neither the header nor the trailer must be written
+        assert!(writer.data.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_metadata() {
+        let build = |builder: MuxerBuilder| {
+            builder.build(
+                IO::from_write_stream(Vec::new()),
+                OutputFormat::find_by_name("ffmetadata").unwrap(),
+            )
+        };
+
+        assert!(build(Muxer::builder().set_metadata("title", "foo")).is_ok());
+        assert!(build(Muxer::builder().set_metadata("ti\0tle", "foo")).is_err());
+        assert!(build(Muxer::builder().set_metadata("title", "f\0oo")).is_err());
+
+        let tb = TimeBase::new(1, 1000);
+
+        let chapter = Chapter::new(Timestamp::new(0, tb), Timestamp::new(1000, tb));
+
+        let builder = Muxer::builder().add_chapter(chapter.clone().with_metadata("title", "foo"));
+
+        assert!(build(builder).is_ok());
+
+        let builder = Muxer::builder().add_chapter(chapter.with_metadata("title", "f\0oo"));
+
+        assert!(build(builder).is_err());
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        let params = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(get_pixel_format("gray"))
+            .width(10)
+            .height(10)
+            .build();
+
+        let mut builder = Muxer::builder().flush_policy(FlushPolicy::Keyframe);
+
+        builder.add_stream(&params.into()).unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_write_stream(Vec::new()),
+                OutputFormat::find_by_name("rawvideo").unwrap(),
+            )
+            .unwrap();
+
+        let video_packet = |pts: i64, key: bool| {
+            let time_base = TimeBase::new(1, 25);
+            let pts = Timestamp::new(pts, time_base);
+
+            PacketMut::from(&[0u8; 100][..])
+                .with_time_base(time_base)
+                .with_pts(pts)
+                .with_dts(pts)
+                .with_key_flag(key)
+                .freeze()
+        };
+
+        muxer.push(video_packet(0, true)).unwrap();
+
+        assert_eq!(muxer.io().stream().len(), 100);
+
+        muxer.push(video_packet(1, false)).unwrap();
+
+        assert_eq!(muxer.io().stream().len(), 100);
+
+        muxer.push(video_packet(2, true)).unwrap();
+
+        assert_eq!(muxer.io().stream().len(), 300);
+
+        muxer.push(video_packet(3, false)).unwrap();
+        muxer.flush().unwrap();
+
+        assert_eq!(muxer.io().stream().len(), 400);
+    }
+
+    #[test]
+    fn test_dts_policy() {
+        let params = VideoCodecParameters::builder("rawvideo")
+            .unwrap()
+            .pixel_format(get_pixel_format("gray"))
+            .width(10)
+            .height(10)
+            .build();
+
+        let new_muxer = |policy| {
+            let mut builder = Muxer::builder().dts_policy(policy);
+
+            builder.add_stream(&params.clone().into()).unwrap();
+
+            builder
+                .build(
+                    IO::from_write_stream(Vec::new()),
+                    OutputFormat::find_by_name("mpegts").unwrap(),
+                )
+                .unwrap()
+        };
+
+        let packet = |dts: i64| {
+            let time_base = TimeBase::new(1, 1000);
+            let dts = Timestamp::new(dts, time_base);
+
+            PacketMut::from(&[0u8; 100][..])
+                .with_time_base(time_base)
+                .with_pts(dts)
+                .with_dts(dts)
+                .freeze()
+        };
+
+        // the third packet jumps backwards
+        let dts = [0, 40, 0, 40, 80];
+
+        let mut muxer = new_muxer(DtsPolicy::Passthrough);
+
+        assert!(dts
+            .iter()
+            .map(|&dts| muxer.push(packet(dts)))
+            .any(|res| res.is_err()));
+
+        let mut muxer = new_muxer(DtsPolicy::Error);
+
+        muxer.push(packet(0)).unwrap();
+        muxer.push(packet(40)).unwrap();
+
+        let err = muxer.push(packet(0)).err().unwrap();
+
+        assert!(err.to_string().contains("stream 0"));
+
+        let mut muxer = new_muxer(DtsPolicy::Offset);
+
+        for &dts in &dts {
+            muxer.push(packet(dts)).unwrap();
+        }
+
+        assert_eq!(muxer.dts_corrections(), 1);
+
+        muxer.close().unwrap();
+    }
+
+    #[test]
+    fn test_output_format_info() {
+        let format = OutputFormat::guess_from_file_name("out.wav").unwrap();
+
+        assert_eq!(format.name(), "wav");
+        assert_eq!(format.extensions(), ["wav"]);
+        assert_eq!(format.default_codec(MediaType::Audio), Some("pcm_s16le"));
+        assert_eq!(format.default_codec(MediaType::Video), None);
+        assert!(!format.global_header());
+
+        assert!(OutputFormat::find_by_name("mp4").unwrap().global_header());
+
+        assert!(OutputFormat::all()
+            .iter()
+            .any(|format| format.name() == "mpegts"));
+    }
+}
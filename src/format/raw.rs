@@ -0,0 +1,236 @@
+//! Raw PCM and raw video input.
+//!
+//! Raw data (e.g. data from a capture device) do not contain any headers,
+//! so the `pcm` and `rawvideo` demuxers need to be told how to interpret
+//! them. The presets here configure the demuxers using typed parameters.
+//!
+//! # Example
+//! ```ignore
+//! let demuxer = RawVideoInput::new(pixel_format, (1920, 1080), Rational::new(30, 1))
+//!     .build(IO::from_read_stream(capture))?;
+//! ```
+
+use std::io::Read;
+
+use crate::{
+    codec::{audio::SampleFormat, video::PixelFormat},
+    format::{
+        demuxer::{Demuxer, DemuxerBuilder, DemuxerWithStreamInfo, InputFormat},
+        io::IO,
+    },
+    math::Rational,
+    Error,
+};
+
+/// Raw PCM input. The input contains interleaved samples in the native
+/// byte order.
+#[derive(Copy, Clone)]
+pub struct RawAudioInput {
+    sample_format: SampleFormat,
+    channels: u32,
+    sample_rate: u32,
+}
+
+impl RawAudioInput {
+    /// Create a new raw PCM input with a given sample format, number of
+    /// channels and sample rate.
+    ///
+    /// # Panics
+    /// The method panics if the number of channels or the sample rate is
+    /// zero.
+    pub fn new(sample_format: SampleFormat, channels: u32, sample_rate: u32) -> Self {
+        assert!(channels > 0, "the number of channels must be positive");
+        assert!(sample_rate > 0, "the sample rate must be positive");
+
+        Self {
+            sample_format,
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Get a demuxer builder configured for the input. The builder can be
+    /// used for setting additional options.
+    pub fn demuxer_builder(&self) -> Result<DemuxerBuilder, Error> {
+        let name = pcm_format_name(self.sample_format)?;
+
+        let format =
+            InputFormat::find_by_name(name).ok_or_else(|| Error::new("unknown input format"))?;
+
+        let res = Demuxer::builder()
+            .input_format(Some(format))
+            .set_option("sample_rate", self.sample_rate)
+            .set_option("channels", self.channels);
+
+        Ok(res)
+    }
+
+    /// Create a demuxer reading the input from a given IO.
+    pub fn build<T>(&self, io: IO<T>) -> Result<DemuxerWithStreamInfo<T>, Error>
+    where
+        T: Read,
+    {
+        self.demuxer_builder()?
+            .build(io)?
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+    }
+}
+
+/// Get name of the PCM demuxer for a given sample format.
+fn pcm_format_name(sample_format: SampleFormat) -> Result<&'static str, Error> {
+    if sample_format.is_planar() {
+        return Err(Error::new("planar sample formats are not supported"));
+    }
+
+    let res = match sample_format.name() {
+        "u8" => "u8",
+        "s16" if cfg!(target_endian = "little") => "s16le",
+        "s16" => "s16be",
+        "s32" if cfg!(target_endian = "little") => "s32le",
+        "s32" => "s32be",
+        "flt" if cfg!(target_endian = "little") => "f32le",
+        "flt" => "f32be",
+        "dbl" if cfg!(target_endian = "little") => "f64le",
+        "dbl" => "f64be",
+        _ => return Err(Error::new("unsupported sample format")),
+    };
+
+    Ok(res)
+}
+
+/// Raw video input. The input contains tightly packed frames (i.e. without
+/// any row padding).
+#[derive(Copy, Clone)]
+pub struct RawVideoInput {
+    pixel_format: PixelFormat,
+    width: usize,
+    height: usize,
+    frame_rate: Rational,
+}
+
+impl RawVideoInput {
+    /// Create a new raw video input with a given pixel format, frame size
+    /// (width and height) and frame rate.
+    ///
+    /// # Panics
+    /// The method panics if the frame size is zero or if the frame rate is
+    /// not positive.
+    pub fn new(pixel_format: PixelFormat, size: (usize, usize), frame_rate: Rational) -> Self {
+        let (width, height) = size;
+
+        assert!(width > 0 && height > 0, "the frame size must be positive");
+        assert!(
+            frame_rate.num() > 0 && frame_rate.den() > 0,
+            "the frame rate must be positive"
+        );
+
+        Self {
+            pixel_format,
+            width,
+            height,
+            frame_rate,
+        }
+    }
+
+    /// Get a demuxer builder configured for the input. The builder can be
+    /// used for setting additional options.
+    pub fn demuxer_builder(&self) -> Result<DemuxerBuilder, Error> {
+        let format = InputFormat::find_by_name("rawvideo")
+            .ok_or_else(|| Error::new("unknown input format"))?;
+
+        let res = Demuxer::builder()
+            .input_format(Some(format))
+            .set_option("pixel_format", self.pixel_format.name())
+            .set_option("video_size", format!("{}x{}", self.width, self.height))
+            .set_option("framerate", self.frame_rate);
+
+        Ok(res)
+    }
+
+    /// Create a demuxer reading the input from a given IO. Every packet will
+    /// contain a single frame and its timestamps will be in 1 / frame_rate
+    /// time base.
+    pub fn build<T>(&self, io: IO<T>) -> Result<DemuxerWithStreamInfo<T>, Error>
+    where
+        T: Read,
+    {
+        self.demuxer_builder()?
+            .build(io)?
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{RawAudioInput, RawVideoInput};
+
+    use crate::{
+        codec::{audio::frame::get_sample_format, video::frame::get_pixel_format},
+        format::io::IO,
+        math::Rational,
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_raw_audio_input() {
+        let sample_format = get_sample_format("s16");
+
+        let mut demuxer = RawAudioInput::new(sample_format, 2, 48000)
+            .build(IO::from_read_stream(Cursor::new(vec![0u8; 19200])))
+            .unwrap();
+
+        let params = demuxer.streams()[0].codec_parameters();
+        let params = params.as_audio_codec_parameters().unwrap();
+
+        assert!(params.sample_format() == sample_format);
+        assert_eq!(params.channels(), 2);
+        assert_eq!(params.sample_rate(), 48000);
+
+        let mut size = 0;
+
+        while let Some(packet) = demuxer.take().unwrap() {
+            size += packet.data().len();
+        }
+
+        assert_eq!(size, 19200);
+
+        assert!(RawAudioInput::new(get_sample_format("s16p"), 2, 48000)
+            .demuxer_builder()
+            .is_err());
+    }
+
+    #[test]
+    fn test_raw_video_input() {
+        let pixel_format = get_pixel_format("yuv420p");
+        let frame_rate = Rational::new(25, 1);
+
+        // three 64x48 YUV 4:2:0 frames
+        let data = vec![0u8; 3 * 64 * 48 * 3 / 2];
+
+        let mut demuxer = RawVideoInput::new(pixel_format, (64, 48), frame_rate)
+            .build(IO::from_read_stream(Cursor::new(data)))
+            .unwrap();
+
+        let params = demuxer.streams()[0].codec_parameters();
+        let params = params.as_video_codec_parameters().unwrap();
+
+        assert!(params.pixel_format() == pixel_format);
+        assert_eq!(params.width(), 64);
+        assert_eq!(params.height(), 48);
+
+        let time_base = TimeBase::new(1, 25);
+
+        for i in 0..3 {
+            let packet = demuxer.take().unwrap().unwrap();
+
+            assert_eq!(packet.data().len(), 64 * 48 * 3 / 2);
+            assert_eq!(packet.pts(), Timestamp::new(i, time_base));
+        }
+
+        assert!(demuxer.take().unwrap().is_none());
+    }
+}
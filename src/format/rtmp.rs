@@ -0,0 +1,327 @@
+//! RTMP publishing.
+//!
+//! The publisher sets up an FLV muxer on top of an RTMP URL stream. The FLV
+//! muxer generates the `onMetaData` script packet from the stream
+//! parameters and the container metadata. If the connection fails with a
+//! transient network error, the publisher reconnects, re-initializes the
+//! muxer and resumes from the next key frame. Packet timestamps are kept
+//! untouched, so the stream continues where it left off.
+
+use std::{io, thread, time::Duration};
+
+use crate::{
+    codec::CodecParameters,
+    format::{
+        io::{UrlStream, IO},
+        muxer::{Muxer, OutputFormat},
+        tee::PacketSink,
+    },
+    packet::Packet,
+    Error,
+};
+
+/// Connection state of an RTMP publisher.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConnectionState {
+    /// Connecting for the first time.
+    Connecting,
+    /// Connected and publishing.
+    Connected,
+    /// Reconnecting after a transient error. The value is the number of the
+    /// current attempt (starting from 1).
+    Reconnecting(u32),
+    /// Disconnected (either closed or all reconnect attempts failed).
+    Disconnected,
+}
+
+/// Builder for the RTMP publisher.
+pub struct RtmpPublisherBuilder {
+    url: String,
+    streams: Vec<CodecParameters>,
+    metadata: Vec<(String, String)>,
+    rw_timeout: Option<Duration>,
+    max_reconnect_attempts: u32,
+    reconnect_delay: Duration,
+    on_state_change: Option<Box<dyn FnMut(ConnectionState) + Send>>,
+}
+
+impl RtmpPublisherBuilder {
+    /// Create a new builder.
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            streams: Vec::new(),
+            metadata: Vec::new(),
+            rw_timeout: Some(Duration::from_secs(10)),
+            max_reconnect_attempts: 5,
+            reconnect_delay: Duration::from_secs(1),
+            on_state_change: None,
+        }
+    }
+
+    /// Add a new stream with given parameters. Streams are indexed in the
+    /// order they were added.
+    pub fn add_stream(mut self, params: &CodecParameters) -> Self {
+        self.streams.push(params.clone());
+        self
+    }
+
+    /// Set a metadata entry that will be sent in the `onMetaData` packet.
+    pub fn set_metadata<V>(mut self, key: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the network read/write timeout. The default is 10 seconds.
+    pub fn rw_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.rw_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of consecutive reconnect attempts. The default
+    /// is 5. Use 0 to disable reconnecting.
+    pub fn max_reconnect_attempts(mut self, attempts: u32) -> Self {
+        self.max_reconnect_attempts = attempts;
+        self
+    }
+
+    /// Set the delay between two reconnect attempts. The default is 1 second.
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Set a callback that will be called whenever the connection state
+    /// changes.
+    pub fn on_state_change<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(ConnectionState) + Send + 'static,
+    {
+        self.on_state_change = Some(Box::new(callback));
+        self
+    }
+
+    /// Connect to the server and build the publisher.
+    pub fn build(self) -> Result<RtmpPublisher, Error> {
+        if self.streams.is_empty() {
+            return Err(Error::new("no streams"));
+        }
+
+        let key_stream = self
+            .streams
+            .iter()
+            .position(|params| params.is_video_codec());
+
+        let mut res = RtmpPublisher {
+            url: self.url,
+            streams: self.streams,
+            metadata: self.metadata,
+            rw_timeout: self.rw_timeout,
+            max_reconnect_attempts: self.max_reconnect_attempts,
+            reconnect_delay: self.reconnect_delay,
+            on_state_change: self.on_state_change,
+            key_stream,
+            muxer: None,
+            wait_for_key: true,
+            reconnects: 0,
+        };
+
+        res.set_state(ConnectionState::Connecting);
+
+        match res.connect() {
+            Ok(muxer) => res.muxer = Some(muxer),
+            Err(err) => {
+                res.set_state(ConnectionState::Disconnected);
+
+                return Err(err);
+            }
+        }
+
+        res.set_state(ConnectionState::Connected);
+
+        Ok(res)
+    }
+}
+
+/// RTMP publisher.
+///
+/// Packet timestamps are expected to be in microseconds as required by the
+/// muxer. Publishing starts with the first key frame of the first video
+/// stream (if any).
+pub struct RtmpPublisher {
+    url: String,
+    streams: Vec<CodecParameters>,
+    metadata: Vec<(String, String)>,
+    rw_timeout: Option<Duration>,
+    max_reconnect_attempts: u32,
+    reconnect_delay: Duration,
+    on_state_change: Option<Box<dyn FnMut(ConnectionState) + Send>>,
+    key_stream: Option<usize>,
+    muxer: Option<Muxer<UrlStream>>,
+    wait_for_key: bool,
+    reconnects: u64,
+}
+
+impl RtmpPublisher {
+    /// Get a builder for a publisher sending to a given RTMP URL.
+    pub fn builder(url: &str) -> RtmpPublisherBuilder {
+        RtmpPublisherBuilder::new(url)
+    }
+
+    /// Check if the publisher is connected.
+    pub fn is_connected(&self) -> bool {
+        self.muxer.is_some()
+    }
+
+    /// Get the total number of successful reconnects.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    /// Publish a given packet. If the connection fails with a transient
+    /// error, the method reconnects (blocking the caller) and the packet is
+    /// dropped. An error is returned if the publisher is disconnected or if
+    /// the error is not transient.
+    pub fn push(&mut self, packet: Packet) -> Result<(), Error> {
+        if self.muxer.is_none() {
+            return Err(Error::new("disconnected"));
+        }
+
+        if self.wait_for_key {
+            let key_stream = self.key_stream.unwrap_or_else(|| packet.stream_index());
+
+            if packet.stream_index() != key_stream || !packet.is_key() {
+                return Ok(());
+            }
+
+            self.wait_for_key = false;
+        }
+
+        let err = match self.muxer.as_mut().unwrap().push(packet) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        self.muxer = None;
+
+        if !is_transient(&err) {
+            self.set_state(ConnectionState::Disconnected);
+
+            return Err(err);
+        }
+
+        self.reconnect()
+    }
+
+    /// Flush and close the connection.
+    pub fn close(mut self) -> Result<(), Error> {
+        let res = if let Some(mut muxer) = self.muxer.take() {
+            muxer.flush().and_then(|_| muxer.close()).map(|_| ())
+        } else {
+            Ok(())
+        };
+
+        self.set_state(ConnectionState::Disconnected);
+
+        res
+    }
+
+    /// Try to reconnect.
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let mut attempt = 0;
+
+        loop {
+            if attempt >= self.max_reconnect_attempts {
+                self.set_state(ConnectionState::Disconnected);
+
+                return Err(Error::new("unable to reconnect"));
+            }
+
+            attempt += 1;
+
+            self.set_state(ConnectionState::Reconnecting(attempt));
+
+            thread::sleep(self.reconnect_delay);
+
+            match self.connect() {
+                Ok(muxer) => {
+                    self.muxer = Some(muxer);
+                    self.wait_for_key = true;
+                    self.reconnects += 1;
+
+                    self.set_state(ConnectionState::Connected);
+
+                    return Ok(());
+                }
+                Err(err) if is_transient(&err) => (),
+                Err(err) => {
+                    self.set_state(ConnectionState::Disconnected);
+
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Open the URL and create a new muxer.
+    fn connect(&self) -> Result<Muxer<UrlStream>, Error> {
+        let mut builder = UrlStream::builder();
+
+        if let Some(timeout) = self.rw_timeout {
+            builder = builder.rw_timeout(timeout);
+        }
+
+        let stream = builder.open_write(&self.url)?;
+
+        let format =
+            OutputFormat::find_by_name("flv").ok_or_else(|| Error::new("unknown format: flv"))?;
+
+        let mut builder = Muxer::builder();
+
+        for params in &self.streams {
+            builder.add_stream(params)?;
+        }
+
+        for (key, value) in &self.metadata {
+            builder = builder.set_metadata(key, value);
+        }
+
+        builder
+            .interleaved(true)
+            .build(IO::from_write_stream(stream), format)
+    }
+
+    /// Report a given connection state.
+    fn set_state(&mut self, state: ConnectionState) {
+        if let Some(cb) = self.on_state_change.as_mut() {
+            cb(state);
+        }
+    }
+}
+
+impl PacketSink for RtmpPublisher {
+    fn push(&mut self, packet: Packet) -> Result<(), Error> {
+        RtmpPublisher::push(self, packet)
+    }
+
+    fn close(self: Box<Self>) -> Result<(), Error> {
+        RtmpPublisher::close(*self)
+    }
+}
+
+/// Check if a given error is a transient network error.
+fn is_transient(err: &Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::UnexpectedEof
+    )
+}
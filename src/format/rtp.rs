@@ -0,0 +1,214 @@
+//! RTP packetization.
+//!
+//! RTP carries only a single stream per session, so the RTP muxer creates
+//! one FFmpeg `rtp` muxer for every stream. The produced RTP (and RTCP
+//! sender report) packets are not sent anywhere. They are queued and can be
+//! taken from the muxer and handed over to an RTP stack. Payload formats
+//! (e.g. H.264 or Opus) are chosen by FFmpeg based on the codec. A
+//! corresponding SDP description can be generated using the `sdp()`
+//! method.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    codec::CodecParameters,
+    format::{
+        io::IO,
+        muxer::{self, Muxer, OutputFormat},
+    },
+    packet::Packet,
+    Error,
+};
+
+/// A single RTP or RTCP packet.
+pub struct RtpPacket {
+    stream_index: usize,
+    data: Vec<u8>,
+}
+
+impl RtpPacket {
+    /// Get index of the stream the packet belongs to.
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    /// Check if this is an RTCP packet (i.e. a sender report).
+    pub fn is_rtcp(&self) -> bool {
+        // RTCP packet types 200-204 collide with RTP payload types 72-76
+        // with the marker bit set, these payload types are not allowed in
+        // RTP
+        self.data.len() > 1 && (200..=204).contains(&self.data[1])
+    }
+
+    /// Get the packet data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the packet data.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Shared queue of the produced packets.
+type PacketQueue = Arc<Mutex<VecDeque<RtpPacket>>>;
+
+/// Output of a single RTP muxer.
+struct RtpOutput {
+    stream_index: usize,
+    queue: PacketQueue,
+}
+
+impl Write for RtpOutput {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        // the RTP muxer flushes the IO after every packet and the IO buffer
+        // is bigger than the maximum packet size, so every write is a
+        // single packet
+        let packet = RtpPacket {
+            stream_index: self.stream_index,
+            data: buffer.to_vec(),
+        };
+
+        self.queue.lock().unwrap().push_back(packet);
+
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Builder for the RTP muxer.
+pub struct RtpMuxerBuilder {
+    streams: Vec<(CodecParameters, String)>,
+    packet_size: usize,
+    options: Vec<(String, String)>,
+}
+
+impl RtpMuxerBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            streams: Vec::new(),
+            packet_size: 1200,
+            options: Vec::new(),
+        }
+    }
+
+    /// Add a new stream with given parameters and a given destination URL
+    /// (e.g. `rtp://192.168.0.10:5004`). The URL is used only in the SDP
+    /// description. Streams are indexed in the order they were added.
+    pub fn add_stream(mut self, params: &CodecParameters, url: &str) -> Self {
+        self.streams.push((params.clone(), url.to_string()));
+        self
+    }
+
+    /// Set the maximum RTP packet size in bytes. The default is 1200 bytes,
+    /// which fits into a UDP datagram on most networks (including WebRTC).
+    pub fn packet_size(mut self, size: usize) -> Self {
+        self.packet_size = size;
+        self
+    }
+
+    /// Set an option of all underlying RTP muxers (e.g. `payload_type`,
+    /// `ssrc` or `rtpflags`).
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.options.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Build the RTP muxer.
+    pub fn build(self) -> Result<RtpMuxer, Error> {
+        if self.packet_size > 4096 {
+            return Err(Error::new("the RTP packet size must not exceed 4096 bytes"));
+        }
+
+        let queue = PacketQueue::default();
+
+        let mut muxers = Vec::new();
+
+        for (stream_index, (params, url)) in self.streams.iter().enumerate() {
+            let format = OutputFormat::find_by_name("rtp")
+                .ok_or_else(|| Error::new("unknown format: rtp"))?;
+
+            let mut builder = Muxer::builder();
+
+            builder.add_stream(params)?;
+
+            builder = builder
+                .set_option("url", url)
+                .set_option("packetsize", self.packet_size);
+
+            for (name, value) in &self.options {
+                builder = builder.set_option(name, value);
+            }
+
+            let output = RtpOutput {
+                stream_index,
+                queue: queue.clone(),
+            };
+
+            let muxer = builder.build(IO::from_write_stream(output), format)?;
+
+            muxers.push(muxer);
+        }
+
+        let res = RtpMuxer { muxers, queue };
+
+        Ok(res)
+    }
+}
+
+/// RTP muxer.
+pub struct RtpMuxer {
+    muxers: Vec<Muxer<RtpOutput>>,
+    queue: PacketQueue,
+}
+
+impl RtpMuxer {
+    /// Get a builder for the RTP muxer.
+    pub fn builder() -> RtpMuxerBuilder {
+        RtpMuxerBuilder::new()
+    }
+
+    /// Get an SDP description of all streams.
+    pub fn sdp(&self) -> Result<String, Error> {
+        muxer::create_sdp(&self.muxers)
+    }
+
+    /// Packetize a given packet. The packet will be split into one or more
+    /// RTP packets that can be taken using the `take()` method.
+    pub fn push(&mut self, packet: Packet) -> Result<(), Error> {
+        let stream_index = packet.stream_index();
+
+        let muxer = self
+            .muxers
+            .get_mut(stream_index)
+            .ok_or_else(|| Error::new("invalid stream index"))?;
+
+        muxer.push(packet.with_stream_index(0))
+    }
+
+    /// Take the next RTP/RTCP packet (if any).
+    pub fn take(&mut self) -> Option<RtpPacket> {
+        self.queue.lock().unwrap().pop_front()
+    }
+
+    /// Flush all muxers.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        for muxer in &mut self.muxers {
+            muxer.flush()?;
+        }
+
+        Ok(())
+    }
+}
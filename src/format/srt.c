@@ -0,0 +1,85 @@
+#include <libavformat/avio.h>
+#include <libavutil/error.h>
+#include <libavutil/log.h>
+#include <libavutil/opt.h>
+
+#include <srt/srt.h>
+
+#include <string.h>
+
+typedef struct UrlIO UrlIO;
+
+// NOTE: FFmpeg does not expose the SRT socket used by the libsrt protocol,
+// so we rely on the socket being the first member of the private protocol
+// context following the AVClass pointer (this is the case in all FFmpeg
+// versions providing the libsrt protocol)
+typedef struct SRTContextHeader {
+    const AVClass* class;
+    int fd;
+} SRTContextHeader;
+
+AVIOContext* ffw_url_io_get_context(const UrlIO* io);
+
+int ffw_srt_get_stats(
+    const UrlIO* io,
+    double* rtt,
+    int64_t* sent, int64_t* received,
+    int64_t* send_loss, int64_t* receive_loss,
+    int64_t* retransmitted,
+    int64_t* send_drop, int64_t* receive_drop);
+
+static SRTContextHeader* get_srt_context(AVIOContext* context) {
+    const AVClass* class;
+    void* url;
+    void* protocol;
+
+    // the only child of the IO context is the URL context and the only child
+    // of the URL context is the private protocol context
+    url = av_opt_child_next(context, NULL);
+    if (url == NULL) {
+        return NULL;
+    }
+
+    protocol = av_opt_child_next(url, NULL);
+    if (protocol == NULL) {
+        return NULL;
+    }
+
+    class = *(const AVClass**)protocol;
+    if (class == NULL || strcmp(class->class_name, "libsrt") != 0) {
+        return NULL;
+    }
+
+    return protocol;
+}
+
+int ffw_srt_get_stats(
+    const UrlIO* io,
+    double* rtt,
+    int64_t* sent, int64_t* received,
+    int64_t* send_loss, int64_t* receive_loss,
+    int64_t* retransmitted,
+    int64_t* send_drop, int64_t* receive_drop) {
+    SRT_TRACEBSTATS perf;
+    SRTContextHeader* context;
+
+    context = get_srt_context(ffw_url_io_get_context(io));
+    if (context == NULL) {
+        return AVERROR(ENOTSUP);
+    }
+
+    if (srt_bstats(context->fd, &perf, 0) < 0) {
+        return AVERROR_EXTERNAL;
+    }
+
+    *rtt = perf.msRTT;
+    *sent = perf.pktSentTotal;
+    *received = perf.pktRecvTotal;
+    *send_loss = perf.pktSndLossTotal;
+    *receive_loss = perf.pktRcvLossTotal;
+    *retransmitted = perf.pktRetransTotal;
+    *send_drop = perf.pktSndDropTotal;
+    *receive_drop = perf.pktRcvDropTotal;
+
+    return 0;
+}
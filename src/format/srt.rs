@@ -0,0 +1,266 @@
+//! SRT (Secure Reliable Transport) input and output.
+//!
+//! SRT streams are opened using the FFmpeg `srt` protocol, so FFmpeg needs to
+//! be built with libsrt support (`--enable-libsrt`). The resulting stream can
+//! be passed to a demuxer or a muxer using `IO::from_read_stream()` or
+//! `IO::from_write_stream()` respectively.
+//!
+//! Connection statistics (RTT, packet loss, retransmissions, ...) can be
+//! obtained using `UrlStream::srt_stats()`. The method is available only with
+//! the `srt` feature enabled because it needs to link libsrt directly.
+
+#[cfg(feature = "srt")]
+use std::os::raw::{c_int, c_void};
+
+use std::time::Duration;
+
+use crate::{
+    format::io::{UrlStream, UrlStreamBuilder},
+    Error,
+};
+
+#[cfg(feature = "srt")]
+extern "C" {
+    fn ffw_srt_get_stats(
+        io: *const c_void,
+        rtt: *mut f64,
+        sent: *mut i64,
+        received: *mut i64,
+        send_loss: *mut i64,
+        receive_loss: *mut i64,
+        retransmitted: *mut i64,
+        send_drop: *mut i64,
+        receive_drop: *mut i64,
+    ) -> c_int;
+}
+
+/// SRT connection mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SrtMode {
+    Caller,
+    Listener,
+    Rendezvous,
+}
+
+impl SrtMode {
+    /// Get name of the mode as expected by FFmpeg.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Caller => "caller",
+            Self::Listener => "listener",
+            Self::Rendezvous => "rendezvous",
+        }
+    }
+}
+
+/// Builder for SRT streams.
+pub struct SrtStreamBuilder {
+    inner: UrlStreamBuilder,
+}
+
+impl SrtStreamBuilder {
+    /// Create a new builder.
+    pub fn new() -> Self {
+        Self {
+            inner: UrlStream::builder(),
+        }
+    }
+
+    /// Set connection mode. The default is `SrtMode::Caller`.
+    pub fn mode(self, mode: SrtMode) -> Self {
+        self.set_option("mode", mode.name())
+    }
+
+    /// Set the receiver delay used to absorb bursts of missed packet
+    /// retransmissions.
+    pub fn latency(self, latency: Duration) -> Self {
+        self.set_option("latency", latency.as_micros())
+    }
+
+    /// Set encryption passphrase (10 to 79 characters).
+    pub fn passphrase(self, passphrase: &str) -> Self {
+        self.set_option("passphrase", passphrase)
+    }
+
+    /// Set encryption key length in bytes (16, 24 or 32).
+    pub fn key_length(self, length: u32) -> Self {
+        self.set_option("pbkeylen", length)
+    }
+
+    /// Set stream ID passed by the caller to the listener.
+    pub fn stream_id(self, stream_id: &str) -> Self {
+        self.set_option("streamid", stream_id)
+    }
+
+    /// Set connection timeout.
+    pub fn connect_timeout(self, timeout: Duration) -> Self {
+        self.set_option("connect_timeout", timeout.as_millis())
+    }
+
+    /// Set timeout of socket read/write operations.
+    pub fn rw_timeout(self, timeout: Duration) -> Self {
+        self.set_option("rw_timeout", timeout.as_micros())
+    }
+
+    /// Set maximum payload size of a single SRT packet. The default (1316
+    /// bytes) fits exactly 7 MPEG-TS packets.
+    pub fn payload_size(self, size: usize) -> Self {
+        self.set_option("payload_size", size)
+    }
+
+    /// Set any other FFmpeg `srt` protocol option.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.inner = self.inner.set_option(name, value);
+        self
+    }
+
+    /// Open a given SRT URL (e.g. `srt://host:port`) for reading.
+    pub fn open_read(self, url: &str) -> Result<UrlStream, Error> {
+        self.inner.open_read(url)
+    }
+
+    /// Open a given SRT URL (e.g. `srt://host:port`) for writing.
+    pub fn open_write(self, url: &str) -> Result<UrlStream, Error> {
+        self.inner.open_write(url)
+    }
+}
+
+impl Default for SrtStreamBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SRT connection statistics.
+#[cfg(feature = "srt")]
+#[derive(Debug, Copy, Clone)]
+pub struct SrtStats {
+    rtt: Duration,
+    packets_sent: u64,
+    packets_received: u64,
+    packets_lost_sent: u64,
+    packets_lost_received: u64,
+    packets_retransmitted: u64,
+    packets_dropped_sent: u64,
+    packets_dropped_received: u64,
+}
+
+#[cfg(feature = "srt")]
+impl SrtStats {
+    /// Get the smoothed round-trip time.
+    pub fn rtt(&self) -> Duration {
+        self.rtt
+    }
+
+    /// Get the total number of sent data packets (including
+    /// retransmissions).
+    pub fn packets_sent(&self) -> u64 {
+        self.packets_sent
+    }
+
+    /// Get the total number of received data packets.
+    pub fn packets_received(&self) -> u64 {
+        self.packets_received
+    }
+
+    /// Get the total number of sent packets reported as lost by the peer.
+    pub fn packets_lost_sent(&self) -> u64 {
+        self.packets_lost_sent
+    }
+
+    /// Get the total number of packets detected as lost on the receiver
+    /// side.
+    pub fn packets_lost_received(&self) -> u64 {
+        self.packets_lost_received
+    }
+
+    /// Get the total number of retransmitted packets.
+    pub fn packets_retransmitted(&self) -> u64 {
+        self.packets_retransmitted
+    }
+
+    /// Get the total number of packets dropped by the sender because they
+    /// could not be delivered in time.
+    pub fn packets_dropped_sent(&self) -> u64 {
+        self.packets_dropped_sent
+    }
+
+    /// Get the total number of packets dropped by the receiver because they
+    /// arrived too late or did not arrive at all.
+    pub fn packets_dropped_received(&self) -> u64 {
+        self.packets_dropped_received
+    }
+}
+
+#[cfg(feature = "srt")]
+impl UrlStream {
+    /// Get SRT connection statistics. The method fails if the stream was not
+    /// opened using the `srt` protocol.
+    pub fn srt_stats(&self) -> Result<SrtStats, Error> {
+        let mut rtt = 0.0;
+        let mut sent = 0;
+        let mut received = 0;
+        let mut send_loss = 0;
+        let mut receive_loss = 0;
+        let mut retransmitted = 0;
+        let mut send_drop = 0;
+        let mut receive_drop = 0;
+
+        let ret = unsafe {
+            ffw_srt_get_stats(
+                self.as_ptr(),
+                &mut rtt,
+                &mut sent,
+                &mut received,
+                &mut send_loss,
+                &mut receive_loss,
+                &mut retransmitted,
+                &mut send_drop,
+                &mut receive_drop,
+            )
+        };
+
+        if ret < 0 {
+            return Err(Error::from_raw_error_code(ret));
+        }
+
+        let res = SrtStats {
+            rtt: Duration::from_secs_f64(rtt.max(0.0) / 1000.0),
+            packets_sent: sent.max(0) as u64,
+            packets_received: received.max(0) as u64,
+            packets_lost_sent: send_loss.max(0) as u64,
+            packets_lost_received: receive_loss.max(0) as u64,
+            packets_retransmitted: retransmitted.max(0) as u64,
+            packets_dropped_sent: send_drop.max(0) as u64,
+            packets_dropped_received: receive_drop.max(0) as u64,
+        };
+
+        Ok(res)
+    }
+}
+
+#[cfg(feature = "srt")]
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::format::io::UrlStream;
+
+    #[test]
+    fn test_stats_of_other_protocols() {
+        let path = std::env::temp_dir().join(format!("ac-ffmpeg-srt-{}", std::process::id()));
+
+        fs::write(&path, [0u8; 188]).unwrap();
+
+        let stream = UrlStream::open_read(path.to_str().unwrap()).unwrap();
+
+        assert!(stream.srt_stats().is_err());
+
+        drop(stream);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,11 +1,49 @@
 #include <libavformat/avformat.h>
+#include <libavutil/mastering_display_metadata.h>
+#include <libavutil/replaygain.h>
+
+#include <string.h>
+
+// Dolby Vision configuration is available since FFmpeg 4.4
+#if defined(__has_include)
+#if __has_include(<libavutil/dovi_meta.h>)
+#include <libavutil/dovi_meta.h>
+#define FFW_HAS_DOVI_CONF
+#endif
+#endif
+
+#define MEDIA_TYPE_UNKNOWN    -1
+#define MEDIA_TYPE_VIDEO      0
+#define MEDIA_TYPE_AUDIO      1
+#define MEDIA_TYPE_SUBTITLE   2
+#define MEDIA_TYPE_DATA       3
+#define MEDIA_TYPE_ATTACHMENT 4
 
 void ffw_stream_get_time_base(const AVStream* stream, uint32_t* num, uint32_t* den);
 int64_t ffw_stream_get_start_time(const AVStream* stream);
 int64_t ffw_stream_get_duration(const AVStream* stream);
 int64_t ffw_stream_get_nb_frames(const AVStream* stream);
+void ffw_stream_get_r_frame_rate(const AVStream* stream, uint32_t* num, uint32_t* den);
+void ffw_stream_get_avg_frame_rate(const AVStream* stream, uint32_t* num, uint32_t* den);
+void ffw_stream_guess_frame_rate(AVStream* stream, uint32_t* num, uint32_t* den);
 AVCodecParameters* ffw_stream_get_codec_parameters(const AVStream* stream);
+int ffw_stream_get_replay_gain(const AVStream* stream, int32_t* track_gain, uint32_t* track_peak, int32_t* album_gain, uint32_t* album_peak);
+const char* ffw_stream_get_metadata(const AVStream* stream, const char* key);
 int ffw_stream_set_metadata(AVStream* stream, const char* key, const char* value);
+int ffw_stream_get_id(const AVStream* stream);
+int ffw_stream_get_disposition(const AVStream* stream);
+int ffw_stream_get_media_type(const AVStream* stream);
+int ffw_stream_get_attachment(const AVStream* stream, const uint8_t** data, int* size);
+int ffw_stream_set_extradata(AVStream* stream, const uint8_t* data, int size);
+const AVDictionaryEntry* ffw_stream_next_metadata_entry(const AVStream* stream, const AVDictionaryEntry* prev, const char** key, const char** value);
+void ffw_stream_get_color_properties(const AVStream* stream, int* primaries, int* transfer, int* space);
+void ffw_stream_set_color_properties(AVStream* stream, int primaries, int transfer, int space);
+const AVMasteringDisplayMetadata* ffw_stream_get_mastering_display_metadata(const AVStream* stream);
+AVMasteringDisplayMetadata* ffw_stream_new_mastering_display_metadata(AVStream* stream);
+int ffw_stream_get_content_light_level(const AVStream* stream, uint32_t* max_cll, uint32_t* max_fall);
+int ffw_stream_set_content_light_level(AVStream* stream, uint32_t max_cll, uint32_t max_fall);
+int ffw_stream_get_dovi_config(const AVStream* stream, int* values);
+int ffw_stream_set_dovi_config(AVStream* stream, const int* values);
 
 void ffw_stream_get_time_base(const AVStream* stream, uint32_t* num, uint32_t* den) {
     *num = stream->time_base.num;
@@ -24,6 +62,38 @@ int64_t ffw_stream_get_nb_frames(const AVStream* stream) {
     return stream->nb_frames;
 }
 
+void ffw_stream_get_r_frame_rate(const AVStream* stream, uint32_t* num, uint32_t* den) {
+    if (stream->r_frame_rate.num > 0 && stream->r_frame_rate.den > 0) {
+        *num = stream->r_frame_rate.num;
+        *den = stream->r_frame_rate.den;
+    } else {
+        *num = 0;
+        *den = 0;
+    }
+}
+
+void ffw_stream_get_avg_frame_rate(const AVStream* stream, uint32_t* num, uint32_t* den) {
+    if (stream->avg_frame_rate.num > 0 && stream->avg_frame_rate.den > 0) {
+        *num = stream->avg_frame_rate.num;
+        *den = stream->avg_frame_rate.den;
+    } else {
+        *num = 0;
+        *den = 0;
+    }
+}
+
+void ffw_stream_guess_frame_rate(AVStream* stream, uint32_t* num, uint32_t* den) {
+    AVRational r = av_guess_frame_rate(NULL, stream, NULL);
+
+    if (r.num > 0 && r.den > 0) {
+        *num = r.num;
+        *den = r.den;
+    } else {
+        *num = 0;
+        *den = 0;
+    }
+}
+
 AVCodecParameters* ffw_stream_get_codec_parameters(const AVStream* stream) {
     AVCodecParameters* res = avcodec_parameters_alloc();
     if (!res) {
@@ -42,6 +112,245 @@ err:
     return NULL;
 }
 
+int ffw_stream_get_replay_gain(const AVStream* stream, int32_t* track_gain, uint32_t* track_peak, int32_t* album_gain, uint32_t* album_peak) {
+    const AVReplayGain* rg;
+    int size;
+
+    rg = (const AVReplayGain*)av_stream_get_side_data(stream, AV_PKT_DATA_REPLAYGAIN, &size);
+    if (!rg || size < (int)sizeof(AVReplayGain)) {
+        return 0;
+    }
+
+    *track_gain = rg->track_gain;
+    *track_peak = rg->track_peak;
+    *album_gain = rg->album_gain;
+    *album_peak = rg->album_peak;
+
+    return 1;
+}
+
+const char* ffw_stream_get_metadata(const AVStream* stream, const char* key) {
+    AVDictionaryEntry* entry = av_dict_get(stream->metadata, key, NULL, 0);
+
+    if (!entry) {
+        return NULL;
+    }
+
+    return entry->value;
+}
+
+const AVDictionaryEntry* ffw_stream_next_metadata_entry(const AVStream* stream, const AVDictionaryEntry* prev, const char** key, const char** value) {
+    AVDictionaryEntry* entry = av_dict_get(stream->metadata, "", prev, AV_DICT_IGNORE_SUFFIX);
+
+    if (entry) {
+        *key = entry->key;
+        *value = entry->value;
+    }
+
+    return entry;
+}
+
 int ffw_stream_set_metadata(AVStream* stream, const char* key, const char* value) {
     return av_dict_set(&stream->metadata, key, value, 0);
 }
+
+int ffw_stream_get_id(const AVStream* stream) {
+    return stream->id;
+}
+
+int ffw_stream_get_disposition(const AVStream* stream) {
+    return stream->disposition;
+}
+
+int ffw_stream_get_media_type(const AVStream* stream) {
+    switch (stream->codecpar->codec_type) {
+        case AVMEDIA_TYPE_VIDEO: return MEDIA_TYPE_VIDEO;
+        case AVMEDIA_TYPE_AUDIO: return MEDIA_TYPE_AUDIO;
+        case AVMEDIA_TYPE_SUBTITLE: return MEDIA_TYPE_SUBTITLE;
+        case AVMEDIA_TYPE_DATA: return MEDIA_TYPE_DATA;
+        case AVMEDIA_TYPE_ATTACHMENT: return MEDIA_TYPE_ATTACHMENT;
+        default: return MEDIA_TYPE_UNKNOWN;
+    }
+}
+
+int ffw_stream_get_attachment(const AVStream* stream, const uint8_t** data, int* size) {
+    if (stream->codecpar->codec_type != AVMEDIA_TYPE_ATTACHMENT) {
+        return 0;
+    }
+
+    *data = stream->codecpar->extradata;
+    *size = stream->codecpar->extradata_size;
+
+    return 1;
+}
+
+int ffw_stream_set_extradata(AVStream* stream, const uint8_t* data, int size) {
+    uint8_t* extradata = NULL;
+
+    if (size > 0) {
+        extradata = av_mallocz(size + AV_INPUT_BUFFER_PADDING_SIZE);
+        if (!extradata) {
+            return AVERROR(ENOMEM);
+        }
+
+        memcpy(extradata, data, size);
+    }
+
+    av_freep(&stream->codecpar->extradata);
+
+    stream->codecpar->extradata = extradata;
+    stream->codecpar->extradata_size = size;
+
+    return 0;
+}
+
+// NOTE: unspecified color properties are represented as -1 on the Rust side
+static int color_property_to_raw(int value) {
+    return value == AVCOL_PRI_UNSPECIFIED ? -1 : value;
+}
+
+static int color_property_from_raw(int value) {
+    return value < 0 ? AVCOL_PRI_UNSPECIFIED : value;
+}
+
+void ffw_stream_get_color_properties(const AVStream* stream, int* primaries, int* transfer, int* space) {
+    *primaries = color_property_to_raw(stream->codecpar->color_primaries);
+    *transfer = color_property_to_raw(stream->codecpar->color_trc);
+    *space = color_property_to_raw(stream->codecpar->color_space);
+}
+
+void ffw_stream_set_color_properties(AVStream* stream, int primaries, int transfer, int space) {
+    stream->codecpar->color_primaries = color_property_from_raw(primaries);
+    stream->codecpar->color_trc = color_property_from_raw(transfer);
+    stream->codecpar->color_space = color_property_from_raw(space);
+}
+
+const AVMasteringDisplayMetadata* ffw_stream_get_mastering_display_metadata(const AVStream* stream) {
+    const uint8_t* data;
+    int size;
+
+    data = av_stream_get_side_data(stream, AV_PKT_DATA_MASTERING_DISPLAY_METADATA, &size);
+    if (!data || size < (int)sizeof(AVMasteringDisplayMetadata)) {
+        return NULL;
+    }
+
+    return (const AVMasteringDisplayMetadata*)data;
+}
+
+AVMasteringDisplayMetadata* ffw_stream_new_mastering_display_metadata(AVStream* stream) {
+    AVMasteringDisplayMetadata* metadata;
+
+    metadata = av_mastering_display_metadata_alloc();
+    if (!metadata) {
+        return NULL;
+    }
+
+    // NOTE: any existing side data of the same type will be replaced
+    if (av_stream_add_side_data(stream, AV_PKT_DATA_MASTERING_DISPLAY_METADATA, (uint8_t*)metadata, sizeof(AVMasteringDisplayMetadata)) < 0) {
+        av_free(metadata);
+        return NULL;
+    }
+
+    return metadata;
+}
+
+int ffw_stream_get_content_light_level(const AVStream* stream, uint32_t* max_cll, uint32_t* max_fall) {
+    const AVContentLightMetadata* metadata;
+    int size;
+
+    metadata = (const AVContentLightMetadata*)av_stream_get_side_data(stream, AV_PKT_DATA_CONTENT_LIGHT_LEVEL, &size);
+    if (!metadata || size < (int)sizeof(AVContentLightMetadata)) {
+        return 0;
+    }
+
+    *max_cll = metadata->MaxCLL;
+    *max_fall = metadata->MaxFALL;
+
+    return 1;
+}
+
+int ffw_stream_set_content_light_level(AVStream* stream, uint32_t max_cll, uint32_t max_fall) {
+    AVContentLightMetadata* metadata;
+    size_t size;
+    int ret;
+
+    metadata = av_content_light_metadata_alloc(&size);
+    if (!metadata) {
+        return AVERROR(ENOMEM);
+    }
+
+    metadata->MaxCLL = max_cll;
+    metadata->MaxFALL = max_fall;
+
+    ret = av_stream_add_side_data(stream, AV_PKT_DATA_CONTENT_LIGHT_LEVEL, (uint8_t*)metadata, size);
+    if (ret < 0) {
+        av_free(metadata);
+    }
+
+    return ret;
+}
+
+// The Dolby Vision configuration record is represented as an array of 8
+// integers (version major, version minor, profile, level, RPU present flag,
+// EL present flag, BL present flag and BL signal compatibility ID).
+int ffw_stream_get_dovi_config(const AVStream* stream, int* values) {
+#ifdef FFW_HAS_DOVI_CONF
+    const AVDOVIDecoderConfigurationRecord* config;
+    int size;
+
+    config = (const AVDOVIDecoderConfigurationRecord*)av_stream_get_side_data(stream, AV_PKT_DATA_DOVI_CONF, &size);
+    if (!config || size < (int)sizeof(AVDOVIDecoderConfigurationRecord)) {
+        return 0;
+    }
+
+    values[0] = config->dv_version_major;
+    values[1] = config->dv_version_minor;
+    values[2] = config->dv_profile;
+    values[3] = config->dv_level;
+    values[4] = config->rpu_present_flag;
+    values[5] = config->el_present_flag;
+    values[6] = config->bl_present_flag;
+    values[7] = config->dv_bl_signal_compatibility_id;
+
+    return 1;
+#else
+    (void)stream;
+    (void)values;
+
+    return 0;
+#endif
+}
+
+int ffw_stream_set_dovi_config(AVStream* stream, const int* values) {
+#ifdef FFW_HAS_DOVI_CONF
+    AVDOVIDecoderConfigurationRecord* config;
+    size_t size;
+    int ret;
+
+    config = av_dovi_alloc(&size);
+    if (!config) {
+        return AVERROR(ENOMEM);
+    }
+
+    config->dv_version_major = values[0];
+    config->dv_version_minor = values[1];
+    config->dv_profile = values[2];
+    config->dv_level = values[3];
+    config->rpu_present_flag = values[4];
+    config->el_present_flag = values[5];
+    config->bl_present_flag = values[6];
+    config->dv_bl_signal_compatibility_id = values[7];
+
+    ret = av_stream_add_side_data(stream, AV_PKT_DATA_DOVI_CONF, (uint8_t*)config, size);
+    if (ret < 0) {
+        av_free(config);
+    }
+
+    return ret;
+#else
+    (void)stream;
+    (void)values;
+
+    return AVERROR(ENOSYS);
+#endif
+}
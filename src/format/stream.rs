@@ -1,21 +1,86 @@
 //! A/V stream information.
 
 use std::{
-    ffi::CString,
+    ffi::{CStr, CString},
+    fmt::{self, Display, Formatter},
     os::raw::{c_char, c_int, c_void},
+    ptr, slice,
 };
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    codec::CodecParameters,
-    time::{TimeBase, Timestamp},
+    codec::{
+        video::{ContentLightLevel, DolbyVisionConfig, HdrMetadata, MasteringDisplayMetadata},
+        CodecParameters, CodecParametersSummary, MediaType,
+    },
+    encryption::EncryptionInitInfo,
+    format::codec_private,
+    math::Rational,
+    time::{Rounding, TimeBase, Timestamp},
+    Error,
 };
 
 extern "C" {
+    fn ffw_stream_get_color_properties(
+        stream: *const c_void,
+        primaries: *mut c_int,
+        transfer: *mut c_int,
+        space: *mut c_int,
+    );
+    fn ffw_stream_set_color_properties(
+        stream: *mut c_void,
+        primaries: c_int,
+        transfer: c_int,
+        space: c_int,
+    );
+    fn ffw_stream_get_mastering_display_metadata(stream: *const c_void) -> *const c_void;
+    fn ffw_stream_new_mastering_display_metadata(stream: *mut c_void) -> *mut c_void;
+    fn ffw_stream_get_content_light_level(
+        stream: *const c_void,
+        max_cll: *mut u32,
+        max_fall: *mut u32,
+    ) -> c_int;
+    fn ffw_stream_set_content_light_level(
+        stream: *mut c_void,
+        max_cll: u32,
+        max_fall: u32,
+    ) -> c_int;
+    fn ffw_stream_get_dovi_config(stream: *const c_void, values: *mut c_int) -> c_int;
+    fn ffw_stream_set_dovi_config(stream: *mut c_void, values: *const c_int) -> c_int;
     fn ffw_stream_get_time_base(stream: *const c_void, num: *mut u32, den: *mut u32);
     fn ffw_stream_get_start_time(stream: *const c_void) -> i64;
     fn ffw_stream_get_duration(stream: *const c_void) -> i64;
     fn ffw_stream_get_nb_frames(stream: *const c_void) -> i64;
+    fn ffw_stream_get_r_frame_rate(stream: *const c_void, num: *mut u32, den: *mut u32);
+    fn ffw_stream_get_avg_frame_rate(stream: *const c_void, num: *mut u32, den: *mut u32);
+    fn ffw_stream_guess_frame_rate(stream: *mut c_void, num: *mut u32, den: *mut u32);
     fn ffw_stream_get_codec_parameters(stream: *const c_void) -> *mut c_void;
+    fn ffw_stream_get_replay_gain(
+        stream: *const c_void,
+        track_gain: *mut i32,
+        track_peak: *mut u32,
+        album_gain: *mut i32,
+        album_peak: *mut u32,
+    ) -> c_int;
+    fn ffw_stream_get_encryption_init_info(stream: *const c_void) -> *mut c_void;
+    fn ffw_stream_get_metadata(stream: *const c_void, key: *const c_char) -> *const c_char;
+    fn ffw_stream_next_metadata_entry(
+        stream: *const c_void,
+        prev: *const c_void,
+        key: *mut *const c_char,
+        value: *mut *const c_char,
+    ) -> *const c_void;
+    fn ffw_stream_get_id(stream: *const c_void) -> c_int;
+    fn ffw_stream_get_disposition(stream: *const c_void) -> c_int;
+    fn ffw_stream_get_media_type(stream: *const c_void) -> c_int;
+    fn ffw_stream_get_attachment(
+        stream: *const c_void,
+        data: *mut *const u8,
+        size: *mut c_int,
+    ) -> c_int;
+    fn ffw_stream_set_extradata(stream: *mut c_void, data: *const u8, size: c_int) -> c_int;
     fn ffw_stream_set_metadata(
         stream: *mut c_void,
         key: *const c_char,
@@ -23,10 +88,183 @@ extern "C" {
     ) -> c_int;
 }
 
+/// Loudness normalization info of an audio stream.
+///
+/// All gains are in dB relative to the ReplayGain reference level (i.e. -18
+/// LUFS). Peaks are relative to the full scale (i.e. 1.0 is the full scale).
+#[derive(Debug, Copy, Clone)]
+pub struct ReplayGain {
+    track_gain: Option<f32>,
+    track_peak: Option<f32>,
+    album_gain: Option<f32>,
+    album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    /// Create replay gain info from the raw representation used by the
+    /// FFmpeg replay gain side data.
+    fn from_raw(track_gain: i32, track_peak: u32, album_gain: i32, album_peak: u32) -> Self {
+        let gain = |gain| {
+            if gain == i32::MIN {
+                None
+            } else {
+                Some(gain as f32 / 100_000.0)
+            }
+        };
+
+        let peak = |peak| {
+            if peak == 0 {
+                None
+            } else {
+                Some(peak as f32 / 100_000.0)
+            }
+        };
+
+        Self {
+            track_gain: gain(track_gain),
+            track_peak: peak(track_peak),
+            album_gain: gain(album_gain),
+            album_peak: peak(album_peak),
+        }
+    }
+
+    /// Create replay gain info from Opus R128 gain tags. The tags contain
+    /// Q7.8 gains relative to the EBU R128 reference level (i.e. -23 LUFS),
+    /// so they need to be shifted by 5 dB.
+    fn from_r128_tags(track_gain: Option<&str>, album_gain: Option<&str>) -> Option<Self> {
+        let gain = |gain: Option<&str>| {
+            gain.and_then(|g| g.trim().parse::<i16>().ok())
+                .map(|g| g as f32 / 256.0 + 5.0)
+        };
+
+        let track_gain = gain(track_gain);
+        let album_gain = gain(album_gain);
+
+        if track_gain.is_none() && album_gain.is_none() {
+            return None;
+        }
+
+        let res = Self {
+            track_gain,
+            track_peak: None,
+            album_gain,
+            album_peak: None,
+        };
+
+        Some(res)
+    }
+
+    /// Get track gain in dB.
+    pub fn track_gain(&self) -> Option<f32> {
+        self.track_gain
+    }
+
+    /// Get track peak.
+    pub fn track_peak(&self) -> Option<f32> {
+        self.track_peak
+    }
+
+    /// Get album gain in dB.
+    pub fn album_gain(&self) -> Option<f32> {
+        self.album_gain
+    }
+
+    /// Get album peak.
+    pub fn album_peak(&self) -> Option<f32> {
+        self.album_peak
+    }
+}
+
+/// Stream disposition flags.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Disposition(c_int);
+
+impl Disposition {
+    /// Check if this is the default stream of its type.
+    pub fn is_default(self) -> bool {
+        self.0 & 0x0001 != 0
+    }
+
+    /// Check if this is a dubbed stream.
+    pub fn is_dub(self) -> bool {
+        self.0 & 0x0002 != 0
+    }
+
+    /// Check if this is a stream in the original language.
+    pub fn is_original(self) -> bool {
+        self.0 & 0x0004 != 0
+    }
+
+    /// Check if this is a commentary stream.
+    pub fn is_comment(self) -> bool {
+        self.0 & 0x0008 != 0
+    }
+
+    /// Check if this is a forced subtitle stream.
+    pub fn is_forced(self) -> bool {
+        self.0 & 0x0040 != 0
+    }
+
+    /// Check if the stream is intended for the hearing impaired.
+    pub fn is_hearing_impaired(self) -> bool {
+        self.0 & 0x0080 != 0
+    }
+
+    /// Check if the stream is intended for the visually impaired.
+    pub fn is_visual_impaired(self) -> bool {
+        self.0 & 0x0100 != 0
+    }
+
+    /// Check if the stream is an attached picture (e.g. a cover art in an
+    /// MP3 file) rather than a real video stream.
+    pub fn is_attached_pic(self) -> bool {
+        self.0 & 0x0400 != 0
+    }
+}
+
+/// Method used for obtaining a frame count.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FrameCountMethod {
+    /// The number of frames stored in the container.
+    Container,
+    /// The number of frames calculated from the stream duration and the
+    /// average frame rate.
+    Duration,
+    /// The number of frames obtained by counting all packets of the stream.
+    Scan,
+}
+
+/// Frame count of a stream.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FrameCount {
+    count: u64,
+    method: FrameCountMethod,
+}
+
+impl FrameCount {
+    /// Create a new frame count.
+    pub(crate) fn new(count: u64, method: FrameCountMethod) -> Self {
+        Self { count, method }
+    }
+
+    /// Get the number of frames.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Get the method that produced the value.
+    pub fn method(&self) -> FrameCountMethod {
+        self.method
+    }
+}
+
 /// Stream.
 pub struct Stream {
     ptr: *mut c_void,
     time_base: TimeBase,
+    skip_samples: u64,
+    padding_samples: u64,
+    pcr_pid: Option<u16>,
 }
 
 impl Stream {
@@ -40,26 +278,49 @@ impl Stream {
         Stream {
             ptr,
             time_base: TimeBase::new(num, den),
+            skip_samples: 0,
+            padding_samples: 0,
+            pcr_pid: None,
         }
     }
 
+    /// Set encoder delay and padding as detected by the demuxer.
+    pub(crate) fn with_gapless_info(mut self, skip_samples: u64, padding_samples: u64) -> Self {
+        self.set_gapless_info(skip_samples, padding_samples);
+        self
+    }
+
+    /// Update encoder delay and padding.
+    pub(crate) fn set_gapless_info(&mut self, skip_samples: u64, padding_samples: u64) {
+        self.skip_samples = skip_samples;
+        self.padding_samples = padding_samples;
+    }
+
+    /// Set PID of the transport stream packets carrying PCR for the program
+    /// this stream belongs to.
+    pub(crate) fn with_pcr_pid(mut self, pcr_pid: Option<u16>) -> Self {
+        self.pcr_pid = pcr_pid;
+        self
+    }
+
     /// Get stream time base.
     pub fn time_base(&self) -> TimeBase {
         self.time_base
     }
 
-    /// Get the pts of the first frame of the stream in presentation order.
-    pub fn start_time(&self) -> Timestamp {
+    /// Get the pts of the first frame of the stream in presentation order
+    /// (if known).
+    pub fn start_time(&self) -> Option<Timestamp> {
         let pts = unsafe { ffw_stream_get_start_time(self.ptr) as _ };
 
-        Timestamp::new(pts, self.time_base)
+        Timestamp::new(pts, self.time_base).into_option()
     }
 
-    /// Get the duration of the stream.
-    pub fn duration(&self) -> Timestamp {
+    /// Get the duration of the stream (if known).
+    pub fn duration(&self) -> Option<Timestamp> {
         let pts = unsafe { ffw_stream_get_duration(self.ptr) as _ };
 
-        Timestamp::new(pts, self.time_base)
+        Timestamp::new(pts, self.time_base).into_option()
     }
 
     /// Get the number of frames in the stream.
@@ -77,6 +338,134 @@ impl Stream {
         }
     }
 
+    /// Get the number of frames in the stream using the most reliable
+    /// method available without reading the stream. The number of frames
+    /// stored in the container is preferred. If it is not available, the
+    /// number of frames is calculated from the stream duration and the
+    /// average frame rate (or the real base frame rate if the average frame
+    /// rate is not known). `None` is returned if neither of the methods is
+    /// applicable. Use `DemuxerWithStreamInfo::count_frames()` to get an
+    /// exact value.
+    pub fn estimated_frame_count(&self) -> Option<FrameCount> {
+        if let Some(count) = self.frames() {
+            return Some(FrameCount::new(count, FrameCountMethod::Container));
+        }
+
+        let duration = self.duration()?;
+        let frame_rate = self.avg_frame_rate().or_else(|| self.r_frame_rate())?;
+
+        // rescale the duration into frame periods
+        let count = duration
+            .with_time_base_rounded(
+                TimeBase::new(frame_rate.den(), frame_rate.num()),
+                Rounding::NearInf,
+            )
+            .timestamp();
+
+        if count > 0 {
+            Some(FrameCount::new(count as u64, FrameCountMethod::Duration))
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of samples that should be skipped at the beginning of
+    /// the stream (i.e. encoder delay/priming). The value is taken from the
+    /// codec parameters or the iTunSMPB tag. Some formats (e.g. MP3 with
+    /// the LAME header or MP4 with edit lists) signal it in the first packet
+    /// of the stream only, so the value is available after the first packet
+    /// has been taken from the demuxer (see `DemuxerWithStreamInfo::take()`).
+    /// Zero is returned for non-audio streams and for streams without this
+    /// information.
+    ///
+    /// # Note
+    /// Decoders skip these samples automatically, so this is useful mainly
+    /// for computing the exact stream duration.
+    pub fn skip_samples(&self) -> u64 {
+        self.skip_samples
+    }
+
+    /// Get the number of padding samples at the end of the stream (i.e.
+    /// samples added by the encoder to fill the last frame). If the padding
+    /// is not stored in the codec parameters or the iTunSMPB tag, it is
+    /// counted from the packets read since the last seek (e.g. for MP3 with
+    /// the LAME header, the value is complete once the last packet has been
+    /// taken). Zero is returned for non-audio streams and for streams
+    /// without this information.
+    pub fn padding_samples(&self) -> u64 {
+        self.padding_samples
+    }
+
+    /// Get format-specific stream ID (e.g. PID for MPEG-TS or track ID for
+    /// MP4). Unlike the stream index, the ID is stored in the container.
+    pub fn id(&self) -> i32 {
+        unsafe { ffw_stream_get_id(self.ptr) as _ }
+    }
+
+    /// Get stream disposition.
+    pub fn disposition(&self) -> Disposition {
+        unsafe { Disposition(ffw_stream_get_disposition(self.ptr)) }
+    }
+
+    /// Get stream language (i.e. the "language" metadata entry, usually an
+    /// ISO 639-2 code like "eng").
+    pub fn language(&self) -> Option<&str> {
+        self.get_metadata("language")
+    }
+
+    /// Get PID of the MPEG-TS packets carrying PCR of the program this
+    /// stream belongs to. `None` is returned for other formats.
+    pub fn pcr_pid(&self) -> Option<u16> {
+        self.pcr_pid
+    }
+
+    /// Get the real base frame rate of the stream (i.e. the lowest frame
+    /// rate that can represent all timestamps in the stream accurately). This
+    /// is only a guess made by the demuxer and it is not the average frame
+    /// rate. `None` is returned if the value is unknown.
+    pub fn r_frame_rate(&self) -> Option<Rational> {
+        let mut num = 0;
+        let mut den = 0;
+
+        unsafe { ffw_stream_get_r_frame_rate(self.ptr, &mut num, &mut den) };
+
+        Rational::from_raw(num, den)
+    }
+
+    /// Get the average frame rate of the stream. This is the value that
+    /// should be used for duration calculations with variable frame rate
+    /// content. `None` is returned if the value is unknown.
+    pub fn avg_frame_rate(&self) -> Option<Rational> {
+        let mut num = 0;
+        let mut den = 0;
+
+        unsafe { ffw_stream_get_avg_frame_rate(self.ptr, &mut num, &mut den) };
+
+        Rational::from_raw(num, den)
+    }
+
+    /// Get the best effort frame rate of the stream. The frame rate is
+    /// guessed by FFmpeg from the real base frame rate, the average frame
+    /// rate and the codec frame rate. If FFmpeg is not able to make a guess,
+    /// the average frame rate and the real base frame rate are used (in this
+    /// order). `None` is returned if none of the values is known.
+    pub fn best_effort_frame_rate(&self) -> Option<Rational> {
+        let mut num = 0;
+        let mut den = 0;
+
+        unsafe { ffw_stream_guess_frame_rate(self.ptr, &mut num, &mut den) };
+
+        Rational::from_raw(num, den)
+            .or_else(|| self.avg_frame_rate())
+            .or_else(|| self.r_frame_rate())
+    }
+
+    /// Get media type of the stream. This is cheaper than getting the codec
+    /// parameters as nothing needs to be copied.
+    pub fn media_type(&self) -> MediaType {
+        unsafe { MediaType::from_raw(ffw_stream_get_media_type(self.ptr)) }
+    }
+
     /// Get codec parameters.
     pub fn codec_parameters(&self) -> CodecParameters {
         unsafe {
@@ -90,19 +479,391 @@ impl Stream {
         }
     }
 
-    /// Set stream metadata.
-    pub fn set_metadata<V>(&mut self, key: &str, value: V)
+    /// Get replay gain info of the stream. The info is taken from the
+    /// ReplayGain tags (exported by FFmpeg as stream side data) or from the
+    /// Opus R128_TRACK_GAIN/R128_ALBUM_GAIN tags. `None` is returned if the
+    /// stream does not contain any of them.
+    pub fn replay_gain(&self) -> Option<ReplayGain> {
+        let mut track_gain = 0;
+        let mut track_peak = 0;
+        let mut album_gain = 0;
+        let mut album_peak = 0;
+
+        let ret = unsafe {
+            ffw_stream_get_replay_gain(
+                self.ptr,
+                &mut track_gain,
+                &mut track_peak,
+                &mut album_gain,
+                &mut album_peak,
+            )
+        };
+
+        if ret != 0 {
+            return Some(ReplayGain::from_raw(
+                track_gain, track_peak, album_gain, album_peak,
+            ));
+        }
+
+        ReplayGain::from_r128_tags(
+            self.get_metadata("R128_TRACK_GAIN"),
+            self.get_metadata("R128_ALBUM_GAIN"),
+        )
+    }
+
+    /// Get HDR metadata of the stream (i.e. color properties from the codec
+    /// parameters and static HDR metadata exported by the demuxer as stream
+    /// side data).
+    pub fn hdr_metadata(&self) -> HdrMetadata {
+        let mut primaries = 0;
+        let mut transfer = 0;
+        let mut space = 0;
+
+        unsafe {
+            ffw_stream_get_color_properties(self.ptr, &mut primaries, &mut transfer, &mut space);
+        }
+
+        let mastering_display = unsafe {
+            let ptr = ffw_stream_get_mastering_display_metadata(self.ptr);
+
+            if ptr.is_null() {
+                None
+            } else {
+                Some(MasteringDisplayMetadata::from_raw_ptr(ptr))
+            }
+        };
+
+        let mut max_cll = 0;
+        let mut max_fall = 0;
+
+        let ret =
+            unsafe { ffw_stream_get_content_light_level(self.ptr, &mut max_cll, &mut max_fall) };
+
+        let content_light_level = if ret != 0 {
+            Some(ContentLightLevel::new(max_cll, max_fall))
+        } else {
+            None
+        };
+
+        HdrMetadata::from_raw_color_properties(primaries, transfer, space)
+            .with_mastering_display(mastering_display)
+            .with_content_light_level(content_light_level)
+    }
+
+    /// Set HDR metadata of the stream. This is useful for muxer streams, the
+    /// metadata are written by muxers supporting it (e.g. Matroska). Note
+    /// that the color properties become part of the stream codec parameters
+    /// and that static HDR metadata missing in the given metadata are not
+    /// removed from the stream.
+    pub fn set_hdr_metadata(&mut self, metadata: &HdrMetadata) {
+        let (primaries, transfer, space) = metadata.raw_color_properties();
+
+        unsafe {
+            ffw_stream_set_color_properties(self.ptr, primaries, transfer, space);
+
+            if let Some(mastering_display) = metadata.mastering_display() {
+                let ptr = ffw_stream_new_mastering_display_metadata(self.ptr);
+
+                if ptr.is_null() {
+                    panic!("unable to allocate mastering display metadata");
+                }
+
+                mastering_display.write_raw(ptr);
+            }
+
+            if let Some(level) = metadata.content_light_level() {
+                let ret =
+                    ffw_stream_set_content_light_level(self.ptr, level.max_cll(), level.max_fall());
+
+                if ret < 0 {
+                    panic!("unable to allocate content light level");
+                }
+            }
+        }
+    }
+
+    /// Get Dolby Vision configuration of the stream (if any). The
+    /// configuration should be copied to the output stream when remuxing
+    /// Dolby Vision content, the RPUs are part of the packets. Note that
+    /// FFmpeg 4.4 or newer is needed, `None` is always returned otherwise.
+    pub fn dolby_vision_config(&self) -> Option<DolbyVisionConfig> {
+        let mut values = [0; 8];
+
+        let ret = unsafe { ffw_stream_get_dovi_config(self.ptr, values.as_mut_ptr()) };
+
+        if ret == 0 {
+            None
+        } else {
+            Some(DolbyVisionConfig::from_raw(&values))
+        }
+    }
+
+    /// Set Dolby Vision configuration of the stream. This is useful for
+    /// muxer streams, the configuration is written by muxers supporting it
+    /// (e.g. MP4 or Matroska). The method fails if the FFmpeg version does
+    /// not support Dolby Vision (i.e. it is older than 4.4).
+    pub fn set_dolby_vision_config(&mut self, config: DolbyVisionConfig) -> Result<(), Error> {
+        let values = config.to_raw();
+
+        let ret = unsafe { ffw_stream_set_dovi_config(self.ptr, values.as_ptr()) };
+
+        if ret < 0 {
+            Err(Error::from_raw_error_code(ret))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get encryption init info of the stream (e.g. content of the MP4
+    /// `pssh` boxes). The returned list is empty if the stream is not
+    /// encrypted.
+    pub fn encryption_init_info(&self) -> Vec<EncryptionInitInfo> {
+        unsafe {
+            let ptr = ffw_stream_get_encryption_init_info(self.ptr);
+
+            if ptr.is_null() {
+                Vec::new()
+            } else {
+                EncryptionInitInfo::from_raw_ptr(ptr)
+            }
+        }
+    }
+
+    /// Get file name and content of an attachment (e.g. a font embedded in a
+    /// Matroska file). `None` is returned if the stream is not an
+    /// attachment stream. The file name is empty if it is not known.
+    pub fn attachment(&self) -> Option<(&str, Vec<u8>)> {
+        let mut data = ptr::null();
+        let mut size = 0;
+
+        let ret = unsafe { ffw_stream_get_attachment(self.ptr, &mut data, &mut size) };
+
+        if ret == 0 {
+            return None;
+        }
+
+        let data = if data.is_null() || size <= 0 {
+            Vec::new()
+        } else {
+            unsafe { slice::from_raw_parts(data, size as usize).to_vec() }
+        };
+
+        let file_name = self.get_metadata("filename").unwrap_or("");
+
+        Some((file_name, data))
+    }
+
+    /// Get the container-level codec private data of the stream (i.e. the
+    /// Matroska `CodecPrivate` representation of the codec extradata, see
+    /// the `codec_private` module). `None` is returned if the stream does
+    /// not have any extradata.
+    pub fn codec_private(&self) -> Result<Option<Vec<u8>>, Error> {
+        let codec_parameters = self.codec_parameters();
+
+        let extradata = if let Some(params) = codec_parameters.as_audio_codec_parameters() {
+            params.extradata()
+        } else if let Some(params) = codec_parameters.as_video_codec_parameters() {
+            params.extradata()
+        } else {
+            None
+        };
+
+        match extradata {
+            Some(data) if !data.is_empty() => {
+                codec_private::to_codec_private(codec_parameters.codec_name(), data).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Set the container-level codec private data of the stream (i.e. the
+    /// Matroska `CodecPrivate` representation, see the `codec_private`
+    /// module). The data are converted into codec extradata. This is useful
+    /// for muxer streams when remuxing streams between containers using
+    /// different representations (e.g. FLAC from Matroska to MP4).
+    pub fn set_codec_private(&mut self, data: &[u8]) -> Result<(), Error> {
+        let codec_name = self.codec_parameters().codec_name();
+
+        let extradata = codec_private::from_codec_private(codec_name, data)?;
+
+        let ret =
+            unsafe { ffw_stream_set_extradata(self.ptr, extradata.as_ptr(), extradata.len() as _) };
+
+        if ret < 0 {
+            Err(Error::from_raw_error_code(ret))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get all stream metadata entries (e.g. "language" or "title").
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let mut res = Vec::new();
+
+        let mut entry = ptr::null();
+
+        loop {
+            let mut key = ptr::null();
+            let mut value = ptr::null();
+
+            unsafe {
+                entry = ffw_stream_next_metadata_entry(self.ptr, entry, &mut key, &mut value);
+
+                if entry.is_null() {
+                    return res;
+                }
+
+                let key = CStr::from_ptr(key).to_string_lossy();
+                let value = CStr::from_ptr(value).to_string_lossy();
+
+                res.push((key.into_owned(), value.into_owned()));
+            }
+        }
+    }
+
+    /// Get stream metadata value for a given key.
+    fn get_metadata(&self, key: &str) -> Option<&str> {
+        let key = CString::new(key).ok()?;
+
+        unsafe {
+            let value = ffw_stream_get_metadata(self.ptr, key.as_ptr());
+
+            if value.is_null() {
+                None
+            } else {
+                CStr::from_ptr(value).to_str().ok()
+            }
+        }
+    }
+
+    /// Set stream metadata. An error is returned if the key or the value
+    /// contains a NUL character.
+    pub fn set_metadata<V>(&mut self, key: &str, value: V) -> Result<(), Error>
     where
         V: ToString,
     {
-        let key = CString::new(key).expect("invalid metadata key");
-        let value = CString::new(value.to_string()).expect("invalid metadata value");
+        let key = CString::new(key).map_err(|_| Error::new("invalid metadata key"))?;
+        let value =
+            CString::new(value.to_string()).map_err(|_| Error::new("invalid metadata value"))?;
 
         let ret = unsafe { ffw_stream_set_metadata(self.ptr, key.as_ptr(), value.as_ptr()) };
 
         if ret < 0 {
-            panic!("unable to allocate metadata");
+            return Err(Error::from_raw_error_code(ret));
+        }
+
+        Ok(())
+    }
+
+    /// Get an owned snapshot of the stream information.
+    pub fn info(&self) -> StreamInfo {
+        StreamInfo {
+            id: self.id(),
+            time_base: self.time_base,
+            start_time: self.start_time(),
+            duration: self.duration(),
+            frames: self.frames(),
+            r_frame_rate: self.r_frame_rate(),
+            avg_frame_rate: self.avg_frame_rate(),
+            codec: self.codec_parameters().summary(),
+            metadata: self.metadata(),
+        }
+    }
+}
+
+/// Owned snapshot of stream information.
+///
+/// The snapshot does not reference the demuxer, so it can be stored (or
+/// serialized if the `serde` feature is enabled) and inspected later.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StreamInfo {
+    id: i32,
+    time_base: TimeBase,
+    #[cfg_attr(feature = "serde", serde(default))]
+    start_time: Option<Timestamp>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    duration: Option<Timestamp>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    frames: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    r_frame_rate: Option<Rational>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    avg_frame_rate: Option<Rational>,
+    codec: CodecParametersSummary,
+    #[cfg_attr(feature = "serde", serde(default))]
+    metadata: Vec<(String, String)>,
+}
+
+impl StreamInfo {
+    /// Get format-specific stream ID.
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// Get stream time base.
+    pub fn time_base(&self) -> TimeBase {
+        self.time_base
+    }
+
+    /// Get the pts of the first frame of the stream (if known).
+    pub fn start_time(&self) -> Option<Timestamp> {
+        self.start_time
+    }
+
+    /// Get the duration of the stream (if known).
+    pub fn duration(&self) -> Option<Timestamp> {
+        self.duration
+    }
+
+    /// Get the number of frames in the stream (if known).
+    pub fn frames(&self) -> Option<u64> {
+        self.frames
+    }
+
+    /// Get the real base frame rate of the stream (if known).
+    pub fn r_frame_rate(&self) -> Option<Rational> {
+        self.r_frame_rate
+    }
+
+    /// Get the average frame rate of the stream (if known).
+    pub fn avg_frame_rate(&self) -> Option<Rational> {
+        self.avg_frame_rate
+    }
+
+    /// Get media type of the stream.
+    pub fn media_type(&self) -> MediaType {
+        self.codec.media_type()
+    }
+
+    /// Get summary of the codec parameters.
+    pub fn codec(&self) -> &CodecParametersSummary {
+        &self.codec
+    }
+
+    /// Get stream metadata entries.
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+}
+
+impl Display for Stream {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.codec_parameters())?;
+
+        if let Some(frame_rate) = self.avg_frame_rate() {
+            let fps = frame_rate.num() as f64 / frame_rate.den() as f64;
+
+            if fps.fract() == 0.0 {
+                write!(f, ", {} fps", fps)?;
+            } else {
+                write!(f, ", {:.2} fps", fps)?;
+            }
         }
+
+        let tb = self.time_base;
+
+        write!(f, ", {}/{} tb", tb.num(), tb.den())
     }
 }
 
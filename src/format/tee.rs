@@ -0,0 +1,406 @@
+//! Packet fan-out.
+//!
+//! The tee duplicates packets to multiple sinks (e.g. a file muxer, an RTMP
+//! muxer and an HLS muxer). Every sink runs in its own thread and has its
+//! own bounded queue, so a slow or failing sink does not affect the others.
+//! Errors are reported using per-output callbacks and a failed output is
+//! simply detached from the tee.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{format::muxer::Muxer, packet::Packet, Error};
+
+/// Common trait for packet sinks.
+pub trait PacketSink: Send {
+    /// Push a given packet into the sink.
+    fn push(&mut self, packet: Packet) -> Result<(), Error>;
+
+    /// Flush and close the sink.
+    fn close(self: Box<Self>) -> Result<(), Error>;
+}
+
+impl<T> PacketSink for Muxer<T>
+where
+    T: Send,
+{
+    fn push(&mut self, packet: Packet) -> Result<(), Error> {
+        Muxer::push(self, packet)
+    }
+
+    fn close(mut self: Box<Self>) -> Result<(), Error> {
+        self.flush()?;
+
+        Muxer::close(*self)?;
+
+        Ok(())
+    }
+}
+
+/// Tee output.
+pub struct TeeOutput {
+    sink: Box<dyn PacketSink>,
+    capacity: usize,
+    blocking: bool,
+    on_error: Option<Box<dyn FnMut(Error) + Send>>,
+}
+
+impl TeeOutput {
+    /// Create a new tee output for a given sink.
+    pub fn new<S>(sink: S) -> Self
+    where
+        S: PacketSink + 'static,
+    {
+        Self {
+            sink: Box::new(sink),
+            capacity: 256,
+            blocking: false,
+            on_error: None,
+        }
+    }
+
+    /// Set the queue capacity (in packets). The default is 256.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Block the tee if the queue of this output is full. By default,
+    /// packets are dropped instead, so a slow output does not block the
+    /// other outputs. Once a packet of a stream has been dropped, all
+    /// packets of the same stream are dropped until its next key packet.
+    pub fn blocking(mut self, blocking: bool) -> Self {
+        self.blocking = blocking;
+        self
+    }
+
+    /// Set a callback that will be called if the sink fails. The output is
+    /// detached from the tee after the error.
+    pub fn on_error<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(Error) + Send + 'static,
+    {
+        self.on_error = Some(Box::new(callback));
+        self
+    }
+}
+
+/// Packet fan-out.
+pub struct Tee {
+    outputs: Vec<OutputHandle>,
+}
+
+impl Tee {
+    /// Create a new tee with given outputs.
+    pub fn new<I>(outputs: I) -> Self
+    where
+        I: IntoIterator<Item = TeeOutput>,
+    {
+        let outputs = outputs.into_iter().map(OutputHandle::spawn).collect();
+
+        Self { outputs }
+    }
+
+    /// Push a given packet into all outputs. An error is returned only if
+    /// all outputs have failed.
+    pub fn push(&mut self, packet: &Packet) -> Result<(), Error> {
+        let mut alive = false;
+
+        for output in &mut self.outputs {
+            alive |= output.push(packet);
+        }
+
+        if alive || self.outputs.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new("all tee outputs have failed"))
+        }
+    }
+
+    /// Get the number of outputs.
+    pub fn outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Check if a given output has failed.
+    pub fn is_failed(&self, output: usize) -> bool {
+        self.outputs[output].failed.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of packets dropped by a given output.
+    pub fn dropped(&self, output: usize) -> u64 {
+        self.outputs[output].dropped
+    }
+
+    /// Flush and close all outputs. The method waits until all queued
+    /// packets are written.
+    pub fn close(self) {
+        let handles = self
+            .outputs
+            .into_iter()
+            .map(|output| {
+                // closing the queue closes the sink
+                drop(output.tx);
+
+                output.thread
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            // the sink thread never panics unless the sink or the error
+            // callback does
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Tee output running in a background thread.
+struct OutputHandle {
+    tx: SyncSender<Packet>,
+    thread: JoinHandle<()>,
+    failed: Arc<AtomicBool>,
+    blocking: bool,
+    skip: Vec<bool>,
+    dropped: u64,
+}
+
+impl OutputHandle {
+    /// Spawn a thread for a given output.
+    fn spawn(output: TeeOutput) -> Self {
+        let (tx, rx) = mpsc::sync_channel(output.capacity);
+
+        let failed = Arc::new(AtomicBool::new(false));
+
+        let blocking = output.blocking;
+
+        let thread = {
+            let failed = failed.clone();
+
+            thread::spawn(move || {
+                let mut on_error = output.on_error;
+
+                if let Err(err) = run_output(output.sink, rx) {
+                    failed.store(true, Ordering::Relaxed);
+
+                    if let Some(cb) = on_error.as_mut() {
+                        cb(err);
+                    }
+                }
+            })
+        };
+
+        Self {
+            tx,
+            thread,
+            failed,
+            blocking,
+            skip: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Push a given packet into the queue. The method returns false if the
+    /// output has failed.
+    fn push(&mut self, packet: &Packet) -> bool {
+        if self.failed.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        let stream_index = packet.stream_index();
+
+        if self.skip.len() <= stream_index {
+            self.skip.resize(stream_index + 1, false);
+        }
+
+        // wait for a key packet of the same stream after dropping something
+        if self.skip[stream_index] && !packet.is_key() {
+            self.dropped += 1;
+
+            return true;
+        }
+
+        self.skip[stream_index] = false;
+
+        let res = if self.blocking {
+            self.tx.send(packet.clone()).map_err(|_| ())
+        } else {
+            match self.tx.try_send(packet.clone()) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    self.skip[stream_index] = true;
+                    self.dropped += 1;
+
+                    Ok(())
+                }
+                Err(TrySendError::Disconnected(_)) => Err(()),
+            }
+        };
+
+        // the receiver is gone only if the output has failed
+        res.is_ok()
+    }
+}
+
+/// Write all packets from a given queue into a given sink.
+fn run_output(mut sink: Box<dyn PacketSink>, rx: Receiver<Packet>) -> Result<(), Error> {
+    for packet in rx {
+        sink.push(packet)?;
+    }
+
+    sink.close()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    };
+
+    use super::{PacketSink, Tee, TeeOutput};
+
+    use crate::{
+        packet::{Packet, PacketMut},
+        Error,
+    };
+
+    struct TestSink {
+        tx: Sender<usize>,
+        fail_at: Option<usize>,
+    }
+
+    impl PacketSink for TestSink {
+        fn push(&mut self, packet: Packet) -> Result<(), Error> {
+            if Some(packet.data().len()) == self.fail_at {
+                return Err(Error::new("sink failure"));
+            }
+
+            self.tx.send(packet.data().len()).unwrap();
+
+            Ok(())
+        }
+
+        fn close(self: Box<Self>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tee() {
+        let (tx1, rx1) = mpsc::channel();
+        let (tx2, rx2) = mpsc::channel();
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+
+        let output1 = TeeOutput::new(TestSink {
+            tx: tx1,
+            fail_at: None,
+        })
+        .blocking(true);
+
+        let output2 = {
+            let errors = errors.clone();
+
+            TeeOutput::new(TestSink {
+                tx: tx2,
+                fail_at: Some(3),
+            })
+            .blocking(true)
+            .on_error(move |err| errors.lock().unwrap().push(err.to_string()))
+        };
+
+        let mut tee = Tee::new(vec![output1, output2]);
+
+        for size in 1..6 {
+            tee.push(&PacketMut::new(size).freeze()).unwrap();
+        }
+
+        tee.close();
+
+        assert_eq!(rx1.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(rx2.iter().collect::<Vec<_>>(), vec![1, 2]);
+
+        assert_eq!(errors.lock().unwrap().as_slice(), ["sink failure"]);
+    }
+
+    /// Sink blocking on the first packet until it is released.
+    struct BlockingSink {
+        tx: Sender<usize>,
+        release: Option<Receiver<()>>,
+    }
+
+    impl PacketSink for BlockingSink {
+        fn push(&mut self, packet: Packet) -> Result<(), Error> {
+            self.tx.send(packet.data().len()).unwrap();
+
+            if let Some(release) = self.release.take() {
+                release.recv().unwrap();
+            }
+
+            Ok(())
+        }
+
+        fn close(self: Box<Self>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_dropping() {
+        let (tx, rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel();
+
+        let output = TeeOutput::new(BlockingSink {
+            tx,
+            release: Some(release_rx),
+        })
+        .capacity(1);
+
+        let mut tee = Tee::new(vec![output]);
+
+        // stream 0 is video, stream 1 is audio (i.e. all packets are key)
+        let mut push = |size: usize, stream_index: usize, key: bool| {
+            let packet = PacketMut::new(size)
+                .with_stream_index(stream_index)
+                .with_key_flag(key)
+                .freeze();
+
+            tee.push(&packet).unwrap();
+        };
+
+        push(1, 0, true);
+
+        // wait until the sink blocks
+        assert_eq!(rx.recv().unwrap(), 1);
+
+        push(2, 0, false);
+
+        // the queue is full now
+        push(3, 0, false);
+        push(4, 1, true);
+
+        release_tx.send(()).unwrap();
+
+        assert_eq!(rx.recv().unwrap(), 2);
+
+        // audio resumes immediately, but video needs a key packet
+        push(5, 1, true);
+
+        assert_eq!(rx.recv().unwrap(), 5);
+
+        push(6, 0, false);
+        push(7, 0, true);
+
+        assert_eq!(tee.dropped(0), 3);
+
+        tee.close();
+
+        assert_eq!(rx.iter().collect::<Vec<_>>(), vec![7]);
+    }
+}
@@ -0,0 +1,284 @@
+//! UDP (including multicast) input and output.
+//!
+//! UDP streams are opened using the FFmpeg `udp` protocol. Unlike the
+//! generic `UrlStreamBuilder`, the builder here rejects protocol options that
+//! are not recognized by the `udp` protocol, because a misspelled buffer
+//! option would otherwise silently result in packet loss. The resulting
+//! stream can be passed to a demuxer or a muxer using
+//! `IO::from_read_stream()` or `IO::from_write_stream()` respectively.
+//!
+//! Note that the FFmpeg `udp` protocol does not expose the fill level of its
+//! receive buffer or the number of buffer overruns, so only the effective
+//! buffer sizes and the number of received bytes are available.
+
+use std::{
+    io::{self, Read, Write},
+    net::IpAddr,
+    time::Duration,
+};
+
+use crate::{
+    format::io::{UrlStream, UrlStreamBuilder},
+    Error,
+};
+
+/// Size of a single unit of the `fifo_size` option.
+const FIFO_UNIT_SIZE: usize = 188;
+
+/// What to do when the receive buffer overflows.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OverrunPolicy {
+    /// Fail the read operation (the FFmpeg default).
+    Fail,
+    /// Drop the incoming data and continue.
+    Drop,
+}
+
+/// Builder for UDP streams.
+pub struct UdpStreamBuilder {
+    inner: UrlStreamBuilder,
+}
+
+impl UdpStreamBuilder {
+    /// Create a new builder.
+    pub fn new() -> Self {
+        Self {
+            inner: UrlStream::builder().strict_options(true),
+        }
+    }
+
+    /// Set local address used for receiving or sending multicast data
+    /// (i.e. it selects the network interface).
+    pub fn local_address(self, addr: IpAddr) -> Self {
+        self.set_option("localaddr", addr)
+    }
+
+    /// Set local port to bind to.
+    pub fn local_port(self, port: u16) -> Self {
+        self.set_option("localport", port)
+    }
+
+    /// Allow other sockets to bind to the same address and port. This is
+    /// necessary for multiple receivers of a multicast group on the same
+    /// host. FFmpeg enables it by default for multicast addresses only.
+    pub fn reuse_address(self, reuse: bool) -> Self {
+        self.set_option("reuse", reuse as i32)
+    }
+
+    /// Set size of the socket buffer in bytes (i.e. `SO_RCVBUF` for input
+    /// and `SO_SNDBUF` for output). The default is 64 kB for input and
+    /// 32 kB for output. Note that the operating system may limit the
+    /// size.
+    pub fn buffer_size(self, bytes: usize) -> Self {
+        self.set_option("buffer_size", bytes)
+    }
+
+    /// Set size of the receive buffer in bytes. The buffer is filled by a
+    /// background thread, so that data are not lost when the consumer is
+    /// temporarily slow. The size will be rounded up to a multiple of 188
+    /// bytes. The default is roughly 5 MB. Zero disables the buffer.
+    pub fn fifo_size(self, bytes: usize) -> Self {
+        let units = bytes.div_ceil(FIFO_UNIT_SIZE);
+
+        self.set_option("fifo_size", units)
+    }
+
+    /// Set what to do when the receive buffer overflows. The default is
+    /// `OverrunPolicy::Fail`.
+    pub fn overrun_policy(self, policy: OverrunPolicy) -> Self {
+        let nonfatal = match policy {
+            OverrunPolicy::Fail => 0,
+            OverrunPolicy::Drop => 1,
+        };
+
+        self.set_option("overrun_nonfatal", nonfatal)
+    }
+
+    /// Set maximum size of UDP packets. The default is 1472 bytes.
+    pub fn packet_size(self, bytes: usize) -> Self {
+        self.set_option("pkt_size", bytes)
+    }
+
+    /// Set timeout of read operations. Reads waiting for data longer than
+    /// the timeout fail with an error of the `io::ErrorKind::TimedOut`
+    /// kind.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.set_option("timeout", timeout.as_micros())
+    }
+
+    /// Set time to live of outgoing multicast packets. The default is 16.
+    pub fn ttl(self, ttl: u8) -> Self {
+        self.set_option("ttl", ttl)
+    }
+
+    /// Receive multicast data only from given source addresses.
+    pub fn sources(self, sources: &[IpAddr]) -> Self {
+        self.set_option("sources", join_addresses(sources))
+    }
+
+    /// Ignore multicast data from given source addresses.
+    pub fn block(self, sources: &[IpAddr]) -> Self {
+        self.set_option("block", join_addresses(sources))
+    }
+
+    /// Set any other FFmpeg `udp` protocol option.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.inner = self.inner.set_option(name, value);
+        self
+    }
+
+    /// Open a given UDP URL (e.g. `udp://239.0.0.1:1234`) for reading.
+    pub fn open_read(self, url: &str) -> Result<UdpStream, Error> {
+        let res = UdpStream {
+            inner: self.inner.open_read(url)?,
+            bytes_received: 0,
+        };
+
+        Ok(res)
+    }
+
+    /// Open a given UDP URL (e.g. `udp://239.0.0.1:1234`) for writing.
+    pub fn open_write(self, url: &str) -> Result<UdpStream, Error> {
+        let res = UdpStream {
+            inner: self.inner.open_write(url)?,
+            bytes_received: 0,
+        };
+
+        Ok(res)
+    }
+}
+
+impl Default for UdpStreamBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Join given addresses into a comma-separated list.
+fn join_addresses(addresses: &[IpAddr]) -> String {
+    addresses
+        .iter()
+        .map(|addr| addr.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Receive buffer statistics.
+#[derive(Debug, Copy, Clone)]
+pub struct UdpStats {
+    socket_buffer_size: usize,
+    fifo_size: usize,
+    bytes_received: u64,
+}
+
+impl UdpStats {
+    /// Get the requested socket buffer size in bytes.
+    pub fn socket_buffer_size(&self) -> usize {
+        self.socket_buffer_size
+    }
+
+    /// Get size of the receive buffer in bytes (zero if the buffer is not
+    /// used).
+    pub fn fifo_size(&self) -> usize {
+        self.fifo_size
+    }
+
+    /// Get the number of bytes read from the stream.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+}
+
+/// UDP stream.
+pub struct UdpStream {
+    inner: UrlStream,
+    bytes_received: u64,
+}
+
+impl UdpStream {
+    /// Get a builder for UDP streams.
+    pub fn builder() -> UdpStreamBuilder {
+        UdpStreamBuilder::new()
+    }
+
+    /// Get receive buffer statistics.
+    pub fn stats(&mut self) -> UdpStats {
+        let socket_buffer_size = self.inner.get_option_int("buffer_size").unwrap_or(0);
+
+        // NOTE: the protocol converts the option value into bytes when
+        // opening the stream
+        let fifo_size = self.inner.get_option_int("fifo_size").unwrap_or(0);
+
+        UdpStats {
+            socket_buffer_size: socket_buffer_size.max(0) as usize,
+            fifo_size: fifo_size.max(0) as usize,
+            bytes_received: self.bytes_received,
+        }
+    }
+
+    /// Get the underlying URL stream.
+    pub fn into_inner(self) -> UrlStream {
+        self.inner
+    }
+}
+
+impl Read for UdpStream {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, io::Error> {
+        let len = self.inner.read(buffer)?;
+
+        self.bytes_received += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl Write for UdpStream {
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, io::Error> {
+        self.inner.write(buffer)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Read};
+
+    use super::UdpStream;
+
+    #[test]
+    fn test_strict_options() {
+        // NOTE: the udp protocol does not need to be enabled in the FFmpeg
+        // build, so a file is used instead
+        let path = std::env::temp_dir().join(format!("ac-ffmpeg-udp-{}", std::process::id()));
+
+        fs::write(&path, [1u8; 188]).unwrap();
+
+        let url = format!("file:{}", path.display());
+
+        let mut stream = UdpStream::builder()
+            .set_option("blocksize", 188)
+            .open_read(&url)
+            .unwrap();
+
+        let mut buffer = Vec::new();
+
+        stream.read_to_end(&mut buffer).unwrap();
+
+        assert_eq!(buffer.len(), 188);
+        assert_eq!(stream.stats().bytes_received(), 188);
+
+        let res = UdpStream::builder().fifo_size(1000).open_read(&url);
+
+        fs::remove_file(&path).unwrap();
+
+        let err = res.err().unwrap();
+
+        assert_eq!(err.to_string(), "unknown protocol option: fifo_size");
+    }
+}
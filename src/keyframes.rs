@@ -0,0 +1,296 @@
+//! Fast keyframe scanning.
+//!
+//! The keyframe scanner walks a video stream keyframe by keyframe and
+//! decodes only the keyframes (the decoder is configured to skip all other
+//! frames, see `VideoDecoderBuilder::skip_frame()`). After emitting a
+//! keyframe, the scanner seeks directly to the next keyframe (optionally at
+//! least a given interval later), so the frames between keyframes do not
+//! even need to be read in most cases. This is the usual way of building
+//! filmstrip previews of a file.
+//!
+//! If the input is not seekable, the scanner falls back to reading the
+//! stream sequentially and skipping all non-key packets.
+
+use std::time::Duration;
+
+use crate::{
+    codec::{
+        video::{Discard, VideoDecoder, VideoFrame},
+        Decoder,
+    },
+    format::demuxer::{DemuxerWithStreamInfo, SeekTarget},
+    packet::Packet,
+    time::Timestamp,
+    Error,
+};
+
+/// Builder for the keyframe scanner.
+pub struct KeyframeScannerBuilder<T> {
+    demuxer: DemuxerWithStreamInfo<T>,
+    stream_index: usize,
+    interval: Duration,
+}
+
+impl<T> KeyframeScannerBuilder<T> {
+    /// Create a new builder.
+    fn new(demuxer: DemuxerWithStreamInfo<T>, stream_index: usize) -> Self {
+        Self {
+            demuxer,
+            stream_index,
+            interval: Duration::from_secs(0),
+        }
+    }
+
+    /// Set the minimum interval between two emitted keyframes. Keyframes
+    /// closer to the previously emitted keyframe are skipped. The default
+    /// is zero (i.e. all keyframes are emitted).
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Build the keyframe scanner.
+    pub fn build(self) -> Result<KeyframeScanner<T>, Error> {
+        let stream = self
+            .demuxer
+            .streams()
+            .get(self.stream_index)
+            .ok_or_else(|| Error::new("invalid stream index"))?;
+
+        let decoder = VideoDecoder::from_stream(stream)?
+            .skip_frame(Discard::NonKey)
+            .build()?;
+
+        let res = KeyframeScanner {
+            demuxer: self.demuxer,
+            decoder,
+            stream_index: self.stream_index,
+            interval: (self.interval.as_micros() as i64).max(1),
+            last: None,
+            seekable: true,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Keyframe scanner.
+///
+/// The scanner is an iterator over decoded keyframes of a given video
+/// stream. Every keyframe is emitted together with the presentation
+/// timestamp of its packet.
+pub struct KeyframeScanner<T> {
+    demuxer: DemuxerWithStreamInfo<T>,
+    decoder: VideoDecoder,
+    stream_index: usize,
+    interval: i64,
+    last: Option<i64>,
+    seekable: bool,
+}
+
+impl<T> KeyframeScanner<T> {
+    /// Get a builder for a keyframe scanner of a given video stream.
+    pub fn builder(
+        demuxer: DemuxerWithStreamInfo<T>,
+        stream_index: usize,
+    ) -> KeyframeScannerBuilder<T> {
+        KeyframeScannerBuilder::new(demuxer, stream_index)
+    }
+
+    /// Get the next keyframe or `None` if the end of the stream has been
+    /// reached.
+    pub fn take(&mut self) -> Result<Option<(Timestamp, VideoFrame)>, Error> {
+        loop {
+            if let Some(last) = self.last {
+                if self.seekable {
+                    let target = Timestamp::from_micros(last + self.interval);
+
+                    // fall back to sequential reading if the input is not
+                    // seekable
+                    if self
+                        .demuxer
+                        .seek_to_timestamp(target, SeekTarget::From)
+                        .is_err()
+                    {
+                        self.seekable = false;
+                    }
+                }
+            }
+
+            let packet = match self.next_keyframe_packet()? {
+                Some(packet) => packet,
+                None => return Ok(None),
+            };
+
+            let timestamp = if packet.pts().is_null() {
+                packet.dts()
+            } else {
+                packet.pts()
+            };
+
+            match timestamp.as_micros() {
+                Some(t) => self.last = Some(t),
+                None => self.seekable = false,
+            }
+
+            if let Some(frame) = self.decode(packet)? {
+                return Ok(Some((timestamp, frame)));
+            }
+        }
+    }
+
+    /// Get the underlying demuxer.
+    pub fn into_demuxer(self) -> DemuxerWithStreamInfo<T> {
+        self.demuxer
+    }
+
+    /// Read packets until the next keyframe packet of the stream that is at
+    /// least the minimum interval after the last emitted keyframe.
+    fn next_keyframe_packet(&mut self) -> Result<Option<Packet>, Error> {
+        while let Some(packet) = self.demuxer.take()? {
+            if packet.stream_index() != self.stream_index || !packet.is_key() {
+                continue;
+            }
+
+            let timestamp = if packet.pts().is_null() {
+                packet.dts()
+            } else {
+                packet.pts()
+            };
+
+            // the demuxer may seek before the target
+            if let (Some(last), Some(t)) = (self.last, timestamp.as_micros()) {
+                if t < last + self.interval {
+                    continue;
+                }
+            }
+
+            return Ok(Some(packet));
+        }
+
+        Ok(None)
+    }
+
+    /// Decode a given keyframe packet.
+    fn decode(&mut self, packet: Packet) -> Result<Option<VideoFrame>, Error> {
+        // every keyframe is decoded independently
+        self.decoder.reset();
+        self.decoder.push(packet)?;
+        self.decoder.flush()?;
+
+        let mut res = None;
+
+        while let Some(frame) = self.decoder.take()? {
+            res.get_or_insert(frame);
+        }
+
+        self.decoder.reset();
+
+        Ok(res)
+    }
+}
+
+impl<T> Iterator for KeyframeScanner<T> {
+    type Item = Result<(Timestamp, VideoFrame), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.take().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, str::FromStr, time::Duration};
+
+    use super::KeyframeScanner;
+
+    use crate::{
+        codec::{
+            video::{PixelFormat, VideoEncoder, VideoFrameMut},
+            Encoder,
+        },
+        format::{
+            demuxer::Demuxer,
+            io::IO,
+            muxer::{Muxer, OutputFormat},
+        },
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_keyframe_scanner() {
+        let pixel_format = PixelFormat::from_str("yuv420p").unwrap();
+        let time_base = TimeBase::new(1, 25);
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .time_base(time_base)
+            .set_option("g", 10)
+            .low_latency()
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder();
+
+        builder
+            .add_stream(&encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_write_stream(Vec::new()),
+                OutputFormat::find_by_name("mpegts").unwrap(),
+            )
+            .unwrap();
+
+        for i in 0..100 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 48)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(0)).unwrap();
+            }
+        }
+
+        let data = muxer.close().unwrap().into_stream();
+
+        let scan = |interval| {
+            let demuxer = Demuxer::builder()
+                .build(IO::from_seekable_read_stream(Cursor::new(data.clone())))
+                .unwrap()
+                .find_stream_info(None)
+                .map_err(|(_, err)| err)
+                .unwrap();
+
+            let start = demuxer.start_time().unwrap();
+
+            KeyframeScanner::builder(demuxer, 0)
+                .interval(interval)
+                .build()
+                .unwrap()
+                .map(|res| {
+                    let (timestamp, frame) = res.unwrap();
+
+                    assert_eq!(frame.width(), 64);
+
+                    let offset = timestamp.as_micros().unwrap() - start.as_micros().unwrap();
+
+                    offset / 40_000
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            scan(Duration::from_secs(0)),
+            [0, 10, 20, 30, 40, 50, 60, 70, 80, 90]
+        );
+        assert_eq!(scan(Duration::from_millis(1000)), [0, 30, 60, 90]);
+    }
+}
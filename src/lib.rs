@@ -1,17 +1,39 @@
 //! Safe Rust interface for FFmpeg libraries. See the `examples` folder for
 //! code examples.
 
+pub mod analysis;
+pub mod animated;
+pub mod channel;
 pub mod codec;
+pub mod encryption;
 pub mod format;
+pub mod keyframes;
+pub mod math;
+pub mod options;
+pub mod pacing;
 pub mod packet;
+pub mod pipeline;
+#[cfg(feature = "playback")]
+pub mod playback;
+pub mod progress;
+pub mod rate;
+pub mod recorder;
+#[cfg(feature = "serde")]
+pub mod report;
+pub mod reverse;
+pub mod sync;
+#[cfg(feature = "ndarray")]
+pub mod tensor;
 pub mod time;
+pub mod timeshift;
+pub mod transcoder;
 
 use std::{
     ffi::CStr,
     fmt::{self, Display, Formatter},
     io,
     os::raw::{c_char, c_int},
-    sync::RwLock,
+    sync::{Arc, RwLock},
 };
 
 use lazy_static::lazy_static;
@@ -49,9 +71,12 @@ extern "C" fn log_callback(level: c_int, message: *const c_char) {
     }
 }
 
+/// Type of the log closure.
+type LogCallbackFn = dyn Fn(i32, &str) + Send + Sync;
+
 /// Wrapper around a log closure.
 struct LogCallback {
-    callback: Option<Box<dyn Fn(i32, &str) + Send + Sync>>,
+    callback: Option<Box<LogCallbackFn>>,
 }
 
 impl LogCallback {
@@ -100,6 +125,7 @@ enum ErrorVariant {
 #[derive(Debug, Clone)]
 pub struct Error {
     variant: ErrorVariant,
+    source: Option<Arc<io::Error>>,
 }
 
 impl Error {
@@ -110,6 +136,7 @@ impl Error {
     {
         Self {
             variant: ErrorVariant::Other(msg.to_string()),
+            source: None,
         }
     }
 
@@ -125,12 +152,42 @@ impl Error {
         }
     }
 
+    /// Get the kind of this error. Errors that do not correspond to any
+    /// standard IO error kind are reported as `io::ErrorKind::Other`. For
+    /// example, read/write timeouts are reported as
+    /// `io::ErrorKind::TimedOut`. The kind of the IO error that caused this
+    /// error (if any) takes precedence.
+    pub fn kind(&self) -> io::ErrorKind {
+        if let Some(source) = self.source.as_ref() {
+            return source.kind();
+        }
+
+        self.to_io_error()
+            .map(|err| err.kind())
+            .unwrap_or(io::ErrorKind::Other)
+    }
+
+    /// Get the IO error that caused this error (if any). Errors returned by
+    /// custom streams passed to muxers and demuxers are propagated this
+    /// way, so that e.g. network failures can be distinguished from corrupt
+    /// data. The error is also available via `std::error::Error::source()`.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        self.source.as_deref()
+    }
+
     /// Create a new FFmpeg error from a given FFmpeg error code.
     fn from_raw_error_code(code: c_int) -> Self {
         Self {
             variant: ErrorVariant::FFmpeg(code),
+            source: None,
         }
     }
+
+    /// Attach a given IO error as the source of this error.
+    fn with_source(mut self, source: io::Error) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
 }
 
 impl Display for Error {
@@ -157,4 +214,10 @@ impl Display for Error {
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|err| err as &(dyn std::error::Error + 'static))
+    }
+}
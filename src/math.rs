@@ -0,0 +1,111 @@
+//! Mathematical primitives.
+
+use std::fmt::{self, Debug, Display, Formatter};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A rational number (e.g. frame rate or aspect ratio).
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rational {
+    num: u32,
+    den: u32,
+}
+
+impl Rational {
+    /// Create a new rational number with a given numerator and denominator.
+    pub const fn new(num: u32, den: u32) -> Self {
+        Self { num, den }
+    }
+
+    /// Create a rational number from its raw representation. `None` is
+    /// returned if the number is zero or undefined (this is how FFmpeg
+    /// represents unknown values).
+    pub(crate) fn from_raw(num: u32, den: u32) -> Option<Self> {
+        if num == 0 || den == 0 {
+            None
+        } else {
+            Some(Self::new(num, den))
+        }
+    }
+
+    /// Get the numerator.
+    pub fn num(&self) -> u32 {
+        self.num
+    }
+
+    /// Get the denominator.
+    pub fn den(&self) -> u32 {
+        self.den
+    }
+
+    /// Get the value as a floating point number. The method returns `None`
+    /// if the denominator is zero.
+    pub fn as_f64(&self) -> Option<f64> {
+        if self.den == 0 {
+            None
+        } else {
+            Some(self.num as f64 / self.den as f64)
+        }
+    }
+
+    /// Get the reciprocal value.
+    pub fn invert(&self) -> Self {
+        Self::new(self.den, self.num)
+    }
+}
+
+impl Debug for Rational {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+/// A rectangle (e.g. a region of a video frame).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rect {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Rect {
+    /// Create a new rectangle with a given top left corner and size.
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Get the left edge.
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    /// Get the top edge.
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    /// Get the width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the height.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
@@ -0,0 +1,162 @@
+#include <libavcodec/avcodec.h>
+#include <libavfilter/avfilter.h>
+#include <libavformat/avformat.h>
+#include <libavutil/opt.h>
+
+#define OPTION_TYPE_UNKNOWN        0
+#define OPTION_TYPE_FLAGS          1
+#define OPTION_TYPE_INT            2
+#define OPTION_TYPE_INT64          3
+#define OPTION_TYPE_UINT64         4
+#define OPTION_TYPE_DOUBLE         5
+#define OPTION_TYPE_FLOAT          6
+#define OPTION_TYPE_STRING         7
+#define OPTION_TYPE_RATIONAL       8
+#define OPTION_TYPE_BINARY         9
+#define OPTION_TYPE_DICT           10
+#define OPTION_TYPE_CONST          11
+#define OPTION_TYPE_IMAGE_SIZE     12
+#define OPTION_TYPE_PIXEL_FMT      13
+#define OPTION_TYPE_SAMPLE_FMT     14
+#define OPTION_TYPE_VIDEO_RATE     15
+#define OPTION_TYPE_DURATION       16
+#define OPTION_TYPE_COLOR          17
+#define OPTION_TYPE_CHANNEL_LAYOUT 18
+#define OPTION_TYPE_BOOL           19
+
+const AVClass* ffw_format_context_get_class();
+const AVClass* ffw_codec_context_get_class();
+const AVClass* ffw_input_format_get_priv_class(const AVInputFormat* format);
+const AVClass* ffw_output_format_get_priv_class(const AVOutputFormat* format);
+int ffw_codec_get_priv_class(const char* name, int encoder, const AVClass** class);
+int ffw_filter_get_priv_class(const char* name, const AVClass** class);
+
+const AVOption* ffw_class_next_option(const AVClass* class, const AVOption* prev);
+
+const AVDictionaryEntry* ffw_dict_next_entry(const AVDictionary* dict, const AVDictionaryEntry* prev);
+const char* ffw_dict_entry_get_key(const AVDictionaryEntry* entry);
+
+const char* ffw_option_get_name(const AVOption* option);
+const char* ffw_option_get_help(const AVOption* option);
+const char* ffw_option_get_unit(const AVOption* option);
+int ffw_option_get_type(const AVOption* option);
+double ffw_option_get_min(const AVOption* option);
+double ffw_option_get_max(const AVOption* option);
+int64_t ffw_option_get_default_i64(const AVOption* option);
+double ffw_option_get_default_double(const AVOption* option);
+const char* ffw_option_get_default_str(const AVOption* option);
+
+const AVClass* ffw_format_context_get_class() {
+    return avformat_get_class();
+}
+
+const AVClass* ffw_codec_context_get_class() {
+    return avcodec_get_class();
+}
+
+const AVClass* ffw_input_format_get_priv_class(const AVInputFormat* format) {
+    return format->priv_class;
+}
+
+const AVClass* ffw_output_format_get_priv_class(const AVOutputFormat* format) {
+    return format->priv_class;
+}
+
+int ffw_codec_get_priv_class(const char* name, int encoder, const AVClass** class) {
+    AVCodec* codec;
+
+    if (encoder) {
+        codec = avcodec_find_encoder_by_name(name);
+    } else {
+        codec = avcodec_find_decoder_by_name(name);
+    }
+
+    if (!codec) {
+        return 0;
+    }
+
+    *class = codec->priv_class;
+
+    return 1;
+}
+
+int ffw_filter_get_priv_class(const char* name, const AVClass** class) {
+    const AVFilter* filter = avfilter_get_by_name(name);
+
+    if (!filter) {
+        return 0;
+    }
+
+    *class = filter->priv_class;
+
+    return 1;
+}
+
+const AVOption* ffw_class_next_option(const AVClass* class, const AVOption* prev) {
+    return av_opt_next(&class, prev);
+}
+
+const char* ffw_option_get_name(const AVOption* option) {
+    return option->name;
+}
+
+const char* ffw_option_get_help(const AVOption* option) {
+    return option->help;
+}
+
+const char* ffw_option_get_unit(const AVOption* option) {
+    return option->unit;
+}
+
+int ffw_option_get_type(const AVOption* option) {
+    switch (option->type) {
+        case AV_OPT_TYPE_FLAGS: return OPTION_TYPE_FLAGS;
+        case AV_OPT_TYPE_INT: return OPTION_TYPE_INT;
+        case AV_OPT_TYPE_INT64: return OPTION_TYPE_INT64;
+        case AV_OPT_TYPE_UINT64: return OPTION_TYPE_UINT64;
+        case AV_OPT_TYPE_DOUBLE: return OPTION_TYPE_DOUBLE;
+        case AV_OPT_TYPE_FLOAT: return OPTION_TYPE_FLOAT;
+        case AV_OPT_TYPE_STRING: return OPTION_TYPE_STRING;
+        case AV_OPT_TYPE_RATIONAL: return OPTION_TYPE_RATIONAL;
+        case AV_OPT_TYPE_BINARY: return OPTION_TYPE_BINARY;
+        case AV_OPT_TYPE_DICT: return OPTION_TYPE_DICT;
+        case AV_OPT_TYPE_CONST: return OPTION_TYPE_CONST;
+        case AV_OPT_TYPE_IMAGE_SIZE: return OPTION_TYPE_IMAGE_SIZE;
+        case AV_OPT_TYPE_PIXEL_FMT: return OPTION_TYPE_PIXEL_FMT;
+        case AV_OPT_TYPE_SAMPLE_FMT: return OPTION_TYPE_SAMPLE_FMT;
+        case AV_OPT_TYPE_VIDEO_RATE: return OPTION_TYPE_VIDEO_RATE;
+        case AV_OPT_TYPE_DURATION: return OPTION_TYPE_DURATION;
+        case AV_OPT_TYPE_COLOR: return OPTION_TYPE_COLOR;
+        case AV_OPT_TYPE_CHANNEL_LAYOUT: return OPTION_TYPE_CHANNEL_LAYOUT;
+        case AV_OPT_TYPE_BOOL: return OPTION_TYPE_BOOL;
+        default: return OPTION_TYPE_UNKNOWN;
+    }
+}
+
+double ffw_option_get_min(const AVOption* option) {
+    return option->min;
+}
+
+double ffw_option_get_max(const AVOption* option) {
+    return option->max;
+}
+
+int64_t ffw_option_get_default_i64(const AVOption* option) {
+    return option->default_val.i64;
+}
+
+double ffw_option_get_default_double(const AVOption* option) {
+    return option->default_val.dbl;
+}
+
+const char* ffw_option_get_default_str(const AVOption* option) {
+    return option->default_val.str;
+}
+
+const AVDictionaryEntry* ffw_dict_next_entry(const AVDictionary* dict, const AVDictionaryEntry* prev) {
+    return av_dict_get(dict, "", prev, AV_DICT_IGNORE_SUFFIX);
+}
+
+const char* ffw_dict_entry_get_key(const AVDictionaryEntry* entry) {
+    return entry->key;
+}
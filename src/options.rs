@@ -0,0 +1,658 @@
+//! AVOption introspection.
+//!
+//! FFmpeg components (demuxers, codecs, filters, ...) are configured using
+//! named options. This module allows enumerating the options available for a
+//! given component (e.g. to generate a settings panel) and validating
+//! user-provided values before applying them.
+
+use std::{
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+};
+
+use crate::{
+    codec::{audio::SampleFormat, video::PixelFormat},
+    Error,
+};
+
+extern "C" {
+    fn ffw_format_context_get_class() -> *const c_void;
+    fn ffw_codec_context_get_class() -> *const c_void;
+    fn ffw_input_format_get_priv_class(format: *const c_void) -> *const c_void;
+    fn ffw_output_format_get_priv_class(format: *const c_void) -> *const c_void;
+    fn ffw_codec_get_priv_class(
+        name: *const c_char,
+        encoder: c_int,
+        class: *mut *const c_void,
+    ) -> c_int;
+    fn ffw_filter_get_priv_class(name: *const c_char, class: *mut *const c_void) -> c_int;
+
+    fn ffw_class_next_option(class: *const c_void, prev: *const c_void) -> *const c_void;
+
+    fn ffw_dict_next_entry(dict: *const c_void, prev: *const c_void) -> *const c_void;
+    fn ffw_dict_entry_get_key(entry: *const c_void) -> *const c_char;
+
+    fn ffw_option_get_name(option: *const c_void) -> *const c_char;
+    fn ffw_option_get_help(option: *const c_void) -> *const c_char;
+    fn ffw_option_get_unit(option: *const c_void) -> *const c_char;
+    fn ffw_option_get_type(option: *const c_void) -> c_int;
+    fn ffw_option_get_min(option: *const c_void) -> f64;
+    fn ffw_option_get_max(option: *const c_void) -> f64;
+    fn ffw_option_get_default_i64(option: *const c_void) -> i64;
+    fn ffw_option_get_default_double(option: *const c_void) -> f64;
+    fn ffw_option_get_default_str(option: *const c_void) -> *const c_char;
+}
+
+const OPTION_TYPE_FLAGS: c_int = 1;
+const OPTION_TYPE_INT: c_int = 2;
+const OPTION_TYPE_INT64: c_int = 3;
+const OPTION_TYPE_UINT64: c_int = 4;
+const OPTION_TYPE_DOUBLE: c_int = 5;
+const OPTION_TYPE_FLOAT: c_int = 6;
+const OPTION_TYPE_STRING: c_int = 7;
+const OPTION_TYPE_RATIONAL: c_int = 8;
+const OPTION_TYPE_BINARY: c_int = 9;
+const OPTION_TYPE_DICT: c_int = 10;
+const OPTION_TYPE_CONST: c_int = 11;
+const OPTION_TYPE_IMAGE_SIZE: c_int = 12;
+const OPTION_TYPE_PIXEL_FMT: c_int = 13;
+const OPTION_TYPE_SAMPLE_FMT: c_int = 14;
+const OPTION_TYPE_VIDEO_RATE: c_int = 15;
+const OPTION_TYPE_DURATION: c_int = 16;
+const OPTION_TYPE_COLOR: c_int = 17;
+const OPTION_TYPE_CHANNEL_LAYOUT: c_int = 18;
+const OPTION_TYPE_BOOL: c_int = 19;
+
+/// Helper function for converting optional C strings.
+unsafe fn to_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
+
+/// Option type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OptionType {
+    Flags,
+    Int,
+    Int64,
+    UInt64,
+    Double,
+    Float,
+    String,
+    Rational,
+    Binary,
+    Dictionary,
+    ImageSize,
+    PixelFormat,
+    SampleFormat,
+    VideoRate,
+    Duration,
+    Color,
+    ChannelLayout,
+    Bool,
+    Unknown,
+}
+
+impl OptionType {
+    /// Create option type from its raw representation.
+    fn from_raw(v: c_int) -> Self {
+        match v {
+            OPTION_TYPE_FLAGS => Self::Flags,
+            OPTION_TYPE_INT => Self::Int,
+            OPTION_TYPE_INT64 => Self::Int64,
+            OPTION_TYPE_UINT64 => Self::UInt64,
+            OPTION_TYPE_DOUBLE => Self::Double,
+            OPTION_TYPE_FLOAT => Self::Float,
+            OPTION_TYPE_STRING => Self::String,
+            OPTION_TYPE_RATIONAL => Self::Rational,
+            OPTION_TYPE_BINARY => Self::Binary,
+            OPTION_TYPE_DICT => Self::Dictionary,
+            OPTION_TYPE_IMAGE_SIZE => Self::ImageSize,
+            OPTION_TYPE_PIXEL_FMT => Self::PixelFormat,
+            OPTION_TYPE_SAMPLE_FMT => Self::SampleFormat,
+            OPTION_TYPE_VIDEO_RATE => Self::VideoRate,
+            OPTION_TYPE_DURATION => Self::Duration,
+            OPTION_TYPE_COLOR => Self::Color,
+            OPTION_TYPE_CHANNEL_LAYOUT => Self::ChannelLayout,
+            OPTION_TYPE_BOOL => Self::Bool,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Check if the type is numeric.
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Self::Flags
+                | Self::Int
+                | Self::Int64
+                | Self::UInt64
+                | Self::Double
+                | Self::Float
+                | Self::Bool
+        )
+    }
+}
+
+/// Named constant that can be used as an option value.
+#[derive(Debug, Clone)]
+pub struct OptionConstant {
+    name: String,
+    help: Option<String>,
+    value: i64,
+}
+
+impl OptionConstant {
+    /// Get name of the constant.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get description of the constant.
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    /// Get value of the constant.
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+}
+
+/// Description of an option.
+#[derive(Debug, Clone)]
+pub struct OptionInfo {
+    name: String,
+    help: Option<String>,
+    option_type: OptionType,
+    min: f64,
+    max: f64,
+    default: Option<String>,
+    unit: Option<String>,
+    constants: Vec<OptionConstant>,
+}
+
+impl OptionInfo {
+    /// Create option info from its raw representation.
+    unsafe fn from_raw_ptr(ptr: *const c_void) -> Self {
+        let option_type = OptionType::from_raw(ffw_option_get_type(ptr));
+
+        let default = match option_type {
+            OptionType::Flags
+            | OptionType::Int
+            | OptionType::Int64
+            | OptionType::UInt64
+            | OptionType::Duration
+            | OptionType::ChannelLayout => Some(ffw_option_get_default_i64(ptr).to_string()),
+            OptionType::Double | OptionType::Float | OptionType::Rational => {
+                Some(ffw_option_get_default_double(ptr).to_string())
+            }
+            OptionType::Bool => match ffw_option_get_default_i64(ptr) {
+                v if v < 0 => Some(String::from("auto")),
+                0 => Some(String::from("false")),
+                _ => Some(String::from("true")),
+            },
+            OptionType::PixelFormat => match ffw_option_get_default_i64(ptr) {
+                v if v < 0 => None,
+                v => Some(PixelFormat::from_raw(v as _).name().to_string()),
+            },
+            OptionType::SampleFormat => match ffw_option_get_default_i64(ptr) {
+                v if v < 0 => None,
+                v => Some(SampleFormat::from_raw(v as _).name().to_string()),
+            },
+            OptionType::String
+            | OptionType::Binary
+            | OptionType::Dictionary
+            | OptionType::ImageSize
+            | OptionType::VideoRate
+            | OptionType::Color => to_string(ffw_option_get_default_str(ptr)),
+            OptionType::Unknown => None,
+        };
+
+        Self {
+            name: to_string(ffw_option_get_name(ptr)).unwrap_or_default(),
+            help: to_string(ffw_option_get_help(ptr)),
+            option_type,
+            min: ffw_option_get_min(ptr),
+            max: ffw_option_get_max(ptr),
+            default,
+            unit: to_string(ffw_option_get_unit(ptr)),
+            constants: Vec::new(),
+        }
+    }
+
+    /// Get option name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get option description.
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    /// Get option type.
+    pub fn option_type(&self) -> OptionType {
+        self.option_type
+    }
+
+    /// Get minimum allowed value. The value is meaningful only for numeric
+    /// options.
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+
+    /// Get maximum allowed value. The value is meaningful only for numeric
+    /// options.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// Get the default value (if any). If the default value of an integer
+    /// option matches one of the named constants, the constant name is
+    /// returned instead.
+    pub fn default_value(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Get named constants accepted by the option.
+    pub fn constants(&self) -> &[OptionConstant] {
+        &self.constants
+    }
+
+    /// Check if a given value is acceptable for this option.
+    ///
+    /// # Note
+    /// Only numeric options (including flags and named constants) are
+    /// validated. Values of other types are always accepted.
+    pub fn validate(&self, value: &str) -> Result<(), Error> {
+        if !self.option_type.is_numeric() {
+            return Ok(());
+        }
+
+        let value = value.trim();
+
+        let res = if self.option_type == OptionType::Flags {
+            self.validate_flags(value)
+        } else if self.option_type == OptionType::Bool {
+            self.validate_bool(value)
+        } else {
+            self.validate_number(value)
+        };
+
+        res.map_err(|msg| Error::new(format!("invalid value of option {}: {}", self.name, msg)))
+    }
+
+    /// Validate a given value of a numeric option.
+    fn validate_number(&self, value: &str) -> Result<(), String> {
+        let n = self
+            .find_constant(value)
+            .map(|c| c.value as f64)
+            .or_else(|| parse_number(value))
+            .ok_or_else(|| format!("\"{}\" is not a number", value))?;
+
+        if n < self.min || n > self.max {
+            return Err(format!(
+                "{} is out of range [{}, {}]",
+                n, self.min, self.max
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate a given value of a boolean option.
+    fn validate_bool(&self, value: &str) -> Result<(), String> {
+        let n = match value {
+            "auto" => -1.0,
+            "true" | "y" | "yes" | "enable" | "on" => 1.0,
+            "false" | "n" | "no" | "disable" | "off" => 0.0,
+            _ => parse_number(value).ok_or_else(|| format!("\"{}\" is not a boolean", value))?,
+        };
+
+        if n < self.min || n > self.max {
+            return Err(format!(
+                "{} is out of range [{}, {}]",
+                n, self.min, self.max
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate a given value of a flags option (e.g. "+a-b").
+    fn validate_flags(&self, value: &str) -> Result<(), String> {
+        let items = value.split(&['+', '-'][..]).filter(|item| !item.is_empty());
+
+        for item in items {
+            if self.find_constant(item).is_none() && parse_number(item).is_none() {
+                return Err(format!("unknown flag \"{}\"", item));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find a named constant.
+    fn find_constant(&self, name: &str) -> Option<&OptionConstant> {
+        self.constants.iter().find(|c| c.name == name)
+    }
+}
+
+/// Parse a given number with an optional SI prefix (e.g. "500k" or "1.5M").
+fn parse_number(value: &str) -> Option<f64> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((pos, 'k')) | Some((pos, 'K')) => (&value[..pos], 1e3),
+        Some((pos, 'M')) => (&value[..pos], 1e6),
+        Some((pos, 'G')) => (&value[..pos], 1e9),
+        _ => (value, 1.0),
+    };
+
+    number.parse::<f64>().ok().map(|n| n * multiplier)
+}
+
+/// Convert a given option name and value into C strings.
+pub(crate) fn option_to_cstrings(name: &str, value: &str) -> Result<(CString, CString), Error> {
+    let name = CString::new(name).map_err(|_| Error::new("invalid option name"))?;
+    let value = CString::new(value).map_err(|_| Error::new("invalid option value"))?;
+
+    Ok((name, value))
+}
+
+/// Check that there are no options left in a given options dictionary after
+/// passing it to FFmpeg (i.e. that all options were recognized). An error
+/// naming the unknown options is returned otherwise.
+pub(crate) unsafe fn check_unused_options(dict: *const c_void) -> Result<(), Error> {
+    let mut names = Vec::new();
+
+    let mut entry = ffw_dict_next_entry(dict, ptr::null());
+
+    while !entry.is_null() {
+        names.push(to_string(ffw_dict_entry_get_key(entry)).unwrap_or_default());
+
+        entry = ffw_dict_next_entry(dict, entry);
+    }
+
+    if names.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::new(format!("unknown options: {}", names.join(", "))))
+    }
+}
+
+/// Get options of a given AVClass.
+pub(crate) unsafe fn class_options(class: *const c_void) -> Vec<OptionInfo> {
+    if class.is_null() {
+        return Vec::new();
+    }
+
+    let mut options = Vec::<OptionInfo>::new();
+    let mut constants = Vec::new();
+
+    let mut option = ffw_class_next_option(class, ptr::null());
+
+    while !option.is_null() {
+        if ffw_option_get_type(option) == OPTION_TYPE_CONST {
+            let name = to_string(ffw_option_get_name(option)).unwrap_or_default();
+            let unit = to_string(ffw_option_get_unit(option));
+
+            let constant = OptionConstant {
+                name,
+                help: to_string(ffw_option_get_help(option)),
+                value: ffw_option_get_default_i64(option),
+            };
+
+            constants.push((unit, constant));
+        } else {
+            options.push(OptionInfo::from_raw_ptr(option));
+        }
+
+        option = ffw_class_next_option(class, option);
+    }
+
+    for option in &mut options {
+        if option.unit.is_none() {
+            continue;
+        }
+
+        for (unit, constant) in &constants {
+            if unit == &option.unit {
+                option.constants.push(constant.clone());
+            }
+        }
+
+        if option.option_type != OptionType::Flags {
+            let default = option
+                .default
+                .as_deref()
+                .and_then(|v| v.parse::<i64>().ok());
+
+            let name = option
+                .constants
+                .iter()
+                .find(|c| Some(c.value) == default)
+                .map(|c| c.name.clone());
+
+            if name.is_some() {
+                option.default = name;
+            }
+        }
+    }
+
+    options
+}
+
+/// Get generic demuxer/muxer options.
+pub fn format_options() -> Vec<OptionInfo> {
+    unsafe { class_options(ffw_format_context_get_class()) }
+}
+
+/// Get options specific to a given input format (raw pointer to an
+/// AVInputFormat).
+pub(crate) unsafe fn input_format_options(format: *const c_void) -> Vec<OptionInfo> {
+    class_options(ffw_input_format_get_priv_class(format))
+}
+
+/// Get options specific to a given output format (raw pointer to an
+/// AVOutputFormat).
+pub(crate) unsafe fn output_format_options(format: *const c_void) -> Vec<OptionInfo> {
+    class_options(ffw_output_format_get_priv_class(format))
+}
+
+/// Get generic codec options (i.e. options common to all encoders and
+/// decoders).
+pub fn codec_options() -> Vec<OptionInfo> {
+    unsafe { class_options(ffw_codec_context_get_class()) }
+}
+
+/// Get options specific to a given encoder (e.g. "libx264").
+pub fn encoder_options(name: &str) -> Result<Vec<OptionInfo>, Error> {
+    codec_private_options(name, true)
+}
+
+/// Get options specific to a given decoder.
+pub fn decoder_options(name: &str) -> Result<Vec<OptionInfo>, Error> {
+    codec_private_options(name, false)
+}
+
+/// Get private options of a given codec.
+fn codec_private_options(name: &str, encoder: bool) -> Result<Vec<OptionInfo>, Error> {
+    let name = CString::new(name).map_err(|_| Error::new("invalid codec name"))?;
+
+    let mut class = ptr::null();
+
+    let ret = unsafe { ffw_codec_get_priv_class(name.as_ptr(), encoder as _, &mut class) };
+
+    if ret == 0 {
+        return Err(Error::new("unknown codec"));
+    }
+
+    unsafe { Ok(class_options(class)) }
+}
+
+/// Get options of a given filter (e.g. "scale" or "yadif").
+pub fn filter_options(name: &str) -> Result<Vec<OptionInfo>, Error> {
+    let name = CString::new(name).map_err(|_| Error::new("invalid filter name"))?;
+
+    let mut class = ptr::null();
+
+    let ret = unsafe { ffw_filter_get_priv_class(name.as_ptr(), &mut class) };
+
+    if ret == 0 {
+        return Err(Error::new("unknown filter"));
+    }
+
+    unsafe { Ok(class_options(class)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{codec_options, parse_number, OptionConstant, OptionInfo, OptionType};
+
+    /// Create an option with a given type, range and named constants.
+    fn option(
+        option_type: OptionType,
+        min: f64,
+        max: f64,
+        constants: &[(&str, i64)],
+    ) -> OptionInfo {
+        let constants = constants
+            .iter()
+            .map(|&(name, value)| OptionConstant {
+                name: name.to_string(),
+                help: None,
+                value,
+            })
+            .collect();
+
+        OptionInfo {
+            name: String::from("test"),
+            help: None,
+            option_type,
+            min,
+            max,
+            default: None,
+            unit: None,
+            constants,
+        }
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("42"), Some(42.0));
+        assert_eq!(parse_number("-1.5"), Some(-1.5));
+        assert_eq!(parse_number("500k"), Some(500e3));
+        assert_eq!(parse_number("500K"), Some(500e3));
+        assert_eq!(parse_number("1.5M"), Some(1.5e6));
+        assert_eq!(parse_number("2G"), Some(2e9));
+        assert_eq!(parse_number(""), None);
+        assert_eq!(parse_number("k"), None);
+        assert_eq!(parse_number("abc"), None);
+        assert_eq!(parse_number("5m"), None);
+    }
+
+    #[test]
+    fn test_validate_range() {
+        let option = option(OptionType::Int, 0.0, 100.0, &[]);
+
+        assert!(option.validate("0").is_ok());
+        assert!(option.validate(" 100 ").is_ok());
+        assert!(option.validate("-1").is_err());
+        assert!(option.validate("101").is_err());
+        assert!(option.validate("1k").is_err());
+        assert!(option.validate("foo").is_err());
+
+        let err = option.validate("101").err().unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            "invalid value of option test: 101 is out of range [0, 100]"
+        );
+    }
+
+    #[test]
+    fn test_validate_enum() {
+        let option = option(
+            OptionType::Int,
+            -2.0,
+            2.0,
+            &[
+                ("strict", 1),
+                ("normal", 0),
+                ("experimental", -2),
+                ("bogus", 3),
+            ],
+        );
+
+        assert!(option.validate("normal").is_ok());
+        assert!(option.validate("experimental").is_ok());
+        assert!(option.validate("-1").is_ok());
+        assert!(option.validate("unknown").is_err());
+
+        // constants are subject to the range check as well
+        assert!(option.validate("bogus").is_err());
+    }
+
+    #[test]
+    fn test_validate_flags() {
+        let option = option(
+            OptionType::Flags,
+            0.0,
+            u32::MAX as f64,
+            &[("a", 1), ("b", 2)],
+        );
+
+        assert!(option.validate("a").is_ok());
+        assert!(option.validate("+a-b").is_ok());
+        assert!(option.validate("a+b+4").is_ok());
+        assert!(option.validate("+c").is_err());
+    }
+
+    #[test]
+    fn test_validate_bool() {
+        let option = option(OptionType::Bool, -1.0, 1.0, &[]);
+
+        assert!(option.validate("true").is_ok());
+        assert!(option.validate("off").is_ok());
+        assert!(option.validate("auto").is_ok());
+        assert!(option.validate("1").is_ok());
+        assert!(option.validate("2").is_err());
+        assert!(option.validate("maybe").is_err());
+
+        let option = OptionInfo { min: 0.0, ..option };
+
+        assert!(option.validate("auto").is_err());
+    }
+
+    #[test]
+    fn test_validate_other_types() {
+        let option = option(OptionType::String, 0.0, 0.0, &[]);
+
+        assert!(option.validate("anything").is_ok());
+    }
+
+    #[test]
+    fn test_codec_options() {
+        let options = codec_options();
+
+        let flags = options.iter().find(|o| o.name() == "flags").unwrap();
+
+        assert_eq!(flags.option_type(), OptionType::Flags);
+        assert!(flags.validate("+global_header").is_ok());
+        assert!(flags.validate("+no_such_flag").is_err());
+
+        let strict = options.iter().find(|o| o.name() == "strict").unwrap();
+
+        assert!(strict.validate("experimental").is_ok());
+        assert!(strict.validate("foo").is_err());
+
+        let bitrate = options.iter().find(|o| o.name() == "b").unwrap();
+
+        assert!(bitrate.validate("500k").is_ok());
+        assert!(bitrate.validate("-1").is_err());
+    }
+}
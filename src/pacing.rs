@@ -0,0 +1,223 @@
+//! Realtime pacing.
+//!
+//! The `Realtime` pacer delays packets or frames according to their
+//! timestamps, so that e.g. a file can be streamed as if it was a live
+//! source. All deadlines are calculated from a single reference point (the
+//! first timestamp and the time when it was seen), so sleep inaccuracies do
+//! not accumulate over time.
+
+use std::{thread, time::Duration};
+
+use crate::{packet::Packet, time::Timestamp};
+
+extern "C" {
+    fn ffw_gettime() -> i64;
+    fn ffw_gettime_relative() -> i64;
+}
+
+/// Get the current wall-clock time (i.e. time since the Unix epoch in
+/// microseconds).
+pub fn wall_clock() -> Timestamp {
+    unsafe { Timestamp::from_micros(ffw_gettime()) }
+}
+
+/// Get the current time of a monotonic clock (in microseconds). The clock
+/// is not affected by wall-clock time changes, but its origin is not
+/// specified, so it is useful only for measuring time intervals.
+pub fn monotonic_clock() -> Timestamp {
+    unsafe { Timestamp::from_micros(ffw_gettime_relative()) }
+}
+
+/// Builder for the realtime pacer.
+pub struct RealtimeBuilder {
+    speed: f64,
+    resync_threshold: Duration,
+}
+
+impl RealtimeBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            speed: 1.0,
+            resync_threshold: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the playback speed (e.g. 2.0 means twice as fast as realtime).
+    /// The default is 1.0.
+    ///
+    /// # Panics
+    /// The method panics if the speed is not a positive number.
+    pub fn speed(mut self, speed: f64) -> Self {
+        assert!(speed.is_finite() && speed > 0.0);
+
+        self.speed = speed;
+        self
+    }
+
+    /// Set the maximum difference between the expected and the actual time
+    /// of a timestamp. If a timestamp is late by more than this value (e.g.
+    /// because the processing got stuck) or if it is too far in the future
+    /// (e.g. because of a timestamp discontinuity), the pacer is
+    /// re-synchronized to the timestamp instead of catching up or waiting.
+    /// The default is 1 second.
+    pub fn resync_threshold(mut self, threshold: Duration) -> Self {
+        self.resync_threshold = threshold;
+        self
+    }
+
+    /// Build the pacer.
+    pub fn build(self) -> Realtime {
+        Realtime {
+            speed: self.speed,
+            resync_threshold: self.resync_threshold.as_micros() as i64,
+            origin: None,
+            resyncs: 0,
+        }
+    }
+}
+
+/// Realtime pacer.
+///
+/// The first timestamp passed to the pacer is due immediately. Every other
+/// timestamp is due when the same amount of time (divided by the speed
+/// factor) passes on the monotonic clock.
+pub struct Realtime {
+    speed: f64,
+    resync_threshold: i64,
+    origin: Option<(i64, i64)>,
+    resyncs: u64,
+}
+
+impl Realtime {
+    /// Create a new pacer with the default settings.
+    pub fn new() -> Self {
+        RealtimeBuilder::new().build()
+    }
+
+    /// Get a builder for the pacer.
+    pub fn builder() -> RealtimeBuilder {
+        RealtimeBuilder::new()
+    }
+
+    /// Get the time remaining until a given timestamp is due. Zero is
+    /// returned for null timestamps and for timestamps that are already
+    /// due. Note that the first timestamp (or a timestamp causing
+    /// re-synchronization) becomes the new reference point.
+    pub fn delay(&mut self, timestamp: Timestamp) -> Duration {
+        let timestamp = match timestamp.as_micros() {
+            Some(t) => t,
+            None => return Duration::from_secs(0),
+        };
+
+        let now = unsafe { ffw_gettime_relative() };
+
+        let deadline = if let Some((origin_timestamp, origin_time)) = self.origin {
+            let offset = (timestamp - origin_timestamp) as f64 / self.speed;
+
+            origin_time + offset as i64
+        } else {
+            self.origin = Some((timestamp, now));
+
+            now
+        };
+
+        let delay = deadline - now;
+
+        if delay.abs() > self.resync_threshold {
+            self.origin = Some((timestamp, now));
+            self.resyncs += 1;
+
+            Duration::from_secs(0)
+        } else if delay > 0 {
+            Duration::from_micros(delay as u64)
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+
+    /// Block the current thread until a given timestamp is due.
+    pub fn wait(&mut self, timestamp: Timestamp) {
+        let delay = self.delay(timestamp);
+
+        if delay > Duration::from_secs(0) {
+            thread::sleep(delay);
+        }
+    }
+
+    /// Block the current thread until a given packet is due. The packet
+    /// DTS is used (or the PTS if the DTS is missing), so packets need to
+    /// be passed in the decoding order.
+    pub fn wait_for_packet(&mut self, packet: &Packet) {
+        let timestamp = if packet.dts().is_null() {
+            packet.pts()
+        } else {
+            packet.dts()
+        };
+
+        self.wait(timestamp)
+    }
+
+    /// Get the number of re-synchronizations.
+    pub fn resyncs(&self) -> u64 {
+        self.resyncs
+    }
+
+    /// Reset the pacer. The next timestamp will be due immediately.
+    pub fn reset(&mut self) {
+        self.origin = None;
+    }
+}
+
+impl Default for Realtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{monotonic_clock, wall_clock, Realtime};
+
+    use crate::time::Timestamp;
+
+    #[test]
+    fn test_clocks() {
+        // 2020-01-01
+        assert!(wall_clock() > Timestamp::from_secs(1_577_836_800));
+
+        let start = monotonic_clock();
+
+        std::thread::sleep(Duration::from_millis(10));
+
+        assert!(monotonic_clock() - start >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_realtime() {
+        let mut pacer = Realtime::builder().speed(10.0).build();
+
+        let start = Instant::now();
+
+        for i in 0..=5 {
+            pacer.wait(Timestamp::from_millis(1000 + i * 100));
+        }
+
+        let elapsed = start.elapsed();
+
+        assert!(elapsed >= Duration::from_millis(50));
+        assert!(elapsed < Duration::from_millis(500));
+        assert_eq!(pacer.resyncs(), 0);
+
+        // a discontinuity should not block the thread
+        assert_eq!(
+            pacer.delay(Timestamp::from_secs(3600)),
+            Duration::from_secs(0)
+        );
+        assert_eq!(pacer.resyncs(), 1);
+
+        assert!(pacer.delay(Timestamp::from_millis(3_600_500)) > Duration::from_millis(40));
+    }
+}
@@ -1,4 +1,7 @@
 #include <libavcodec/avcodec.h>
+#include <libavutil/intreadwrite.h>
+
+#include <string.h>
 
 AVPacket* ffw_packet_alloc() {
     return av_packet_alloc();
@@ -22,8 +25,47 @@ err:
     return NULL;
 }
 
+AVPacket* ffw_packet_from_data(uint8_t* data, int size, void (*free_buffer)(void*, uint8_t*), void* opaque) {
+    AVBufferRef* buffer;
+
+    AVPacket* packet = av_packet_alloc();
+    if (packet == NULL) {
+        return NULL;
+    }
+
+    // NOTE: the buffer is created as the last step, so that the caller keeps
+    // the ownership of the data in case of an error
+    buffer = av_buffer_create(data, size + AV_INPUT_BUFFER_PADDING_SIZE, free_buffer, opaque, 0);
+    if (buffer == NULL) {
+        av_packet_free(&packet);
+        return NULL;
+    }
+
+    packet->buf = buffer;
+    packet->data = data;
+    packet->size = size;
+
+    return packet;
+}
+
+size_t ffw_packet_get_padding_size() {
+    return AV_INPUT_BUFFER_PADDING_SIZE;
+}
+
 AVPacket* ffw_packet_clone(const AVPacket* src) {
-    return av_packet_clone(src);
+    AVPacket* packet = av_packet_alloc();
+    if (packet == NULL) {
+        return NULL;
+    }
+
+    // NOTE: the data buffer is shared if the source packet is reference
+    // counted, it is copied otherwise
+    if (av_packet_ref(packet, src) != 0) {
+        av_packet_free(&packet);
+        return NULL;
+    }
+
+    return packet;
 }
 
 void ffw_packet_free(AVPacket* packet) {
@@ -46,6 +88,18 @@ void ffw_packet_set_dts(AVPacket* packet, int64_t dts) {
     packet->dts = dts;
 }
 
+void ffw_packet_rescale_ts(AVPacket* packet, uint32_t src_num, uint32_t src_den, uint32_t dst_num, uint32_t dst_den) {
+    AVRational src_tb;
+    AVRational dst_tb;
+
+    src_tb.num = src_num;
+    src_tb.den = src_den;
+    dst_tb.num = dst_num;
+    dst_tb.den = dst_den;
+
+    av_packet_rescale_ts(packet, src_tb, dst_tb);
+}
+
 int ffw_packet_get_stream_index(const AVPacket* packet) {
     return packet->stream_index;
 }
@@ -54,6 +108,59 @@ void ffw_packet_set_stream_index(AVPacket* packet, int index) {
     packet->stream_index = index;
 }
 
+int64_t ffw_packet_get_pos(const AVPacket* packet) {
+    return packet->pos;
+}
+
+int ffw_packet_get_pes_stream_id(const AVPacket* packet) {
+    uint8_t* data;
+    int size;
+
+    data = av_packet_get_side_data(packet, AV_PKT_DATA_MPEGTS_STREAM_ID, &size);
+    if (!data || size < 1) {
+        return -1;
+    }
+
+    return data[0];
+}
+
+int ffw_packet_get_block_additional(const AVPacket* packet, uint64_t* id, const uint8_t** data, int* size) {
+    uint8_t* side_data;
+    int side_data_size;
+
+    side_data = av_packet_get_side_data(packet, AV_PKT_DATA_MATROSKA_BLOCKADDITIONAL, &side_data_size);
+    if (!side_data || side_data_size < 8) {
+        return 0;
+    }
+
+    *id = AV_RB64(side_data);
+    *data = side_data + 8;
+    *size = side_data_size - 8;
+
+    return 1;
+}
+
+int ffw_packet_set_block_additional(AVPacket* packet, uint64_t id, const uint8_t* data, int size) {
+    uint8_t* side_data;
+    int ret;
+
+    side_data = av_malloc(size + 8);
+    if (!side_data) {
+        return AVERROR(ENOMEM);
+    }
+
+    AV_WB64(side_data, id);
+    memcpy(side_data + 8, data, size);
+
+    // existing BlockAdditional side data are replaced
+    ret = av_packet_add_side_data(packet, AV_PKT_DATA_MATROSKA_BLOCKADDITIONAL, side_data, size + 8);
+    if (ret < 0) {
+        av_free(side_data);
+    }
+
+    return ret;
+}
+
 int ffw_packet_is_key(const AVPacket* packet) {
     return packet->flags & AV_PKT_FLAG_KEY;
 }
@@ -8,11 +8,32 @@ use std::{
     ptr, slice,
 };
 
-use crate::time::{TimeBase, Timestamp};
+#[cfg(feature = "bytes")]
+use bytes::{Bytes, BytesMut};
+
+use crate::{
+    encryption::{EncryptionInfo, EncryptionInitInfo},
+    time::{Rounding, TimeBase, Timestamp},
+};
+
+#[cfg(feature = "bytes")]
+use crate::Error;
+
+#[cfg(feature = "bytes")]
+type FreeBufferCallback = extern "C" fn(opaque: *mut c_void, data: *mut u8);
 
 extern "C" {
     fn ffw_packet_alloc() -> *mut c_void;
     fn ffw_packet_new(size: c_int) -> *mut c_void;
+    #[cfg(feature = "bytes")]
+    fn ffw_packet_from_data(
+        data: *mut u8,
+        size: c_int,
+        free_buffer: FreeBufferCallback,
+        opaque: *mut c_void,
+    ) -> *mut c_void;
+    #[cfg(feature = "bytes")]
+    fn ffw_packet_get_padding_size() -> usize;
     fn ffw_packet_clone(src: *const c_void) -> *mut c_void;
     fn ffw_packet_free(packet: *mut c_void);
     fn ffw_packet_get_size(packet: *const c_void) -> c_int;
@@ -21,13 +42,40 @@ extern "C" {
     fn ffw_packet_set_pts(packet: *mut c_void, pts: i64);
     fn ffw_packet_get_dts(packet: *const c_void) -> i64;
     fn ffw_packet_set_dts(packet: *mut c_void, pts: i64);
+    fn ffw_packet_rescale_ts(
+        packet: *mut c_void,
+        src_num: u32,
+        src_den: u32,
+        dst_num: u32,
+        dst_den: u32,
+    );
     fn ffw_packet_is_key(packet: *const c_void) -> c_int;
     fn ffw_packet_set_key(packet: *mut c_void, key: c_int);
     fn ffw_packet_get_stream_index(packet: *const c_void) -> c_int;
     fn ffw_packet_set_stream_index(packet: *mut c_void, index: c_int);
+    fn ffw_packet_get_pos(packet: *const c_void) -> i64;
+    fn ffw_packet_get_pes_stream_id(packet: *const c_void) -> c_int;
+    fn ffw_packet_get_block_additional(
+        packet: *const c_void,
+        id: *mut u64,
+        data: *mut *const u8,
+        size: *mut c_int,
+    ) -> c_int;
+    fn ffw_packet_set_block_additional(
+        packet: *mut c_void,
+        id: u64,
+        data: *const u8,
+        size: c_int,
+    ) -> c_int;
     fn ffw_packet_make_writable(packet: *mut c_void) -> c_int;
+    fn ffw_packet_get_encryption_info(packet: *const c_void) -> *mut c_void;
+    fn ffw_packet_get_encryption_init_info(packet: *const c_void) -> *mut c_void;
 }
 
+/// BlockAddID of Matroska BlockAdditional data containing the alpha channel
+/// of VP8/VP9/AV1 video (encoded as a separate bitstream).
+pub const BLOCK_ADDITIONAL_ID_ALPHA: u64 = 1;
+
 /// Packet with mutable data.
 pub struct PacketMut {
     ptr: *mut c_void,
@@ -90,6 +138,7 @@ impl PacketMut {
     }
 
     /// Get packet presentation timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn pts(&self) -> Timestamp {
         let pts = unsafe { ffw_packet_get_pts(self.ptr) };
 
@@ -106,6 +155,7 @@ impl PacketMut {
     }
 
     /// Get packet decoding timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn dts(&self) -> Timestamp {
         let dts = unsafe { ffw_packet_get_dts(self.ptr) };
 
@@ -133,6 +183,31 @@ impl PacketMut {
         self
     }
 
+    /// Attach given Matroska BlockAdditional data with a given BlockAddID to
+    /// the packet (e.g. the alpha channel bitstream using
+    /// `BLOCK_ADDITIONAL_ID_ALPHA`). The Matroska muxer writes the data
+    /// along with the packet. Data attached previously are replaced.
+    ///
+    /// # Panics
+    /// The method panics if the data cannot be allocated or if the data is
+    /// too large.
+    pub fn with_block_additional(self, id: u64, data: &[u8]) -> Self {
+        assert!(
+            data.len() <= (c_int::MAX - 8) as usize,
+            "BlockAdditional data too large"
+        );
+
+        let ret = unsafe {
+            ffw_packet_set_block_additional(self.ptr, id, data.as_ptr(), data.len() as _)
+        };
+
+        if ret < 0 {
+            panic!("unable to allocate BlockAdditional data");
+        }
+
+        self
+    }
+
     /// Get packet data.
     pub fn data(&self) -> &[u8] {
         unsafe {
@@ -199,6 +274,13 @@ unsafe impl Send for PacketMut {}
 unsafe impl Sync for PacketMut {}
 
 /// Packet with immutable data.
+///
+/// Packet data are reference counted. Cloning a packet creates a new
+/// reference to the same data buffer, so it is cheap even for large packets
+/// and the clones can be sent to other threads (e.g. when sending a single
+/// packet to multiple muxers). The data are copied only when a mutable
+/// access is requested while the buffer is shared (see `data_mut()` and
+/// `into_mut()`).
 pub struct Packet {
     ptr: *mut c_void,
     time_base: TimeBase,
@@ -210,6 +292,62 @@ impl Packet {
         Packet { ptr, time_base }
     }
 
+    /// Create a new packet from given bytes. The time base of the packet
+    /// will be in microseconds.
+    ///
+    /// FFmpeg requires some zeroed padding after the packet data. The data
+    /// will not be copied if the underlying buffer is not shared and it has
+    /// enough spare capacity for the padding. Otherwise, the data will be
+    /// copied into a new buffer.
+    ///
+    /// # Panics
+    /// The method panics if the packet cannot be allocated or if the data is
+    /// too large. Use `try_from_bytes()` to handle these errors.
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes(data: Bytes) -> Self {
+        Self::try_from_bytes(data).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a new packet from given bytes. This is a fallible version of
+    /// `from_bytes()`. An error is returned if the data (including the
+    /// padding) is too large or if the packet cannot be allocated.
+    #[cfg(feature = "bytes")]
+    pub fn try_from_bytes(data: Bytes) -> Result<Self, Error> {
+        let size = data.len();
+
+        let padding = unsafe { ffw_packet_get_padding_size() };
+
+        if size > (c_int::MAX as usize - padding) {
+            return Err(Error::new("packet data too large"));
+        }
+
+        let mut buffer = match data.try_into_mut() {
+            Ok(buffer) if buffer.capacity() >= (size + padding) => buffer,
+            Ok(buffer) => copy_with_padding(&buffer, padding),
+            Err(data) => copy_with_padding(&data, padding),
+        };
+
+        buffer.resize(size + padding, 0);
+
+        let data = buffer.as_mut_ptr();
+        let opaque = Box::into_raw(Box::new(buffer));
+
+        let ptr = unsafe { ffw_packet_from_data(data, size as _, free_packet_buffer, opaque as _) };
+
+        if ptr.is_null() {
+            unsafe { drop(Box::from_raw(opaque)) }
+
+            return Err(Error::new("unable to allocate a packet"));
+        }
+
+        let res = Self {
+            ptr,
+            time_base: TimeBase::MICROSECONDS,
+        };
+
+        Ok(res)
+    }
+
     /// Get stream index.
     pub fn stream_index(&self) -> usize {
         unsafe { ffw_packet_get_stream_index(self.ptr) as _ }
@@ -243,7 +381,48 @@ impl Packet {
         self
     }
 
+    /// Set packet time base and rescale the current timestamps using a
+    /// given rounding method.
+    pub fn with_time_base_rounded(mut self, time_base: TimeBase, rounding: Rounding) -> Self {
+        self.rescale(time_base, rounding);
+        self
+    }
+
+    /// Rescale all packet timestamps (including the packet duration) into a
+    /// given time base in the same way as muxers do it.
+    pub(crate) fn with_muxer_time_base(mut self, time_base: TimeBase) -> Self {
+        let tb = self.time_base;
+
+        unsafe {
+            ffw_packet_rescale_ts(
+                self.ptr,
+                tb.num(),
+                tb.den(),
+                time_base.num(),
+                time_base.den(),
+            );
+        }
+
+        self.time_base = time_base;
+
+        self
+    }
+
+    /// Rescale the packet timestamps into a given time base.
+    fn rescale(&mut self, time_base: TimeBase, rounding: Rounding) {
+        let new_pts = self.pts().with_time_base_rounded(time_base, rounding);
+        let new_dts = self.dts().with_time_base_rounded(time_base, rounding);
+
+        unsafe {
+            ffw_packet_set_pts(self.ptr, new_pts.timestamp());
+            ffw_packet_set_dts(self.ptr, new_dts.timestamp());
+        }
+
+        self.time_base = time_base;
+    }
+
     /// Get packet presentation timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn pts(&self) -> Timestamp {
         let pts = unsafe { ffw_packet_get_pts(self.ptr) };
 
@@ -260,6 +439,7 @@ impl Packet {
     }
 
     /// Get packet decoding timestamp.
+    /// The timestamp will be null if it is not known.
     pub fn dts(&self) -> Timestamp {
         let dts = unsafe { ffw_packet_get_dts(self.ptr) };
 
@@ -280,6 +460,92 @@ impl Packet {
         unsafe { ffw_packet_is_key(self.ptr) != 0 }
     }
 
+    /// Get byte position of the packet in the input or `None` if the
+    /// position is not known. For MPEG-TS, this is the position of the
+    /// transport stream packet containing the start of the PES packet and it
+    /// can be used for correlating packets with the transport layer.
+    ///
+    /// # Note
+    /// FFmpeg does not export PCR values of the transport stream packets.
+    pub fn position(&self) -> Option<u64> {
+        let pos = unsafe { ffw_packet_get_pos(self.ptr) };
+
+        if pos < 0 {
+            None
+        } else {
+            Some(pos as u64)
+        }
+    }
+
+    /// Get the original PES stream_id (e.g. 0xe0 for the first video stream)
+    /// as exported by the MPEG-TS demuxer. `None` is returned for other
+    /// formats.
+    pub fn pes_stream_id(&self) -> Option<u8> {
+        let id = unsafe { ffw_packet_get_pes_stream_id(self.ptr) };
+
+        if id < 0 {
+            None
+        } else {
+            Some(id as u8)
+        }
+    }
+
+    /// Get encryption info of the packet or None if the packet is not
+    /// encrypted (or the demuxer does not provide the info).
+    pub fn encryption_info(&self) -> Option<EncryptionInfo> {
+        unsafe {
+            let ptr = ffw_packet_get_encryption_info(self.ptr);
+
+            if ptr.is_null() {
+                None
+            } else {
+                Some(EncryptionInfo::from_raw_ptr(ptr))
+            }
+        }
+    }
+
+    /// Get new encryption init info attached to this packet. The info is
+    /// attached to a packet only if it changes in the middle of a stream.
+    pub fn encryption_init_info(&self) -> Vec<EncryptionInitInfo> {
+        unsafe {
+            let ptr = ffw_packet_get_encryption_init_info(self.ptr);
+
+            if ptr.is_null() {
+                Vec::new()
+            } else {
+                EncryptionInitInfo::from_raw_ptr(ptr)
+            }
+        }
+    }
+
+    /// Get Matroska BlockAdditional data attached to the packet and their
+    /// BlockAddID (if any). The Matroska demuxer attaches the data to
+    /// packets of blocks containing them.
+    pub fn block_additional(&self) -> Option<(u64, &[u8])> {
+        let mut id = 0;
+        let mut data = ptr::null();
+        let mut size = 0;
+
+        let ret =
+            unsafe { ffw_packet_get_block_additional(self.ptr, &mut id, &mut data, &mut size) };
+
+        if ret == 0 {
+            None
+        } else if size <= 0 {
+            Some((id, &[]))
+        } else {
+            unsafe { Some((id, slice::from_raw_parts(data, size as usize))) }
+        }
+    }
+
+    /// Get the alpha channel bitstream attached to the packet (i.e.
+    /// BlockAdditional data with `BLOCK_ADDITIONAL_ID_ALPHA`).
+    pub fn alpha_data(&self) -> Option<&[u8]> {
+        self.block_additional()
+            .filter(|(id, _)| *id == BLOCK_ADDITIONAL_ID_ALPHA)
+            .map(|(_, data)| data)
+    }
+
     /// Get raw pointer.
     pub(crate) fn as_ptr(&self) -> *const c_void {
         self.ptr
@@ -306,6 +572,29 @@ impl Packet {
         }
     }
 
+    /// Get mutable reference to the packet data. If there are other
+    /// references to the packet data, the data will be copied first (i.e.
+    /// other packets sharing the same data will not be affected).
+    ///
+    /// # Panics
+    /// The method panics if the data cannot be copied.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        unsafe {
+            if ffw_packet_make_writable(self.ptr) < 0 {
+                panic!("unable to make the packet data writable");
+            }
+
+            let data = ffw_packet_get_data(self.ptr) as *mut u8;
+            let size = ffw_packet_get_size(self.ptr) as usize;
+
+            if data.is_null() {
+                &mut []
+            } else {
+                slice::from_raw_parts_mut(data, size)
+            }
+        }
+    }
+
     /// Make this packet mutable. If there are no other references to the
     /// packet data, the mutable packet will be created without copying the
     /// data.
@@ -327,7 +616,16 @@ impl Packet {
     }
 }
 
+/// Rescale timestamps of all given packets into a given time base using a
+/// given rounding method.
+pub fn rescale_packets(packets: &mut [Packet], time_base: TimeBase, rounding: Rounding) {
+    for packet in packets {
+        packet.rescale(time_base, rounding);
+    }
+}
+
 impl Clone for Packet {
+    /// Create a new reference to the packet. The packet data are not copied.
     fn clone(&self) -> Packet {
         let ptr = unsafe { ffw_packet_clone(self.ptr) };
 
@@ -350,3 +648,115 @@ impl Drop for Packet {
 
 unsafe impl Send for Packet {}
 unsafe impl Sync for Packet {}
+
+/// Copy given data into a new buffer with enough capacity for a given
+/// padding.
+#[cfg(feature = "bytes")]
+fn copy_with_padding(data: &[u8], padding: usize) -> BytesMut {
+    let mut res = BytesMut::with_capacity(data.len() + padding);
+
+    res.extend_from_slice(data);
+    res
+}
+
+/// Free a packet buffer created from `BytesMut`.
+#[cfg(feature = "bytes")]
+extern "C" fn free_packet_buffer(opaque: *mut c_void, _: *mut u8) {
+    unsafe { drop(Box::from_raw(opaque as *mut BytesMut)) }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "bytes")]
+    use bytes::{Bytes, BytesMut};
+
+    #[cfg(feature = "bytes")]
+    use super::Packet;
+
+    use super::{PacketMut, BLOCK_ADDITIONAL_ID_ALPHA};
+
+    #[test]
+    fn test_packet_clone() {
+        let mut packet = PacketMut::new(4);
+
+        packet.data_mut().copy_from_slice(&[1, 2, 3, 4]);
+
+        let packet = packet.freeze();
+        let mut clone = packet.clone();
+
+        // the data buffer is shared
+        assert_eq!(packet.data().as_ptr(), clone.data().as_ptr());
+
+        // ... until the clone is modified
+        clone.data_mut()[0] = 5;
+
+        assert_ne!(packet.data().as_ptr(), clone.data().as_ptr());
+        assert_eq!(packet.data(), &[1, 2, 3, 4]);
+        assert_eq!(clone.data(), &[5, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_block_additional() {
+        let packet = PacketMut::from([1, 2, 3]).freeze();
+
+        assert!(packet.block_additional().is_none());
+
+        let packet = packet
+            .into_mut()
+            .with_block_additional(2, &[4])
+            .with_block_additional(BLOCK_ADDITIONAL_ID_ALPHA, &[5, 6])
+            .freeze();
+
+        assert_eq!(packet.block_additional(), Some((1, &[5, 6][..])));
+        assert_eq!(packet.clone().alpha_data(), Some(&[5, 6][..]));
+        assert_eq!(packet.data(), &[1, 2, 3]);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_from_shared_bytes() {
+        let data = Bytes::from_static(&[1, 2, 3, 4]);
+
+        let mut packet = Packet::try_from_bytes(data.clone()).unwrap();
+
+        // static data cannot be shared with the packet
+        assert_ne!(packet.data().as_ptr(), data.as_ptr());
+        assert_eq!(packet.data(), &[1, 2, 3, 4]);
+
+        packet.data_mut()[0] = 5;
+
+        assert_eq!(packet.data(), &[5, 2, 3, 4]);
+        assert_eq!(&data[..], &[1, 2, 3, 4]);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_from_unique_bytes() {
+        let mut buffer = BytesMut::with_capacity(1024);
+
+        buffer.extend_from_slice(&[1, 2, 3, 4]);
+
+        let ptr = buffer.as_ptr();
+
+        let mut packet = Packet::try_from_bytes(buffer.freeze()).unwrap();
+
+        // the buffer has enough spare capacity for the padding
+        assert_eq!(packet.data().as_ptr(), ptr);
+
+        // the data is not copied if the packet is the only reference
+        packet.data_mut()[0] = 5;
+
+        assert_eq!(packet.data().as_ptr(), ptr);
+
+        let mut clone = packet.clone();
+
+        assert_eq!(clone.data().as_ptr(), ptr);
+
+        // ... but it is copied if the data is shared
+        clone.data_mut()[1] = 6;
+
+        assert_ne!(clone.data().as_ptr(), ptr);
+        assert_eq!(packet.data(), &[5, 2, 3, 4]);
+        assert_eq!(clone.data(), &[5, 6, 3, 4]);
+    }
+}
@@ -0,0 +1,505 @@
+//! Multi-threaded decoding pipeline.
+//!
+//! The pipeline reads packets using a demuxer running in a separate thread
+//! and decodes every selected stream in its own thread. Decoded frames are
+//! returned in presentation order (across all selected streams) regardless
+//! of the thread scheduling, i.e. the output is deterministic.
+//!
+//! All queues between the threads are bounded, so the pipeline does not run
+//! ahead of the consumer more than necessary. Note that frames of one stream
+//! may still need to be buffered while waiting for frames of another stream
+//! (e.g. in badly interleaved files).
+//!
+//! Dropping the pipeline does not wait for the worker threads. The threads
+//! stop on their own once they notice that the pipeline is gone. This means
+//! that the demuxer (and its IO) may outlive the pipeline until its pending
+//! read returns.
+
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread,
+};
+
+use crate::{
+    codec::{Decoder, Frame, MediaDecoder},
+    format::demuxer::{Demuxer, DemuxerWithStreamInfo},
+    packet::Packet,
+    Error,
+};
+
+/// Message sent from the worker threads to the consumer.
+enum Message {
+    Frame(usize, Frame),
+    End(usize),
+    Error(Option<usize>, Error),
+}
+
+/// Builder for the decoding pipeline.
+pub struct DecodingPipelineBuilder {
+    streams: Option<Vec<usize>>,
+    queue_size: usize,
+    decoder_threads: Option<usize>,
+}
+
+impl DecodingPipelineBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            streams: None,
+            queue_size: 32,
+            decoder_threads: None,
+        }
+    }
+
+    /// Decode a given stream. If no stream is selected, all audio and video
+    /// streams will be decoded.
+    pub fn stream(mut self, stream_index: usize) -> Self {
+        self.streams.get_or_insert_with(Vec::new).push(stream_index);
+        self
+    }
+
+    /// Set capacity of the packet and frame queues. The default is 32.
+    pub fn queue_size(mut self, size: usize) -> Self {
+        self.queue_size = size;
+        self
+    }
+
+    /// Set the number of threads used internally by each decoder (i.e. the
+    /// FFmpeg `threads` option). The FFmpeg default is used if not set.
+    pub fn decoder_threads(mut self, threads: usize) -> Self {
+        self.decoder_threads = Some(threads);
+        self
+    }
+
+    /// Build the pipeline and start all threads.
+    pub fn build<T>(self, demuxer: DemuxerWithStreamInfo<T>) -> Result<DecodingPipeline, Error>
+    where
+        T: Send + 'static,
+    {
+        let streams = demuxer.streams();
+
+        let selected = if let Some(selected) = self.streams {
+            selected
+        } else {
+            streams
+                .iter()
+                .enumerate()
+                .filter(|(_, stream)| {
+                    let params = stream.codec_parameters();
+
+                    params.is_audio_codec() || params.is_video_codec()
+                })
+                .map(|(index, _)| index)
+                .collect()
+        };
+
+        let (output_tx, output_rx) = mpsc::sync_channel(self.queue_size);
+
+        let mut packet_senders = Vec::new();
+        let mut queues = Vec::new();
+
+        packet_senders.resize_with(streams.len(), || None);
+
+        for &stream_index in &selected {
+            let stream = streams
+                .get(stream_index)
+                .ok_or_else(|| Error::new("invalid stream index"))?;
+
+            if packet_senders[stream_index].is_some() {
+                continue;
+            }
+
+            let (packet_tx, packet_rx) = mpsc::sync_channel(self.queue_size);
+
+            let output = output_tx.clone();
+
+            let mut builder = MediaDecoder::from_stream(stream)?;
+
+            if let Some(threads) = self.decoder_threads {
+                builder = builder.set_option("threads", threads);
+            }
+
+            let decoder = builder.build()?;
+
+            // NOTE: the worker threads are detached, see the module docs
+            thread::spawn(move || decoder_thread(decoder, stream_index, packet_rx, output));
+
+            packet_senders[stream_index] = Some(packet_tx);
+
+            queues.push(StreamQueue::new(stream_index));
+        }
+
+        let demuxer = demuxer.into_demuxer();
+
+        thread::spawn(move || demuxer_thread(demuxer, packet_senders, output_tx));
+
+        let res = DecodingPipeline {
+            output: output_rx,
+            queues,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Decoded frames of a single stream.
+struct StreamQueue {
+    stream_index: usize,
+    frames: VecDeque<Frame>,
+    finished: bool,
+}
+
+impl StreamQueue {
+    /// Create a new queue.
+    fn new(stream_index: usize) -> Self {
+        Self {
+            stream_index,
+            frames: VecDeque::new(),
+            finished: false,
+        }
+    }
+}
+
+/// Multi-threaded decoding pipeline.
+pub struct DecodingPipeline {
+    output: Receiver<Message>,
+    queues: Vec<StreamQueue>,
+}
+
+impl DecodingPipeline {
+    /// Get a builder for the decoding pipeline.
+    pub fn builder() -> DecodingPipelineBuilder {
+        DecodingPipelineBuilder::new()
+    }
+
+    /// Take the next frame in presentation order together with its stream
+    /// index. `None` is returned when all selected streams have been
+    /// decoded.
+    pub fn take(&mut self) -> Result<Option<(usize, Frame)>, Error> {
+        loop {
+            if let Some(queue) = self.next_queue() {
+                let frame = queue.frames.pop_front().unwrap();
+
+                return Ok(Some((queue.stream_index, frame)));
+            }
+
+            if self.queues.iter().all(|queue| queue.finished) {
+                return Ok(None);
+            }
+
+            let msg = self.output.recv().ok();
+
+            match msg {
+                Some(Message::Frame(stream_index, frame)) => {
+                    if let Some(queue) = self.find_queue(stream_index) {
+                        queue.frames.push_back(frame);
+                    }
+                }
+                Some(Message::End(stream_index)) => {
+                    if let Some(queue) = self.find_queue(stream_index) {
+                        queue.finished = true;
+                    }
+                }
+                Some(Message::Error(stream_index, err)) => {
+                    if let Some(queue) = stream_index.and_then(|index| self.find_queue(index)) {
+                        queue.finished = true;
+                    }
+
+                    return Err(err);
+                }
+                None => {
+                    // all worker threads are gone
+                    for queue in &mut self.queues {
+                        queue.finished = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Find the queue of a given stream.
+    fn find_queue(&mut self, stream_index: usize) -> Option<&mut StreamQueue> {
+        self.queues
+            .iter_mut()
+            .find(|queue| queue.stream_index == stream_index)
+    }
+
+    /// Get the queue containing the next frame in presentation order. `None`
+    /// is returned if we need to wait for more frames.
+    fn next_queue(&mut self) -> Option<&mut StreamQueue> {
+        let waiting = self
+            .queues
+            .iter()
+            .any(|queue| !queue.finished && queue.frames.is_empty());
+
+        if waiting {
+            return None;
+        }
+
+        let mut res: Option<&mut StreamQueue> = None;
+
+        for queue in &mut self.queues {
+            let pts = match queue.frames.front() {
+                Some(frame) => frame.pts(),
+                None => continue,
+            };
+
+            let earlier = match res.as_ref() {
+                None => true,
+                Some(current) => {
+                    let current = current.frames.front().unwrap().pts();
+
+                    // frames without timestamps are returned as soon as
+                    // possible
+                    !current.is_null() && (pts.is_null() || pts < current)
+                }
+            };
+
+            if earlier {
+                res = Some(queue);
+            }
+        }
+
+        res
+    }
+}
+
+/// Demuxer thread routing packets to the decoder threads.
+fn demuxer_thread<T>(
+    mut demuxer: Demuxer<T>,
+    mut packet_senders: Vec<Option<SyncSender<Packet>>>,
+    output: SyncSender<Message>,
+) {
+    loop {
+        let packet = match demuxer.take() {
+            Ok(Some(packet)) => packet,
+            Ok(None) => return,
+            Err(err) => {
+                let _ = output.send(Message::Error(None, err));
+
+                return;
+            }
+        };
+
+        let stream_index = packet.stream_index();
+
+        if let Some(Some(sender)) = packet_senders.get(stream_index) {
+            // the decoder thread has already finished due to an error or
+            // the pipeline has been dropped
+            if sender.send(packet).is_err() {
+                packet_senders[stream_index] = None;
+
+                if packet_senders.iter().all(|sender| sender.is_none()) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Decoder thread.
+fn decoder_thread<D>(
+    decoder: D,
+    stream_index: usize,
+    packets: Receiver<Packet>,
+    output: SyncSender<Message>,
+) where
+    D: Decoder,
+    D::Frame: Into<Frame>,
+{
+    let msg = match decode(decoder, stream_index, packets, &output) {
+        Ok(()) => Message::End(stream_index),
+        Err(err) => Message::Error(Some(stream_index), err),
+    };
+
+    let _ = output.send(msg);
+}
+
+/// Decode all packets from a given queue.
+fn decode<D>(
+    mut decoder: D,
+    stream_index: usize,
+    packets: Receiver<Packet>,
+    output: &SyncSender<Message>,
+) -> Result<(), Error>
+where
+    D: Decoder,
+    D::Frame: Into<Frame>,
+{
+    for packet in packets {
+        decoder.push(packet)?;
+
+        while let Some(frame) = decoder.take()? {
+            if output
+                .send(Message::Frame(stream_index, frame.into()))
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    decoder.flush()?;
+
+    while let Some(frame) = decoder.take()? {
+        if output
+            .send(Message::Frame(stream_index, frame.into()))
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Cursor, Read},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::DecodingPipeline;
+
+    use crate::{
+        codec::{
+            video::{frame::get_pixel_format, VideoEncoder, VideoFrameMut},
+            Encoder,
+        },
+        format::{
+            demuxer::Demuxer,
+            io::IO,
+            muxer::{Muxer, OutputFormat},
+        },
+        time::{TimeBase, Timestamp},
+    };
+
+    /// Reader blocking forever once armed (until the test finishes).
+    struct BlockingReader {
+        inner: Cursor<Vec<u8>>,
+        armed: Arc<AtomicBool>,
+        release: mpsc::Receiver<()>,
+    }
+
+    impl Read for BlockingReader {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.armed.load(Ordering::SeqCst) {
+                let _ = self.release.recv();
+
+                return Err(io::Error::other("released"));
+            }
+
+            self.inner.read(buffer)
+        }
+    }
+
+    /// Create a short MPEG-TS file with a single MPEG-4 video stream.
+    fn mpegts_file() -> Vec<u8> {
+        let pixel_format = get_pixel_format("yuv420p");
+        let time_base = TimeBase::new(1, 25);
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .time_base(time_base)
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder();
+
+        builder
+            .add_stream(&encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_write_stream(Vec::new()),
+                OutputFormat::find_by_name("mpegts").unwrap(),
+            )
+            .unwrap();
+
+        for i in 0..25 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 48)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(0)).unwrap();
+            }
+        }
+
+        muxer.close().unwrap().into_stream()
+    }
+
+    #[test]
+    fn test_decoding() {
+        let demuxer = Demuxer::builder()
+            .build(IO::from_read_stream(Cursor::new(mpegts_file())))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        let mut pipeline = DecodingPipeline::builder().build(demuxer).unwrap();
+
+        let mut frames = 0;
+
+        while let Some((stream_index, _)) = pipeline.take().unwrap() {
+            assert_eq!(stream_index, 0);
+
+            frames += 1;
+        }
+
+        assert_eq!(frames, 25);
+    }
+
+    #[test]
+    fn test_drop_with_blocked_input() {
+        let armed = Arc::new(AtomicBool::new(false));
+
+        let (release_tx, release_rx) = mpsc::channel();
+
+        let reader = BlockingReader {
+            inner: Cursor::new(mpegts_file()),
+            armed: armed.clone(),
+            release: release_rx,
+        };
+
+        let demuxer = Demuxer::builder()
+            .build(IO::from_read_stream(reader))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        // all further reads will block
+        armed.store(true, Ordering::SeqCst);
+
+        let pipeline = DecodingPipeline::builder().build(demuxer).unwrap();
+
+        let (done_tx, done_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            drop(pipeline);
+
+            let _ = done_tx.send(());
+        });
+
+        assert!(done_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        // unblock the demuxer thread
+        drop(release_tx);
+    }
+}
@@ -0,0 +1,691 @@
+//! Audio playback.
+//!
+//! The audio output resamples decoded audio frames into the format of an
+//! output device and plays them using `cpal`. The resampled audio is kept in
+//! an internal jitter buffer. The playback starts (and resumes after an
+//! underrun) once the buffer contains a given amount of audio.
+//!
+//! The output also provides an audio clock that can be used as the master
+//! clock for audio/video synchronization (i.e. video frames are presented
+//! once the clock reaches their presentation timestamps).
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, OutputCallbackInfo, SizedSample, Stream, StreamConfig,
+};
+
+use crate::{
+    codec::audio::{self, AudioFrame, AudioResampler, ChannelLayout, SampleFormat},
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Builder for the audio output.
+pub struct AudioOutputBuilder {
+    device: Option<String>,
+    buffer_duration: Duration,
+    prefill_duration: Duration,
+}
+
+impl AudioOutputBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            device: None,
+            buffer_duration: Duration::from_millis(500),
+            prefill_duration: Duration::from_millis(100),
+        }
+    }
+
+    /// Use an output device with a given name. The default output device is
+    /// used if the name is not set.
+    pub fn device(mut self, name: &str) -> Self {
+        self.device = Some(name.to_string());
+        self
+    }
+
+    /// Set the maximum amount of buffered audio. Pushing frames into the
+    /// output blocks while the buffer is full. The default is 500 ms.
+    pub fn buffer_duration(mut self, duration: Duration) -> Self {
+        self.buffer_duration = duration;
+        self
+    }
+
+    /// Set the amount of audio that needs to be buffered before the
+    /// playback starts (or resumes after an underrun). The default is
+    /// 100 ms.
+    pub fn prefill_duration(mut self, duration: Duration) -> Self {
+        self.prefill_duration = duration;
+        self
+    }
+
+    /// Open the output device and start the playback.
+    pub fn build(self) -> Result<AudioOutput, Error> {
+        let host = cpal::default_host();
+
+        let device = if let Some(name) = self.device {
+            host.output_devices()
+                .map_err(|err| Error::new(format!("unable to get output devices: {}", err)))?
+                .find(|device| device.name().map(|n| n == name).unwrap_or(false))
+                .ok_or_else(|| Error::new("unknown output device"))?
+        } else {
+            host.default_output_device()
+                .ok_or_else(|| Error::new("no default output device"))?
+        };
+
+        let supported = device.default_output_config().map_err(|err| {
+            Error::new(format!(
+                "unable to get output device configuration: {}",
+                err
+            ))
+        })?;
+
+        let config = supported.config();
+
+        let sample_rate = config.sample_rate.0;
+        let channels = config.channels as usize;
+
+        let channel_layout = ChannelLayout::from_channels(channels as u32)
+            .ok_or_else(|| Error::new("unsupported number of output channels"))?;
+
+        let capacity = duration_to_samples(self.buffer_duration, sample_rate).max(1);
+        let prefill = duration_to_samples(self.prefill_duration, sample_rate).min(capacity);
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                buffer: JitterBuffer::new(channels, capacity, prefill),
+                clock: ClockState::new(),
+                paused: false,
+                error: None,
+            }),
+            cond: Condvar::new(),
+        });
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::I8 => build_stream::<i8>(&device, &config, shared.clone()),
+            cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, shared.clone()),
+            cpal::SampleFormat::I32 => build_stream::<i32>(&device, &config, shared.clone()),
+            cpal::SampleFormat::U8 => build_stream::<u8>(&device, &config, shared.clone()),
+            cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, shared.clone()),
+            cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, shared.clone()),
+            cpal::SampleFormat::F64 => build_stream::<f64>(&device, &config, shared.clone()),
+            _ => Err(Error::new("unsupported output sample format")),
+        }?;
+
+        stream
+            .play()
+            .map_err(|err| Error::new(format!("unable to start the playback: {}", err)))?;
+
+        let res = AudioOutput {
+            stream,
+            shared,
+            resampler: None,
+            sample_rate,
+            channel_layout,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Build an output stream for a given device sample type.
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    shared: Arc<Shared>,
+) -> Result<Stream, Error>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let error_shared = shared.clone();
+
+    let mut samples = Vec::new();
+
+    let data_callback = move |output: &mut [T], info: &OutputCallbackInfo| {
+        let timestamp = info.timestamp();
+
+        let latency = timestamp
+            .playback
+            .duration_since(&timestamp.callback)
+            .unwrap_or_default();
+
+        samples.resize(output.len(), 0f32);
+
+        shared.render(&mut samples, latency);
+
+        for (dst, src) in output.iter_mut().zip(&samples) {
+            *dst = T::from_sample(*src);
+        }
+    };
+
+    let error_callback = move |err| {
+        error_shared.set_error(format!("audio output error: {}", err));
+    };
+
+    device
+        .build_output_stream(config, data_callback, error_callback, None)
+        .map_err(|err| Error::new(format!("unable to open an output stream: {}", err)))
+}
+
+/// Audio output.
+///
+/// Audio frames pushed into the output are resampled into the output device
+/// format. The source format may change between frames. Timestamps of the
+/// pushed frames drive the audio clock.
+///
+/// The output is not `Send` because the underlying audio stream is not
+/// `Send` on some platforms. Use the audio clock to get the playback
+/// position from other threads.
+pub struct AudioOutput {
+    stream: Stream,
+    shared: Arc<Shared>,
+    resampler: Option<(AudioResampler, ChannelLayout, SampleFormat, u32)>,
+    sample_rate: u32,
+    channel_layout: ChannelLayout,
+}
+
+impl AudioOutput {
+    /// Get a builder for the audio output.
+    pub fn builder() -> AudioOutputBuilder {
+        AudioOutputBuilder::new()
+    }
+
+    /// Get the output sample rate.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Get the number of output channels.
+    pub fn channels(&self) -> u32 {
+        self.channel_layout.channels()
+    }
+
+    /// Get the audio clock.
+    pub fn clock(&self) -> AudioClock {
+        AudioClock {
+            shared: self.shared.clone(),
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    /// Push a given frame into the output. The method blocks while the
+    /// jitter buffer is full.
+    pub fn push(&mut self, frame: AudioFrame) -> Result<(), Error> {
+        let channel_layout = frame.channel_layout();
+        let sample_format = frame.sample_format();
+        let sample_rate = frame.sample_rate();
+
+        let reuse = self
+            .resampler
+            .as_ref()
+            .map(|(_, l, f, r)| *l == channel_layout && *f == sample_format && *r == sample_rate)
+            .unwrap_or(false);
+
+        if !reuse {
+            // drain the current resampler before switching to the new format
+            self.flush_resampler()?;
+
+            let resampler = AudioResampler::builder()
+                .source_channel_layout(channel_layout)
+                .source_sample_format(sample_format)
+                .source_sample_rate(sample_rate)
+                .target_channel_layout(self.channel_layout)
+                .target_sample_format(audio::frame::get_sample_format("flt"))
+                .target_sample_rate(self.sample_rate)
+                .build()?;
+
+            self.resampler = Some((resampler, channel_layout, sample_format, sample_rate));
+        }
+
+        self.shared.lock().buffer.set_end_of_stream(false);
+
+        if let Some((resampler, _, _, _)) = self.resampler.as_mut() {
+            resampler.push(frame)?;
+
+            while let Some(frame) = resampler.take()? {
+                self.shared.enqueue(&frame)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flush the output. The method blocks until all buffered audio has
+    /// been played.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.flush_resampler()?;
+
+        let mut state = self.shared.lock();
+
+        state.buffer.set_end_of_stream(true);
+
+        while state.buffer.buffered() > 0 && state.error.is_none() {
+            state = self.shared.cond.wait(state).unwrap();
+        }
+
+        if let Some(err) = state.error.as_ref() {
+            return Err(Error::new(err));
+        }
+
+        Ok(())
+    }
+
+    /// Drop all buffered audio (e.g. after seeking). The audio clock is
+    /// reset as well.
+    pub fn clear(&mut self) {
+        self.resampler = None;
+
+        let mut state = self.shared.lock();
+
+        state.buffer.clear();
+        state.clock = ClockState::new();
+
+        self.shared.cond.notify_all();
+    }
+
+    /// Pause the playback. Note that pushing frames into a paused output
+    /// blocks once the jitter buffer gets full.
+    pub fn pause(&self) {
+        self.shared.lock().paused = true;
+    }
+
+    /// Resume the playback.
+    pub fn resume(&self) {
+        self.shared.lock().paused = false;
+    }
+
+    /// Get the amount of buffered audio.
+    pub fn buffered(&self) -> Duration {
+        let samples = self.shared.lock().buffer.buffered();
+
+        samples_to_duration(samples, self.sample_rate)
+    }
+
+    /// Get the number of buffer underruns since the output was created.
+    pub fn underruns(&self) -> u64 {
+        self.shared.lock().buffer.underruns()
+    }
+
+    /// Flush the current resampler (if any) into the jitter buffer.
+    fn flush_resampler(&mut self) -> Result<(), Error> {
+        if let Some((mut resampler, _, _, _)) = self.resampler.take() {
+            resampler.flush()?;
+
+            while let Some(frame) = resampler.take()? {
+                self.shared.enqueue(&frame)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for AudioOutput {
+    fn drop(&mut self) {
+        let _ = self.stream.pause();
+    }
+}
+
+/// Audio clock.
+///
+/// The clock reports presentation timestamp of the audio sample being
+/// played at the moment, taking the device latency into account. It can be
+/// shared with other threads.
+#[derive(Clone)]
+pub struct AudioClock {
+    shared: Arc<Shared>,
+    sample_rate: u32,
+}
+
+impl AudioClock {
+    /// Get the current playback position. `None` is returned if the
+    /// playback has not started yet.
+    pub fn position(&self) -> Option<Timestamp> {
+        let state = self.shared.lock();
+
+        state.clock.position(self.sample_rate)
+    }
+
+    /// Check if the clock is running (i.e. the output is not paused and
+    /// there is audio to be played).
+    pub fn is_running(&self) -> bool {
+        let state = self.shared.lock();
+
+        !state.paused && state.clock.played > 0
+    }
+}
+
+/// State shared between the output, the audio clock and the device
+/// callback.
+struct Shared {
+    state: Mutex<State>,
+    cond: Condvar,
+}
+
+impl Shared {
+    /// Lock the state.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Put a given resampled frame into the jitter buffer. The method blocks
+    /// while the buffer is full.
+    fn enqueue(&self, frame: &AudioFrame) -> Result<(), Error> {
+        let channels = frame.channels() as usize;
+        let samples = frame.samples();
+
+        let planes = frame.planes();
+
+        let data = planes[0]
+            .data()
+            .chunks_exact(4)
+            .take(channels * samples)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect::<Vec<_>>();
+
+        let pts = frame.pts();
+
+        let pts = if pts.is_null() {
+            None
+        } else {
+            Some(pts.timestamp())
+        };
+
+        let mut state = self.lock();
+
+        while state.buffer.is_full() && state.error.is_none() {
+            state = self.cond.wait(state).unwrap();
+        }
+
+        if let Some(err) = state.error.as_ref() {
+            return Err(Error::new(err));
+        }
+
+        state.buffer.push(data, pts);
+
+        Ok(())
+    }
+
+    /// Fill a given device buffer with interleaved samples.
+    fn render(&self, output: &mut [f32], latency: Duration) {
+        let mut state = self.lock();
+
+        let (start, played) = if state.paused {
+            output.iter_mut().for_each(|sample| *sample = 0.0);
+
+            (state.buffer.position(), 0)
+        } else {
+            let start = state.buffer.position();
+            let played = state.buffer.read(output);
+
+            (start, played)
+        };
+
+        state.clock.update(start, played, latency);
+
+        self.cond.notify_all();
+    }
+
+    /// Store a given stream error.
+    fn set_error(&self, err: String) {
+        self.lock().error = Some(err);
+
+        self.cond.notify_all();
+    }
+}
+
+/// Playback state.
+struct State {
+    buffer: JitterBuffer,
+    clock: ClockState,
+    paused: bool,
+    error: Option<String>,
+}
+
+/// Audio clock state updated on every device callback.
+struct ClockState {
+    pts: Option<i64>,
+    played: usize,
+    instant: Instant,
+    latency: Duration,
+}
+
+impl ClockState {
+    /// Create a new clock state.
+    fn new() -> Self {
+        Self {
+            pts: None,
+            played: 0,
+            instant: Instant::now(),
+            latency: Duration::from_secs(0),
+        }
+    }
+
+    /// Update the clock state. The start position is the position of the
+    /// first sample passed to the device. Played is the number of samples
+    /// (per channel) passed to the device.
+    fn update(&mut self, start: Option<i64>, played: usize, latency: Duration) {
+        let pts = start.or_else(|| self.pts.map(|pts| pts + self.played as i64));
+
+        self.pts = pts;
+        self.played = played;
+        self.instant = Instant::now();
+        self.latency = latency;
+    }
+
+    /// Get the current position.
+    fn position(&self, sample_rate: u32) -> Option<Timestamp> {
+        let pts = self.pts?;
+
+        let start = Timestamp::new(pts, TimeBase::new(1, sample_rate)).as_micros()?;
+
+        let elapsed = self.instant.elapsed().as_micros() as i64;
+        let latency = self.latency.as_micros() as i64;
+        let played = samples_to_duration(self.played, sample_rate).as_micros() as i64;
+
+        let offset = (elapsed - latency).min(played);
+
+        Some(Timestamp::from_micros(start + offset))
+    }
+}
+
+/// A chunk of interleaved samples.
+struct Chunk {
+    data: Vec<f32>,
+    offset: usize,
+    pts: i64,
+}
+
+/// Jitter buffer of interleaved samples. All positions and sizes are in
+/// samples per channel.
+struct JitterBuffer {
+    chunks: VecDeque<Chunk>,
+    channels: usize,
+    capacity: usize,
+    prefill: usize,
+    buffered: usize,
+    next_pts: i64,
+    buffering: bool,
+    end_of_stream: bool,
+    underruns: u64,
+}
+
+impl JitterBuffer {
+    /// Create a new jitter buffer.
+    fn new(channels: usize, capacity: usize, prefill: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            channels,
+            capacity,
+            prefill,
+            buffered: 0,
+            next_pts: 0,
+            buffering: true,
+            end_of_stream: false,
+            underruns: 0,
+        }
+    }
+
+    /// Get the number of buffered samples.
+    fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    /// Get the number of buffer underruns.
+    fn underruns(&self) -> u64 {
+        self.underruns
+    }
+
+    /// Check if the buffer is full.
+    fn is_full(&self) -> bool {
+        self.buffered >= self.capacity
+    }
+
+    /// Set the end of stream flag. The buffer does not wait for the prefill
+    /// at the end of the stream.
+    fn set_end_of_stream(&mut self, end_of_stream: bool) {
+        self.end_of_stream = end_of_stream;
+    }
+
+    /// Get position of the next sample to be read.
+    fn position(&self) -> Option<i64> {
+        self.chunks
+            .front()
+            .map(|chunk| chunk.pts + (chunk.offset / self.channels) as i64)
+    }
+
+    /// Push given interleaved samples. Samples without a timestamp
+    /// continue after the previous ones.
+    fn push(&mut self, data: Vec<f32>, pts: Option<i64>) {
+        let samples = data.len() / self.channels;
+
+        if samples == 0 {
+            return;
+        }
+
+        let pts = pts.unwrap_or(self.next_pts);
+
+        self.next_pts = pts + samples as i64;
+        self.buffered += samples;
+
+        self.chunks.push_back(Chunk {
+            data,
+            offset: 0,
+            pts,
+        });
+    }
+
+    /// Read interleaved samples into a given buffer. The rest of the buffer
+    /// is filled with silence. The method returns the number of samples
+    /// (per channel) read.
+    fn read(&mut self, output: &mut [f32]) -> usize {
+        output.iter_mut().for_each(|sample| *sample = 0.0);
+
+        if self.buffering {
+            if self.buffered >= self.prefill || (self.end_of_stream && self.buffered > 0) {
+                self.buffering = false;
+            } else {
+                return 0;
+            }
+        }
+
+        let mut written = 0;
+
+        while written < output.len() {
+            let chunk = match self.chunks.front_mut() {
+                Some(chunk) => chunk,
+                None => break,
+            };
+
+            let available = &chunk.data[chunk.offset..];
+
+            let len = available.len().min(output.len() - written);
+
+            output[written..written + len].copy_from_slice(&available[..len]);
+
+            chunk.offset += len;
+            written += len;
+
+            if chunk.offset >= chunk.data.len() {
+                self.chunks.pop_front();
+            }
+        }
+
+        let samples = written / self.channels;
+
+        self.buffered -= samples;
+
+        if written < output.len() && !self.end_of_stream {
+            self.buffering = true;
+            self.underruns += 1;
+        }
+
+        samples
+    }
+
+    /// Drop all buffered samples.
+    fn clear(&mut self) {
+        self.chunks.clear();
+        self.buffered = 0;
+        self.next_pts = 0;
+        self.buffering = true;
+        self.end_of_stream = false;
+    }
+}
+
+/// Convert a given duration into the number of samples.
+fn duration_to_samples(duration: Duration, sample_rate: u32) -> usize {
+    (duration.as_micros() * sample_rate as u128 / 1_000_000) as usize
+}
+
+/// Convert a given number of samples into duration.
+fn samples_to_duration(samples: usize, sample_rate: u32) -> Duration {
+    Duration::from_micros((samples as u64 * 1_000_000) / sample_rate as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JitterBuffer;
+
+    #[test]
+    fn test_jitter_buffer() {
+        let mut buffer = JitterBuffer::new(2, 8, 4);
+
+        let mut output = [1f32; 4];
+
+        // waiting for the prefill
+        buffer.push(vec![0.5; 4], Some(100));
+
+        assert_eq!(buffer.read(&mut output), 0);
+        assert_eq!(output, [0.0; 4]);
+
+        buffer.push(vec![0.25; 4], None);
+
+        assert_eq!(buffer.position(), Some(100));
+        assert_eq!(buffer.read(&mut output), 2);
+        assert_eq!(output, [0.5; 4]);
+        assert_eq!(buffer.position(), Some(102));
+
+        // underrun
+        let mut output = [1f32; 6];
+
+        assert_eq!(buffer.read(&mut output), 2);
+        assert_eq!(output, [0.25, 0.25, 0.25, 0.25, 0.0, 0.0]);
+        assert_eq!(buffer.underruns(), 1);
+        assert_eq!(buffer.position(), None);
+
+        // the end of stream does not wait for the prefill
+        buffer.push(vec![0.75; 2], None);
+        buffer.set_end_of_stream(true);
+
+        assert_eq!(buffer.position(), Some(104));
+        assert_eq!(buffer.read(&mut output), 1);
+        assert_eq!(buffer.underruns(), 1);
+    }
+}
@@ -0,0 +1,273 @@
+//! Progress reporting.
+//!
+//! Long running pipelines (e.g. the `Transcoder`) report their progress
+//! using the `Progress` struct. The progress can be either received in a
+//! callback or polled at any time (from any thread) using a
+//! `ProgressHandle`.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::time::Timestamp;
+
+/// Snapshot of a pipeline progress.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    elapsed: Duration,
+    processed: Duration,
+    duration: Option<Duration>,
+    positions: Vec<Timestamp>,
+    frames: u64,
+    bytes: u64,
+    finished: bool,
+}
+
+impl Progress {
+    /// Get the wall-clock time elapsed since the processing started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Get duration of the media processed so far.
+    pub fn processed(&self) -> Duration {
+        self.processed
+    }
+
+    /// Get total duration of the media (if known).
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Get timestamp of the last packet of a given output stream. `None` is
+    /// returned only if the stream index is out of range. If no packet of
+    /// the stream has been processed yet, the returned timestamp is null.
+    pub fn stream_position(&self, stream_index: usize) -> Option<Timestamp> {
+        self.positions.get(stream_index).copied()
+    }
+
+    /// Get timestamps of the last packets of all output streams.
+    pub fn stream_positions(&self) -> &[Timestamp] {
+        &self.positions
+    }
+
+    /// Get the number of video frames processed so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Get the number of output bytes produced so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Get the average number of video frames processed per second of the
+    /// wall-clock time.
+    pub fn fps(&self) -> Option<f64> {
+        let elapsed = self.elapsed.as_secs_f64();
+
+        if elapsed > 0.0 {
+            Some(self.frames as f64 / elapsed)
+        } else {
+            None
+        }
+    }
+
+    /// Get the average output bit rate (in bits per second of the processed
+    /// media).
+    pub fn bit_rate(&self) -> Option<u64> {
+        let processed = self.processed.as_secs_f64();
+
+        if processed > 0.0 {
+            Some((self.bytes as f64 * 8.0 / processed) as u64)
+        } else {
+            None
+        }
+    }
+
+    /// Get the processing speed relative to real time (e.g. 2.0 means that
+    /// two seconds of the media are processed every second).
+    pub fn speed(&self) -> Option<f64> {
+        let elapsed = self.elapsed.as_secs_f64();
+
+        if elapsed > 0.0 {
+            Some(self.processed.as_secs_f64() / elapsed)
+        } else {
+            None
+        }
+    }
+
+    /// Get the progress as a number between 0 and 1 (if the total duration
+    /// is known).
+    pub fn fraction(&self) -> Option<f64> {
+        if self.finished {
+            return Some(1.0);
+        }
+
+        let duration = self.duration?.as_secs_f64();
+
+        if duration > 0.0 {
+            Some((self.processed.as_secs_f64() / duration).clamp(0.0, 1.0))
+        } else {
+            None
+        }
+    }
+
+    /// Get the estimated wall-clock time remaining until the processing is
+    /// finished (if the total duration is known).
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.finished {
+            return Some(Duration::from_secs(0));
+        }
+
+        let duration = self.duration?;
+        let speed = self.speed()?;
+
+        if speed > 0.0 {
+            let remaining = duration.checked_sub(self.processed).unwrap_or_default();
+
+            Some(Duration::from_secs_f64(remaining.as_secs_f64() / speed))
+        } else {
+            None
+        }
+    }
+
+    /// Check if the processing has finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// Internal progress state.
+struct ProgressState {
+    started: Instant,
+    stopped: Option<Instant>,
+    start: Option<Timestamp>,
+    duration: Option<Duration>,
+    positions: Vec<Timestamp>,
+    video: Vec<bool>,
+    frames: u64,
+    bytes: u64,
+    finished: bool,
+}
+
+impl ProgressState {
+    /// Get the current progress.
+    fn progress(&self) -> Progress {
+        let now = self.stopped.unwrap_or_else(Instant::now);
+
+        let mut processed = 0;
+
+        for pts in &self.positions {
+            let start = self.start.and_then(|start| start.as_micros()).unwrap_or(0);
+
+            if let Some(pts) = pts.as_micros() {
+                processed = processed.max(pts - start);
+            }
+        }
+
+        Progress {
+            elapsed: now - self.started,
+            processed: Duration::from_micros(processed as u64),
+            duration: self.duration,
+            positions: self.positions.clone(),
+            frames: self.frames,
+            bytes: self.bytes,
+            finished: self.finished,
+        }
+    }
+}
+
+/// Progress tracker updated by the pipeline.
+pub(crate) struct ProgressTracker {
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl ProgressTracker {
+    /// Create a new tracker.
+    ///
+    /// # Arguments
+    /// * `video` - flags marking video streams (used for frame counting)
+    /// * `start` - start timestamp of the media (if known)
+    /// * `duration` - total duration of the media (if known)
+    pub fn new(video: Vec<bool>, start: Option<Timestamp>, duration: Option<Duration>) -> Self {
+        let state = ProgressState {
+            started: Instant::now(),
+            stopped: None,
+            start,
+            duration,
+            positions: vec![Timestamp::null(); video.len()],
+            video,
+            frames: 0,
+            bytes: 0,
+            finished: false,
+        };
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Get a handle for polling the progress.
+    pub fn handle(&self) -> ProgressHandle {
+        ProgressHandle {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Restart the wall-clock timer.
+    pub fn start(&self) {
+        self.state.lock().unwrap().started = Instant::now();
+    }
+
+    /// Register a given output packet and return the current progress.
+    pub fn update(&self, stream_index: usize, pts: Timestamp, size: usize) -> Progress {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(position) = state.positions.get_mut(stream_index) {
+            if !pts.is_null() && (position.is_null() || pts > *position) {
+                *position = pts;
+            }
+        }
+
+        if state.video.get(stream_index).copied().unwrap_or(false) {
+            state.frames += 1;
+        }
+
+        state.bytes += size as u64;
+
+        state.progress()
+    }
+
+    /// Mark the processing as finished and return the final progress.
+    pub fn finish(&self) -> Progress {
+        let mut state = self.state.lock().unwrap();
+
+        state.stopped = Some(Instant::now());
+        state.finished = true;
+
+        state.progress()
+    }
+}
+
+/// Handle for polling progress of a pipeline.
+///
+/// The handle can be cloned and sent to other threads (e.g. to a UI thread
+/// rendering a progress bar).
+#[derive(Clone)]
+pub struct ProgressHandle {
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl ProgressHandle {
+    /// Get the current progress.
+    pub fn progress(&self) -> Progress {
+        self.state.lock().unwrap().progress()
+    }
+
+    /// Check if the processing has finished.
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished
+    }
+}
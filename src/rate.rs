@@ -0,0 +1,253 @@
+//! Playback rate conversion.
+//!
+//! The rate converter changes playback speed of a video/audio stream. Video
+//! frame timestamps are rescaled and audio is time-stretched using the
+//! `atempo` filter, so the pitch is preserved. A single `atempo` filter
+//! supports only tempo factors between 0.5 and 2.0. Other rates are
+//! achieved by chaining multiple `atempo` filters.
+//!
+//! Timestamps of the output audio frames are derived from the first input
+//! audio frame and the number of output samples, so they are consistent
+//! with the rescaled video timestamps.
+
+use crate::{
+    codec::{
+        audio::{AudioFilter, AudioFrame},
+        video::VideoFrame,
+        AudioCodecParameters, Filter,
+    },
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Builder for the rate converter.
+pub struct RateConverterBuilder {
+    rate: f64,
+    audio: Option<AudioCodecParameters>,
+}
+
+impl RateConverterBuilder {
+    /// Create a new builder.
+    fn new(rate: f64) -> Self {
+        Self { rate, audio: None }
+    }
+
+    /// Enable audio conversion for audio frames described by given codec
+    /// parameters. Note that the sample format, sample rate and channel
+    /// layout must match the frames pushed into the converter (i.e. the
+    /// decoder output).
+    pub fn audio(mut self, codec_parameters: &AudioCodecParameters) -> Self {
+        self.audio = Some(codec_parameters.clone());
+        self
+    }
+
+    /// Build the rate converter.
+    pub fn build(self) -> Result<RateConverter, Error> {
+        if !self.rate.is_finite() || self.rate <= 0.0 {
+            return Err(Error::new("invalid playback rate"));
+        }
+
+        let audio = if let Some(codec_parameters) = self.audio {
+            let description = atempo_chain(self.rate)
+                .iter()
+                .map(|tempo| format!("atempo={}", tempo))
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let description = if description.is_empty() {
+                "anull".to_string()
+            } else {
+                description
+            };
+
+            let filter = AudioFilter::new(&codec_parameters, &description)?;
+
+            Some(AudioRateConverter {
+                filter,
+                sample_rate: codec_parameters.sample_rate(),
+                origin: None,
+                samples: 0,
+            })
+        } else {
+            None
+        };
+
+        let res = RateConverter {
+            rate: self.rate,
+            audio,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Rate converter.
+///
+/// # Audio conversion
+/// 1. Push an audio frame to the converter.
+/// 2. Take all frames from the converter until you get None.
+/// 3. If there are more frames to be converted, continue with 1.
+/// 4. Flush the converter.
+/// 5. Take all frames from the converter until you get None.
+///
+/// The converter cannot be used anymore once it has been flushed (e.g.
+/// create a new one after seeking).
+pub struct RateConverter {
+    rate: f64,
+    audio: Option<AudioRateConverter>,
+}
+
+impl RateConverter {
+    /// Create a new rate converter for video only. A rate of 2.0 means
+    /// twice the normal speed.
+    pub fn new(rate: f64) -> Result<Self, Error> {
+        RateConverterBuilder::new(rate).build()
+    }
+
+    /// Get a builder for a rate converter with a given rate. A rate of 2.0
+    /// means twice the normal speed.
+    pub fn builder(rate: f64) -> RateConverterBuilder {
+        RateConverterBuilder::new(rate)
+    }
+
+    /// Get the playback rate.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Rescale a given timestamp. The result is in microseconds.
+    pub fn convert_timestamp(&self, timestamp: Timestamp) -> Timestamp {
+        if let Some(micros) = timestamp.as_micros() {
+            Timestamp::from_micros((micros as f64 / self.rate).round() as i64)
+        } else {
+            Timestamp::null()
+        }
+    }
+
+    /// Rescale timestamp of a given video frame. The returned frame will be
+    /// in microsecond time base.
+    pub fn convert_video_frame(&self, frame: VideoFrame) -> VideoFrame {
+        let pts = self.convert_timestamp(frame.pts());
+
+        frame.with_time_base(TimeBase::MICROSECONDS).with_pts(pts)
+    }
+
+    /// Push a given audio frame to the converter.
+    ///
+    /// # Panics
+    /// The method panics if the converter was not created for audio or if
+    /// the operation is not expected (i.e. another operation needs to be
+    /// done).
+    pub fn push_audio_frame(&mut self, frame: AudioFrame) -> Result<(), Error> {
+        let rate = self.rate;
+
+        let audio = self
+            .audio
+            .as_mut()
+            .expect("the rate converter was not created for audio");
+
+        if audio.origin.is_none() {
+            let pts = frame.pts();
+
+            if !pts.is_null() {
+                let pts = Timestamp::new(
+                    (pts.timestamp() as f64 / rate).round() as i64,
+                    pts.time_base(),
+                );
+
+                let tb = TimeBase::new(1, audio.sample_rate);
+
+                audio.origin = Some(pts.with_time_base(tb).timestamp());
+            }
+        }
+
+        audio.filter.push(frame)
+    }
+
+    /// Flush the audio converter.
+    ///
+    /// # Panics
+    /// The method panics if the converter was not created for audio or if
+    /// the operation is not expected (i.e. another operation needs to be
+    /// done).
+    pub fn flush_audio(&mut self) -> Result<(), Error> {
+        self.audio
+            .as_mut()
+            .expect("the rate converter was not created for audio")
+            .filter
+            .flush()
+    }
+
+    /// Take the next converted audio frame (if available). Timestamps of the
+    /// output frames are in 1 / sample_rate time base.
+    ///
+    /// # Panics
+    /// The method panics if the converter was not created for audio.
+    pub fn take_audio_frame(&mut self) -> Result<Option<AudioFrame>, Error> {
+        let audio = self
+            .audio
+            .as_mut()
+            .expect("the rate converter was not created for audio");
+
+        let frame = match audio.filter.take()? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let tb = TimeBase::new(1, audio.sample_rate);
+
+        let pts = audio.origin.unwrap_or(0) + audio.samples as i64;
+
+        audio.samples += frame.samples() as u64;
+
+        let frame = frame.with_time_base(tb).with_pts(Timestamp::new(pts, tb));
+
+        Ok(Some(frame))
+    }
+}
+
+/// Audio part of the rate converter.
+struct AudioRateConverter {
+    filter: AudioFilter,
+    sample_rate: u32,
+    origin: Option<i64>,
+    samples: u64,
+}
+
+/// Split a given rate into a chain of tempo factors supported by the
+/// `atempo` filter (i.e. between 0.5 and 2.0).
+fn atempo_chain(mut rate: f64) -> Vec<f64> {
+    let mut res = Vec::new();
+
+    while rate > 2.0 {
+        res.push(2.0);
+        rate /= 2.0;
+    }
+
+    while rate < 0.5 {
+        res.push(0.5);
+        rate /= 0.5;
+    }
+
+    if (rate - 1.0).abs() > f64::EPSILON {
+        res.push(rate);
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::atempo_chain;
+
+    #[test]
+    fn test_atempo_chain() {
+        assert_eq!(atempo_chain(1.0), Vec::<f64>::new());
+        assert_eq!(atempo_chain(1.5), vec![1.5]);
+        assert_eq!(atempo_chain(2.0), vec![2.0]);
+        assert_eq!(atempo_chain(3.0), vec![2.0, 1.5]);
+        assert_eq!(atempo_chain(8.0), vec![2.0, 2.0, 2.0]);
+        assert_eq!(atempo_chain(0.25), vec![0.5, 0.5]);
+        assert_eq!(atempo_chain(0.3), vec![0.5, 0.6]);
+    }
+}
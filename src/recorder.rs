@@ -0,0 +1,656 @@
+//! Segmented stream-copy recorder.
+//!
+//! The recorder writes packets (e.g. packets from a live input) into a
+//! sequence of files. A new file is started at a key frame once the current
+//! file reaches a given duration or size, so that every file can be played
+//! independently. Files are written under a temporary name (with the
+//! `.part` suffix) and renamed once they are complete, so that other
+//! processes never see incomplete files.
+//!
+//! Optionally, the recorder maintains an HLS playlist referencing the
+//! completed files (this makes sense for MPEG-TS files only).
+//!
+//! # Example
+//! ```ignore
+//! let mut builder = Recorder::builder();
+//!
+//! for stream in demuxer.streams() {
+//!     builder.add_stream(&stream.codec_parameters());
+//! }
+//!
+//! let mut recorder = builder
+//!     .segment_duration(Duration::from_secs(60))
+//!     .playlist("recordings/camera.m3u8")
+//!     .on_segment(|segment| println!("recorded {}", segment.path().display()))
+//!     .build("recordings/camera_%06d.ts")?;
+//!
+//! while let Some(packet) = demuxer.take()? {
+//!     recorder.push(packet)?;
+//! }
+//!
+//! recorder.finish()?;
+//! ```
+
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    codec::{CodecParameters, MediaType},
+    format::{
+        io::IO,
+        muxer::{Muxer, OutputFormat},
+    },
+    packet::Packet,
+    time::Timestamp,
+    Error,
+};
+
+/// Suffix of files that are being written.
+const PART_SUFFIX: &str = ".part";
+
+/// Create an error from a given IO error.
+fn io_error(msg: &str, err: io::Error) -> Error {
+    Error::new(format!("{}: {}", msg, err)).with_source(err)
+}
+
+/// Replace the first `%d` (or `%0Nd`) placeholder in a given pattern with a
+/// given number. `None` is returned if the pattern does not contain any
+/// placeholder.
+fn format_file_name(pattern: &str, number: u64) -> Option<String> {
+    let mut start = 0;
+
+    while let Some(offset) = pattern[start..].find('%') {
+        let begin = start + offset;
+        let spec = &pattern[begin + 1..];
+
+        let digits = spec.bytes().take_while(|b| b.is_ascii_digit()).count();
+
+        if spec[digits..].starts_with('d') && (digits == 0 || spec.starts_with('0')) {
+            let width = spec[..digits].parse::<usize>().unwrap_or(0);
+
+            let res = format!(
+                "{}{:0width$}{}",
+                &pattern[..begin],
+                number,
+                &spec[digits + 1..],
+                width = width
+            );
+
+            return Some(res);
+        }
+
+        start = begin + 1;
+    }
+
+    None
+}
+
+/// Get path of the temporary file for a given file.
+fn part_path(path: &Path) -> PathBuf {
+    let mut res = path.as_os_str().to_owned();
+
+    res.push(PART_SUFFIX);
+
+    PathBuf::from(res)
+}
+
+/// Write a given file atomically.
+fn write_atomically(path: &Path, content: &str) -> Result<(), Error> {
+    let tmp = part_path(path);
+
+    fs::write(&tmp, content).map_err(|err| io_error("unable to write a file", err))?;
+    fs::rename(&tmp, path).map_err(|err| io_error("unable to rename a file", err))
+}
+
+/// Type of the completed file callback.
+type SegmentCallback = dyn FnMut(&RecordedSegment) + Send;
+
+/// Completed file.
+#[derive(Debug, Clone)]
+pub struct RecordedSegment {
+    path: PathBuf,
+    number: u64,
+    start: Timestamp,
+    duration: Duration,
+    size: u64,
+}
+
+impl RecordedSegment {
+    /// Get path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get sequence number of the file.
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Get timestamp of the first packet in the file (null if none of the
+    /// packets had a timestamp).
+    pub fn start(&self) -> Timestamp {
+        self.start
+    }
+
+    /// Get duration of the file. The duration is measured between the
+    /// first packet of the file and the first packet of the next file (or
+    /// the last packet of the file if it is the last one).
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Get size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Builder for the recorder.
+pub struct RecorderBuilder {
+    streams: Vec<CodecParameters>,
+    output_format: Option<OutputFormat>,
+    segment_duration: Duration,
+    segment_size: Option<u64>,
+    key_stream: Option<usize>,
+    start_number: u64,
+    playlist: Option<PathBuf>,
+    on_segment: Option<Box<SegmentCallback>>,
+}
+
+impl RecorderBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            streams: Vec::new(),
+            output_format: None,
+            segment_duration: Duration::from_secs(10),
+            segment_size: None,
+            key_stream: None,
+            start_number: 0,
+            playlist: None,
+            on_segment: None,
+        }
+    }
+
+    /// Add a new stream with given parameters. The index of the new stream
+    /// is returned. Streams of every file are created in the same order.
+    pub fn add_stream(&mut self, params: &CodecParameters) -> usize {
+        self.streams.push(params.clone());
+        self.streams.len() - 1
+    }
+
+    /// Set the output format. By default, the format is guessed from the
+    /// file name pattern.
+    pub fn output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = Some(format);
+        self
+    }
+
+    /// Set the target duration of a single file. A new file is started at
+    /// the first key frame after the duration is reached, so the files will
+    /// be longer if key frames are sparse. The default is 10 seconds.
+    pub fn segment_duration(mut self, duration: Duration) -> Self {
+        self.segment_duration = duration;
+        self
+    }
+
+    /// Set the target size of a single file in bytes. A new file is started
+    /// at the first key frame after the size is reached. The size is not
+    /// limited by default.
+    pub fn segment_size(mut self, bytes: u64) -> Self {
+        self.segment_size = Some(bytes);
+        self
+    }
+
+    /// Set index of the stream whose key frames are used as split points.
+    /// By default, the first video stream is used (or key packets of all
+    /// streams if there is no video stream).
+    pub fn key_stream(mut self, stream_index: usize) -> Self {
+        self.key_stream = Some(stream_index);
+        self
+    }
+
+    /// Set number of the first file. The default is 0.
+    pub fn start_number(mut self, number: u64) -> Self {
+        self.start_number = number;
+        self
+    }
+
+    /// Maintain an HLS playlist at a given path. The playlist is rewritten
+    /// (atomically) every time a file is completed. The files are referenced
+    /// by their file names, so the playlist needs to be in the same
+    /// directory as the files.
+    pub fn playlist<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.playlist = Some(path.into());
+        self
+    }
+
+    /// Set a closure that will be called for every completed file (i.e.
+    /// after the file was renamed to its final name).
+    pub fn on_segment<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&RecordedSegment) + Send + 'static,
+    {
+        self.on_segment = Some(Box::new(callback));
+        self
+    }
+
+    /// Build the recorder. The file names are created from a given pattern
+    /// by replacing the `%d` (or `%0Nd`) placeholder with the file number
+    /// (e.g. `segment_%05d.ts`). The first file is created when the first
+    /// key frame is pushed.
+    pub fn build(self, pattern: &str) -> Result<Recorder, Error> {
+        if self.streams.is_empty() {
+            return Err(Error::new("no streams"));
+        }
+
+        if format_file_name(pattern, 0).is_none() {
+            return Err(Error::new("the file name pattern has no placeholder"));
+        }
+
+        let output_format = match self.output_format {
+            Some(format) => format,
+            None => OutputFormat::guess_from_file_name(pattern)
+                .ok_or_else(|| Error::new("unable to guess the output format"))?,
+        };
+
+        let key_stream = match self.key_stream {
+            Some(index) if index >= self.streams.len() => {
+                return Err(Error::new("invalid stream index"))
+            }
+            Some(index) => Some(index),
+            None => self
+                .streams
+                .iter()
+                .position(|params| params.media_type() == MediaType::Video),
+        };
+
+        let start_number = self.start_number;
+
+        let res = Recorder {
+            pattern: pattern.to_string(),
+            output_format: output_format.name(),
+            streams: self.streams,
+            segment_duration: self.segment_duration.as_micros() as i64,
+            segment_size: self.segment_size,
+            key_stream,
+            next_number: start_number,
+            playlist: self.playlist.map(|path| Playlist {
+                path,
+                media_sequence: start_number,
+                segments: Vec::new(),
+            }),
+            on_segment: self.on_segment,
+            current: None,
+        };
+
+        Ok(res)
+    }
+}
+
+/// HLS playlist.
+struct Playlist {
+    path: PathBuf,
+    media_sequence: u64,
+    segments: Vec<(String, Duration)>,
+}
+
+impl Playlist {
+    /// Add a given segment and rewrite the playlist.
+    fn add(&mut self, segment: &RecordedSegment) -> Result<(), Error> {
+        let name = segment
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        self.segments.push((name, segment.duration));
+
+        self.write(false)
+    }
+
+    /// Write the playlist.
+    fn write(&self, end: bool) -> Result<(), Error> {
+        let target_duration = self
+            .segments
+            .iter()
+            .map(|(_, duration)| duration.as_secs_f64().ceil() as u64)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let mut content = String::new();
+
+        let _ = writeln!(content, "#EXTM3U");
+        let _ = writeln!(content, "#EXT-X-VERSION:3");
+        let _ = writeln!(content, "#EXT-X-TARGETDURATION:{}", target_duration);
+        let _ = writeln!(content, "#EXT-X-MEDIA-SEQUENCE:{}", self.media_sequence);
+
+        for (name, duration) in &self.segments {
+            let _ = writeln!(content, "#EXTINF:{:.3},", duration.as_secs_f64());
+            let _ = writeln!(content, "{}", name);
+        }
+
+        if end {
+            let _ = writeln!(content, "#EXT-X-ENDLIST");
+        }
+
+        write_atomically(&self.path, &content)
+    }
+}
+
+/// File that is being written.
+struct Segment {
+    muxer: Muxer<File>,
+    path: PathBuf,
+    number: u64,
+    start: Timestamp,
+    first: Option<i64>,
+    last: Option<i64>,
+}
+
+impl Segment {
+    /// Get the current size of the file.
+    fn size(&self) -> u64 {
+        self.muxer.io().position()
+    }
+}
+
+/// Segmented stream-copy recorder.
+///
+/// Packets pushed into the recorder are muxed without any modification
+/// (i.e. their timestamps are kept). Packets preceding the first key frame
+/// of the key stream are dropped, so that every file starts with a key
+/// frame.
+pub struct Recorder {
+    pattern: String,
+    output_format: &'static str,
+    streams: Vec<CodecParameters>,
+    segment_duration: i64,
+    segment_size: Option<u64>,
+    key_stream: Option<usize>,
+    next_number: u64,
+    playlist: Option<Playlist>,
+    on_segment: Option<Box<SegmentCallback>>,
+    current: Option<Segment>,
+}
+
+impl Recorder {
+    /// Get a builder for the recorder.
+    pub fn builder() -> RecorderBuilder {
+        RecorderBuilder::new()
+    }
+
+    /// Push a given packet into the recorder.
+    pub fn push(&mut self, packet: Packet) -> Result<(), Error> {
+        let timestamp = if packet.dts().is_null() {
+            packet.pts()
+        } else {
+            packet.dts()
+        };
+
+        let micros = timestamp.as_micros();
+
+        let split_point = packet.is_key()
+            && self
+                .key_stream
+                .map(|index| index == packet.stream_index())
+                .unwrap_or(true);
+
+        if split_point {
+            if let Some(segment) = self.current.as_ref() {
+                if self.is_complete(segment, micros) {
+                    self.finish_segment(micros)?;
+                }
+            }
+        }
+
+        if self.current.is_none() {
+            if !split_point {
+                return Ok(());
+            }
+
+            self.current = Some(self.start_segment(timestamp)?);
+        }
+
+        let segment = self.current.as_mut().unwrap();
+
+        if let Some(t) = micros {
+            segment.first.get_or_insert(t);
+            segment.last = Some(segment.last.map_or(t, |last| last.max(t)));
+        }
+
+        segment.muxer.push(packet)
+    }
+
+    /// Close the current file and write the final playlist (if any).
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.finish_segment(None)?;
+
+        if let Some(playlist) = self.playlist.as_ref() {
+            playlist.write(true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if a given segment should be completed before a key frame with
+    /// a given timestamp.
+    fn is_complete(&self, segment: &Segment, timestamp: Option<i64>) -> bool {
+        if let Some(limit) = self.segment_size {
+            if segment.size() >= limit {
+                return true;
+            }
+        }
+
+        match (segment.first, timestamp) {
+            (Some(first), Some(t)) => t - first >= self.segment_duration,
+            _ => false,
+        }
+    }
+
+    /// Start a new file.
+    fn start_segment(&mut self, start: Timestamp) -> Result<Segment, Error> {
+        let number = self.next_number;
+
+        // the pattern is checked by the builder
+        let path = PathBuf::from(format_file_name(&self.pattern, number).unwrap());
+
+        let file = File::create(part_path(&path))
+            .map_err(|err| io_error("unable to create a file", err))?;
+
+        let mut builder = Muxer::builder();
+
+        for params in &self.streams {
+            builder.add_stream(params)?;
+        }
+
+        let output_format = OutputFormat::find_by_name(self.output_format)
+            .ok_or_else(|| Error::new("unknown output format"))?;
+
+        let muxer = builder.build(IO::from_seekable_write_stream(file), output_format)?;
+
+        self.next_number += 1;
+
+        let res = Segment {
+            muxer,
+            path,
+            number,
+            start,
+            first: None,
+            last: None,
+        };
+
+        Ok(res)
+    }
+
+    /// Complete the current file (if any). The file ends at a given
+    /// timestamp (i.e. at the start of the next file) or at its last packet
+    /// if the timestamp is not known.
+    fn finish_segment(&mut self, end: Option<i64>) -> Result<(), Error> {
+        let mut segment = match self.current.take() {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+
+        segment.muxer.flush()?;
+
+        let size = segment.size();
+
+        let file = segment.muxer.close()?.into_stream();
+
+        file.sync_all()
+            .map_err(|err| io_error("unable to sync a file", err))?;
+
+        drop(file);
+
+        fs::rename(part_path(&segment.path), &segment.path)
+            .map_err(|err| io_error("unable to rename a file", err))?;
+
+        let duration = match (segment.first, end.or(segment.last)) {
+            (Some(first), Some(end)) if end > first => Duration::from_micros((end - first) as u64),
+            _ => Duration::from_secs(0),
+        };
+
+        let res = RecordedSegment {
+            path: segment.path,
+            number: segment.number,
+            start: segment.start,
+            duration,
+            size,
+        };
+
+        if let Some(playlist) = self.playlist.as_mut() {
+            playlist.add(&res)?;
+        }
+
+        if let Some(callback) = self.on_segment.as_mut() {
+            callback(&res);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{format_file_name, Recorder};
+
+    use crate::{
+        codec::{
+            video::{frame::get_pixel_format, VideoEncoder, VideoFrameMut},
+            Encoder,
+        },
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_format_file_name() {
+        assert_eq!(format_file_name("a_%d.ts", 7).unwrap(), "a_7.ts");
+        assert_eq!(format_file_name("a_%04d.ts", 7).unwrap(), "a_0007.ts");
+        assert_eq!(format_file_name("100%_%03d", 7).unwrap(), "100%_007");
+        assert!(format_file_name("a.ts", 7).is_none());
+    }
+
+    #[test]
+    fn test_recorder() {
+        let pixel_format = get_pixel_format("yuv420p");
+        let time_base = TimeBase::new(1, 25);
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .time_base(time_base)
+            .set_option("g", 25)
+            .low_latency()
+            .build()
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("ac-ffmpeg-rec-{}", std::process::id()));
+
+        fs::create_dir_all(&dir).unwrap();
+
+        let segments = Arc::new(Mutex::new(Vec::new()));
+        let callback_segments = segments.clone();
+
+        let mut builder = Recorder::builder();
+
+        builder.add_stream(&encoder.codec_parameters().into());
+
+        let mut recorder = builder
+            .segment_duration(Duration::from_secs(2))
+            .playlist(dir.join("index.m3u8"))
+            .on_segment(move |segment| {
+                callback_segments.lock().unwrap().push(segment.clone());
+            })
+            .build(dir.join("seg_%03d.ts").to_str().unwrap())
+            .unwrap();
+
+        // 5 seconds with a key frame every second
+        for i in 0..125 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 48)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                recorder.push(packet.with_stream_index(0)).unwrap();
+            }
+        }
+
+        encoder.flush().unwrap();
+
+        while let Some(packet) = encoder.take().unwrap() {
+            recorder.push(packet.with_stream_index(0)).unwrap();
+        }
+
+        // the first two files should be completed by now
+        assert_eq!(segments.lock().unwrap().len(), 2);
+        assert!(dir.join("seg_002.ts.part").exists());
+
+        recorder.finish().unwrap();
+
+        let segments = segments.lock().unwrap();
+
+        let durations = segments
+            .iter()
+            .map(|segment| segment.duration().as_millis())
+            .collect::<Vec<_>>();
+
+        assert_eq!(durations, [2000, 2000, 960]);
+
+        for (i, segment) in segments.iter().enumerate() {
+            assert_eq!(segment.number(), i as u64);
+            assert!(segment.size() > 0);
+            assert_eq!(fs::metadata(segment.path()).unwrap().len(), segment.size());
+        }
+
+        assert!(!dir.join("seg_002.ts.part").exists());
+
+        let playlist = fs::read_to_string(dir.join("index.m3u8")).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(playlist.contains("#EXT-X-TARGETDURATION:2\n"));
+        assert!(playlist.contains("#EXTINF:2.000,\nseg_001.ts\n"));
+        assert!(playlist.ends_with("#EXT-X-ENDLIST\n"));
+    }
+}
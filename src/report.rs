@@ -0,0 +1,472 @@
+//! ffprobe-compatible reports.
+//!
+//! The `Report` struct collects container, stream, packet and frame
+//! information and serializes it using the same schema as
+//! `ffprobe -print_format json` (e.g. `-show_format -show_streams
+//! -show_packets -show_frames`). Tools parsing the ffprobe output should be
+//! able to consume the serialized report unchanged.
+//!
+//! Following the ffprobe JSON writer, fields that are not available are
+//! omitted and most numeric values are formatted as strings.
+//!
+//! # Example
+//! ```ignore
+//! let mut demuxer = Demuxer::builder()
+//!     .build(io)?
+//!     .find_stream_info(None)
+//!     .map_err(|(_, err)| err)?;
+//!
+//! let mut report = Report::from_demuxer(&demuxer).with_filename("input.mp4");
+//!
+//! while let Some(packet) = demuxer.take()? {
+//!     report.add_packet(&packet);
+//! }
+//!
+//! let json = serde_json::to_string_pretty(&report)?;
+//! ```
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    codec::{audio::AudioFrame, video::VideoFrame, CodecParameters, MediaType},
+    format::{demuxer::DemuxerWithStreamInfo, stream::Stream},
+    math::Rational,
+    packet::Packet,
+    time::{TimeBase, Timestamp},
+};
+
+/// ffprobe-compatible report.
+#[derive(Default, Serialize)]
+pub struct Report {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    packets: Option<Vec<PacketInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frames: Option<Vec<FrameInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    streams: Option<Vec<StreamInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<FormatInfo>,
+    #[serde(skip)]
+    codec_types: Vec<&'static str>,
+}
+
+impl Report {
+    /// Create a new empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new report containing the format and stream sections
+    /// describing a given demuxer.
+    pub fn from_demuxer<T>(demuxer: &DemuxerWithStreamInfo<T>) -> Self {
+        let streams = demuxer
+            .streams()
+            .iter()
+            .enumerate()
+            .map(|(index, stream)| StreamInfo::new(index, stream))
+            .collect::<Vec<_>>();
+
+        let codec_types = streams.iter().map(|stream| stream.codec_type).collect();
+
+        Self {
+            packets: None,
+            frames: None,
+            streams: Some(streams),
+            format: Some(FormatInfo::new(demuxer)),
+            codec_types,
+        }
+    }
+
+    /// Set the input file name reported in the format section.
+    pub fn with_filename<T>(mut self, filename: T) -> Self
+    where
+        T: Into<String>,
+    {
+        if let Some(format) = self.format.as_mut() {
+            format.filename = Some(filename.into());
+        }
+
+        self
+    }
+
+    /// Add a given packet into the packet section.
+    pub fn add_packet(&mut self, packet: &Packet) {
+        let stream_index = packet.stream_index();
+
+        let codec_type = self.codec_types.get(stream_index).copied();
+
+        let info = PacketInfo {
+            codec_type,
+            stream_index,
+            pts: ts(packet.pts()),
+            pts_time: ts_time(packet.pts()),
+            dts: ts(packet.dts()),
+            dts_time: ts_time(packet.dts()),
+            size: packet.data().len().to_string(),
+            pos: packet.position().map(|pos| pos.to_string()),
+            flags: if packet.is_key() { "K_" } else { "__" },
+        };
+
+        self.packets.get_or_insert_with(Vec::new).push(info);
+    }
+
+    /// Add a given video frame into the frame section.
+    pub fn add_video_frame(&mut self, stream_index: usize, frame: &VideoFrame) {
+        let format = frame.pixel_format();
+
+        let info = FrameInfo {
+            media_type: "video",
+            stream_index,
+            key_frame: frame.is_key() as i32,
+            pts: ts(frame.pts()),
+            pts_time: ts_time(frame.pts()),
+            width: Some(frame.width() as _),
+            height: Some(frame.height() as _),
+            pix_fmt: if format.into_raw() >= 0 {
+                Some(format.name())
+            } else {
+                None
+            },
+            pict_type: Some(frame.picture_type().to_string()),
+            interlaced_frame: Some(frame.is_interlaced() as i32),
+            top_field_first: Some(frame.top_field_first() as i32),
+            sample_fmt: None,
+            nb_samples: None,
+            channels: None,
+            channel_layout: None,
+        };
+
+        self.frames.get_or_insert_with(Vec::new).push(info);
+    }
+
+    /// Add a given audio frame into the frame section.
+    pub fn add_audio_frame(&mut self, stream_index: usize, frame: &AudioFrame) {
+        let format = frame.sample_format();
+
+        let info = FrameInfo {
+            media_type: "audio",
+            stream_index,
+            key_frame: 1,
+            pts: ts(frame.pts()),
+            pts_time: ts_time(frame.pts()),
+            width: None,
+            height: None,
+            pix_fmt: None,
+            pict_type: None,
+            interlaced_frame: None,
+            top_field_first: None,
+            sample_fmt: if format.into_raw() >= 0 {
+                Some(format.name())
+            } else {
+                None
+            },
+            nb_samples: Some(frame.samples() as _),
+            channels: Some(frame.channels()),
+            channel_layout: Some(frame.channel_layout().to_string()),
+        };
+
+        self.frames.get_or_insert_with(Vec::new).push(info);
+    }
+}
+
+/// Packet section entry.
+#[derive(Serialize)]
+struct PacketInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codec_type: Option<&'static str>,
+    stream_index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pts_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dts_time: Option<String>,
+    size: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pos: Option<String>,
+    flags: &'static str,
+}
+
+/// Frame section entry.
+#[derive(Serialize)]
+struct FrameInfo {
+    media_type: &'static str,
+    stream_index: usize,
+    key_frame: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pts_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pix_fmt: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pict_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interlaced_frame: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_field_first: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_fmt: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nb_samples: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channels: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_layout: Option<String>,
+}
+
+/// Stream section entry.
+#[derive(Serialize)]
+struct StreamInfo {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codec_name: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codec_long_name: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<&'static str>,
+    codec_type: &'static str,
+    codec_tag_string: String,
+    codec_tag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_aspect_ratio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pix_fmt: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_fmt: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_rate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channels: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_layout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    r_frame_rate: String,
+    avg_frame_rate: String,
+    time_base: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_pts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_rate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nb_frames: Option<String>,
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_tags"
+    )]
+    tags: Vec<(String, String)>,
+}
+
+impl StreamInfo {
+    /// Create a new stream section entry.
+    fn new(index: usize, stream: &Stream) -> Self {
+        let params = stream.codec_parameters();
+
+        let codec_tag = params.codec_tag();
+
+        let mut res = Self {
+            index,
+            codec_name: Some(params.codec_name()).filter(|name| !name.is_empty()),
+            codec_long_name: params.codec_long_name(),
+            profile: params.profile_name(),
+            codec_type: codec_type(&params),
+            codec_tag_string: fourcc(codec_tag),
+            codec_tag: format!("0x{:04x}", codec_tag),
+            width: None,
+            height: None,
+            sample_aspect_ratio: None,
+            pix_fmt: None,
+            level: None,
+            sample_fmt: None,
+            sample_rate: None,
+            channels: None,
+            channel_layout: None,
+            id: Some(stream.id())
+                .filter(|id| *id != 0)
+                .map(|id| format!("0x{:x}", id)),
+            r_frame_rate: rational(stream.r_frame_rate()),
+            avg_frame_rate: rational(stream.avg_frame_rate()),
+            time_base: time_base(stream.time_base()),
+            start_pts: stream.start_time().and_then(ts),
+            start_time: stream.start_time().and_then(ts_time),
+            duration_ts: stream.duration().and_then(ts),
+            duration: stream.duration().and_then(ts_time),
+            bit_rate: None,
+            nb_frames: stream.frames().map(|frames| frames.to_string()),
+            tags: stream.metadata(),
+        };
+
+        if let Some(params) = params.as_video_codec_parameters() {
+            let format = params.pixel_format();
+
+            res.width = Some(params.width() as _);
+            res.height = Some(params.height() as _);
+            res.sample_aspect_ratio = params.sample_aspect_ratio().map(|r| ratio(r, ':'));
+            res.level = params.level();
+            res.bit_rate = Some(params.bit_rate())
+                .filter(|bit_rate| *bit_rate > 0)
+                .map(|bit_rate| bit_rate.to_string());
+
+            if format.into_raw() >= 0 {
+                res.pix_fmt = Some(format.name());
+            }
+        } else if let Some(params) = params.as_audio_codec_parameters() {
+            let format = params.sample_format();
+
+            res.sample_rate = Some(params.sample_rate().to_string());
+            res.channels = Some(params.channels());
+            res.channel_layout = Some(params.channel_layout().to_string());
+            res.bit_rate = Some(params.bit_rate())
+                .filter(|bit_rate| *bit_rate > 0)
+                .map(|bit_rate| bit_rate.to_string());
+
+            if format.into_raw() >= 0 {
+                res.sample_fmt = Some(format.name());
+            }
+        }
+
+        res
+    }
+}
+
+/// Format section.
+#[derive(Serialize)]
+struct FormatInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    nb_streams: usize,
+    nb_programs: usize,
+    format_name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format_long_name: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_rate: Option<String>,
+    probe_score: i32,
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_tags"
+    )]
+    tags: Vec<(String, String)>,
+}
+
+impl FormatInfo {
+    /// Create a new format section.
+    fn new<T>(demuxer: &DemuxerWithStreamInfo<T>) -> Self {
+        let format = demuxer.input_format();
+
+        Self {
+            filename: None,
+            nb_streams: demuxer.streams().len(),
+            nb_programs: demuxer.program_count(),
+            format_name: format.name(),
+            format_long_name: Some(format.long_name()).filter(|name| !name.is_empty()),
+            start_time: demuxer.start_time().and_then(ts_time),
+            duration: demuxer.duration().and_then(ts_time),
+            bit_rate: demuxer.bit_rate().map(|bit_rate| bit_rate.to_string()),
+            probe_score: demuxer.probe_score(),
+            tags: demuxer.metadata(),
+        }
+    }
+}
+
+/// Serialize given tags as a JSON object (preserving the order).
+fn serialize_tags<S>(tags: &[(String, String)], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(tags.iter().map(|(k, v)| (k, v)))
+}
+
+/// Get the ffprobe codec type name.
+fn codec_type(params: &CodecParameters) -> &'static str {
+    match params.media_type() {
+        MediaType::Video => "video",
+        MediaType::Audio => "audio",
+        MediaType::Subtitle => "subtitle",
+        MediaType::Data => "data",
+        MediaType::Attachment => "attachment",
+        MediaType::Unknown => "unknown",
+    }
+}
+
+/// Get the raw timestamp value (if not null).
+fn ts(ts: Timestamp) -> Option<i64> {
+    if ts.is_null() {
+        None
+    } else {
+        Some(ts.timestamp())
+    }
+}
+
+/// Format a given timestamp in seconds (if not null).
+fn ts_time(ts: Timestamp) -> Option<String> {
+    ts.as_f64().map(|secs| format!("{:.6}", secs))
+}
+
+/// Format a given time base.
+fn time_base(time_base: TimeBase) -> String {
+    format!("{}/{}", time_base.num(), time_base.den())
+}
+
+/// Format a given frame rate ("0/0" if unknown).
+fn rational(r: Option<Rational>) -> String {
+    r.map(|r| ratio(r, '/'))
+        .unwrap_or_else(|| String::from("0/0"))
+}
+
+/// Format a given rational number using a given separator.
+fn ratio(r: Rational, separator: char) -> String {
+    format!("{}{}{}", r.num(), separator, r.den())
+}
+
+/// Format a given codec tag the same way as `av_fourcc_make_string()`.
+fn fourcc(tag: u32) -> String {
+    let mut res = String::new();
+
+    for byte in tag.to_le_bytes() {
+        let c = byte as char;
+
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-' || c == ' ' {
+            res.push(c);
+        } else {
+            res += &format!("[{}]", byte);
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fourcc;
+
+    #[test]
+    fn test_fourcc() {
+        assert_eq!(fourcc(0), "[0][0][0][0]");
+        assert_eq!(fourcc(u32::from_le_bytes(*b"avc1")), "avc1");
+        assert_eq!(fourcc(0x1b), "[27][0][0][0]");
+    }
+}
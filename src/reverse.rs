@@ -0,0 +1,398 @@
+//! Reverse playback.
+//!
+//! The reverse player walks a video stream backwards GOP by GOP. For each
+//! GOP, it seeks to the preceding key frame, decodes all frames up to the
+//! position of the previously emitted frame and emits them in reverse
+//! presentation order. At most a given number of decoded frames is kept in
+//! memory. If a GOP contains more frames, only the last frames are kept and
+//! the same GOP is decoded again for the remaining frames.
+
+use std::collections::VecDeque;
+
+use crate::{
+    codec::{
+        video::{VideoDecoder, VideoFrame},
+        Decoder,
+    },
+    format::demuxer::{DemuxerWithStreamInfo, SeekTarget},
+    time::Timestamp,
+    Error,
+};
+
+/// Initial distance (in microseconds) between the seek target and the
+/// position of the previously emitted frame.
+const INITIAL_BACKOFF: i64 = 1;
+
+/// Builder for the reverse player.
+pub struct ReversePlayerBuilder<T> {
+    demuxer: DemuxerWithStreamInfo<T>,
+    stream_index: usize,
+    start: Option<Timestamp>,
+    max_frames: usize,
+}
+
+impl<T> ReversePlayerBuilder<T> {
+    /// Create a new builder.
+    fn new(demuxer: DemuxerWithStreamInfo<T>, stream_index: usize) -> Self {
+        Self {
+            demuxer,
+            stream_index,
+            start: None,
+            max_frames: 64,
+        }
+    }
+
+    /// Set the initial position. The first emitted frame will be the last
+    /// frame at or before this position. The default is the end of the
+    /// input.
+    pub fn start(mut self, position: Timestamp) -> Self {
+        self.start = Some(position);
+        self
+    }
+
+    /// Set the maximum number of decoded frames kept in memory. The default
+    /// is 64.
+    ///
+    /// # Panics
+    /// The method panics if the number of frames is zero.
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        assert!(max_frames > 0);
+
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Build the reverse player.
+    pub fn build(self) -> Result<ReversePlayer<T>, Error> {
+        let stream = self
+            .demuxer
+            .streams()
+            .get(self.stream_index)
+            .ok_or_else(|| Error::new("invalid stream index"))?;
+
+        let decoder = VideoDecoder::from_stream(stream)?.build()?;
+
+        let origin = self
+            .demuxer
+            .start_time()
+            .and_then(|start| start.as_micros())
+            .unwrap_or(0);
+
+        let end = if let Some(start) = self.start {
+            start
+                .as_micros()
+                .ok_or_else(|| Error::new("null timestamp"))?
+                + 1
+        } else {
+            let duration = self
+                .demuxer
+                .duration()
+                .and_then(|duration| duration.as_micros())
+                .ok_or_else(|| Error::new("unknown duration, the start position must be set"))?;
+
+            origin + duration
+        };
+
+        let res = ReversePlayer {
+            demuxer: self.demuxer,
+            decoder,
+            stream_index: self.stream_index,
+            max_frames: self.max_frames,
+            origin,
+            end,
+            backoff: INITIAL_BACKOFF,
+            frames: VecDeque::new(),
+        };
+
+        Ok(res)
+    }
+}
+
+/// Reverse player.
+///
+/// The player is an iterator over decoded frames of a given video stream in
+/// reverse presentation order.
+pub struct ReversePlayer<T> {
+    demuxer: DemuxerWithStreamInfo<T>,
+    decoder: VideoDecoder,
+    stream_index: usize,
+    max_frames: usize,
+    origin: i64,
+    end: i64,
+    backoff: i64,
+    frames: VecDeque<VideoFrame>,
+}
+
+impl<T> ReversePlayer<T> {
+    /// Get a builder for a reverse player of a given video stream.
+    pub fn builder(
+        demuxer: DemuxerWithStreamInfo<T>,
+        stream_index: usize,
+    ) -> ReversePlayerBuilder<T> {
+        ReversePlayerBuilder::new(demuxer, stream_index)
+    }
+
+    /// Continue from a given position. The next emitted frame will be the
+    /// last frame at or before this position.
+    pub fn seek(&mut self, position: Timestamp) -> Result<(), Error> {
+        let position = position
+            .as_micros()
+            .ok_or_else(|| Error::new("null timestamp"))?;
+
+        self.frames.clear();
+
+        self.end = position + 1;
+        self.backoff = INITIAL_BACKOFF;
+
+        Ok(())
+    }
+
+    /// Get the next frame (in reverse order) or `None` if the beginning of
+    /// the stream has been reached.
+    pub fn take(&mut self) -> Result<Option<VideoFrame>, Error> {
+        if self.frames.is_empty() {
+            self.decode_chunk()?;
+        }
+
+        Ok(self.frames.pop_back())
+    }
+
+    /// Get the underlying demuxer.
+    pub fn into_demuxer(self) -> DemuxerWithStreamInfo<T> {
+        self.demuxer
+    }
+
+    /// Decode the next chunk of frames preceding the current end position.
+    fn decode_chunk(&mut self) -> Result<(), Error> {
+        while self.end > self.origin {
+            let target = (self.end - self.backoff).max(self.origin);
+
+            self.demuxer
+                .seek_to_timestamp(Timestamp::from_micros(target), SeekTarget::UpTo)?;
+
+            self.decoder.reset();
+
+            self.decode_until_end()?;
+
+            if let Some(first) = self.frames.front() {
+                // the pts is known for all buffered frames
+                let first = first.pts().as_micros().unwrap_or(self.origin);
+
+                // note that if some frames were dropped, the same GOP will be
+                // decoded again next time
+                self.end = first;
+                self.backoff = INITIAL_BACKOFF;
+
+                return Ok(());
+            } else if target <= self.origin {
+                break;
+            }
+
+            // the demuxer did not seek far enough
+            self.backoff = self.backoff.saturating_mul(2).max(40_000);
+        }
+
+        self.end = self.origin;
+
+        Ok(())
+    }
+
+    /// Decode frames from the current position up to the current end
+    /// position.
+    fn decode_until_end(&mut self) -> Result<(), Error> {
+        let mut key = false;
+
+        while let Some(packet) = self.demuxer.take()? {
+            if packet.stream_index() != self.stream_index {
+                continue;
+            }
+
+            // skip everything before the first key frame
+            key |= packet.is_key();
+
+            if !key {
+                continue;
+            }
+
+            self.decoder.push(packet)?;
+
+            while let Some(frame) = self.decoder.take()? {
+                if !self.add_frame(frame) {
+                    return Ok(());
+                }
+            }
+        }
+
+        self.decoder.flush()?;
+
+        while let Some(frame) = self.decoder.take()? {
+            if !self.add_frame(frame) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add a given frame into the buffer. The oldest frame is dropped if the
+    /// buffer is full. The method returns false if the frame is past the
+    /// current end position.
+    fn add_frame(&mut self, frame: VideoFrame) -> bool {
+        let pts = match frame.pts().as_micros() {
+            Some(pts) => pts,
+            None => return true,
+        };
+
+        if pts >= self.end {
+            return false;
+        }
+
+        if self.frames.len() >= self.max_frames {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(frame);
+
+        true
+    }
+}
+
+impl<T> Iterator for ReversePlayer<T> {
+    type Item = Result<VideoFrame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.take().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::ReversePlayer;
+
+    use crate::{
+        codec::{
+            video::{frame::get_pixel_format, VideoDecoder, VideoEncoder, VideoFrameMut},
+            Decoder, Encoder,
+        },
+        format::{
+            demuxer::Demuxer,
+            io::IO,
+            muxer::{Muxer, OutputFormat},
+        },
+        time::{TimeBase, Timestamp},
+    };
+
+    /// Create a short MP4 file with a given number of MPEG-4 frames and a
+    /// given GOP size.
+    fn mp4_file(frames: i64, gop_size: usize) -> Vec<u8> {
+        let pixel_format = get_pixel_format("yuv420p");
+        let time_base = TimeBase::new(1, 25);
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .time_base(time_base)
+            .set_option("g", gop_size)
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder();
+
+        builder
+            .add_stream(&encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_seekable_write_stream(Cursor::new(Vec::new())),
+                OutputFormat::find_by_name("mp4").unwrap(),
+            )
+            .unwrap();
+
+        for i in 0..frames {
+            let frame = VideoFrameMut::black(pixel_format, 64, 48)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(0)).unwrap();
+            }
+        }
+
+        encoder.flush().unwrap();
+
+        while let Some(packet) = encoder.take().unwrap() {
+            muxer.push(packet.with_stream_index(0)).unwrap();
+        }
+
+        muxer.close().unwrap().into_stream().into_inner()
+    }
+
+    #[test]
+    fn test_reverse_playback() {
+        let data = mp4_file(20, 5);
+
+        // get all presentable frames in the forward direction first
+        let mut demuxer = Demuxer::builder()
+            .build(IO::from_seekable_read_stream(Cursor::new(data.clone())))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        let mut decoder = VideoDecoder::from_stream(&demuxer.streams()[0])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut expected = Vec::new();
+
+        while let Some(packet) = demuxer.take().unwrap() {
+            decoder.push(packet).unwrap();
+
+            while let Some(frame) = decoder.take().unwrap() {
+                expected.push(frame.pts().as_micros().unwrap());
+            }
+        }
+
+        decoder.flush().unwrap();
+
+        while let Some(frame) = decoder.take().unwrap() {
+            expected.push(frame.pts().as_micros().unwrap());
+        }
+
+        // NOTE: the last frame has zero duration, so it is outside of the
+        // edit list created by the mp4 muxer
+        assert_eq!(expected.len(), 19);
+
+        expected.reverse();
+
+        let demuxer = Demuxer::builder()
+            .build(IO::from_seekable_read_stream(Cursor::new(data)))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        // keep fewer frames than the GOP size, so that every GOP needs to
+        // be decoded more than once
+        let player = ReversePlayer::builder(demuxer, 0)
+            .max_frames(3)
+            .build()
+            .unwrap();
+
+        let pts = player
+            .map(|frame| frame.unwrap().pts().as_micros().unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(pts.windows(2).all(|w| w[0] > w[1]));
+        assert_eq!(pts, expected);
+    }
+}
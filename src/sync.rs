@@ -0,0 +1,365 @@
+//! Audio/video synchronization.
+//!
+//! Video frames are presented according to a master clock. The master clock
+//! is usually the audio clock of an audio output (see the `playback` module)
+//! or an external clock driven by the system time (e.g. for streams without
+//! audio).
+//!
+//! The frame scheduler decides which video frame should be presented at a
+//! given moment. Frames that are late are dropped (if a later frame is due
+//! as well) and the last frame can be repeated if no new frame is
+//! available, depending on the configured policies.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{codec::video::VideoFrame, time::Timestamp};
+
+/// Common trait for master clocks.
+pub trait MasterClock {
+    /// Get the current clock position. `None` is returned if the clock has
+    /// not been started yet.
+    fn now(&self) -> Option<Timestamp>;
+}
+
+#[cfg(feature = "playback")]
+impl MasterClock for crate::playback::AudioClock {
+    fn now(&self) -> Option<Timestamp> {
+        self.position()
+    }
+}
+
+/// External clock driven by the system time.
+///
+/// The clock is shared. Cloning the clock creates a new handle to the same
+/// clock.
+#[derive(Clone)]
+pub struct ExternalClock {
+    inner: Arc<Mutex<ExternalClockState>>,
+}
+
+impl ExternalClock {
+    /// Create a new clock. The clock needs to be set before it starts
+    /// running.
+    pub fn new() -> Self {
+        let state = ExternalClockState {
+            base: None,
+            instant: Instant::now(),
+            paused: false,
+        };
+
+        Self {
+            inner: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Set the current clock position.
+    pub fn set(&self, position: Timestamp) {
+        let mut state = self.inner.lock().unwrap();
+
+        if position.is_null() {
+            state.base = None;
+        } else {
+            state.base = Some(position);
+        }
+
+        state.instant = Instant::now();
+    }
+
+    /// Pause the clock.
+    pub fn pause(&self) {
+        let mut state = self.inner.lock().unwrap();
+
+        state.base = state.position();
+        state.paused = true;
+    }
+
+    /// Resume the clock.
+    pub fn resume(&self) {
+        let mut state = self.inner.lock().unwrap();
+
+        if state.paused {
+            state.instant = Instant::now();
+            state.paused = false;
+        }
+    }
+
+    /// Check if the clock is paused.
+    pub fn is_paused(&self) -> bool {
+        self.inner.lock().unwrap().paused
+    }
+}
+
+impl Default for ExternalClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MasterClock for ExternalClock {
+    fn now(&self) -> Option<Timestamp> {
+        self.inner.lock().unwrap().position()
+    }
+}
+
+/// Internal state of the external clock.
+struct ExternalClockState {
+    base: Option<Timestamp>,
+    instant: Instant,
+    paused: bool,
+}
+
+impl ExternalClockState {
+    /// Get the current position.
+    fn position(&self) -> Option<Timestamp> {
+        let base = self.base?;
+
+        if self.paused {
+            Some(base)
+        } else {
+            Some(base + self.instant.elapsed())
+        }
+    }
+}
+
+/// Policy for late video frames.
+#[derive(Debug, Copy, Clone)]
+pub enum DropPolicy {
+    /// Never drop any frames. Late frames are presented one by one until
+    /// the video catches up with the clock.
+    Never,
+    /// Drop frames that are late by more than a given threshold if the
+    /// next frame is already due as well.
+    Late(Duration),
+}
+
+/// Policy for missing video frames (i.e. no new frame is due).
+#[derive(Debug, Copy, Clone)]
+pub enum RepeatPolicy {
+    /// Do not return anything. The caller keeps presenting the last frame.
+    Hold,
+    /// Return the last frame again if no new frame has been returned for a
+    /// given interval (e.g. for outputs that require a constant frame
+    /// rate).
+    Repeat(Duration),
+}
+
+/// Video frame scheduler.
+///
+/// Frames are expected to be pushed in presentation order. Frames without a
+/// timestamp are presented immediately.
+pub struct FrameScheduler {
+    frames: VecDeque<VideoFrame>,
+    drop_policy: DropPolicy,
+    repeat_policy: RepeatPolicy,
+    last: Option<(VideoFrame, i64)>,
+    dropped: u64,
+    repeated: u64,
+}
+
+impl FrameScheduler {
+    /// Create a new frame scheduler. Frames late by more than 100 ms are
+    /// dropped and no frames are repeated by default.
+    pub fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            drop_policy: DropPolicy::Late(Duration::from_millis(100)),
+            repeat_policy: RepeatPolicy::Hold,
+            last: None,
+            dropped: 0,
+            repeated: 0,
+        }
+    }
+
+    /// Set the policy for late frames.
+    pub fn drop_policy(mut self, policy: DropPolicy) -> Self {
+        self.drop_policy = policy;
+        self
+    }
+
+    /// Set the policy for missing frames.
+    pub fn repeat_policy(mut self, policy: RepeatPolicy) -> Self {
+        self.repeat_policy = policy;
+        self
+    }
+
+    /// Push a given frame into the scheduler.
+    pub fn push(&mut self, frame: VideoFrame) {
+        self.frames.push_back(frame);
+    }
+
+    /// Get the number of queued frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Check if there are no queued frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Get the number of dropped frames.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Get the number of repeated frames.
+    pub fn repeated(&self) -> u64 {
+        self.repeated
+    }
+
+    /// Drop all queued frames (e.g. after seeking).
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.last = None;
+    }
+
+    /// Get the frame that should be presented at a given clock position
+    /// (if any).
+    pub fn next_frame_due(&mut self, now: Timestamp) -> Option<VideoFrame> {
+        let now = now.as_micros()?;
+
+        if let DropPolicy::Late(threshold) = self.drop_policy {
+            let threshold = threshold.as_micros() as i64;
+
+            while self.frames.len() > 1 {
+                let current = frame_pts(&self.frames[0]);
+                let next = frame_pts(&self.frames[1]);
+
+                let late = current.map(|pts| now - pts > threshold).unwrap_or(false);
+                let next_due = next.map(|pts| pts <= now).unwrap_or(true);
+
+                if late && next_due {
+                    self.frames.pop_front();
+                    self.dropped += 1;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let due = self
+            .frames
+            .front()
+            .map(|frame| frame_pts(frame).map(|pts| pts <= now).unwrap_or(true))
+            .unwrap_or(false);
+
+        if due {
+            let frame = self.frames.pop_front()?;
+
+            self.last = Some((frame.clone(), now));
+
+            return Some(frame);
+        }
+
+        if let RepeatPolicy::Repeat(interval) = self.repeat_policy {
+            if let Some((frame, presented)) = self.last.as_mut() {
+                if now - *presented >= interval.as_micros() as i64 {
+                    *presented = now;
+
+                    self.repeated += 1;
+
+                    return Some(frame.clone());
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Get time remaining until the next queued frame is due. `None` is
+    /// returned if there are no queued frames.
+    pub fn time_until_next(&self, now: Timestamp) -> Option<Duration> {
+        let frame = self.frames.front()?;
+
+        let now = now.as_micros();
+        let pts = frame_pts(frame);
+
+        if let (Some(now), Some(pts)) = (now, pts) {
+            if pts > now {
+                return Some(Duration::from_micros((pts - now) as u64));
+            }
+        }
+
+        Some(Duration::from_secs(0))
+    }
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get presentation timestamp of a given frame in microseconds.
+fn frame_pts(frame: &VideoFrame) -> Option<i64> {
+    frame.pts().as_micros()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{DropPolicy, FrameScheduler, RepeatPolicy};
+
+    use crate::{
+        codec::video::{self, VideoFrame, VideoFrameMut},
+        time::Timestamp,
+    };
+
+    fn frame(pts: i64) -> VideoFrame {
+        let format = video::frame::get_pixel_format("gray");
+
+        VideoFrameMut::black(format, 2, 2)
+            .with_pts(Timestamp::from_millis(pts))
+            .freeze()
+    }
+
+    #[test]
+    fn test_frame_scheduler() {
+        let mut scheduler = FrameScheduler::new()
+            .drop_policy(DropPolicy::Late(Duration::from_millis(50)))
+            .repeat_policy(RepeatPolicy::Repeat(Duration::from_millis(100)));
+
+        for pts in &[0, 40, 80, 120, 160] {
+            scheduler.push(frame(*pts));
+        }
+
+        // nothing is due yet
+        assert!(scheduler
+            .next_frame_due(Timestamp::from_millis(-10))
+            .is_none());
+        assert_eq!(
+            scheduler.time_until_next(Timestamp::from_millis(-10)),
+            Some(Duration::from_millis(10))
+        );
+
+        let next = scheduler.next_frame_due(Timestamp::from_millis(0));
+
+        assert_eq!(next.map(|f| f.pts()), Some(Timestamp::from_millis(0)));
+
+        // the frames at 40 and 80 are too late
+        let next = scheduler.next_frame_due(Timestamp::from_millis(140));
+
+        assert_eq!(next.map(|f| f.pts()), Some(Timestamp::from_millis(120)));
+        assert_eq!(scheduler.dropped(), 2);
+
+        let next = scheduler.next_frame_due(Timestamp::from_millis(200));
+
+        assert_eq!(next.map(|f| f.pts()), Some(Timestamp::from_millis(160)));
+        assert!(scheduler.is_empty());
+
+        // repeat the last frame
+        assert!(scheduler
+            .next_frame_due(Timestamp::from_millis(250))
+            .is_none());
+
+        let next = scheduler.next_frame_due(Timestamp::from_millis(300));
+
+        assert_eq!(next.map(|f| f.pts()), Some(Timestamp::from_millis(160)));
+        assert_eq!(scheduler.repeated(), 1);
+    }
+}
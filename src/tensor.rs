@@ -0,0 +1,273 @@
+//! Frame export into `ndarray` arrays.
+//!
+//! Decoded video frames can be exported as HWC (height, width, channels)
+//! arrays and decoded audio frames as (channels, samples) arrays, so they can
+//! be passed directly into ML inference pipelines.
+//!
+//! Only packed 8-bit pixel formats (e.g. "rgb24", "bgra" or "gray") can be
+//! exported directly. Frames in other pixel formats (e.g. "yuv420p") need to
+//! be converted first using `VideoFrame::to_rgb24()`.
+
+use ndarray::{Array2, Array3};
+
+use crate::{
+    codec::{
+        audio::AudioFrame,
+        video::{self, scaler::Algorithm, VideoFrame, VideoFrameScaler},
+    },
+    Error,
+};
+
+/// Normalization of exported pixel values.
+#[derive(Debug, Clone)]
+pub enum Normalization {
+    /// Keep the original range (i.e. 0 to 255).
+    None,
+    /// Scale the values into the 0 to 1 range.
+    UnitRange,
+    /// Scale the values into the 0 to 1 range, subtract a given per-channel
+    /// mean and divide the result by a given per-channel standard deviation.
+    MeanStd { mean: Vec<f32>, std: Vec<f32> },
+}
+
+impl VideoFrame {
+    /// Convert the frame into the "rgb24" pixel format. The frame dimensions
+    /// are preserved.
+    pub fn to_rgb24(&self) -> Result<VideoFrame, Error> {
+        let mut scaler = VideoFrameScaler::builder()
+            .source_pixel_format(self.pixel_format())
+            .source_width(self.width())
+            .source_height(self.height())
+            .target_pixel_format(video::frame::get_pixel_format("rgb24"))
+            .target_width(self.width())
+            .target_height(self.height())
+            .algorithm(Algorithm::Bilinear)
+            .build()?;
+
+        scaler.scale(self)
+    }
+
+    /// Export the frame as an HWC array. The frame must use a packed 8-bit
+    /// pixel format (e.g. "rgb24", "bgra" or "gray").
+    pub fn to_ndarray(&self) -> Result<Array3<u8>, Error> {
+        let channels = packed_channels(self)?;
+
+        let width = self.width();
+        let height = self.height();
+
+        let planes = self.planes();
+
+        let mut data = Vec::with_capacity(width * height * channels);
+
+        for line in planes[0].lines().take(height) {
+            data.extend_from_slice(&line[..width * channels]);
+        }
+
+        Array3::from_shape_vec((height, width, channels), data)
+            .map_err(|_| Error::new("invalid frame dimensions"))
+    }
+
+    /// Export the frame as an HWC array of floats using a given
+    /// normalization. The frame must use a packed 8-bit pixel format (e.g.
+    /// "rgb24", "bgra" or "gray").
+    pub fn to_ndarray_f32(&self, normalization: &Normalization) -> Result<Array3<f32>, Error> {
+        let array = self.to_ndarray()?;
+
+        let channels = array.dim().2;
+
+        let res = match normalization {
+            Normalization::None => array.mapv(f32::from),
+            Normalization::UnitRange => array.mapv(|v| f32::from(v) / 255.0),
+            Normalization::MeanStd { mean, std } => {
+                if mean.len() != channels || std.len() != channels {
+                    return Err(Error::new(
+                        "the number of mean/std values does not match the number of channels",
+                    ));
+                }
+
+                let mut res = array.mapv(|v| f32::from(v) / 255.0);
+
+                for ((_, _, c), v) in res.indexed_iter_mut() {
+                    *v = (*v - mean[c]) / std[c];
+                }
+
+                res
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+/// Get the number of channels of a given frame with a packed 8-bit pixel
+/// format.
+fn packed_channels(frame: &VideoFrame) -> Result<usize, Error> {
+    let format = frame.pixel_format();
+
+    if format.into_raw() < 0 {
+        return Err(Error::new("unknown pixel format"));
+    }
+
+    let channels = match format.name() {
+        "gray" => 1,
+        "rgb24" | "bgr24" => 3,
+        "rgba" | "bgra" | "argb" | "abgr" | "rgb0" | "bgr0" | "0rgb" | "0bgr" => 4,
+        _ => {
+            return Err(Error::new(
+                "unsupported pixel format, convert the frame using to_rgb24() first",
+            ))
+        }
+    };
+
+    Ok(channels)
+}
+
+impl AudioFrame {
+    /// Export the frame as a (channels, samples) array of floats. Integer
+    /// samples are scaled into the -1 to 1 range.
+    pub fn to_ndarray(&self) -> Result<Array2<f32>, Error> {
+        let format = self.sample_format();
+
+        let convert = format
+            .f32_converter()
+            .ok_or_else(|| Error::new("unsupported sample format"))?;
+
+        let bytes_per_sample = format.bytes_per_sample();
+        let channels = self.channels() as usize;
+        let samples = self.samples();
+
+        let planes = self.planes();
+
+        let mut res = Array2::zeros((channels, samples));
+
+        if format.is_planar() {
+            for (channel, plane) in planes.iter().enumerate().take(channels) {
+                let data = plane.data().chunks_exact(bytes_per_sample);
+
+                for (sample, bytes) in data.take(samples).enumerate() {
+                    res[(channel, sample)] = convert(bytes);
+                }
+            }
+        } else {
+            let data = planes[0].data().chunks_exact(bytes_per_sample);
+
+            for (index, bytes) in data.take(channels * samples).enumerate() {
+                res[(index % channels, index / channels)] = convert(bytes);
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Normalization;
+
+    use crate::codec::{
+        audio::{
+            frame::{get_channel_layout, get_sample_format},
+            AudioFrameMut,
+        },
+        video::{frame::get_pixel_format, VideoFrameMut},
+    };
+
+    #[test]
+    fn test_video_frame_padding() {
+        let mut frame = VideoFrameMut::black(get_pixel_format("rgb24"), 5, 2);
+
+        let mut planes = frame.planes_mut();
+
+        // the line size is aligned, so there is padding after every line
+        assert!(planes[0].line_size() > 5 * 3);
+
+        for (y, line) in planes[0].lines_mut().enumerate() {
+            for (x, v) in line.iter_mut().enumerate() {
+                *v = (y * 100 + x) as u8;
+            }
+        }
+
+        let frame = frame.freeze();
+
+        let array = frame.to_ndarray().unwrap();
+
+        assert_eq!(array.dim(), (2, 5, 3));
+
+        for ((y, x, c), v) in array.indexed_iter() {
+            assert_eq!(*v as usize, y * 100 + x * 3 + c);
+        }
+    }
+
+    #[test]
+    fn test_video_frame_normalization() {
+        let frame = VideoFrameMut::black(get_pixel_format("rgb24"), 4, 4).freeze();
+
+        let normalization = Normalization::MeanStd {
+            mean: vec![0.5, 0.5, 0.5],
+            std: vec![0.5, 0.5, 0.5],
+        };
+
+        let array = frame.to_ndarray_f32(&normalization).unwrap();
+
+        assert!(array.iter().all(|v| *v == -1.0));
+
+        let normalization = Normalization::MeanStd {
+            mean: vec![0.5],
+            std: vec![0.5],
+        };
+
+        assert!(frame.to_ndarray_f32(&normalization).is_err());
+
+        let frame = VideoFrameMut::black(get_pixel_format("yuv420p"), 4, 4).freeze();
+
+        assert!(frame.to_ndarray().is_err());
+    }
+
+    #[test]
+    fn test_audio_frame_s16() {
+        let channel_layout = get_channel_layout("stereo");
+        let sample_format = get_sample_format("s16");
+
+        let mut frame = AudioFrameMut::silence(channel_layout, sample_format, 48_000, 3);
+
+        let samples: [i16; 6] = [16384, -16384, 0, 8192, -32768, 16384];
+
+        let mut planes = frame.planes_mut();
+
+        for (bytes, sample) in planes[0].data_mut().chunks_exact_mut(2).zip(&samples) {
+            bytes.copy_from_slice(&sample.to_ne_bytes());
+        }
+
+        let array = frame.freeze().to_ndarray().unwrap();
+
+        assert_eq!(array.dim(), (2, 3));
+
+        assert_eq!(array.row(0).to_vec(), [0.5, 0.0, -1.0]);
+        assert_eq!(array.row(1).to_vec(), [-0.5, 0.25, 0.5]);
+    }
+
+    #[test]
+    fn test_audio_frame_fltp() {
+        let channel_layout = get_channel_layout("stereo");
+        let sample_format = get_sample_format("fltp");
+
+        let mut frame = AudioFrameMut::silence(channel_layout, sample_format, 48_000, 2);
+
+        let mut planes = frame.planes_mut();
+
+        for (channel, plane) in planes.iter_mut().enumerate() {
+            for (sample, bytes) in plane.data_mut().chunks_exact_mut(4).enumerate() {
+                let v = (channel as f32 + 1.0) * (sample as f32 + 1.0) / 10.0;
+
+                bytes.copy_from_slice(&v.to_ne_bytes());
+            }
+        }
+
+        let array = frame.freeze().to_ndarray().unwrap();
+
+        assert_eq!(array.dim(), (2, 2));
+
+        assert_eq!(array.row(0).to_vec(), [0.1, 0.2]);
+        assert_eq!(array.row(1).to_vec(), [0.2, 0.4]);
+    }
+}
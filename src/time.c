@@ -1,5 +1,7 @@
 #include <libavutil/avutil.h>
 #include <libavutil/mathematics.h>
+#include <libavutil/parseutils.h>
+#include <libavutil/time.h>
 
 int64_t ffw_rescale_q(int64_t n, uint32_t aq_num, uint32_t aq_den, uint32_t bq_num, uint32_t bq_den) {
     int64_t a = aq_num * (int64_t)bq_den;
@@ -8,6 +10,35 @@ int64_t ffw_rescale_q(int64_t n, uint32_t aq_num, uint32_t aq_den, uint32_t bq_n
     return av_rescale_rnd(n, a, b, AV_ROUND_ZERO);
 }
 
+int64_t ffw_rescale_q_rnd(int64_t n, uint32_t aq_num, uint32_t aq_den, uint32_t bq_num, uint32_t bq_den, int rnd) {
+    int64_t a = aq_num * (int64_t)bq_den;
+    int64_t b = bq_num * (int64_t)aq_den;
+
+    return av_rescale_rnd(n, a, b, rnd | AV_ROUND_PASS_MINMAX);
+}
+
+void ffw_rescale_q_rnd_array(int64_t* values, size_t count, uint32_t aq_num, uint32_t aq_den, uint32_t bq_num, uint32_t bq_den, int rnd) {
+    int64_t a = aq_num * (int64_t)bq_den;
+    int64_t b = bq_num * (int64_t)aq_den;
+    size_t i;
+
+    for (i = 0; i < count; i++) {
+        values[i] = av_rescale_rnd(values[i], a, b, rnd | AV_ROUND_PASS_MINMAX);
+    }
+}
+
 int64_t ffw_null_timestamp() {
     return AV_NOPTS_VALUE;
 }
+
+int ffw_parse_duration(const char* value, int64_t* duration) {
+    return av_parse_time(duration, value, 1);
+}
+
+int64_t ffw_gettime() {
+    return av_gettime();
+}
+
+int64_t ffw_gettime_relative() {
+    return av_gettime_relative();
+}
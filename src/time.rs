@@ -2,18 +2,45 @@
 
 use std::{
     cmp::{Eq, Ordering, PartialEq, PartialOrd},
-    fmt::{self, Debug, Formatter},
+    ffi::CString,
+    fmt::{self, Debug, Display, Formatter},
     ops::{Add, AddAssign, Sub, SubAssign},
+    os::raw::{c_char, c_int},
+    str::FromStr,
     time::Duration,
 };
 
+use crate::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 extern "C" {
     fn ffw_rescale_q(n: i64, aq_num: u32, aq_den: u32, bq_num: u32, bq_den: u32) -> i64;
+    fn ffw_rescale_q_rnd(
+        n: i64,
+        aq_num: u32,
+        aq_den: u32,
+        bq_num: u32,
+        bq_den: u32,
+        rnd: c_int,
+    ) -> i64;
+    fn ffw_rescale_q_rnd_array(
+        values: *mut i64,
+        count: usize,
+        aq_num: u32,
+        aq_den: u32,
+        bq_num: u32,
+        bq_den: u32,
+        rnd: c_int,
+    );
     fn ffw_null_timestamp() -> i64;
+    fn ffw_parse_duration(value: *const c_char, duration: *mut i64) -> c_int;
 }
 
 /// A rational time base (e.g. 1/1000 is a millisecond time base).
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TimeBase {
     num: u32,
     den: u32,
@@ -40,15 +67,80 @@ impl TimeBase {
     }
 }
 
+impl TimeBase {
+    /// Rescale a given value from one time base to another using a given
+    /// rounding method. The minimum and maximum values (i.e. `i64::MIN`,
+    /// which is also used for null timestamps, and `i64::MAX`) are passed
+    /// through unchanged.
+    pub fn rescale(value: i64, from: TimeBase, to: TimeBase, rounding: Rounding) -> i64 {
+        unsafe {
+            ffw_rescale_q_rnd(
+                value,
+                from.num,
+                from.den,
+                to.num,
+                to.den,
+                rounding.into_raw(),
+            )
+        }
+    }
+
+    /// Rescale all given values in place. This is the same as calling
+    /// `rescale()` for every value.
+    pub fn rescale_slice(values: &mut [i64], from: TimeBase, to: TimeBase, rounding: Rounding) {
+        unsafe {
+            ffw_rescale_q_rnd_array(
+                values.as_mut_ptr(),
+                values.len(),
+                from.num,
+                from.den,
+                to.num,
+                to.den,
+                rounding.into_raw(),
+            )
+        }
+    }
+}
+
 impl Debug for TimeBase {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
         write!(f, "{}/{}", self.num(), self.den())
     }
 }
 
+/// Rounding method used when rescaling timestamps.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Rounding {
+    /// Round toward zero. This is the rounding used by
+    /// `Timestamp::with_time_base()`.
+    Zero,
+    /// Round away from zero.
+    Inf,
+    /// Round toward negative infinity.
+    Down,
+    /// Round toward positive infinity.
+    Up,
+    /// Round to the nearest value and halfway cases away from zero.
+    NearInf,
+}
+
+impl Rounding {
+    /// Get the corresponding AVRounding value.
+    fn into_raw(self) -> c_int {
+        match self {
+            Self::Zero => 0,
+            Self::Inf => 1,
+            Self::Down => 2,
+            Self::Up => 3,
+            Self::NearInf => 5,
+        }
+    }
+}
+
 /// A timestamp supporting various time bases. All comparisons are done within
 /// microsecond time base.
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Timestamp {
     timestamp: i64,
     time_base: TimeBase,
@@ -109,6 +201,16 @@ impl Timestamp {
         unsafe { self.timestamp == ffw_null_timestamp() }
     }
 
+    /// Convert the timestamp into an option. `None` is returned for the
+    /// "null" timestamp.
+    pub fn into_option(self) -> Option<Self> {
+        if self.is_null() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+
     /// Rescale the timestamp value to a given time base.
     pub fn with_time_base(&self, time_base: TimeBase) -> Self {
         let timestamp = if self.is_null() {
@@ -131,6 +233,40 @@ impl Timestamp {
         }
     }
 
+    /// Rescale the timestamp value to a given time base using a given
+    /// rounding method.
+    pub fn with_time_base_rounded(&self, time_base: TimeBase, rounding: Rounding) -> Self {
+        Self {
+            timestamp: TimeBase::rescale(self.timestamp, self.time_base, time_base, rounding),
+            time_base,
+        }
+    }
+
+    /// Parse a given time string. The string can be either in the
+    /// `[-][HH:]MM:SS[.m...]` format or in the `[-]S+[.m...]` format (i.e.
+    /// the FFmpeg duration syntax, e.g. `01:23:45.678` or `5025.678`). The
+    /// time base of the returned timestamp is in microseconds.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let value = CString::new(s).map_err(|_| Error::new("invalid timestamp"))?;
+
+        let mut micros = 0;
+
+        let ret = unsafe { ffw_parse_duration(value.as_ptr(), &mut micros) };
+
+        if ret < 0 {
+            return Err(Error::new("invalid timestamp"));
+        }
+
+        Ok(Self::from_micros(micros))
+    }
+
+    /// Format the timestamp as `HH:MM:SS.mmm` (with a leading minus sign if
+    /// the timestamp is negative). `None` is returned for null timestamps.
+    pub fn format_hhmmss(&self) -> Option<String> {
+        self.as_millis()
+            .map(|millis| format_time(millis as i128, 3))
+    }
+
     /// Get the timestamp value in seconds.
     pub fn as_secs(&self) -> Option<i64> {
         if self.is_null() {
@@ -206,6 +342,73 @@ impl Debug for Timestamp {
     }
 }
 
+impl Display for Timestamp {
+    /// Format the timestamp as `HH:MM:SS` followed by as many fractional
+    /// digits as needed for the precision of the time base (at most 9), e.g.
+    /// `00:00:01.04` for 26/25 or `00:00:01.040000` for 1040000/1000000.
+    /// Null timestamps are formatted as `N/A`.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        if self.is_null() {
+            return f.write_str("N/A");
+        }
+
+        let precision = fractional_digits(self.time_base);
+
+        let value = self.timestamp as i128 * self.time_base.num as i128 * 10i128.pow(precision)
+            / self.time_base.den as i128;
+
+        f.write_str(&format_time(value, precision))
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Get the number of fractional digits needed for a given time base.
+fn fractional_digits(time_base: TimeBase) -> u32 {
+    let mut digits = 0;
+    let mut scale = time_base.num as u64;
+
+    while scale < time_base.den as u64 && digits < 9 {
+        scale *= 10;
+        digits += 1;
+    }
+
+    digits
+}
+
+/// Format a given time value with a given number of fractional digits as
+/// `HH:MM:SS.f...`.
+fn format_time(value: i128, precision: u32) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+
+    let value = value.abs();
+
+    let scale = 10i128.pow(precision);
+
+    let fraction = value % scale;
+    let secs = value / scale;
+
+    let res = format!(
+        "{}{:02}:{:02}:{:02}",
+        sign,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+
+    if precision > 0 {
+        format!("{}.{:0width$}", res, fraction, width = precision as usize)
+    } else {
+        res
+    }
+}
+
 impl Add<Duration> for Timestamp {
     type Output = Timestamp;
 
@@ -311,7 +514,7 @@ impl PartialOrd for Timestamp {
 mod tests {
     use std::time::Duration;
 
-    use super::{TimeBase, Timestamp};
+    use super::{Rounding, TimeBase, Timestamp};
 
     #[test]
     fn test_duration_add() {
@@ -367,4 +570,74 @@ mod tests {
 
         assert_eq!(a, b);
     }
+
+    #[test]
+    fn test_rescale() {
+        let from = TimeBase::new(1, 90_000);
+        let to = TimeBase::new(1, 1_000);
+
+        assert_eq!(TimeBase::rescale(1_499, from, to, Rounding::Zero), 16);
+        assert_eq!(TimeBase::rescale(1_499, from, to, Rounding::NearInf), 17);
+        assert_eq!(TimeBase::rescale(-1_499, from, to, Rounding::Down), -17);
+        assert_eq!(
+            TimeBase::rescale(i64::MIN, from, to, Rounding::Up),
+            i64::MIN
+        );
+
+        let mut values = [0, 45, 1_499, i64::MAX];
+
+        TimeBase::rescale_slice(&mut values, from, to, Rounding::NearInf);
+
+        assert_eq!(values, [0, 1, 17, i64::MAX]);
+
+        let ts = Timestamp::null().with_time_base_rounded(to, Rounding::NearInf);
+
+        assert!(ts.is_null());
+    }
+
+    #[test]
+    fn test_into_option() {
+        assert_eq!(Timestamp::null().into_option(), None);
+        assert_eq!(
+            Timestamp::from_millis(40).into_option(),
+            Some(Timestamp::from_micros(40_000))
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let ts = Timestamp::new(5_025_678, TimeBase::new(1, 1_000));
+
+        assert_eq!(ts.format_hhmmss().unwrap(), "01:23:45.678");
+        assert_eq!(ts.to_string(), "01:23:45.678");
+
+        let ts = Timestamp::new(-26, TimeBase::new(1, 25));
+
+        assert_eq!(ts.format_hhmmss().unwrap(), "-00:00:01.040");
+        assert_eq!(ts.to_string(), "-00:00:01.04");
+
+        assert_eq!(
+            Timestamp::new(90_000, TimeBase::new(1, 90_000)).to_string(),
+            "00:00:01.00000"
+        );
+        assert_eq!(Timestamp::from_secs(61).to_string(), "00:01:01");
+        assert_eq!(Timestamp::null().to_string(), "N/A");
+        assert!(Timestamp::null().format_hhmmss().is_none());
+    }
+
+    #[test]
+    fn test_parse() {
+        let ts = Timestamp::parse("01:23:45.678").unwrap();
+
+        assert_eq!(ts, Timestamp::from_millis(5_025_678));
+        assert_eq!(
+            "-5.5".parse::<Timestamp>().unwrap(),
+            Timestamp::from_millis(-5_500)
+        );
+        assert_eq!(
+            "02:03".parse::<Timestamp>().unwrap(),
+            Timestamp::from_secs(123)
+        );
+        assert!(Timestamp::parse("1:xx").is_err());
+    }
 }
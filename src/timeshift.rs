@@ -0,0 +1,422 @@
+//! Timeshift buffer.
+//!
+//! The timeshift buffer retains demuxed packets of the last N seconds of a
+//! live input. Readers can be attached at any retained position and they
+//! follow the live input afterwards. Any retained interval can be also
+//! exported into a muxer (e.g. for instant replays).
+//!
+//! Packets are ordered and evicted by their decoding timestamps (or
+//! presentation timestamps if the decoding timestamps are not known).
+//! Packets without any timestamp share the timestamp of the preceding
+//! packet.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{format::muxer::Muxer, packet::Packet, time::Timestamp, Error};
+
+/// Retained packet.
+struct Entry {
+    sequence: u64,
+    timestamp: i64,
+    packet: Packet,
+}
+
+/// Shared state of the buffer and its readers.
+struct Inner {
+    entries: VecDeque<Entry>,
+    next_sequence: u64,
+    last_timestamp: Option<i64>,
+}
+
+impl Inner {
+    /// Get index of the first entry with a given sequence number or a
+    /// greater one.
+    fn position(&self, sequence: u64) -> usize {
+        self.entries
+            .partition_point(|entry| entry.sequence < sequence)
+    }
+}
+
+/// Builder for the timeshift buffer.
+pub struct TimeshiftBufferBuilder {
+    duration: Duration,
+    key_stream: Option<usize>,
+}
+
+impl TimeshiftBufferBuilder {
+    /// Create a new builder.
+    fn new() -> Self {
+        Self {
+            duration: Duration::from_secs(30),
+            key_stream: None,
+        }
+    }
+
+    /// Set the retained duration. The default is 30 seconds.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Set index of the stream whose key frames are used as attach points
+    /// (typically the video stream). By default, key packets of all
+    /// streams are used.
+    pub fn key_stream(mut self, stream_index: usize) -> Self {
+        self.key_stream = Some(stream_index);
+        self
+    }
+
+    /// Build the timeshift buffer.
+    pub fn build(self) -> TimeshiftBuffer {
+        let inner = Inner {
+            entries: VecDeque::new(),
+            next_sequence: 0,
+            last_timestamp: None,
+        };
+
+        TimeshiftBuffer {
+            inner: Arc::new(Mutex::new(inner)),
+            duration: self.duration.as_micros() as i64,
+            key_stream: self.key_stream,
+        }
+    }
+}
+
+/// Timeshift buffer.
+pub struct TimeshiftBuffer {
+    inner: Arc<Mutex<Inner>>,
+    duration: i64,
+    key_stream: Option<usize>,
+}
+
+impl TimeshiftBuffer {
+    /// Create a new timeshift buffer retaining a given duration of packets.
+    pub fn new(duration: Duration) -> Self {
+        TimeshiftBufferBuilder::new().duration(duration).build()
+    }
+
+    /// Get a builder for the timeshift buffer.
+    pub fn builder() -> TimeshiftBufferBuilder {
+        TimeshiftBufferBuilder::new()
+    }
+
+    /// Push a given packet into the buffer. Packets older than the retained
+    /// duration (relative to the given packet) are evicted.
+    pub fn push(&self, packet: Packet) {
+        let mut inner = self.inner.lock().unwrap();
+
+        let timestamp = packet_timestamp(&packet)
+            .or(inner.last_timestamp)
+            .unwrap_or(0);
+
+        inner.last_timestamp = Some(timestamp);
+
+        let sequence = inner.next_sequence;
+
+        inner.next_sequence += 1;
+
+        inner.entries.push_back(Entry {
+            sequence,
+            timestamp,
+            packet,
+        });
+
+        let threshold = timestamp - self.duration;
+
+        while let Some(entry) = inner.entries.front() {
+            if entry.timestamp >= threshold {
+                break;
+            }
+
+            inner.entries.pop_front();
+        }
+    }
+
+    /// Get the number of retained packets.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Check if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get timestamp of the oldest retained packet (in microseconds).
+    pub fn start(&self) -> Option<Timestamp> {
+        let inner = self.inner.lock().unwrap();
+
+        inner
+            .entries
+            .front()
+            .map(|entry| Timestamp::from_micros(entry.timestamp))
+    }
+
+    /// Get timestamp of the newest retained packet (in microseconds).
+    pub fn end(&self) -> Option<Timestamp> {
+        let inner = self.inner.lock().unwrap();
+
+        inner
+            .entries
+            .back()
+            .map(|entry| Timestamp::from_micros(entry.timestamp))
+    }
+
+    /// Attach a new reader at the last attach point (i.e. a key packet) at
+    /// or before a given timestamp. The reader starts at the oldest retained
+    /// packet if there is no such attach point.
+    pub fn attach(&self, timestamp: Timestamp) -> TimeshiftReader {
+        let inner = self.inner.lock().unwrap();
+
+        let index = self.attach_point(&inner, timestamp);
+
+        let next_sequence = inner
+            .entries
+            .get(index)
+            .map(|entry| entry.sequence)
+            .unwrap_or(inner.next_sequence);
+
+        TimeshiftReader {
+            inner: self.inner.clone(),
+            next_sequence,
+            skipped: 0,
+        }
+    }
+
+    /// Attach a new reader at the live position. The reader will get only
+    /// packets pushed after this call.
+    pub fn attach_live(&self) -> TimeshiftReader {
+        let inner = self.inner.lock().unwrap();
+
+        TimeshiftReader {
+            inner: self.inner.clone(),
+            next_sequence: inner.next_sequence,
+            skipped: 0,
+        }
+    }
+
+    /// Export all retained packets between a given start and end timestamp
+    /// into a given muxer. The export begins at the last attach point at or
+    /// before the start timestamp. Timestamps of the exported packets are
+    /// shifted so that the export starts at zero. Streams of the muxer must
+    /// correspond to the streams of the buffered packets. The muxer is not
+    /// flushed or closed.
+    pub fn export<T>(
+        &self,
+        start: Timestamp,
+        end: Timestamp,
+        muxer: &mut Muxer<T>,
+    ) -> Result<(), Error> {
+        let end = end
+            .as_micros()
+            .ok_or_else(|| Error::new("null timestamp"))?;
+
+        let packets = {
+            let inner = self.inner.lock().unwrap();
+
+            let index = self.attach_point(&inner, start);
+
+            inner
+                .entries
+                .iter()
+                .skip(index)
+                .take_while(|entry| entry.timestamp <= end)
+                .map(|entry| entry.packet.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let offset = packets
+            .iter()
+            .filter_map(|packet| {
+                let pts = packet.pts().as_micros();
+                let dts = packet.dts().as_micros();
+
+                match (pts, dts) {
+                    (Some(pts), Some(dts)) => Some(pts.min(dts)),
+                    (pts, dts) => pts.or(dts),
+                }
+            })
+            .min()
+            .unwrap_or(0)
+            .max(0);
+
+        let offset = Duration::from_micros(offset as u64);
+
+        for packet in packets {
+            let pts = packet.pts() - offset;
+            let dts = packet.dts() - offset;
+
+            muxer.push(packet.with_pts(pts).with_dts(dts))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get index of the last attach point at or before a given timestamp.
+    fn attach_point(&self, inner: &Inner, timestamp: Timestamp) -> usize {
+        let timestamp = match timestamp.as_micros() {
+            Some(ts) => ts,
+            None => return 0,
+        };
+
+        inner
+            .entries
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| entry.timestamp <= timestamp)
+            .find(|(_, entry)| self.is_attach_point(&entry.packet))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    /// Check if a given packet can be used as an attach point.
+    fn is_attach_point(&self, packet: &Packet) -> bool {
+        if !packet.is_key() {
+            return false;
+        }
+
+        self.key_stream
+            .map(|stream_index| packet.stream_index() == stream_index)
+            .unwrap_or(true)
+    }
+}
+
+/// Reader of the timeshift buffer.
+///
+/// The reader returns all packets from its attach point onwards including
+/// packets pushed into the buffer after the reader was attached. If the
+/// reader falls behind the retained interval, it continues from the oldest
+/// retained packet.
+pub struct TimeshiftReader {
+    inner: Arc<Mutex<Inner>>,
+    next_sequence: u64,
+    skipped: u64,
+}
+
+impl TimeshiftReader {
+    /// Take the next packet or `None` if the reader has caught up with the
+    /// live position.
+    pub fn take(&mut self) -> Option<Packet> {
+        let inner = self.inner.lock().unwrap();
+
+        let index = inner.position(self.next_sequence);
+
+        let entry = inner.entries.get(index)?;
+
+        self.skipped += entry.sequence - self.next_sequence;
+        self.next_sequence = entry.sequence + 1;
+
+        Some(entry.packet.clone())
+    }
+
+    /// Get the number of packets lost because they were evicted before the
+    /// reader could take them.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Get the number of packets available for reading.
+    pub fn available(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+
+        inner.entries.len() - inner.position(self.next_sequence)
+    }
+}
+
+/// Get timestamp of a given packet in microseconds.
+fn packet_timestamp(packet: &Packet) -> Option<i64> {
+    packet
+        .dts()
+        .as_micros()
+        .or_else(|| packet.pts().as_micros())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TimeshiftBuffer;
+
+    use crate::{
+        packet::PacketMut,
+        time::{TimeBase, Timestamp},
+    };
+
+    #[test]
+    fn test_timeshift_buffer() {
+        let tb = TimeBase::new(1, 1000);
+
+        let buffer = TimeshiftBuffer::builder()
+            .duration(Duration::from_millis(1000))
+            .key_stream(0)
+            .build();
+
+        let push = |ms: i64, stream_index: usize, key: bool| {
+            let packet = PacketMut::new(0)
+                .with_stream_index(stream_index)
+                .with_time_base(tb)
+                .with_pts(Timestamp::new(ms, tb))
+                .with_dts(Timestamp::new(ms, tb))
+                .with_key_flag(key)
+                .freeze();
+
+            buffer.push(packet);
+        };
+
+        for i in 0..10 {
+            push(i * 100, 0, i % 4 == 0);
+            push(i * 100 + 50, 1, true);
+        }
+
+        assert_eq!(buffer.len(), 20);
+        assert_eq!(buffer.start(), Some(Timestamp::from_millis(0)));
+        assert_eq!(buffer.end(), Some(Timestamp::from_millis(950)));
+
+        // attach points are at 0, 400 and 800
+        let mut reader = buffer.attach(Timestamp::from_millis(750));
+
+        assert_eq!(reader.available(), 12);
+
+        let packet = reader.take().unwrap();
+
+        assert_eq!(packet.pts(), Timestamp::from_millis(400));
+
+        let mut old = buffer.attach(Timestamp::from_millis(0));
+        let mut live = buffer.attach_live();
+
+        assert!(live.take().is_none());
+
+        // evict everything up to 300ms
+        push(1300, 0, false);
+
+        assert_eq!(buffer.len(), 15);
+        assert_eq!(buffer.start(), Some(Timestamp::from_millis(300)));
+
+        let packet = live.take().unwrap();
+
+        assert_eq!(packet.pts(), Timestamp::from_millis(1300));
+        assert!(live.take().is_none());
+
+        let packet = reader.take().unwrap();
+
+        assert_eq!(packet.pts(), Timestamp::from_millis(450));
+        assert_eq!(reader.skipped(), 0);
+
+        let packet = old.take().unwrap();
+
+        assert_eq!(packet.pts(), Timestamp::from_millis(300));
+        assert_eq!(old.skipped(), 6);
+
+        // an evicted position
+        let mut reader = buffer.attach(Timestamp::from_millis(100));
+
+        let packet = reader.take().unwrap();
+
+        assert_eq!(packet.pts(), Timestamp::from_millis(300));
+    }
+}
@@ -0,0 +1,1109 @@
+//! High-level transcoding API.
+//!
+//! The `Transcoder` reads packets from a demuxer, performs a given action for
+//! every input stream (copy, transcode or drop) and writes the resulting
+//! packets into a muxer. It covers the common "convert this file" use case
+//! without having to wire decoders, filters, encoders and a muxer manually.
+//!
+//! # Example
+//! ```ignore
+//! let demuxer = Demuxer::builder()
+//!     .build(input)?
+//!     .find_stream_info(None)
+//!     .map_err(|(_, err)| err)?;
+//!
+//! let transcoder = Transcoder::builder(demuxer)
+//!     .stream_action(0, StreamAction::TranscodeVideo(
+//!         VideoTranscodeSettings::new("libx264")
+//!             .filter("scale=1280:-2")
+//!             .bit_rate(4_000_000),
+//!     ))
+//!     .build(output, OutputFormat::find_by_name("mp4").unwrap())?;
+//!
+//! transcoder.run(|progress| println!("{:?}", progress.fraction()))?;
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Write,
+    time::Duration,
+};
+
+use crate::{
+    codec::{
+        audio::{
+            AudioDecoder, AudioEncoder, AudioFrame, AudioResampler, ChannelLayout, SampleFormat,
+        },
+        video::{
+            PixelFormat, VideoDecoder, VideoEncoder, VideoFilter, VideoFrame, VideoFrameScaler,
+        },
+        CodecParameters, Decoder, Encoder, Filter,
+    },
+    format::{
+        demuxer::{Demuxer, DemuxerWithStreamInfo},
+        io::IO,
+        muxer::{Muxer, MuxerBuilder, OutputFormat},
+        stream::Stream,
+    },
+    packet::Packet,
+    progress::{Progress, ProgressHandle, ProgressTracker},
+    time::{TimeBase, Timestamp},
+    Error,
+};
+
+/// Video transcoding settings.
+#[derive(Clone)]
+pub struct VideoTranscodeSettings {
+    encoder: String,
+    pixel_format: Option<PixelFormat>,
+    width: Option<usize>,
+    height: Option<usize>,
+    bit_rate: Option<u64>,
+    time_base: Option<TimeBase>,
+    filter: Option<String>,
+    options: Vec<(String, String)>,
+}
+
+impl VideoTranscodeSettings {
+    /// Create new settings for a given encoder (e.g. `"libx264"`).
+    pub fn new(encoder: &str) -> Self {
+        Self {
+            encoder: encoder.to_string(),
+            pixel_format: None,
+            width: None,
+            height: None,
+            bit_rate: None,
+            time_base: None,
+            filter: None,
+            options: Vec::new(),
+        }
+    }
+
+    /// Set output pixel format. The pixel format of the input stream is
+    /// used by default.
+    pub fn pixel_format(mut self, format: PixelFormat) -> Self {
+        self.pixel_format = Some(format);
+        self
+    }
+
+    /// Set output frame width. The width of the input stream is used by
+    /// default.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Set output frame height. The height of the input stream is used by
+    /// default.
+    pub fn height(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Set output bit rate.
+    pub fn bit_rate(mut self, bit_rate: u64) -> Self {
+        self.bit_rate = Some(bit_rate);
+        self
+    }
+
+    /// Set encoder time base. The time base of the input stream is used by
+    /// default. The average frame rate of the input stream (if known) is
+    /// passed to the encoder as its nominal frame rate.
+    pub fn time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = Some(time_base);
+        self
+    }
+
+    /// Apply a given filter graph (e.g. `"scale=1280:-2,hflip"`) to all
+    /// decoded frames before encoding them. Frames produced by the filter
+    /// are scaled to the output resolution and pixel format if needed.
+    pub fn filter(mut self, description: &str) -> Self {
+        self.filter = Some(description.to_string());
+        self
+    }
+
+    /// Set an encoder option.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.options.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Audio transcoding settings.
+#[derive(Clone)]
+pub struct AudioTranscodeSettings {
+    encoder: String,
+    sample_format: Option<SampleFormat>,
+    sample_rate: Option<u32>,
+    channel_layout: Option<ChannelLayout>,
+    bit_rate: Option<u64>,
+    options: Vec<(String, String)>,
+}
+
+impl AudioTranscodeSettings {
+    /// Create new settings for a given encoder (e.g. `"aac"`).
+    pub fn new(encoder: &str) -> Self {
+        Self {
+            encoder: encoder.to_string(),
+            sample_format: None,
+            sample_rate: None,
+            channel_layout: None,
+            bit_rate: None,
+            options: Vec::new(),
+        }
+    }
+
+    /// Set output sample format. The sample format of the input stream is
+    /// used by default.
+    pub fn sample_format(mut self, format: SampleFormat) -> Self {
+        self.sample_format = Some(format);
+        self
+    }
+
+    /// Set output sample rate. The sample rate of the input stream is used
+    /// by default.
+    pub fn sample_rate(mut self, rate: u32) -> Self {
+        self.sample_rate = Some(rate);
+        self
+    }
+
+    /// Set output channel layout. The channel layout of the input stream is
+    /// used by default.
+    pub fn channel_layout(mut self, layout: ChannelLayout) -> Self {
+        self.channel_layout = Some(layout);
+        self
+    }
+
+    /// Set output bit rate.
+    pub fn bit_rate(mut self, bit_rate: u64) -> Self {
+        self.bit_rate = Some(bit_rate);
+        self
+    }
+
+    /// Set an encoder option.
+    pub fn set_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.options.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Action performed with an input stream.
+#[derive(Clone)]
+pub enum StreamAction {
+    /// Copy the stream into the output without decoding it.
+    Copy,
+    /// Decode the stream and encode it using given settings.
+    TranscodeVideo(VideoTranscodeSettings),
+    /// Decode the stream and encode it using given settings.
+    TranscodeAudio(AudioTranscodeSettings),
+    /// Do not include the stream in the output.
+    Drop,
+}
+
+/// Builder for the transcoder.
+pub struct TranscoderBuilder<R> {
+    demuxer: DemuxerWithStreamInfo<R>,
+    actions: HashMap<usize, StreamAction>,
+    muxer_builder: MuxerBuilder,
+}
+
+impl<R> TranscoderBuilder<R> {
+    /// Create a new builder.
+    fn new(demuxer: DemuxerWithStreamInfo<R>) -> Self {
+        Self {
+            demuxer,
+            actions: HashMap::new(),
+            muxer_builder: Muxer::builder().interleaved(true),
+        }
+    }
+
+    /// Set action for a given input stream. By default, all audio, video
+    /// and subtitle streams are copied and all other streams are dropped.
+    pub fn stream_action(mut self, stream_index: usize, action: StreamAction) -> Self {
+        self.actions.insert(stream_index, action);
+        self
+    }
+
+    /// Set a muxer option.
+    pub fn set_muxer_option<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.muxer_builder = self.muxer_builder.set_option(name, value);
+        self
+    }
+
+    /// Set output metadata.
+    pub fn set_metadata<V>(mut self, key: &str, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.muxer_builder = self.muxer_builder.set_metadata(key, value);
+        self
+    }
+
+    /// Build the transcoder. The output header is written immediately.
+    pub fn build<W>(mut self, io: IO<W>, format: OutputFormat) -> Result<Transcoder<R, W>, Error>
+    where
+        W: Write,
+    {
+        let mut routes = Vec::new();
+        let mut video = Vec::new();
+
+        let global_header = format.global_header();
+
+        let mut start: Option<Timestamp> = None;
+        let mut duration: Option<Timestamp> = None;
+
+        for (index, stream) in self.demuxer.streams().iter().enumerate() {
+            let params = stream.codec_parameters();
+
+            let action = self.actions.remove(&index).unwrap_or_else(|| {
+                if params.is_audio_codec() || params.is_video_codec() || params.is_subtitle_codec()
+                {
+                    StreamAction::Copy
+                } else {
+                    StreamAction::Drop
+                }
+            });
+
+            let route = match action {
+                StreamAction::Copy => {
+                    let output_index = self.muxer_builder.add_stream(&params)?;
+
+                    video.push(params.is_video_codec());
+
+                    Route::Copy(output_index)
+                }
+                StreamAction::TranscodeVideo(settings) => {
+                    let transcoder = VideoStreamTranscoder::new(stream, settings, global_header)?;
+
+                    let output_index = self
+                        .muxer_builder
+                        .add_stream(&transcoder.codec_parameters())?;
+
+                    video.push(true);
+
+                    Route::Transcode(output_index, Box::new(transcoder))
+                }
+                StreamAction::TranscodeAudio(settings) => {
+                    let transcoder = AudioStreamTranscoder::new(stream, settings, global_header)?;
+
+                    let output_index = self
+                        .muxer_builder
+                        .add_stream(&transcoder.codec_parameters())?;
+
+                    video.push(false);
+
+                    Route::Transcode(output_index, Box::new(transcoder))
+                }
+                StreamAction::Drop => Route::Drop,
+            };
+
+            if !matches!(route, Route::Drop) {
+                if let Some(stream_start) = stream.start_time() {
+                    if start.map(|start| stream_start < start).unwrap_or(true) {
+                        start = Some(stream_start);
+                    }
+                }
+
+                if let Some(stream_duration) = stream.duration() {
+                    if duration
+                        .map(|duration| stream_duration > duration)
+                        .unwrap_or(true)
+                    {
+                        duration = Some(stream_duration);
+                    }
+                }
+            }
+
+            routes.push(route);
+        }
+
+        let duration = duration
+            .and_then(|duration| duration.as_micros())
+            .map(|duration| Duration::from_micros(duration as u64));
+
+        let output = Output {
+            muxer: self.muxer_builder.build(io, format)?,
+            tracker: ProgressTracker::new(video, start, duration),
+        };
+
+        let res = Transcoder {
+            demuxer: self.demuxer.into_demuxer(),
+            output,
+            routes,
+        };
+
+        Ok(res)
+    }
+}
+
+/// Transcoder.
+///
+/// The transcoder reads all packets from a given demuxer and writes the
+/// processed streams into a muxer.
+pub struct Transcoder<R, W> {
+    demuxer: Demuxer<R>,
+    output: Output<W>,
+    routes: Vec<Route>,
+}
+
+impl Transcoder<(), ()> {
+    /// Get a transcoder builder for a given input.
+    pub fn builder<R>(demuxer: DemuxerWithStreamInfo<R>) -> TranscoderBuilder<R> {
+        TranscoderBuilder::new(demuxer)
+    }
+}
+
+impl<R, W> Transcoder<R, W> {
+    /// Get a handle that can be used for polling the transcoding progress
+    /// (e.g. from another thread).
+    pub fn progress_handle(&self) -> ProgressHandle {
+        self.output.tracker.handle()
+    }
+
+    /// Process the whole input and return the output IO. The progress
+    /// callback is called after every input packet and once more when the
+    /// transcoding is finished.
+    pub fn run<F>(mut self, mut progress: F) -> Result<IO<W>, Error>
+    where
+        F: FnMut(&Progress),
+    {
+        let tracker = self.output.tracker.handle();
+
+        self.output.tracker.start();
+
+        while let Some(packet) = self.demuxer.take()? {
+            if let Some(route) = self.routes.get_mut(packet.stream_index()) {
+                route.push(packet, &mut self.output)?;
+            }
+
+            progress(&tracker.progress());
+        }
+
+        for route in &mut self.routes {
+            route.flush(&mut self.output)?;
+        }
+
+        self.output.muxer.flush()?;
+
+        let io = self.output.muxer.close()?;
+
+        progress(&self.output.tracker.finish());
+
+        Ok(io)
+    }
+}
+
+/// Muxer combined with a progress tracker.
+struct Output<W> {
+    muxer: Muxer<W>,
+    tracker: ProgressTracker,
+}
+
+impl<W> Output<W> {
+    /// Write a given packet into a given output stream.
+    fn push(&mut self, output_index: usize, packet: Packet) -> Result<(), Error> {
+        let pts = packet.pts();
+        let size = packet.data().len();
+
+        self.muxer.push(packet.with_stream_index(output_index))?;
+        self.tracker.update(output_index, pts, size);
+
+        Ok(())
+    }
+}
+
+/// Route of a single input stream.
+enum Route {
+    Copy(usize),
+    Transcode(usize, Box<dyn StreamTranscoder + Send>),
+    Drop,
+}
+
+impl Route {
+    /// Process a given input packet.
+    fn push<W>(&mut self, packet: Packet, output: &mut Output<W>) -> Result<(), Error> {
+        match self {
+            Self::Copy(output_index) => output.push(*output_index, packet),
+            Self::Transcode(output_index, transcoder) => {
+                transcoder.push(packet)?;
+
+                write_packets(*output_index, transcoder.as_mut(), output)
+            }
+            Self::Drop => Ok(()),
+        }
+    }
+
+    /// Flush the route.
+    fn flush<W>(&mut self, output: &mut Output<W>) -> Result<(), Error> {
+        if let Self::Transcode(output_index, transcoder) = self {
+            transcoder.flush()?;
+
+            write_packets(*output_index, transcoder.as_mut(), output)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Take all packets from a given transcoder and write them into the output.
+fn write_packets<W>(
+    output_index: usize,
+    transcoder: &mut dyn StreamTranscoder,
+    output: &mut Output<W>,
+) -> Result<(), Error> {
+    while let Some(packet) = transcoder.take()? {
+        output.push(output_index, packet)?;
+    }
+
+    Ok(())
+}
+
+/// Push a given frame to an encoder and move all encoded packets into a
+/// given queue.
+fn encode<E>(encoder: &mut E, frame: E::Frame, ready: &mut VecDeque<Packet>) -> Result<(), Error>
+where
+    E: Encoder,
+{
+    encoder.push(frame)?;
+
+    while let Some(packet) = encoder.take()? {
+        ready.push_back(packet);
+    }
+
+    Ok(())
+}
+
+/// Common interface of the audio and video stream transcoders.
+trait StreamTranscoder {
+    /// Get codec parameters of the transcoded stream.
+    fn codec_parameters(&self) -> CodecParameters;
+
+    /// Push a given packet to the transcoder.
+    fn push(&mut self, packet: Packet) -> Result<(), Error>;
+
+    /// Flush the transcoder.
+    fn flush(&mut self) -> Result<(), Error>;
+
+    /// Take the next encoded packet.
+    fn take(&mut self) -> Result<Option<Packet>, Error>;
+}
+
+/// Audio decoder, resampler and encoder combined into a single pipeline.
+///
+/// Unlike `AudioTranscoder`, the resampler is created from the first
+/// decoded frame, so it does not rely on the stream parameters being
+/// accurate.
+struct AudioStreamTranscoder {
+    decoder: AudioDecoder,
+    resampler: Option<AudioResampler>,
+    encoder: AudioEncoder,
+    ready: VecDeque<Packet>,
+
+    sample_format: SampleFormat,
+    sample_rate: u32,
+    channel_layout: ChannelLayout,
+}
+
+impl AudioStreamTranscoder {
+    /// Create a new transcoder for a given stream. The global header flag
+    /// should be set if required by the output format.
+    fn new(
+        stream: &Stream,
+        settings: AudioTranscodeSettings,
+        global_header: bool,
+    ) -> Result<Self, Error> {
+        let params = stream.codec_parameters();
+
+        let input = params
+            .as_audio_codec_parameters()
+            .ok_or_else(|| Error::new("not an audio stream"))?;
+
+        let decoder = AudioDecoder::from_stream(stream)?.build()?;
+
+        let sample_format = settings
+            .sample_format
+            .unwrap_or_else(|| input.sample_format());
+        let sample_rate = settings.sample_rate.unwrap_or_else(|| input.sample_rate());
+        let channel_layout = settings
+            .channel_layout
+            .unwrap_or_else(|| input.channel_layout());
+
+        let mut builder = AudioEncoder::builder(&settings.encoder)?
+            .sample_format(sample_format)
+            .sample_rate(sample_rate)
+            .channel_layout(channel_layout)
+            .time_base(TimeBase::new(1, sample_rate))
+            .global_header(global_header);
+
+        if let Some(bit_rate) = settings.bit_rate {
+            builder = builder.bit_rate(bit_rate);
+        }
+
+        for (name, value) in &settings.options {
+            builder = builder.set_option(name, value);
+        }
+
+        let res = Self {
+            decoder,
+            resampler: None,
+            encoder: builder.build()?,
+            ready: VecDeque::new(),
+
+            sample_format,
+            sample_rate,
+            channel_layout,
+        };
+
+        Ok(res)
+    }
+
+    /// Pass all decoded frames to the resampler.
+    fn process_decoded_frames(&mut self) -> Result<(), Error> {
+        while let Some(frame) = self.decoder.take()? {
+            self.resample(frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// Push a given frame to the resampler and pass all resampled frames to
+    /// the encoder.
+    fn resample(&mut self, frame: AudioFrame) -> Result<(), Error> {
+        if self.resampler.is_none() {
+            let resampler = AudioResampler::builder()
+                .source_channel_layout(frame.channel_layout())
+                .source_sample_format(frame.sample_format())
+                .source_sample_rate(frame.sample_rate())
+                .target_channel_layout(self.channel_layout)
+                .target_sample_format(self.sample_format)
+                .target_sample_rate(self.sample_rate)
+                .target_frame_samples(self.encoder.samples_per_frame())
+                .build()?;
+
+            self.resampler = Some(resampler);
+        }
+
+        let resampler = self.resampler.as_mut().unwrap();
+
+        resampler.push(frame)?;
+
+        while let Some(frame) = resampler.take()? {
+            encode(&mut self.encoder, frame, &mut self.ready)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl StreamTranscoder for AudioStreamTranscoder {
+    fn codec_parameters(&self) -> CodecParameters {
+        self.encoder.codec_parameters().into()
+    }
+
+    fn push(&mut self, packet: Packet) -> Result<(), Error> {
+        self.decoder.push(packet)?;
+
+        self.process_decoded_frames()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.decoder.flush()?;
+
+        self.process_decoded_frames()?;
+
+        if let Some(resampler) = self.resampler.as_mut() {
+            resampler.flush()?;
+
+            while let Some(frame) = resampler.take()? {
+                encode(&mut self.encoder, frame, &mut self.ready)?;
+            }
+        }
+
+        self.encoder.flush()?;
+
+        while let Some(packet) = self.encoder.take()? {
+            self.ready.push_back(packet);
+        }
+
+        Ok(())
+    }
+
+    fn take(&mut self) -> Result<Option<Packet>, Error> {
+        Ok(self.ready.pop_front())
+    }
+}
+
+/// Video decoder, filter and encoder combined into a single pipeline.
+struct VideoStreamTranscoder {
+    decoder: VideoDecoder,
+    filter: Option<VideoFilter>,
+    scaler: Option<VideoFrameScaler>,
+    encoder: VideoEncoder,
+    ready: VecDeque<Packet>,
+
+    pixel_format: PixelFormat,
+    width: usize,
+    height: usize,
+}
+
+impl VideoStreamTranscoder {
+    /// Create a new transcoder for a given stream. The global header flag
+    /// should be set if required by the output format.
+    fn new(
+        stream: &Stream,
+        settings: VideoTranscodeSettings,
+        global_header: bool,
+    ) -> Result<Self, Error> {
+        let params = stream.codec_parameters();
+
+        let input = params
+            .as_video_codec_parameters()
+            .ok_or_else(|| Error::new("not a video stream"))?;
+
+        let decoder = VideoDecoder::from_stream(stream)?.build()?;
+
+        let filter = if let Some(description) = settings.filter.as_ref() {
+            let mut builder = VideoFilter::builder(input, description);
+
+            if let Some(frame_rate) = stream.avg_frame_rate() {
+                builder = builder.input_frame_rate(frame_rate);
+            }
+
+            Some(builder.build()?)
+        } else {
+            None
+        };
+
+        let pixel_format = settings
+            .pixel_format
+            .unwrap_or_else(|| input.pixel_format());
+        let width = settings.width.unwrap_or_else(|| input.width());
+        let height = settings.height.unwrap_or_else(|| input.height());
+
+        let time_base = settings.time_base.unwrap_or_else(|| stream.time_base());
+
+        let mut builder = VideoEncoder::builder(&settings.encoder)?
+            .pixel_format(pixel_format)
+            .width(width)
+            .height(height)
+            .time_base(time_base)
+            .global_header(global_header);
+
+        if let Some(frame_rate) = stream.avg_frame_rate() {
+            builder = builder.frame_rate(frame_rate);
+        }
+
+        if let Some(bit_rate) = settings.bit_rate {
+            builder = builder.bit_rate(bit_rate);
+        }
+
+        for (name, value) in &settings.options {
+            builder = builder.set_option(name, value);
+        }
+
+        let res = Self {
+            decoder,
+            filter,
+            scaler: None,
+            encoder: builder.build()?,
+            ready: VecDeque::new(),
+
+            pixel_format,
+            width,
+            height,
+        };
+
+        Ok(res)
+    }
+
+    /// Pass all decoded frames to the filter (or directly to the encoder).
+    fn process_decoded_frames(&mut self) -> Result<(), Error> {
+        while let Some(frame) = self.decoder.take()? {
+            if let Some(filter) = self.filter.as_mut() {
+                filter.push(frame)?;
+
+                self.process_filtered_frames()?;
+            } else {
+                self.push_to_encoder(frame)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pass all filtered frames to the encoder.
+    fn process_filtered_frames(&mut self) -> Result<(), Error> {
+        let mut frames = Vec::new();
+
+        if let Some(filter) = self.filter.as_mut() {
+            while let Some(frame) = filter.take()? {
+                frames.push(frame);
+            }
+        }
+
+        for frame in frames {
+            self.push_to_encoder(frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// Convert a given frame into the output format (if needed) and push it
+    /// to the encoder.
+    fn push_to_encoder(&mut self, frame: VideoFrame) -> Result<(), Error> {
+        let frame = if frame.pixel_format() == self.pixel_format
+            && frame.width() == self.width
+            && frame.height() == self.height
+        {
+            frame
+        } else {
+            self.scale(&frame)?
+        };
+
+        encode(&mut self.encoder, frame, &mut self.ready)
+    }
+
+    /// Scale a given frame to the output resolution and pixel format.
+    fn scale(&mut self, frame: &VideoFrame) -> Result<VideoFrame, Error> {
+        if let Some(scaler) = self.scaler.as_mut() {
+            // the scaler will fail if the input format has changed
+            if let Ok(scaled) = scaler.scale(frame) {
+                return Ok(scaled.with_pts(frame.pts()));
+            }
+        }
+
+        let mut scaler = VideoFrameScaler::builder()
+            .source_pixel_format(frame.pixel_format())
+            .source_width(frame.width())
+            .source_height(frame.height())
+            .target_pixel_format(self.pixel_format)
+            .target_width(self.width)
+            .target_height(self.height)
+            .build()?;
+
+        let scaled = scaler.scale(frame)?.with_pts(frame.pts());
+
+        self.scaler = Some(scaler);
+
+        Ok(scaled)
+    }
+}
+
+impl StreamTranscoder for VideoStreamTranscoder {
+    fn codec_parameters(&self) -> CodecParameters {
+        self.encoder.codec_parameters().into()
+    }
+
+    fn push(&mut self, packet: Packet) -> Result<(), Error> {
+        self.decoder.push(packet)?;
+
+        self.process_decoded_frames()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.decoder.flush()?;
+
+        self.process_decoded_frames()?;
+
+        if let Some(filter) = self.filter.as_mut() {
+            filter.flush()?;
+        }
+
+        self.process_filtered_frames()?;
+
+        self.encoder.flush()?;
+
+        while let Some(packet) = self.encoder.take()? {
+            self.ready.push_back(packet);
+        }
+
+        Ok(())
+    }
+
+    fn take(&mut self) -> Result<Option<Packet>, Error> {
+        Ok(self.ready.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{AudioTranscodeSettings, StreamAction, Transcoder, VideoTranscodeSettings};
+
+    use crate::{
+        codec::{
+            audio::{
+                frame::{get_channel_layout, get_sample_format},
+                AudioEncoder, AudioFrameMut,
+            },
+            video::{frame::get_pixel_format, VideoEncoder, VideoFrameMut},
+            Encoder,
+        },
+        format::{
+            demuxer::Demuxer,
+            io::IO,
+            muxer::{Muxer, OutputFormat},
+        },
+        math::Rational,
+        time::{TimeBase, Timestamp},
+    };
+
+    /// Create a short Matroska file with a 23.976 fps MPEG-4 video stream
+    /// (Matroska timestamps are in milliseconds).
+    fn matroska_file() -> Vec<u8> {
+        let pixel_format = get_pixel_format("yuv420p");
+        let time_base = TimeBase::new(1, 24_000);
+
+        let mut encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .time_base(time_base)
+            .frame_rate(Rational::new(24_000, 1001))
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder();
+
+        builder
+            .add_stream(&encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_seekable_write_stream(Cursor::new(Vec::new())),
+                OutputFormat::find_by_name("matroska").unwrap(),
+            )
+            .unwrap();
+
+        for i in 0..48 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 48)
+                .with_time_base(time_base)
+                .with_pts(Timestamp::new(i * 1001, time_base))
+                .freeze();
+
+            encoder.push(frame).unwrap();
+
+            while let Some(packet) = encoder.take().unwrap() {
+                muxer.push(packet).unwrap();
+            }
+        }
+
+        encoder.flush().unwrap();
+
+        while let Some(packet) = encoder.take().unwrap() {
+            muxer.push(packet).unwrap();
+        }
+
+        muxer.close().unwrap().into_stream().into_inner()
+    }
+
+    /// Create a short MPEG-TS file with an MPEG-4 video stream and an MP2
+    /// audio stream.
+    fn mpegts_file() -> Vec<u8> {
+        let pixel_format = get_pixel_format("yuv420p");
+        let video_time_base = TimeBase::new(1, 25);
+
+        let channel_layout = get_channel_layout("mono");
+        let sample_format = get_sample_format("s16");
+        let audio_time_base = TimeBase::new(1, 48_000);
+
+        let mut video_encoder = VideoEncoder::builder("mpeg4")
+            .unwrap()
+            .pixel_format(pixel_format)
+            .width(64)
+            .height(48)
+            .time_base(video_time_base)
+            .build()
+            .unwrap();
+
+        let mut audio_encoder = AudioEncoder::builder("mp2")
+            .unwrap()
+            .sample_format(sample_format)
+            .sample_rate(48_000)
+            .channel_layout(channel_layout)
+            .time_base(audio_time_base)
+            .build()
+            .unwrap();
+
+        let mut builder = Muxer::builder().interleaved(true);
+
+        builder
+            .add_stream(&video_encoder.codec_parameters().into())
+            .unwrap();
+        builder
+            .add_stream(&audio_encoder.codec_parameters().into())
+            .unwrap();
+
+        let mut muxer = builder
+            .build(
+                IO::from_write_stream(Vec::new()),
+                OutputFormat::find_by_name("mpegts").unwrap(),
+            )
+            .unwrap();
+
+        let samples = audio_encoder.samples_per_frame().unwrap();
+
+        for i in 0..25 {
+            let frame = VideoFrameMut::black(pixel_format, 64, 48)
+                .with_time_base(video_time_base)
+                .with_pts(Timestamp::new(i, video_time_base))
+                .freeze();
+
+            video_encoder.push(frame).unwrap();
+
+            while let Some(packet) = video_encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(0)).unwrap();
+            }
+        }
+
+        for i in 0..40 {
+            let frame = AudioFrameMut::silence(channel_layout, sample_format, 48_000, samples)
+                .with_time_base(audio_time_base)
+                .with_pts(Timestamp::new((i * samples) as i64, audio_time_base))
+                .freeze();
+
+            audio_encoder.push(frame).unwrap();
+
+            while let Some(packet) = audio_encoder.take().unwrap() {
+                muxer.push(packet.with_stream_index(1)).unwrap();
+            }
+        }
+
+        video_encoder.flush().unwrap();
+        audio_encoder.flush().unwrap();
+
+        while let Some(packet) = video_encoder.take().unwrap() {
+            muxer.push(packet.with_stream_index(0)).unwrap();
+        }
+
+        while let Some(packet) = audio_encoder.take().unwrap() {
+            muxer.push(packet.with_stream_index(1)).unwrap();
+        }
+
+        muxer.close().unwrap().into_stream()
+    }
+
+    #[test]
+    fn test_transcoder() {
+        let demuxer = Demuxer::builder()
+            .build(IO::from_seekable_read_stream(Cursor::new(mpegts_file())))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        let video = VideoTranscodeSettings::new("mpeg4").time_base(TimeBase::new(1, 25));
+        let audio = AudioTranscodeSettings::new("aac").sample_format(get_sample_format("fltp"));
+
+        let output = Transcoder::builder(demuxer)
+            .stream_action(0, StreamAction::TranscodeVideo(video))
+            .stream_action(1, StreamAction::TranscodeAudio(audio))
+            .build(
+                IO::from_seekable_write_stream(Cursor::new(Vec::new())),
+                OutputFormat::find_by_name("mp4").unwrap(),
+            )
+            .unwrap()
+            .run(|_| ())
+            .unwrap()
+            .into_stream()
+            .into_inner();
+
+        let mut demuxer = Demuxer::builder()
+            .build(IO::from_seekable_read_stream(Cursor::new(output)))
+            .unwrap()
+            .find_stream_info(None)
+            .map_err(|(_, err)| err)
+            .unwrap();
+
+        // MP4 requires global headers
+        let params = demuxer.streams()[0].codec_parameters();
+
+        let extradata = params.as_video_codec_parameters().unwrap().extradata();
+
+        assert!(extradata.is_some_and(|data| !data.is_empty()));
+
+        let mut video_packets = 0;
+        let mut audio_packets = 0;
+
+        while let Some(packet) = demuxer.take().unwrap() {
+            if packet.stream_index() == 0 {
+                // the headers must not be repeated in-band, i.e. key frames
+                // must not start with a visual object sequence start code
+                assert!(!packet.data().starts_with(&[0, 0, 1, 0xb0]));
+
+                video_packets += 1;
+            } else {
+                audio_packets += 1;
+            }
+        }
+
+        assert_eq!(video_packets, 25);
+        assert!(audio_packets > 0);
+    }
+
+    #[test]
+    fn test_millisecond_input_timestamps() {
+        let input = matroska_file();
+
+        // the default encoder time base and a time base coarser than the
+        // millisecond input time base
+        for time_base in [None, Some(TimeBase::new(1001, 24_000))].iter() {
+            let demuxer = Demuxer::builder()
+                .build(IO::from_seekable_read_stream(Cursor::new(input.clone())))
+                .unwrap()
+                .find_stream_info(None)
+                .map_err(|(_, err)| err)
+                .unwrap();
+
+            let input_time_base = demuxer.streams()[0].time_base();
+
+            assert_eq!(input_time_base.num(), 1);
+            assert_eq!(input_time_base.den(), 1000);
+
+            let mut video = VideoTranscodeSettings::new("mpeg4");
+
+            if let Some(time_base) = time_base {
+                video = video.time_base(*time_base);
+            }
+
+            let output = Transcoder::builder(demuxer)
+                .stream_action(0, StreamAction::TranscodeVideo(video))
+                .build(
+                    IO::from_seekable_write_stream(Cursor::new(Vec::new())),
+                    OutputFormat::find_by_name("matroska").unwrap(),
+                )
+                .unwrap()
+                .run(|_| ())
+                .unwrap()
+                .into_stream()
+                .into_inner();
+
+            let mut demuxer = Demuxer::builder()
+                .build(IO::from_seekable_read_stream(Cursor::new(output)))
+                .unwrap()
+                .find_stream_info(None)
+                .map_err(|(_, err)| err)
+                .unwrap();
+
+            let mut packets = 0;
+
+            while demuxer.take().unwrap().is_some() {
+                packets += 1;
+            }
+
+            assert_eq!(packets, 48);
+        }
+    }
+}